@@ -362,6 +362,8 @@ pub(crate) fn expand_test_or_bench(
                                         cx.expr_path(test_type_path("Unknown"))
                                     }
                                 },),
+                                // doctest_info: None
+                                field("doctest_info", cx.expr_none(sp)),
                                 // },
                             ],),
                         ),
@@ -1,4 +1,5 @@
 use std::ffi::OsStr;
+use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -106,6 +107,30 @@ const fn jit_bin(config: &'static str, source: &'static str, args: &'static str)
             "expected graceful error, not ICE:\n{combined}"
         );
     }),
+    TestCase::custom("aot.mono_inline", &|runner| {
+        // Writing the Cranelift IR of each function requires `--emit llvm-ir`.
+        let clif_dir = BUILD_EXAMPLE_OUT_DIR.to_path(&runner.dirs).join("mono_inline.clif");
+        for (mir_opt_level, inlined) in [(2, true), (1, false)] {
+            ensure_empty_dir(&clif_dir);
+            let mir_opt_level_arg = format!("-Cllvm-args=mir-opt-level={mir_opt_level}");
+            runner.run_rustc([
+                "example/mono-inline.rs",
+                "--crate-type",
+                "lib",
+                "--emit",
+                "obj,llvm-ir",
+                "-Copt-level=2",
+                "-Ccodegen-units=1",
+                &mir_opt_level_arg,
+            ]);
+            let clif = fs::read_to_string(clif_dir.join("caller.unopt.clif")).unwrap();
+            assert_eq!(
+                !clif.contains("add_one"),
+                inlined,
+                "unexpected Cranelift IR for mir-opt-level={mir_opt_level}:\n{clif}"
+            );
+        }
+    }),
     TestCase::build_bin_and_run("aot.issue-72793", "example/issue-72793.rs", &[]),
     TestCase::build_bin("aot.issue-59326", "example/issue-59326.rs"),
     TestCase::build_bin_and_run("aot.neon", "example/neon.rs", &[]),
@@ -0,0 +1,11 @@
+// Used by the `aot.mono_inline` test. `add_one::<u32>` only exists after monomorphization and is
+// small enough to be inlined into `caller` unless the MIR optimizations of cg_clif are disabled.
+
+fn add_one<T: Into<u64>>(x: T) -> u64 {
+    x.into() + 1
+}
+
+#[unsafe(no_mangle)]
+pub fn caller(x: u32) -> u64 {
+    add_one(x)
+}
@@ -2,10 +2,12 @@
 
 use cranelift_codegen::CodegenError;
 use cranelift_codegen::ir::UserFuncName;
+use cranelift_codegen::isa::TargetIsa;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
 use cranelift_module::ModuleError;
 use rustc_ast::InlineAsmOptions;
 use rustc_codegen_ssa::base::is_call_from_compiler_builtins_to_upstream_monomorphization;
+use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::profiling::SelfProfilerRef;
 use rustc_errors::DiagCtxtHandle;
 use rustc_hir::attrs::lang_items::LangItem;
@@ -19,8 +21,10 @@
 
 use crate::constant::ConstantCx;
 use crate::debuginfo::{FunctionDebugContext, TypeDebugContext};
+use crate::optimize::OptConfig;
 use crate::prelude::*;
 use crate::pretty_clif::{CommentWriter, format_clif_ir_header};
+use crate::unwind_module::UnwindModule;
 use crate::{codegen_f16_f128, enable_verifier};
 
 pub(crate) struct CodegenedFunction {
@@ -30,6 +34,9 @@ pub(crate) struct CodegenedFunction {
     clif_comments: CommentWriter,
     func_debug_cx: Option<FunctionDebugContext>,
     inline_asm: String,
+    /// Whether to run the egraph pass on the function, if this differs from what the ISA of the
+    /// module does.
+    egraph: Option<bool>,
 }
 
 pub(crate) fn codegen_fn<'tcx>(
@@ -40,13 +47,16 @@ pub(crate) fn codegen_fn<'tcx>(
     cached_func: Function,
     module: &mut dyn Module,
     instance: Instance<'tcx>,
+    opt_config: OptConfig,
+    codegen_unit: &FxHashSet<Instance<'tcx>>,
 ) -> CodegenedFunction {
     debug_assert!(!instance.args.has_infer());
 
     let symbol_name = tcx.symbol_name(instance).name.to_string();
     let _timer = tcx.prof.generic_activity_with_arg("codegen fn", &*symbol_name);
 
-    let mir = tcx.instance_mir(instance.def);
+    let mir = opt_config.instance_mir(tcx, instance, codegen_unit);
+    let mir = &*mir;
     let _mir_guard = crate::PrintOnPanic(|| {
         let mut buf = Vec::new();
         with_no_trimmed_paths!({
@@ -145,7 +155,17 @@ pub(crate) fn codegen_fn<'tcx>(
     // Verify function
     verify_func(tcx, &clif_comments, &func);
 
-    CodegenedFunction { symbol_name, func_id, func, clif_comments, func_debug_cx, inline_asm }
+    let egraph = opt_config.egraph_override(tcx, instance, &func);
+
+    CodegenedFunction {
+        symbol_name,
+        func_id,
+        func,
+        clif_comments,
+        func_debug_cx,
+        inline_asm,
+        egraph,
+    }
 }
 
 pub(crate) fn compile_fn(
@@ -154,7 +174,8 @@ pub(crate) fn compile_fn(
     output_filenames: &OutputFilenames,
     should_write_ir: bool,
     cached_context: &mut Context,
-    module: &mut dyn Module,
+    module: &mut UnwindModule<impl Module>,
+    unoptimized_isa: Option<&dyn TargetIsa>,
     debug_context: Option<&mut DebugContext>,
     global_asm: &mut String,
     codegened_func: CodegenedFunction,
@@ -189,10 +210,24 @@ pub(crate) fn compile_fn(
         })
     };
 
+    if codegened_func.egraph == Some(true) {
+        prof.generic_activity("egraph").run(|| {
+            if let Err(err) = crate::optimize::run_egraph(context, module.isa()) {
+                bug!("Error while optimizing {}: {err:?}", codegened_func.symbol_name);
+            }
+        });
+    }
+
     // Define function
     prof.generic_activity("define function").run(|| {
         context.want_disasm = should_write_ir;
-        match module.define_function(codegened_func.func_id, context) {
+        let result = match (codegened_func.egraph, unoptimized_isa) {
+            (Some(false), Some(isa)) => {
+                module.define_function_with_isa(codegened_func.func_id, context, isa)
+            }
+            _ => module.define_function(codegened_func.func_id, context),
+        };
+        match result {
             Ok(()) => {}
             Err(ModuleError::Compilation(CodegenError::ImplLimitExceeded)) => {
                 dcx.fatal(format!(
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 /// Configuration of cg_clif as passed in through `-Cllvm-args` and various env vars.
 #[derive(Debug)]
 pub struct BackendConfig {
@@ -10,6 +12,25 @@ pub struct BackendConfig {
     ///
     /// Defaults to the value of `CG_CLIF_JIT_ARGS`.
     pub jit_args: Vec<String>,
+
+    /// How much to optimize the MIR of each function after monomorphization.
+    ///
+    /// 0 codegens the MIR as is, 1 simplifies it and 2 additionally inlines small callees from the
+    /// same codegen unit. Defaults to 0 for `-Copt-level=0` and to 2 otherwise. Can be set using
+    /// `-Cllvm-args=mir-opt-level=N`.
+    pub mir_opt_level: Option<u8>,
+
+    /// The cost up to which callees are inlined at MIR opt level 2, doubled for `#[inline]`
+    /// callees.
+    ///
+    /// Defaults to 50. Can be set using `-Cllvm-args=mir-inline-threshold=N`.
+    pub mir_inline_threshold: usize,
+
+    /// Functions with more Cranelift instructions than this are not optimized by the egraph pass,
+    /// as it would take too long.
+    ///
+    /// Defaults to 20000. Can be set using `-Cllvm-args=egraph-max-insts=N`.
+    pub egraph_max_insts: usize,
 }
 
 impl BackendConfig {
@@ -24,6 +45,9 @@ pub fn from_opts(opts: &[String]) -> Result<Self, String> {
                     panic!("CG_CLIF_JIT_ARGS not unicode: {:?}", s);
                 }
             },
+            mir_opt_level: None,
+            mir_inline_threshold: 50,
+            egraph_max_insts: 20_000,
         };
 
         for opt in opts {
@@ -32,6 +56,20 @@ pub fn from_opts(opts: &[String]) -> Result<Self, String> {
                 // testing cg_clif.
                 continue;
             }
+            if let Some((name, value)) = opt.split_once('=') {
+                match name {
+                    "mir-opt-level" => match parse_value(name, value)? {
+                        level @ 0..=2 => config.mir_opt_level = Some(level),
+                        _ => return Err(invalid_value(name, value)),
+                    },
+                    "mir-inline-threshold" => {
+                        config.mir_inline_threshold = parse_value(name, value)?
+                    }
+                    "egraph-max-insts" => config.egraph_max_insts = parse_value(name, value)?,
+                    _ => return Err(format!("Unknown option `{}`", opt)),
+                }
+                continue;
+            }
             match &**opt {
                 "jit-mode" => config.jit_mode = true,
                 _ => return Err(format!("Unknown option `{}`", opt)),
@@ -41,3 +79,11 @@ pub fn from_opts(opts: &[String]) -> Result<Self, String> {
         Ok(config)
     }
 }
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| invalid_value(name, value))
+}
+
+fn invalid_value(name: &str, value: &str) -> String {
+    format!("Invalid value `{}` for `{}`", value, name)
+}
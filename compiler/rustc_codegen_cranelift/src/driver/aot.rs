@@ -8,6 +8,7 @@
 use std::sync::Arc;
 use std::time::Instant;
 
+use cranelift_codegen::isa::TargetIsa;
 use cranelift_object::{ObjectBuilder, ObjectModule};
 use rustc_ast::expand::allocator::AllocatorMethod;
 use rustc_codegen_ssa::back::lto::ThinModule;
@@ -16,6 +17,7 @@
 };
 use rustc_codegen_ssa::traits::{ExtraBackendMethods, WriteBackendMethods};
 use rustc_codegen_ssa::{CompiledModule, ModuleCodegen, ModuleKind};
+use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::profiling::SelfProfilerRef;
 use rustc_errors::{DiagCtxt, DiagCtxtHandle};
 use rustc_hir::attrs::Linkage as RLinkage;
@@ -29,6 +31,7 @@
 use crate::base::CodegenedFunction;
 use crate::debuginfo::TypeDebugContext;
 use crate::global_asm::{GlobalAsmConfig, GlobalAsmContext};
+use crate::optimize::OptConfig;
 use crate::prelude::*;
 use crate::unwind_module::UnwindModule;
 
@@ -36,6 +39,8 @@ pub(crate) struct AotModule {
     producer: String,
     global_asm_config: GlobalAsmConfig,
     module: UnwindModule<ObjectModule>,
+    /// See [`OptConfig::unoptimized_isa`].
+    unoptimized_isa: Option<Arc<dyn TargetIsa>>,
     debug_context: Option<DebugContext>,
     codegened_functions: Vec<CodegenedFunction>,
     global_asm: String,
@@ -74,6 +79,7 @@ fn make_module(tcx: TyCtxt<'_>, cgu_name: &str) -> AotModule {
         producer,
         global_asm_config,
         module,
+        unoptimized_isa: None,
         debug_context,
         codegened_functions,
         global_asm,
@@ -144,13 +150,21 @@ fn emit_module(
     })
 }
 
-fn codegen_cgu(tcx: TyCtxt<'_>, cgu_name: Symbol) -> AotModule {
+fn codegen_cgu(tcx: TyCtxt<'_>, cgu_name: Symbol, opt_config: OptConfig) -> AotModule {
     let _timer = tcx.prof.generic_activity_with_arg("codegen cgu", cgu_name.as_str());
 
     let cgu = tcx.codegen_unit(cgu_name);
     let mono_items = cgu.items_in_deterministic_order(tcx);
+    let instances = mono_items
+        .iter()
+        .filter_map(|&(mono_item, _)| match mono_item {
+            MonoItem::Fn(instance) => Some(instance),
+            MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+        })
+        .collect::<FxHashSet<_>>();
 
     let mut module = make_module(tcx, cgu_name.as_str());
+    module.unoptimized_isa = opt_config.unoptimized_isa(tcx.sess, false);
     let mut type_dbg = TypeDebugContext::default();
     super::predefine_mono_items(tcx, &mut module.module, &mono_items);
     for (mono_item, item_data) in mono_items {
@@ -181,6 +195,8 @@ fn codegen_cgu(tcx: TyCtxt<'_>, cgu_name: Symbol) -> AotModule {
                     Function::new(),
                     &mut module.module,
                     instance,
+                    opt_config,
+                    &instances,
                 );
                 module.codegened_functions.push(codegened_function);
             }
@@ -226,6 +242,7 @@ fn compile_cgu(
                 should_write_ir,
                 &mut cached_context,
                 &mut aot_module.module,
+                aot_module.unoptimized_isa.as_deref(),
                 aot_module.debug_context.as_mut(),
                 &mut aot_module.global_asm,
                 codegened_func,
@@ -264,7 +281,9 @@ fn compile_cgu(
 }
 
 #[derive(Copy, Clone)]
-pub(crate) struct AotDriver;
+pub(crate) struct AotDriver {
+    pub(crate) opt_config: OptConfig,
+}
 
 impl ExtraBackendMethods for AotDriver {
     type Module = AotModule;
@@ -292,7 +311,7 @@ fn compile_codegen_unit(
             dep_node,
             tcx,
             || {
-                let aot_module = codegen_cgu(tcx, cgu_name);
+                let aot_module = codegen_cgu(tcx, cgu_name, self.opt_config);
                 ModuleCodegen::new_regular(cgu_name.as_str().to_owned(), aot_module)
             },
             Some(rustc_middle::dep_graph::hash_result),
@@ -4,9 +4,11 @@
 use std::ffi::CString;
 use std::os::raw::{c_char, c_int};
 
+use cranelift_codegen::isa::TargetIsa;
 use cranelift_jit::{JITBuilder, JITModule};
 use rustc_codegen_ssa::CrateInfo;
 use rustc_codegen_ssa::base::{allocator_kind_for_codegen, allocator_shim_contents};
+use rustc_data_structures::fx::FxHashSet;
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::mono::MonoItem;
 use rustc_session::Session;
@@ -14,6 +16,7 @@
 use rustc_span::sym;
 
 use crate::debuginfo::TypeDebugContext;
+use crate::optimize::OptConfig;
 use crate::prelude::*;
 use crate::unwind_module::UnwindModule;
 
@@ -36,7 +39,12 @@ fn create_jit_module(
     (jit_module, cx)
 }
 
-pub(crate) fn run_jit(tcx: TyCtxt<'_>, target_cpu: String, jit_args: Vec<String>) -> ! {
+pub(crate) fn run_jit(
+    tcx: TyCtxt<'_>,
+    target_cpu: String,
+    jit_args: Vec<String>,
+    opt_config: OptConfig,
+) -> ! {
     if !tcx.crate_types().contains(&rustc_session::config::CrateType::Executable) {
         tcx.dcx().fatal("can't jit non-executable crate");
     }
@@ -45,6 +53,7 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, target_cpu: String, jit_args: Vec<String>
     let crate_info = CrateInfo::new(tcx, target_cpu);
     let should_write_ir = crate::pretty_clif::should_write_ir(tcx.sess);
     let (mut jit_module, mut debug_context) = create_jit_module(tcx, &crate_info);
+    let unoptimized_isa = opt_config.unoptimized_isa(tcx.sess, true);
     let mut cached_context = Context::new();
 
     let cgus = tcx.collect_and_partition_mono_items(()).codegen_units;
@@ -55,6 +64,13 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, target_cpu: String, jit_args: Vec<String>
         .collect::<FxHashMap<_, _>>()
         .into_iter()
         .collect::<Vec<(_, _)>>();
+    let instances = mono_items
+        .iter()
+        .filter_map(|&(mono_item, _)| match mono_item {
+            MonoItem::Fn(instance) => Some(instance),
+            MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+        })
+        .collect::<FxHashSet<_>>();
 
     tcx.sess.time("codegen mono items", || {
         super::predefine_mono_items(tcx, &mut jit_module, &mono_items);
@@ -68,7 +84,10 @@ pub(crate) fn run_jit(tcx: TyCtxt<'_>, target_cpu: String, jit_args: Vec<String>
                         debug_context.as_mut(),
                         &mut cached_context,
                         &mut jit_module,
+                        unoptimized_isa.as_deref(),
                         inst,
+                        opt_config,
+                        &instances,
                     );
                 }
                 MonoItem::Static(def_id) => {
@@ -127,8 +146,11 @@ fn codegen_and_compile_fn<'tcx>(
     should_write_ir: bool,
     mut debug_context: Option<&mut DebugContext>,
     cached_context: &mut Context,
-    module: &mut dyn Module,
+    module: &mut UnwindModule<JITModule>,
+    unoptimized_isa: Option<&dyn TargetIsa>,
     instance: Instance<'tcx>,
+    opt_config: OptConfig,
+    instances: &FxHashSet<Instance<'tcx>>,
 ) {
     if tcx.codegen_instance_attrs(instance.def).flags.contains(CodegenFnAttrFlags::NAKED) {
         tcx.dcx()
@@ -152,6 +174,8 @@ fn codegen_and_compile_fn<'tcx>(
             cached_func,
             module,
             instance,
+            opt_config,
+            instances,
         );
 
         let mut global_asm = String::new();
@@ -162,6 +186,7 @@ fn codegen_and_compile_fn<'tcx>(
             should_write_ir,
             cached_context,
             module,
+            unoptimized_isa,
             debug_context.as_deref_mut(),
             &mut global_asm,
             codegened_func,
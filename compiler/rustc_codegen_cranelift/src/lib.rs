@@ -21,6 +21,7 @@
 extern crate rustc_hir;
 extern crate rustc_index;
 extern crate rustc_log;
+extern crate rustc_mir_transform;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_symbol_mangling;
@@ -42,9 +43,10 @@
 use rustc_data_structures::unord::UnordSet;
 use rustc_log::tracing::info;
 use rustc_middle::dep_graph::WorkProductMap;
-use rustc_session::config::{NATIVE_CPU, OutputFilenames};
+use rustc_session::config::{NATIVE_CPU, OptLevel, OutputFilenames};
 use rustc_session::{IncrCompSession, Session};
 use rustc_span::{Symbol, sym};
+use rustc_target::callconv::BackendLoweredAbi;
 use rustc_target::spec::{Arch, CfgAbi, Env, Os};
 
 pub use crate::config::*;
@@ -154,6 +156,12 @@ fn thin_lto_supported(&self) -> bool {
         false
     }
 
+    fn llvm_compatible_abi(&self) -> BackendLoweredAbi {
+        // `enable_llvm_abi_extensions` makes Cranelift pass `i128` the same way as LLVM. Return
+        // values that don't fit in the return registers and SIMD vectors are lowered differently.
+        BackendLoweredAbi::WIDE_SCALAR
+    }
+
     fn target_config(&self, sess: &Session) -> TargetConfig {
         // FIXME return the actually used target features. this is necessary for #[cfg(target_feature)]
         let target_features = match sess.target.arch {
@@ -216,14 +224,23 @@ fn target_cpu(&self, sess: &Session) -> String {
     fn codegen_crate(&self, tcx: TyCtxt<'_>) -> Box<dyn Any> {
         info!("codegen crate {}", tcx.crate_name(LOCAL_CRATE));
         let config = self.config.get().unwrap();
+        let opt_config = optimize::OptConfig::new(tcx.sess, config);
         if config.jit_mode {
             #[cfg(feature = "jit")]
-            driver::jit::run_jit(tcx, self.target_cpu(tcx.sess), config.jit_args.clone());
+            driver::jit::run_jit(
+                tcx,
+                self.target_cpu(tcx.sess),
+                config.jit_args.clone(),
+                opt_config,
+            );
 
             #[cfg(not(feature = "jit"))]
             tcx.dcx().fatal("jit support was disabled when compiling rustc_codegen_cranelift");
         } else {
-            Box::new(rustc_codegen_ssa::base::codegen_crate(driver::aot::AotDriver, tcx))
+            Box::new(rustc_codegen_ssa::base::codegen_crate(
+                driver::aot::AotDriver { opt_config },
+                tcx,
+            ))
         }
     }
 
@@ -266,6 +283,15 @@ fn target_tuple(sess: &Session) -> target_lexicon::Triple {
 }
 
 fn build_isa(sess: &Session, jit: bool) -> Arc<dyn TargetIsa + 'static> {
+    build_isa_with_opt_level(sess, jit, sess.opts.optimize)
+}
+
+/// Like [`build_isa`], but configures Cranelift as if `-Copt-level` was `opt_level`.
+fn build_isa_with_opt_level(
+    sess: &Session,
+    jit: bool,
+    opt_level: OptLevel,
+) -> Arc<dyn TargetIsa + 'static> {
     use target_lexicon::BinaryFormat;
 
     let target_tuple = crate::target_tuple(sess);
@@ -299,8 +325,8 @@ fn build_isa(sess: &Session, jit: bool) -> Arc<dyn TargetIsa + 'static> {
             .unwrap();
     }
 
-    use rustc_session::config::OptLevel;
-    match sess.opts.optimize {
+    // Individual functions may still be compiled differently, see `optimize::OptConfig`.
+    match opt_level {
         OptLevel::No => {
             flags_builder.set("opt_level", "none").unwrap();
         }
@@ -1,3 +1,114 @@
 //! Various optimizations specific to cg_clif
 
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use cranelift_codegen::CodegenResult;
+use cranelift_codegen::control::ControlPlane;
+use cranelift_codegen::isa::TargetIsa;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::attrs::OptimizeAttr;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_mir_transform::MonoOptConfig;
+use rustc_session::Session;
+use rustc_session::config::OptLevel;
+
+use crate::BackendConfig;
+use crate::prelude::*;
+
 pub(crate) mod peephole;
+
+/// Optimizations done by cg_clif on top of the MIR optimizations of rustc.
+///
+/// Cranelift runs its egraph pass according to the `-Copt-level` the ISA is built with. Individual
+/// functions can deviate from that as decided by [`OptConfig::egraph_override`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct OptConfig {
+    /// The MIR optimizations to run on each function after monomorphization, if any.
+    mir: Option<MonoOptConfig>,
+    /// Whether the ISA runs the egraph pass, which is the case when optimizing.
+    egraph: bool,
+    /// See [`BackendConfig::egraph_max_insts`].
+    egraph_max_insts: usize,
+}
+
+impl OptConfig {
+    pub(crate) fn new(sess: &Session, backend_config: &BackendConfig) -> Self {
+        let optimize = sess.opts.optimize != OptLevel::No;
+        let mir = match backend_config.mir_opt_level.unwrap_or(if optimize { 2 } else { 0 }) {
+            0 => None,
+            1 => Some(MonoOptConfig { inline_threshold: None }),
+            _ => {
+                Some(MonoOptConfig { inline_threshold: Some(backend_config.mir_inline_threshold) })
+            }
+        };
+        OptConfig { mir, egraph: optimize, egraph_max_insts: backend_config.egraph_max_insts }
+    }
+
+    /// Returns the MIR to codegen for `instance`, which is part of `codegen_unit`.
+    ///
+    /// When MIR optimizations are enabled this is already instantiated for `instance` and owned by
+    /// the caller, so that it is freed once the function has been codegened.
+    pub(crate) fn instance_mir<'tcx>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        instance: Instance<'tcx>,
+        codegen_unit: &FxHashSet<Instance<'tcx>>,
+    ) -> Cow<'tcx, Body<'tcx>> {
+        match self.mir {
+            Some(config) => {
+                let _timer =
+                    tcx.prof.generic_activity_with_arg("optimize mir", instance.to_string());
+                Cow::Owned(rustc_mir_transform::optimize_mono_body(
+                    tcx,
+                    instance,
+                    codegen_unit,
+                    config,
+                ))
+            }
+            None => Cow::Borrowed(tcx.instance_mir(instance.def)),
+        }
+    }
+
+    /// Returns the ISA to compile functions with for which [`OptConfig::egraph_override`] disables
+    /// the egraph pass, if the ISA of the module runs it.
+    pub(crate) fn unoptimized_isa(
+        &self,
+        sess: &Session,
+        jit: bool,
+    ) -> Option<Arc<dyn TargetIsa + 'static>> {
+        self.egraph.then(|| crate::build_isa_with_opt_level(sess, jit, OptLevel::No))
+    }
+
+    /// Whether to run the egraph pass on `func`, the Cranelift IR of `instance`, if this differs
+    /// from what the ISA does.
+    pub(crate) fn egraph_override<'tcx>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        instance: Instance<'tcx>,
+        func: &Function,
+    ) -> Option<bool> {
+        let attrs = tcx.codegen_instance_attrs(instance.def);
+        let enabled = match attrs.optimize {
+            OptimizeAttr::DoNotOptimize => false,
+            OptimizeAttr::Speed => true,
+            OptimizeAttr::Default | OptimizeAttr::Size => {
+                self.egraph && !attrs.flags.contains(CodegenFnAttrFlags::COLD)
+            }
+        };
+        let enabled = enabled && func.dfg.num_insts() <= self.egraph_max_insts;
+        (enabled != self.egraph).then_some(enabled)
+    }
+}
+
+/// Runs the egraph pass on the function in `context` when the ISA does not.
+///
+/// This does the same preparation as Cranelift does before the egraph pass when `opt_level` is not
+/// `none`. Compiling the function afterwards repeats it, which is cheap.
+pub(crate) fn run_egraph(context: &mut Context, isa: &dyn TargetIsa) -> CodegenResult<()> {
+    context.compute_cfg();
+    context.legalize(isa)?;
+    context.compute_domtree();
+    context.eliminate_unreachable_code(isa)?;
+    context.egraph_pass(isa, &mut ControlPlane::default())
+}
@@ -21,6 +21,32 @@ pub(crate) fn new(mut module: T, pic_eh_frame: bool) -> Self {
         let unwind_context = UnwindContext::new(&mut module, pic_eh_frame);
         UnwindModule { module, unwind_context }
     }
+
+    /// Like [`Module::define_function`], but compiles the function with `isa` rather than the ISA
+    /// of the module. `isa` must only differ from the latter in its optimization settings.
+    pub(crate) fn define_function_with_isa(
+        &mut self,
+        func: FuncId,
+        ctx: &mut Context,
+        isa: &dyn TargetIsa,
+    ) -> ModuleResult<()> {
+        ctx.compile(isa, &mut ControlPlane::default())?;
+        let code = ctx.compiled_code().unwrap();
+        let relocs = code
+            .buffer
+            .relocs()
+            .iter()
+            .map(|reloc| ModuleReloc::from_mach_reloc(reloc, &ctx.func, func))
+            .collect::<Vec<_>>();
+        self.module.define_function_bytes(
+            func,
+            u64::from(code.buffer.alignment),
+            code.code_buffer(),
+            &relocs,
+        )?;
+        self.unwind_context.add_function(&mut self.module, func, ctx);
+        Ok(())
+    }
 }
 
 impl UnwindModule<ObjectModule> {
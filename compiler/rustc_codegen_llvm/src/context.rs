@@ -1049,6 +1049,10 @@ fn declare_c_main(&self, fn_type: Self::Type) -> Option<Self::Function> {
     fn intrinsic_call_expects_place_always(&self, name: Symbol) -> bool {
         matches!(name, sym::black_box)
     }
+
+    fn instruction_count(&self, llfn: &'ll Value) -> Option<u64> {
+        Some(unsafe { llvm::LLVMRustFunctionInstructionCount(llfn) })
+    }
 }
 
 impl<'ll> CodegenCx<'ll, '_> {
@@ -41,6 +41,7 @@
 use rustc_session::config::{OptLevel, OutputFilenames, PrintKind, PrintRequest};
 use rustc_session::{IncrCompSession, Session};
 use rustc_span::{Symbol, sym};
+use rustc_target::callconv::BackendLoweredAbi;
 use rustc_target::spec::{RelocModel, TlsModel};
 
 use crate::llvm::ToLlvmBool;
@@ -317,6 +318,10 @@ fn has_zstd(&self) -> bool {
         llvm::LLVMRustLLVMHasZstdCompression()
     }
 
+    fn llvm_compatible_abi(&self) -> BackendLoweredAbi {
+        BackendLoweredAbi::all()
+    }
+
     fn has_mnemonic(&self, sess: &Session, mnemonic: &str) -> bool {
         llvm_util::target_has_mnemonic(sess, mnemonic)
     }
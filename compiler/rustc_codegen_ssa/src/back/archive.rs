@@ -611,6 +611,10 @@ fn build_inner(self, output: &Path, symbols: Option<ArchiveSymbols>) -> io::Resu
         let mut entries = Vec::new();
 
         for (entry_name, entry) in self.entries {
+            let edit_error = |err| {
+                let member = String::from_utf8_lossy(&entry_name);
+                io_error_context(&format!("failed to edit the symbols of `{member}`"), err)
+            };
             let data: Box<dyn AsRef<[u8]>> = match entry.source {
                 ArchiveEntrySource::Archive { archive_index, file_range } => {
                     let src_archive = &self.src_archives[archive_index];
@@ -636,7 +640,10 @@ fn build_inner(self, output: &Path, symbols: Option<ArchiveSymbols>) -> io::Resu
                     if entry.kind == ArchiveEntryKind::RustObj
                         && let Some(sym) = &symbols
                     {
-                        Box::new(apply_edits(data, &sym.exported, sym.hide, rename.as_ref()))
+                        Box::new(
+                            apply_edits(data, &sym.exported, sym.hide, rename.as_ref())
+                                .map_err(edit_error)?,
+                        )
                     } else {
                         Box::new(data)
                     }
@@ -650,7 +657,8 @@ fn build_inner(self, output: &Path, symbols: Option<ArchiveSymbols>) -> io::Resu
                     if entry.kind == ArchiveEntryKind::RustObj
                         && let Some(sym) = &symbols
                     {
-                        let edited = apply_edits(&mmap, &sym.exported, sym.hide, rename.as_ref());
+                        let edited = apply_edits(&mmap, &sym.exported, sym.hide, rename.as_ref())
+                            .map_err(edit_error)?;
                         match edited {
                             Cow::Borrowed(_) => Box::new(mmap) as Box<dyn AsRef<[u8]>>,
                             Cow::Owned(v) => Box::new(v),
@@ -1,3 +1,4 @@
+mod incremental;
 mod raw_dylib;
 
 use std::collections::BTreeSet;
@@ -51,6 +52,7 @@
 };
 use tracing::{debug, info, warn};
 
+use self::incremental::IncrementalLink;
 use super::archive::{
     AddArchiveKind, ArchiveBuilder, ArchiveBuilderBuilder, ArchiveEntryKind, ArchiveSymbols,
 };
@@ -59,7 +61,7 @@
 use super::metadata::{MetadataPosition, create_wrapper_file};
 use super::rmeta_link::RmetaLinkCache;
 use super::rpath::{self, RPathConfig};
-use super::{apple, rmeta_link, versioned_llvm_target};
+use super::{apple, linker_map, rmeta_link, size_report, versioned_llvm_target};
 use crate::base::needs_allocator_shim_for_linking;
 use crate::{
     CodegenLintLevelSpecs, CompiledModule, CompiledModules, CrateInfo, NativeLib, SymbolExport,
@@ -386,6 +388,14 @@ pub fn link_binary(
                     );
                 }
                 _ => {
+                    if sess.opts.unstable_opts.linker_map {
+                        linker_map::remove_stale(&out_filename);
+                    }
+                    let mut incremental_link =
+                        IncrementalLink::new(sess, &out_filename, &compiled_modules);
+                    let tmpdir = incremental_link
+                        .as_ref()
+                        .map_or_else(|| path.as_ref().to_owned(), |link| link.tmpdir().to_owned());
                     link_natively(
                         sess,
                         archive_builder_builder,
@@ -395,9 +405,19 @@ pub fn link_binary(
                         &compiled_modules,
                         &crate_info,
                         &metadata,
-                        path.as_ref(),
+                        &tmpdir,
                         codegen_backend,
+                        incremental_link.as_mut(),
                     );
+                    if let Some(incremental_link) = incremental_link {
+                        incremental_link.finish(sess, &out_filename);
+                    }
+                    if size_report::enabled(sess) {
+                        size_report::report(sess, &crate_info, &out_filename);
+                    }
+                    if sess.opts.unstable_opts.linker_map {
+                        linker_map::report(sess, &out_filename);
+                    }
                 }
             }
             if sess.opts.json_artifact_notifications {
@@ -836,7 +856,10 @@ fn link_staticlib(
     let rename = sess.opts.unstable_opts.staticlib_rename_internal_symbols;
 
     let exported_symbols = if hide || rename {
-        if !matches!(sess.target.binary_format, BinaryFormat::Elf | BinaryFormat::MachO) {
+        if !matches!(
+            sess.target.binary_format,
+            BinaryFormat::Elf | BinaryFormat::MachO | BinaryFormat::Coff | BinaryFormat::Xcoff
+        ) {
             if hide {
                 sess.dcx().emit_warn(diagnostics::StaticlibHideInternalSymbolsUnsupported {
                     binary_format: sess.target.archive_format.to_string(),
@@ -1229,6 +1252,7 @@ fn link_natively(
     metadata: &EncodedMetadata,
     tmpdir: &Path,
     codegen_backend: &'static str,
+    mut incremental_link: Option<&mut IncrementalLink>,
 ) {
     info!("preparing {:?} to {:?}", crate_type, out_filename);
     let (linker_path, flavor) = linker_and_flavor(sess);
@@ -1257,6 +1281,7 @@ fn link_natively(
         metadata,
         self_contained_components,
         codegen_backend,
+        incremental_link.as_deref_mut(),
     );
 
     linker::disable_localization(&mut cmd);
@@ -1397,6 +1422,15 @@ fn link_natively(
             continue;
         }
 
+        if let Some(incremental_link) = incremental_link.as_deref_mut()
+            && incremental_link.fall_back_to_full_link(&mut cmd, &out)
+        {
+            info!("linker output: {:?}", out);
+            info!("The linker failed to update the previous output. Retrying with a full link.");
+            info!("{cmd:?}");
+            continue;
+        }
+
         break;
     }
 
@@ -2626,13 +2660,14 @@ fn msvc_drectve_export(symbol: &SymbolExport) -> String {
 }
 
 /// Add object files containing code from the current crate.
-fn add_local_crate_regular_objects(cmd: &mut dyn Linker, compiled_modules: &CompiledModules) {
+fn add_local_crate_regular_objects(
+    cmd: &mut dyn Linker,
+    compiled_modules: &CompiledModules,
+    incremental_link: Option<&IncrementalLink>,
+) {
     for m in &compiled_modules.modules {
-        if let Some(obj) = &m.object {
-            cmd.add_object(obj);
-        }
-        if let Some(obj) = &m.global_asm_object {
-            cmd.add_object(obj);
+        for obj in m.object.iter().chain(&m.global_asm_object) {
+            cmd.add_object(incremental_link.map_or(obj.as_path(), |link| link.object_path(obj)));
         }
     }
 }
@@ -2643,15 +2678,13 @@ fn add_local_crate_allocator_objects(
     compiled_modules: &CompiledModules,
     crate_info: &CrateInfo,
     crate_type: CrateType,
+    incremental_link: Option<&IncrementalLink>,
 ) {
     if needs_allocator_shim_for_linking(&crate_info.dependency_formats, crate_type)
         && let Some(m) = &compiled_modules.allocator_module
     {
-        if let Some(obj) = &m.object {
-            cmd.add_object(obj);
-        }
-        if let Some(obj) = &m.global_asm_object {
-            cmd.add_object(obj);
+        for obj in m.object.iter().chain(&m.global_asm_object) {
+            cmd.add_object(incremental_link.map_or(obj.as_path(), |link| link.object_path(obj)));
         }
     }
 }
@@ -2906,6 +2939,7 @@ fn linker_with_args(
     metadata: &EncodedMetadata,
     self_contained_components: LinkSelfContainedComponents,
     codegen_backend: &'static str,
+    incremental_link: Option<&mut IncrementalLink>,
 ) -> (Command, Vec<jobserver::Acquired>) {
     let self_contained_crt_objects = self_contained_components.is_crt_objects_enabled();
     let cmd = &mut *super::linker::get_linker(
@@ -2996,7 +3030,7 @@ fn linker_with_args(
     // link line. And finally upstream native libraries can't depend on anything
     // in this DAG so far because they can only depend on other native libraries
     // and such dependencies are also required to be specified.
-    add_local_crate_regular_objects(cmd, compiled_modules);
+    add_local_crate_regular_objects(cmd, compiled_modules, incremental_link.as_deref());
     add_local_crate_metadata_objects(
         cmd,
         sess,
@@ -3006,7 +3040,13 @@ fn linker_with_args(
         crate_info,
         metadata,
     );
-    add_local_crate_allocator_objects(cmd, compiled_modules, crate_info, crate_type);
+    add_local_crate_allocator_objects(
+        cmd,
+        compiled_modules,
+        crate_info,
+        crate_type,
+        incremental_link.as_deref(),
+    );
 
     // Avoid linking to dynamic libraries unless they satisfy some undefined symbols
     // at the point at which they are specified on the command line.
@@ -3155,6 +3195,7 @@ fn linker_with_args(
         crate_info,
         out_filename,
         tmpdir,
+        incremental_link.is_some(),
     );
 
     // Can be used for arbitrary order-independent options.
@@ -3182,6 +3223,10 @@ fn linker_with_args(
     // to it and remove the option. Currently the last holdout is wasm32-unknown-emscripten.
     add_post_link_args(cmd, sess, flavor);
 
+    if let Some(incremental_link) = incremental_link {
+        incremental_link.add_args(cmd, path, out_filename);
+    }
+
     // Only LLD supports controlling parallelism at the moment.
     let mut tokens = Vec::new();
     if let LinkerJobs::Explicit(limit) = sess.opts.jobs.linker
@@ -3226,6 +3271,7 @@ fn add_order_independent_options(
     crate_info: &CrateInfo,
     out_filename: &Path,
     tmpdir: &Path,
+    incremental_link: bool,
 ) {
     // Take care of the flavors and CLI options requesting the `lld` linker.
     add_lld_args(cmd, sess, flavor, self_contained_components);
@@ -3292,7 +3338,8 @@ fn add_order_independent_options(
 
     // Try to strip as much out of the generated object by removing unused
     // sections if possible. See more comments in linker.rs
-    if !sess.link_dead_code() {
+    // Incremental linkers can't remove sections, which could be used again after an update.
+    if !sess.link_dead_code() && !incremental_link {
         // If PGO is enabled sometimes gc_sections will remove the profile data section
         // as it appears to be unused. This can then cause the PGO profile file to lose
         // some functions. If we are generating a profile we shouldn't strip those metadata
@@ -3343,6 +3390,10 @@ fn add_order_independent_options(
         cmd.ehcont_guard();
     }
 
+    if sess.opts.unstable_opts.linker_map {
+        cmd.map_file(&linker_map::map_path(out_filename));
+    }
+
     add_rpath_args(cmd, sess, crate_info, out_filename);
 }
 
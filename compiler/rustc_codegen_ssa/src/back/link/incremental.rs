@@ -0,0 +1,370 @@
+//! Incremental linking for `-Zincremental-link`.
+//!
+//! Linkers that link incrementally update their previous output in place, and only read again the
+//! inputs that changed since, which they tell by their modification time. So the objects of the
+//! local crate, and the temporary files of the link, are given to the linker at paths that are the
+//! same in every session, in a directory next to the output, and an object there is only replaced
+//! when its contents changed. Objects reused from the incremental compilation cache are hard links
+//! of the same saved work product in every session, so they are recognized by their size and
+//! modification time alone; the others are compared by hash.
+//!
+//! The layout of the output depends on the whole command line of the link, so the linker is only
+//! asked to update the output if the command line is the one of the last link, which was itself
+//! incremental, and the output was not modified since. Otherwise the output is linked in full, as it
+//! is when the linker fails to update it.
+//!
+//! `link.exe` pads functions and calls them through thunks so that they can be replaced in place,
+//! and keeps its state in an `.ilk` file. GNU gold reserves space in every section of the output,
+//! and keeps its state in the output itself.
+
+use std::ffi::OsString;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use std::{fs, io};
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::memmap::Mmap;
+use rustc_data_structures::stable_hash::StableHasher;
+use rustc_fs_util::link_or_copy;
+use rustc_hashes::Hash128;
+use rustc_session::Session;
+use rustc_session::config::Strip;
+use rustc_target::spec::{Cc, LinkerFlavor, Lld};
+use tracing::info;
+
+use super::{is_msvc_link_exe, linker_and_flavor};
+use crate::back::command::Command;
+use crate::back::linker::Linker;
+use crate::{CompiledModules, diagnostics};
+
+#[cfg(test)]
+mod tests;
+
+/// The first line of the state file, to be changed along with its format.
+const STATE_VERSION: &str = "rustc-incremental-link 1";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IncrementalLinker {
+    Msvc,
+    Gold,
+}
+
+/// The linker, if it is one that can link incrementally.
+fn incremental_linker(sess: &Session) -> Option<IncrementalLinker> {
+    if is_msvc_link_exe(sess) {
+        return Some(IncrementalLinker::Msvc);
+    }
+    let (linker_path, flavor) = linker_and_flavor(sess);
+    let is_gold = match flavor {
+        LinkerFlavor::Gnu(Cc::No, Lld::No) => {
+            linker_path.file_name().is_some_and(|name| name == "ld.gold" || name == "gold")
+        }
+        LinkerFlavor::Gnu(Cc::Yes, Lld::No) => {
+            sess.opts.cg.link_args.iter().any(|arg| arg == "-fuse-ld=gold")
+        }
+        _ => false,
+    };
+    is_gold.then_some(IncrementalLinker::Gold)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    /// Nanoseconds since the Unix epoch.
+    modified: u128,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> io::Result<FileStamp> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map_err(io::Error::other)?;
+        Ok(FileStamp { len: metadata.len(), modified: modified.as_nanos() })
+    }
+}
+
+/// An object of the local crate, as it was given to the linker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StagedObject {
+    /// The file name of the object, in the `objects` directory.
+    name: String,
+    /// The stamp of the object the staged one was taken from.
+    source: FileStamp,
+    hash: Hash128,
+}
+
+/// What is known of the output of the last incremental link.
+#[derive(Debug, PartialEq, Eq)]
+struct State {
+    /// The hash of the linker and its arguments.
+    command: Hash128,
+    output: FileStamp,
+    objects: Vec<StagedObject>,
+}
+
+impl State {
+    fn parse(s: &str) -> Option<State> {
+        fn hash(s: &str) -> Option<Hash128> {
+            u128::from_str_radix(s, 16).ok().map(Hash128::new)
+        }
+        fn stamp(len: &str, modified: &str) -> Option<FileStamp> {
+            Some(FileStamp { len: len.parse().ok()?, modified: modified.parse().ok()? })
+        }
+
+        let mut lines = s.lines();
+        if lines.next()? != STATE_VERSION {
+            return None;
+        }
+        let command = hash(lines.next()?.strip_prefix("command ")?)?;
+        let mut output = lines.next()?.strip_prefix("output ")?.split(' ');
+        let output = stamp(output.next()?, output.next()?)?;
+        let objects = lines
+            .map(|line| {
+                let mut fields = line.strip_prefix("object ")?.splitn(4, ' ');
+                let source = stamp(fields.next()?, fields.next()?)?;
+                let hash = hash(fields.next()?)?;
+                Some(StagedObject { name: fields.next()?.to_owned(), source, hash })
+            })
+            .collect::<Option<_>>()?;
+        Some(State { command, output, objects })
+    }
+
+    fn serialize(&self) -> String {
+        let mut s = format!(
+            "{STATE_VERSION}\ncommand {:x}\noutput {} {}\n",
+            self.command, self.output.len, self.output.modified
+        );
+        for object in &self.objects {
+            s += &format!(
+                "object {} {} {:x} {}\n",
+                object.source.len, object.source.modified, object.hash, object.name
+            );
+        }
+        s
+    }
+}
+
+fn hash_file(path: &Path) -> io::Result<Hash128> {
+    // SAFETY: The objects of the local crate are not modified while they are being linked.
+    let mmap = unsafe { Mmap::map(fs::File::open(path)?) }?;
+    let mut hasher = StableHasher::new();
+    hasher.write(&mmap);
+    Ok(hasher.finish())
+}
+
+/// What gold says when it cannot update the previous output: when a section or table outgrew the
+/// space reserved for it, and when the previous output is not one it can update.
+const GOLD_UPDATE_FAILURES: [&str; 2] =
+    ["relink with --incremental-full", "cannot perform incremental link"];
+
+/// Whether gold failed because it could not update the previous output, rather than for a reason
+/// that a full link would fail for as well.
+fn gold_update_failed(linker_output: &str) -> bool {
+    linker_output
+        .lines()
+        .any(|line| GOLD_UPDATE_FAILURES.iter().any(|failure| line.contains(failure)))
+}
+
+/// The argument that has gold link in full instead of updating the previous output.
+fn full_link_arg(arg: OsString) -> OsString {
+    match arg.to_str().and_then(|arg| arg.strip_suffix("--incremental-update")) {
+        Some(prefix) => format!("{prefix}--incremental-full").into(),
+        None => arg,
+    }
+}
+
+/// The incremental link of one output.
+pub(super) struct IncrementalLink {
+    linker: IncrementalLinker,
+    /// The directory the state of the incremental link is kept in.
+    dir: PathBuf,
+    tmpdir: PathBuf,
+    previous: Option<State>,
+    /// The objects of the local crate, and where they are staged for the linker.
+    staged: FxHashMap<PathBuf, PathBuf>,
+    objects: Vec<StagedObject>,
+    command: Option<Hash128>,
+    /// Whether the linker updates the previous output, rather than linking it in full.
+    update: bool,
+}
+
+impl IncrementalLink {
+    /// Prepares to link `out_filename` incrementally, if `-Zincremental-link` asks for it and the
+    /// linker can.
+    pub(super) fn new(
+        sess: &Session,
+        out_filename: &Path,
+        compiled_modules: &CompiledModules,
+    ) -> Option<IncrementalLink> {
+        if !sess.opts.unstable_opts.incremental_link {
+            return None;
+        }
+        let unavailable = |reason| {
+            sess.dcx().emit_warn(diagnostics::IncrementalLinkUnavailable { reason });
+            None
+        };
+        let Some(linker) = incremental_linker(sess) else {
+            return unavailable("only `link.exe` and GNU gold can link incrementally");
+        };
+        if sess.opts.incremental.is_none() {
+            return unavailable("incremental compilation is not enabled");
+        }
+        if sess.opts.cg.strip != Strip::None {
+            return unavailable("the output is stripped after it is linked");
+        }
+        if sess.opts.cg.linker_plugin_lto.enabled() {
+            return unavailable("linker-plugin-based LTO is enabled");
+        }
+
+        let mut dir = OsString::from(out_filename);
+        dir.push(".incr-link");
+        let dir = PathBuf::from(dir);
+        let state_path = dir.join("state");
+        let previous = fs::read_to_string(&state_path).ok().and_then(|s| State::parse(&s));
+        let mut link = IncrementalLink {
+            linker,
+            tmpdir: dir.join("tmp"),
+            dir,
+            previous,
+            staged: FxHashMap::default(),
+            objects: Vec::new(),
+            command: None,
+            update: false,
+        };
+        // A link that fails must not leave behind the state of an output it didn't produce.
+        let prepared = match fs::remove_file(&state_path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => link.prepare_dirs().and_then(|()| link.stage_objects(compiled_modules)),
+        };
+        match prepared {
+            Ok(()) => Some(link),
+            Err(error) => {
+                sess.dcx().emit_warn(diagnostics::IncrementalLinkState { path: &link.dir, error });
+                None
+            }
+        }
+    }
+
+    /// Creates the directories of the state, and empties the one of the temporary files.
+    fn prepare_dirs(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.tmpdir) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+        fs::create_dir_all(&self.tmpdir)?;
+        fs::create_dir_all(self.dir.join("objects"))
+    }
+
+    fn stage_objects(&mut self, compiled_modules: &CompiledModules) -> io::Result<()> {
+        let objects_dir = self.dir.join("objects");
+        let previous: FxHashMap<&str, &StagedObject> = self
+            .previous
+            .iter()
+            .flat_map(|state| &state.objects)
+            .map(|object| (&*object.name, object))
+            .collect();
+
+        let mut changed = 0;
+        let modules = compiled_modules.modules.iter().chain(&compiled_modules.allocator_module);
+        for path in modules.flat_map(|m| m.object.iter().chain(&m.global_asm_object)) {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let staged = objects_dir.join(&name);
+            let source = FileStamp::of(path)?;
+            let last = previous.get(&*name).filter(|_| staged.exists());
+            let hash = match last {
+                Some(last) if last.source == source => last.hash,
+                _ => hash_file(path)?,
+            };
+            if last.is_none_or(|last| last.hash != hash) {
+                changed += 1;
+                link_or_copy(path, &staged)?;
+            }
+            self.staged.insert(path.clone(), staged);
+            self.objects.push(StagedObject { name, source, hash });
+        }
+        info!(
+            "{changed} of {} objects changed since the last incremental link",
+            self.objects.len()
+        );
+
+        // The objects of codegen units that are gone would pile up otherwise.
+        for entry in fs::read_dir(&objects_dir)? {
+            let entry = entry?;
+            if !self.objects.iter().any(|object| *object.name == *entry.file_name()) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The directory for the temporary files of the link. Unlike a fresh temporary directory, it
+    /// is the same in every session.
+    pub(super) fn tmpdir(&self) -> &Path {
+        &self.tmpdir
+    }
+
+    /// The path the linker is given the object at `path` at.
+    pub(super) fn object_path<'a>(&'a self, path: &'a Path) -> &'a Path {
+        self.staged.get(path).map_or(path, |staged| staged)
+    }
+
+    /// Asks the linker to link incrementally. This comes after all other arguments, as the output
+    /// is only updated if they are the same as in the last link.
+    pub(super) fn add_args(
+        &mut self,
+        cmd: &mut dyn Linker,
+        linker_path: &Path,
+        out_filename: &Path,
+    ) {
+        let mut hasher = StableHasher::new();
+        linker_path.hash(&mut hasher);
+        cmd.cmd().get_args().hash(&mut hasher);
+        let command: Hash128 = hasher.finish();
+
+        self.update = self.previous.as_ref().is_some_and(|previous| {
+            previous.command == command && FileStamp::of(out_filename).ok() == Some(previous.output)
+        });
+        self.command = Some(command);
+        info!("linking {out_filename:?} incrementally, update: {}", self.update);
+
+        let ilk = self.dir.join("incremental.ilk");
+        if !self.update && self.linker == IncrementalLinker::Msvc {
+            // `link.exe` falls back to a full link by itself when there is no state.
+            let _ = fs::remove_file(&ilk);
+        }
+        cmd.incremental_link(&ilk, self.update);
+    }
+
+    /// Switches to a full link if the linker failed to update the previous output, and returns
+    /// whether the link should be retried.
+    pub(super) fn fall_back_to_full_link(
+        &mut self,
+        cmd: &mut Command,
+        linker_output: &str,
+    ) -> bool {
+        if !self.update
+            || self.linker != IncrementalLinker::Gold
+            || !gold_update_failed(linker_output)
+        {
+            return false;
+        }
+        self.update = false;
+        for arg in cmd.take_args() {
+            cmd.arg(full_link_arg(arg));
+        }
+        true
+    }
+
+    /// Records the state of the output that was just linked, for the next incremental link.
+    pub(super) fn finish(self, sess: &Session, out_filename: &Path) {
+        let Some(command) = self.command else { return };
+        let state_path = self.dir.join("state");
+        let result = FileStamp::of(out_filename).and_then(|output| {
+            let state = State { command, output, objects: self.objects };
+            fs::write(&state_path, state.serialize())
+        });
+        if let Err(error) = result {
+            sess.dcx().emit_warn(diagnostics::IncrementalLinkState { path: &state_path, error });
+        }
+    }
+}
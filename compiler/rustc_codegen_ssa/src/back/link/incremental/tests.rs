@@ -0,0 +1,68 @@
+use std::ffi::OsString;
+
+use rustc_hashes::Hash128;
+
+use super::{FileStamp, STATE_VERSION, StagedObject, State, full_link_arg, gold_update_failed};
+
+#[test]
+fn state_round_trip() {
+    let state = State {
+        command: Hash128::new(0x0123_4567_89ab_cdef_0011_2233_4455_6677),
+        output: FileStamp { len: 300_000_000, modified: 1_760_000_000_123_456_789 },
+        objects: vec![
+            StagedObject {
+                name: "main.main.a1b2c3d4-cgu.0.rcgu.o".to_owned(),
+                source: FileStamp { len: 4096, modified: 1_759_000_000_000_000_000 },
+                hash: Hash128::new(1),
+            },
+            StagedObject {
+                name: "main.b5c6 with spaces.rcgu.o".to_owned(),
+                source: FileStamp { len: 0, modified: 0 },
+                hash: Hash128::new(u128::MAX),
+            },
+        ],
+    };
+    assert_eq!(State::parse(&state.serialize()), Some(state));
+}
+
+#[test]
+fn invalid_state() {
+    assert_eq!(State::parse(""), None);
+    assert_eq!(State::parse("rustc-incremental-link 0\ncommand 1\noutput 1 2\n"), None);
+    assert_eq!(State::parse(&format!("{STATE_VERSION}\ncommand 1\n")), None);
+    assert_eq!(
+        State::parse(&format!("{STATE_VERSION}\ncommand 1\noutput 1 2\nobject 1 2\n")),
+        None
+    );
+    assert_eq!(
+        State::parse(&format!("{STATE_VERSION}\ncommand 1\noutput 1 2\n")),
+        Some(State {
+            command: Hash128::new(1),
+            output: FileStamp { len: 1, modified: 2 },
+            objects: Vec::new()
+        })
+    );
+}
+
+#[test]
+fn full_link_args() {
+    let full = |arg: &str| full_link_arg(OsString::from(arg)).into_string().unwrap();
+    assert_eq!(full("--incremental-update"), "--incremental-full");
+    assert_eq!(full("-Wl,--incremental-update"), "-Wl,--incremental-full");
+    assert_eq!(full("--gc-sections"), "--gc-sections");
+    assert_eq!(full("foo.incremental-update.o"), "foo.incremental-update.o");
+}
+
+#[test]
+fn gold_update_failures() {
+    assert!(gold_update_failed(
+        "ld.gold: fatal error: out of patch space for section .text; relink with --incremental-full\n"
+    ));
+    assert!(gold_update_failed(
+        "ld.gold: warning: the link might take longer: cannot perform incremental link: \
+         no incremental data from previous build\n"
+    ));
+    // Errors that a full link runs into as well.
+    assert!(!gold_update_failed("ld.gold: error: undefined reference to 'incremental_counter'\n"));
+    assert!(!gold_update_failed("ld.gold: error: cannot open foo.incremental.o\n"));
+}
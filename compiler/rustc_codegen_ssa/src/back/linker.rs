@@ -342,6 +342,11 @@ fn add_no_exec(&mut self) {}
     fn add_as_needed(&mut self) {}
     fn reset_per_library_state(&mut self) {}
     fn enable_profiling(&mut self) {}
+    /// Asks the linker to write a map of the linked artifact to `path`.
+    fn map_file(&mut self, _path: &Path) {}
+    /// Asks the linker to link incrementally, updating its previous output if `update` is set, and
+    /// to keep its state at `state` if it keeps it in a separate file.
+    fn incremental_link(&mut self, _state: &Path, _update: bool) {}
 }
 
 impl dyn Linker + '_ {
@@ -933,6 +938,21 @@ fn add_as_needed(&mut self) {
             self.link_args(&["-z", "ignore"]);
         }
     }
+
+    fn map_file(&mut self, path: &Path) {
+        if self.sess.target.is_like_darwin {
+            self.link_args(&[OsStr::new("-map"), path.as_os_str()]);
+        } else if self.is_gnu {
+            let mut arg = OsString::from("-Map=");
+            arg.push(path);
+            self.link_arg(arg);
+        }
+    }
+
+    fn incremental_link(&mut self, _state: &Path, update: bool) {
+        // Only gold links incrementally, and keeps the state in the output.
+        self.link_arg(if update { "--incremental-update" } else { "--incremental-full" });
+    }
 }
 
 struct MsvcLinker<'a> {
@@ -1066,6 +1086,20 @@ fn control_flow_guard(&mut self) {
         self.link_arg("/guard:cf");
     }
 
+    fn map_file(&mut self, path: &Path) {
+        let mut arg = OsString::from("/MAP:");
+        arg.push(path);
+        self.link_arg(arg);
+    }
+
+    fn incremental_link(&mut self, state: &Path, _update: bool) {
+        // `link.exe` updates the output if the state is there, and links it in full otherwise.
+        self.link_arg("/INCREMENTAL");
+        let mut arg = OsString::from("/ILK:");
+        arg.push(state);
+        self.link_arg(arg);
+    }
+
     fn ehcont_guard(&mut self) {
         if self.sess.target.pointer_width == 64 {
             self.link_arg("/guard:ehcont");
@@ -1424,6 +1458,12 @@ fn control_flow_guard(&mut self) {}
 
     fn ehcont_guard(&mut self) {}
 
+    fn map_file(&mut self, path: &Path) {
+        let mut arg = OsString::from("-Map=");
+        arg.push(path);
+        self.link_arg(arg);
+    }
+
     fn no_crt_objects(&mut self) {}
 
     fn no_default_libraries(&mut self) {}
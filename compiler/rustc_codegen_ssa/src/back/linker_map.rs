@@ -0,0 +1,539 @@
+//! The linker map of `-Zlinker-map`.
+//!
+//! Every linker has its own flag for writing a map file and its own format for it. The linker is
+//! asked to write its map next to the linked artifact through [`Linker::map_file`], which is then
+//! parsed into a common form and written out as JSON. The GNU ld, ELF and wasm lld, MSVC `link.exe`
+//! (and `lld-link`) and Apple `ld64` formats are understood.
+//!
+//! [`Linker::map_file`]: super::linker::Linker::map_file
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustc_session::Session;
+
+use super::size_report;
+use crate::diagnostics;
+
+#[cfg(test)]
+mod tests;
+
+/// The path the linker writes its map for the artifact at `out_filename` to.
+pub(super) fn map_path(out_filename: &Path) -> PathBuf {
+    let mut path = OsString::from(out_filename);
+    path.push(".map");
+    PathBuf::from(path)
+}
+
+/// The path the normalized map for the artifact at `out_filename` is written to.
+fn json_path(out_filename: &Path) -> PathBuf {
+    let mut path = OsString::from(out_filename);
+    path.push(".map.json");
+    PathBuf::from(path)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MapFormat {
+    GnuLd,
+    Lld,
+    Msvc,
+    Ld64,
+}
+
+impl MapFormat {
+    fn name(self) -> &'static str {
+        match self {
+            MapFormat::GnuLd => "gnu-ld",
+            MapFormat::Lld => "lld",
+            MapFormat::Msvc => "msvc",
+            MapFormat::Ld64 => "ld64",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct LinkerMap {
+    format: MapFormat,
+    sections: Vec<OutputSection>,
+}
+
+/// A section of the linked artifact.
+#[derive(Debug, PartialEq, Eq)]
+struct OutputSection {
+    name: String,
+    address: u64,
+    size: u64,
+    inputs: Vec<InputSection>,
+}
+
+/// The part of an output section that comes from a single section of an input object.
+#[derive(Debug, PartialEq, Eq)]
+struct InputSection {
+    /// The name of the section in the input object, if the map records it.
+    name: Option<String>,
+    /// The object file, or the `archive(member)` it was taken from.
+    object: String,
+    address: u64,
+    size: u64,
+    symbols: Vec<MapSymbol>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct MapSymbol {
+    name: String,
+    address: u64,
+    /// The size of the symbol, or `None` if it should be computed from the next symbol.
+    size: Option<u64>,
+}
+
+fn parse_map(map: &str) -> Option<LinkerMap> {
+    let first = map.lines().find(|line| !line.trim().is_empty())?;
+    let (format, mut sections) = if map.contains("\nLinker script and memory map") {
+        (MapFormat::GnuLd, parse_gnu_ld(map))
+    } else if first.split_whitespace().rev().take(3).eq(["Symbol", "In", "Out"]) {
+        (MapFormat::Lld, parse_lld(map)?)
+    } else if map.contains("Publics by Value") {
+        (MapFormat::Msvc, parse_msvc(map))
+    } else if first.starts_with("# Path:") {
+        (MapFormat::Ld64, parse_ld64(map))
+    } else {
+        return None;
+    };
+    for section in &mut sections {
+        for input in &mut section.inputs {
+            compute_symbol_sizes(input);
+        }
+    }
+    Some(LinkerMap { format, sections })
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s, 16).ok()
+}
+
+/// Parses a hexadecimal number with a `0x` prefix.
+fn parse_0x(s: &str) -> Option<u64> {
+    parse_hex(s.strip_prefix("0x")?)
+}
+
+/// Splits off the first whitespace-separated token of `s`.
+fn split_token(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    if s.is_empty() {
+        return None;
+    }
+    Some(s.split_once(char::is_whitespace).unwrap_or((s, "")))
+}
+
+/// Symbols that are assignments in the linker script rather than definitions in an object.
+fn is_script_assignment(name: &str) -> bool {
+    name.contains(" = ") || name.starts_with("PROVIDE") || name == "."
+}
+
+/// Parses the map written by GNU ld for `-Map`.
+fn parse_gnu_ld(map: &str) -> Vec<OutputSection> {
+    let (_, memory_map) = map.split_once("\nLinker script and memory map").unwrap();
+    let mut sections: Vec<OutputSection> = Vec::new();
+    let mut lines = memory_map.lines().peekable();
+    while let Some(line) = lines.next() {
+        let mut line = line.to_owned();
+        // Long section names are followed by their address and size on the next line.
+        if line.split_whitespace().count() == 1
+            && let Some(next) = lines.peek()
+            && let [address, size, ..] = next.split_whitespace().collect::<Vec<_>>()[..]
+            && parse_0x(address).is_some()
+            && parse_0x(size).is_some()
+        {
+            line.push(' ');
+            line.push_str(lines.next().unwrap().trim_start());
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if !line.starts_with(' ') {
+            // `.text  0x401000  0x1234`
+            if let [name, address, size, ..] = tokens[..]
+                && let (Some(address), Some(size)) = (parse_0x(address), parse_0x(size))
+            {
+                sections.push(OutputSection {
+                    name: name.to_owned(),
+                    address,
+                    size,
+                    inputs: vec![],
+                });
+            }
+        } else if !line.starts_with("  ") {
+            // ` .text.main  0x401010  0x20 main.o`, but not ` *(.text .text.*)` or ` *fill*`
+            if let [name, address, size, ref object @ ..] = tokens[..]
+                && !name.starts_with('*')
+                && !object.is_empty()
+                && let (Some(address), Some(size)) = (parse_0x(address), parse_0x(size))
+                && let Some(section) = sections.last_mut()
+            {
+                section.inputs.push(InputSection {
+                    name: Some(name.to_owned()),
+                    object: object.join(" "),
+                    address,
+                    size,
+                    symbols: vec![],
+                });
+            }
+        } else if let Some((address, name)) = split_token(&line)
+            && let Some(address) = parse_0x(address)
+            && let Some(name) = Some(name.trim()).filter(|name| !name.is_empty())
+            && !is_script_assignment(name)
+            && let Some(input) = sections.last_mut().and_then(|s| s.inputs.last_mut())
+        {
+            // `                0x401010                main`
+            input.symbols.push(MapSymbol { name: name.to_owned(), address, size: None });
+        }
+    }
+    sections
+}
+
+/// Parses the map written by ELF and wasm lld for `-Map`.
+///
+/// The columns are the same for output sections, input sections and symbols, only the column the
+/// name is printed in differs.
+fn parse_lld(map: &str) -> Option<Vec<OutputSection>> {
+    let mut lines = map.lines().skip_while(|line| line.trim().is_empty());
+    let header = lines.next()?;
+    let columns: Vec<&str> = header.split_whitespace().collect();
+    let numbers = columns.iter().position(|&c| c == "Out")?;
+    let size_column = columns.iter().position(|&c| c == "Size")?;
+    let in_offset = header.find(" In ")? + 1;
+    let symbol_offset = header.find(" Symbol")? + 1;
+
+    let mut sections: Vec<OutputSection> = Vec::new();
+    'lines: for line in lines {
+        let mut rest = line;
+        let mut values = Vec::with_capacity(numbers);
+        for _ in 0..numbers {
+            let Some((token, tail)) = split_token(rest) else { continue 'lines };
+            let Some(value) = parse_hex(token) else { continue 'lines };
+            values.push(value);
+            rest = tail;
+        }
+        let name = rest.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let (address, size) = (values[0], values[size_column]);
+        let offset = line.len() - rest.trim_start().len();
+        if offset < in_offset {
+            sections.push(OutputSection { name: name.to_owned(), address, size, inputs: vec![] });
+        } else if offset < symbol_offset {
+            let Some(section) = sections.last_mut() else { continue };
+            // `object:(section)`, where the object may itself be `archive(member)`.
+            let (object, input_name) = match name.rsplit_once(":(") {
+                Some((object, input_name)) => {
+                    (object, Some(input_name.strip_suffix(')').unwrap_or(input_name).to_owned()))
+                }
+                None => (name, None),
+            };
+            section.inputs.push(InputSection {
+                name: input_name,
+                object: object.to_owned(),
+                address,
+                size,
+                symbols: vec![],
+            });
+        } else if !is_script_assignment(name)
+            && let Some(input) = sections.last_mut().and_then(|s| s.inputs.last_mut())
+        {
+            input.symbols.push(MapSymbol { name: name.to_owned(), address, size: None });
+        }
+    }
+    Some(sections)
+}
+
+/// Parses the map written by `link.exe` and `lld-link` for `/MAP`.
+///
+/// These maps only list the section groups (like `.text$mn`) and the public and static symbols with
+/// the object they come from. Each output section gets one input per run of symbols from the same
+/// object and section group, which extends up to the next input.
+fn parse_msvc(map: &str) -> Vec<OutputSection> {
+    struct Group<'a> {
+        section: u32,
+        offset: u64,
+        name: &'a str,
+    }
+    struct Public<'a> {
+        section: u32,
+        offset: u64,
+        address: u64,
+        name: &'a str,
+        object: &'a str,
+    }
+
+    let mut sections: Vec<(u32, OutputSection)> = Vec::new();
+    let mut groups: Vec<Group<'_>> = Vec::new();
+    let mut publics: Vec<Public<'_>> = Vec::new();
+    for line in map.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((section, offset)) = tokens.first().and_then(|t| t.split_once(':')) else {
+            continue;
+        };
+        let (Ok(section), Some(offset)) = (section.parse::<u32>(), parse_hex(offset)) else {
+            continue;
+        };
+        if section == 0 {
+            // Absolute symbols.
+            continue;
+        }
+        match tokens[1..] {
+            // `0001:00000000 00001234H .text$mn CODE`
+            [size, name, _class] if let Some(size) = size.strip_suffix('H').and_then(parse_hex) => {
+                let end = offset + size;
+                match sections.last_mut() {
+                    Some((number, output)) if *number == section => {
+                        output.size = output.size.max(end)
+                    }
+                    _ => {
+                        let name = name.split('$').next().unwrap().to_owned();
+                        let output = OutputSection { name, address: 0, size: end, inputs: vec![] };
+                        sections.push((section, output));
+                    }
+                }
+                groups.push(Group { section, offset, name });
+            }
+            // `0001:00000010 main 0000000140001010 f foo.obj`
+            [name, address, .., object] if let Some(address) = parse_hex(address) => {
+                publics.push(Public { section, offset, address, name, object });
+            }
+            _ => {}
+        }
+    }
+    // Static symbols are listed after the public ones.
+    publics.sort_by_key(|p| (p.section, p.offset));
+    publics.dedup_by(|a, b| (a.section, a.offset, a.name) == (b.section, b.offset, b.name));
+
+    for (number, section) in &mut sections {
+        let mut groups = groups.iter().filter(|g| g.section == *number).peekable();
+        let mut group = None;
+        for public in publics.iter().filter(|p| p.section == *number) {
+            section.address = public.address - public.offset;
+            while let Some(next) = groups.next_if(|g| g.offset <= public.offset) {
+                group = Some(next.name.to_owned());
+            }
+            if !section.inputs.last().is_some_and(|i| i.object == public.object && i.name == group)
+            {
+                section.inputs.push(InputSection {
+                    name: group.clone(),
+                    object: public.object.to_owned(),
+                    address: public.address,
+                    size: 0,
+                    symbols: vec![],
+                });
+            }
+            let symbol =
+                MapSymbol { name: public.name.to_owned(), address: public.address, size: None };
+            section.inputs.last_mut().unwrap().symbols.push(symbol);
+        }
+        let end = section.address + section.size;
+        let starts: Vec<u64> =
+            section.inputs.iter().skip(1).map(|i| i.address).chain([end]).collect();
+        for (input, next) in section.inputs.iter_mut().zip(starts) {
+            input.size = next.saturating_sub(input.address);
+        }
+    }
+    sections.into_iter().map(|(_, section)| section).collect()
+}
+
+/// Parses `[  1] rest` into the object file index and the rest.
+fn parse_ld64_index(s: &str) -> Option<(u32, &str)> {
+    let (index, rest) = s.trim_start().strip_prefix('[')?.split_once(']')?;
+    Some((index.trim().parse().ok()?, rest.trim()))
+}
+
+/// Parses the map written by Apple `ld64` and `ld64.lld` for `-map`.
+///
+/// These maps list each symbol with its size and object, but not the input sections. Each output
+/// section gets one input per run of symbols from the same object.
+fn parse_ld64(map: &str) -> Vec<OutputSection> {
+    enum Part {
+        Other,
+        Objects,
+        Sections,
+        Symbols,
+    }
+    let mut part = Part::Other;
+    let mut objects: Vec<(u32, &str)> = Vec::new();
+    let mut sections: Vec<OutputSection> = Vec::new();
+    for line in map.lines() {
+        if let Some(comment) = line.strip_prefix('#') {
+            part = match comment.trim() {
+                "Object files:" => Part::Objects,
+                "Sections:" => Part::Sections,
+                "Symbols:" => Part::Symbols,
+                "Dead Stripped Symbols:" => Part::Other,
+                _ => continue,
+            };
+            continue;
+        }
+        match part {
+            Part::Other => {}
+            // `[  1] /tmp/foo.o`
+            Part::Objects => objects.extend(parse_ld64_index(line)),
+            // `0x100003F50	0x00000020	__TEXT	__text`
+            Part::Sections => {
+                if let [address, size, segment, name] =
+                    line.split_whitespace().collect::<Vec<_>>()[..]
+                    && let (Some(address), Some(size)) = (parse_0x(address), parse_0x(size))
+                {
+                    let name = format!("{segment},{name}");
+                    sections.push(OutputSection { name, address, size, inputs: vec![] });
+                }
+            }
+            // `0x100003F50	0x00000020	[  1] _main`
+            Part::Symbols => {
+                let Some((address, rest)) = split_token(line) else { continue };
+                let Some((size, rest)) = split_token(rest) else { continue };
+                let (Some(address), Some(size)) = (parse_0x(address), parse_0x(size)) else {
+                    continue;
+                };
+                let Some((index, name)) = parse_ld64_index(rest) else { continue };
+                let Some(&(_, object)) = objects.iter().find(|&&(i, _)| i == index) else {
+                    continue;
+                };
+                let Some(section) = sections
+                    .iter_mut()
+                    .find(|s| (s.address..s.address + s.size).contains(&address))
+                else {
+                    continue;
+                };
+                if section.inputs.last().is_none_or(|i| i.object != object) {
+                    section.inputs.push(InputSection {
+                        name: None,
+                        object: object.to_owned(),
+                        address,
+                        size: 0,
+                        symbols: vec![],
+                    });
+                }
+                let input = section.inputs.last_mut().unwrap();
+                input.size = address + size - input.address;
+                input.symbols.push(MapSymbol { name: name.to_owned(), address, size: Some(size) });
+            }
+        }
+    }
+    sections
+}
+
+/// Gives the symbols without a size the size up to the next symbol or the end of the input.
+fn compute_symbol_sizes(input: &mut InputSection) {
+    input.symbols.sort_by_key(|symbol| symbol.address);
+    let end = input.address + input.size;
+    let starts: Vec<u64> = input.symbols.iter().skip(1).map(|s| s.address).chain([end]).collect();
+    for (symbol, next) in input.symbols.iter_mut().zip(starts) {
+        if symbol.size.is_none() {
+            symbol.size = Some(next.max(symbol.address) - symbol.address);
+        }
+    }
+}
+
+/// Returns the crate that the Rust object file or rlib `object` was produced for.
+fn object_crate<'a>(object: &'a str) -> Option<&'a str> {
+    // `libfoo-0123abcd.rlib(foo-0123abcd.foo.a1b2c3-cgu.0.rcgu.o)`, `libfoo.rlib:member.o` or
+    // `foo.foo.a1b2c3-cgu.0.rcgu.o`.
+    let (archive, member) = match object.strip_suffix(')').and_then(|o| o.split_once('(')) {
+        Some((archive, member)) => (Some(archive), member),
+        None => match object.rsplit_once(':') {
+            Some((archive, member)) if archive.ends_with(".rlib") => (Some(archive), member),
+            _ => (None, object),
+        },
+    };
+    let file_name = |path: &'a str| path.rsplit(['/', '\\']).next().unwrap();
+    let strip_hash = |name: &'a str| name.split_once('-').map_or(name, |(name, _)| name);
+    if let Some(archive) = archive {
+        let archive = file_name(archive);
+        return archive.strip_prefix("lib")?.strip_suffix(".rlib").map(strip_hash);
+    }
+    let member = file_name(member);
+    if member.ends_with(".rcgu.o") || member.ends_with(".rcgu.obj") {
+        return member.split('.').next().map(strip_hash);
+    }
+    None
+}
+
+fn symbol_json(symbol: &MapSymbol) -> serde_json::Value {
+    // Mach-O and 32-bit Windows prefix symbol names with an underscore.
+    let demangled = rustc_demangle::try_demangle(&symbol.name)
+        .or_else(|_| rustc_demangle::try_demangle(symbol.name.strip_prefix('_').unwrap_or("")))
+        .ok()
+        .map(|demangled| format!("{demangled:#}"));
+    serde_json::json!({
+        "name": symbol.name,
+        "demangled": demangled,
+        "crate": size_report::symbol_crate(&symbol.name),
+        "address": symbol.address,
+        "size": symbol.size,
+    })
+}
+
+fn input_json(input: &InputSection) -> serde_json::Value {
+    let symbols: Vec<_> = input.symbols.iter().map(symbol_json).collect();
+    // Objects that are not produced by rustc can still contain Rust symbols, e.g. after LTO.
+    let krate = object_crate(&input.object)
+        .map(str::to_owned)
+        .or_else(|| symbols.iter().find_map(|s| s["crate"].as_str().map(str::to_owned)));
+    serde_json::json!({
+        "section": input.name,
+        "object": input.object,
+        "crate": krate,
+        "address": input.address,
+        "size": input.size,
+        "symbols": symbols,
+    })
+}
+
+fn to_json(map: &LinkerMap, out_filename: &Path) -> serde_json::Value {
+    let sections: Vec<_> = map
+        .sections
+        .iter()
+        .map(|section| {
+            let inputs: Vec<_> = section.inputs.iter().map(input_json).collect();
+            serde_json::json!({
+                "name": section.name,
+                "address": section.address,
+                "size": section.size,
+                "inputs": inputs,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "output": out_filename.display().to_string(),
+        "format": map.format.name(),
+        "sections": sections,
+    })
+}
+
+/// Removes the map of a previous link of `out_filename`, so that a linker that ignores the request
+/// for a map is noticed.
+pub(super) fn remove_stale(out_filename: &Path) {
+    let _ = fs::remove_file(map_path(out_filename));
+}
+
+/// Parses the map the linker wrote for the artifact at `out_filename` and writes it out as JSON.
+pub(super) fn report(sess: &Session, out_filename: &Path) {
+    let _timer = sess.timer("linker_map");
+    let path = map_path(out_filename);
+    let map = match fs::read(&path) {
+        Ok(map) => map,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            sess.dcx().emit_warn(diagnostics::LinkerMapMissing { path: &path });
+            return;
+        }
+        Err(error) => {
+            sess.dcx().emit_err(diagnostics::LinkerMapRead { path: &path, error });
+            return;
+        }
+    };
+    let Some(map) = parse_map(&String::from_utf8_lossy(&map)) else {
+        sess.dcx().emit_warn(diagnostics::LinkerMapParse { path: &path });
+        return;
+    };
+    let json_path = json_path(out_filename);
+    if let Err(error) = fs::write(&json_path, to_json(&map, out_filename).to_string()) {
+        sess.dcx().emit_err(diagnostics::LinkerMapWrite { path: &json_path, error });
+    }
+}
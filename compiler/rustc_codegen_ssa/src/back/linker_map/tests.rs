@@ -0,0 +1,271 @@
+use super::{
+    InputSection, LinkerMap, MapFormat, MapSymbol, OutputSection, object_crate, parse_map,
+};
+
+const MAIN_OBJECT: &str = "/tmp/main.main.a1b2c3-cgu.0.rcgu.o";
+const FOO_OBJECT: &str = "/tmp/libfoo-0123abcd.rlib(foo-0123abcd.foo.a1b2c3-cgu.0.rcgu.o)";
+const FOO_BAR: &str = "_ZN3foo3bar17h0123456789abcdefE";
+
+fn symbol(name: &str, address: u64, size: u64) -> MapSymbol {
+    MapSymbol { name: name.to_owned(), address, size: Some(size) }
+}
+
+fn input(
+    name: Option<&str>,
+    object: &str,
+    address: u64,
+    size: u64,
+    symbols: Vec<MapSymbol>,
+) -> InputSection {
+    InputSection {
+        name: name.map(str::to_owned),
+        object: object.to_owned(),
+        address,
+        size,
+        symbols,
+    }
+}
+
+fn section(name: &str, address: u64, size: u64, inputs: Vec<InputSection>) -> OutputSection {
+    OutputSection { name: name.to_owned(), address, size, inputs }
+}
+
+#[test]
+fn gnu_ld() {
+    let map = format!(
+        "\
+Memory Configuration
+
+Name             Origin             Length             Attributes
+*default*        0x0000000000000000 0xffffffffffffffff
+
+Linker script and memory map
+
+LOAD {MAIN_OBJECT}
+                [!provide]                        PROVIDE (__executable_start = SEGMENT_START (\"text-segment\", 0x400000))
+
+.text           0x0000000000401000       0x40
+ *(.text.unlikely .text.*_unlikely .text.unlikely.*)
+ .text.main     0x0000000000401000       0x20 {MAIN_OBJECT}
+                0x0000000000401000                main
+ .text.{FOO_BAR}
+                0x0000000000401020       0x20 {FOO_OBJECT}
+                0x0000000000401020                {FOO_BAR}
+                0x0000000000401040                . = ALIGN (0x8)
+"
+    );
+    assert_eq!(
+        parse_map(&map),
+        Some(LinkerMap {
+            format: MapFormat::GnuLd,
+            sections: vec![section(
+                ".text",
+                0x401000,
+                0x40,
+                vec![
+                    input(
+                        Some(".text.main"),
+                        MAIN_OBJECT,
+                        0x401000,
+                        0x20,
+                        vec![symbol("main", 0x401000, 0x20)]
+                    ),
+                    input(
+                        Some(&format!(".text.{FOO_BAR}")),
+                        FOO_OBJECT,
+                        0x401020,
+                        0x20,
+                        vec![symbol(FOO_BAR, 0x401020, 0x20)]
+                    ),
+                ]
+            )],
+        })
+    );
+}
+
+#[test]
+fn lld() {
+    let line = |address: u64, size: u64, align: u64, name: &str| {
+        format!("{address:>16x} {address:>16x} {size:>8x} {align:>5} {name}\n")
+    };
+    let mut map = format!(
+        "{:>16} {:>16} {:>8} {:>5} Out     In      Symbol\n",
+        "VMA", "LMA", "Size", "Align"
+    );
+    map += &line(0x2011f0, 0x40, 16, ".text");
+    map += &line(0x2011f0, 0x20, 16, &format!("        {MAIN_OBJECT}:(.text.main)"));
+    map += &line(0x2011f0, 0, 1, "                main");
+    map += &line(0x201210, 0x20, 16, &format!("        {FOO_OBJECT}:(.text.{FOO_BAR})"));
+    map += &line(0x201210, 0, 1, &format!("                {FOO_BAR}"));
+    map += &line(0x201230, 0, 1, "                __bss_start = .");
+
+    assert_eq!(
+        parse_map(&map),
+        Some(LinkerMap {
+            format: MapFormat::Lld,
+            sections: vec![section(
+                ".text",
+                0x2011f0,
+                0x40,
+                vec![
+                    input(
+                        Some(".text.main"),
+                        MAIN_OBJECT,
+                        0x2011f0,
+                        0x20,
+                        vec![symbol("main", 0x2011f0, 0x20)]
+                    ),
+                    input(
+                        Some(&format!(".text.{FOO_BAR}")),
+                        FOO_OBJECT,
+                        0x201210,
+                        0x20,
+                        vec![symbol(FOO_BAR, 0x201210, 0x20)]
+                    ),
+                ]
+            )],
+        })
+    );
+}
+
+#[test]
+fn msvc() {
+    let map = format!(
+        "\
+ main
+
+ Timestamp is 00000000 (Thu Jan  1 00:00:00 1970)
+
+ Preferred load address is 0000000140000000
+
+ Start         Length     Name                   Class
+ 0001:00000000 00000040H .text$mn                CODE
+ 0002:00000000 00000010H .rdata                  DATA
+
+  Address         Publics by Value              Rva+Base               Lib:Object
+
+ 0000:00000000       __ImageBase                0000000140000000     <linker-defined>
+ 0001:00000000       main                       0000000140001000 f   main.obj
+ 0001:00000020       {FOO_BAR} 0000000140001020 f   libfoo-0123abcd.rlib:foo.o
+ 0002:00000000       FOO                        0000000140002000     main.obj
+
+ entry point at        0001:00000000
+
+ Static symbols
+
+ 0001:00000010       helper                     0000000140001010 f   main.obj
+"
+    );
+    assert_eq!(
+        parse_map(&map),
+        Some(LinkerMap {
+            format: MapFormat::Msvc,
+            sections: vec![
+                section(
+                    ".text",
+                    0x140001000,
+                    0x40,
+                    vec![
+                        input(
+                            Some(".text$mn"),
+                            "main.obj",
+                            0x140001000,
+                            0x20,
+                            vec![
+                                symbol("main", 0x140001000, 0x10),
+                                symbol("helper", 0x140001010, 0x10)
+                            ]
+                        ),
+                        input(
+                            Some(".text$mn"),
+                            "libfoo-0123abcd.rlib:foo.o",
+                            0x140001020,
+                            0x20,
+                            vec![symbol(FOO_BAR, 0x140001020, 0x20)]
+                        ),
+                    ]
+                ),
+                section(
+                    ".rdata",
+                    0x140002000,
+                    0x10,
+                    vec![input(
+                        Some(".rdata"),
+                        "main.obj",
+                        0x140002000,
+                        0x10,
+                        vec![symbol("FOO", 0x140002000, 0x10)]
+                    )]
+                ),
+            ],
+        })
+    );
+}
+
+#[test]
+fn ld64() {
+    let map = format!(
+        "\
+# Path: /tmp/main
+# Arch: arm64
+# Object files:
+[  0] linker synthesized
+[  1] {MAIN_OBJECT}
+[  2] {FOO_OBJECT}
+# Sections:
+# Address\tSize    \tSegment\tSection
+0x100003F50\t0x00000040\t__TEXT\t__text
+# Symbols:
+# Address\tSize    \tFile  Name
+0x100003F50\t0x00000020\t[  1] _main
+0x100003F70\t0x00000020\t[  2] _{FOO_BAR}
+"
+    );
+    assert_eq!(
+        parse_map(&map),
+        Some(LinkerMap {
+            format: MapFormat::Ld64,
+            sections: vec![section(
+                "__TEXT,__text",
+                0x100003f50,
+                0x40,
+                vec![
+                    input(
+                        None,
+                        MAIN_OBJECT,
+                        0x100003f50,
+                        0x20,
+                        vec![symbol("_main", 0x100003f50, 0x20)]
+                    ),
+                    input(
+                        None,
+                        FOO_OBJECT,
+                        0x100003f70,
+                        0x20,
+                        vec![symbol(&format!("_{FOO_BAR}"), 0x100003f70, 0x20)]
+                    ),
+                ]
+            )],
+        })
+    );
+}
+
+#[test]
+fn unknown_format() {
+    assert_eq!(parse_map(""), None);
+    assert_eq!(parse_map("not a linker map\n"), None);
+}
+
+#[test]
+fn object_crates() {
+    assert_eq!(object_crate(FOO_OBJECT), Some("foo"));
+    assert_eq!(
+        object_crate("libfoo-0123abcd.rlib:foo-0123abcd.foo.a1b2c3-cgu.0.rcgu.o"),
+        Some("foo")
+    );
+    assert_eq!(object_crate(MAIN_OBJECT), Some("main"));
+    assert_eq!(object_crate(r"C:\tmp\main.main.a1b2c3-cgu.0.rcgu.obj"), Some("main"));
+    assert_eq!(object_crate("/usr/lib/libc.a(printf.o)"), None);
+    assert_eq!(object_crate("/usr/lib/crt1.o"), None);
+    assert_eq!(object_crate("<linker-defined>"), None);
+}
@@ -7,10 +7,13 @@
 pub(crate) mod command;
 pub mod link;
 pub(crate) mod linker;
+mod linker_map;
 pub mod lto;
 pub mod metadata;
+mod opt_remarks;
 pub mod rmeta_link;
 pub(crate) mod rpath;
+pub mod size_report;
 mod symbol_edit;
 pub mod symbol_export;
 pub mod write;
@@ -0,0 +1,335 @@
+//! The optimization remark report of `-Zremark-dir`.
+//!
+//! LLVM writes the remarks of each module to `<module>.<stage>.opt.yaml` in the remark directory.
+//! The remarks of MIR passes are collected in the session instead, and are written next to them
+//! in the same format, as `<crate>.mir.opt.yaml`. All of them are then merged into a report that
+//! groups them by function, `<crate>.remarks.json`.
+//!
+//! LLVM names functions by their symbol names, which MIR passes cannot ask for, as symbol names
+//! depend on the optimized MIR. The functions of their remarks are named once all codegen units
+//! have been codegened instead.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{Instance, TyCtxt};
+use rustc_session::Session;
+use rustc_session::code_stats::{OptRemark, RemarkArg, RemarkKind, RemarkLoc};
+use rustc_session::config::OutputFilenames;
+
+use crate::diagnostics;
+
+#[cfg(test)]
+mod tests;
+
+/// The stages LLVM writes remarks for, in the order they run.
+const LLVM_STAGES: [&str; 3] = ["opt", "lto", "codegen"];
+
+/// Takes the remarks of MIR passes out of the session, and names the functions they are about.
+pub(crate) fn mir_remarks(tcx: TyCtxt<'_>) -> Vec<OptRemark> {
+    let mir_remarks = std::mem::take(&mut *tcx.sess.code_stats.opt_remarks.borrow_mut());
+    mir_remarks
+        .into_iter()
+        .map(|mir_remark| OptRemark {
+            function: function_name(tcx, mir_remark.def_id),
+            ..mir_remark.remark
+        })
+        .collect()
+}
+
+/// The name LLVM's remarks use for the function `def_id`, which is its symbol name. Generic
+/// functions, which have no single symbol, are named by their path instead.
+fn function_name(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    if matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        && !tcx.generics_of(def_id).requires_monomorphization(tcx)
+    {
+        tcx.symbol_name(Instance::mono(tcx, def_id)).name.to_owned()
+    } else {
+        tcx.def_path_str(def_id)
+    }
+}
+
+/// Writes `mir_remarks`, and the report of all remarks of the codegen units in `modules`.
+pub(crate) fn write_report(
+    sess: &Session,
+    mir_remarks: Vec<OptRemark>,
+    modules: &[String],
+    outputs: &OutputFilenames,
+) {
+    let Some(dir) = &sess.opts.unstable_opts.remark_dir else { return };
+    let _timer = sess.timer("opt_remark_report");
+
+    let mut remarks = Vec::new();
+    if !mir_remarks.is_empty() {
+        let path = outputs.with_directory_and_extension(dir, "mir.opt.yaml");
+        if let Err(error) = fs::write(&path, to_yaml(&mir_remarks)) {
+            sess.dcx().emit_err(diagnostics::OptRemarksWrite { path: &path, error });
+        }
+        remarks.extend(mir_remarks.into_iter().map(|remark| ("mir", remark)));
+    }
+
+    for stage in LLVM_STAGES {
+        for module in modules {
+            let path = dir.join(format!("{module}.{stage}.opt.yaml"));
+            match fs::read_to_string(&path) {
+                Ok(yaml) => {
+                    remarks.extend(parse_yaml(&yaml).into_iter().map(|remark| (stage, remark)))
+                }
+                // Modules that LLVM had nothing to say about have no file.
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    sess.dcx().emit_err(diagnostics::OptRemarksRead { path: &path, error });
+                }
+            }
+        }
+    }
+
+    let path = outputs.with_directory_and_extension(dir, "remarks.json");
+    if let Err(error) = fs::write(&path, to_json(&remarks).to_string()) {
+        sess.dcx().emit_err(diagnostics::OptRemarksWrite { path: &path, error });
+    }
+}
+
+/// Quotes `value` as a YAML scalar.
+fn yaml_scalar(value: &str) -> String {
+    if !value.chars().any(char::is_control) {
+        return format!("'{}'", value.replace('\'', "''"));
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn yaml_loc(loc: &RemarkLoc) -> String {
+    format!("{{ File: {}, Line: {}, Column: {} }}", yaml_scalar(&loc.file), loc.line, loc.column)
+}
+
+/// Writes `remarks` the way LLVM writes its remark files, which tools like `opt-viewer` read.
+fn to_yaml(remarks: &[OptRemark]) -> String {
+    let mut yaml = String::new();
+    for remark in remarks {
+        writeln!(yaml, "--- !{}", remark.kind.tag()).unwrap();
+        writeln!(yaml, "{:<17}{}", "Pass:", yaml_scalar(&remark.pass)).unwrap();
+        writeln!(yaml, "{:<17}{}", "Name:", yaml_scalar(&remark.name)).unwrap();
+        if let Some(loc) = &remark.loc {
+            writeln!(yaml, "{:<17}{}", "DebugLoc:", yaml_loc(loc)).unwrap();
+        }
+        writeln!(yaml, "{:<17}{}", "Function:", yaml_scalar(&remark.function)).unwrap();
+        if !remark.args.is_empty() {
+            yaml.push_str("Args:\n");
+        }
+        for arg in &remark.args {
+            writeln!(yaml, "  - {:<17}{}", format!("{}:", arg.key), yaml_scalar(&arg.value))
+                .unwrap();
+            if let Some(loc) = &arg.loc {
+                writeln!(yaml, "    {:<17}{}", "DebugLoc:", yaml_loc(loc)).unwrap();
+            }
+        }
+        yaml.push_str("...\n");
+    }
+    yaml
+}
+
+/// Reads a YAML scalar from the start of `s`, up to a `,` in a flow mapping. Returns the value
+/// and what follows it.
+fn take_scalar(s: &str) -> (String, &str) {
+    let mut chars = s.char_indices().peekable();
+    let mut value = String::new();
+    match s.chars().next() {
+        Some('\'') => {
+            chars.next();
+            while let Some((i, c)) = chars.next() {
+                if c != '\'' {
+                    value.push(c);
+                } else if chars.next_if(|&(_, c)| c == '\'').is_some() {
+                    value.push('\'');
+                } else {
+                    return (value, skip_comma(&s[i + 1..]));
+                }
+            }
+            (value, "")
+        }
+        Some('"') => {
+            chars.next();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => return (value, skip_comma(&s[i + 1..])),
+                    '\\' => {
+                        let Some((_, escape)) = chars.next() else { break };
+                        let hex_len = match escape {
+                            'x' => 2,
+                            'u' => 4,
+                            'U' => 8,
+                            _ => 0,
+                        };
+                        let unescaped = if hex_len > 0 {
+                            let hex: String =
+                                (0..hex_len).filter_map(|_| chars.next()).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                        } else {
+                            Some(match escape {
+                                'n' => '\n',
+                                't' => '\t',
+                                'r' => '\r',
+                                '0' => '\0',
+                                c => c,
+                            })
+                        };
+                        value.extend(unescaped);
+                    }
+                    c => value.push(c),
+                }
+            }
+            (value, "")
+        }
+        _ => {
+            let (plain, rest) = s.split_once(',').map_or((s, ""), |(plain, rest)| (plain, rest));
+            (plain.trim().to_owned(), rest)
+        }
+    }
+}
+
+fn skip_comma(s: &str) -> &str {
+    let s = s.trim_start();
+    s.strip_prefix(',').unwrap_or(s)
+}
+
+fn scalar(s: &str) -> String {
+    take_scalar(s).0
+}
+
+/// Reads a `{ File: <file>, Line: <line>, Column: <column> }` flow mapping.
+fn parse_loc(s: &str) -> Option<RemarkLoc> {
+    let mut rest = s.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let (mut file, mut line, mut column) = (None, None, None);
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after_key) = rest.split_once(':')?;
+        let (value, after_value) = take_scalar(after_key.trim_start());
+        match key.trim() {
+            "File" => file = Some(value),
+            "Line" => line = value.parse().ok(),
+            "Column" => column = value.parse().ok(),
+            _ => {}
+        }
+        rest = after_value;
+    }
+    Some(RemarkLoc { file: file?, line: line?, column: column? })
+}
+
+/// Reads the remarks of a YAML remark file as written by LLVM. Remarks of unknown kinds are
+/// skipped.
+fn parse_yaml(yaml: &str) -> Vec<OptRemark> {
+    let mut remarks = Vec::new();
+    let mut remark: Option<OptRemark> = None;
+    let mut in_args = false;
+    for line in yaml.lines() {
+        if let Some(tag) = line.strip_prefix("--- !") {
+            remarks.extend(remark.take());
+            in_args = false;
+            remark = RemarkKind::from_tag(tag.trim()).map(|kind| OptRemark {
+                kind,
+                pass: String::new(),
+                name: String::new(),
+                function: String::new(),
+                loc: None,
+                args: Vec::new(),
+            });
+            continue;
+        }
+        if line == "..." {
+            remarks.extend(remark.take());
+            continue;
+        }
+        let Some(remark) = &mut remark else { continue };
+
+        let trimmed = line.trim_start();
+        if trimmed.len() == line.len() {
+            let Some((key, value)) = trimmed.split_once(':') else { continue };
+            in_args = key == "Args";
+            match key {
+                "Pass" => remark.pass = scalar(value.trim()),
+                "Name" => remark.name = scalar(value.trim()),
+                "Function" => remark.function = scalar(value.trim()),
+                "DebugLoc" => remark.loc = parse_loc(value),
+                _ => {}
+            }
+        } else if in_args {
+            // Each argument is a mapping of its key, and optionally its location.
+            let (item, new_arg) = match trimmed.strip_prefix("- ") {
+                Some(item) => (item.trim_start(), true),
+                None => (trimmed, false),
+            };
+            let Some((key, value)) = item.split_once(':') else { continue };
+            if key == "DebugLoc" && !new_arg {
+                if let Some(arg) = remark.args.last_mut() {
+                    arg.loc = parse_loc(value);
+                }
+            } else if new_arg {
+                let value = scalar(value.trim());
+                remark.args.push(RemarkArg { key: key.to_owned(), value, loc: None });
+            }
+        }
+    }
+    remarks.extend(remark);
+    remarks
+}
+
+fn loc_json(loc: &Option<RemarkLoc>) -> serde_json::Value {
+    match loc {
+        Some(loc) => {
+            serde_json::json!({ "file": loc.file, "line": loc.line, "column": loc.column })
+        }
+        None => serde_json::Value::Null,
+    }
+}
+
+fn remark_json(stage: &str, remark: &OptRemark) -> serde_json::Value {
+    let args: Vec<_> = remark
+        .args
+        .iter()
+        .map(|arg| serde_json::json!({ "key": arg.key, "value": arg.value, "location": loc_json(&arg.loc) }))
+        .collect();
+    serde_json::json!({
+        "stage": stage,
+        "kind": remark.kind.tag(),
+        "pass": remark.pass,
+        "name": remark.name,
+        "location": loc_json(&remark.loc),
+        "message": remark.message(),
+        "args": args,
+    })
+}
+
+/// Groups `remarks`, tagged with the stage they were made in, by the function they are about.
+fn to_json(remarks: &[(&str, OptRemark)]) -> serde_json::Value {
+    let mut functions: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+    for (stage, remark) in remarks {
+        functions.entry(&remark.function).or_default().push(remark_json(stage, remark));
+    }
+    let functions: Vec<_> = functions
+        .into_iter()
+        .map(|(function, remarks)| {
+            let demangled = rustc_demangle::try_demangle(function)
+                .map_or_else(|_| function.to_owned(), |demangled| format!("{demangled:#}"));
+            serde_json::json!({ "function": function, "demangled": demangled, "remarks": remarks })
+        })
+        .collect();
+    serde_json::json!({ "functions": functions })
+}
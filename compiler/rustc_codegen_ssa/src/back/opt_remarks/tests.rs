@@ -0,0 +1,133 @@
+use rustc_session::code_stats::{OptRemark, RemarkArg, RemarkKind, RemarkLoc};
+
+use super::{parse_yaml, to_json, to_yaml};
+
+const MAIN: &str = "_ZN4main4main17h0123456789abcdefE";
+
+fn loc(file: &str, line: u32, column: u32) -> Option<RemarkLoc> {
+    Some(RemarkLoc { file: file.to_owned(), line, column })
+}
+
+fn arg(key: &str, value: &str, loc: Option<RemarkLoc>) -> RemarkArg {
+    RemarkArg { key: key.to_owned(), value: value.to_owned(), loc }
+}
+
+#[test]
+fn llvm_remarks() {
+    let yaml = format!(
+        "\
+--- !Passed
+Pass:            inline
+Name:            Inlined
+DebugLoc:        {{ File: src/main.rs, Line: 9, Column: 5 }}
+Function:        {MAIN}
+Args:
+  - String:          ''''
+  - Callee:          _ZN4main3foo17h0123456789abcdefE
+    DebugLoc:        {{ File: 'src/main.rs', Line: 1, Column: 0 }}
+  - String:          ''' inlined into '''
+  - Caller:          {MAIN}
+    DebugLoc:        {{ File: src/main.rs, Line: 8, Column: 0 }}
+  - String:          ''''
+  - String:          ' with '
+  - String:          '(cost='
+  - Cost:            '-15'
+  - String:          ', threshold='
+  - Threshold:       '250'
+  - String:          ')'
+...
+--- !Analysis
+Pass:            prologepilog
+Name:            StackSize
+DebugLoc:        {{ File: src/main.rs, Line: 8, Column: 0 }}
+Function:        {MAIN}
+Args:
+  - NumStackBytes:   '8'
+  - String:          \" stack bytes in function\\n\"
+...
+--- !SomethingNew
+Pass:            future
+Name:            Unknown
+Function:        {MAIN}
+...
+"
+    );
+    let remarks = parse_yaml(&yaml);
+    assert_eq!(remarks.len(), 2);
+    assert_eq!(remarks[0].kind, RemarkKind::Passed);
+    assert_eq!(remarks[0].pass, "inline");
+    assert_eq!(remarks[0].name, "Inlined");
+    assert_eq!(remarks[0].function, MAIN);
+    assert_eq!(remarks[0].loc, loc("src/main.rs", 9, 5));
+    assert_eq!(
+        remarks[0].args[1],
+        arg("Callee", "_ZN4main3foo17h0123456789abcdefE", loc("src/main.rs", 1, 0))
+    );
+    assert_eq!(
+        remarks[0].message(),
+        format!(
+            "'_ZN4main3foo17h0123456789abcdefE' inlined into '{MAIN}' with (cost=-15, threshold=250)"
+        )
+    );
+    assert_eq!(remarks[1].kind, RemarkKind::Analysis);
+    assert_eq!(remarks[1].message(), "8 stack bytes in function\n");
+}
+
+#[test]
+fn mir_remarks_round_trip() {
+    let remarks = vec![
+        OptRemark {
+            kind: RemarkKind::Missed,
+            pass: "mir-inline".to_owned(),
+            name: "NotInlined".to_owned(),
+            function: MAIN.to_owned(),
+            loc: loc("src/it's here.rs", 3, 14),
+            args: vec![
+                arg("Callee", "foo::<T>", loc("src/lib.rs", 1, 1)),
+                arg("String", " not inlined into ", None),
+                arg("Caller", "main", loc("src/main.rs", 2, 1)),
+                arg("String", " because ", None),
+                arg("Reason", "cost above threshold", None),
+            ],
+        },
+        OptRemark {
+            kind: RemarkKind::Passed,
+            pass: "mir-gvn".to_owned(),
+            name: "Replaced".to_owned(),
+            function: "bar::<u8>".to_owned(),
+            loc: loc("src/main.rs", 5, 9),
+            args: vec![arg("Value", "Add(copy _1, const 1_u8)\n\t\"x\"", None)],
+        },
+    ];
+    assert_eq!(parse_yaml(&to_yaml(&remarks)), remarks);
+}
+
+#[test]
+fn report() {
+    let remark = |function: &str, pass: &str| OptRemark {
+        kind: RemarkKind::Passed,
+        pass: pass.to_owned(),
+        name: "Name".to_owned(),
+        function: function.to_owned(),
+        loc: None,
+        args: vec![arg("String", "message", None)],
+    };
+    let report = to_json(&[
+        ("mir", remark(MAIN, "mir-inline")),
+        ("mir", remark("foo::<T>", "mir-sroa")),
+        ("opt", remark(MAIN, "inline")),
+    ]);
+    let functions = report["functions"].as_array().unwrap();
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions[0]["function"], MAIN);
+    assert_eq!(functions[0]["demangled"], "main::main");
+    let stages: Vec<_> = functions[0]["remarks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|remark| (remark["stage"].as_str().unwrap(), remark["pass"].as_str().unwrap()))
+        .collect();
+    assert_eq!(stages, [("mir", "mir-inline"), ("opt", "inline")]);
+    assert_eq!(functions[1]["demangled"], "foo::<T>");
+    assert_eq!(functions[1]["remarks"][0]["message"], "message");
+}
@@ -0,0 +1,290 @@
+//! The post-link binary size report of `-Zprint-binary-size` and `-Zprint-binary-size-json`.
+//!
+//! The symbols of the linked binary are attributed to the crate, module, function and generic
+//! instantiation they were generated for. Symbols of the local crate are looked up in the list of
+//! mono items collected before codegen, all other Rust symbols are demangled.
+
+use std::fs;
+use std::path::Path;
+
+use object::{Object, ObjectSection, ObjectSymbol, SectionKind, SymbolKind};
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
+use rustc_hir::def::DefKind;
+use rustc_macros::{Decodable, Encodable};
+use rustc_middle::mono::MonoItem;
+use rustc_middle::ty::print::{with_no_trimmed_paths, with_resolve_crate_name};
+use rustc_middle::ty::{GenericArgKind, TyCtxt};
+use rustc_session::Session;
+
+use crate::{CrateInfo, diagnostics};
+
+const NON_RUST: &str = "[non-Rust]";
+const UNATTRIBUTED: &str = "[unattributed]";
+
+/// Where a symbol of the local crate comes from.
+#[derive(Clone, Debug, Encodable, Decodable)]
+pub struct SymbolOrigin {
+    krate: String,
+    module: String,
+    function: String,
+    /// The rendered generic arguments, or an empty string for non-generic items.
+    args: String,
+}
+
+pub fn enabled(sess: &Session) -> bool {
+    sess.opts.unstable_opts.print_binary_size
+        || sess.opts.unstable_opts.print_binary_size_json.is_some()
+}
+
+/// Maps the symbol names of all mono items of the local crate to the item they were generated
+/// for.
+pub fn collect_symbol_origins(tcx: TyCtxt<'_>) -> Vec<(String, SymbolOrigin)> {
+    let mut origins = Vec::new();
+    for cgu in tcx.collect_and_partition_mono_items(()).codegen_units {
+        for item in cgu.items().keys() {
+            let (def_id, args) = match *item {
+                MonoItem::Fn(instance) => (instance.def_id(), Some(instance.args)),
+                MonoItem::Static(def_id) => (def_id, None),
+                MonoItem::GlobalAsm(_) => continue,
+            };
+            let mut module = tcx.parent(def_id);
+            while tcx.def_kind(module) != DefKind::Mod {
+                module = tcx.parent(module);
+            }
+            let (module, path) = with_resolve_crate_name!(with_no_trimmed_paths!((
+                tcx.def_path_str(module),
+                tcx.def_path_str(def_id)
+            )));
+            let function = match path.strip_prefix(&module).and_then(|p| p.strip_prefix("::")) {
+                Some(function) => function.to_owned(),
+                None => path,
+            };
+            let args = args
+                .map(|args| {
+                    let args: Vec<String> = args
+                        .iter()
+                        .filter(|arg| !matches!(arg.kind(), GenericArgKind::Lifetime(_)))
+                        .map(|arg| with_no_trimmed_paths!(arg.to_string()))
+                        .collect();
+                    if args.is_empty() { String::new() } else { format!("<{}>", args.join(", ")) }
+                })
+                .unwrap_or_default();
+            let origin = SymbolOrigin {
+                krate: tcx.crate_name(def_id.krate).to_string(),
+                module,
+                function,
+                args,
+            };
+            origins.push((item.symbol_name(tcx).name.to_owned(), origin));
+        }
+    }
+    origins
+}
+
+/// Splits a demangled path into its top-level `::`-separated segments.
+fn split_path(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' | b'(' | b'[' => depth += 1,
+            // The `>` of a `->` in a function pointer type does not close anything.
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' | b')' | b']' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                segments.push(&path[start..i]);
+                i += 2;
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+/// Attributes a symbol that is not a mono item of the local crate by demangling it.
+fn demangled_origin(name: &str) -> Option<SymbolOrigin> {
+    let demangled = format!("{:#}", rustc_demangle::try_demangle(name).ok()?);
+    let mut segments = split_path(&demangled);
+    let mut args = String::new();
+    // `path::<T>` in v0 symbols, `path<T>` in legacy symbols.
+    if segments.len() > 1 && segments.last().is_some_and(|s| s.starts_with('<')) {
+        args = segments.pop().unwrap().to_owned();
+    } else if let Some(last) = segments.last_mut()
+        && last.ends_with('>')
+        && let Some(open) = last.find('<')
+        && open > 0
+    {
+        args = last[open..].to_owned();
+        *last = &last[..open];
+    }
+    let function = segments.pop()?.to_owned();
+    // For paths like `<T as Trait>::method` the crate is the one of the self type.
+    let first = segments.first().copied().unwrap_or(&function);
+    let krate = first.trim_start_matches(['<', '&', '*']).trim_start_matches("dyn ");
+    let krate = split_path(krate)[0].to_owned();
+    let module = if segments.is_empty() { krate.clone() } else { segments.join("::") };
+    Some(SymbolOrigin { krate, module, function, args })
+}
+
+/// Returns the crate that the Rust symbol `name` was generated for.
+pub(super) fn symbol_crate(name: &str) -> Option<String> {
+    let origin = demangled_origin(name).or_else(|| demangled_origin(name.strip_prefix('_')?))?;
+    Some(origin.krate)
+}
+
+#[derive(Default)]
+struct SizeNode {
+    size: u64,
+    children: FxIndexMap<String, SizeNode>,
+}
+
+impl SizeNode {
+    fn add(&mut self, path: &[&str], size: u64) {
+        self.size += size;
+        if let Some((first, rest)) = path.split_first() {
+            self.children.entry((*first).to_owned()).or_default().add(rest, size);
+        }
+    }
+
+    fn sorted_children(&self) -> Vec<(&String, &SizeNode)> {
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by(|(a_name, a), (b_name, b)| b.size.cmp(&a.size).then(a_name.cmp(b_name)));
+        children
+    }
+
+    fn print(&self, total: u64, depth: usize, out: &mut String) {
+        for (name, child) in self.sorted_children() {
+            let percent = if total == 0 { 0.0 } else { child.size as f64 * 100.0 / total as f64 };
+            out.push_str(&format!(
+                "{:indent$}{:>10} bytes {percent:>5.1}%  {name}\n",
+                "",
+                child.size,
+                indent = depth * 2,
+            ));
+            child.print(total, depth + 1, out);
+        }
+    }
+
+    fn to_json(&self, name: &str) -> serde_json::Value {
+        let children: Vec<_> =
+            self.sorted_children().into_iter().map(|(name, child)| child.to_json(name)).collect();
+        serde_json::json!({ "name": name, "size": self.size, "children": children })
+    }
+}
+
+/// Builds the size tree of the linked binary `data`, or returns `None` if it cannot be parsed.
+fn size_tree(data: &[u8], origins: &FxHashMap<&str, &SymbolOrigin>) -> Option<SizeNode> {
+    let file = object::File::parse(data).ok()?;
+    let mut tree = SizeNode::default();
+    for section in file.sections() {
+        if !matches!(
+            section.kind(),
+            SectionKind::Text
+                | SectionKind::Data
+                | SectionKind::ReadOnlyData
+                | SectionKind::ReadOnlyDataWithRel
+                | SectionKind::ReadOnlyString
+                | SectionKind::UninitializedData
+                | SectionKind::Tls
+                | SectionKind::UninitializedTls
+        ) {
+            continue;
+        }
+        let start = section.address();
+        let end = start + section.size();
+        let mut symbols: Vec<_> = file
+            .symbols()
+            .filter(|sym| {
+                sym.section_index() == Some(section.index())
+                    && sym.is_definition()
+                    && !matches!(sym.kind(), SymbolKind::Section | SymbolKind::File)
+                    && (start..end).contains(&sym.address())
+            })
+            .filter_map(|sym| Some((sym.address(), sym.size(), sym.name().ok()?)))
+            .filter(|(_, _, name)| !name.is_empty())
+            .collect();
+        symbols.sort_by_key(|&(address, size, _)| (address, std::cmp::Reverse(size)));
+        // Aliases share an address, only count the first one we know about.
+        symbols.dedup_by(|(b_address, _, b_name), (a_address, _, a_name)| {
+            if a_address != b_address {
+                return false;
+            }
+            if lookup(origins, a_name).is_none() && lookup(origins, b_name).is_some() {
+                *a_name = *b_name;
+            }
+            true
+        });
+
+        let mut attributed = 0;
+        for (idx, &(address, size, name)) in symbols.iter().enumerate() {
+            let next = symbols.get(idx + 1).map_or(end, |&(next, _, _)| next);
+            let size = if size == 0 { next - address } else { size.min(end - address) };
+            attributed += size;
+            let origin = lookup(origins, name).cloned().or_else(|| {
+                demangled_origin(name).or_else(|| demangled_origin(name.strip_prefix('_')?))
+            });
+            match &origin {
+                Some(origin) if origin.args.is_empty() => {
+                    tree.add(&[&origin.krate, &origin.module, &origin.function], size)
+                }
+                Some(origin) => {
+                    tree.add(&[&origin.krate, &origin.module, &origin.function, &origin.args], size)
+                }
+                None => tree.add(&[NON_RUST, name], size),
+            }
+        }
+        let unattributed = section.size().saturating_sub(attributed);
+        tree.add(&[UNATTRIBUTED, section.name().unwrap_or("")], unattributed);
+    }
+    Some(tree)
+}
+
+fn lookup<'a>(origins: &FxHashMap<&str, &'a SymbolOrigin>, name: &str) -> Option<&'a SymbolOrigin> {
+    // Mach-O and 32-bit Windows prefix symbol names with an underscore.
+    origins.get(name).or_else(|| origins.get(name.strip_prefix('_')?)).copied()
+}
+
+/// Prints and writes the size report for the binary at `out_filename`.
+pub(super) fn report(sess: &Session, crate_info: &CrateInfo, out_filename: &Path) {
+    let _timer = sess.timer("print_binary_size");
+    let data = match fs::read(out_filename) {
+        Ok(data) => data,
+        Err(error) => {
+            sess.dcx().emit_err(diagnostics::BinarySizeRead { path: out_filename, error });
+            return;
+        }
+    };
+    let origins: FxHashMap<&str, &SymbolOrigin> =
+        crate_info.binary_size_symbols.iter().map(|(name, origin)| (&**name, origin)).collect();
+    let Some(tree) = size_tree(&data, &origins) else {
+        sess.dcx().emit_err(diagnostics::BinarySizeParse { path: out_filename });
+        return;
+    };
+
+    if sess.opts.unstable_opts.print_binary_size {
+        let mut out = format!(
+            "binary size of `{}`: {} bytes in {} bytes of code and data\n",
+            out_filename.display(),
+            data.len(),
+            tree.size,
+        );
+        tree.print(tree.size, 1, &mut out);
+        print!("{out}");
+    }
+
+    if let Some(path) = &sess.opts.unstable_opts.print_binary_size_json {
+        let mut json = tree.to_json(&out_filename.display().to_string());
+        json["file_size"] = data.len().into();
+        let path = Path::new(path);
+        if let Err(error) = fs::write(path, json.to_string()) {
+            sess.dcx().emit_err(diagnostics::BinarySizeWrite { path, error });
+        }
+    }
+}
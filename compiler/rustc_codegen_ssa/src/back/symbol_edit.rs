@@ -1,17 +1,26 @@
 //! Binary-level symbol editing for staticlib post-processing.
 //!
-//! - **Hide**: sets STV_HIDDEN (ELF) or N_PEXT (Mach-O) on non-exported symbols.
+//! - **Hide**: sets STV_HIDDEN (ELF), N_PEXT (Mach-O) or SYM_V_HIDDEN (XCOFF) on non-exported
+//!   symbols by patching that byte in place. COFF has no symbol visibility, so there the
+//!   `/EXPORT` directives of non-exported symbols are blanked out of `.drectve` instead.
 //! - **Rename**: appends a vendor-specific suffix to non-exported symbol names by
-//!   rebuilding the string table.
+//!   rebuilding the string table. On COFF, the `/EXPORT`, `/INCLUDE` and `/ALTERNATENAME`
+//!   directives that name a renamed symbol are blanked out as well. COFF and XCOFF keep the
+//!   string table at the end of the file, so renaming fails if anything follows it.
 
 use std::borrow::Cow;
-use std::mem;
+use std::ops::Range;
+use std::{io, mem};
 
+use object::read::coff::CoffHeader;
 use object::read::elf::{SectionHeader as _, Sym as _};
 use object::read::macho::Nlist;
-use object::{Endianness, elf, macho};
+use object::{BigEndian, Endianness, LittleEndian, ReadRef, elf, macho, pe, xcoff};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 
+#[cfg(test)]
+mod tests;
+
 struct Patch {
     offset: usize,
     value: u8,
@@ -27,7 +36,7 @@ pub(super) fn apply_edits<'a>(
     exported: &FxHashSet<String>,
     hide: bool,
     rename: Option<&(FxHashSet<String>, &str)>,
-) -> Cow<'a, [u8]> {
+) -> io::Result<Cow<'a, [u8]>> {
     let result = match object::File::parse(data).ok() {
         Some(object::File::Elf64(_)) => elf_edit_impl::<elf::FileHeader64<Endianness>>(
             data,
@@ -57,12 +66,33 @@ pub(super) fn apply_edits<'a>(
             rename,
             mem::offset_of!(macho::Nlist32<Endianness>, n_type),
         ),
+        Some(object::File::Coff(_)) => {
+            coff_edit_impl::<pe::ImageFileHeader, pe::ImageSymbol>(data, exported, hide, rename)?
+        }
+        Some(object::File::CoffBig(_)) => coff_edit_impl::<
+            pe::AnonObjectHeaderBigobj,
+            pe::ImageSymbolEx,
+        >(data, exported, hide, rename)?,
+        Some(object::File::Xcoff32(_)) => xcoff_edit_impl::<xcoff::FileHeader32, xcoff::Symbol32>(
+            data,
+            exported,
+            hide,
+            rename,
+            mem::offset_of!(xcoff::Symbol32, n_type),
+        )?,
+        Some(object::File::Xcoff64(_)) => xcoff_edit_impl::<xcoff::FileHeader64, xcoff::Symbol64>(
+            data,
+            exported,
+            hide,
+            rename,
+            mem::offset_of!(xcoff::Symbol64, n_type),
+        )?,
         _ => None,
     };
-    match result {
+    Ok(match result {
         Some(v) => Cow::Owned(v),
         None => Cow::Borrowed(data),
-    }
+    })
 }
 
 pub(super) fn collect_internal_names(
@@ -84,6 +114,18 @@ pub(super) fn collect_internal_names(
         object::File::MachO32(_) => {
             macho_collect_impl::<macho::MachHeader32<Endianness>>(data, exported, out)
         }
+        object::File::Coff(_) => {
+            coff_collect_impl::<pe::ImageFileHeader, pe::ImageSymbol>(data, exported, out)
+        }
+        object::File::CoffBig(_) => {
+            coff_collect_impl::<pe::AnonObjectHeaderBigobj, pe::ImageSymbolEx>(data, exported, out)
+        }
+        object::File::Xcoff32(_) => {
+            xcoff_collect_impl::<xcoff::FileHeader32, xcoff::Symbol32>(data, exported, out)
+        }
+        object::File::Xcoff64(_) => {
+            xcoff_collect_impl::<xcoff::FileHeader64, xcoff::Symbol64>(data, exported, out)
+        }
         _ => {}
     }
 }
@@ -441,6 +483,463 @@ fn macho_rebuild_strtab(
     Some(result)
 }
 
+// ---------------------------------------------------------------------------
+// COFF and XCOFF: shared symbol table handling
+// ---------------------------------------------------------------------------
+
+/// A COFF or XCOFF symbol table entry. Both formats keep long names in a string table that
+/// directly follows the symbol table and starts with its own 4-byte size.
+trait CoffSymbol: object::Pod {
+    /// Offset of the 4-byte string table offset of the name within the entry.
+    const NAME_OFFSET_FIELD: usize;
+    /// Whether names of up to 8 bytes are stored inline. Entries with a long name then start
+    /// with 4 zero bytes.
+    const INLINE_NAMES: bool;
+    const ENDIAN: Endianness;
+
+    fn is_external(&self) -> bool;
+    fn is_undefined(&self) -> bool;
+    fn aux_count(&self) -> usize;
+}
+
+impl CoffSymbol for pe::ImageSymbol {
+    const NAME_OFFSET_FIELD: usize = 4;
+    const INLINE_NAMES: bool = true;
+    const ENDIAN: Endianness = Endianness::Little;
+
+    fn is_external(&self) -> bool {
+        matches!(
+            self.storage_class,
+            pe::IMAGE_SYM_CLASS_EXTERNAL | pe::IMAGE_SYM_CLASS_WEAK_EXTERNAL
+        )
+    }
+
+    fn is_undefined(&self) -> bool {
+        self.section_number.get(LittleEndian) == 0
+    }
+
+    fn aux_count(&self) -> usize {
+        self.number_of_aux_symbols.into()
+    }
+}
+
+impl CoffSymbol for pe::ImageSymbolEx {
+    const NAME_OFFSET_FIELD: usize = 4;
+    const INLINE_NAMES: bool = true;
+    const ENDIAN: Endianness = Endianness::Little;
+
+    fn is_external(&self) -> bool {
+        matches!(
+            self.storage_class,
+            pe::IMAGE_SYM_CLASS_EXTERNAL | pe::IMAGE_SYM_CLASS_WEAK_EXTERNAL
+        )
+    }
+
+    fn is_undefined(&self) -> bool {
+        self.section_number.get(LittleEndian) == 0
+    }
+
+    fn aux_count(&self) -> usize {
+        self.number_of_aux_symbols.into()
+    }
+}
+
+impl CoffSymbol for xcoff::Symbol32 {
+    const NAME_OFFSET_FIELD: usize = 4;
+    const INLINE_NAMES: bool = true;
+    const ENDIAN: Endianness = Endianness::Big;
+
+    fn is_external(&self) -> bool {
+        matches!(self.n_sclass, xcoff::C_EXT | xcoff::C_WEAKEXT)
+    }
+
+    fn is_undefined(&self) -> bool {
+        self.n_scnum.get(BigEndian) == 0
+    }
+
+    fn aux_count(&self) -> usize {
+        self.n_numaux.into()
+    }
+}
+
+impl CoffSymbol for xcoff::Symbol64 {
+    const NAME_OFFSET_FIELD: usize = mem::offset_of!(xcoff::Symbol64, n_offset);
+    const INLINE_NAMES: bool = false;
+    const ENDIAN: Endianness = Endianness::Big;
+
+    fn is_external(&self) -> bool {
+        matches!(self.n_sclass, xcoff::C_EXT | xcoff::C_WEAKEXT)
+    }
+
+    fn is_undefined(&self) -> bool {
+        self.n_scnum.get(BigEndian) == 0
+    }
+
+    fn aux_count(&self) -> usize {
+        self.n_numaux.into()
+    }
+}
+
+struct CoffSymbolTable<'a, Sym> {
+    symbols: &'a [Sym],
+    symtab_offset: usize,
+    strtab_offset: usize,
+    strtab: &'a [u8],
+}
+
+impl<'a, Sym: CoffSymbol> CoffSymbolTable<'a, Sym> {
+    fn parse(data: &'a [u8], symtab_offset: usize, count: usize) -> Option<Self> {
+        let symbols: &[Sym] = data.read_slice_at(symtab_offset as u64, count).ok()?;
+        let strtab_offset = symtab_offset + count * mem::size_of::<Sym>();
+        // Objects without long names may omit the string table entirely.
+        let strtab_size = match read_u32_at(data, strtab_offset, Sym::ENDIAN) {
+            Some(size) => size as usize,
+            None => 0,
+        };
+        let strtab = data.get(strtab_offset..strtab_offset + strtab_size)?;
+        Some(CoffSymbolTable { symbols, symtab_offset, strtab_offset, strtab })
+    }
+
+    fn name(&self, sym: &'a Sym) -> Option<&'a str> {
+        let bytes = object::pod::bytes_of(sym);
+        let name = if Sym::INLINE_NAMES && bytes[..4] != [0; 4] {
+            let inline = &bytes[..8];
+            &inline[..inline.iter().position(|&b| b == 0).unwrap_or(8)]
+        } else {
+            let offset = read_u32_at(bytes, Sym::NAME_OFFSET_FIELD, Sym::ENDIAN)? as usize;
+            let rest = self.strtab.get(offset..)?;
+            &rest[..rest.iter().position(|&b| b == 0)?]
+        };
+        str::from_utf8(name).ok()
+    }
+
+    /// Returns the file offset, entry and name of every external symbol, skipping auxiliary
+    /// entries.
+    fn external_symbols(&self) -> Vec<(usize, &'a Sym, &'a str)> {
+        let mut result = Vec::new();
+        let mut index = 0;
+        while let Some(sym) = self.symbols.get(index) {
+            if sym.is_external()
+                && let Some(name) = self.name(sym)
+            {
+                result.push((self.symtab_offset + index * mem::size_of::<Sym>(), sym, name));
+            }
+            index += 1 + sym.aux_count();
+        }
+        result
+    }
+
+    fn collect_internal_names(
+        &self,
+        exported: &FxHashSet<String>,
+        prefix: Option<char>,
+        out: &mut FxHashSet<String>,
+    ) {
+        for (_, sym, name) in self.external_symbols() {
+            let name = strip_decoration(name, prefix);
+            if !sym.is_undefined() && !exported.contains(name) {
+                out.insert(name.to_string());
+            }
+        }
+    }
+
+    fn collect_renames(
+        &self,
+        rename_set: &FxHashSet<String>,
+        prefix: Option<char>,
+        renames: &mut Vec<RenameEntry>,
+    ) {
+        for (entry_offset, _, name) in self.external_symbols() {
+            if rename_set.contains(strip_decoration(name, prefix)) {
+                renames
+                    .push(RenameEntry { name_field_offset: entry_offset, name: name.to_string() });
+            }
+        }
+    }
+
+    fn apply(
+        &self,
+        data: &[u8],
+        patches: &[Patch],
+        renames: &[RenameEntry],
+        rename: Option<&(FxHashSet<String>, &str)>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        if patches.is_empty() && renames.is_empty() {
+            return Ok(None);
+        }
+
+        let mut result = data.to_vec();
+        for p in patches {
+            result[p.offset] = p.value;
+        }
+
+        if !renames.is_empty() {
+            let suffix = rename.unwrap().1;
+            result = self.rebuild_strtab(&result, renames, suffix)?;
+        }
+
+        Ok(Some(result))
+    }
+
+    fn rebuild_strtab(
+        &self,
+        data: &[u8],
+        renames: &[RenameEntry],
+        suffix: &str,
+    ) -> io::Result<Vec<u8>> {
+        // The string table has to stay directly behind the symbol table, so it can only grow if
+        // nothing follows it.
+        let old_strtab = if self.strtab.is_empty() { &[0; 4][..] } else { self.strtab };
+        if self.strtab_offset + self.strtab.len() != data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "cannot rename symbols: {} bytes of data follow the string table",
+                    data.len().saturating_sub(self.strtab_offset + self.strtab.len()),
+                ),
+            ));
+        }
+
+        let (new_strtab, rename_map) = build_renamed_strtab(old_strtab, renames, suffix);
+
+        let mut result = Vec::with_capacity(self.strtab_offset + new_strtab.len());
+        result.extend_from_slice(&data[..self.strtab_offset]);
+        result.extend_from_slice(&new_strtab);
+        write_u32_at(&mut result, self.strtab_offset, new_strtab.len() as u32, Sym::ENDIAN);
+
+        for entry in renames {
+            if Sym::INLINE_NAMES {
+                write_u32_at(&mut result, entry.name_field_offset, 0, Sym::ENDIAN);
+            }
+            write_u32_at(
+                &mut result,
+                entry.name_field_offset + Sym::NAME_OFFSET_FIELD,
+                rename_map[&entry.name],
+                Sym::ENDIAN,
+            );
+        }
+
+        Ok(result)
+    }
+}
+
+/// Strips the prefix that the target adds to C symbol names.
+fn strip_decoration(name: &str, prefix: Option<char>) -> &str {
+    prefix.and_then(|prefix| name.strip_prefix(prefix)).unwrap_or(name)
+}
+
+fn coff_collect_impl<Coff: CoffHeader, Sym: CoffSymbol>(
+    data: &[u8],
+    exported: &FxHashSet<String>,
+    out: &mut FxHashSet<String>,
+) {
+    let mut offset = 0;
+    let Ok(header) = Coff::parse(data, &mut offset) else { return };
+    let Some(table) = CoffSymbolTable::<Sym>::parse(
+        data,
+        header.pointer_to_symbol_table() as usize,
+        header.number_of_symbols() as usize,
+    ) else {
+        return;
+    };
+    table.collect_internal_names(exported, coff_decoration(header.machine()), out);
+}
+
+fn xcoff_collect_impl<Xcoff: object::read::xcoff::FileHeader, Sym: CoffSymbol>(
+    data: &[u8],
+    exported: &FxHashSet<String>,
+    out: &mut FxHashSet<String>,
+) {
+    let mut offset = 0;
+    let Ok(header) = Xcoff::parse(data, &mut offset) else { return };
+    let symptr: u64 = header.f_symptr().into();
+    let Some(table) =
+        CoffSymbolTable::<Sym>::parse(data, symptr as usize, header.f_nsyms() as usize)
+    else {
+        return;
+    };
+    table.collect_internal_names(exported, Some('.'), out);
+}
+
+// ---------------------------------------------------------------------------
+// COFF: linker directive removal + rename
+// ---------------------------------------------------------------------------
+
+/// 32-bit x86 prefixes C symbol names with an underscore.
+fn coff_decoration(machine: u16) -> Option<char> {
+    (machine == pe::IMAGE_FILE_MACHINE_I386).then_some('_')
+}
+
+fn coff_edit_impl<Coff: CoffHeader, Sym: CoffSymbol>(
+    data: &[u8],
+    exported: &FxHashSet<String>,
+    hide: bool,
+    rename: Option<&(FxHashSet<String>, &str)>,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut offset = 0;
+    let Ok(header) = Coff::parse(data, &mut offset) else { return Ok(None) };
+    let Ok(sections) = header.sections(data, offset) else { return Ok(None) };
+    let Some(table) = CoffSymbolTable::<Sym>::parse(
+        data,
+        header.pointer_to_symbol_table() as usize,
+        header.number_of_symbols() as usize,
+    ) else {
+        return Ok(None);
+    };
+    let prefix = coff_decoration(header.machine());
+
+    let mut patches = Vec::new();
+    let mut renames = Vec::new();
+
+    // COFF has no symbol visibility. A symbol only leaves the final binary if it is exported from
+    // a DLL, which the `/EXPORT` directives in `.drectve` request. Renamed symbols cannot be
+    // exported under their original name, so their directives go away as well. `/INCLUDE` (which
+    // `#[used]` statics are kept alive with) and `/ALTERNATENAME` would make the linker look for
+    // a symbol that no longer exists. Rewriting them would need room for the longer name, so they
+    // are dropped; a renamed `#[used]` static is then only kept if something references it.
+    for section in sections.iter() {
+        if !section.name.starts_with(b".drectve") {
+            continue;
+        }
+        let start = section.pointer_to_raw_data.get(LittleEndian) as usize;
+        let size = section.size_of_raw_data.get(LittleEndian) as usize;
+        let Some(directives) = data.get(start..start + size) else { continue };
+        let renamed = |name: &str| {
+            rename.is_some_and(|(rename_set, _)| {
+                rename_set.contains(name) || rename_set.contains(strip_decoration(name, prefix))
+            })
+        };
+        let remove = |directive: &Directive<'_>| match *directive {
+            Directive::Export(name) => (hide && !exported.contains(name)) || renamed(name),
+            Directive::Include(symbol) => renamed(symbol),
+            Directive::AlternateName(symbol, default) => renamed(symbol) || renamed(default),
+        };
+        for range in symbol_directives(directives, remove) {
+            for offset in range {
+                patches.push(Patch { offset: start + offset, value: b' ' });
+            }
+        }
+    }
+    if let Some((rename_set, _)) = rename {
+        table.collect_renames(rename_set, prefix, &mut renames);
+    }
+
+    table.apply(data, &patches, &renames, rename)
+}
+
+/// A `.drectve` directive that names a symbol.
+#[derive(Debug)]
+enum Directive<'a> {
+    /// `/EXPORT:name[=internal][,@ordinal][,NONAME][,DATA]`
+    Export(&'a str),
+    /// `/INCLUDE:symbol`
+    Include(&'a str),
+    /// `/ALTERNATENAME:symbol=default`
+    AlternateName(&'a str, &'a str),
+}
+
+/// Returns the byte ranges of the directives in the contents of a `.drectve` section that name a
+/// symbol and satisfy `remove`.
+fn symbol_directives(
+    directives: &[u8],
+    mut remove: impl FnMut(&Directive<'_>) -> bool,
+) -> Vec<Range<usize>> {
+    let is_separator = |b: u8| b.is_ascii_whitespace() || b == 0;
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    while pos < directives.len() {
+        if is_separator(directives[pos]) {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        let mut quoted = false;
+        while pos < directives.len() && (quoted || !is_separator(directives[pos])) {
+            if directives[pos] == b'"' {
+                quoted = !quoted;
+            }
+            pos += 1;
+        }
+        let Ok(directive) = str::from_utf8(&directives[start..pos]) else { continue };
+        let Some((option, arg)) =
+            directive.strip_prefix(['/', '-']).and_then(|directive| directive.split_once(':'))
+        else {
+            continue;
+        };
+        let directive = if option.eq_ignore_ascii_case("export") {
+            // The name may be followed by `=internal` and further options unless it is quoted.
+            let name = if arg.starts_with('"') {
+                unquote(arg)
+            } else {
+                arg.split([',', '=']).next().unwrap_or(arg)
+            };
+            Directive::Export(name)
+        } else if option.eq_ignore_ascii_case("include") {
+            Directive::Include(unquote(arg))
+        } else if option.eq_ignore_ascii_case("alternatename") {
+            let Some((symbol, default)) = arg.split_once('=') else { continue };
+            Directive::AlternateName(unquote(symbol), unquote(default))
+        } else {
+            continue;
+        };
+        if remove(&directive) {
+            ranges.push(start..pos);
+        }
+    }
+    ranges
+}
+
+/// Strips the quotes around a directive argument, e.g. in `/EXPORT:"name",DATA`.
+fn unquote(arg: &str) -> &str {
+    match arg.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or(quoted),
+        None => arg,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XCOFF: single-pass collection + apply
+// ---------------------------------------------------------------------------
+
+fn xcoff_edit_impl<Xcoff: object::read::xcoff::FileHeader, Sym: CoffSymbol>(
+    data: &[u8],
+    exported: &FxHashSet<String>,
+    hide: bool,
+    rename: Option<&(FxHashSet<String>, &str)>,
+    n_type_offset: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut offset = 0;
+    let Ok(header) = Xcoff::parse(data, &mut offset) else { return Ok(None) };
+    let symptr: u64 = header.f_symptr().into();
+    let Some(table) =
+        CoffSymbolTable::<Sym>::parse(data, symptr as usize, header.f_nsyms() as usize)
+    else {
+        return Ok(None);
+    };
+    // Function entry points are named after their descriptor with a leading `.`.
+    let prefix = Some('.');
+
+    let mut patches = Vec::new();
+    let mut renames = Vec::new();
+
+    if hide {
+        // The visibility lives in the high nibble of the big-endian `n_type`.
+        let mask = (xcoff::SYM_V_MASK >> 8) as u8;
+        let hidden = (xcoff::SYM_V_HIDDEN >> 8) as u8;
+        for (entry_offset, sym, name) in table.external_symbols() {
+            if !sym.is_undefined() && !exported.contains(strip_decoration(name, prefix)) {
+                let offset = entry_offset + n_type_offset;
+                patches.push(Patch { offset, value: (data[offset] & !mask) | hidden });
+            }
+        }
+    }
+    if let Some((rename_set, _)) = rename {
+        table.collect_renames(rename_set, prefix, &mut renames);
+    }
+
+    table.apply(data, &patches, &renames, rename)
+}
+
 // ---------------------------------------------------------------------------
 // Shared helpers
 // ---------------------------------------------------------------------------
@@ -476,6 +975,14 @@ fn write_u32_at(buf: &mut [u8], offset: usize, value: u32, endian: Endianness) {
     buf[offset..offset + 4].copy_from_slice(&bytes);
 }
 
+fn read_u32_at(buf: &[u8], offset: usize, endian: Endianness) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?.try_into().unwrap();
+    Some(match endian {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    })
+}
+
 fn write_u64_at(buf: &mut [u8], offset: usize, value: u64, endian: Endianness) {
     let bytes = match endian {
         Endianness::Little => value.to_le_bytes(),
@@ -0,0 +1,218 @@
+use object::read::xcoff::FileHeader as _;
+use object::write::{self, SymbolSection};
+use object::{
+    Architecture, BinaryFormat, Object, ObjectSection, ObjectSymbol, SectionKind, SymbolFlags,
+    SymbolKind, SymbolScope,
+};
+
+use super::*;
+
+const SUFFIX: &str = ".rs1234";
+
+fn set(names: &[&str]) -> FxHashSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Builds an object that defines `exported_fn`, a short and a long internal function, and
+/// references an internal function of another object.
+fn fixture(format: BinaryFormat, arch: Architecture, endian: Endianness) -> Vec<u8> {
+    let mut obj = write::Object::new(format, arch, endian);
+    let text = obj.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    obj.append_section_data(text, &[0; 48], 16);
+    for (value, name) in
+        [(0, "exported_fn"), (16, "short"), (32, "internal_function_with_long_name")]
+    {
+        obj.add_symbol(write::Symbol {
+            name: name.as_bytes().to_vec(),
+            value,
+            size: 16,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+    }
+    obj.add_symbol(write::Symbol {
+        name: b"other_internal_function".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    if format == BinaryFormat::Coff {
+        let drectve = obj.add_section(Vec::new(), b".drectve".to_vec(), SectionKind::Linker);
+        obj.append_section_data(
+            drectve,
+            b" /EXPORT:exported_fn /EXPORT:\"short\",DATA -export:other=exported_fn \
+              /INCLUDE:short /alternatename:exported_fn=internal_function_with_long_name ",
+            1,
+        );
+    }
+    obj.write().unwrap()
+}
+
+fn defined_names(data: &[u8]) -> Vec<String> {
+    let file = object::File::parse(data).unwrap();
+    let mut names: Vec<_> = file
+        .symbols()
+        .filter(|sym| sym.is_global())
+        .map(|sym| sym.name().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn drectve(data: &[u8]) -> Vec<String> {
+    let file = object::File::parse(data).unwrap();
+    let drectve = file.section_by_name(".drectve").unwrap().data().unwrap();
+    str::from_utf8(drectve).unwrap().split_ascii_whitespace().map(str::to_string).collect()
+}
+
+#[test]
+fn directive_parsing() {
+    let directives =
+        b"  /DEFAULTLIB:\"msvcrt\" /EXPORT:a,DATA\0-export:\"b c\" /export:d=a /EXPORT:e \
+        /INCLUDE:_f -include:\"g h\" /ALTERNATENAME:i=j /alternatename:\"k\"=\"l\"";
+    let mut seen = Vec::new();
+    let removed: Vec<_> = symbol_directives(directives, |directive| {
+        seen.push(format!("{directive:?}"));
+        !matches!(directive, Directive::Export("e") | Directive::AlternateName("i", _))
+    })
+    .into_iter()
+    .map(|range| str::from_utf8(&directives[range]).unwrap())
+    .collect();
+    assert_eq!(
+        seen,
+        [
+            "Export(\"a\")",
+            "Export(\"b c\")",
+            "Export(\"d\")",
+            "Export(\"e\")",
+            "Include(\"_f\")",
+            "Include(\"g h\")",
+            "AlternateName(\"i\", \"j\")",
+            "AlternateName(\"k\", \"l\")",
+        ]
+    );
+    assert_eq!(
+        removed,
+        [
+            "/EXPORT:a,DATA",
+            "-export:\"b c\"",
+            "/export:d=a",
+            "/INCLUDE:_f",
+            "-include:\"g h\"",
+            "/alternatename:\"k\"=\"l\"",
+        ]
+    );
+}
+
+#[test]
+fn coff_collect() {
+    let data = fixture(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let mut internal = FxHashSet::default();
+    collect_internal_names(&data, &set(&["exported_fn"]), &mut internal);
+    assert_eq!(internal, set(&["short", "internal_function_with_long_name"]));
+
+    // 32-bit x86 decorates C symbol names with a leading underscore.
+    let data = fixture(BinaryFormat::Coff, Architecture::I386, Endianness::Little);
+    let mut internal = FxHashSet::default();
+    collect_internal_names(&data, &set(&["exported_fn"]), &mut internal);
+    assert_eq!(internal, set(&["short", "internal_function_with_long_name"]));
+}
+
+#[test]
+fn coff_hide_removes_exports() {
+    let data = fixture(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    let edited = apply_edits(&data, &set(&["exported_fn"]), true, None).unwrap();
+
+    // Only exports are affected by hiding.
+    assert_eq!(
+        drectve(&edited),
+        [
+            "/EXPORT:exported_fn",
+            "/INCLUDE:short",
+            "/alternatename:exported_fn=internal_function_with_long_name",
+        ]
+    );
+    // The symbol table itself is untouched.
+    assert_eq!(defined_names(&edited), defined_names(&data));
+}
+
+#[test]
+fn coff_rename() {
+    for arch in [Architecture::X86_64, Architecture::I386] {
+        let data = fixture(BinaryFormat::Coff, arch, Endianness::Little);
+        let rename_set =
+            set(&["short", "internal_function_with_long_name", "other_internal_function"]);
+        let edited =
+            apply_edits(&data, &set(&["exported_fn"]), false, Some(&(rename_set, SUFFIX))).unwrap();
+
+        let prefix = if arch == Architecture::I386 { "_" } else { "" };
+        let mut expected = vec![
+            format!("{prefix}exported_fn"),
+            format!("{prefix}internal_function_with_long_name{SUFFIX}"),
+            format!("{prefix}other_internal_function{SUFFIX}"),
+            format!("{prefix}short{SUFFIX}"),
+        ];
+        expected.sort();
+        assert_eq!(defined_names(&edited), expected);
+
+        // Directives naming a renamed symbol cannot be kept.
+        assert_eq!(drectve(&edited), ["/EXPORT:exported_fn", "-export:other=exported_fn"]);
+    }
+}
+
+#[test]
+fn coff_rename_with_trailing_data() {
+    let mut data = fixture(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little);
+    data.extend_from_slice(&[0; 16]);
+    let rename = (set(&["short"]), SUFFIX);
+    let err = apply_edits(&data, &set(&["exported_fn"]), false, Some(&rename)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Hiding alone does not need to grow the string table.
+    assert!(apply_edits(&data, &set(&["exported_fn"]), true, None).is_ok());
+}
+
+#[test]
+fn xcoff_hide_and_rename() {
+    let data = fixture(BinaryFormat::Xcoff, Architecture::PowerPc64, Endianness::Big);
+    let exported = set(&["exported_fn"]);
+    let mut internal = FxHashSet::default();
+    collect_internal_names(&data, &exported, &mut internal);
+    assert_eq!(internal, set(&["short", "internal_function_with_long_name"]));
+
+    let edited = apply_edits(&data, &exported, true, Some(&(internal, SUFFIX))).unwrap();
+
+    let mut offset = 0;
+    let header = xcoff::FileHeader64::parse(&*edited, &mut offset).unwrap();
+    let table = CoffSymbolTable::<xcoff::Symbol64>::parse(
+        &edited,
+        header.f_symptr() as usize,
+        header.f_nsyms() as usize,
+    )
+    .unwrap();
+    let mut seen = Vec::new();
+    for (_, sym, name) in table.external_symbols() {
+        if sym.is_undefined() {
+            continue;
+        }
+        let hidden = sym.n_type.get(BigEndian) & xcoff::SYM_V_MASK == xcoff::SYM_V_HIDDEN;
+        assert_eq!(hidden, name != "exported_fn", "visibility of `{name}`");
+        seen.push(name.to_string());
+    }
+    seen.sort();
+    assert_eq!(
+        seen,
+        [
+            "exported_fn".to_string(),
+            format!("internal_function_with_long_name{SUFFIX}"),
+            format!("short{SUFFIX}"),
+        ]
+    );
+}
@@ -22,6 +22,7 @@
 use rustc_middle::bug;
 use rustc_middle::dep_graph::{WorkProduct, WorkProductMap};
 use rustc_middle::ty::TyCtxt;
+use rustc_session::code_stats::OptRemark;
 use rustc_session::config::{
     self, CrateType, Lto, OptLevel, OutFileName, OutputFilenames, OutputType, Passes,
     SwitchWithOptPath,
@@ -34,6 +35,7 @@
 
 use crate::back::link::ensure_removed;
 use crate::back::lto::{self, SerializedModule, check_lto_allowed};
+use crate::back::opt_remarks;
 use crate::diagnostics::ErrorCreatingRemarkDir;
 use crate::traits::*;
 use crate::{
@@ -438,6 +440,14 @@ pub(crate) fn start_async_codegen<B: WriteBackendMethods>(
     let (shared_emitter, shared_emitter_main) = SharedEmitter::new();
     let (codegen_worker_send, codegen_worker_receive) = channel();
 
+    let remark_modules = tcx.sess.opts.unstable_opts.remark_dir.is_some().then(|| {
+        let cgus = tcx.collect_and_partition_mono_items(()).codegen_units;
+        cgus.iter()
+            .map(|cgu| cgu.name().to_string())
+            .chain(allocator_module.as_ref().map(|module| module.name.clone()))
+            .collect()
+    });
+
     let coordinator_thread = start_executing_work(
         backend.clone(),
         tcx,
@@ -461,6 +471,8 @@ pub(crate) fn start_async_codegen<B: WriteBackendMethods>(
             phantom: PhantomData,
         },
         output_filenames: Arc::clone(tcx.output_filenames(())),
+        remark_modules,
+        mir_remarks: Vec::new(),
     }
 }
 
@@ -2136,6 +2148,10 @@ pub struct OngoingCodegen<B: WriteBackendMethods> {
     pub(crate) coordinator: Coordinator<B>,
     codegen_worker_receive: Receiver<CguMessage>,
     shared_emitter_main: SharedEmitterMain,
+    /// The names of all modules whose LLVM remarks go into the report of `-Zremark-dir`.
+    remark_modules: Option<Vec<String>>,
+    /// The remarks of MIR passes for `-Zremark-dir`, taken from the session once codegen is done.
+    mir_remarks: Vec<OptRemark>,
 }
 
 impl<B: WriteBackendMethods> OngoingCodegen<B> {
@@ -2229,13 +2245,27 @@ pub fn join(
         );
         produce_final_output_artifacts(sess, &compiled_modules, &self.output_filenames);
 
+        if let Some(remark_modules) = &self.remark_modules {
+            opt_remarks::write_report(
+                sess,
+                self.mir_remarks,
+                remark_modules,
+                &self.output_filenames,
+            );
+        }
+
         (compiled_modules, work_products)
     }
 
-    pub(crate) fn codegen_finished(&self, tcx: TyCtxt<'_>) {
+    pub(crate) fn codegen_finished(&mut self, tcx: TyCtxt<'_>) {
         self.wait_for_signal_to_codegen_item();
         self.check_for_errors(tcx.sess);
         drop(self.coordinator.sender.send(Message::CodegenComplete::<B>));
+
+        // All MIR has been optimized by now, and the symbol names are known.
+        if self.remark_modules.is_some() {
+            self.mir_remarks = opt_remarks::mir_remarks(tcx);
+        }
     }
 
     pub(crate) fn check_for_errors(&self, sess: &Session) {
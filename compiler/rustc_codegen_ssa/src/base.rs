@@ -40,6 +40,7 @@
 
 use crate::assert_module_sources::CguReuse;
 use crate::back::link::are_upstream_rust_objects_already_included;
+use crate::back::size_report;
 use crate::back::write::{
     ComputedLtoType, OngoingCodegen, compute_per_cgu_lto_type, start_async_codegen,
     submit_codegened_module_to_llvm, submit_post_lto_module_to_llvm, submit_pre_lto_module_to_llvm,
@@ -402,6 +403,13 @@ pub(crate) fn codegen_instance<'a, 'tcx: 'a, Bx: BuilderMethods<'a, 'tcx>>(
     info!("codegen_instance({})", instance);
 
     mir::codegen_mir::<Bx>(cx, instance);
+
+    let tcx = cx.tcx();
+    if tcx.sess.opts.unstable_opts.print_generic_bloat.is_some()
+        && let Some(instructions) = cx.instruction_count(cx.get_fn(instance))
+    {
+        tcx.sess.code_stats.record_codegen_size(tcx.symbol_name(instance).name, instructions);
+    }
 }
 
 pub fn codegen_global_asm<'tcx, Cx>(cx: &mut Cx, item_id: ItemId)
@@ -761,7 +769,7 @@ pub fn codegen_crate<
         None
     };
 
-    let ongoing_codegen = start_async_codegen(backend.clone(), tcx, allocator_module);
+    let mut ongoing_codegen = start_async_codegen(backend.clone(), tcx, allocator_module);
 
     // For better throughput during parallel processing by LLVM, we used to sort
     // CGUs largest to smallest. This would lead to better thread utilization
@@ -880,6 +888,10 @@ pub fn codegen_crate<
 
     ongoing_codegen.codegen_finished(tcx);
 
+    if let Some(limit) = tcx.sess.opts.unstable_opts.print_generic_bloat {
+        tcx.print_generic_bloat(limit);
+    }
+
     // Since the main thread is sometimes blocked during codegen, we keep track
     // -Ztime-passes output manually.
     if tcx.sess.opts.unstable_opts.time_passes {
@@ -1068,6 +1080,11 @@ pub fn new(tcx: TyCtxt<'_>, target_cpu: String) -> CrateInfo {
             symbol_rename_suffix: format!(".rs{:x}", tcx.stable_crate_id(LOCAL_CRATE)),
             each_linked_rlib_file_for_lto: Default::default(),
             exported_symbols_for_lto: Default::default(),
+            binary_size_symbols: if size_report::enabled(tcx.sess) {
+                size_report::collect_symbol_origins(tcx)
+            } else {
+                Vec::new()
+            },
         };
 
         info.native_libraries.reserve(n_crates);
@@ -160,6 +160,67 @@ pub(crate) struct IgnoringOutput {
     pub extension: &'static str,
 }
 
+#[derive(Diagnostic)]
+#[diag("failed to read `{$path}` for the binary size report: {$error}")]
+pub(crate) struct BinarySizeRead<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to parse `{$path}` for the binary size report")]
+pub(crate) struct BinarySizeParse<'a> {
+    pub path: &'a Path,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to write the binary size report to `{$path}`: {$error}")]
+pub(crate) struct BinarySizeWrite<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("the linker did not write a map file to `{$path}`")]
+#[note("`-Zlinker-map` is not supported by every linker")]
+pub(crate) struct LinkerMapMissing<'a> {
+    pub path: &'a Path,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to read the linker map `{$path}`: {$error}")]
+pub(crate) struct LinkerMapRead<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to parse the linker map `{$path}`, its format is not known")]
+pub(crate) struct LinkerMapParse<'a> {
+    pub path: &'a Path,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to write the linker map to `{$path}`: {$error}")]
+pub(crate) struct LinkerMapWrite<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("not linking incrementally: {$reason}")]
+pub(crate) struct IncrementalLinkUnavailable {
+    pub reason: &'static str,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to update the incremental link state in `{$path}`: {$error}")]
+#[note("the output will be linked in full")]
+pub(crate) struct IncrementalLinkState<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
 #[derive(Diagnostic)]
 #[diag("couldn't create a temp dir: {$error}")]
 pub(crate) struct CreateTempDir {
@@ -688,7 +749,7 @@ pub(crate) struct IncompatibleArchiveFormat {
 
 #[derive(Diagnostic)]
 #[diag(
-    "-Zstaticlib-hide-internal-symbols only supports ELF and Mach-O targets, but the target uses `{$binary_format}`"
+    "-Zstaticlib-hide-internal-symbols only supports ELF, Mach-O, COFF and XCOFF targets, but the target uses `{$binary_format}`"
 )]
 pub(crate) struct StaticlibHideInternalSymbolsUnsupported {
     pub binary_format: String,
@@ -696,7 +757,7 @@ pub(crate) struct StaticlibHideInternalSymbolsUnsupported {
 
 #[derive(Diagnostic)]
 #[diag(
-    "-Zstaticlib-rename-internal-symbols only supports ELF and Mach-O targets, but the target uses `{$binary_format}`"
+    "-Zstaticlib-rename-internal-symbols only supports ELF, Mach-O, COFF and XCOFF targets, but the target uses `{$binary_format}`"
 )]
 pub(crate) struct StaticlibRenameInternalSymbolsUnsupported {
     pub binary_format: String,
@@ -1144,6 +1205,20 @@ pub(crate) struct ErrorCreatingRemarkDir {
     pub error: std::io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag("failed to read the optimization remarks `{$path}`: {$error}")]
+pub(crate) struct OptRemarksRead<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to write the optimization remarks to `{$path}`: {$error}")]
+pub(crate) struct OptRemarksWrite<'a> {
+    pub path: &'a Path,
+    pub error: Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(
     "`compiler_builtins` cannot call functions through upstream monomorphizations; encountered invalid call from `{$caller}` to `{$callee}`"
@@ -302,6 +302,9 @@ pub struct CrateInfo {
     pub symbol_rename_suffix: String,
     pub each_linked_rlib_file_for_lto: Vec<PathBuf>,
     pub exported_symbols_for_lto: Vec<String>,
+    /// The symbol names of the local mono items and the items they come from, only collected for
+    /// `-Zprint-binary-size`.
+    pub binary_size_symbols: Vec<(String, back::size_report::SymbolOrigin)>,
 }
 
 /// Target-specific options that get set in `sess`/`cfg(...)`.
@@ -11,6 +11,7 @@
 use rustc_session::config::{CrateType, OutputFilenames, PrintRequest};
 use rustc_session::{IncrCompSession, Session};
 use rustc_span::Symbol;
+use rustc_target::callconv::BackendLoweredAbi;
 
 use super::CodegenObject;
 use crate::back::archive::ArArchiveBuilderBuilder;
@@ -87,6 +88,14 @@ fn thin_lto_supported(&self) -> bool {
         true
     }
 
+    /// The parts of the function ABI that this backend lowers the same way as LLVM.
+    ///
+    /// Calls between crates codegened by different backends are rejected if the callee's ABI
+    /// contains a part that is not lowered like LLVM by both backends.
+    fn llvm_compatible_abi(&self) -> BackendLoweredAbi {
+        BackendLoweredAbi::empty()
+    }
+
     /// Value printed by `--print=backend-has-zstd`.
     ///
     /// Used by compiletest to determine whether tests involving zstd compression
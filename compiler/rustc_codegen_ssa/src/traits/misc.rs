@@ -37,4 +37,9 @@ fn get_fn_addr(
     ///
     /// This is discouraged, but here for now to simplify migration to using OperandValues
     fn intrinsic_call_expects_place_always(&self, name: Symbol) -> bool;
+
+    /// The number of instructions in the body of `llfn`, if the backend can count them.
+    fn instruction_count(&self, _llfn: Self::Function) -> Option<u64> {
+        None
+    }
 }
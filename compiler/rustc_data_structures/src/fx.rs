@@ -1,4 +1,40 @@
-pub use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet, FxHasher};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::hash::BuildHasher;
+use std::sync::OnceLock;
+
+pub use rustc_hash::FxHasher;
+
+/// The environment variable that seeds the hashers of [`FxHashMap`]s and [`FxHashSet`]s, which
+/// changes their iteration order. It is used by `-Zverify-reproducibility` to find output that
+/// depends on that order.
+pub const HASH_SEED_ENV: &str = "RUSTC_HASH_SEED";
+
+/// The seed of [`FxBuildHasher`], read from [`HASH_SEED_ENV`] the first time it is needed and 0 if
+/// that is not set. It must not change afterwards, as existing maps would be hashed differently.
+#[inline]
+pub fn hash_seed() -> usize {
+    static SEED: OnceLock<usize> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        env::var(HASH_SEED_ENV).ok().and_then(|seed| seed.parse().ok()).unwrap_or(0)
+    })
+}
+
+/// Like `rustc_hash::FxBuildHasher`, but seeded with [`hash_seed`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FxBuildHasher;
+
+impl BuildHasher for FxBuildHasher {
+    type Hasher = FxHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> FxHasher {
+        FxHasher::with_seed(hash_seed())
+    }
+}
+
+pub type FxHashMap<K, V> = HashMap<K, V, FxBuildHasher>;
+pub type FxHashSet<V> = HashSet<V, FxBuildHasher>;
 
 pub type StdEntry<'a, K, V> = std::collections::hash_map::Entry<'a, K, V>;
 
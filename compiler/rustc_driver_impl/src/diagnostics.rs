@@ -113,3 +113,41 @@ pub(crate) struct IceFlags {
 pub(crate) struct UnstableFeatureUsage {
     pub error: Box<dyn Error>,
 }
+
+#[derive(Diagnostic)]
+#[diag("`-Zverify-reproducibility` cannot be used with input from stdin")]
+pub(crate) struct ReproducibilityStdin;
+
+#[derive(Diagnostic)]
+#[diag("failed to create the output directories of the reproducibility check: {$error}")]
+pub(crate) struct ReproducibilityTempDir {
+    pub error: std::io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("the {$run} run of the reproducibility check failed: {$error}")]
+pub(crate) struct ReproducibilityRunFailed<'a> {
+    pub run: &'a str,
+    pub error: String,
+}
+
+#[derive(Diagnostic)]
+#[diag("failed to read `{$path}`: {$error}")]
+pub(crate) struct ReproducibilityRead {
+    pub path: std::path::PathBuf,
+    pub error: std::io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("`{$artifact}` was only emitted by the {$run} run")]
+pub(crate) struct ReproducibilityMissing<'a> {
+    pub artifact: String,
+    pub run: &'a str,
+}
+
+#[derive(Diagnostic)]
+#[diag("`{$artifact}` is not reproducible, the first difference is in {$difference}")]
+pub(crate) struct NotReproducible {
+    pub artifact: String,
+    pub difference: String,
+}
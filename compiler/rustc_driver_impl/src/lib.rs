@@ -89,6 +89,7 @@
 mod print;
 mod diagnostics;
 pub mod highlighter;
+mod reproducibility;
 
 // Keep the OS parts of this `cfg` in sync with the `cfg` on the `libc`
 // dependency in `compiler/rustc_driver/Cargo.toml`, to keep
@@ -258,6 +259,11 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
             return;
         }
 
+        if sess.opts.unstable_opts.verify_reproducibility {
+            reproducibility::verify(sess, &*codegen_backend.metadata_loader(), &args);
+            return;
+        }
+
         if sess.opts.unstable_opts.link_only {
             process_rlink(sess, compiler);
             return;
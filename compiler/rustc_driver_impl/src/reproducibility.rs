@@ -0,0 +1,403 @@
+//! The reproducibility check of `-Zverify-reproducibility`.
+//!
+//! The compiler runs itself twice with the same arguments, but from a different working
+//! directory, with a different seed for the hashers of `FxHashMap`s and with a different number of
+//! frontend and backend threads. Each run is a separate process, so the address space layout
+//! differs as well. Both runs write their artifacts to the same temporary output directory one after the other, so the
+//! output paths do not introduce differences. The artifacts are then compared and for every one
+//! that differs, the first difference is reported as a metadata table entry, an archive member, an
+//! object file section and symbol, or a byte offset.
+
+use std::hash::{BuildHasher, RandomState};
+use std::num::NonZero;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::{env, fs, io, iter};
+
+use object::read::archive::ArchiveFile;
+use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
+use rustc_data_structures::fx;
+use rustc_fs_util::TempDirBuilder;
+use rustc_metadata::creader::MetadataLoader;
+use rustc_metadata::{METADATA_FILENAME, locator};
+use rustc_session::Session;
+use rustc_session::config::Input;
+
+use crate::diagnostics::{
+    NotReproducible, ReproducibilityMissing, ReproducibilityRead, ReproducibilityRunFailed,
+    ReproducibilityStdin, ReproducibilityTempDir,
+};
+
+/// Splits `arg` into the name and value of one of the options the check needs to rewrite, taking
+/// the value from the next argument if it is not attached.
+fn rewritten_option<'a>(
+    arg: &'a str,
+    rest: &mut impl Iterator<Item = &'a String>,
+) -> Option<(&'static str, &'a str)> {
+    const LONG: &[&str] =
+        &["out-dir", "jobs", "jobs-frontend", "jobs-backend", "extern", "sysroot"];
+    const SHORT: &[&str] = &["o", "j", "L", "C", "Z"];
+
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        let name = *LONG.iter().find(|&&option| option == name)?;
+        let value = match value {
+            Some(value) => value,
+            None => rest.next()?,
+        };
+        return Some((name, value));
+    }
+
+    let short = arg.strip_prefix('-')?;
+    let name = *SHORT.iter().find(|&&option| short.starts_with(option))?;
+    let value = match &short[name.len()..] {
+        "" => rest.next()?,
+        value => value,
+    };
+    Some((name, value))
+}
+
+fn absolute(path: &str) -> String {
+    match std::path::absolute(path) {
+        Ok(path) => path.display().to_string(),
+        Err(_) => path.to_owned(),
+    }
+}
+
+/// The arguments shared by both runs: the original arguments without the check itself, the output
+/// location, incremental compilation and the thread counts, and with relative input and search
+/// paths made absolute. Also returns the file name given with `-o`, if any.
+fn run_args(args: &[String], input: &Path) -> (Vec<String>, Option<String>) {
+    let input = input.to_str();
+    let mut run_args = Vec::new();
+    let mut output_file = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let Some((name, value)) = rewritten_option(arg, &mut rest) else {
+            if Some(&**arg) == input {
+                run_args.push(absolute(arg));
+            } else {
+                run_args.push(arg.clone());
+            }
+            continue;
+        };
+        let option = value.split('=').next().unwrap_or_default().replace('_', "-");
+        match name {
+            "o" => {
+                output_file =
+                    Path::new(value).file_name().map(|name| name.to_string_lossy().into_owned())
+            }
+            "out-dir" | "j" | "jobs" | "jobs-frontend" | "jobs-backend" => {}
+            "Z" if option == "verify-reproducibility" || option == "threads" => {}
+            "C" if option == "incremental" => {}
+            "L" => {
+                let value = match value.split_once('=') {
+                    Some((kind, path)) => format!("{kind}={}", absolute(path)),
+                    None => absolute(value),
+                };
+                run_args.extend(["-L".to_owned(), value]);
+            }
+            "extern" => {
+                let value = match value.split_once('=') {
+                    Some((name, path)) => format!("{name}={}", absolute(path)),
+                    None => value.to_owned(),
+                };
+                run_args.extend(["--extern".to_owned(), value]);
+            }
+            "sysroot" => run_args.extend(["--sysroot".to_owned(), absolute(value)]),
+            _ => run_args.extend([format!("-{name}"), value.to_owned()]),
+        }
+    }
+    (run_args, output_file)
+}
+
+/// Recursively lists the files in `dir`, relative to `dir`.
+fn artifacts(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut artifacts = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                artifacts.push(path);
+            }
+        }
+    }
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    iter::zip(a, b)
+        .position(|(a, b)| a != b)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
+/// Describes the first difference between two object files by section and symbol, or returns
+/// `None` if they cannot be parsed.
+fn diff_object(a: &[u8], b: &[u8]) -> Option<String> {
+    let file_a = object::File::parse(a).ok()?;
+    let file_b = object::File::parse(b).ok()?;
+    let mut sections_b = file_b.sections();
+    for section in file_a.sections() {
+        let name = section.name().unwrap_or_default();
+        let Some(other) = sections_b.next() else {
+            return Some(format!("section `{name}`, which only the first run emitted"));
+        };
+        let other_name = other.name().unwrap_or_default();
+        if name != other_name {
+            return Some(format!("section headers, `{name}` vs `{other_name}`"));
+        }
+        let (Ok(data), Ok(other_data)) = (section.data(), other.data()) else { continue };
+        let Some(offset) = first_difference(data, other_data) else { continue };
+
+        // Find the symbol covering the difference, or the closest one before it.
+        let address = section.address() + offset as u64;
+        let symbol = file_a
+            .symbols()
+            .filter(|symbol| {
+                symbol.section_index() == Some(section.index())
+                    && symbol.is_definition()
+                    && !matches!(symbol.kind(), SymbolKind::Section | SymbolKind::File)
+                    && symbol.address() <= address
+            })
+            .max_by_key(|symbol| (symbol.address(), symbol.size()));
+        return Some(
+            match symbol.and_then(|symbol| Some((symbol.name().ok()?, symbol.address()))) {
+                Some((symbol, start)) => {
+                    format!("section `{name}`, symbol `{symbol}` + {:#x}", address - start)
+                }
+                None => format!("section `{name}` at offset {offset:#x}"),
+            },
+        );
+    }
+    if let Some(other) = sections_b.next() {
+        let name = other.name().unwrap_or_default();
+        return Some(format!("section `{name}`, which only the second run emitted"));
+    }
+    Some("object file headers or relocations".to_owned())
+}
+
+/// Describes the first difference between two archives by member, or returns `None` if they
+/// cannot be parsed.
+fn diff_archive(a: &[u8], b: &[u8]) -> Option<String> {
+    let archive_a = ArchiveFile::parse(a).ok()?;
+    let archive_b = ArchiveFile::parse(b).ok()?;
+    let mut members_b = archive_b.members();
+    for member in archive_a.members() {
+        let member = member.ok()?;
+        let name = String::from_utf8_lossy(member.name());
+        let Some(Ok(other)) = members_b.next() else {
+            return Some(format!("archive member `{name}`, which only the first run emitted"));
+        };
+        let other_name = String::from_utf8_lossy(other.name());
+        if name != other_name {
+            return Some(format!("archive members, `{name}` vs `{other_name}`"));
+        }
+        let (Ok(data), Ok(other_data)) = (member.data(a), other.data(b)) else { continue };
+        if data == other_data {
+            continue;
+        }
+        return Some(match diff_object(data, other_data) {
+            Some(difference) => format!("archive member `{name}`, {difference}"),
+            None => format!(
+                "archive member `{name}` at byte {}",
+                first_difference(data, other_data).unwrap_or_default()
+            ),
+        });
+    }
+    Some("archive headers or symbol table".to_owned())
+}
+
+/// Describes the first difference between the two copies of an artifact, or returns `None` if
+/// they are identical.
+fn diff_artifact(
+    sess: &Session,
+    metadata_loader: &dyn MetadataLoader,
+    path_a: &Path,
+    path_b: &Path,
+) -> io::Result<Option<String>> {
+    let a = fs::read(path_a)?;
+    let b = fs::read(path_b)?;
+    if a == b {
+        return Ok(None);
+    }
+
+    // Crate metadata is diffed first, the differences in everything else usually follow from it.
+    // Artifacts that do not contain metadata fail to load and are diffed as files instead.
+    if let Ok(Some(difference)) =
+        locator::diff_file_metadata(&sess.target, path_a, path_b, metadata_loader, sess.cfg_version)
+    {
+        return Ok(Some(format!("metadata {difference}")));
+    }
+
+    let difference = diff_archive(&a, &b)
+        .map(|difference| difference.replace(METADATA_FILENAME, "metadata"))
+        .or_else(|| diff_object(&a, &b))
+        .unwrap_or_else(|| format!("byte {}", first_difference(&a, &b).unwrap_or_default()));
+    Ok(Some(difference))
+}
+
+/// A thread count that differs from `threads`.
+fn other_thread_count(threads: usize) -> usize {
+    if threads == 1 {
+        std::thread::available_parallelism().map_or(2, |threads| threads.get().max(2))
+    } else {
+        1
+    }
+}
+
+/// A random hash seed that differs from the one of this invocation.
+fn other_hash_seed() -> usize {
+    let seed = RandomState::new().hash_one(process::id()) as usize;
+    if seed == fx::hash_seed() { !seed } else { seed }
+}
+
+/// The environment of one of the two runs.
+struct RunEnv<'a> {
+    name: &'static str,
+    /// The directory the artifacts are moved to once the run finished.
+    dir: &'a Path,
+    /// The working directory, or `None` for the one of this invocation.
+    cwd: Option<&'a Path>,
+    hash_seed: usize,
+    frontend_threads: usize,
+    backend_threads: usize,
+}
+
+/// Runs the compiler with `args`, writing the artifacts to `out_dir`.
+fn run(
+    sess: &Session,
+    args: &[String],
+    output_file: Option<&str>,
+    out_dir: &Path,
+    run_env: &RunEnv<'_>,
+) -> bool {
+    let RunEnv { name, dir: _, cwd, hash_seed, frontend_threads, backend_threads } = *run_env;
+    // Printed first, so that a run that fails or differs can be reproduced.
+    safe_println!(
+        "{name} run: hash seed {hash_seed}, {frontend_threads} frontend and {backend_threads} \
+        backend threads"
+    );
+
+    let mut command = Command::new(env::current_exe().unwrap_or_else(|_| "rustc".into()));
+    command
+        .args(args)
+        .arg(format!("-Zthreads={frontend_threads}"))
+        // `--jobs-backend` is unstable.
+        .arg("-Zunstable-options")
+        .arg(format!("--jobs-backend={backend_threads}"))
+        .env(fx::HASH_SEED_ENV, hash_seed.to_string());
+    match output_file {
+        Some(output_file) => command.arg("-o").arg(out_dir.join(output_file)),
+        None => command.arg("--out-dir").arg(out_dir),
+    };
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let error = match command.status() {
+        Ok(status) if status.success() => return true,
+        Ok(status) => status.to_string(),
+        Err(error) => error.to_string(),
+    };
+    sess.dcx().emit_err(ReproducibilityRunFailed { run: name, error });
+    false
+}
+
+pub(crate) fn verify(sess: &Session, metadata_loader: &dyn MetadataLoader, args: &[String]) {
+    let Input::File(input) = &sess.io.input else {
+        sess.dcx().emit_fatal(ReproducibilityStdin);
+    };
+    let (args, output_file) = run_args(args, input);
+    let frontend_threads = sess.opts.jobs.frontend.map_or(1, NonZero::get);
+    let backend_threads = sess.opts.jobs.backend.map_or(1, NonZero::get);
+
+    let tmpdir = TempDirBuilder::new()
+        .prefix("rustc-reproducibility")
+        .tempdir_in(env::temp_dir())
+        .unwrap_or_else(|error| sess.dcx().emit_fatal(ReproducibilityTempDir { error }));
+    let out_dir = tmpdir.path().join("out");
+    let first = tmpdir.path().join("first");
+    let second = tmpdir.path().join("second");
+    let cwd = tmpdir.path().join("cwd");
+    if let Err(error) = fs::create_dir(&cwd) {
+        sess.dcx().emit_fatal(ReproducibilityTempDir { error });
+    }
+
+    // The first run uses the environment of this invocation, the second one an empty temporary
+    // directory, another hash seed and other thread counts.
+    let runs = [
+        RunEnv {
+            name: "first",
+            dir: &first,
+            cwd: None,
+            hash_seed: fx::hash_seed(),
+            frontend_threads,
+            backend_threads,
+        },
+        RunEnv {
+            name: "second",
+            dir: &second,
+            cwd: Some(&cwd),
+            hash_seed: other_hash_seed(),
+            frontend_threads: other_thread_count(frontend_threads),
+            backend_threads: other_thread_count(backend_threads),
+        },
+    ];
+    for run_env in runs {
+        if let Err(error) = fs::create_dir(&out_dir) {
+            sess.dcx().emit_fatal(ReproducibilityTempDir { error });
+        }
+        if !run(sess, &args, output_file.as_deref(), &out_dir, &run_env) {
+            return;
+        }
+        if let Err(error) = fs::rename(&out_dir, run_env.dir) {
+            sess.dcx().emit_fatal(ReproducibilityTempDir { error });
+        }
+    }
+
+    let list = |dir: &Path| {
+        artifacts(dir).unwrap_or_else(|error| {
+            sess.dcx().emit_fatal(ReproducibilityRead { path: dir.to_owned(), error })
+        })
+    };
+    let artifacts_first = list(&first);
+    let artifacts_second = list(&second);
+    for artifact in artifacts_second.iter().filter(|artifact| !artifacts_first.contains(artifact)) {
+        sess.dcx().emit_err(ReproducibilityMissing {
+            artifact: artifact.display().to_string(),
+            run: "second",
+        });
+    }
+
+    let mut reproducible = 0;
+    for artifact in &artifacts_first {
+        if !artifacts_second.contains(artifact) {
+            sess.dcx().emit_err(ReproducibilityMissing {
+                artifact: artifact.display().to_string(),
+                run: "first",
+            });
+            continue;
+        }
+        let path = first.join(artifact);
+        match diff_artifact(sess, metadata_loader, &path, &second.join(artifact)) {
+            Ok(None) => reproducible += 1,
+            Ok(Some(difference)) => {
+                sess.dcx().emit_err(NotReproducible {
+                    artifact: artifact.display().to_string(),
+                    difference,
+                });
+            }
+            Err(error) => {
+                sess.dcx().emit_err(ReproducibilityRead { path, error });
+            }
+        }
+    }
+    safe_println!("{reproducible} of {} artifacts are reproducible", artifacts_first.len());
+}
@@ -17,6 +17,18 @@ pub(crate) struct CrateNameDoesNotMatch {
     pub(crate) attr_crate_name: Symbol,
 }
 
+#[derive(Diagnostic)]
+#[diag(
+    "`-Zcrate-codegen-backend` chose the codegen backend for the crate name `{$early_crate_name}`, but `#![crate_name]` names the crate `{$crate_name}`"
+)]
+#[help(
+    "pass `--crate-name {$crate_name}` so that the crate name is known before the crate is parsed"
+)]
+pub(crate) struct CrateCodegenBackendCrateName {
+    pub(crate) crate_name: Symbol,
+    pub(crate) early_crate_name: String,
+}
+
 #[derive(Diagnostic)]
 #[diag("crate names cannot start with a `-`, but `{$crate_name}` has a leading hyphen")]
 pub(crate) struct CrateNameInvalid<'a> {
@@ -429,7 +429,7 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
                 None => util::get_codegen_backend(
                     &early_dcx,
                     &sess.opts.sysroot,
-                    sess.opts.unstable_opts.codegen_backend.as_deref(),
+                    sess.codegen_backend(),
                     &sess.target,
                 ),
                 Some(make_codegen_backend) => {
@@ -442,6 +442,8 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
             sess.replaced_intrinsics = FxHashSet::from_iter(codegen_backend.replaced_intrinsics());
             sess.fallback_intrinsics = FxHashSet::from_iter(codegen_backend.fallback_intrinsics());
             sess.thin_lto_supported = codegen_backend.thin_lto_supported();
+            sess.codegen_backend_name = codegen_backend.name();
+            sess.codegen_backend_llvm_compatible_abi = codegen_backend.llvm_compatible_abi();
 
             let cfg = parse_cfg(sess.dcx(), config.crate_cfg);
             let mut cfg = config::build_configuration(&sess, cfg);
@@ -676,7 +676,7 @@ fn hash_iter_files<P: AsRef<Path>>(
         }
 
         if sess.binary_dep_depinfo() {
-            if let Some(ref backend) = sess.opts.unstable_opts.codegen_backend {
+            if let Some(backend) = sess.codegen_backend() {
                 if backend.contains('.') {
                     // If the backend name contain a `.`, it is the path to an external dynamic
                     // library. If not, it is not a path.
@@ -942,6 +942,7 @@ pub fn create_and_enter_global_ctxt<T, F: for<'tcx> FnOnce(TyCtxt<'tcx>) -> T>(
     let pre_configured_attrs = rustc_expand::config::pre_configure_attrs(sess, &krate.attrs);
 
     let crate_name = get_crate_name(sess, &pre_configured_attrs);
+    check_crate_codegen_backend(sess, crate_name);
     let crate_types = collect_crate_types(
         sess,
         &compiler.codegen_backend.supported_crate_types(sess),
@@ -1400,6 +1401,19 @@ pub fn get_crate_name(sess: &Session, krate_attrs: &[ast::Attribute]) -> Symbol
     sym::rust_out
 }
 
+/// The codegen backend is chosen before the crate is parsed, see [`Session::codegen_backend`].
+/// Reject a `#![crate_name]` for which `-Zcrate-codegen-backend` would have chosen another one.
+fn check_crate_codegen_backend(sess: &Session, crate_name: Symbol) {
+    let early_crate_name = sess.early_crate_name();
+    if crate_name.as_str() != early_crate_name
+        && sess.opts.crate_codegen_backend(crate_name.as_str())
+            != sess.opts.crate_codegen_backend(&early_crate_name)
+    {
+        sess.dcx()
+            .emit_err(diagnostics::CrateCodegenBackendCrateName { crate_name, early_crate_name });
+    }
+}
+
 pub(crate) fn parse_crate_name(
     sess: &Session,
     attrs: &[ast::Attribute],
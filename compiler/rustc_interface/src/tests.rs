@@ -665,6 +665,18 @@ macro_rules! tracked {
     tracked!(target_cpu, Some(String::from("abc")));
     tracked!(target_feature, String::from("all the features, all of them"));
     // tidy-alphabetical-end
+
+    macro_rules! tracked_no_crate_hash {
+        ($name: ident, $non_default_value: expr) => {
+            opts = reference.clone();
+            assert_ne!(opts.cg.$name, $non_default_value);
+            opts.cg.$name = $non_default_value;
+            assert_non_crate_hash_different(&reference, &opts);
+        };
+    }
+    // Only the remarks of MIR passes are tracked.
+    tracked_no_crate_hash!(remark, Passes::Some(vec![String::from("mir-inline")]));
+    tracked_no_crate_hash!(remark, Passes::All);
 }
 
 #[test]
@@ -726,9 +738,11 @@ macro_rules! untracked {
     untracked!(future_incompat_test, true);
     untracked!(identify_regions, true);
     untracked!(incremental_info, true);
+    untracked!(incremental_link, true);
     untracked!(incremental_verify_ich, true);
     untracked!(input_stats, true);
     untracked!(link_native_libraries, false);
+    untracked!(linker_map, true);
     untracked!(llvm_time_trace, true);
     untracked!(ls, vec!["all".to_owned()]);
     untracked!(macro_backtrace, true);
@@ -743,7 +757,10 @@ macro_rules! untracked {
     untracked!(parse_crate_root_only, true);
     // `pre_link_arg` is omitted because it just forwards to `pre_link_args`.
     untracked!(pre_link_args, vec![String::from("abc"), String::from("def")]);
+    untracked!(print_binary_size, true);
+    untracked!(print_binary_size_json, Some(String::from("abc")));
     untracked!(print_codegen_stats, true);
+    untracked!(print_generic_bloat, Some(10));
     untracked!(print_llvm_passes, true);
     untracked!(print_mono_items, true);
     untracked!(print_type_sizes, true);
@@ -768,6 +785,7 @@ macro_rules! untracked {
     untracked!(unpretty, Some("expanded".to_string()));
     untracked!(unstable_options, true);
     untracked!(validate_mir, true);
+    untracked!(verify_reproducibility, true);
     untracked!(write_long_types_to_disk, false);
     // tidy-alphabetical-end
 
@@ -809,6 +827,7 @@ macro_rules! tracked {
         }
     );
     tracked!(crate_attr, vec!["abc".to_string()]);
+    tracked!(crate_codegen_backend, vec![("foo".to_string(), "cranelift".to_string())]);
     tracked!(cross_crate_inline_threshold, InliningThreshold::Always);
     tracked!(debug_info_type_line_numbers, true);
     tracked!(debuginfo_for_profiling, true);
@@ -834,6 +853,7 @@ macro_rules! tracked {
     tracked!(indirect_branch_cs_prefix, true);
     tracked!(inline_mir, Some(true));
     tracked!(inline_mir_hint_threshold, Some(123));
+    tracked!(inline_mir_hot_threshold, Some(123));
     tracked!(inline_mir_threshold, Some(123));
     tracked!(instrument_mcount, InstrumentMcount::Mcount(InstrumentMcountOpts::default()));
     tracked!(instrument_xray, Some(InstrumentXRay::default()));
@@ -1501,6 +1501,10 @@ extern "C" uint64_t LLVMRustModuleInstructionStats(LLVMModuleRef M) {
   return unwrap(M)->getInstructionCount();
 }
 
+extern "C" uint64_t LLVMRustFunctionInstructionCount(LLVMValueRef F) {
+  return unwrap<Function>(F)->getInstructionCount();
+}
+
 // Transfers ownership of DiagnosticHandler unique_ptr to the caller.
 extern "C" DiagnosticHandler *
 LLVMRustContextGetDiagnosticHandler(LLVMContextRef C) {
@@ -958,6 +958,23 @@ pub fn list_file_metadata(
     }
 }
 
+/// Describes the first difference between the metadata of two crate files, or returns `None` if
+/// the metadata is identical.
+pub fn diff_file_metadata(
+    target: &Target,
+    path: &Path,
+    other_path: &Path,
+    metadata_loader: &dyn MetadataLoader,
+    cfg_version: &'static str,
+) -> IoResult<Option<String>> {
+    let load = |path: &Path| {
+        let flavor = get_flavor_from_path(path);
+        get_metadata_section(target, flavor, path, metadata_loader, cfg_version, None)
+            .map_err(|msg| IoError::new(IoErrorKind::Other, msg.to_string()))
+    };
+    Ok(load(path)?.first_difference(&load(other_path)?))
+}
+
 fn get_flavor_from_path(path: &Path) -> CrateFlavor {
     let filename = path.file_name().unwrap().to_str().unwrap();
 
@@ -4,7 +4,7 @@
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
-use std::{io, mem};
+use std::{io, iter, mem};
 
 pub(super) use cstore_impl::provide;
 use rustc_ast as ast;
@@ -774,6 +774,11 @@ pub(crate) fn list_crate_metadata(
                     writeln!(out, "triple {}", root.header.triple.tuple())?;
                     writeln!(out, "edition {}", root.edition)?;
                     writeln!(out, "symbol_mangling_version {:?}", root.symbol_mangling_version)?;
+                    writeln!(
+                        out,
+                        "codegen_backend {} llvm_compatible_abi {:?}",
+                        root.codegen_backend, root.llvm_compatible_abi
+                    )?;
                     writeln!(
                         out,
                         "required_panic_strategy {:?} panic_in_drop_strategy {:?}",
@@ -955,6 +960,91 @@ fn print_item(
         Ok(())
     }
 
+    /// Describes the first difference between two metadata blobs in terms of the crate root
+    /// and the entries of its tables, or returns `None` if the blobs are identical.
+    pub(crate) fn first_difference(&self, other: &MetadataBlob) -> Option<String> {
+        let header_len = METADATA_HEADER.len();
+        if self[..header_len] != other[..header_len] {
+            return Some("metadata header".to_owned());
+        }
+        // The root position only differs if something before the root differs, skip it.
+        let start = header_len + 8;
+        let offset = match iter::zip(&self[start..], &other[start..]).position(|(a, b)| a != b) {
+            Some(index) => start + index,
+            None if self.len() == other.len() => return None,
+            None => self.len().min(other.len()),
+        };
+
+        let root = self.get_root();
+        let other_root = other.get_root();
+        let tables = root.tables.layouts();
+        let other_tables = other_root.tables.layouts();
+
+        for (table, other_table) in iter::zip(&tables, &other_tables) {
+            if !(table.position..table.entry(table.len).start).contains(&offset) {
+                continue;
+            }
+            if (table.position, table.width, table.len)
+                != (other_table.position, other_table.width, other_table.len)
+            {
+                return Some(format!(
+                    "layout of table `{}`: {} entries of {} bytes at byte {} vs {} entries of {} \
+                    bytes at byte {}",
+                    table.name,
+                    table.len,
+                    table.width,
+                    table.position,
+                    other_table.len,
+                    other_table.width,
+                    other_table.position,
+                ));
+            }
+            let index = (offset - table.position) / table.width;
+            return Some(format!("table `{}`, {}", table.name, self.describe_entry(table, index)));
+        }
+
+        if offset >= self.root_pos().get() {
+            return Some(format!("crate root at byte {offset}"));
+        }
+
+        // The difference is in a lazily encoded value, find the table entry referring to the
+        // closest value starting at or before the difference.
+        let mut closest = None;
+        for table in &tables {
+            for index in 0..table.len {
+                let Some(position) = (table.lazy_position)(&self[table.entry(index)]) else {
+                    continue;
+                };
+                if position <= offset && closest.is_none_or(|(closest, ..)| position > closest) {
+                    closest = Some((position, table, index));
+                }
+            }
+        }
+        Some(match closest {
+            Some((position, table, index)) => format!(
+                "value of table `{}`, {}, at byte {} (encoded at byte {position})",
+                table.name,
+                self.describe_entry(table, index),
+                offset,
+            ),
+            None => format!("byte {offset}"),
+        })
+    }
+
+    fn describe_entry(&self, table: &TableLayout, index: usize) -> String {
+        let def_keys = self.get_root().tables.def_keys;
+        if !table.by_def_index
+            || index >= def_keys.size()
+            || def_keys.get(self, DefIndex::from_usize(index)).is_none()
+        {
+            return format!("entry {index}");
+        }
+        let path = DefPath::make(LOCAL_CRATE, DefIndex::from_usize(index), |parent| {
+            def_keys.get(self, parent).unwrap().decode(self)
+        });
+        format!("entry {index} (`crate{}`)", path.to_string_no_crate_verbose())
+    }
+
     pub(crate) fn get_proc_macro_info(&self) -> Vec<ProcMacroKind> {
         self.get_root()
             .proc_macro_data
@@ -359,6 +359,7 @@ fn into_args(self) -> (DefId, SimplifiedType) {
     extern_crate => { cdata.extern_crate.map(|c| &*tcx.arena.alloc(c)) }
     is_no_builtins => { cdata.root.no_builtins }
     symbol_mangling_version => { cdata.root.symbol_mangling_version }
+    crate_codegen_backend => { (cdata.root.codegen_backend, cdata.root.llvm_compatible_abi) }
     specialization_enabled_in => { cdata.root.specialization_enabled_in }
     reachable_non_generics => {
         let reachable_non_generics = tcx
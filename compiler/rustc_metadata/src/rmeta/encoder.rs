@@ -752,6 +752,8 @@ macro_rules! stat {
                 panic_runtime: find_attr!(attrs, PanicRuntime),
                 profiler_runtime: find_attr!(attrs, ProfilerRuntime),
                 symbol_mangling_version: tcx.sess.opts.get_symbol_mangling_version(),
+                codegen_backend: Symbol::intern(tcx.sess.codegen_backend_name),
+                llvm_compatible_abi: tcx.sess.codegen_backend_llvm_compatible_abi,
 
                 crate_deps,
                 dylib_dependency_formats,
@@ -42,6 +42,7 @@
 use rustc_span::edition::Edition;
 use rustc_span::hygiene::{ExpnIndex, MacroKind, SyntaxContextKey};
 use rustc_span::{self, ExpnData, ExpnHash, ExpnId, Ident, Span, Symbol};
+use rustc_target::callconv::BackendLoweredAbi;
 use rustc_target::spec::{PanicStrategy, TargetTuple};
 use table::TableBuilder;
 
@@ -303,6 +304,8 @@ pub(crate) struct CrateRoot {
     panic_runtime: bool,
     profiler_runtime: bool,
     symbol_mangling_version: SymbolManglingVersion,
+    codegen_backend: Symbol,
+    llvm_compatible_abi: BackendLoweredAbi,
 
     specialization_enabled_in: bool,
 }
@@ -379,6 +382,55 @@ fn encode(&self, buf: &mut FileEncoder<'_>) -> LazyTables {
                 }
             }
         }
+
+        impl LazyTables {
+            fn layouts(&self) -> Vec<TableLayout> {
+                vec![
+                    $(TableLayout::new::<$IDX1, _, _>(stringify!($name1), &self.$name1),)+
+                    $(TableLayout::new::<$IDX2, _, _>(stringify!($name2), &self.$name2),)+
+                ]
+            }
+        }
+    }
+}
+
+/// Where a table is encoded in the metadata blob, used to attribute differences between two
+/// blobs to table entries.
+struct TableLayout {
+    name: &'static str,
+    position: usize,
+    width: usize,
+    len: usize,
+    by_def_index: bool,
+    /// Decodes the position of the lazily encoded value an entry refers to, if any.
+    lazy_position: fn(&[u8]) -> Option<usize>,
+}
+
+impl TableLayout {
+    fn new<I: 'static, T, const N: usize>(
+        name: &'static str,
+        table: &LazyTable<I, T>,
+    ) -> TableLayout
+    where
+        T: table::FixedSizeEncoding<ByteArray = [u8; N]>,
+    {
+        TableLayout {
+            name,
+            position: table.position.get(),
+            width: table.width,
+            len: table.len,
+            by_def_index: std::any::TypeId::of::<I>() == std::any::TypeId::of::<DefIndex>(),
+            lazy_position: |bytes| {
+                let mut fixed = [0u8; N];
+                fixed[..bytes.len()].copy_from_slice(bytes);
+                T::lazy_position(&fixed)
+            },
+        }
+    }
+
+    fn entry(&self, index: usize) -> std::ops::Range<usize> {
+        let start = self.position + self.width * index;
+        start..start + self.width
     }
 }
 
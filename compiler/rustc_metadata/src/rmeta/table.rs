@@ -57,6 +57,11 @@ pub(super) trait FixedSizeEncoding: IsDefault {
 
     fn from_bytes(b: &Self::ByteArray) -> Self;
     fn write_to_bytes(self, b: &mut Self::ByteArray);
+
+    /// For lazily encoded values, the position of the value in the metadata blob.
+    fn lazy_position(_b: &Self::ByteArray) -> Option<usize> {
+        None
+    }
 }
 
 impl FixedSizeEncoding for u64 {
@@ -348,6 +353,10 @@ impl<T> FixedSizeEncoding for Option<LazyValue<T>> {
             }
         }
     }
+
+    fn lazy_position(b: &[u8; 8]) -> Option<usize> {
+        Self::from_bytes(b).map(|lazy| lazy.position.get())
+    }
 }
 
 impl<T> LazyArray<T> {
@@ -414,6 +423,11 @@ impl<T> FixedSizeEncoding for LazyArray<T> {
         assert!(!self.is_default());
         self.write_to_bytes_impl(b)
     }
+
+    fn lazy_position(b: &[u8; 16]) -> Option<usize> {
+        let lazy = Self::from_bytes(b);
+        (!lazy.is_default()).then(|| lazy.position.get())
+    }
 }
 
 impl<T> FixedSizeEncoding for Option<LazyArray<T>> {
@@ -433,6 +447,10 @@ impl<T> FixedSizeEncoding for Option<LazyArray<T>> {
             Some(lazy) => lazy.write_to_bytes_impl(b),
         }
     }
+
+    fn lazy_position(b: &[u8; 16]) -> Option<usize> {
+        Self::from_bytes(b).map(|lazy| lazy.position.get())
+    }
 }
 
 /// Helper for constructing a table's serialization (also see `Table`).
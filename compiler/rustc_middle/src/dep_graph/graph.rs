@@ -17,6 +17,7 @@
 use rustc_macros::{Decodable, Encodable};
 use rustc_serialize::opaque::{FileEncodeResult, FileEncoder};
 use rustc_session::Session;
+use rustc_session::code_stats::MirOptRemark;
 use rustc_span::Symbol;
 use smallvec::SmallVec;
 use tracing::instrument;
@@ -52,6 +53,10 @@ pub enum QuerySideEffect {
     /// if we mark the query as green, as that query will have
     /// the side effect dep node as a dependency.
     CheckFeature { symbol: Symbol },
+    /// Stores the remark of a MIR pass made during query execution.
+    /// This remark will be emitted again if we mark the query as green,
+    /// as that query will have the side effect dep node as a dependency.
+    OptRemark(MirOptRemark),
 }
 
 #[derive(Clone)]
@@ -561,6 +566,22 @@ pub fn record_diagnostic<'tcx>(&self, tcx: TyCtxt<'tcx>, diagnostic: &DiagInner)
             })
         }
     }
+    /// This encodes the remark of a MIR pass as a side effect, like
+    /// [`DepGraph::record_diagnostic`].
+    #[inline]
+    pub fn record_opt_remark<'tcx>(&self, tcx: TyCtxt<'tcx>, remark: &MirOptRemark) {
+        if let Some(ref data) = self.data {
+            read_deps(|task_deps| match task_deps {
+                TaskDepsRef::EvalAlways | TaskDepsRef::Ignore => return,
+                TaskDepsRef::Forbid | TaskDepsRef::Allow(..) => {
+                    let dep_node_index =
+                        data.encode_side_effect(tcx, QuerySideEffect::OptRemark(remark.clone()));
+                    self.read_index(dep_node_index);
+                }
+            })
+        }
+    }
+
     /// This forces a side effect node green by running its side effect. `prev_index` would
     /// refer to a node created used `encode_side_effect` in the previous session.
     #[inline]
@@ -781,6 +802,9 @@ fn force_side_effect<'tcx>(&self, tcx: TyCtxt<'tcx>, prev_index: SerializedDepNo
                 QuerySideEffect::CheckFeature { symbol } => {
                     tcx.query_system.used_features.lock().insert(*symbol, dep_node_index);
                 }
+                QuerySideEffect::OptRemark(remark) => {
+                    crate::mir::remark::emit_untracked(tcx, remark.clone());
+                }
             }
 
             // This will just overwrite the same value for concurrent calls.
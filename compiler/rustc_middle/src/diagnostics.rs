@@ -200,3 +200,13 @@ pub struct DuplicateEiiImpls {
     )]
     pub help: (),
 }
+
+#[derive(Diagnostic)]
+#[diag("{$pass} ({$kind}): {$message}")]
+pub(crate) struct MirOptRemark<'a> {
+    #[primary_span]
+    pub span: Span,
+    pub pass: &'a str,
+    pub kind: &'static str,
+    pub message: String,
+}
@@ -91,6 +91,9 @@ fn clone(&self) -> Self { *self }
 
     hook alloc_self_profile_query_strings() -> ();
 
+    /// Prints the report of `-Zprint-generic-bloat`, once all codegen units have been codegened.
+    hook print_generic_bloat(limit: usize) -> ();
+
     /// Saves and writes the DepGraph to the file system.
     ///
     /// This function saves both the dep-graph and the query result cache,
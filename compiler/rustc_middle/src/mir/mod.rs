@@ -44,7 +44,9 @@
 pub mod graphviz;
 pub mod interpret;
 pub mod pretty;
+pub mod profile;
 mod query;
+pub mod remark;
 mod statement;
 mod syntax;
 mod terminator;
@@ -0,0 +1,50 @@
+//! Execution counts from the instrumentation profile of `-Cprofile-use`, as far as MIR
+//! optimizations can make use of them.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_macros::StableHash;
+
+/// The function counts of an indexed instrumentation profile (`.profdata`).
+///
+/// The counters of an IR-level profile are placed on the edges of the LLVM IR that LLVM
+/// instrumented, which MIR knows nothing about, so only their per-function aggregate is kept.
+#[derive(Debug, Default)]
+pub struct InstrProfile {
+    /// The largest counter of each function, keyed by the function's symbol name.
+    pub max_counts: FxHashMap<String, u64>,
+    /// The smallest count of the blocks that make up 99% of all executed blocks, as recorded in
+    /// the profile summary. Functions with a counter of at least this value contain hot code.
+    pub hot_count: u64,
+}
+
+impl InstrProfile {
+    /// Records the counters of a function named `name` in the profile. Records of functions with
+    /// internal linkage are named `<file>;<symbol>`, and are keyed by the symbol only.
+    pub fn add_function(&mut self, name: &str, counts: &[u64]) {
+        let name = name.rsplit_once(';').map_or(name, |(_, symbol)| symbol);
+        let max = counts.iter().copied().max().unwrap_or(0);
+        let entry = self.max_counts.entry(name.to_owned()).or_insert(0);
+        *entry = (*entry).max(max);
+    }
+
+    pub fn hotness(&self, symbol_name: &str) -> Option<Hotness> {
+        let &max = self.max_counts.get(symbol_name)?;
+        Some(if max == 0 {
+            Hotness::Cold
+        } else if max >= self.hot_count {
+            Hotness::Hot
+        } else {
+            Hotness::Normal
+        })
+    }
+}
+
+/// How often a function was executed while the profile was collected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, StableHash)]
+pub enum Hotness {
+    /// The function was never entered.
+    Cold,
+    Normal,
+    /// The function contains some of the most executed code of the program.
+    Hot,
+}
@@ -0,0 +1,39 @@
+//! Optimization remarks of MIR passes.
+//!
+//! The passes run in queries, so their remarks are side effects of those queries, and are
+//! emitted again when the query results are loaded from the incremental cache.
+
+use rustc_session::code_stats::{MirOptRemark, RemarkKind};
+
+use crate::diagnostics;
+use crate::ty::TyCtxt;
+
+impl<'tcx> TyCtxt<'tcx> {
+    /// Emits `remark`, and records it as a side effect of the current query.
+    pub fn emit_mir_opt_remark(self, remark: MirOptRemark) {
+        self.dep_graph.record_opt_remark(self, &remark);
+        // The note is part of the side effect, it must not be recorded on its own.
+        self.dep_graph.with_ignore(|| emit_untracked(self, remark));
+    }
+}
+
+/// Prints `remark` as a note, or with `-Zremark-dir` collects it in the session, from where
+/// codegen writes it next to LLVM's remark files.
+pub(crate) fn emit_untracked(tcx: TyCtxt<'_>, remark: MirOptRemark) {
+    if tcx.sess.opts.unstable_opts.remark_dir.is_some() {
+        tcx.sess.code_stats.record_opt_remark(remark);
+    } else {
+        tcx.dcx().emit_note(diagnostics::MirOptRemark {
+            span: remark.span,
+            pass: &remark.remark.pass,
+            kind: match remark.remark.kind {
+                RemarkKind::Passed => "success",
+                RemarkKind::Missed | RemarkKind::Failure => "missed",
+                RemarkKind::Analysis
+                | RemarkKind::AnalysisFPCommute
+                | RemarkKind::AnalysisAliasing => "analysis",
+            },
+            message: remark.remark.message(),
+        });
+    }
+}
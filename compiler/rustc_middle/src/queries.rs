@@ -75,6 +75,7 @@
 use rustc_session::config::{EntryFnType, OptLevel, OutputFilenames, SymbolManglingVersion};
 use rustc_span::def_id::{LOCAL_CRATE, ModId};
 use rustc_span::{DUMMY_SP, LocalExpnId, Span, Spanned, Symbol};
+use rustc_target::callconv::BackendLoweredAbi;
 use rustc_target::spec::PanicStrategy;
 
 use crate::infer::canonical::{self, Canonical};
@@ -741,6 +742,21 @@
         arena_cache
     }
 
+    /// The function counts of the `-Cprofile-use` profile, or `None` if there is no profile or
+    /// it could not be read.
+    query instr_profile(_: ()) -> Option<&'tcx mir::profile::InstrProfile> {
+        desc { "reading the instrumentation profile" }
+        arena_cache
+        eval_always
+        no_hash
+    }
+
+    /// How often `key` was executed according to the `-Cprofile-use` profile, or `None` if the
+    /// profile doesn't know about it.
+    query instance_hotness(key: ty::Instance<'tcx>) -> Option<mir::profile::Hotness> {
+        desc { "looking up the profile counts of `{}`", key }
+    }
+
     /// The `DefId` is the `DefId` of the containing MIR body. Promoteds do not have their own
     /// `DefId`. This function returns all promoteds in the specified body. The body references
     /// promoteds by the `DefId` and the `mir::Promoted` index. This is necessary, because
@@ -1434,6 +1450,13 @@
         cache_on_disk
     }
 
+    /// The symbol names that the non-generic instance `key` has with and without the suffix of
+    /// the local crate. Used to look `key` up in the `-Cprofile-use` profile, which cannot use
+    /// `symbol_name` because the suffix depends on the optimized MIR of `key`.
+    query profile_symbol_names(key: ty::Instance<'tcx>) -> (ty::SymbolName<'tcx>, ty::SymbolName<'tcx>) {
+        desc { "computing the possible symbols for `{}`", key }
+    }
+
     query def_kind(def_id: DefId) -> DefKind {
         desc { "looking up definition kind of `{}`", tcx.def_path_str(def_id) }
         separate_provide_extern
@@ -1861,6 +1884,13 @@
         desc { "getting a crate's symbol mangling version" }
         separate_provide_extern
     }
+    /// The name of the codegen backend that codegened an upstream crate, and the parts of the
+    /// function ABI that it lowers the same way as LLVM. The local crate uses
+    /// `Session::codegen_backend_name` instead.
+    query crate_codegen_backend(_: CrateNum) -> (Symbol, BackendLoweredAbi) {
+        desc { "getting a crate's codegen backend" }
+        separate_provide_extern
+    }
 
     query extern_crate(def_id: CrateNum) -> Option<&'tcx ExternCrate> {
         eval_always
@@ -172,6 +172,8 @@ impl Erasable for $ty {
     // tidy-alphabetical-start
     (&'_ ty::CrateInherentImpls, Result<(), ErrorGuaranteed>),
     (),
+    (rustc_middle::ty::SymbolName<'_>, rustc_middle::ty::SymbolName<'_>),
+    (rustc_span::Symbol, rustc_target::callconv::BackendLoweredAbi),
     (traits::solve::QueryResult<'_>, &'_ traits::solve::inspect::Probe<TyCtxt<'_>>),
     Option<&'_ OsStr>,
     Option<&'_ [rustc_hir::PreciseCapturingArgKind<rustc_span::Symbol, rustc_span::Symbol>]>,
@@ -185,6 +187,7 @@ impl Erasable for $ty {
     Option<rustc_hir::DefaultBodyStability>,
     Option<rustc_hir::Stability>,
     Option<rustc_middle::middle::stability::DeprecationEntry>,
+    Option<rustc_middle::mir::profile::Hotness>,
     Option<rustc_middle::ty::AsyncDestructor>,
     Option<rustc_middle::ty::Destructor>,
     Option<rustc_middle::ty::IntrinsicDef>,
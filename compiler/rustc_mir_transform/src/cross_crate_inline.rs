@@ -3,13 +3,16 @@
 use rustc_hir::def_id::LocalDefId;
 use rustc_hir::{self as hir, find_attr};
 use rustc_middle::bug;
+use rustc_middle::mir::profile::Hotness;
 use rustc_middle::mir::visit::Visitor;
 use rustc_middle::mir::*;
 use rustc_middle::query::Providers;
 use rustc_middle::ty::TyCtxt;
+use rustc_session::code_stats::RemarkKind;
 use rustc_session::config::{InliningThreshold, OptLevel};
 
-use crate::{inline, pass_manager as pm};
+use crate::remarks::{self, Remark};
+use crate::{inline, pass_manager as pm, pgo};
 
 pub(super) fn provide(providers: &mut Providers) {
     providers.cross_crate_inlinable = cross_crate_inlinable;
@@ -93,6 +96,13 @@ fn cross_crate_inlinable(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
         return false;
     }
 
+    // Functions that were never executed while profiling are not worth inlining anywhere, and
+    // hot ones are worth inlining even if they call other functions.
+    let hotness = pgo::item_hotness(tcx, def_id.to_def_id());
+    if hotness == Some(Hotness::Cold) {
+        return false;
+    }
+
     let threshold = match tcx.sess.opts.unstable_opts.cross_crate_inline_threshold {
         InliningThreshold::Always => return true,
         InliningThreshold::Sometimes(threshold) => threshold,
@@ -103,10 +113,36 @@ fn cross_crate_inlinable(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
     let mut checker =
         CostChecker { tcx, callee_body: mir, calls: 0, statements: 0, landing_pads: 0, resumes: 0 };
     checker.visit_body(mir);
-    checker.calls == 0
+    let inlinable = (checker.calls == 0 || hotness == Some(Hotness::Hot))
         && checker.resumes == 0
         && checker.landing_pads == 0
-        && checker.statements <= threshold
+        && checker.statements <= threshold;
+
+    if remarks::enabled(tcx, remarks::CROSS_CRATE_INLINE) {
+        let (kind, name, verdict) = if inlinable {
+            (RemarkKind::Passed, "CrossCrateInlinable", " is inlinable across crates")
+        } else {
+            (RemarkKind::Missed, "NotCrossCrateInlinable", " is not inlinable across crates")
+        };
+        let def_id = def_id.to_def_id();
+        Remark::new(tcx, remarks::CROSS_CRATE_INLINE, kind, name, def_id, tcx.def_span(def_id))
+            .function_arg("Callee", def_id)
+            .text(verdict)
+            .text(" (statements=")
+            .arg("Statements", checker.statements)
+            .text(", threshold=")
+            .arg("Threshold", threshold)
+            .text(", calls=")
+            .arg("Calls", checker.calls)
+            .text(", landing pads=")
+            .arg("LandingPads", checker.landing_pads)
+            .text(", resumes=")
+            .arg("Resumes", checker.resumes)
+            .text(")")
+            .emit();
+    }
+
+    inlinable
 }
 
 // The threshold that CostChecker computes is balancing the desire to make more things
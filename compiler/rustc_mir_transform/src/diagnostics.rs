@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use rustc_errors::codes::*;
 use rustc_errors::{
     Applicability, Diag, DiagCtxtHandle, DiagSymbolList, Diagnostic, EmissionGuarantee, Level,
@@ -417,3 +419,17 @@ pub(crate) struct ConstructionOfTyWithMutRestrictedField {
     pub descr: &'static str,
     pub restriction_path: String,
 }
+
+#[derive(Diagnostic)]
+#[diag("failed to read the profile `{$path}` for MIR optimizations: {$error}")]
+pub(crate) struct ProfileUseRead<'a> {
+    pub path: &'a Path,
+    pub error: std::io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag("the profile `{$path}` is not used for MIR optimizations because {$reason}")]
+pub(crate) struct ProfileUseParse<'a> {
+    pub path: &'a Path,
+    pub reason: &'static str,
+}
@@ -118,11 +118,13 @@
 use rustc_middle::ty::layout::HasTypingEnv;
 use rustc_middle::ty::{self, Ty, TyCtxt, TypeVisitableExt, Unnormalized};
 use rustc_mir_dataflow::{Analysis, ResultsCursor};
+use rustc_session::code_stats::RemarkKind;
 use rustc_span::DUMMY_SP;
 use smallvec::SmallVec;
 use tracing::{debug, instrument, trace};
 
 use crate::PassPolicy;
+use crate::remarks::{self, Remark};
 use crate::ssa::{MaybeUninitializedLocals, SsaLocals};
 
 pub(super) struct GVN;
@@ -134,7 +136,8 @@ fn policy(&self, sess: &rustc_session::Session) -> PassPolicy {
 
     #[instrument(level = "trace", skip(self, tcx, body))]
     fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
-        debug!(def_id = ?body.source.def_id());
+        let def_id = body.source.def_id();
+        debug!(?def_id);
 
         let typing_env = body.typing_env(tcx);
         let ssa = SsaLocals::new(tcx, body, typing_env);
@@ -156,6 +159,17 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
             state.visit_basic_block_data(bb, data);
         }
 
+        for (location, old_rvalue, new_rvalue) in state.replacements.take().into_iter().flatten() {
+            let span = body.source_info(location).span;
+            Remark::new(tcx, remarks::GVN, RemarkKind::Passed, "Replaced", def_id, span)
+                .text("`")
+                .arg("Value", old_rvalue)
+                .text("` replaced by the known value `")
+                .arg("Replacement", new_rvalue)
+                .text("`")
+                .emit();
+        }
+
         // When emitting storage statements, we want to retain the reused locals' storage statements,
         // as this enables better optimizations. For each local use location, we mark it for storage removal
         // only if it might be uninitialized at that point.
@@ -399,6 +413,8 @@ struct VnState<'body, 'a, 'tcx> {
     dominators: Dominators<BasicBlock>,
     reused_locals: DenseBitSet<Local>,
     arena: &'a DroplessArena,
+    /// The rvalues that were replaced, with their replacement, if `-Cremark` asks for them.
+    replacements: Option<Vec<(Location, String, String)>>,
 }
 
 impl<'body, 'a, 'tcx> VnState<'body, 'a, 'tcx> {
@@ -431,6 +447,7 @@ fn new(
             dominators,
             reused_locals: DenseBitSet::new_empty(local_decls.len()),
             arena,
+            replacements: remarks::enabled(tcx, remarks::GVN).then(Vec::new),
         }
     }
 
@@ -2101,6 +2118,7 @@ fn visit_assign(
 
         let value = self.simplify_rvalue(lhs, rvalue, location);
         if let Some(value) = value {
+            let old_rvalue = self.replacements.is_some().then(|| format!("{rvalue:?}"));
             // FIXME: Is it correct to make these retagging assignments?
             if let Some(const_) = self.try_as_constant(value) {
                 *rvalue = Rvalue::Use(Operand::Constant(Box::new(const_)), WithRetag::Yes);
@@ -2110,6 +2128,14 @@ fn visit_assign(
                 *rvalue = Rvalue::Use(Operand::Copy(place), WithRetag::Yes);
                 self.reused_locals.insert(place.local);
             }
+            if let Some(replacements) = &mut self.replacements
+                && let Some(old_rvalue) = old_rvalue
+            {
+                let new_rvalue = format!("{rvalue:?}");
+                if new_rvalue != old_rvalue {
+                    replacements.push((location, old_rvalue, new_rvalue));
+                }
+            }
         }
 
         if let Some(local) = lhs.as_local()
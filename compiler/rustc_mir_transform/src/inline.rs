@@ -4,6 +4,7 @@
 use std::{debug_assert_matches, iter};
 
 use rustc_abi::{ExternAbi, FieldIdx};
+use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::thin_vec::ThinVec;
 use rustc_hir::attrs::lang_items::LangItem;
 use rustc_hir::attrs::{InlineAttr, OptimizeAttr};
@@ -12,20 +13,23 @@
 use rustc_index::Idx;
 use rustc_index::bit_set::DenseBitSet;
 use rustc_middle::bug;
-use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrs;
+use rustc_middle::middle::codegen_fn_attrs::{CodegenFnAttrFlags, CodegenFnAttrs};
+use rustc_middle::mir::profile::Hotness;
 use rustc_middle::mir::visit::*;
 use rustc_middle::mir::*;
 use rustc_middle::ty::{
     self, Instance, InstanceKind, ShimKind, Ty, TyCtxt, TypeFlags, TypeVisitableExt, Unnormalized,
 };
+use rustc_session::code_stats::RemarkKind;
 use rustc_session::config::{DebugInfo, OptLevel};
 use rustc_span::Spanned;
 use tracing::{debug, instrument, trace, trace_span};
 
 use crate::cost_checker::{CostChecker, is_call_like};
+use crate::remarks::{self, Remark};
 use crate::simplify::{UsedInStmtLocals, simplify_cfg};
 use crate::validate::validate_types;
-use crate::{PassPolicy, check_inline, util};
+use crate::{PassPolicy, check_inline, pgo, util};
 
 pub(crate) mod cycle;
 
@@ -62,7 +66,7 @@ fn policy(&self, sess: &rustc_session::Session) -> PassPolicy {
     fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
         let span = trace_span!("inline", body = %tcx.def_path_str(body.source.def_id()));
         let _guard = span.enter();
-        if inline::<NormalInliner<'tcx>>(tcx, body) {
+        if inline(tcx, body, NormalInliner::new) {
             debug!("running simplify cfg on {:?}", body.source);
             simplify_cfg(tcx, body);
         }
@@ -86,21 +90,60 @@ fn policy(&self, _sess: &rustc_session::Session) -> PassPolicy {
     fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
         let span = trace_span!("force_inline", body = %tcx.def_path_str(body.source.def_id()));
         let _guard = span.enter();
-        if inline::<ForceInliner<'tcx>>(tcx, body) {
+        if inline(tcx, body, ForceInliner::new) {
             debug!("running simplify cfg on {:?}", body.source);
             simplify_cfg(tcx, body);
         }
     }
 }
 
-trait Inliner<'tcx> {
-    fn new(tcx: TyCtxt<'tcx>, def_id: DefId, body: &Body<'tcx>) -> Self;
+/// Inlines callees from the same codegen unit into a monomorphized body.
+///
+/// This is not part of the regular MIR pipeline, codegen backends run it through
+/// [`crate::optimize_mono_body`].
+pub(crate) struct MonoInline<'a, 'tcx> {
+    pub(crate) codegen_unit: &'a FxHashSet<Instance<'tcx>>,
+    pub(crate) threshold: usize,
+}
+
+impl<'tcx> crate::MirPass<'tcx> for MonoInline<'_, 'tcx> {
+    fn policy(&self, _sess: &rustc_session::Session) -> PassPolicy {
+        // Only enabled when a backend asks for it.
+        PassPolicy::optimization(false)
+    }
+
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        let span = trace_span!("mono_inline", body = %tcx.def_path_str(body.source.def_id()));
+        let _guard = span.enter();
+        let new_inliner = |tcx, def_id, _: &Body<'tcx>| MonoInliner {
+            tcx,
+            def_id,
+            codegen_unit: self.codegen_unit,
+            threshold: self.threshold,
+            history: Vec::new(),
+            top_down_counter: 0,
+            changed: false,
+        };
+        if inline(tcx, body, new_inliner) {
+            debug!("running simplify cfg on {:?}", body.source);
+            simplify_cfg(tcx, body);
+        }
+    }
+}
 
+trait Inliner<'tcx> {
     fn tcx(&self) -> TyCtxt<'tcx>;
     fn typing_env(&self) -> ty::TypingEnv<'tcx>;
     fn history(&self) -> &[DefId];
     fn caller_def_id(&self) -> DefId;
 
+    /// The instances codegened in the same codegen unit as the caller, when inlining into a
+    /// monomorphized body. Only these may be inlined, but they need not be exported and cannot
+    /// cause query cycles.
+    fn codegen_unit(&self) -> Option<&FxHashSet<Instance<'tcx>>> {
+        None
+    }
+
     /// Has the caller body been changed?
     fn changed(self) -> bool;
 
@@ -150,11 +193,13 @@ struct ForceInliner<'tcx> {
     changed: bool,
 }
 
-impl<'tcx> Inliner<'tcx> for ForceInliner<'tcx> {
+impl<'tcx> ForceInliner<'tcx> {
     fn new(tcx: TyCtxt<'tcx>, def_id: DefId, body: &Body<'tcx>) -> Self {
         Self { tcx, typing_env: body.typing_env(tcx), def_id, history: Vec::new(), changed: false }
     }
+}
 
+impl<'tcx> Inliner<'tcx> for ForceInliner<'tcx> {
     fn tcx(&self) -> TyCtxt<'tcx> {
         self.tcx
     }
@@ -277,15 +322,13 @@ struct NormalInliner<'tcx> {
     /// Indicates that the caller is #[inline] and just calls another function,
     /// and thus we can inline less into it as it'll be inlined itself.
     caller_is_inline_forwarder: bool,
+    /// How often the caller was executed according to `-Cprofile-use`.
+    caller_hotness: Option<Hotness>,
+    /// Blocks of the caller that were not executed according to `-Cprofile-use`.
+    cold_blocks: Option<DenseBitSet<BasicBlock>>,
 }
 
 impl<'tcx> NormalInliner<'tcx> {
-    fn past_depth_limit(&self) -> bool {
-        self.history.len() > HISTORY_DEPTH_LIMIT || self.top_down_counter > TOP_DOWN_DEPTH_LIMIT
-    }
-}
-
-impl<'tcx> Inliner<'tcx> for NormalInliner<'tcx> {
     fn new(tcx: TyCtxt<'tcx>, def_id: DefId, body: &Body<'tcx>) -> Self {
         let typing_env = body.typing_env(tcx);
         let codegen_fn_attrs = tcx.codegen_fn_attrs(def_id);
@@ -301,9 +344,43 @@ fn new(tcx: TyCtxt<'tcx>, def_id: DefId, body: &Body<'tcx>) -> Self {
                 codegen_fn_attrs.inline,
                 InlineAttr::Hint | InlineAttr::Always | InlineAttr::Force { .. }
             ) && body_is_forwarder(body),
+            caller_hotness: pgo::item_hotness(tcx, def_id),
+            cold_blocks: tcx
+                .sess
+                .opts
+                .cg
+                .profile_use
+                .is_some()
+                .then(|| pgo::cold_blocks(tcx, typing_env, body)),
+        }
+    }
+
+    /// How often `callsite` was executed according to `-Cprofile-use`. Blocks that come from
+    /// inlined bodies are only judged by their callee.
+    fn callsite_hotness(&self, callsite: &CallSite<'tcx>) -> Option<Hotness> {
+        if self.caller_hotness == Some(Hotness::Cold)
+            || self.cold_blocks.as_ref().is_some_and(|cold_blocks| {
+                callsite.block.index() < cold_blocks.domain_size()
+                    && cold_blocks.contains(callsite.block)
+            })
+        {
+            return Some(Hotness::Cold);
         }
+        match pgo::hotness(self.tcx, callsite.callee)? {
+            Hotness::Cold => Some(Hotness::Cold),
+            // A hot callee may still be called from a cold part of the program, so the call is
+            // only hot if the caller is known to be hot as well.
+            Hotness::Hot if self.caller_hotness == Some(Hotness::Hot) => Some(Hotness::Hot),
+            Hotness::Hot | Hotness::Normal => Some(Hotness::Normal),
+        }
+    }
+
+    fn past_depth_limit(&self) -> bool {
+        self.history.len() > HISTORY_DEPTH_LIMIT || self.top_down_counter > TOP_DOWN_DEPTH_LIMIT
     }
+}
 
+impl<'tcx> Inliner<'tcx> for NormalInliner<'tcx> {
     fn tcx(&self) -> TyCtxt<'tcx> {
         self.tcx
     }
@@ -367,87 +444,146 @@ fn check_callee_mir_body(
             return Err("Not inlining multi-block body as we're past a depth limit");
         }
 
-        let mut threshold = if self.caller_is_inline_forwarder || self.past_depth_limit() {
+        let hotness = self.callsite_hotness(callsite);
+        // Leave calls that were never executed out of line, so that LLVM sees small hot paths.
+        if hotness == Some(Hotness::Cold)
+            && !matches!(callee_attrs.inline, InlineAttr::Always | InlineAttr::Force { .. })
+        {
+            return Err("call site was not executed while profiling");
+        }
+
+        let threshold = if self.caller_is_inline_forwarder || self.past_depth_limit() {
             tcx.sess.opts.unstable_opts.inline_mir_forwarder_threshold.unwrap_or(30)
+        } else if hotness == Some(Hotness::Hot) {
+            tcx.sess.opts.unstable_opts.inline_mir_hot_threshold.unwrap_or(200)
         } else if tcx.cross_crate_inlinable(callsite.callee.def_id()) {
             tcx.sess.opts.unstable_opts.inline_mir_hint_threshold.unwrap_or(100)
         } else {
             tcx.sess.opts.unstable_opts.inline_mir_threshold.unwrap_or(50)
         };
 
-        // Give a bonus functions with a small number of blocks,
-        // We normally have two or three blocks for even
-        // very small functions.
-        if callee_body.basic_blocks.len() <= 3 {
-            threshold += threshold / 4;
+        check_inline_cost(self, callsite, callee_body, callee_attrs, threshold)
+    }
+
+    fn on_inline_success(
+        &mut self,
+        callsite: &CallSite<'tcx>,
+        caller_body: &mut Body<'tcx>,
+        new_blocks: std::ops::Range<BasicBlock>,
+    ) {
+        self.changed = true;
+
+        let new_calls_count = new_blocks
+            .clone()
+            .filter(|&bb| is_call_like(caller_body.basic_blocks[bb].terminator()))
+            .count();
+        if new_calls_count > 1 {
+            self.top_down_counter += 1;
         }
-        debug!("    final inline threshold = {}", threshold);
 
-        // FIXME: Give a bonus to functions with only a single caller
+        self.history.push(callsite.callee.def_id());
+        process_blocks(self, caller_body, new_blocks);
+        self.history.pop();
 
-        let mut checker =
-            CostChecker::new(tcx, self.typing_env(), Some(callsite.callee), callee_body);
+        if self.history.is_empty() {
+            self.top_down_counter = 0;
+        }
+    }
 
-        checker.add_function_level_costs();
+    fn on_inline_failure(&self, _: &CallSite<'tcx>, _: &'static str) {}
+}
 
-        // Traverse the MIR manually so we can account for the effects of inlining on the CFG.
-        let mut work_list = vec![START_BLOCK];
-        let mut visited = DenseBitSet::new_empty(callee_body.basic_blocks.len());
-        while let Some(bb) = work_list.pop() {
-            if !visited.insert(bb.index()) {
-                continue;
-            }
+struct MonoInliner<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// `DefId` of caller.
+    def_id: DefId,
+    /// Instances codegened alongside the caller.
+    codegen_unit: &'a FxHashSet<Instance<'tcx>>,
+    /// Cost threshold for callees without `#[inline]`, doubled for callees with it.
+    threshold: usize,
+    /// Stack of inlined instances, see `NormalInliner::history`.
+    history: Vec<DefId>,
+    /// How many (multi-call) callsites have we inlined for the top-level call?
+    top_down_counter: usize,
+    /// Indicates that the caller body has been modified.
+    changed: bool,
+}
 
-            let blk = &callee_body.basic_blocks[bb];
-            checker.visit_basic_block_data(bb, blk);
+impl<'tcx> MonoInliner<'_, 'tcx> {
+    fn past_depth_limit(&self) -> bool {
+        self.history.len() > HISTORY_DEPTH_LIMIT || self.top_down_counter > TOP_DOWN_DEPTH_LIMIT
+    }
+}
 
-            let term = blk.terminator();
-            let caller_attrs = tcx.codegen_fn_attrs(self.caller_def_id());
-            if let TerminatorKind::Drop { ref place, target, unwind, replace: _, drop: _ } =
-                term.kind
-            {
-                work_list.push(target);
-
-                // If the place doesn't actually need dropping, treat it like a regular goto.
-                let ty = callsite.callee.instantiate_mir(
-                    tcx,
-                    ty::EarlyBinder::bind(tcx, place.ty(callee_body, tcx).ty),
-                );
-                if ty.needs_drop(tcx, self.typing_env())
-                    && let UnwindAction::Cleanup(unwind) = unwind
-                {
-                    work_list.push(unwind);
-                }
-            } else if callee_attrs.instruction_set != caller_attrs.instruction_set
-                && matches!(term.kind, TerminatorKind::InlineAsm { .. })
-            {
-                // During the attribute checking stage we allow a callee with no
-                // instruction_set assigned to count as compatible with a function that does
-                // assign one. However, during this stage we require an exact match when any
-                // inline-asm is detected. LLVM will still possibly do an inline later on
-                // if the no-attribute function ends up with the same instruction set anyway.
-                return Err("cannot move inline-asm across instruction sets");
-            } else if let TerminatorKind::TailCall { .. } = term.kind {
-                // FIXME(explicit_tail_calls): figure out how exactly functions containing tail
-                // calls can be inlined (and if they even should)
-                return Err("can't inline functions with tail calls");
-            } else {
-                work_list.extend(term.successors())
-            }
-        }
+impl<'tcx> Inliner<'tcx> for MonoInliner<'_, 'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
 
-        // N.B. We still apply our cost threshold to #[inline(always)] functions.
-        // That attribute is often applied to very large functions that exceed LLVM's (very
-        // generous) inlining threshold. Such functions are very poor MIR inlining candidates.
-        // Always inlining #[inline(always)] functions in MIR, on net, slows down the compiler.
-        let cost = checker.cost();
-        if cost <= threshold {
-            debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
-            Ok(())
+    fn caller_def_id(&self) -> DefId {
+        self.def_id
+    }
+
+    fn typing_env(&self) -> ty::TypingEnv<'tcx> {
+        ty::TypingEnv::fully_monomorphized()
+    }
+
+    fn history(&self) -> &[DefId] {
+        &self.history
+    }
+
+    fn codegen_unit(&self) -> Option<&FxHashSet<Instance<'tcx>>> {
+        Some(self.codegen_unit)
+    }
+
+    fn changed(self) -> bool {
+        self.changed
+    }
+
+    fn should_inline_for_callee(&self, _: DefId) -> bool {
+        true
+    }
+
+    fn check_codegen_attributes_extra(
+        &self,
+        callee_attrs: &CodegenFnAttrs,
+    ) -> Result<(), &'static str> {
+        if callee_attrs.flags.contains(CodegenFnAttrFlags::NAKED) {
+            // Naked functions are never exported for inlining, but may share a codegen unit.
+            Err("naked function")
+        } else if self.past_depth_limit() && matches!(callee_attrs.inline, InlineAttr::None) {
+            Err("Past depth limit so not inspecting unmarked callee")
         } else {
-            debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
-            Err("cost above threshold")
+            Ok(())
+        }
+    }
+
+    fn check_caller_mir_body(&self, body: &Body<'tcx>) -> bool {
+        // The layout of a coroutine has already been computed from its optimized MIR.
+        body.coroutine.is_none()
+    }
+
+    #[instrument(level = "debug", skip(self, callee_body))]
+    fn check_callee_mir_body(
+        &self,
+        callsite: &CallSite<'tcx>,
+        callee_body: &Body<'tcx>,
+        callee_attrs: &CodegenFnAttrs,
+    ) -> Result<(), &'static str> {
+        if let Some(_) = callee_body.tainted_by_errors {
+            return Err("body has errors");
+        }
+
+        if self.past_depth_limit() && callee_body.basic_blocks.len() > 1 {
+            return Err("Not inlining multi-block body as we're past a depth limit");
         }
+
+        let threshold = match callee_attrs.inline {
+            InlineAttr::Hint | InlineAttr::Always | InlineAttr::Force { .. } => self.threshold * 2,
+            InlineAttr::None | InlineAttr::Never => self.threshold,
+        };
+
+        check_inline_cost(self, callsite, callee_body, callee_attrs, threshold)
     }
 
     fn on_inline_success(
@@ -478,7 +614,102 @@ fn on_inline_success(
     fn on_inline_failure(&self, _: &CallSite<'tcx>, _: &'static str) {}
 }
 
-fn inline<'tcx, T: Inliner<'tcx>>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) -> bool {
+/// Returns an error if the estimated cost of the callee body exceeds `threshold`.
+fn check_inline_cost<'tcx, I: Inliner<'tcx>>(
+    inliner: &I,
+    callsite: &CallSite<'tcx>,
+    callee_body: &Body<'tcx>,
+    callee_attrs: &CodegenFnAttrs,
+    mut threshold: usize,
+) -> Result<(), &'static str> {
+    let tcx = inliner.tcx();
+
+    // Give a bonus functions with a small number of blocks,
+    // We normally have two or three blocks for even
+    // very small functions.
+    if callee_body.basic_blocks.len() <= 3 {
+        threshold += threshold / 4;
+    }
+    debug!("    final inline threshold = {}", threshold);
+
+    // FIXME: Give a bonus to functions with only a single caller
+
+    let mut checker =
+        CostChecker::new(tcx, inliner.typing_env(), Some(callsite.callee), callee_body);
+
+    checker.add_function_level_costs();
+
+    // Traverse the MIR manually so we can account for the effects of inlining on the CFG.
+    let mut work_list = vec![START_BLOCK];
+    let mut visited = DenseBitSet::new_empty(callee_body.basic_blocks.len());
+    while let Some(bb) = work_list.pop() {
+        if !visited.insert(bb.index()) {
+            continue;
+        }
+
+        let blk = &callee_body.basic_blocks[bb];
+        checker.visit_basic_block_data(bb, blk);
+
+        let term = blk.terminator();
+        let caller_attrs = tcx.codegen_fn_attrs(inliner.caller_def_id());
+        if let TerminatorKind::Drop { ref place, target, unwind, replace: _, drop: _ } = term.kind {
+            work_list.push(target);
+
+            // If the place doesn't actually need dropping, treat it like a regular goto.
+            let ty = callsite
+                .callee
+                .instantiate_mir(tcx, ty::EarlyBinder::bind(tcx, place.ty(callee_body, tcx).ty));
+            if ty.needs_drop(tcx, inliner.typing_env())
+                && let UnwindAction::Cleanup(unwind) = unwind
+            {
+                work_list.push(unwind);
+            }
+        } else if callee_attrs.instruction_set != caller_attrs.instruction_set
+            && matches!(term.kind, TerminatorKind::InlineAsm { .. })
+        {
+            // During the attribute checking stage we allow a callee with no
+            // instruction_set assigned to count as compatible with a function that does
+            // assign one. However, during this stage we require an exact match when any
+            // inline-asm is detected. LLVM will still possibly do an inline later on
+            // if the no-attribute function ends up with the same instruction set anyway.
+            return Err("cannot move inline-asm across instruction sets");
+        } else if let TerminatorKind::TailCall { .. } = term.kind {
+            // FIXME(explicit_tail_calls): figure out how exactly functions containing tail
+            // calls can be inlined (and if they even should)
+            return Err("can't inline functions with tail calls");
+        } else {
+            work_list.extend(term.successors())
+        }
+    }
+
+    // N.B. We still apply our cost threshold to #[inline(always)] functions.
+    // That attribute is often applied to very large functions that exceed LLVM's (very
+    // generous) inlining threshold. Such functions are very poor MIR inlining candidates.
+    // Always inlining #[inline(always)] functions in MIR, on net, slows down the compiler.
+    let cost = checker.cost();
+    if remarks::enabled(tcx, remarks::INLINE) {
+        inline_remark(inliner, callsite, RemarkKind::Analysis, "InlineCost")
+            .text(" has an inlining cost of ")
+            .arg("Cost", cost)
+            .text(" (threshold=")
+            .arg("Threshold", threshold)
+            .text(")")
+            .emit();
+    }
+    if cost <= threshold {
+        debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
+        Ok(())
+    } else {
+        debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
+        Err("cost above threshold")
+    }
+}
+
+fn inline<'tcx, I: Inliner<'tcx>>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+    new_inliner: impl FnOnce(TyCtxt<'tcx>, DefId, &Body<'tcx>) -> I,
+) -> bool {
     let def_id = body.source.def_id();
 
     // Only do inlining into fn bodies.
@@ -486,7 +717,7 @@ fn inline<'tcx, T: Inliner<'tcx>>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) -> b
         return false;
     }
 
-    let mut inliner = T::new(tcx, def_id, body);
+    let mut inliner = new_inliner(tcx, def_id, body);
     if !inliner.check_caller_mir_body(body) {
         return false;
     }
@@ -517,16 +748,48 @@ fn process_blocks<'tcx, I: Inliner<'tcx>>(
         match try_inlining(inliner, caller_body, &callsite) {
             Err(reason) => {
                 debug!("not-inlined {} [{}]", callsite.callee, reason);
+                if remarks::enabled(inliner.tcx(), remarks::INLINE) {
+                    inline_remark(inliner, &callsite, RemarkKind::Missed, "NotInlined")
+                        .text(" not inlined into ")
+                        .function_arg("Caller", inliner.caller_def_id())
+                        .text(" because ")
+                        .arg("Reason", reason)
+                        .emit();
+                }
                 inliner.on_inline_failure(&callsite, reason);
             }
             Ok(new_blocks) => {
                 debug!("inlined {}", callsite.callee);
+                if remarks::enabled(inliner.tcx(), remarks::INLINE) {
+                    inline_remark(inliner, &callsite, RemarkKind::Passed, "Inlined")
+                        .text(" inlined into ")
+                        .function_arg("Caller", inliner.caller_def_id())
+                        .emit();
+                }
                 inliner.on_inline_success(&callsite, caller_body, new_blocks);
             }
         }
     }
 }
 
+/// Starts a remark about `callsite`, whose message begins with the callee.
+fn inline_remark<'tcx, I: Inliner<'tcx>>(
+    inliner: &I,
+    callsite: &CallSite<'tcx>,
+    kind: RemarkKind,
+    name: &str,
+) -> Remark<'tcx> {
+    Remark::new(
+        inliner.tcx(),
+        remarks::INLINE,
+        kind,
+        name,
+        inliner.caller_def_id(),
+        callsite.source_info.span,
+    )
+    .function_arg("Callee", callsite.callee.def_id())
+}
+
 fn resolve_callsite<'tcx, I: Inliner<'tcx>>(
     inliner: &I,
     caller_body: &Body<'tcx>,
@@ -605,6 +868,11 @@ fn try_inlining<'tcx, I: Inliner<'tcx>>(
     callsite: &CallSite<'tcx>,
 ) -> Result<std::ops::Range<BasicBlock>, &'static str> {
     let tcx = inliner.tcx();
+    if let Some(codegen_unit) = inliner.codegen_unit()
+        && !codegen_unit.contains(&callsite.callee)
+    {
+        return Err("callee is not codegened in the same codegen unit");
+    }
     check_mir_is_available(inliner, caller_body, callsite.callee)?;
 
     let callee_attrs = tcx.codegen_instance_attrs(callsite.callee.def);
@@ -775,6 +1043,12 @@ fn check_mir_is_available<'tcx, I: Inliner<'tcx>>(
         return Ok(());
     }
 
+    if inliner.codegen_unit().is_some() {
+        // After monomorphization all callee MIR is already optimized, so there is no query that
+        // could cycle back to the caller.
+        return Ok(());
+    }
+
     if let Some(callee_def_id) = callee_def_id.as_local()
         && !inliner.tcx().is_lang_item(inliner.tcx().parent(caller_def_id), LangItem::FnOnce)
     {
@@ -820,8 +1094,12 @@ fn check_codegen_attributes<'tcx, I: Inliner<'tcx>>(
 
     // Reachability pass defines which functions are eligible for inlining. Generally inlining
     // other functions is incorrect because they could reference symbols that aren't exported.
+    // Callees codegened in the same codegen unit are available regardless.
     let is_generic = callsite.callee.args.non_erasable_generics().next().is_some();
-    if !is_generic && !tcx.cross_crate_inlinable(callsite.callee.def_id()) {
+    if !is_generic
+        && inliner.codegen_unit().is_none()
+        && !tcx.cross_crate_inlinable(callsite.callee.def_id())
+    {
         return Err("not exported");
     }
 
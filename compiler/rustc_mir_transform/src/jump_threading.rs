@@ -65,11 +65,13 @@
 use rustc_mir_dataflow::value_analysis::{
     Map, PlaceCollectionMode, PlaceIndex, TrackElem, ValueIndex,
 };
-use rustc_span::DUMMY_SP;
+use rustc_session::code_stats::RemarkKind;
+use rustc_span::{DUMMY_SP, Span};
 use tracing::{debug, instrument, trace};
 
 use crate::PassPolicy;
 use crate::cost_checker::CostChecker;
+use crate::remarks::{self, Remark};
 
 pub(super) struct JumpThreading;
 
@@ -148,7 +150,21 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
         remove_costly_conditions(tcx, typing_env, body, &mut entry_states);
 
         if let Some(opportunities) = OpportunitySet::new(body, entry_states) {
-            opportunities.apply();
+            let threaded = opportunities.apply();
+            if remarks::enabled(tcx, remarks::JUMP_THREADING) {
+                for span in threaded {
+                    Remark::new(
+                        tcx,
+                        remarks::JUMP_THREADING,
+                        RemarkKind::Passed,
+                        "Threaded",
+                        def_id,
+                        span,
+                    )
+                    .text("branch replaced by a jump on a path where its outcome is known")
+                    .emit();
+                }
+            }
         }
     }
 }
@@ -944,6 +960,8 @@ struct OpportunitySet<'a, 'tcx> {
     /// Cache duplicated block. When cloning a basic block `bb` to fulfill a condition `c`,
     /// record the target of this `bb with c` edge.
     duplicates: FxHashMap<(BasicBlock, ConditionIndex), BasicBlock>,
+    /// The spans of the branches that were replaced by jumps.
+    threaded: Vec<Span>,
 }
 
 impl<'a, 'tcx> OpportunitySet<'a, 'tcx> {
@@ -963,12 +981,13 @@ fn new(
         }
         let duplicates = Default::default();
         let basic_blocks = body.basic_blocks.as_mut();
-        Some(OpportunitySet { basic_blocks, entry_states, duplicates })
+        Some(OpportunitySet { basic_blocks, entry_states, duplicates, threaded: Vec::new() })
     }
 
-    /// Apply the opportunities on the graph.
+    /// Apply the opportunities on the graph, and return the spans of the branches that were
+    /// replaced by jumps.
     #[instrument(level = "debug", skip(self))]
-    fn apply(mut self) {
+    fn apply(mut self) -> Vec<Span> {
         let mut worklist = Vec::with_capacity(self.basic_blocks.len());
         worklist.push(START_BLOCK);
 
@@ -986,6 +1005,8 @@ fn apply(mut self) {
             // Only visit actual successors.
             worklist.extend(self.basic_blocks[bb].terminator().successors());
         }
+
+        self.threaded
     }
 
     /// Apply the opportunities on `bb`.
@@ -1055,7 +1076,11 @@ fn apply_once(&mut self, bb: BasicBlock) {
 
     #[instrument(level = "debug", skip(self))]
     fn apply_goto(&mut self, bb: BasicBlock, target: BasicBlock) {
-        self.basic_blocks[bb].terminator_mut().kind = TerminatorKind::Goto { target };
+        let terminator = self.basic_blocks[bb].terminator_mut();
+        if let TerminatorKind::SwitchInt { .. } = terminator.kind {
+            self.threaded.push(terminator.source_info.span);
+        }
+        terminator.kind = TerminatorKind::Goto { target };
     }
 
     #[instrument(level = "debug", skip(self), ret)]
@@ -47,11 +47,16 @@
 mod lint;
 mod lint_tail_expr_drop_order;
 mod liveness;
+mod mono_optimize;
 mod patch;
+mod pgo;
+mod remarks;
 mod shim;
 mod ssa;
 mod trivial_const;
 
+/// Exposed for codegen backends.
+pub use mono_optimize::{MonoOptConfig, optimize_mono_body};
 /// Exposed for rustc drivers.
 pub use shim::build_drop_shim;
 
@@ -154,7 +159,7 @@ mod dead_store_elimination : DeadStoreElimination {
     mod gvn : GVN;
     // Made public so that `mir_drops_elaborated_and_const_checked` can be overridden
     // by custom rustc drivers, running all the steps by themselves. See #114628.
-    pub mod inline : Inline, ForceInline;
+    pub mod inline : Inline, ForceInline, MonoInline;
     mod impossible_clauses : ImpossibleClauses;
     mod instsimplify : InstSimplify { BeforeInline, AfterSimplifyCfg };
     mod jump_threading : JumpThreading;
@@ -216,6 +221,7 @@ pub fn provide(providers: &mut Providers) {
     ffi_unwind_calls::provide(&mut providers.queries);
     shim::provide(&mut providers.queries);
     cross_crate_inline::provide(&mut providers.queries);
+    pgo::provide(&mut providers.queries);
     providers.queries = query::Providers {
         mir_keys,
         mir_built,
@@ -0,0 +1,85 @@
+//! Optimization of monomorphized MIR, for codegen backends that do little optimization of their
+//! own.
+//!
+//! The regular pipeline in [`crate::run_optimization_passes`] runs on generic MIR, before it is
+//! known which functions end up in which codegen unit. Once a backend is about to codegen an
+//! instance, its MIR can be instantiated and the callees codegened in the same codegen unit can be
+//! inlined without exporting anything, after which the usual simplifications get another chance.
+
+use rustc_data_structures::fx::FxHashSet;
+use rustc_middle::mir::Body;
+use rustc_middle::ty::{self, Instance, InstanceKind, TyCtxt};
+use tracing::instrument;
+
+use crate::pass_manager::{self as pm, Enabled};
+use crate::{
+    add_call_guards, copy_prop, dead_store_elimination, gvn, inline, instsimplify, ref_prop,
+    remove_unneeded_drops, remove_zsts, simplify, simplify_branches, sroa,
+    unreachable_enum_branching, unreachable_prop,
+};
+
+/// Which parts of [`optimize_mono_body`] to run.
+#[derive(Copy, Clone, Debug)]
+pub struct MonoOptConfig {
+    /// Inline callees from the same codegen unit whose estimated cost is at most this, or twice
+    /// this for `#[inline]` callees. `None` disables inlining.
+    pub inline_threshold: Option<usize>,
+}
+
+/// Returns the MIR of `instance` instantiated for its generic arguments and optimized again,
+/// inlining callees that are part of `codegen_unit`.
+///
+/// Only [`InstanceKind::Item`]s are optimized, the MIR of other instances is just instantiated.
+/// The returned body no longer needs to be monomorphized by the caller.
+#[instrument(level = "debug", skip(tcx, codegen_unit))]
+pub fn optimize_mono_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: Instance<'tcx>,
+    codegen_unit: &FxHashSet<Instance<'tcx>>,
+    config: MonoOptConfig,
+) -> Body<'tcx> {
+    let body = tcx.instance_mir(instance.def);
+    let mut body = instance.instantiate_mir_and_normalize_erasing_regions(
+        tcx,
+        ty::TypingEnv::fully_monomorphized(),
+        ty::EarlyBinder::bind(tcx, body.clone()),
+    );
+    if !matches!(instance.def, InstanceKind::Item(_)) || body.tainted_by_errors.is_some() {
+        return body;
+    }
+
+    if let Some(threshold) = config.inline_threshold {
+        let mono_inline = Enabled(inline::MonoInline { codegen_unit, threshold });
+        pm::run_passes(tcx, &mut body, &[&mono_inline], None);
+    }
+
+    pm::run_passes(
+        tcx,
+        &mut body,
+        &[
+            // Instantiation may introduce ZSTs, useless drops and uninhabited enums.
+            &Enabled(remove_zsts::RemoveZsts),
+            &Enabled(remove_unneeded_drops::RemoveUnneededDrops),
+            &Enabled(unreachable_enum_branching::UnreachableEnumBranching),
+            &Enabled(unreachable_prop::UnreachablePropagation),
+            &Enabled(simplify::SimplifyCfg::AfterUnreachableEnumBranching),
+            &Enabled(instsimplify::InstSimplify::AfterSimplifyCfg),
+            &Enabled(simplify_branches::SimplifyConstCondition::AfterInstSimplify),
+            &Enabled(ref_prop::ReferencePropagation),
+            &Enabled(sroa::ScalarReplacementOfAggregates),
+            &Enabled(simplify::SimplifyLocals::BeforeConstProp),
+            &Enabled(gvn::GVN),
+            &Enabled(simplify::SimplifyLocals::AfterGVN),
+            &Enabled(simplify_branches::SimplifyConstCondition::Final),
+            &Enabled(simplify::SimplifyCfg::Final),
+            &Enabled(copy_prop::CopyProp),
+            &Enabled(dead_store_elimination::DeadStoreElimination::Final),
+            &Enabled(simplify::SimplifyLocals::Final),
+            // Inlining may have introduced critical call edges again.
+            &add_call_guards::CriticalCallEdges,
+        ],
+        None,
+    );
+
+    body
+}
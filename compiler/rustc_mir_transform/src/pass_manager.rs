@@ -201,6 +201,32 @@ fn policy(&self, sess: &Session) -> PassPolicy {
     }
 }
 
+/// Enables an optional pass regardless of the session's `-Zmir-opt-level`, for pipelines that the
+/// caller asked for explicitly. `-Zmir-enable-passes` and `#[optimize(none)]` still apply.
+pub(super) struct Enabled<T>(pub T);
+
+impl<'tcx, T> MirPass<'tcx> for Enabled<T>
+where
+    T: MirPass<'tcx>,
+{
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        self.0.run_pass(tcx, body)
+    }
+
+    fn policy(&self, sess: &Session) -> PassPolicy {
+        match self.0.policy(sess) {
+            PassPolicy::Required => PassPolicy::Required,
+            PassPolicy::Optional { generally_enabled: _, optimization } => {
+                PassPolicy::Optional { generally_enabled: true, optimization }
+            }
+        }
+    }
+}
+
 /// Whether to allow [optimization passes].
 ///
 /// [optimization passes]: PassPolicy::Optional::optimization
@@ -0,0 +1,249 @@
+//! Use of the `-Cprofile-use` profile in MIR optimizations.
+//!
+//! The profile is an indexed LLVM instrumentation profile. Its counters belong to the edges of
+//! the LLVM IR that was instrumented, which cannot be mapped back to MIR, so only the counts of
+//! whole functions are used. These still tell a lot about call sites and blocks: a call to a
+//! function that was never entered was never executed either, and neither was a block that can
+//! only continue into such a call.
+//!
+//! The profile only decides what gets inlined. Cold blocks are not outlined into functions of their
+//! own, as MIR optimizations cannot create new functions; that is left to LLVM's hot/cold
+//! splitting, which reads the same profile with its per-edge counts.
+
+use std::ops::RangeInclusive;
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_index::bit_set::DenseBitSet;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::profile::{Hotness, InstrProfile};
+use rustc_middle::mir::{BasicBlock, Body, TerminatorKind, traversal};
+use rustc_middle::query::Providers;
+use rustc_middle::ty::{self, Instance, InstanceKind, TyCtxt, TypeVisitableExt, Unnormalized};
+
+use crate::diagnostics;
+
+#[cfg(test)]
+mod tests;
+
+pub(super) fn provide(providers: &mut Providers) {
+    providers.instr_profile = instr_profile;
+    providers.instance_hotness = instance_hotness;
+}
+
+fn instr_profile(tcx: TyCtxt<'_>, (): ()) -> Option<InstrProfile> {
+    let path = tcx.sess.opts.cg.profile_use.as_ref()?;
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        // A missing profile has already been reported when the session was created.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            tcx.dcx().emit_warn(diagnostics::ProfileUseRead { path, error });
+            return None;
+        }
+    };
+    match parse_indexed_profile(&data) {
+        Ok(profile) => Some(profile),
+        Err(reason) => {
+            tcx.dcx().emit_warn(diagnostics::ProfileUseParse { path, reason });
+            None
+        }
+    }
+}
+
+// Looks `instance` up under both symbol names it can have, as the one it actually gets depends on
+// its instantiation mode, which depends on its optimized MIR and so on the profile.
+fn instance_hotness<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> Option<Hotness> {
+    let profile = tcx.instr_profile(())?;
+    let (symbol_name, local_symbol_name) = tcx.profile_symbol_names(instance);
+    profile.hotness(symbol_name.name).or_else(|| profile.hotness(local_symbol_name.name))
+}
+
+/// How often `instance` was executed according to the `-Cprofile-use` profile. Returns `None`
+/// without a profile, and for instances that are not fully monomorphic items.
+pub(crate) fn hotness<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> Option<Hotness> {
+    if tcx.sess.opts.cg.profile_use.is_none()
+        || !matches!(instance.def, InstanceKind::Item(_))
+        || instance.args.has_non_region_param()
+    {
+        return None;
+    }
+    tcx.instance_hotness(instance)
+}
+
+/// How often the function `def_id` was executed according to the `-Cprofile-use` profile, if it
+/// is not generic.
+pub(crate) fn item_hotness(tcx: TyCtxt<'_>, def_id: DefId) -> Option<Hotness> {
+    if tcx.sess.opts.cg.profile_use.is_none()
+        || !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        || tcx.generics_of(def_id).requires_monomorphization(tcx)
+    {
+        return None;
+    }
+    hotness(tcx, Instance::mono(tcx, def_id))
+}
+
+/// Finds the blocks of `body` that were not executed while the `-Cprofile-use` profile was
+/// collected: blocks that end in a call to a function that was never entered or is `#[cold]`,
+/// or in `unreachable`, and blocks whose successors are all such blocks.
+pub(crate) fn cold_blocks<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    typing_env: ty::TypingEnv<'tcx>,
+    body: &Body<'tcx>,
+) -> DenseBitSet<BasicBlock> {
+    let mut cold_blocks = DenseBitSet::new_empty(body.basic_blocks.len());
+    for (bb, bb_data) in traversal::postorder(body) {
+        let terminator = bb_data.terminator();
+        let is_cold = match terminator.kind {
+            TerminatorKind::Call { ref func, .. }
+                if let ty::FnDef(def_id, args) = *func.ty(body, tcx).kind() =>
+            {
+                tcx.codegen_fn_attrs(def_id).flags.contains(CodegenFnAttrFlags::COLD)
+                    || tcx
+                        .try_normalize_erasing_regions(typing_env, Unnormalized::new_wip(args))
+                        .ok()
+                        .and_then(|args| args.no_bound_vars())
+                        .and_then(|args| Instance::try_resolve(tcx, typing_env, def_id, args).ok())
+                        .flatten()
+                        .and_then(|callee| hotness(tcx, callee))
+                        == Some(Hotness::Cold)
+            }
+            TerminatorKind::Unreachable => true,
+            _ => false,
+        };
+
+        let mut successors = terminator.successors();
+        if is_cold
+            || successors.next().is_some_and(|first| cold_blocks.contains(first))
+                && successors.all(|succ| cold_blocks.contains(succ))
+        {
+            cold_blocks.insert(bb);
+        }
+    }
+    cold_blocks
+}
+
+const MAGIC: u64 = u64::from_le_bytes(*b"\xfflprofi\x81");
+/// The versions of the indexed profile format that can be read. Version 4 added the summary.
+const VERSIONS: RangeInclusive<u64> = 4..=12;
+/// The flags in the top byte of the version.
+const VARIANT_MASK: u64 = 0xff << 56;
+/// Flag for profiles that contain a second, context-sensitive summary.
+const VARIANT_MASK_CSIR_PROF: u64 = 1 << 57;
+/// The cutoff in the profile summary, in parts per million of all counts, whose minimum count is
+/// the threshold for hot code. This is the default of LLVM's `-profile-summary-cutoff-hot`.
+const HOT_CUTOFF: u64 = 990_000;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: u64) -> Result<&'a [u8], &'static str> {
+        let end = usize::try_from(len).ok().and_then(|len| self.pos.checked_add(len));
+        let bytes = end.and_then(|end| self.data.get(self.pos..end)).ok_or("it is truncated")?;
+        self.pos += bytes.len();
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, &'static str> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+/// Reads the function counts of an indexed profile, as written by `llvm-profdata merge`.
+fn parse_indexed_profile(data: &[u8]) -> Result<InstrProfile, &'static str> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.u64() != Ok(MAGIC) {
+        return Err("it is not an indexed profile");
+    }
+    let variant = reader.u64()?;
+    let version = variant & !VARIANT_MASK;
+    if !VERSIONS.contains(&version) {
+        return Err("its version of the indexed profile format is not supported");
+    }
+
+    // The header grew by one offset in each of versions 8, 9, 10 and 12.
+    let _unused = reader.u64()?;
+    let _hash_type = reader.u64()?;
+    let hash_table_offset = reader.u64()?;
+    for _ in [8, 9, 10, 12].into_iter().filter(|&v| version >= v) {
+        reader.u64()?;
+    }
+
+    let hot_count = read_summary(&mut reader)?;
+    if variant & VARIANT_MASK_CSIR_PROF != 0 {
+        read_summary(&mut reader)?;
+    }
+    let mut profile = InstrProfile { hot_count, ..InstrProfile::default() };
+
+    // The records follow the summary, grouped in the buckets of an on-disk hash table whose
+    // bucket array comes after them. Only the number of records is needed from the latter.
+    let mut table = Reader { data, pos: 0 };
+    table.bytes(hash_table_offset)?;
+    let _num_buckets = table.u64()?;
+    let num_entries = table.u64()?;
+    let mut left_in_bucket = 0;
+    for _ in 0..num_entries {
+        while left_in_bucket == 0 {
+            left_in_bucket = reader.u16()?;
+        }
+        left_in_bucket -= 1;
+        let _hash = reader.u64()?;
+        let name_len = reader.u64()?;
+        let data_len = reader.u64()?;
+        let name =
+            std::str::from_utf8(reader.bytes(name_len)?).map_err(|_| "a name is not UTF-8")?;
+        let mut records = Reader { data: reader.bytes(data_len)?, pos: 0 };
+        // There is one record for each structural hash a function was profiled with.
+        let mut counts = Vec::new();
+        while !records.is_empty() {
+            let _hash = records.u64()?;
+            let num_counts = records.u64()?;
+            counts.clear();
+            for _ in 0..num_counts {
+                counts.push(records.u64()?);
+            }
+            if version >= 11 {
+                // MC/DC bitmap bytes, each stored as a `u64`.
+                let num_bitmap_bytes = records.u64()?;
+                records.bytes(num_bitmap_bytes.saturating_mul(8))?;
+            }
+            // Value profile data, whose size includes the size itself.
+            let value_data_len = records.u32()?;
+            records.bytes(u64::from(value_data_len).saturating_sub(4))?;
+            profile.add_function(name, &counts);
+        }
+    }
+    Ok(profile)
+}
+
+/// Reads a profile summary, and returns the count threshold for hot code.
+fn read_summary(reader: &mut Reader<'_>) -> Result<u64, &'static str> {
+    let num_fields = reader.u64()?;
+    let num_cutoffs = reader.u64()?;
+    reader.bytes(num_fields.saturating_mul(8))?;
+    let mut hot_count = None;
+    for _ in 0..num_cutoffs {
+        let cutoff = reader.u64()?;
+        let min_count = reader.u64()?;
+        let _num_counts = reader.u64()?;
+        if cutoff >= HOT_CUTOFF && hot_count.is_none() {
+            hot_count = Some(min_count.max(1));
+        }
+    }
+    Ok(hot_count.unwrap_or(u64::MAX))
+}
@@ -0,0 +1,110 @@
+use rustc_middle::mir::profile::Hotness;
+
+use super::{MAGIC, VARIANT_MASK_CSIR_PROF, parse_indexed_profile};
+
+/// Writes an indexed profile in the given format version with all `functions` in one bucket.
+fn indexed_profile(variant: u64, functions: &[(&str, &[&[u64]])]) -> Vec<u8> {
+    let version = variant & 0xff;
+    let mut out = Vec::new();
+    let push = |out: &mut Vec<u8>, value: u64| out.extend_from_slice(&value.to_le_bytes());
+
+    push(&mut out, MAGIC);
+    push(&mut out, variant);
+    push(&mut out, 0);
+    push(&mut out, 0);
+    let hash_table_offset_pos = out.len();
+    push(&mut out, 0);
+    for _ in [8, 9, 10, 12].into_iter().filter(|&v| version >= v) {
+        push(&mut out, 0);
+    }
+
+    let summaries = if variant & VARIANT_MASK_CSIR_PROF != 0 { 2 } else { 1 };
+    for _ in 0..summaries {
+        // Two fields, and cutoffs at 90% and 99%.
+        push(&mut out, 2);
+        push(&mut out, 2);
+        push(&mut out, 1);
+        push(&mut out, 1000);
+        for (cutoff, min_count) in [(900_000, 5000), (990_000, 1000)] {
+            push(&mut out, cutoff);
+            push(&mut out, min_count);
+            push(&mut out, 1);
+        }
+    }
+
+    let bucket_offset = out.len() as u64;
+    out.extend_from_slice(&(functions.len() as u16).to_le_bytes());
+    for &(name, records) in functions {
+        let mut data = Vec::new();
+        for (hash, counts) in records.iter().enumerate() {
+            push(&mut data, hash as u64);
+            push(&mut data, counts.len() as u64);
+            for &count in *counts {
+                push(&mut data, count);
+            }
+            if version >= 11 {
+                push(&mut data, 1);
+                push(&mut data, 0xff);
+            }
+            // Empty value profile data.
+            data.extend_from_slice(&8u32.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        push(&mut out, 0x1234);
+        push(&mut out, name.len() as u64);
+        push(&mut out, data.len() as u64);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&data);
+    }
+
+    let hash_table_offset = out.len() as u64;
+    out[hash_table_offset_pos..hash_table_offset_pos + 8]
+        .copy_from_slice(&hash_table_offset.to_le_bytes());
+    push(&mut out, 1);
+    push(&mut out, functions.len() as u64);
+    push(&mut out, bucket_offset);
+    out
+}
+
+const IR_PROFILE: u64 = 1 << 56;
+
+#[test]
+fn function_counts() {
+    for variant in [
+        IR_PROFILE | 7,
+        IR_PROFILE | 10,
+        IR_PROFILE | 12,
+        5,
+        IR_PROFILE | 11 | VARIANT_MASK_CSIR_PROF,
+    ] {
+        let profile = indexed_profile(
+            variant,
+            &[
+                ("_ZN3foo3hot17h0123456789abcdefE", &[&[1, 20000, 3]]),
+                ("_ZN3foo4warm17h0123456789abcdefE", &[&[10], &[999]]),
+                ("foo.rs;_ZN3foo4cold17h0123456789abcdefE", &[&[0, 0]]),
+                ("main", &[&[1]]),
+            ],
+        );
+        let profile = parse_indexed_profile(&profile).unwrap();
+        assert_eq!(profile.hot_count, 1000);
+        assert_eq!(profile.hotness("_ZN3foo3hot17h0123456789abcdefE"), Some(Hotness::Hot));
+        assert_eq!(profile.hotness("_ZN3foo4warm17h0123456789abcdefE"), Some(Hotness::Normal));
+        assert_eq!(profile.hotness("_ZN3foo4cold17h0123456789abcdefE"), Some(Hotness::Cold));
+        assert_eq!(profile.hotness("main"), Some(Hotness::Normal));
+        assert_eq!(profile.hotness("_ZN3foo7missing17h0123456789abcdefE"), None);
+    }
+}
+
+#[test]
+fn invalid_profiles() {
+    assert!(parse_indexed_profile(b"").is_err());
+    // A raw profile, as written by the instrumented program.
+    assert!(parse_indexed_profile(&0xff_6c_70_72_6f_66_72_81u64.to_le_bytes()).is_err());
+    // A version from the future.
+    assert!(parse_indexed_profile(&indexed_profile(IR_PROFILE | 100, &[])).is_err());
+
+    let mut profile = indexed_profile(IR_PROFILE | 12, &[("main", &[&[1, 2, 3]])]);
+    profile.truncate(profile.len() - 40);
+    assert!(parse_indexed_profile(&profile).is_err());
+}
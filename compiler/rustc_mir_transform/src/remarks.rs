@@ -0,0 +1,96 @@
+//! Optimization remarks of MIR passes.
+//!
+//! They are enabled like LLVM's, by naming the pass in `-Cremark`, and are printed as notes.
+//! With `-Zremark-dir` they are instead collected in the session, and written next to LLVM's
+//! remark files by codegen, in the same format.
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::code_stats::{MirOptRemark, OptRemark, RemarkArg, RemarkKind, RemarkLoc};
+use rustc_span::{RemapPathScopeComponents, Span};
+
+pub(crate) const INLINE: &str = "mir-inline";
+pub(crate) const CROSS_CRATE_INLINE: &str = "mir-cross-crate-inline";
+pub(crate) const GVN: &str = "mir-gvn";
+pub(crate) const SROA: &str = "mir-sroa";
+pub(crate) const JUMP_THREADING: &str = "mir-jump-threading";
+
+pub(crate) fn enabled(tcx: TyCtxt<'_>, pass: &str) -> bool {
+    tcx.sess.opts.cg.remark.contains(pass)
+}
+
+/// A remark about the function `def_id`, which is built up and then passed to [`Remark::emit`].
+#[must_use]
+pub(crate) struct Remark<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    span: Span,
+    remark: OptRemark,
+}
+
+impl<'tcx> Remark<'tcx> {
+    pub(crate) fn new(
+        tcx: TyCtxt<'tcx>,
+        pass: &str,
+        kind: RemarkKind,
+        name: &str,
+        def_id: DefId,
+        span: Span,
+    ) -> Self {
+        let remark = OptRemark {
+            kind,
+            pass: pass.to_owned(),
+            name: name.to_owned(),
+            function: String::new(),
+            loc: None,
+            args: Vec::new(),
+        };
+        Remark { tcx, def_id, span, remark }
+    }
+
+    /// Adds `value` to the message, recorded under `key`.
+    pub(crate) fn arg(mut self, key: &str, value: impl ToString) -> Self {
+        self.remark.args.push(RemarkArg {
+            key: key.to_owned(),
+            value: value.to_string(),
+            loc: None,
+        });
+        self
+    }
+
+    /// Adds plain text to the message.
+    pub(crate) fn text(self, text: &str) -> Self {
+        self.arg("String", text)
+    }
+
+    /// Adds the path of the function `def_id` to the message, along with its location.
+    pub(crate) fn function_arg(mut self, key: &str, def_id: DefId) -> Self {
+        let value = self.tcx.def_path_str(def_id);
+        let loc = Some(self.loc(self.tcx.def_span(def_id)));
+        self.remark.args.push(RemarkArg { key: key.to_owned(), value, loc });
+        self
+    }
+
+    pub(crate) fn emit(mut self) {
+        self.remark.loc = Some(self.loc(self.span));
+        self.tcx.emit_mir_opt_remark(MirOptRemark {
+            def_id: self.def_id,
+            span: self.span,
+            remark: self.remark,
+        });
+    }
+
+    fn loc(&self, span: Span) -> RemarkLoc {
+        let loc = self.tcx.sess.source_map().lookup_char_pos(span.lo());
+        RemarkLoc {
+            file: loc
+                .file
+                .name
+                .display(RemapPathScopeComponents::DEBUGINFO)
+                .to_string_lossy()
+                .into(),
+            line: loc.line as u32,
+            column: loc.col_display as u32 + 1,
+        }
+    }
+}
@@ -8,10 +8,12 @@
 use rustc_middle::mir::*;
 use rustc_middle::ty::{self, Ty, TyCtxt};
 use rustc_mir_dataflow::value_analysis::{excluded_locals, iter_fields};
+use rustc_session::code_stats::RemarkKind;
 use tracing::{debug, instrument};
 
 use crate::PassPolicy;
 use crate::patch::MirPatch;
+use crate::remarks::{self, Remark};
 
 pub(super) struct ScalarReplacementOfAggregates;
 
@@ -31,12 +33,21 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
 
         let mut excluded = excluded_locals(body);
         let typing_env = body.typing_env(tcx);
+        let remarks = remarks::enabled(tcx, remarks::SROA);
+        let mut first_iteration = true;
         loop {
             debug!(?excluded);
             let escaping = escaping_locals(tcx, &excluded, body);
             debug!(?escaping);
+            if remarks && first_iteration {
+                not_split_remarks(tcx, body, &escaping);
+            }
+            first_iteration = false;
             let replacements = compute_flattening(tcx, typing_env, body, escaping);
             debug!(?replacements);
+            if remarks {
+                split_remarks(tcx, body, &replacements);
+            }
             let all_dead_locals = replace_flattened_locals(tcx, body, replacements);
             if !all_dead_locals.is_empty() {
                 excluded.union(&all_dead_locals);
@@ -52,6 +63,58 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
     }
 }
 
+/// The name of `local` in the source, or its MIR name if it has none.
+fn local_name(body: &Body<'_>, local: Local) -> String {
+    body.var_debug_info
+        .iter()
+        .find(|info| {
+            matches!(info.value, VarDebugInfoContents::Place(place) if place.as_local() == Some(local))
+        })
+        .map_or_else(|| format!("{local:?}"), |info| info.name.to_string())
+}
+
+/// Reports the user variables of struct or tuple type that are not split into their fields.
+fn not_split_remarks<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, escaping: &DenseBitSet<Local>) {
+    for (local, decl) in body.local_decls.iter_enumerated().skip(1 + body.arg_count) {
+        let is_aggregate = match *decl.ty.kind() {
+            ty::Adt(def, _) => def.is_struct(),
+            ty::Tuple(tys) => !tys.is_empty(),
+            _ => false,
+        };
+        // Bodies of other crates, as optimized after monomorphization, don't know their variables.
+        let is_user_variable = matches!(&decl.local_info, ClearCrossCrate::Set(info) if matches!(**info, LocalInfo::User(_)));
+        if !is_aggregate || !is_user_variable || !escaping.contains(local) {
+            continue;
+        }
+        let span = decl.source_info.span;
+        Remark::new(tcx, remarks::SROA, RemarkKind::Missed, "NotSplit", body.source.def_id(), span)
+            .text("`")
+            .arg("Local", local_name(body, local))
+            .text("` of type `")
+            .arg("Type", decl.ty)
+            .text("` is not split into its fields because it is used as a whole")
+            .emit();
+    }
+}
+
+/// Reports the locals that are split into their fields.
+fn split_remarks<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, replacements: &ReplacementMap<'tcx>) {
+    for (local, fields) in replacements.fragments.iter_enumerated() {
+        let Some(fields) = fields else { continue };
+        let decl = &body.local_decls[local];
+        let span = decl.source_info.span;
+        Remark::new(tcx, remarks::SROA, RemarkKind::Passed, "Split", body.source.def_id(), span)
+            .text("`")
+            .arg("Local", local_name(body, local))
+            .text("` of type `")
+            .arg("Type", decl.ty)
+            .text("` split into ")
+            .arg("Fields", fields.iter().flatten().count())
+            .text(" locals")
+            .emit();
+    }
+}
+
 /// Identify all locals that are not eligible for SROA.
 ///
 /// There are 3 cases:
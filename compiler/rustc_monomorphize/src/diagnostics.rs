@@ -200,6 +200,25 @@ pub(crate) struct AbiRequiredTargetFeature<'a> {
     pub is_call: bool,
 }
 
+#[derive(Diagnostic)]
+#[diag(
+    "this function call passes {$parts} to `{$callee}`, which the `{$local_backend}` and `{$upstream_backend}` codegen backends lower differently"
+)]
+#[note("`{$callee}` is codegened in crate `{$krate}`, which was built with `{$upstream_backend}`")]
+#[help(
+    "build `{$krate}` with the same codegen backend, for example with `-Zcrate-codegen-backend={$krate}={$local_backend}`"
+)]
+pub(crate) struct AbiErrorCrossBackend<'a> {
+    #[primary_span]
+    #[label("function called here")]
+    pub span: Span,
+    pub callee: String,
+    pub parts: String,
+    pub local_backend: &'a str,
+    pub upstream_backend: Symbol,
+    pub krate: Symbol,
+}
+
 #[derive(Diagnostic)]
 #[diag("static initializer forms a cycle involving `{$head}`")]
 #[note("cyclic static initializers are not supported for target `{$target}`")]
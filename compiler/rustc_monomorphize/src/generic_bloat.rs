@@ -0,0 +1,282 @@
+//! The generic instantiation report of `-Zprint-generic-bloat`.
+//!
+//! For the generic functions with the most instantiations, this lists the sizes of their
+//! instantiations and, for each type parameter, whether it affects codegen. Two sizes are shown:
+//! the estimate used to partition codegen units, which is computed from the MIR of the
+//! instantiation, and the codegen size, the number of instructions the backend generated for the
+//! instantiation before optimizing it (LLVM IR instructions with the LLVM backend). The report is
+//! printed once all codegen units have been codegened, for the codegen sizes to be known.
+//!
+//! A type parameter that is only used behind a reference and as the receiver of dyn-compatible
+//! trait methods could be replaced by a trait object, which makes it a good candidate for moving
+//! the body into a non-generic inner function.
+
+use rustc_data_structures::fx::{FxHashSet, FxIndexMap, FxIndexSet};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::visit::{TyContext, Visitor};
+use rustc_middle::mir::{Body, ConstOperand, Location};
+use rustc_middle::mono::MonoItem;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{
+    self, GenericArgKind, GenericArgsRef, GenericParamDefKind, Instance, InstanceKind, Ty, TyCtxt,
+};
+
+/// How the generic MIR body of a function uses a type parameter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ParamUsage {
+    Unused,
+    /// Only used behind a reference or raw pointer and as the receiver of methods that can be
+    /// called through a vtable.
+    DynCompatible,
+    AffectsCodegen,
+}
+
+struct ParamUsageVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    usage: Vec<ParamUsage>,
+}
+
+impl<'tcx> ParamUsageVisitor<'tcx> {
+    fn params(ty: Ty<'tcx>) -> impl Iterator<Item = usize> {
+        ty.walk().filter_map(|arg| match arg.kind() {
+            GenericArgKind::Type(ty) => match *ty.kind() {
+                ty::Param(param) => Some(param.index as usize),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    fn mark(&mut self, index: usize, usage: ParamUsage) {
+        if let Some(slot) = self.usage.get_mut(index)
+            && *slot != ParamUsage::AffectsCodegen
+        {
+            *slot = usage;
+        }
+    }
+
+    fn mark_all(&mut self, ty: Ty<'tcx>) {
+        for index in Self::params(ty) {
+            self.mark(index, ParamUsage::AffectsCodegen);
+        }
+    }
+
+    /// Returns the type parameter that is the receiver of `callee` if it is a trait method that
+    /// can be called through a vtable.
+    fn dyn_receiver(&self, def_id: DefId, args: GenericArgsRef<'tcx>) -> Option<usize> {
+        let trait_def_id = self.tcx.trait_of_assoc(def_id)?;
+        if !self.tcx.is_dyn_compatible(trait_def_id)
+            || !self.tcx.own_existential_vtable_entries(trait_def_id).contains(&def_id)
+        {
+            return None;
+        }
+        match *args.type_at(0).kind() {
+            ty::Param(param) => Some(param.index as usize),
+            _ => None,
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for ParamUsageVisitor<'tcx> {
+    fn visit_ty(&mut self, ty: Ty<'tcx>, context: TyContext) {
+        match context {
+            TyContext::LocalDecl { .. } | TyContext::ReturnTy(_) => {
+                let pointee = match *ty.kind() {
+                    ty::Ref(_, pointee, _) | ty::RawPtr(pointee, _) => pointee,
+                    _ => ty,
+                };
+                match *pointee.kind() {
+                    ty::Param(param) if pointee != ty => {
+                        self.mark(param.index as usize, ParamUsage::DynCompatible)
+                    }
+                    _ => self.mark_all(ty),
+                }
+            }
+            // User type annotations do not matter for codegen.
+            TyContext::UserTy(_) => {}
+            _ => self.mark_all(ty),
+        }
+    }
+
+    fn visit_args(&mut self, args: &GenericArgsRef<'tcx>, _: Location) {
+        for ty in args.types() {
+            self.mark_all(ty);
+        }
+    }
+
+    fn visit_const_operand(&mut self, constant: &ConstOperand<'tcx>, location: Location) {
+        if let ty::FnDef(def_id, args) = *constant.ty().kind()
+            && let Some(receiver) = self.dyn_receiver(def_id, args)
+        {
+            self.mark(receiver, ParamUsage::DynCompatible);
+            for ty in args.types().skip(1) {
+                self.mark_all(ty);
+            }
+            return;
+        }
+        self.super_const_operand(constant, location);
+    }
+}
+
+fn param_usage<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, params: usize) -> Vec<ParamUsage> {
+    let mut visitor = ParamUsageVisitor { tcx, usage: vec![ParamUsage::Unused; params] };
+    visitor.visit_body(body);
+    visitor.usage
+}
+
+/// The indices of the type parameters of `def_id` that are written by the user.
+fn type_params(tcx: TyCtxt<'_>, def_id: DefId) -> Vec<usize> {
+    let generics = tcx.generics_of(def_id);
+    // The synthetic parameters of closures only exist to carry the signature and captures.
+    let count = if tcx.is_closure_like(def_id) { generics.parent_count } else { generics.count() };
+    (0..count)
+        .filter(|&index| {
+            matches!(generics.param_at(index, tcx).kind, GenericParamDefKind::Type { .. })
+        })
+        .collect()
+}
+
+/// Sizes of the instantiations of one generic function.
+struct Sizes<T> {
+    min: T,
+    max: T,
+    total: T,
+}
+
+impl<T: Copy + Ord + std::ops::AddAssign + std::fmt::Display> Sizes<T> {
+    fn new(size: T) -> Self {
+        Sizes { min: size, max: size, total: size }
+    }
+
+    fn add(sizes: &mut Option<Self>, size: T) {
+        match sizes {
+            Some(sizes) => {
+                sizes.min = sizes.min.min(size);
+                sizes.max = sizes.max.max(size);
+                sizes.total += size;
+            }
+            None => *sizes = Some(Sizes::new(size)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let Sizes { min, max, total } = self;
+        if min == max {
+            format!("{min} each, {total} total")
+        } else {
+            format!("{min} to {max} each, {total} total")
+        }
+    }
+}
+
+/// All instantiations of one generic function.
+struct GenericFn<'tcx> {
+    instances: Vec<Instance<'tcx>>,
+    estimates: Option<Sizes<usize>>,
+    /// The sizes of the instantiations that were codegened in this session.
+    codegen_sizes: Option<Sizes<u64>>,
+    codegened: usize,
+}
+
+/// Prints the report once all codegen units have been codegened, so that the number of
+/// instructions the backend generated for each instantiation is known.
+pub(crate) fn print_generic_bloat<'tcx>(tcx: TyCtxt<'tcx>, limit: usize) {
+    let items: FxIndexSet<_> = tcx
+        .collect_and_partition_mono_items(())
+        .codegen_units
+        .iter()
+        .flat_map(|cgu| cgu.items().keys().copied())
+        .collect();
+    let codegen_sizes = tcx.sess.code_stats.codegen_sizes.borrow();
+
+    let mut functions: FxIndexMap<_, GenericFn<'tcx>> = FxIndexMap::default();
+    for item in items {
+        let MonoItem::Fn(instance) = item else { continue };
+        if !matches!(instance.def, InstanceKind::Item(_))
+            || type_params(tcx, instance.def_id()).is_empty()
+        {
+            continue;
+        }
+        let function = functions.entry(instance.def_id()).or_insert_with(|| GenericFn {
+            instances: Vec::new(),
+            estimates: None,
+            codegen_sizes: None,
+            codegened: 0,
+        });
+        function.instances.push(instance);
+        Sizes::add(&mut function.estimates, tcx.size_estimate(instance));
+        if let Some(&size) = codegen_sizes.get(tcx.symbol_name(instance).name) {
+            Sizes::add(&mut function.codegen_sizes, size);
+            function.codegened += 1;
+        }
+    }
+
+    let mut functions: Vec<_> = functions.into_iter().collect();
+    let total = |function: &GenericFn<'_>| {
+        let codegen = function.codegen_sizes.as_ref().map_or(0, |sizes| sizes.total);
+        let estimate = function.estimates.as_ref().map_or(0, |sizes| sizes.total);
+        (codegen, estimate)
+    };
+    functions.sort_by_key(|(_, function)| {
+        (std::cmp::Reverse(function.instances.len()), std::cmp::Reverse(total(function)))
+    });
+
+    let total_instances: usize =
+        functions.iter().map(|(_, function)| function.instances.len()).sum();
+    let (total_codegen_size, total_estimate) = functions
+        .iter()
+        .map(|(_, function)| total(function))
+        .fold((0, 0), |(a, b), (c, d)| (a + c, b + d));
+    println!(
+        "print-generic-bloat: {} generic functions with {total_instances} instantiations, \
+        total MIR size estimate {total_estimate}, total codegen size {total_codegen_size}",
+        functions.len(),
+    );
+
+    for (def_id, function) in functions.into_iter().take(limit) {
+        let GenericFn { instances, estimates, codegen_sizes, codegened } = function;
+        let estimates = estimates.expect("a generic function has at least one instantiation");
+        let codegen_sizes = match codegen_sizes {
+            Some(sizes) if codegened == instances.len() => {
+                format!("codegen size {}", sizes.describe())
+            }
+            Some(sizes) => format!(
+                "codegen size {} ({} instantiations reused from the incremental cache)",
+                sizes.describe(),
+                instances.len() - codegened,
+            ),
+            None => "codegen size unknown".to_owned(),
+        };
+        println!(
+            "print-generic-bloat: `{}`: {} instantiations, MIR size estimate {}, {codegen_sizes}",
+            with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+            instances.len(),
+            estimates.describe(),
+        );
+
+        let generics = tcx.generics_of(def_id);
+        let usage = tcx.is_mir_available(def_id).then(|| {
+            param_usage(tcx, tcx.instance_mir(InstanceKind::Item(def_id)), generics.count())
+        });
+        for index in type_params(tcx, def_id) {
+            let param = generics.param_at(index, tcx);
+            let distinct: FxHashSet<_> =
+                instances.iter().map(|instance| instance.args.type_at(index)).collect();
+            let note = match usage.as_ref().map(|usage| usage[index]) {
+                None => "no MIR available",
+                Some(ParamUsage::Unused) => "unused, does not affect codegen",
+                Some(ParamUsage::DynCompatible) => {
+                    "only used behind references and through dyn-compatible trait methods, \
+                    could be a trait object in a non-generic inner function"
+                }
+                Some(ParamUsage::AffectsCodegen) if distinct.len() > 1 => "affects codegen",
+                Some(ParamUsage::AffectsCodegen) => "affects codegen, but is always the same type",
+            };
+            println!(
+                "print-generic-bloat:     `{}`: {} distinct types, {note}",
+                param.name,
+                distinct.len(),
+            );
+        }
+    }
+}
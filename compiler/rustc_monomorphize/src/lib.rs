@@ -14,6 +14,7 @@
 
 mod collector;
 mod diagnostics;
+mod generic_bloat;
 mod graph_checks;
 mod mono_checks;
 mod offload;
@@ -1,5 +1,6 @@
 //! This module ensures that if a function's ABI requires a particular target feature,
-//! that target feature is enabled both on the callee and all callers.
+//! that target feature is enabled both on the callee and all callers, and that calls into
+//! crates built with another codegen backend agree on the ABI.
 use rustc_abi::{BackendRepr, CanonAbi, ExternAbi, RegKind, X86Call};
 use rustc_hir::{CRATE_HIR_ID, HirId};
 use rustc_middle::mir::{self, Location, traversal};
@@ -241,6 +242,72 @@ fn check_call_site_abi<'tcx>(
     do_check_simd_vector_abi(tcx, callee_abi, caller.def_id(), /*is_call*/ true, loc);
 }
 
+/// Check a call to a function that is codegened by an upstream crate, emitting an error when the
+/// upstream crate was built with a different codegen backend that may lower part of the callee's
+/// ABI differently than the local backend.
+///
+/// Calls through function pointers and trait objects are not checked as their callee is unknown.
+fn check_cross_backend_call_abi<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    callee: Ty<'tcx>,
+    loc: impl Fn() -> (Span, HirId),
+) {
+    let ty::FnDef(def_id, args) = *callee.kind() else {
+        return;
+    };
+    if def_id.is_local() || tcx.is_foreign_item(def_id) || tcx.intrinsic(def_id).is_some() {
+        // Only calls to Rust functions in upstream crates can cross backends.
+        return;
+    }
+    if callee.fn_sig(tcx).abi() == ExternAbi::Unadjusted {
+        return;
+    }
+    let typing_env = ty::TypingEnv::fully_monomorphized();
+    let instance = ty::Instance::expect_resolve(
+        tcx,
+        typing_env,
+        def_id,
+        args.no_bound_vars().unwrap(),
+        DUMMY_SP,
+    );
+    let InstanceKind::Item(def_id) = instance.def else {
+        // Shims are codegened locally.
+        return;
+    };
+    let krate = if tcx.is_reachable_non_generic(def_id) {
+        def_id.krate
+    } else if let Some(krate) = instance.upstream_monomorphization(tcx) {
+        krate
+    } else {
+        // The callee is instantiated locally.
+        return;
+    };
+    let (upstream_backend, upstream_abi) = tcx.crate_codegen_backend(krate);
+    let local_backend = tcx.sess.codegen_backend_name;
+    if upstream_backend.as_str() == local_backend {
+        return;
+    }
+    let Ok(callee_abi) =
+        tcx.fn_abi_of_instance(typing_env.as_query_input((instance, ty::List::empty())))
+    else {
+        // ABI failed to compute; this will not get through codegen.
+        return;
+    };
+    let compatible = upstream_abi & tcx.sess.codegen_backend_llvm_compatible_abi;
+    let mismatch = callee_abi.backend_lowered_abi(&tcx) - compatible;
+    if !mismatch.is_empty() {
+        let (span, _hir_id) = loc();
+        tcx.dcx().emit_err(diagnostics::AbiErrorCrossBackend {
+            span,
+            callee: tcx.def_path_str_with_args(def_id, instance.args),
+            parts: mismatch.descr(),
+            local_backend,
+            upstream_backend,
+            krate: tcx.crate_name(krate),
+        });
+    }
+}
+
 fn check_callees_abi<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>, body: &mir::Body<'tcx>) {
     // Check all function call terminators.
     for (bb, _data) in traversal::mono_reachable(body, tcx, instance) {
@@ -254,7 +321,7 @@ fn check_callees_abi<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>, body: &m
                     ty::TypingEnv::fully_monomorphized(),
                     ty::EarlyBinder::bind(tcx, callee_ty),
                 );
-                check_call_site_abi(tcx, callee_ty, body.source.instance, || {
+                let loc = || {
                     let loc = Location {
                         block: bb,
                         statement_index: body.basic_blocks[bb].statements.len(),
@@ -266,7 +333,9 @@ fn check_callees_abi<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>, body: &m
                             .lint_root(&body.source_scopes)
                             .unwrap_or(CRATE_HIR_ID),
                     )
-                });
+                };
+                check_call_site_abi(tcx, callee_ty, body.source.instance, loc);
+                check_cross_backend_call_abi(tcx, callee_ty, loc);
             }
             _ => {}
         }
@@ -126,6 +126,7 @@
 
 use crate::collector::{self, MonoItemCollectionStrategy, UsageMap};
 use crate::diagnostics::{CouldntDumpMonoStats, SymbolAlreadyDefined};
+use crate::generic_bloat::print_generic_bloat;
 use crate::graph_checks::target_specific_checks;
 
 struct PartitioningCx<'a, 'tcx> {
@@ -1353,6 +1354,7 @@ struct MonoItem {
 
 pub(crate) fn provide(providers: &mut Providers) {
     providers.queries.collect_and_partition_mono_items = collect_and_partition_mono_items;
+    providers.hooks.print_generic_bloat = print_generic_bloat;
 
     providers.queries.is_codegened_item =
         |tcx, def_id| tcx.collect_and_partition_mono_items(()).all_mono_items.contains(&def_id);
@@ -1,9 +1,11 @@
 use std::cmp;
 
 use rustc_abi::{Align, Size};
-use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::sync::Lock;
-use rustc_span::Symbol;
+use rustc_macros::{Decodable, Encodable};
+use rustc_span::def_id::DefId;
+use rustc_span::{Span, Symbol};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -70,11 +72,95 @@ pub struct TypeSizeInfo {
     pub variants: Vec<VariantInfo>,
 }
 
+/// The kind of an optimization remark, named like the YAML tags of LLVM's remark files.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Encodable, Decodable)]
+pub enum RemarkKind {
+    Passed,
+    Missed,
+    Analysis,
+    AnalysisFPCommute,
+    AnalysisAliasing,
+    Failure,
+}
+
+impl RemarkKind {
+    pub fn tag(self) -> &'static str {
+        match self {
+            RemarkKind::Passed => "Passed",
+            RemarkKind::Missed => "Missed",
+            RemarkKind::Analysis => "Analysis",
+            RemarkKind::AnalysisFPCommute => "AnalysisFPCommute",
+            RemarkKind::AnalysisAliasing => "AnalysisAliasing",
+            RemarkKind::Failure => "Failure",
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<RemarkKind> {
+        Some(match tag {
+            "Passed" => RemarkKind::Passed,
+            "Missed" => RemarkKind::Missed,
+            "Analysis" => RemarkKind::Analysis,
+            "AnalysisFPCommute" => RemarkKind::AnalysisFPCommute,
+            "AnalysisAliasing" => RemarkKind::AnalysisAliasing,
+            "Failure" => RemarkKind::Failure,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Encodable, Decodable)]
+pub struct RemarkLoc {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A piece of the message of a remark. The message is the concatenation of all values, and
+/// pieces that are only text have the key `String`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Encodable, Decodable)]
+pub struct RemarkArg {
+    pub key: String,
+    pub value: String,
+    pub loc: Option<RemarkLoc>,
+}
+
+/// An optimization remark, as recorded by a MIR pass or read from LLVM's remark files.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Encodable, Decodable)]
+pub struct OptRemark {
+    pub kind: RemarkKind,
+    pub pass: String,
+    pub name: String,
+    /// The symbol name of the function the remark is about, or its path if it is generic. Empty
+    /// in a [`MirOptRemark`], whose function is only named once codegen has decided on symbols.
+    pub function: String,
+    pub loc: Option<RemarkLoc>,
+    pub args: Vec<RemarkArg>,
+}
+
+impl OptRemark {
+    pub fn message(&self) -> String {
+        self.args.iter().map(|arg| &*arg.value).collect()
+    }
+}
+
+/// The remark of a MIR pass about the function `def_id`, made at `span`.
+#[derive(Clone, Debug, Encodable, Decodable)]
+pub struct MirOptRemark {
+    pub def_id: DefId,
+    pub span: Span,
+    pub remark: OptRemark,
+}
+
 #[derive(Default)]
 pub struct CodeStats {
     /// The hash set that actually holds all the type size information.
     /// The field is public for use in external tools. See #139876.
     pub type_sizes: Lock<FxHashSet<TypeSizeInfo>>,
+    /// The remarks of MIR optimizations for `-Zremark-dir`, in the order they were made.
+    pub opt_remarks: Lock<Vec<MirOptRemark>>,
+    /// The number of instructions the backend generated for each function, by symbol name, for
+    /// `-Zprint-generic-bloat`. Summed over the copies of functions codegened in several CGUs.
+    pub codegen_sizes: Lock<FxHashMap<String, u64>>,
 }
 
 impl CodeStats {
@@ -108,6 +194,14 @@ pub fn record_type_size<S: ToString>(
         self.type_sizes.borrow_mut().insert(info);
     }
 
+    pub fn record_opt_remark(&self, remark: MirOptRemark) {
+        self.opt_remarks.borrow_mut().push(remark);
+    }
+
+    pub fn record_codegen_size(&self, symbol_name: &str, instructions: u64) {
+        *self.codegen_sizes.borrow_mut().entry(symbol_name.to_owned()).or_insert(0) += instructions;
+    }
+
     pub fn print_type_sizes(&self) {
         let type_sizes = self.type_sizes.borrow();
         // We will soon sort, so the initial order does not matter.
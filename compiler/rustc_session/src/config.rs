@@ -1528,6 +1528,17 @@ pub fn share_generics(&self) -> bool {
         }
     }
 
+    /// The codegen backend that `-Zcrate-codegen-backend` requests for the crate named
+    /// `crate_name`, if any.
+    pub fn crate_codegen_backend(&self, crate_name: &str) -> Option<&str> {
+        self.unstable_opts
+            .crate_codegen_backend
+            .iter()
+            .rev()
+            .find(|(krate, _)| krate == crate_name)
+            .map(|(_, backend)| backend.as_str())
+    }
+
     pub fn get_symbol_mangling_version(&self) -> SymbolManglingVersion {
         self.cg.symbol_mangling_version.unwrap_or(SymbolManglingVersion::V0)
     }
@@ -1595,6 +1606,13 @@ fn is_empty(&self) -> bool {
         }
     }
 
+    pub fn contains(&self, pass: &str) -> bool {
+        match *self {
+            Passes::Some(ref v) => v.iter().any(|p| p == pass),
+            Passes::All => true,
+        }
+    }
+
     pub(crate) fn extend(&mut self, passes: impl IntoIterator<Item = String>) {
         match *self {
             Passes::Some(ref mut v) => v.extend(passes),
@@ -3338,7 +3356,7 @@ pub(crate) mod dep_tracking {
         FunctionReturn, InliningThreshold, InstrumentCoverage, InstrumentMcount,
         InstrumentMcountOpts, InstrumentXRay, LinkerPluginLto, LocationDetail, LtoCli,
         MirStripDebugInfo, NextSolverConfig, Offload, OptLevel, OutFileName, OutputType,
-        OutputTypes, PatchableFunctionEntry, PointerAuthOption, Polonius, ResolveDocLinks,
+        OutputTypes, Passes, PatchableFunctionEntry, PointerAuthOption, Polonius, ResolveDocLinks,
         SourceFileHashAlgorithm, SplitDwarfKind, SwitchWithOptPath, SymbolManglingVersion,
         WasiExecModel,
     };
@@ -3537,6 +3555,24 @@ fn hash(
         }
     }
 
+    impl DepTrackingHash for Passes {
+        fn hash(&self, hasher: &mut StableHasher, _: ErrorOutputType, _for_crate_hash: bool) {
+            // The remarks of MIR passes are side effects of queries, and a query result that was
+            // cached without them cannot replay them. Remarks of other passes are not cached.
+            match self {
+                Passes::Some(passes) => {
+                    Hash::hash(&0, hasher);
+                    let mut mir_passes: Vec<_> =
+                        passes.iter().filter(|pass| pass.starts_with("mir-")).collect();
+                    mir_passes.sort_unstable();
+                    mir_passes.dedup();
+                    Hash::hash(&mir_passes, hasher);
+                }
+                Passes::All => Hash::hash(&1, hasher),
+            }
+        }
+    }
+
     // This is a stable hash because BTreeMap is a sorted container
     pub(crate) fn stable_hash(
         sub_hashes: BTreeMap<&'static str, &dyn DepTrackingHash>,
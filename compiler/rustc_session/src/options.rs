@@ -906,6 +906,7 @@ mod desc {
     pub(crate) const parse_inlining_threshold: &str =
         "either a boolean (`yes`, `no`, `on`, `off`, etc), or a non-negative number";
     pub(crate) const parse_llvm_module_flag: &str = "<key>:<type>:<value>:<behavior>. Type must currently be `u32`. Behavior should be one of (`error`, `warning`, `require`, `override`, `append`, `appendunique`, `max`, `min`)";
+    pub(crate) const parse_crate_codegen_backend: &str = "<crate>=<backend>";
     pub(crate) const parse_function_return: &str = "`keep` or `thunk-extern`";
     pub(crate) const parse_wasm_c_abi: &str = "`spec`";
     pub(crate) const parse_mir_include_spans: &str =
@@ -2134,6 +2135,21 @@ pub(crate) fn parse_llvm_module_flag(
         true
     }
 
+    pub(crate) fn parse_crate_codegen_backend(
+        slot: &mut Vec<(String, String)>,
+        v: Option<&str>,
+    ) -> bool {
+        let Some((krate, backend)) = v.and_then(|v| v.split_once('=')) else {
+            return false;
+        };
+        if krate.is_empty() || backend.is_empty() {
+            return false;
+        }
+        // Cargo passes crate names with `-` replaced by `_`, accept package names as well.
+        slot.push((krate.replace('-', "_"), backend.to_string()));
+        true
+    }
+
     pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>) -> bool {
         match v {
             Some("keep") => *slot = FunctionReturn::Keep,
@@ -2316,7 +2332,7 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
         (`rustc --print relocation-models` for details)"),
     relro_level: Option<RelroLevel> = (None, parse_relro_level, [TRACKED],
         "choose which RELRO level to use"),
-    remark: Passes = (Passes::Some(Vec::new()), parse_passes, [UNTRACKED],
+    remark: Passes = (Passes::Some(Vec::new()), parse_passes, [TRACKED_NO_CRATE_HASH],
         "output remarks for these optimization passes (space separated, or \"all\")"),
     rpath: bool = (false, parse_bool, [UNTRACKED],
         "set rpath values in libs/exes (default: no)"),
@@ -2425,6 +2441,9 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
         "control details of coverage instrumentation"),
     crate_attr: Vec<String> = (Vec::new(), parse_string_push, [TRACKED],
         "inject the given attribute in the crate"),
+    crate_codegen_backend: Vec<(String, String)> = (Vec::new(), parse_crate_codegen_backend, [TRACKED],
+        "the backend to use for the crate with the given name, taking precedence over \
+        `-Zcodegen-backend` (can be specified multiple times)"),
     cross_crate_inline_threshold: InliningThreshold = (InliningThreshold::Sometimes(100), parse_inlining_threshold, [TRACKED],
         "threshold to allow cross crate inlining of functions"),
     debug_info_type_line_numbers: bool = (false, parse_bool, [TRACKED],
@@ -2554,6 +2573,9 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof) \
         (default: no)"),
+    incremental_link: bool = (false, parse_bool, [UNTRACKED],
+        "link incrementally, updating the previous output with the changed object files, if the \
+        linker supports it (default: no)"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
         "verify extended properties for incr. comp. (default: no):
         - hashes of green query instances
@@ -2569,6 +2591,8 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
         "inlining threshold when the caller is a simple forwarding function (default: 30)"),
     inline_mir_hint_threshold: Option<usize> = (None, parse_opt_number, [TRACKED],
         "inlining threshold for functions with inline hint (default: 100)"),
+    inline_mir_hot_threshold: Option<usize> = (None, parse_opt_number, [TRACKED],
+        "inlining threshold for call sites that are hot according to `-C profile-use` (default: 200)"),
     inline_mir_preserve_debug: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "when MIR inlining, whether to preserve debug info for callee variables \
         (default: preserve for debuginfo != None, otherwise remove)"),
@@ -2607,6 +2631,8 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
         "link native libraries in the linker invocation (default: yes)"),
     link_only: bool = (false, parse_bool, [TRACKED],
         "link the `.rlink` file generated by `-Z no-link` (default: no)"),
+    linker_map: bool = (false, parse_bool, [UNTRACKED],
+        "have the linker write a map of each linked artifact and normalize it into JSON (default: no)"),
     lint_llvm_ir: bool = (false, parse_bool, [TRACKED],
         "lint LLVM IR (default: no)"),
     lint_mir: bool = (false, parse_bool, [UNTRACKED],
@@ -2757,12 +2783,21 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
         "use a more precise version of drop elaboration for matches on enums (default: yes). \
         This results in better codegen, but has caused miscompilations on some tier 2 platforms. \
         See #77382 and #74551."),
+    print_binary_size: bool = (false, parse_bool, [UNTRACKED],
+        "print the size of the linked binary by crate, module, function and generic \
+        instantiation (default: no)"),
+    print_binary_size_json: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "write the binary size report in JSON to a file (default: no)"),
     #[rustc_lint_opt_deny_field_access("use `Session::print_codegen_stats` instead of this field")]
     print_codegen_stats: bool = (false, parse_bool, [UNTRACKED],
         "print codegen statistics (default: no)"),
     #[rustc_lint_opt_deny_field_access("use `Session::print_llvm_stats_json` instead of this field")]
     print_codegen_stats_json: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print codegen statistics in JSON to a file (default: no)"),
+    print_generic_bloat: Option<usize> = (None, parse_opt_number, [UNTRACKED],
+        "print the given number of generic functions with the most instantiations, with their \
+        MIR size estimates, the number of instructions generated for them and the type parameters \
+        that affect codegen (default: no)"),
     print_llvm_passes: bool = (false, parse_bool, [UNTRACKED],
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: bool = (false, parse_bool, [UNTRACKED],
@@ -2977,6 +3012,9 @@ pub(crate) fn parse_rust_version(slot: &mut Option<RustcVersion>, v: Option<&str
     #[rustc_lint_opt_deny_field_access("use `Session::verify_llvm_ir` instead of this field")]
     verify_llvm_ir: bool = (false, parse_bool, [TRACKED],
         "verify LLVM IR (default: no)"),
+    verify_reproducibility: bool = (false, parse_bool, [UNTRACKED],
+        "compile twice with a perturbed environment and report the first difference \
+        between the emitted artifacts (default: no)"),
     virtual_function_elimination: bool = (false, parse_bool, [TRACKED],
         "enables dead virtual function elimination optimization. \
         Requires `-Clto[=[fat,yes]]`"),
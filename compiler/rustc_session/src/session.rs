@@ -26,6 +26,7 @@
 use rustc_span::source_map::{FilePathMapping, SourceMap};
 use rustc_span::{RealFileName, Span, Symbol};
 use rustc_target::asm::InlineAsmArch;
+use rustc_target::callconv::BackendLoweredAbi;
 use rustc_target::spec::{
     Arch, CfgAbi, CodeModel, DebuginfoKind, Os, PanicStrategy, RelocModel, RelroLevel,
     SanitizerSet, SmallDataThresholdSupport, SplitDebuginfo, StackProtector, SymbolVisibility,
@@ -406,6 +407,11 @@ pub struct Session {
     /// Does the codegen backend support ThinLTO?
     pub thin_lto_supported: bool,
 
+    /// The name of the codegen backend, as recorded in the crate metadata.
+    pub codegen_backend_name: &'static str,
+    /// The parts of the ABI that the codegen backend lowers the same way as LLVM.
+    pub codegen_backend_llvm_compatible_abi: BackendLoweredAbi,
+
     /// Global per-session counter for MIR optimization pass applications.
     ///
     /// Used by `-Zmir-opt-bisect-limit` to assign an index to each
@@ -460,6 +466,27 @@ pub fn local_crate_source_file(&self) -> Option<RealFileName> {
         )
     }
 
+    /// The name of the crate as far as it is known before the crate is parsed: the `--crate-name`
+    /// or otherwise the name inferred from the input file.
+    pub fn early_crate_name(&self) -> String {
+        match &self.opts.crate_name {
+            Some(crate_name) => crate_name.clone(),
+            None => self.io.input.filestem().replace('-', "_"),
+        }
+    }
+
+    /// The codegen backend requested for this crate, either through `-Zcrate-codegen-backend` or
+    /// through `-Zcodegen-backend`.
+    ///
+    /// The backend is loaded before the crate is parsed, so `-Zcrate-codegen-backend` is matched
+    /// against [`Session::early_crate_name`]. A different `#![crate_name]` is rejected once the
+    /// crate has been parsed.
+    pub fn codegen_backend(&self) -> Option<&str> {
+        let crate_name = self.early_crate_name();
+        let codegen_backend = self.opts.unstable_opts.codegen_backend.as_deref();
+        self.opts.crate_codegen_backend(&crate_name).or(codegen_backend)
+    }
+
     fn check_miri_unleashed_features(&self) -> Option<ErrorGuaranteed> {
         let mut guar = None;
         let unleashed_features = self.miri_unleashed_features.lock();
@@ -1382,6 +1409,8 @@ pub fn build_session(
         replaced_intrinsics: FxHashSet::default(), // filled by `run_compiler`
         fallback_intrinsics: FxHashSet::default(), // filled by `run_compiler`
         thin_lto_supported: true,                  // filled by `run_compiler`
+        codegen_backend_name: "",                  // filled by `run_compiler`
+        codegen_backend_llvm_compatible_abi: BackendLoweredAbi::empty(), // filled by `run_compiler`
         mir_opt_bisect_eval_count: AtomicUsize::new(0),
         removed_rustc_main_attr: AtomicBool::new(false),
         pointer_auth_config,
@@ -53,18 +53,22 @@ pub enum Edition {
 
 impl fmt::Display for Edition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match *self {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Edition {
+    /// Returns the edition as written on the command line, e.g. `2021`.
+    pub fn as_str(self) -> &'static str {
+        match self {
             Edition::Edition2015 => "2015",
             Edition::Edition2018 => "2018",
             Edition::Edition2021 => "2021",
             Edition::Edition2024 => "2024",
             Edition::EditionFuture => "future",
-        };
-        write!(f, "{s}")
+        }
     }
-}
 
-impl Edition {
     pub fn lint_name(self) -> &'static str {
         match self {
             Edition::Edition2015 => "rust_2015_compatibility",
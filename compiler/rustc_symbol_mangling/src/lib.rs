@@ -125,7 +125,11 @@ pub fn symbol_name_for_instance_in_crate<'tcx>(
 }
 
 pub fn provide(providers: &mut Providers) {
-    *providers = Providers { symbol_name: symbol_name_provider, ..*providers };
+    *providers = Providers {
+        symbol_name: symbol_name_provider,
+        profile_symbol_names: profile_symbol_names_provider,
+        ..*providers
+    };
 }
 
 /// Compute the v0 symbol name for an offload kernel instance. Forces
@@ -162,6 +166,25 @@ fn symbol_name_provider<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> ty
     ty::SymbolName::new(tcx, &symbol_name)
 }
 
+// The `profile_symbol_names` query provides the symbol names that a non-generic instance has with
+// and without the suffix of the local crate. Unlike `symbol_name`, it does not ask whether the
+// instance needs that suffix, as that depends on its instantiation mode and therefore on the
+// optimized MIR that the `-Cprofile-use` profile is looked up for.
+fn profile_symbol_names_provider<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: Instance<'tcx>,
+) -> (ty::SymbolName<'tcx>, ty::SymbolName<'tcx>) {
+    debug_assert!(!is_generic(instance));
+    if let Some(symbol) = symbol_name_from_attrs(tcx, instance.def) {
+        let symbol_name = ty::SymbolName::new(tcx, &symbol);
+        return (symbol_name, symbol_name);
+    }
+    let mangle = |instantiating_crate| {
+        ty::SymbolName::new(tcx, &mangle_symbol_name(tcx, instance, instantiating_crate))
+    };
+    (mangle(None), mangle(Some(LOCAL_CRATE)))
+}
+
 pub fn typeid_for_trait_ref<'tcx>(
     tcx: TyCtxt<'tcx>,
     trait_ref: ty::ExistentialTraitRef<'tcx>,
@@ -297,6 +320,18 @@ fn compute_symbol_name<'tcx>(
 
     let instantiating_crate = avoid_cross_crate_conflicts.then(compute_instantiating_crate);
 
+    mangle_symbol_name(tcx, instance, instantiating_crate)
+}
+
+/// Mangles the symbol name of `instance`, which has no symbol name given by its attributes, with
+/// the suffix of `instantiating_crate`, if any.
+fn mangle_symbol_name<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: Instance<'tcx>,
+    instantiating_crate: Option<CrateNum>,
+) -> String {
+    let def_id = instance.def_id();
+
     // Pick the crate responsible for the symbol mangling version, which has to:
     // 1. be stable for each instance, whether it's being defined or imported
     // 2. obey each crate's own `-C symbol-mangling-version`, as much as possible
@@ -5,7 +5,7 @@
     AddressSpace, Align, BackendRepr, CanonAbi, ExternAbi, FieldsShape, HasDataLayout, Primitive,
     Reg, RegKind, Scalar, Size, TyAbiInterface, TyAndLayout, Variants,
 };
-use rustc_macros::StableHash;
+use rustc_macros::{BlobDecodable, Encodable, StableHash};
 
 pub use crate::spec::AbiMap;
 use crate::spec::{Arch, HasTargetSpec, HasX86AbiOpt};
@@ -36,6 +36,9 @@
 mod x86_win64;
 mod xtensa;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug, StableHash)]
 pub enum PassMode {
     /// Ignore the argument.
@@ -133,6 +136,45 @@ impl ArgAttribute: u16 {
     rustc_data_structures::external_bitflags_debug! { ArgAttribute }
 }
 
+/// The parts of a function ABI whose lowering to machine code is left to the codegen backend.
+///
+/// Two backends only agree on how such a signature is passed if they lower every part of it that
+/// occurs in the signature the same way. Each backend reports the parts it lowers like LLVM does
+/// through `CodegenBackend::llvm_compatible_abi`, which is also recorded in the crate metadata so
+/// that calls into crates codegened by a different backend can be checked.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Encodable, BlobDecodable, StableHash)]
+pub struct BackendLoweredAbi(u8);
+
+bitflags::bitflags! {
+    impl BackendLoweredAbi: u8 {
+        /// Scalars wider than a pointer passed directly, e.g. `i128` on 64-bit targets. LLVM
+        /// splits them over two registers or passes them on the stack depending on the target.
+        const WIDE_SCALAR = 1 << 0;
+        /// Values returned directly that do not fit in two pointer-sized registers. LLVM returns
+        /// them through a return area pointer as a whole, while other backends may return part of
+        /// the value in registers. See also `FnAbi::adjust_for_rust_abi`.
+        const WIDE_RETURN = 1 << 1;
+        /// SIMD vectors passed directly in vector registers.
+        const SIMD_VECTOR = 1 << 2;
+    }
+}
+rustc_data_structures::external_bitflags_debug! { BackendLoweredAbi }
+
+impl BackendLoweredAbi {
+    /// A short description of the parts of the ABI in `self`, for use in diagnostics.
+    pub fn descr(self) -> String {
+        self.iter()
+            .map(|part| match part {
+                Self::WIDE_SCALAR => "scalars wider than a pointer",
+                Self::WIDE_RETURN => "return values wider than two pointers",
+                Self::SIMD_VECTOR => "SIMD vectors",
+                _ => unreachable!(),
+            })
+            .intersperse(" and ")
+            .collect()
+    }
+}
+
 /// Sometimes an ABI requires small integers to be extended to a full or partial register. This enum
 /// defines if this extension should be zero-extension or sign-extension when necessary. When it is
 /// not necessary to extend the argument, this enum is ignored.
@@ -555,6 +597,39 @@ pub fn is_ignore(&self) -> bool {
         matches!(self.mode, PassMode::Ignore)
     }
 
+    /// Returns the parts of this argument's ABI whose lowering depends on the codegen backend.
+    ///
+    /// `is_ret` indicates whether this is the return value.
+    pub fn backend_lowered_abi<C: HasDataLayout>(&self, cx: &C, is_ret: bool) -> BackendLoweredAbi {
+        let ptr_size = Primitive::Pointer(AddressSpace::ZERO).size(cx);
+        let mut abi = BackendLoweredAbi::empty();
+        match (&self.mode, self.layout.backend_repr) {
+            (PassMode::Ignore | PassMode::Indirect { .. }, _) => return abi,
+            (
+                PassMode::Direct(_) | PassMode::Pair(..),
+                BackendRepr::SimdVector { .. } | BackendRepr::SimdScalableVector { .. },
+            ) => {
+                abi |= BackendLoweredAbi::SIMD_VECTOR;
+            }
+            (PassMode::Direct(_), BackendRepr::Scalar(scalar)) if scalar.size(cx) > ptr_size => {
+                abi |= BackendLoweredAbi::WIDE_SCALAR;
+            }
+            (PassMode::Pair(..), BackendRepr::ScalarPair { a, b, b_offset: _ })
+                if a.size(cx) > ptr_size || b.size(cx) > ptr_size =>
+            {
+                abi |= BackendLoweredAbi::WIDE_SCALAR;
+            }
+            _ => {}
+        }
+        if is_ret
+            && self.layout.size > ptr_size * 2
+            && !abi.contains(BackendLoweredAbi::SIMD_VECTOR)
+        {
+            abi |= BackendLoweredAbi::WIDE_RETURN;
+        }
+        abi
+    }
+
     /// Checks if these two `ArgAbi` are equal enough to be considered "the same for all
     /// function call ABIs".
     pub fn eq_abi(&self, other: &Self) -> bool
@@ -879,6 +954,13 @@ pub fn adjust_for_rust_abi<C>(&mut self, cx: &C)
             }
         }
     }
+
+    /// Returns the parts of this function's ABI whose lowering depends on the codegen backend.
+    pub fn backend_lowered_abi<C: HasDataLayout>(&self, cx: &C) -> BackendLoweredAbi {
+        self.args.iter().fold(self.ret.backend_lowered_abi(cx, true), |abi, arg| {
+            abi | arg.backend_lowered_abi(cx, false)
+        })
+    }
 }
 
 /// Determines whether `layout` contains no uninit bytes (no padding, no unions),
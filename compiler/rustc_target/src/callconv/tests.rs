@@ -0,0 +1,91 @@
+use rustc_abi::{
+    FieldIdx, Integer, Layout, LayoutData, Primitive, Scalar, TargetDataLayout, TyAndLayout,
+    VariantIdx, WrappingRange,
+};
+use rustc_data_structures::intern::Interned;
+
+use super::{ArgAbi, ArgAttributes, BackendLoweredAbi, CanonAbi, FnAbi};
+
+type TestLayoutData = LayoutData<FieldIdx, VariantIdx>;
+
+fn int(integer: Integer) -> Scalar {
+    Scalar::Initialized {
+        value: Primitive::Int(integer, false),
+        valid_range: WrappingRange::full(integer.size()),
+    }
+}
+
+fn arg<'a>(data: &'a TestLayoutData) -> ArgAbi<'a, ()> {
+    let layout = TyAndLayout { ty: (), layout: Layout(Interned::new_unchecked(data)) };
+    ArgAbi::new(layout, |_, _| ArgAttributes::new())
+}
+
+fn fn_abi<'a>(args: &[&'a TestLayoutData], ret: &'a TestLayoutData) -> FnAbi<'a, ()> {
+    FnAbi {
+        args: args.iter().map(|data| arg(data)).collect(),
+        ret: arg(ret),
+        c_variadic: false,
+        fixed_count: args.len() as u32,
+        conv: CanonAbi::Rust,
+        can_unwind: false,
+    }
+}
+
+#[test]
+fn narrow_scalars_are_backend_independent() {
+    let dl = TargetDataLayout::default();
+    let unit = TestLayoutData::unit(&dl, true);
+    let i64 = TestLayoutData::scalar(&dl, int(Integer::I64));
+    let pair = TestLayoutData::scalar_pair(&dl, int(Integer::I64), int(Integer::I32));
+
+    assert_eq!(
+        fn_abi(&[&i64, &pair, &unit], &pair).backend_lowered_abi(&dl),
+        BackendLoweredAbi::empty()
+    );
+}
+
+#[test]
+fn wide_scalars_are_backend_lowered() {
+    let dl = TargetDataLayout::default();
+    let unit = TestLayoutData::unit(&dl, true);
+    let i128 = TestLayoutData::scalar(&dl, int(Integer::I128));
+    let pair = TestLayoutData::scalar_pair(&dl, int(Integer::I8), int(Integer::I128));
+
+    assert_eq!(arg(&i128).backend_lowered_abi(&dl, false), BackendLoweredAbi::WIDE_SCALAR);
+    assert_eq!(arg(&pair).backend_lowered_abi(&dl, false), BackendLoweredAbi::WIDE_SCALAR);
+    assert_eq!(fn_abi(&[&i128], &unit).backend_lowered_abi(&dl), BackendLoweredAbi::WIDE_SCALAR);
+}
+
+#[test]
+fn wide_returns_are_backend_lowered() {
+    let dl = TargetDataLayout::default();
+    let i64 = TestLayoutData::scalar(&dl, int(Integer::I64));
+    // `(u8, u128)` does not fit in two pointers with the default data layout.
+    let pair = TestLayoutData::scalar_pair(&dl, int(Integer::I8), int(Integer::I128));
+
+    assert_eq!(
+        fn_abi(&[&i64], &pair).backend_lowered_abi(&dl),
+        BackendLoweredAbi::WIDE_SCALAR | BackendLoweredAbi::WIDE_RETURN
+    );
+}
+
+#[test]
+fn indirect_arguments_are_backend_independent() {
+    let dl = TargetDataLayout::default();
+    let i128 = TestLayoutData::scalar(&dl, int(Integer::I128));
+    let pair = TestLayoutData::scalar_pair(&dl, int(Integer::I8), int(Integer::I128));
+
+    let mut abi = fn_abi(&[&i128], &pair);
+    abi.args[0].make_indirect();
+    abi.ret.make_indirect();
+    assert_eq!(abi.backend_lowered_abi(&dl), BackendLoweredAbi::empty());
+}
+
+#[test]
+fn backend_lowered_abi_descr() {
+    assert_eq!(BackendLoweredAbi::SIMD_VECTOR.descr(), "SIMD vectors");
+    assert_eq!(
+        (BackendLoweredAbi::WIDE_SCALAR | BackendLoweredAbi::WIDE_RETURN).descr(),
+        "scalars wider than a pointer and return values wider than two pointers"
+    );
+}
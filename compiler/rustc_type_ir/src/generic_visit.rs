@@ -215,6 +215,9 @@ fn generic_visit_with(&self, _visitor: &mut V) {}
     rustc_abi::ExternAbi,
 );
 
+#[cfg(feature = "nightly")]
+trivial_impls!(rustc_data_structures::fx::FxBuildHasher);
+
 impl<I: Interner, V> GenericTypeVisitable<V> for crate::FnSigKind<I> {
     fn generic_visit_with(&self, _visitor: &mut V) {}
 }
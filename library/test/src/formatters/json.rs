@@ -6,7 +6,7 @@
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
 use crate::test_result::TestResult;
 use crate::time;
-use crate::types::TestDesc;
+use crate::types::{DocTestInfo, TestDesc};
 
 pub(crate) struct JsonFormatter<T> {
     out: OutputLocation<T>,
@@ -29,14 +29,15 @@ fn writeln_message(&mut self, s: &str) -> io::Result<()> {
     fn write_event(
         &mut self,
         ty: &str,
-        name: &str,
+        desc: &TestDesc,
         event: &str,
         exec_time: Option<&time::TestExecTime>,
         stdout: Option<Cow<'_, str>>,
         extra: Option<&str>,
     ) -> io::Result<()> {
         // A doc test's name includes a filename which must be escaped for correct json.
-        let name = EscapedString(name);
+        let name = EscapedString(desc.name.as_slice());
+        let doctest_json = doctest_location_json(desc);
         let exec_time_json = if let Some(exec_time) = exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
         } else {
@@ -52,7 +53,7 @@ fn write_event(
         let newline = "\n";
 
         self.writeln_message(&format!(
-                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
+                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{doctest_json}{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
     }
 }
 
@@ -63,24 +64,17 @@ fn write_discovery_start(&mut self) -> io::Result<()> {
 
     fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
         let TestDesc {
-            name,
-            ignore,
-            ignore_message,
-            source_file,
-            start_line,
-            start_col,
-            end_line,
-            end_col,
-            ..
+            name, ignore, ignore_message, start_line, start_col, end_line, end_col, ..
         } = desc;
 
         let name = EscapedString(name.as_slice());
         let ignore_message = ignore_message.unwrap_or("");
-        let source_path = EscapedString(source_file);
+        let source_path = EscapedString(desc.source_path());
+        let doctest_json = doctest_info_json(desc);
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col} }}{newline}"#
+            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col}{doctest_json} }}{newline}"#
         ))
     }
 
@@ -108,9 +102,10 @@ fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> i
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = EscapedString(desc.name.as_slice());
+        let doctest_json = doctest_location_json(desc);
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "test", "event": "started", "name": "{name}" }}{newline}"#
+            r#"{{ "type": "test", "event": "started", "name": "{name}"{doctest_json} }}{newline}"#
         ))
     }
 
@@ -129,17 +124,15 @@ fn write_result(
             None
         };
         match *result {
-            TestResult::TrOk => {
-                self.write_event("test", desc.name.as_slice(), "ok", exec_time, stdout, None)
-            }
+            TestResult::TrOk => self.write_event("test", desc, "ok", exec_time, stdout, None),
 
             TestResult::TrFailed => {
-                self.write_event("test", desc.name.as_slice(), "failed", exec_time, stdout, None)
+                self.write_event("test", desc, "failed", exec_time, stdout, None)
             }
 
             TestResult::TrTimedFail => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
                 stdout,
@@ -148,7 +141,7 @@ fn write_result(
 
             TestResult::TrFailedMsg(ref m) => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
                 stdout,
@@ -157,7 +150,7 @@ fn write_result(
 
             TestResult::TrIgnored => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "ignored",
                 exec_time,
                 stdout,
@@ -189,9 +182,10 @@ fn write_result(
 
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = EscapedString(desc.name.as_slice());
+        let doctest_json = doctest_location_json(desc);
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "test", "event": "timeout", "name": "{name}" }}{newline}"#,
+            r#"{{ "type": "test", "event": "timeout", "name": "{name}"{doctest_json} }}{newline}"#,
         ))
     }
 
@@ -228,6 +222,35 @@ fn write_merged_doctests_times(
     }
 }
 
+/// Extra fields describing a doctest, appended to its `discovered` event.
+///
+/// Empty for tests that aren't doctests.
+fn doctest_info_json(desc: &TestDesc) -> String {
+    let Some(DocTestInfo { item_path, edition, .. }) = &desc.doctest_info else {
+        return String::new();
+    };
+    let item_path = EscapedString(item_path);
+    format!(r#", "item_path": "{item_path}", "edition": "{edition}""#)
+}
+
+/// Extra fields describing a doctest, appended to its `started`, result and `timeout` events.
+///
+/// Unlike the `discovered` event, these events don't carry the source location of the test by
+/// default, so it is included here in order for tools to map results back to the doc comment.
+/// Empty for tests that aren't doctests.
+fn doctest_location_json(desc: &TestDesc) -> String {
+    if desc.doctest_info.is_none() {
+        return String::new();
+    }
+    let source_path = EscapedString(desc.source_path());
+    let start_line = desc.start_line;
+    let end_line = desc.end_line;
+    let doctest_info_json = doctest_info_json(desc);
+    format!(
+        r#", "source_path": "{source_path}", "start_line": {start_line}, "end_line": {end_line}{doctest_info_json}"#
+    )
+}
+
 /// A formatting utility used to print strings with characters in need of escaping.
 /// Base code taken form `libserialize::json::escape_str`
 struct EscapedString<S: AsRef<str>>(S);
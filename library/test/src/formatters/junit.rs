@@ -98,14 +98,16 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         ))?;
         for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
             let (class_name, test_name) = parse_class_name(&desc);
+            let location = location_attributes(&desc);
             match result {
                 TestResult::TrIgnored => { /* no-op */ }
                 TestResult::TrFailed => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\"{} time=\"{}\">",
                         class_name,
                         test_name,
+                        location,
                         duration.as_secs_f64()
                     ))?;
                     self.write_message("<failure type=\"assert\"/>")?;
@@ -120,9 +122,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrFailedMsg(ref m) => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\"{} time=\"{}\">",
                         class_name,
                         test_name,
+                        location,
                         duration.as_secs_f64()
                     ))?;
                     self.write_message(&format!("<failure message=\"{m}\" type=\"assert\"/>"))?;
@@ -137,9 +140,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrTimedFail => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\"{} time=\"{}\">",
                         class_name,
                         test_name,
+                        location,
                         duration.as_secs_f64()
                     ))?;
                     self.write_message("<failure type=\"timeout\"/>")?;
@@ -157,9 +161,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrOk => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\"",
+                         name=\"{}\"{} time=\"{}\"",
                         class_name,
                         test_name,
+                        location,
                         duration.as_secs_f64()
                     ))?;
                     if stdout.is_empty() || !state.options.display_output {
@@ -196,6 +201,34 @@ fn write_merged_doctests_times(
     }
 }
 
+/// Returns the `file` and `line` attributes of a `<testcase>` element.
+///
+/// These are only emitted for doctests, whose location would otherwise only be available as part
+/// of the test name. CI systems use them to annotate the doc comment the test comes from.
+fn location_attributes(desc: &TestDesc) -> String {
+    if desc.doctest_info.is_none() || desc.source_path().is_empty() {
+        return String::new();
+    }
+    let file = escape_attribute(desc.source_path());
+    format!(" file=\"{file}\" line=\"{}\"", desc.start_line)
+}
+
+/// Escapes the characters that can't appear verbatim in a quoted XML attribute value.
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 fn parse_class_name(desc: &TestDesc) -> (String, String) {
     match desc.test_type {
         TestType::UnitTest => parse_class_name_unit(desc),
@@ -54,6 +54,7 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                doctest_info: None,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -71,6 +72,7 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                doctest_info: None,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -96,6 +98,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -124,6 +127,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -153,6 +157,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -182,6 +187,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -216,6 +222,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -255,6 +262,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -287,6 +295,7 @@ fn f() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                doctest_info: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -320,6 +329,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -362,6 +372,7 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -406,6 +417,7 @@ fn typed_test_desc(test_type: TestType) -> TestDesc {
         compile_fail: false,
         no_run: false,
         test_type,
+        doctest_info: None,
     }
 }
 
@@ -523,6 +535,7 @@ fn exclude_should_panic_option() {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynTestFn(Box::new(move || Ok(()))),
     });
@@ -552,6 +565,7 @@ fn tests() -> TestList {
                     compile_fail: false,
                     no_run: false,
                     test_type: TestType::Unknown,
+                    doctest_info: None,
                 },
                 testfn: DynTestFn(Box::new(move || Ok(()))),
             })
@@ -649,6 +663,7 @@ fn testfn() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                doctest_info: None,
             },
             testfn: DynTestFn(Box::new(testfn)),
         };
@@ -783,6 +798,7 @@ fn f(_: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        doctest_info: None,
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -811,6 +827,7 @@ fn f(b: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        doctest_info: None,
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -832,6 +849,7 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        doctest_info: None,
     };
 
     let test_b = TestDesc {
@@ -847,6 +865,7 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        doctest_info: None,
     };
 
     let mut out = PrettyFormatter::new(OutputLocation::Raw(Vec::new()), false, 10, false, None);
@@ -899,6 +918,7 @@ fn f(_: &mut Bencher) -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            doctest_info: None,
         },
         testfn: DynBenchFn(Box::new(f)),
     };
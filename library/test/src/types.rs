@@ -207,6 +207,7 @@ pub struct TestDesc {
     pub compile_fail: bool,
     pub no_run: bool,
     pub test_type: TestType,
+    pub doctest_info: Option<DocTestInfo>,
 }
 
 impl TestDesc {
@@ -243,6 +244,31 @@ pub fn test_mode(&self) -> Option<&'static str> {
         }
         None
     }
+
+    /// Returns the path of the file the test is defined in, or an empty string if it isn't known.
+    pub fn source_path(&self) -> &str {
+        match &self.doctest_info {
+            Some(info) => &info.source_file,
+            None => self.source_file,
+        }
+    }
+}
+
+/// Structured metadata of a documentation test.
+///
+/// Doctest names (`src/lib.rs - foo (line 12)`) are meant for humans. Formatters use this to
+/// report the location of the doc comment and the item it is attached to without having to
+/// parse the name back.
+#[derive(Clone, Debug)]
+pub struct DocTestInfo {
+    /// Path of the file containing the doc comment. Rustdoc builds doctests at runtime, so unlike
+    /// [`TestDesc::source_file`] this isn't necessarily `'static`.
+    pub source_file: Cow<'static, str>,
+    /// Path of the documented item, e.g. `foo::Bar::baz`. Empty for crate-level doctests and
+    /// for doctests coming from Markdown files.
+    pub item_path: Cow<'static, str>,
+    /// Edition the doctest is compiled with, e.g. `2021`.
+    pub edition: &'static str,
 }
 
 #[derive(Debug)]
@@ -257,6 +283,9 @@ pub const fn new_doctest(
         ignore: bool,
         source_file: &'static str,
         start_line: usize,
+        end_line: usize,
+        item_path: &'static str,
+        edition: &'static str,
         no_run: bool,
         should_panic: bool,
         testfn: TestFn,
@@ -269,7 +298,7 @@ pub const fn new_doctest(
                 source_file,
                 start_line,
                 start_col: 0,
-                end_line: 0,
+                end_line,
                 end_col: 0,
                 compile_fail: false,
                 no_run,
@@ -279,6 +308,11 @@ pub const fn new_doctest(
                     options::ShouldPanic::No
                 },
                 test_type: TestType::DocTest,
+                doctest_info: Some(DocTestInfo {
+                    source_file: Cow::Borrowed(source_file),
+                    item_path: Cow::Borrowed(item_path),
+                    edition,
+                }),
             },
             testfn,
         }
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::io::Read;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::{fmt, io};
@@ -41,10 +42,12 @@ pub(crate) enum OutputFormat {
     Doctest,
 }
 
-/// Either an input crate, markdown file, or nothing (--merge=finalize).
+/// Either an input crate, markdown file, or nothing (--merge=finalize or --serve).
 pub(crate) enum InputMode {
     /// The `--merge=finalize` step does not need an input crate to rustdoc.
     NoInputMergeFinalize,
+    /// Serving an existing doc root with `--serve` does not need an input crate either.
+    NoInputServe,
     /// A crate or markdown file.
     HasFile(Input),
 }
@@ -155,6 +158,11 @@ pub(crate) struct Options {
     pub(crate) json_unused_externs: JsonUnusedExterns,
     /// Whether to skip capturing stdout and stderr of tests.
     pub(crate) no_capture: bool,
+    /// Whether to update the expected output of `check_output` doctests instead of failing.
+    pub(crate) bless: bool,
+    /// Whether to type-check doctests marked `ignore`, reporting those which don't compile through
+    /// the `rustdoc::broken_ignored_doctests` lint.
+    pub(crate) check_ignored_doctests: bool,
 
     /// Configuration for scraping examples from the current crate. If this option is Some(..) then
     /// the compiler will scrape examples and not generate documentation.
@@ -212,6 +220,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("remap-file-prefix", &self.remap_path_prefix)
             .field("remap-file-scope", &self.remap_path_scope)
             .field("no_capture", &self.no_capture)
+            .field("bless", &self.bless)
+            .field("check_ignored_doctests", &self.check_ignored_doctests)
             .field("scrape_examples_options", &self.scrape_examples_options)
             .field("unstable_features", &self.unstable_features)
             .finish()
@@ -276,6 +286,8 @@ pub(crate) struct RenderOptions {
     pub(crate) generate_redirect_map: bool,
     /// Show the memory layout of types in the docs.
     pub(crate) show_type_layout: bool,
+    /// Include the auto traits, layout and blanket impls of types in the JSON output.
+    pub(crate) show_type_facts: bool,
     /// Note: this field is duplicated in `Options` because it's useful to have
     /// it in both places.
     pub(crate) unstable_features: rustc_feature::UnstableFeatures,
@@ -297,6 +309,8 @@ pub(crate) struct RenderOptions {
     pub(crate) include_parts_dir: Vec<PathToParts>,
     /// Where to write crate-info
     pub(crate) parts_out_dir: Option<PathToParts>,
+    /// If present, serve the output directory over HTTP at this address once it is written.
+    pub(crate) serve: Option<SocketAddr>,
     /// disable minification of CSS/JS
     pub(crate) disable_minification: bool,
     /// If `true`, HTML source pages will generate the possibility to expand macros.
@@ -649,6 +663,14 @@ fn println_condition(condition: Condition) {
             dcx.fatal("deprecated parameter merge is used with new doc-meta-dir stuff");
         }
 
+        let serve = match matches.opt_str("serve").map(|addr| addr.parse::<SocketAddr>()) {
+            None => None,
+            Some(Ok(addr)) => Some(addr),
+            Some(Err(e)) => {
+                dcx.fatal(format!("--serve expects an address like `127.0.0.1:8000`: {e}"))
+            }
+        };
+
         let input = if describe_lints {
             InputMode::HasFile(make_input(early_dcx, ""))
         } else {
@@ -656,7 +678,9 @@ fn println_condition(condition: Condition) {
                 [] if !include_parts_dir.is_empty() && should_merge.write_rendered_cci => {
                     InputMode::NoInputMergeFinalize
                 }
+                [] if serve.is_some() => InputMode::NoInputServe,
                 [] => dcx.fatal("missing file operand"),
+                [_] if serve.is_some() => dcx.fatal("--serve cannot be used with an input file"),
                 [input] => InputMode::HasFile(make_input(early_dcx, input)),
                 _ => dcx.fatal("too many file operands"),
             }
@@ -886,7 +910,10 @@ fn println_condition(condition: Condition) {
         let run_check = matches.opt_present("check");
         let generate_redirect_map = matches.opt_present("generate-redirect-map");
         let show_type_layout = matches.opt_present("show-type-layout");
+        let show_type_facts = matches.opt_present("show-type-facts");
         let no_capture = matches.opt_present("no-capture");
+        let bless = matches.opt_present("bless");
+        let check_ignored_doctests = matches.opt_present("check-ignored-doctests");
         let generate_link_to_definition = matches.opt_present("generate-link-to-definition");
         let generate_macro_expansion = matches.opt_present("generate-macro-expansion");
         let extern_html_root_takes_precedence =
@@ -955,6 +982,8 @@ fn println_condition(condition: Condition) {
             remap_path_prefix,
             remap_path_scope,
             no_capture,
+            bless,
+            check_ignored_doctests,
             crate_name,
             output_format,
             json_unused_externs,
@@ -985,6 +1014,7 @@ fn println_condition(condition: Condition) {
             document_hidden,
             generate_redirect_map,
             show_type_layout,
+            show_type_facts,
             unstable_features,
             emit,
             generate_link_to_definition,
@@ -996,6 +1026,7 @@ fn println_condition(condition: Condition) {
             should_merge,
             include_parts_dir,
             parts_out_dir,
+            serve,
             disable_minification,
         };
         Some((input, options, render_options, loaded_paths))
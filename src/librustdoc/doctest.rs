@@ -1,9 +1,14 @@
+mod check_ignored;
+mod check_output;
 mod extracted;
 mod make;
 mod markdown;
 mod runner;
 mod rust;
 
+// The stage0 libtest doesn't know about doctest metadata yet.
+#[cfg(not(bootstrap))]
+use std::borrow::Cow;
 use std::fs::File;
 use std::hash::{Hash, Hasher};
 use std::io::{self, Write};
@@ -11,7 +16,7 @@
 use std::process::{self, Command, Stdio};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::{Duration, Instant};
 use std::{panic, str};
 
@@ -34,6 +39,8 @@
 use tempfile::{Builder as TempFileBuilder, TempDir};
 use tracing::{debug, info};
 
+use self::check_ignored::{BrokenIgnoredDocTest, BrokenIgnoredLevel};
+use self::check_output::{BlessedOutput, ExpectedOutput};
 use self::rust::HirCollector;
 use crate::config::{MergeDoctests, Options as RustdocOptions, OutputFormat};
 use crate::html::markdown::{CodeLineMapping, ErrorCodes, Ignore, LangString, MdRelLine};
@@ -136,16 +143,20 @@ fn get_doctest_dir(opts: &RustdocOptions) -> io::Result<TempDir> {
 
 pub(crate) fn run(dcx: DiagCtxtHandle<'_>, input: Input, options: RustdocOptions) {
     let invalid_codeblock_attributes_name = crate::lint::INVALID_CODEBLOCK_ATTRIBUTES.name;
+    let broken_ignored_doctests_name = crate::lint::BROKEN_IGNORED_DOCTESTS.name;
 
     // See core::create_config for what's going on here.
     let allowed_lints = vec![
         invalid_codeblock_attributes_name.to_owned(),
+        broken_ignored_doctests_name.to_owned(),
         lint::builtin::UNKNOWN_LINTS.name.to_owned(),
         lint::builtin::RENAMED_AND_REMOVED_LINTS.name.to_owned(),
     ];
 
     let (lint_opts, lint_caps) = init_lints(allowed_lints, options.lint_opts.clone(), |lint| {
-        if lint.name == invalid_codeblock_attributes_name {
+        if lint.name == invalid_codeblock_attributes_name
+            || lint.name == broken_ignored_doctests_name
+        {
             None
         } else {
             Some((lint.name_lower(), lint::Allow))
@@ -259,6 +270,8 @@ pub(crate) fn run(dcx: DiagCtxtHandle<'_>, input: Input, options: RustdocOptions
         rustdoc_options,
         opts,
         unused_extern_reports,
+        blessed_outputs,
+        broken_ignored_tests,
         compiling_test_count,
         ..
     } = match result {
@@ -280,6 +293,8 @@ pub(crate) fn run(dcx: DiagCtxtHandle<'_>, input: Input, options: RustdocOptions
         opts,
         &rustdoc_options,
         &unused_extern_reports,
+        &blessed_outputs,
+        &broken_ignored_tests,
         standalone_tests,
         mergeable_tests,
         Some(temp_dir),
@@ -332,6 +347,8 @@ pub(crate) fn run_tests(
     opts: GlobalTestOptions,
     rustdoc_options: &Arc<RustdocOptions>,
     unused_extern_reports: &Arc<Mutex<Vec<UnusedExterns>>>,
+    blessed_outputs: &Arc<Mutex<Vec<BlessedOutput>>>,
+    broken_ignored_tests: &Arc<Mutex<Vec<BrokenIgnoredDocTest>>>,
     mut standalone_tests: Vec<test::TestDescAndFn>,
     mergeable_tests: FxIndexMap<MergeableTestKey, Vec<(DocTestBuilder, ScrapedDocTest)>>,
     // We pass this argument so we can drop it manually before using `exit`.
@@ -364,7 +381,7 @@ pub(crate) fn run_tests(
         );
 
         for (doctest, scraped_test) in &doctests {
-            tests_runner.add_test(doctest, scraped_test, &target_str);
+            tests_runner.add_test(doctest, scraped_test, &target_str, edition);
         }
         let (duration, ret) = tests_runner.run_merged_tests(
             rustdoc_test_options,
@@ -404,6 +421,8 @@ pub(crate) fn run_tests(
                 opts.clone(),
                 Arc::clone(rustdoc_options),
                 unused_extern_reports.clone(),
+                blessed_outputs.clone(),
+                broken_ignored_tests.clone(),
             ));
         }
     }
@@ -413,6 +432,8 @@ pub(crate) fn run_tests(
     if ran_edition_tests == 0 || !standalone_tests.is_empty() {
         standalone_tests.sort_by(|a, b| a.desc.name.as_slice().cmp(b.desc.name.as_slice()));
         test::test_main_with_exit_callback(&test_args, standalone_tests, None, || {
+            check_output::bless(std::mem::take(&mut blessed_outputs.lock().unwrap()));
+            check_ignored::report(dcx, std::mem::take(&mut broken_ignored_tests.lock().unwrap()));
             let times = times.times_in_secs();
             // We ensure temp dir destructor is called.
             std::mem::drop(temp_dir.take());
@@ -490,6 +511,15 @@ enum TestFailure {
     ExecutionFailure(process::Output),
     /// The test is marked `should_panic` but the test binary executed successfully.
     UnexpectedRunPass,
+    /// The test is marked `check_output` but isn't followed by a code block.
+    MissingExpectedOutput,
+    /// The test is marked `check_output` and its stdout didn't match the expected output.
+    OutputMismatch(process::Output),
+    /// The test is marked `ignore` and failed to type-check with `--check-ignored-doctests`.
+    ///
+    /// Contains the output of the compiler, which is either reported once all tests ran or
+    /// printed as a test failure, depending on the level of `rustdoc::broken_ignored_doctests`.
+    IgnoredCompileError(String),
 }
 
 enum DirState {
@@ -556,6 +586,9 @@ pub(crate) struct RunnableDocTest {
     edition: Edition,
     no_run: bool,
     merged_test_code: Option<String>,
+    expected_output: Option<ExpectedOutput>,
+    /// Whether the test is marked `ignore` and only type-checked.
+    check_only: bool,
 }
 
 impl RunnableDocTest {
@@ -611,7 +644,11 @@ fn run_test(
         compiler_args.extend_from_slice(&["-Z".to_owned(), "unstable-options".to_owned()]);
     }
 
-    if doctest.no_run && !langstr.compile_fail && rustdoc_options.persist_doctests.is_none() {
+    if doctest.check_only {
+        // Ignored doctests are only type-checked, even when persisting doctests.
+        compiler_args.push("--emit=metadata".to_owned());
+    } else if doctest.no_run && !langstr.compile_fail && rustdoc_options.persist_doctests.is_none()
+    {
         // FIXME: why does this code check if it *shouldn't* persist doctests
         //        -- shouldn't it be the negation?
         compiler_args.push("--emit=metadata".to_owned());
@@ -811,6 +848,10 @@ fn drop(&mut self) {
         out.push('\n');
     }
 
+    if doctest.check_only && !output.status.success() {
+        return (instant.elapsed(), Err(TestFailure::IgnoredCompileError(out)));
+    }
+
     let _bomb = Bomb(&out);
     match (output.status.success(), langstr.compile_fail) {
         (true, true) => {
@@ -843,6 +884,9 @@ fn drop(&mut self) {
     if doctest.no_run {
         return (duration, Ok(()));
     }
+    if langstr.check_output && doctest.expected_output.is_none() {
+        return (duration, Err(TestFailure::MissingExpectedOutput));
+    }
 
     // Run the code!
     let mut cmd;
@@ -865,7 +909,10 @@ fn drop(&mut self) {
 
     info!("running doctest executable: {cmd:?}");
 
-    let result = if doctest.is_multiple_tests() || rustdoc_options.no_capture {
+    // The output of `check_output` tests is always captured, since it needs to be compared.
+    let result = if doctest.is_multiple_tests()
+        || (rustdoc_options.no_capture && doctest.expected_output.is_none())
+    {
         cmd.status().map(|status| process::Output {
             status,
             stdout: Vec::new(),
@@ -881,6 +928,10 @@ fn drop(&mut self) {
                 return (duration, Err(TestFailure::UnexpectedRunPass));
             } else if !langstr.should_panic && !out.status.success() {
                 return (duration, Err(TestFailure::ExecutionFailure(out)));
+            } else if let Some(expected_output) = &doctest.expected_output
+                && !expected_output.matches(&String::from_utf8_lossy(&out.stdout))
+            {
+                return (duration, Err(TestFailure::OutputMismatch(out)));
             }
         }
     }
@@ -942,9 +993,15 @@ pub(crate) struct ScrapedDocTest {
     langstr: LangString,
     text: String,
     name: String,
+    /// Path of the documented item, e.g. `foo::Bar`. Empty for crate-level and Markdown doctests.
+    item_path: String,
     span: Span,
     code_mappings: Vec<CodeLineMapping>,
     global_crate_attrs: Vec<String>,
+    /// The code block following this doctest, if it is marked `check_output`.
+    expected_output: Option<ExpectedOutput>,
+    /// The level of `rustdoc::broken_ignored_doctests` for this doctest, if it is marked `ignore`.
+    broken_ignored_level: BrokenIgnoredLevel,
 }
 
 impl ScrapedDocTest {
@@ -960,22 +1017,47 @@ fn new(
     ) -> Self {
         let mut item_path = logical_path.join("::");
         item_path.retain(|c| c != ' ');
-        if !item_path.is_empty() {
-            item_path.push(' ');
+        let filename_display = filename.display(RemapPathScopeComponents::DOCUMENTATION);
+        let name = if item_path.is_empty() {
+            format!("{filename_display} - (line {line})")
+        } else {
+            format!("{filename_display} - {item_path} (line {line})")
+        };
+
+        Self {
+            filename,
+            line,
+            langstr,
+            text,
+            name,
+            item_path,
+            span,
+            code_mappings,
+            global_crate_attrs,
+            expected_output: None,
+            broken_ignored_level: BrokenIgnoredLevel::default(),
         }
-        let name = format!(
-            "{} - {item_path}(line {line})",
-            filename.display(RemapPathScopeComponents::DOCUMENTATION)
-        );
+    }
 
-        Self { filename, line, langstr, text, name, span, code_mappings, global_crate_attrs }
+    /// Line of the fence closing the code block, i.e. the last line of the doctest in its source
+    /// file.
+    fn end_line(&self) -> usize {
+        self.line + self.text.lines().count() + 1
     }
     fn edition(&self, opts: &RustdocOptions) -> Edition {
         self.langstr.edition.unwrap_or(opts.edition)
     }
 
     fn no_run(&self, opts: &RustdocOptions) -> bool {
-        self.langstr.no_run || opts.no_run
+        self.langstr.no_run || opts.no_run || self.check_only(opts)
+    }
+
+    /// Whether this test is marked `ignore` but should be type-checked anyway.
+    fn check_only(&self, opts: &RustdocOptions) -> bool {
+        opts.check_ignored_doctests
+            && self.langstr.ignore == Ignore::All
+            && !self.langstr.compile_fail
+            && self.broken_ignored_level.is_checked()
     }
 
     fn path(&self) -> PathBuf {
@@ -996,6 +1078,8 @@ fn visit_test(
         rel_line: MdRelLine,
         code_mappings: Vec<CodeLineMapping>,
     );
+    /// Called with the code block following a doctest marked `check_output`.
+    fn visit_expected_output(&mut self, _output: String, _rel_line: MdRelLine) {}
     fn visit_header(&mut self, _name: &str, _level: u32) {}
 }
 
@@ -1013,6 +1097,10 @@ struct CreateRunnableDocTests {
     opts: GlobalTestOptions,
     visited_tests: FxHashMap<(String, usize), usize>,
     unused_extern_reports: Arc<Mutex<Vec<UnusedExterns>>>,
+    /// Expected outputs to rewrite once all tests ran, when running with `--bless`.
+    blessed_outputs: Arc<Mutex<Vec<BlessedOutput>>>,
+    /// Tests to report once all tests ran, when running with `--check-ignored-doctests`.
+    broken_ignored_tests: Arc<Mutex<Vec<BrokenIgnoredDocTest>>>,
     compiling_test_count: AtomicUsize,
     can_merge_doctests: MergeDoctests,
 }
@@ -1025,6 +1113,8 @@ fn new(rustdoc_options: RustdocOptions, opts: GlobalTestOptions) -> CreateRunnab
             opts,
             visited_tests: FxHashMap::default(),
             unused_extern_reports: Default::default(),
+            blessed_outputs: Default::default(),
+            broken_ignored_tests: Default::default(),
             compiling_test_count: AtomicUsize::new(0),
             can_merge_doctests: rustdoc_options.merge_doctests,
             rustdoc_options: Arc::new(rustdoc_options),
@@ -1069,6 +1159,7 @@ fn add_test(&mut self, scraped_test: ScrapedDocTest, dcx: Option<DiagCtxtHandle<
             .code_mappings(&scraped_test.code_mappings)
             .build(dcx);
         let is_standalone = !doctest.can_be_merged
+            || scraped_test.check_only(&self.rustdoc_options)
             || self.rustdoc_options.no_capture
             || self.rustdoc_options.test_args.iter().any(|arg| arg == "--show-output");
         if is_standalone {
@@ -1104,6 +1195,8 @@ fn generate_test_desc_and_fn(
             self.opts.clone(),
             Arc::clone(&self.rustdoc_options),
             self.unused_extern_reports.clone(),
+            self.blessed_outputs.clone(),
+            self.broken_ignored_tests.clone(),
         )
     }
 }
@@ -1114,31 +1207,41 @@ fn generate_test_desc_and_fn(
     opts: GlobalTestOptions,
     rustdoc_options: Arc<RustdocOptions>,
     unused_externs: Arc<Mutex<Vec<UnusedExterns>>>,
+    blessed_outputs: Arc<Mutex<Vec<BlessedOutput>>>,
+    broken_ignored_tests: Arc<Mutex<Vec<BrokenIgnoredDocTest>>>,
 ) -> test::TestDescAndFn {
     let target_str = rustdoc_options.target.to_string();
     let rustdoc_test_options =
         IndividualTestOptions::new(&rustdoc_options, &test.test_id, scraped_test.path());
 
     debug!("creating test {}: {}", scraped_test.name, scraped_test.text);
+    let source_file = static_source_file(&scraped_test.path());
     test::TestDescAndFn {
         desc: test::TestDesc {
             name: test::DynTestName(scraped_test.name.clone()),
             ignore: match scraped_test.langstr.ignore {
-                Ignore::All => true,
+                Ignore::All => !scraped_test.check_only(&rustdoc_options),
                 Ignore::None => false,
                 Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
             },
             ignore_message: None,
-            source_file: "",
-            start_line: 0,
+            source_file,
+            start_line: scraped_test.line,
             start_col: 0,
-            end_line: 0,
+            end_line: scraped_test.end_line(),
             end_col: 0,
             // compiler failures are test failures
             should_panic: test::ShouldPanic::No,
             compile_fail: scraped_test.langstr.compile_fail,
             no_run: scraped_test.no_run(&rustdoc_options),
             test_type: test::TestType::DocTest,
+            // The stage0 libtest doesn't know about doctest metadata yet.
+            #[cfg(not(bootstrap))]
+            doctest_info: Some(test::DocTestInfo {
+                source_file: Cow::Borrowed(source_file),
+                item_path: Cow::Owned(scraped_test.item_path.clone()),
+                edition: scraped_test.edition(&rustdoc_options).as_str(),
+            }),
         },
         testfn: test::DynTestFn(Box::new(move || {
             doctest_run_fn(
@@ -1148,11 +1251,28 @@ fn generate_test_desc_and_fn(
                 scraped_test,
                 rustdoc_options,
                 unused_externs,
+                blessed_outputs,
+                broken_ignored_tests,
             )
         })),
     }
 }
 
+/// `TestDesc` only stores `'static` strings, so the path of each file with doctests is leaked,
+/// once, however many doctests it has.
+fn static_source_file(path: &Path) -> &'static str {
+    static SOURCE_FILES: LazyLock<Mutex<FxHashSet<&'static str>>> = LazyLock::new(Default::default);
+
+    let path = path.display().to_string();
+    let mut source_files = SOURCE_FILES.lock().unwrap();
+    if let Some(&source_file) = source_files.get(&*path) {
+        return source_file;
+    }
+    let source_file = path.leak();
+    source_files.insert(source_file);
+    source_file
+}
+
 fn doctest_run_fn(
     test_opts: IndividualTestOptions,
     global_opts: GlobalTestOptions,
@@ -1160,6 +1280,8 @@ fn doctest_run_fn(
     scraped_test: ScrapedDocTest,
     rustdoc_options: Arc<RustdocOptions>,
     unused_externs: Arc<Mutex<Vec<UnusedExterns>>>,
+    blessed_outputs: Arc<Mutex<Vec<BlessedOutput>>>,
+    broken_ignored_tests: Arc<Mutex<Vec<BrokenIgnoredDocTest>>>,
 ) -> Result<(), String> {
     let report_unused_externs = |uext| {
         unused_externs.lock().unwrap().push(uext);
@@ -1180,6 +1302,8 @@ fn doctest_run_fn(
         edition: scraped_test.edition(&rustdoc_options),
         no_run: scraped_test.no_run(&rustdoc_options),
         merged_test_code: None,
+        expected_output: scraped_test.expected_output.clone(),
+        check_only: scraped_test.check_only(&rustdoc_options),
     };
     let (_, res) =
         run_test(runnable_test, &rustdoc_options, doctest.supports_color, report_unused_externs);
@@ -1195,6 +1319,60 @@ fn doctest_run_fn(
             TestFailure::UnexpectedRunPass => {
                 eprint!("Test executable succeeded, but it's marked `should_panic`.");
             }
+            TestFailure::MissingExpectedOutput => {
+                eprint!(
+                    "Test is marked `check_output`, but isn't followed by a code block with the \
+                    expected output."
+                );
+            }
+            TestFailure::OutputMismatch(out) => {
+                let actual = String::from_utf8_lossy(&out.stdout).into_owned();
+                let expected = scraped_test.expected_output.unwrap();
+                if rustdoc_options.bless
+                    && let Some(line) = expected.line
+                {
+                    blessed_outputs.lock().unwrap().push(BlessedOutput {
+                        path: scraped_test.path(),
+                        line,
+                        expected: expected.text,
+                        actual,
+                    });
+                    return Ok(());
+                }
+                match expected.line {
+                    Some(line) => {
+                        eprintln!("Test output doesn't match the expected output at line {line}.")
+                    }
+                    None => eprintln!("Test output doesn't match the expected output."),
+                }
+                eprintln!("\nexpected:\n{}", expected.text.trim_end());
+                eprintln!("\nactual:\n{}", actual.trim_end());
+                if expected.line.is_some() {
+                    eprint!("\nRun with `--bless` to update the expected output.");
+                } else {
+                    eprint!(
+                        "\nThe expected output can't be blessed, as the documentation doesn't \
+                        come from doc comments."
+                    );
+                }
+            }
+            TestFailure::IgnoredCompileError(errors) => {
+                let level = scraped_test.broken_ignored_level;
+                if !level.is_error() {
+                    broken_ignored_tests.lock().unwrap().push(BrokenIgnoredDocTest {
+                        name: scraped_test.name,
+                        level,
+                        errors,
+                    });
+                    return Ok(());
+                }
+                eprint!("{errors}");
+                eprint!(
+                    "Test is marked `ignore`, but doesn't compile against the current API, and \
+                    `{}` is denied.",
+                    crate::lint::BROKEN_IGNORED_DOCTESTS.name_lower(),
+                );
+            }
             TestFailure::MissingErrorCodes(codes) => {
                 eprint!("Some expected error codes were not found: {codes:?}");
             }
@@ -0,0 +1,91 @@
+//! Support for `--check-ignored-doctests`.
+//!
+//! Doctests marked `ignore` are never compiled, so nothing tells when they stop matching the API
+//! they document. With this flag, they are type-checked without being linked nor run, and the
+//! ones which don't compile are reported through the `rustdoc::broken_ignored_doctests` lint: as
+//! warnings once all doctests ran by default, or as test failures where the lint is denied.
+
+use rustc_errors::DiagCtxtHandle;
+use rustc_lint::Level;
+use rustc_middle::lint::{LintLevelSource, StableLevelSpec};
+
+use crate::config::Options;
+use crate::lint::BROKEN_IGNORED_DOCTESTS;
+
+/// The level of the `rustdoc::broken_ignored_doctests` lint where a doctest is written.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BrokenIgnoredLevel {
+    pub(crate) level: Level,
+    /// Whether the level is the lint's default one, and wasn't set by an attribute or on the
+    /// command line.
+    pub(crate) is_default: bool,
+}
+
+impl Default for BrokenIgnoredLevel {
+    fn default() -> Self {
+        BrokenIgnoredLevel { level: BROKEN_IGNORED_DOCTESTS.default_level, is_default: true }
+    }
+}
+
+impl BrokenIgnoredLevel {
+    pub(crate) fn from_spec(spec: StableLevelSpec) -> Self {
+        BrokenIgnoredLevel {
+            level: spec.level(),
+            is_default: matches!(spec.src, LintLevelSource::Default),
+        }
+    }
+
+    /// The level set with `-A`/`-W`/`-D`/`-F` on the command line, for doctests which aren't
+    /// attached to any item, like the ones of Markdown files.
+    pub(crate) fn from_command_line(options: &Options) -> Self {
+        let name = BROKEN_IGNORED_DOCTESTS.name_lower();
+        let mut level = options
+            .lint_opts
+            .iter()
+            .rev()
+            .find(|(lint, _)| *lint == name || lint == "rustdoc::all")
+            .map_or_else(Self::default, |&(_, level)| BrokenIgnoredLevel {
+                level,
+                is_default: false,
+            });
+        if let Some(cap) = options.lint_cap {
+            level.level = level.level.min(cap);
+        }
+        level
+    }
+
+    /// Whether the doctests marked `ignore` should be type-checked at all.
+    pub(crate) fn is_checked(self) -> bool {
+        !matches!(self.level, Level::Allow | Level::Expect)
+    }
+
+    /// Whether a doctest marked `ignore` which doesn't compile fails instead of being reported
+    /// once all doctests ran.
+    pub(crate) fn is_error(self) -> bool {
+        matches!(self.level, Level::Deny | Level::Forbid)
+    }
+}
+
+/// A doctest marked `ignore` which doesn't compile, where the lint only warns.
+#[derive(Debug)]
+pub(crate) struct BrokenIgnoredDocTest {
+    pub(crate) name: String,
+    pub(crate) level: BrokenIgnoredLevel,
+    /// The diagnostics emitted by the compiler.
+    pub(crate) errors: String,
+}
+
+/// Emits a `rustdoc::broken_ignored_doctests` warning for each doctest marked `ignore` which
+/// doesn't compile.
+pub(crate) fn report(dcx: DiagCtxtHandle<'_>, mut tests: Vec<BrokenIgnoredDocTest>) {
+    tests.sort_by(|a, b| a.name.cmp(&b.name));
+    for test in tests {
+        let mut diag =
+            dcx.struct_warn(format!("doctest marked `ignore` doesn't compile: {}", test.name));
+        if test.level.is_default {
+            diag.note(format!("`#[warn({})]` on by default", BROKEN_IGNORED_DOCTESTS.name_lower()));
+        }
+        diag.emit();
+        eprintln!("{}\n", test.errors.trim_end());
+    }
+}
@@ -0,0 +1,117 @@
+//! Support for doctests marked `check_output`.
+//!
+//! The stdout of such a doctest is compared against the code block following it in the
+//! documentation. With `--bless`, mismatching blocks are rewritten in the source files instead.
+
+use std::fs;
+use std::path::PathBuf;
+
+/// The code block holding the expected output of a `check_output` doctest.
+#[derive(Clone, Debug)]
+pub(crate) struct ExpectedOutput {
+    /// The contents of the code block.
+    pub(crate) text: String,
+    /// The line of the fence opening the code block in the source file.
+    ///
+    /// `None` if the documentation doesn't come from doc comments, e.g. with
+    /// `#[doc = include_str!("README.md")]`: the lines of the documentation then don't map to
+    /// lines of the file the doctest is reported in, so the block can't be blessed.
+    pub(crate) line: Option<usize>,
+}
+
+impl ExpectedOutput {
+    /// Returns whether `actual` matches the expected output.
+    ///
+    /// Trailing whitespace is ignored, both at the end of each line and at the end of the output.
+    pub(crate) fn matches(&self, actual: &str) -> bool {
+        normalize(&self.text) == normalize(actual)
+    }
+}
+
+fn normalize(output: &str) -> String {
+    let mut normalized = output.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
+
+/// An expected output block to be updated with the actual output of its doctest.
+///
+/// These are collected while running the doctests and applied once all of them are done: several
+/// doctests can live in the same file and rewriting one block shifts the lines of the next ones.
+#[derive(Debug)]
+pub(crate) struct BlessedOutput {
+    pub(crate) path: PathBuf,
+    /// The line of the fence opening the expected output block.
+    pub(crate) line: usize,
+    /// The current contents of the expected output block.
+    pub(crate) expected: String,
+    pub(crate) actual: String,
+}
+
+/// Rewrites the expected output blocks in their source files.
+pub(crate) fn bless(mut outputs: Vec<BlessedOutput>) {
+    // Apply the edits of each file from the bottom up, so that the recorded lines of the blocks
+    // which haven't been rewritten yet stay valid.
+    outputs.sort_by(|a, b| a.path.cmp(&b.path).then(b.line.cmp(&a.line)));
+    for outputs in outputs.chunk_by(|a, b| a.path == b.path) {
+        let path = &outputs[0].path;
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                eprintln!("Couldn't read `{}` to bless doctest output: {error}", path.display());
+                continue;
+            }
+        };
+        let mut lines = contents.split_inclusive('\n').map(str::to_owned).collect::<Vec<_>>();
+        for output in outputs {
+            if let Err(error) = replace_block(&mut lines, output) {
+                eprintln!(
+                    "Couldn't bless the expected output at {}:{}: {error}",
+                    path.display(),
+                    output.line,
+                );
+            }
+        }
+        if let Err(error) = fs::write(path, lines.concat()) {
+            eprintln!("Couldn't write `{}` to bless doctest output: {error}", path.display());
+            continue;
+        }
+        eprintln!("Blessed the output of {} doctest(s) in `{}`", outputs.len(), path.display());
+    }
+}
+
+/// Replaces the contents of the code block opened on `output.line` with the actual
+/// output, keeping the prefix of the opening fence (e.g. `/// `) on every line.
+fn replace_block(lines: &mut Vec<String>, output: &BlessedOutput) -> Result<(), &'static str> {
+    let fence_index = output.line - 1;
+    let fence = lines.get(fence_index).ok_or("the code block is past the end of the file")?;
+    let prefix_len = fence
+        .find("```")
+        .or_else(|| fence.find("~~~"))
+        .ok_or("the code block fence couldn't be found")?;
+    let prefix = &fence[..prefix_len];
+    let line_ending = if fence.ends_with("\r\n") { "\r\n" } else { "\n" };
+
+    let old_len = output.expected.lines().count();
+    let closing_fence =
+        lines.get(fence_index + 1 + old_len).ok_or("the code block is past the end of the file")?;
+    if !closing_fence.starts_with(prefix)
+        || !(closing_fence[prefix_len..].starts_with("```")
+            || closing_fence[prefix_len..].starts_with("~~~"))
+    {
+        return Err("the source file changed since the doctests were collected");
+    }
+
+    let new_lines = normalize(&output.actual)
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                format!("{}{line_ending}", prefix.trim_end())
+            } else {
+                format!("{prefix}{line}{line_ending}")
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.splice(fence_index + 1..fence_index + 1 + old_len, new_lines);
+    Ok(())
+}
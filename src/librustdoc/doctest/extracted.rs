@@ -17,7 +17,7 @@
 /// This integer is incremented with every breaking change to the API,
 /// and is returned along with the JSON blob into the `format_version` root field.
 /// Consuming code should assert that this value matches the format version(s) that it supports.
-const FORMAT_VERSION: u32 = 2;
+const FORMAT_VERSION: u32 = 3;
 
 #[derive(Serialize)]
 pub(crate) struct ExtractedDocTests {
@@ -146,6 +146,7 @@ struct LangString {
     pub(crate) test_harness: bool,
     pub(crate) compile_fail: bool,
     pub(crate) standalone_crate: bool,
+    pub(crate) check_output: bool,
     pub(crate) error_codes: Vec<String>,
     pub(crate) edition: Option<String>,
     pub(crate) added_css_classes: Vec<String>,
@@ -163,6 +164,7 @@ fn from(original: markdown::LangString) -> Self {
             test_harness,
             compile_fail,
             standalone_crate,
+            check_output,
             error_codes,
             edition,
             added_classes,
@@ -178,6 +180,7 @@ fn from(original: markdown::LangString) -> Self {
             test_harness,
             compile_fail,
             standalone_crate,
+            check_output,
             error_codes,
             edition: edition.map(|edition| edition.to_string()),
             added_css_classes: added_classes,
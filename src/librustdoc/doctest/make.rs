@@ -166,9 +166,13 @@ pub(crate) fn build(self, dcx: Option<DiagCtxtHandle<'_>>) -> DocTestBuilder {
         // Up until now, we've been dealing with settings for the whole crate.
         // Now, infer settings for this particular test.
         //
-        // Avoid tests with incompatible attributes.
+        // Avoid tests with incompatible attributes. The output of merged doctests isn't captured
+        // separately, so `check_output` tests have to run on their own as well.
         let opt_out = lang_str.is_some_and(|lang_str| {
-            lang_str.compile_fail || lang_str.test_harness || lang_str.standalone_crate
+            lang_str.compile_fail
+                || lang_str.test_harness
+                || lang_str.standalone_crate
+                || lang_str.check_output
         });
         let can_be_merged = if can_merge_doctests == MergeDoctests::Auto {
             // We try to look at the contents of the test to detect whether it should be merged.
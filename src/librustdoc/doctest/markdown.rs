@@ -9,8 +9,10 @@
 use rustc_span::{DUMMY_SP, FileName, RealFileName};
 use tempfile::tempdir;
 
+use super::check_ignored::BrokenIgnoredLevel;
 use super::{
-    CreateRunnableDocTests, DocTestVisitor, GlobalTestOptions, ScrapedDocTest, generate_args_file,
+    CreateRunnableDocTests, DocTestVisitor, ExpectedOutput, GlobalTestOptions, ScrapedDocTest,
+    generate_args_file,
 };
 use crate::config::Options;
 use crate::html::markdown::{
@@ -21,6 +23,8 @@ struct MdCollector {
     tests: Vec<ScrapedDocTest>,
     cur_path: Vec<String>,
     filename: FileName,
+    /// Markdown files have no items to set lint levels on, so only the command line sets it.
+    broken_ignored_level: BrokenIgnoredLevel,
 }
 
 impl DocTestVisitor for MdCollector {
@@ -34,7 +38,7 @@ fn visit_test(
         let filename = self.filename.clone();
         // First line of Markdown is line 1.
         let line = 1 + rel_line.offset();
-        self.tests.push(ScrapedDocTest::new(
+        let mut test = ScrapedDocTest::new(
             filename,
             line,
             self.cur_path.clone(),
@@ -43,7 +47,17 @@ fn visit_test(
             DUMMY_SP,
             code_mappings,
             Vec::new(),
-        ));
+        );
+        test.broken_ignored_level = self.broken_ignored_level;
+        self.tests.push(test);
+    }
+
+    fn visit_expected_output(&mut self, output: String, rel_line: MdRelLine) {
+        // First line of Markdown is line 1.
+        let line = 1 + rel_line.offset();
+        if let Some(test) = self.tests.last_mut() {
+            test.expected_output = Some(ExpectedOutput { text: output, line: Some(line) });
+        }
     }
 
     fn visit_header(&mut self, name: &str, level: u32) {
@@ -123,6 +137,7 @@ pub(crate) fn test(input: &Input, options: Options, dcx: DiagCtxtHandle<'_>) ->
                 FileName::Real(file_mapping.to_real_filename(&RealFileName::empty(), f))
             })
             .unwrap_or(FileName::Custom("input".to_owned())),
+        broken_ignored_level: BrokenIgnoredLevel::from_command_line(&options),
     };
     let codes = ErrorCodes::from(options.unstable_features.is_nightly_build());
 
@@ -130,13 +145,22 @@ pub(crate) fn test(input: &Input, options: Options, dcx: DiagCtxtHandle<'_>) ->
 
     let mut collector = CreateRunnableDocTests::new(options.clone(), opts);
     md_collector.tests.into_iter().for_each(|t| collector.add_test(t, None));
-    let CreateRunnableDocTests { opts, rustdoc_options, standalone_tests, mergeable_tests, .. } =
-        collector;
+    let CreateRunnableDocTests {
+        opts,
+        rustdoc_options,
+        standalone_tests,
+        mergeable_tests,
+        blessed_outputs,
+        broken_ignored_tests,
+        ..
+    } = collector;
     crate::doctest::run_tests(
         dcx,
         opts,
         &rustdoc_options,
         &Arc::new(Mutex::new(Vec::new())),
+        &blessed_outputs,
+        &broken_ignored_tests,
         standalone_tests,
         mergeable_tests,
         None,
@@ -39,6 +39,7 @@ pub(crate) fn add_test(
         doctest: &DocTestBuilder,
         scraped_test: &ScrapedDocTest,
         target_str: &str,
+        edition: Edition,
     ) {
         let ignore = match scraped_test.langstr.ignore {
             Ignore::All => true,
@@ -59,6 +60,7 @@ pub(crate) fn add_test(
                 doctest,
                 scraped_test,
                 ignore,
+                edition,
                 self.nb_tests,
                 &mut self.output,
                 &mut self.output_merged_tests,
@@ -208,6 +210,8 @@ fn main() -> std::process::ExitCode {{
             edition,
             no_run: false,
             merged_test_code: Some(code),
+            expected_output: None,
+            check_only: false,
         };
         let (duration, ret) =
             run_test(runnable_test, rustdoc_options, self.supports_color, |_: UnusedExterns| {});
@@ -220,6 +224,7 @@ fn generate_mergeable_doctest(
     doctest: &DocTestBuilder,
     scraped_test: &ScrapedDocTest,
     ignore: bool,
+    edition: Edition,
     id: usize,
     output: &mut String,
     output_merged_tests: &mut String,
@@ -262,7 +267,8 @@ fn main() {returns_result} {{
         "
 mod {test_id} {{
 pub const TEST: test::TestDescAndFn = test::TestDescAndFn::new_doctest(
-{test_name:?}, {ignore}, {file:?}, {line}, {no_run}, {should_panic},
+{test_name:?}, {ignore}, {file:?}, {line}, {end_line}, {item_path:?}, {edition:?},
+{no_run}, {should_panic},
 test::StaticTestFn(
     || {{{runner}}},
 ));
@@ -270,6 +276,9 @@ mod {test_id} {{
         test_name = scraped_test.name,
         file = scraped_test.path(),
         line = scraped_test.line,
+        end_line = scraped_test.end_line(),
+        item_path = scraped_test.item_path,
+        edition = edition.as_str(),
         no_run = scraped_test.langstr.no_run,
         should_panic = !scraped_test.langstr.no_run && scraped_test.langstr.should_panic,
         // Setting `no_run` to `true` in `TestDesc` still makes the test run, so we simply
@@ -15,7 +15,8 @@
 use rustc_span::source_map::SourceMap;
 use rustc_span::{BytePos, DUMMY_SP, FileName, Pos, Span, sym};
 
-use super::{DocTestVisitor, ScrapedDocTest};
+use super::check_ignored::BrokenIgnoredLevel;
+use super::{DocTestVisitor, ExpectedOutput, ScrapedDocTest};
 use crate::clean::cfg::Cfg;
 use crate::clean::{Attributes, CfgInfo};
 use crate::html::markdown::{self, CodeLineMapping, ErrorCodes, LangString, MdRelLine};
@@ -26,6 +27,11 @@ struct RustCollector {
     cur_path: Vec<String>,
     position: Span,
     global_crate_attrs: Vec<String>,
+    /// Whether the documentation being visited only consists of doc comments, so that its lines
+    /// map to lines of the source file. Not the case with `#[doc = include_str!("...")]`.
+    doc_from_comments: bool,
+    /// The level of `rustdoc::broken_ignored_doctests` on the item being visited.
+    broken_ignored_level: BrokenIgnoredLevel,
 }
 
 impl RustCollector {
@@ -69,7 +75,7 @@ fn visit_test(
         } else {
             self.position
         };
-        self.tests.push(ScrapedDocTest::new(
+        let mut test = ScrapedDocTest::new(
             self.get_filename(),
             line,
             self.cur_path.clone(),
@@ -78,7 +84,16 @@ fn visit_test(
             span,
             code_mappings,
             self.global_crate_attrs.clone(),
-        ));
+        );
+        test.broken_ignored_level = self.broken_ignored_level;
+        self.tests.push(test);
+    }
+
+    fn visit_expected_output(&mut self, output: String, rel_line: MdRelLine) {
+        let line = self.doc_from_comments.then(|| self.get_base_line() + rel_line.offset());
+        if let Some(test) = self.tests.last_mut() {
+            test.expected_output = Some(ExpectedOutput { text: output, line });
+        }
     }
 
     fn visit_header(&mut self, _name: &str, _level: u32) {}
@@ -98,6 +113,8 @@ pub fn new(codes: ErrorCodes, tcx: TyCtxt<'tcx>) -> Self {
             position: DUMMY_SP,
             tests: vec![],
             global_crate_attrs: Vec::new(),
+            doc_from_comments: true,
+            broken_ignored_level: BrokenIgnoredLevel::default(),
         };
         Self { codes, tcx, collector }
     }
@@ -119,7 +136,8 @@ fn visit_testable<F: FnOnce(&mut Self)>(
         sp: Span,
         nested: F,
     ) {
-        let hir_attrs = self.tcx.hir_attrs(self.tcx.local_def_id_to_hir_id(def_id));
+        let hir_id = self.tcx.local_def_id_to_hir_id(def_id);
+        let hir_attrs = self.tcx.hir_attrs(hir_id);
 
         let mut cfg_info = CfgInfo::default();
         let mut found_features = 0;
@@ -213,6 +231,11 @@ fn visit_testable<F: FnOnce(&mut Self)>(
         let attrs = Attributes::from_hir(hir_attrs);
         if let Some(doc) = attrs.opt_doc_value() {
             let span = span_of_fragments(&attrs.doc_strings).unwrap_or(sp);
+            self.collector.doc_from_comments =
+                attrs.doc_strings.iter().all(|frag| frag.kind.is_sugared() && !frag.from_expansion);
+            self.collector.broken_ignored_level = BrokenIgnoredLevel::from_spec(
+                self.tcx.lint_level_spec_at_node(crate::lint::BROKEN_IGNORED_DOCTESTS, hir_id),
+            );
             self.collector.position = if span.edition().at_least_rust_2024() {
                 span
             } else {
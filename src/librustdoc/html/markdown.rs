@@ -745,7 +745,11 @@ pub(crate) fn find_codes<T: doctest::DocTestVisitor>(
     let mut prev_offset = 0;
     let mut nb_lines = 0;
     let mut register_header = None;
+    // Set after visiting a `check_output` doctest: if the next event starts a non-Rust code block,
+    // that block holds its expected output.
+    let mut expect_output_block = false;
     while let Some((event, offset)) = parser.next() {
+        let expect_output = std::mem::take(&mut expect_output_block);
         match event {
             Event::Start(Tag::CodeBlock(kind)) => {
                 let block_info = match kind {
@@ -758,6 +762,21 @@ pub(crate) fn find_codes<T: doctest::DocTestVisitor>(
                     }
                     CodeBlockKind::Indented => Default::default(),
                 };
+                if expect_output && !block_info.rust {
+                    // The expected output is compared as-is, so lines starting with `#` are kept.
+                    let mut output = String::new();
+                    while let Some((Event::Text(s), _)) = parser.next() {
+                        output.push_str(&s);
+                    }
+
+                    nb_lines += doc[prev_offset..offset.start].lines().count();
+                    if nb_lines != 0 && !&doc[prev_offset..offset.start].ends_with('\n') {
+                        nb_lines -= 1;
+                    }
+                    tests.visit_expected_output(output, MdRelLine::new(nb_lines));
+                    prev_offset = offset.start;
+                    continue;
+                }
                 if !include_non_rust && !block_info.rust {
                     continue;
                 }
@@ -780,6 +799,7 @@ pub(crate) fn find_codes<T: doctest::DocTestVisitor>(
                     nb_lines -= 1;
                 }
                 let line = MdRelLine::new(nb_lines);
+                expect_output_block = block_info.rust && block_info.check_output;
                 tests.visit_test(text, block_info, line, code_mappings);
                 prev_offset = offset.start;
             }
@@ -898,6 +918,8 @@ pub(crate) struct LangString {
     pub(crate) test_harness: bool,
     pub(crate) compile_fail: bool,
     pub(crate) standalone_crate: bool,
+    /// Whether the stdout of this doctest is compared against the code block following it.
+    pub(crate) check_output: bool,
     pub(crate) error_codes: Vec<String>,
     pub(crate) edition: Option<Edition>,
     pub(crate) added_classes: Vec<String>,
@@ -1221,6 +1243,7 @@ fn default() -> Self {
             test_harness: false,
             compile_fail: false,
             standalone_crate: false,
+            check_output: false,
             error_codes: Vec::new(),
             edition: None,
             added_classes: Vec::new(),
@@ -1289,6 +1312,10 @@ fn parse(
                         data.standalone_crate = true;
                         seen_rust_tags = !seen_other_tags || seen_rust_tags;
                     }
+                    LangStringToken::LangToken("check_output") => {
+                        data.check_output = true;
+                        seen_rust_tags = !seen_other_tags || seen_rust_tags;
+                    }
                     LangStringToken::LangToken(x)
                         if let Some(edition) = x.strip_prefix("edition") =>
                     {
@@ -1338,6 +1365,10 @@ fn parse(
                                 "use `test_harness` to run functions marked `#[test]` instead of a \
                                 potentially-implicit `main` function",
                             ),
+                            "check-output" | "checkoutput" => Some(
+                                "use `check_output` to compare the output of this test with the \
+                                code block following it",
+                            ),
                             "standalone" | "standalone_crate" | "standalone-crate"
                                 if extra.sp.at_least_rust_2024() =>
                             {
@@ -1,9 +1,11 @@
 use rustc_span::edition::{DEFAULT_EDITION, Edition};
 
 use super::{
-    ErrorCodes, HeadingOffset, IdMap, Ignore, LangString, LangStringToken, Markdown,
-    MarkdownItemInfo, TagIterator, find_testable_code, plain_text_summary, short_markdown_summary,
+    CodeLineMapping, ErrorCodes, HeadingOffset, IdMap, Ignore, LangString, LangStringToken,
+    Markdown, MarkdownItemInfo, MdRelLine, TagIterator, find_testable_code, plain_text_summary,
+    short_markdown_summary,
 };
+use crate::doctest::DocTestVisitor;
 
 #[test]
 fn test_unique_id() {
@@ -89,6 +91,7 @@ fn t(lg: LangString) {
     t(LangString { original: "should_panic".into(), should_panic: true, ..Default::default() });
     t(LangString { original: "no_run".into(), no_run: true, ..Default::default() });
     t(LangString { original: "test_harness".into(), test_harness: true, ..Default::default() });
+    t(LangString { original: "check_output".into(), check_output: true, ..Default::default() });
     t(LangString {
         original: "compile_fail".into(),
         no_run: true,
@@ -497,6 +500,47 @@ fn t(input: &str, expect: &[usize]) {
     t("```rust\n```\n ```rust\n```", &[1, 3]);
 }
 
+#[test]
+fn test_find_testable_code_expected_output() {
+    #[derive(Default)]
+    struct Collector {
+        tests: Vec<usize>,
+        outputs: Vec<(usize, String)>,
+    }
+
+    impl DocTestVisitor for Collector {
+        fn visit_test(
+            &mut self,
+            _: String,
+            _: LangString,
+            line: MdRelLine,
+            _: Vec<CodeLineMapping>,
+        ) {
+            self.tests.push(1 + line.offset());
+        }
+
+        fn visit_expected_output(&mut self, output: String, line: MdRelLine) {
+            self.outputs.push((1 + line.offset(), output));
+        }
+    }
+
+    fn t(input: &str, tests: &[usize], outputs: &[(usize, &str)]) {
+        let mut collector = Collector::default();
+        find_testable_code(input, &mut collector, ErrorCodes::No, None);
+        assert_eq!(collector.tests, tests);
+        let outputs = outputs.iter().map(|&(line, s)| (line, s.to_owned())).collect::<Vec<_>>();
+        assert_eq!(collector.outputs, outputs);
+    }
+
+    t("```check_output\n```\n\n```text\n# a\n```", &[1], &[(4, "# a\n")]);
+    // Only a non-Rust code block can hold the expected output.
+    t("```check_output\n```\n```\nb\n```\n```\n```", &[1, 3, 6], &[]);
+    // The expected output block must immediately follow the doctest.
+    t("```check_output\n```\n\nd\n\n```text\ne\n```", &[1], &[]);
+    // Only `check_output` doctests have an expected output block.
+    t("```\n```\n```text\nc\n```", &[1], &[]);
+}
+
 #[test]
 fn test_ascii_with_prepending_hashtag() {
     fn t(input: &str, expect: &str) {
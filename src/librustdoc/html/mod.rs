@@ -7,6 +7,7 @@
 // used by the error-index generator, so it needs to be public
 pub mod markdown;
 pub(crate) mod render;
+pub(crate) mod serve;
 pub(crate) mod sources;
 pub(crate) mod span_map;
 pub(crate) mod static_files;
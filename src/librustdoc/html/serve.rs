@@ -0,0 +1,207 @@
+//! A small HTTP server to browse documentation locally, used by `--serve`.
+//!
+//! Only the files of the doc root are served: the search, including searching by type signature,
+//! runs in the browser like it does for static files. Merging the cross-crate info of many crates
+//! with `--merge=finalize` beforehand makes all of them searchable from any page.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use std::{fs, thread};
+
+use tracing::debug;
+
+use crate::error::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of connections answered at the same time. Browsers only open a handful of
+/// connections to a server, and answering a request is just reading a file.
+const WORKERS: usize = 8;
+
+/// How long a connection may stay idle before it is dropped, so that clients which never send a
+/// request or never read the response can't tie up the workers.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the files in `root` at `addr` until the process is killed.
+pub(crate) fn serve(root: &Path, addr: SocketAddr) -> Result<(), Error> {
+    let listener = TcpListener::bind(addr).map_err(|error| Error {
+        file: PathBuf::new(),
+        error: format!("couldn't listen on {addr}: {error}"),
+    })?;
+    let addr = listener.local_addr().unwrap_or(addr);
+    eprintln!("Serving the documentation in `{}` at http://{addr}/", root.display());
+    serve_listener(root, &listener);
+    Ok(())
+}
+
+/// Answers the connections made to `listener` with a fixed number of worker threads.
+fn serve_listener(root: &Path, listener: &TcpListener) {
+    thread::scope(|scope| {
+        for _ in 0..WORKERS {
+            scope.spawn(|| {
+                for stream in listener.incoming() {
+                    let result = stream.and_then(|stream| {
+                        stream.set_read_timeout(Some(TIMEOUT))?;
+                        stream.set_write_timeout(Some(TIMEOUT))?;
+                        handle_connection(root, stream)
+                    });
+                    if let Err(error) = result {
+                        debug!("failed to answer a connection: {error}");
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn handle_connection(root: &Path, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // None of the headers make a difference to the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? != 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let response = match method {
+        "GET" | "HEAD" if target.starts_with('/') => resolve(root, target),
+        "GET" | "HEAD" => Response::error(400, "Bad Request"),
+        _ => Response::error(405, "Method Not Allowed"),
+    };
+    response.write_to(&mut &stream, method == "HEAD")
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Response {
+            status: 200,
+            reason: "OK",
+            headers: vec![("Content-Type", content_type.to_owned())],
+            body,
+        }
+    }
+
+    fn redirect(location: String) -> Self {
+        Response {
+            status: 302,
+            reason: "Found",
+            headers: vec![("Location", location)],
+            body: vec![],
+        }
+    }
+
+    fn error(status: u16, reason: &'static str) -> Self {
+        Response {
+            status,
+            reason,
+            headers: vec![("Content-Type", "text/plain; charset=utf-8".to_owned())],
+            body: format!("{status} {reason}\n").into_bytes(),
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write, head_only: bool) -> io::Result<()> {
+        write!(out, "HTTP/1.1 {} {}\r\n", self.status, self.reason)?;
+        for (name, value) in &self.headers {
+            write!(out, "{name}: {value}\r\n")?;
+        }
+        write!(out, "Content-Length: {}\r\nConnection: close\r\n\r\n", self.body.len())?;
+        if !head_only {
+            out.write_all(&self.body)?;
+        }
+        out.flush()
+    }
+}
+
+/// Finds the response to a request for `target`, which must start with a `/`.
+fn resolve(root: &Path, target: &str) -> Response {
+    let target = target.split_once('#').map_or(target, |(target, _fragment)| target);
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+
+    // `/search?q=...` opens the search of the crates index page, which lets browsers use the
+    // server as a search engine.
+    if path == "/search" {
+        let query = query
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .find_map(|param| param.strip_prefix("q="))
+            .unwrap_or_default();
+        return Response::redirect(format!("/index.html?search={query}"));
+    }
+
+    let Some(decoded) = percent_decode(path) else {
+        return Response::error(400, "Bad Request");
+    };
+    let mut file = root.to_path_buf();
+    for component in Path::new(&decoded).components() {
+        match component {
+            Component::Normal(component) => file.push(component),
+            Component::RootDir | Component::CurDir => {}
+            // Never serve files outside of the doc root.
+            Component::ParentDir | Component::Prefix(_) => {
+                return Response::error(404, "Not Found");
+            }
+        }
+    }
+
+    if file.is_dir() {
+        // Relative links in the index page of a directory only work with a trailing slash.
+        if !path.ends_with('/') {
+            return Response::redirect(match query {
+                Some(query) => format!("{path}/?{query}"),
+                None => format!("{path}/"),
+            });
+        }
+        file.push("index.html");
+    }
+    match fs::read(&file) {
+        Ok(body) => Response::ok(content_type(&file), body),
+        Err(_) => Response::error(404, "Not Found"),
+    }
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            decoded.push(u8::from_str_radix(str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt" | "md") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        _ => "application/octet-stream",
+    }
+}
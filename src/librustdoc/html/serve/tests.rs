@@ -0,0 +1,80 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::{fs, thread};
+
+use super::{percent_decode, resolve, serve_listener};
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!(percent_decode("/std/index.html").as_deref(), Some("/std/index.html"));
+    assert_eq!(percent_decode("/a%20b/%3Cc%3E").as_deref(), Some("/a b/<c>"));
+    assert_eq!(percent_decode("/%E2%9C%93").as_deref(), Some("/✓"));
+    assert_eq!(percent_decode("/%2"), None);
+    assert_eq!(percent_decode("/%zz"), None);
+    assert_eq!(percent_decode("/%FF"), None);
+}
+
+#[test]
+fn test_resolve() {
+    let root = tempfile::TempDir::new().unwrap();
+    fs::create_dir(root.path().join("krate")).unwrap();
+    fs::write(root.path().join("krate/index.html"), "<p>krate</p>").unwrap();
+    fs::write(root.path().join("krate/struct.Foo.html"), "<p>Foo</p>").unwrap();
+    let root = root.path();
+
+    let response = resolve(root, "/krate/struct.Foo.html#method.bar");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"<p>Foo</p>");
+    assert_eq!(response.headers, [("Content-Type", "text/html; charset=utf-8".to_owned())]);
+
+    assert_eq!(resolve(root, "/krate/").body, b"<p>krate</p>");
+    let response = resolve(root, "/krate?search=Foo");
+    assert_eq!(response.status, 302);
+    assert_eq!(response.headers, [("Location", "/krate/?search=Foo".to_owned())]);
+
+    let response = resolve(root, "/search?q=u8%20-%3E%20Foo");
+    assert_eq!(response.status, 302);
+    assert_eq!(response.headers, [("Location", "/index.html?search=u8%20-%3E%20Foo".to_owned())]);
+
+    assert_eq!(resolve(root, "/krate/struct.Bar.html").status, 404);
+    assert_eq!(resolve(root, "/krate/../../etc/passwd").status, 404);
+    assert_eq!(resolve(root, "/krate/%2E%2E/%2E%2E/etc/passwd").status, 404);
+    assert_eq!(resolve(root, "/%zz").status, 400);
+}
+
+#[test]
+fn test_serve() {
+    let root = tempfile::TempDir::new().unwrap();
+    fs::write(root.path().join("index.html"), "<p>crates</p>").unwrap();
+    fs::create_dir(root.path().join("krate")).unwrap();
+    fs::write(root.path().join("krate/index.html"), "<p>krate</p>").unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let served_root = root.path().to_path_buf();
+    thread::spawn(move || serve_listener(&served_root, &listener));
+
+    let request = |request: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    // A client that never sends its request doesn't keep the others from being answered.
+    let _idle = TcpStream::connect(addr).unwrap();
+
+    let response = request("GET /index.html HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"), "{response}");
+    assert!(response.ends_with("\r\n\r\n<p>crates</p>"), "{response}");
+
+    let response = request("HEAD /krate/ HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("Content-Length: 12\r\n"), "{response}");
+    assert!(response.ends_with("\r\n\r\n"), "{response}");
+
+    let response = request("POST /index.html HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{response}");
+}
@@ -481,6 +481,7 @@ fn from_clean(struct_: &clean::Struct, renderer: &JsonRenderer<'_>) -> Self {
             kind,
             generics: generics.into_json(renderer),
             impls: Vec::new(), // Added in JsonRenderer::item
+            facts: None,       // Added in JsonRenderer::item
         }
     }
 }
@@ -494,6 +495,7 @@ fn from_clean(union_: &clean::Union, renderer: &JsonRenderer<'_>) -> Self {
             has_stripped_fields,
             fields: renderer.ids(fields),
             impls: Vec::new(), // Added in JsonRenderer::item
+            facts: None,       // Added in JsonRenderer::item
         }
     }
 }
@@ -874,6 +876,7 @@ fn from_clean(enum_: &clean::Enum, renderer: &JsonRenderer<'_>) -> Self {
             has_stripped_variants,
             variants: renderer.ids(&variants.as_slice().raw),
             impls: Vec::new(), // Added in JsonRenderer::item
+            facts: None,       // Added in JsonRenderer::item
         }
     }
 }
@@ -7,6 +7,7 @@
 mod conversions;
 mod ids;
 mod import_finder;
+mod type_facts;
 
 use std::cell::RefCell;
 use std::fs::{File, create_dir_all};
@@ -50,6 +51,8 @@ pub(crate) struct JsonRenderer<'tcx> {
     cache: Rc<Cache>,
     imported_items: DefIdSet,
     id_interner: RefCell<ids::IdInterner>,
+    /// Whether to include the facts resolved by the compiler about structs, enums and unions.
+    show_type_facts: bool,
 }
 
 impl<'tcx> JsonRenderer<'tcx> {
@@ -219,6 +222,7 @@ pub(crate) fn init(
                 cache: Rc::new(cache),
                 imported_items,
                 id_interner: Default::default(),
+                show_type_facts: options.show_type_facts,
             },
             krate,
         ))
@@ -267,14 +271,17 @@ fn item(&mut self, item: &clean::Item) -> Result<(), Error> {
                 }
                 types::ItemEnum::Struct(ref mut s) => {
                     s.impls = self.get_impls(item_id.expect_def_id());
+                    s.facts = self.type_facts(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Enum(ref mut e) => {
                     e.impls = self.get_impls(item_id.expect_def_id());
+                    e.facts = self.type_facts(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Union(ref mut u) => {
                     u.impls = self.get_impls(item_id.expect_def_id());
+                    u.facts = self.type_facts(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Primitive(ref mut p) => {
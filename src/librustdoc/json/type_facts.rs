@@ -0,0 +1,108 @@
+//! Facts about types resolved by the compiler, emitted with `--show-type-facts`.
+//!
+//! These save consumers of the JSON output from having to reimplement parts of the trait solver
+//! and of the layout computation, which they can't do accurately anyway.
+
+use rustc_hir::LangItem;
+use rustc_hir::def_id::DefId;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_middle::ty::{self, TypingMode};
+use rustc_span::{DUMMY_SP, sym};
+use rustc_trait_selection::infer::InferCtxtExt;
+use rustdoc_json_types as types;
+
+use crate::formats::Impl;
+use crate::json::JsonRenderer;
+
+impl JsonRenderer<'_> {
+    /// Returns the facts about the struct, enum or union `def_id`, if they were requested.
+    pub(super) fn type_facts(&self, def_id: DefId) -> Option<types::TypeFacts> {
+        if !self.show_type_facts {
+            return None;
+        }
+
+        let tcx = self.tcx;
+        let auto_trait = |trait_def_id: Option<DefId>| match trait_def_id {
+            Some(trait_def_id) => self.auto_trait_impl(def_id, trait_def_id),
+            // Only possible with `#![no_core]`.
+            None => types::AutoTraitImpl::NotImplemented,
+        };
+        let auto_traits = types::AutoTraits {
+            send: auto_trait(tcx.get_diagnostic_item(sym::Send)),
+            sync: auto_trait(tcx.lang_items().get(LangItem::Sync)),
+            unpin: auto_trait(tcx.lang_items().get(LangItem::Unpin)),
+            unwind_safe: auto_trait(tcx.get_diagnostic_item(sym::unwind_safe_trait)),
+        };
+
+        let blanket_impls = self
+            .impls_of(def_id)
+            .filter(|i| i.inner_impl().kind.is_blanket())
+            .map(|i| self.id_from_item(&i.impl_item))
+            .collect();
+
+        Some(types::TypeFacts { auto_traits, layout: self.type_layout(def_id), blanket_impls })
+    }
+
+    fn impls_of(&self, def_id: DefId) -> impl Iterator<Item = &Impl> {
+        self.cache.impls.get(&def_id).into_iter().flatten()
+    }
+
+    fn auto_trait_impl(&self, def_id: DefId, trait_def_id: DefId) -> types::AutoTraitImpl {
+        let tcx = self.tcx;
+
+        // The impls synthesized in `clean::auto_trait` are negative exactly when the type never
+        // implements the trait, whatever its generic arguments.
+        let synthesized = self.impls_of(def_id).map(Impl::inner_impl).find(|impl_| {
+            impl_.kind.is_auto() && impl_.trait_.as_ref().map(|t| t.def_id()) == Some(trait_def_id)
+        });
+        if let Some(impl_) = synthesized
+            && impl_.is_negative_trait_impl()
+        {
+            return types::AutoTraitImpl::NotImplemented;
+        }
+
+        // The trait is implemented unconditionally if the where clauses of the type are enough to
+        // prove it.
+        let typing_env = ty::TypingEnv::non_body_analysis(tcx, def_id);
+        let (infcx, param_env) = tcx.infer_ctxt().build_with_typing_env(typing_env);
+        let ty = tcx.type_of(def_id).instantiate_identity().skip_norm_wip();
+        if infcx.type_implements_trait(trait_def_id, [ty], param_env).must_apply_modulo_regions() {
+            return types::AutoTraitImpl::Implemented;
+        }
+        if synthesized.is_some() {
+            return types::AutoTraitImpl::Conditional;
+        }
+
+        // Otherwise the trait is either implemented manually or not at all: check whether it can
+        // hold for some generic arguments.
+        let infcx = tcx.infer_ctxt().build(TypingMode::non_body_analysis());
+        let args = infcx.fresh_args_for_item(DUMMY_SP, def_id);
+        let ty = tcx.type_of(def_id).instantiate(tcx, args).skip_norm_wip();
+        if infcx.type_implements_trait(trait_def_id, [ty], ty::ParamEnv::empty()).may_apply() {
+            types::AutoTraitImpl::Conditional
+        } else {
+            types::AutoTraitImpl::NotImplemented
+        }
+    }
+
+    /// Computes the layout of `def_id` like `html::render::type_layout` does, but only for types
+    /// which aren't generic: their layout can't be known before their instantiation.
+    fn type_layout(&self, def_id: DefId) -> Option<types::TypeLayout> {
+        let tcx = self.tcx;
+        if tcx.generics_of(def_id).requires_monomorphization(tcx) {
+            return None;
+        }
+
+        let typing_env = ty::TypingEnv::post_analysis(tcx, def_id);
+        let ty = tcx.type_of(def_id).instantiate_identity().skip_norm_wip();
+        let layout = tcx.layout_of(typing_env.as_query_input(ty)).ok()?;
+        if layout.is_unsized() {
+            return None;
+        }
+        Some(types::TypeLayout {
+            size: layout.size.bytes(),
+            align: layout.align.bytes(),
+            is_uninhabited: layout.is_uninhabited(),
+        })
+    }
+}
@@ -566,7 +566,31 @@ fn opts() -> Vec<RustcOptGroup> {
             "Include the memory layout of types in the docs",
             "",
         ),
+        opt(
+            Unstable,
+            FlagMulti,
+            "",
+            "show-type-facts",
+            "Include the auto traits, layout and blanket impls of types in the JSON output",
+            "",
+        ),
         opt(Unstable, Flag, "", "no-capture", "Don't capture stdout and stderr of tests", ""),
+        opt(
+            Unstable,
+            Flag,
+            "",
+            "bless",
+            "Update the expected output of `check_output` doctests instead of failing",
+            "",
+        ),
+        opt(
+            Unstable,
+            Flag,
+            "",
+            "check-ignored-doctests",
+            "Type-check doctests marked `ignore`, warning about those which don't compile",
+            "",
+        ),
         opt(
             Unstable,
             Flag,
@@ -640,6 +664,14 @@ fn opts() -> Vec<RustcOptGroup> {
             "Deprecated option to specify read/write-doc-meta-dir mode",
             "none, shared, finalize",
         ),
+        opt(
+            Unstable,
+            Opt,
+            "",
+            "serve",
+            "Serve the documentation in the output directory over HTTP",
+            "127.0.0.1:8000",
+        ),
         opt(Unstable, Flag, "", "html-no-source", "Disable HTML source code pages generation", ""),
         opt(
             Unstable,
@@ -840,6 +872,7 @@ fn main_args(early_dcx: &mut EarlyDiagCtxt, at_args: &[String]) {
     let input = match input {
         config::InputMode::HasFile(input) => input,
         config::InputMode::NoInputMergeFinalize => {
+            let serve = render_options.serve.map(|addr| (render_options.output.clone(), addr));
             let config = core::create_config(
                 Input::Str {
                     name: rustc_span::FileName::Custom(String::new()),
@@ -848,12 +881,30 @@ fn main_args(early_dcx: &mut EarlyDiagCtxt, at_args: &[String]) {
                 options,
                 &render_options,
             );
+            let finalized = interface::run_compiler(config, |compiler| {
+                let finalized = run_merge_finalize(render_options, compiler)
+                    .map_err(|e| format!("could not write merged cross-crate info: {e}"));
+                // Errors reported while finalizing leave the output incomplete as well.
+                if finalized.is_ok() && compiler.sess.dcx().has_errors().is_some() {
+                    return Err("could not finalize the merged documentation".to_owned());
+                }
+                finalized
+            });
+            // Don't serve stale or partial documentation.
+            if let Err(err) = finalized {
+                return wrap_return(dcx, Err(err));
+            }
+            if let Some((output, addr)) = serve {
+                wrap_return(dcx, html::serve::serve(&output, addr).map_err(|e| e.to_string()));
+            }
+            return;
+        }
+        config::InputMode::NoInputServe => {
+            let addr =
+                render_options.serve.expect("config.rs only returns NoInputServe with --serve");
             return wrap_return(
                 dcx,
-                interface::run_compiler(config, |compiler| {
-                    run_merge_finalize(render_options, compiler)
-                        .map_err(|e| format!("could not write merged cross-crate info: {e}"))
-                }),
+                html::serve::serve(&render_options.output, addr).map_err(|e| e.to_string()),
             );
         }
     };
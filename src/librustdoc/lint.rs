@@ -209,6 +209,17 @@ macro_rules! declare_rustdoc_lint {
     "detects unused footnote definitions"
 }
 
+declare_rustdoc_lint! {
+    /// The `broken_ignored_doctests` lint detects doctests marked `ignore` which don't compile,
+    /// when they are type-checked with `--check-ignored-doctests`. This is a `rustdoc` only lint,
+    /// see the documentation in the [rustdoc book].
+    ///
+    /// [rustdoc book]: ../../../rustdoc/lints.html#broken_ignored_doctests
+    BROKEN_IGNORED_DOCTESTS,
+    Warn,
+    "detects doctests marked `ignore` which don't compile"
+}
+
 pub(crate) static RUSTDOC_LINTS: Lazy<Vec<&'static Lint>> = Lazy::new(|| {
     vec![
         BROKEN_INTRA_DOC_LINKS,
@@ -224,6 +235,7 @@ macro_rules! declare_rustdoc_lint {
         REDUNDANT_EXPLICIT_LINKS,
         BROKEN_FOOTNOTE,
         UNUSED_FOOTNOTE_DEFINITION,
+        BROKEN_IGNORED_DOCTESTS,
     ]
 });
 
@@ -114,8 +114,8 @@
 // will instead cause conflicts. See #94591 for more. (This paragraph and the "Latest feature" line
 // are deliberately not in a doc comment, because they need not be in public docs.)
 //
-// Latest feature: Make `Stability` work with non-self-describing formats
-pub const FORMAT_VERSION: u32 = 61;
+// Latest feature: Add `facts` to structs, enums and unions with `--show-type-facts`
+pub const FORMAT_VERSION: u32 = 62;
 
 /// The root of the emitted JSON blob.
 ///
@@ -1003,6 +1003,10 @@ pub struct Union {
     ///
     /// All of the corresponding [`Item`]s are of kind [`ItemEnum::Impl`].
     pub impls: Vec<Id>,
+    /// Facts about this union resolved by the compiler.
+    ///
+    /// Only present when rustdoc was given `--show-type-facts`.
+    pub facts: Option<TypeFacts>,
 }
 
 /// A `struct`.
@@ -1018,6 +1022,10 @@ pub struct Struct {
     /// All impls (both of traits and inherent) for this struct.
     /// All of the corresponding [`Item`]s are of kind [`ItemEnum::Impl`].
     pub impls: Vec<Id>,
+    /// Facts about this struct resolved by the compiler.
+    ///
+    /// Only present when rustdoc was given `--show-type-facts`.
+    pub facts: Option<TypeFacts>,
 }
 
 /// The kind of a [`Struct`] and the data specific to it, i.e. fields.
@@ -1074,6 +1082,76 @@ pub struct Enum {
     pub variants: Vec<Id>,
     /// `impl`s for the enum.
     pub impls: Vec<Id>,
+    /// Facts about this enum resolved by the compiler.
+    ///
+    /// Only present when rustdoc was given `--show-type-facts`.
+    pub facts: Option<TypeFacts>,
+}
+
+/// Facts about a [`Struct`], [`Enum`] or [`Union`] which are resolved by the compiler, and which
+/// would otherwise need to be recomputed from the impls and fields of the type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "rkyv_0_8", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv_0_8", rkyv(derive(Debug)))]
+pub struct TypeFacts {
+    /// Whether the type implements the most commonly checked auto traits.
+    pub auto_traits: AutoTraits,
+    /// The layout of the type.
+    ///
+    /// Absent if the type is generic or unsized, or if its layout couldn't be computed.
+    pub layout: Option<TypeLayout>,
+    /// The blanket impls which apply to the type, such as `impl<T: Display> ToString for T`.
+    ///
+    /// All of the corresponding [`Item`]s are of kind [`ItemEnum::Impl`] and have
+    /// [`Impl::blanket_impl`] set. They are also part of the `impls` of the type.
+    pub blanket_impls: Vec<Id>,
+}
+
+/// Whether a type implements the most commonly checked auto traits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "rkyv_0_8", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv_0_8", rkyv(derive(Debug)))]
+pub struct AutoTraits {
+    /// [`Send`](https://doc.rust-lang.org/std/marker/trait.Send.html)
+    pub send: AutoTraitImpl,
+    /// [`Sync`](https://doc.rust-lang.org/std/marker/trait.Sync.html)
+    pub sync: AutoTraitImpl,
+    /// [`Unpin`](https://doc.rust-lang.org/std/marker/trait.Unpin.html)
+    pub unpin: AutoTraitImpl,
+    /// [`UnwindSafe`](https://doc.rust-lang.org/std/panic/trait.UnwindSafe.html)
+    pub unwind_safe: AutoTraitImpl,
+}
+
+/// Whether a type implements an auto trait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "rkyv_0_8", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv_0_8", rkyv(derive(Debug)))]
+#[serde(rename_all = "snake_case")]
+pub enum AutoTraitImpl {
+    /// The trait is implemented for every valid choice of generic arguments.
+    Implemented,
+    /// The trait is only implemented for some choices of generic arguments.
+    ///
+    /// The conditions can be found in the corresponding impl, listed in the `impls` of the type.
+    Conditional,
+    /// The trait is never implemented.
+    NotImplemented,
+}
+
+/// The layout of a type, as computed by the compiler for the target rustdoc was run for.
+///
+/// This is not a stable guarantee: the layout of types without a `#[repr]` can change between
+/// compiler versions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "rkyv_0_8", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv_0_8", rkyv(derive(Debug)))]
+pub struct TypeLayout {
+    /// The size of the type, in bytes.
+    pub size: u64,
+    /// The ABI-required alignment of the type, in bytes.
+    pub align: u64,
+    /// Whether the type has no values, such as an enum without variants.
+    pub is_uninhabited: bool,
 }
 
 /// A variant of an enum.
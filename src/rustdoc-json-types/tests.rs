@@ -23,7 +23,17 @@ fn test_struct_info_roundtrip() {
     let s = ItemEnum::Struct(Struct {
         generics: Generics { params: vec![], where_predicates: vec![] },
         kind: StructKind::Plain { fields: vec![], has_stripped_fields: false },
-        impls: vec![],
+        impls: vec![Id(1)],
+        facts: Some(TypeFacts {
+            auto_traits: AutoTraits {
+                send: AutoTraitImpl::Implemented,
+                sync: AutoTraitImpl::Conditional,
+                unpin: AutoTraitImpl::Implemented,
+                unwind_safe: AutoTraitImpl::NotImplemented,
+            },
+            layout: Some(TypeLayout { size: 16, align: 8, is_uninhabited: false }),
+            blanket_impls: vec![Id(1)],
+        }),
     });
 
     check_roundtrips(s);
@@ -36,6 +46,7 @@ fn test_union_info_roundtrip() {
         has_stripped_fields: false,
         fields: vec![],
         impls: vec![],
+        facts: None,
     });
 
     check_roundtrips(u);
@@ -78,6 +89,7 @@ fn test_rkyv_roundtrip() {
             generics: Generics { params: vec![], where_predicates: vec![] },
             kind: StructKind::Plain { fields: vec![Id(1), Id(2)], has_stripped_fields: false },
             impls: vec![Id(3)],
+            facts: None,
         });
         rkyv_roundtrip(&s);
 
@@ -86,6 +98,7 @@ fn test_rkyv_roundtrip() {
             has_stripped_fields: false,
             fields: vec![Id(1)],
             impls: vec![],
+            facts: None,
         });
         rkyv_roundtrip(&u);
 
@@ -5,8 +5,8 @@
     AssocItemConstraint, AssocItemConstraintKind, Constant, Crate, DynTrait, Enum, Function,
     FunctionPointer, FunctionSignature, GenericArg, GenericArgs, GenericBound, GenericParamDef,
     Generics, Id, Impl, ItemEnum, ItemSummary, Module, Path, Primitive, ProcMacro, Static, Struct,
-    StructKind, Term, Trait, TraitAlias, Type, TypeAlias, Union, Use, Variant, VariantKind,
-    WherePredicate,
+    StructKind, Term, Trait, TraitAlias, Type, TypeAlias, TypeFacts, Union, Use, Variant,
+    VariantKind, WherePredicate,
 };
 use serde_json::Value;
 
@@ -169,6 +169,9 @@ fn check_union(&mut self, x: &'a Union) {
         self.check_generics(&x.generics);
         x.fields.iter().for_each(|i| self.add_field_id(i));
         x.impls.iter().for_each(|i| self.add_impl_id(i));
+        if let Some(facts) = &x.facts {
+            self.check_type_facts(facts);
+        }
     }
 
     fn check_struct(&mut self, x: &'a Struct) {
@@ -181,6 +184,9 @@ fn check_struct(&mut self, x: &'a Struct) {
             }
         }
         x.impls.iter().for_each(|i| self.add_impl_id(i));
+        if let Some(facts) = &x.facts {
+            self.check_type_facts(facts);
+        }
     }
 
     fn check_struct_field(&mut self, x: &'a Type) {
@@ -191,6 +197,13 @@ fn check_enum(&mut self, x: &'a Enum) {
         self.check_generics(&x.generics);
         x.variants.iter().for_each(|i| self.add_variant_id(i));
         x.impls.iter().for_each(|i| self.add_impl_id(i));
+        if let Some(facts) = &x.facts {
+            self.check_type_facts(facts);
+        }
+    }
+
+    fn check_type_facts(&mut self, x: &'a TypeFacts) {
+        x.blanket_impls.iter().for_each(|i| self.add_impl_id(i));
     }
 
     fn check_variant(&mut self, x: &'a Variant, id: &'a Id) {
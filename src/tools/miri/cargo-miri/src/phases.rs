@@ -631,6 +631,8 @@ pub fn phase_runner(mut binary_args: impl Iterator<Item = String>, phase: Runner
     // But then we need to switch to the run-time one, which we instruct Miri to do by setting `MIRI_CWD`.
     cmd.current_dir(&info.current_dir);
     cmd.env("MIRI_CWD", env::current_dir().unwrap());
+    // Let Miri know how to run the other binaries we built, in case the program spawns them.
+    cmd.env("MIRI_CARGO_MIRI", env::current_exe().unwrap());
 
     // Run it.
     debug_cmd("[cargo-miri runner]", verbose, &cmd);
@@ -236,9 +236,28 @@ pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
     // Returns the `AllocId` that corresponds to the specified addr,
     // or `None` if the addr is out of bounds.
     fn alloc_id_from_addr(&self, addr: u64, size: i64) -> Option<AllocId> {
+        let this = self.eval_context_ref();
+        assert!(this.machine.alloc_addresses.borrow().provenance_mode != ProvenanceMode::Strict);
+
+        let alloc_id = this.alloc_id_containing_addr(addr, size)?;
+
+        // We only use this provenance if it has been exposed.
+        let global_state = this.machine.alloc_addresses.borrow();
+        if global_state.exposed.contains(&alloc_id) {
+            // This must still be live, since we remove allocations from `int_to_ptr_map` when they get freed.
+            debug_assert!(this.is_alloc_live(alloc_id));
+            Some(alloc_id)
+        } else {
+            None
+        }
+    }
+
+    // Returns the `AllocId` of the live allocation that the specified addr is in bounds of, no
+    // matter whether it has been exposed. This must not be used to determine the provenance of a
+    // pointer; it exists for tools that inspect memory from the outside, like the debugger stub.
+    fn alloc_id_containing_addr(&self, addr: u64, size: i64) -> Option<AllocId> {
         let this = self.eval_context_ref();
         let global_state = this.machine.alloc_addresses.borrow();
-        assert!(global_state.provenance_mode != ProvenanceMode::Strict);
 
         // We always search the allocation to the right of this address. So if the size is strictly
         // negative, we have to search for `addr-1` instead.
@@ -246,7 +265,7 @@ fn alloc_id_from_addr(&self, addr: u64, size: i64) -> Option<AllocId> {
         let pos = global_state.int_to_ptr_map.binary_search_by_key(&addr, |(addr, _)| *addr);
 
         // Determine the in-bounds provenance for this pointer.
-        let alloc_id = match pos {
+        match pos {
             Ok(pos) => Some(global_state.int_to_ptr_map[pos].1),
             Err(0) => None,
             Err(pos) => {
@@ -262,15 +281,6 @@ fn alloc_id_from_addr(&self, addr: u64, size: i64) -> Option<AllocId> {
                 let size = this.get_alloc_info(alloc_id).size;
                 if offset < size.bytes() { Some(alloc_id) } else { None }
             }
-        }?;
-
-        // We only use this provenance if it has been exposed.
-        if global_state.exposed.contains(&alloc_id) {
-            // This must still be live, since we remove allocations from `int_to_ptr_map` when they get freed.
-            debug_assert!(this.is_alloc_live(alloc_id));
-            Some(alloc_id)
-        } else {
-            None
         }
     }
 
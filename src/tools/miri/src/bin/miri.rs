@@ -190,6 +190,16 @@ fn after_analysis<'tcx>(
             run_many_seeds(many_seeds, |seed| {
                 let mut config = config.clone();
                 config.seed = Some(seed);
+                // Every seed gets its own trace, coverage, heap profile and leak report.
+                let outputs = [
+                    &mut config.record_trace,
+                    &mut config.coverage,
+                    &mut config.heap_profile,
+                    &mut config.leak_report,
+                ];
+                for path in outputs.into_iter().flatten() {
+                    path.as_mut_os_string().push(format!(".{seed}"));
+                }
                 eprintln!("Trying seed: {seed}");
                 miri::eval_entry(tcx, entry_def_id, entry_type, &config, /* genmc_ctx */ None)
             })
@@ -443,6 +453,10 @@ fn main() -> ExitCode {
     let mut many_seeds_keep_going = false;
     let mut miri_config = MiriConfig::default();
     miri_config.env = env_snapshot;
+    // Remember how we were started, before `MIRI_CWD` changes the working directory.
+    miri_config.invocation = env::current_dir()
+        .ok()
+        .map(|dir| (dir, args.iter().take_while(|arg| *arg != "--").cloned().collect()));
 
     let mut rustc_args = vec![];
     let mut after_dashdash = false;
@@ -543,6 +557,8 @@ fn main() -> ExitCode {
             miri_config.float_rounding_error = miri::FloatRoundingErrorMode::Max;
         } else if arg == "-Zmiri-no-short-fd-operations" {
             miri_config.short_fd_operations = false;
+        } else if arg == "-Zmiri-io-uring-shuffle-completions" {
+            miri_config.io_uring_shuffle_completions = true;
         } else if arg == "-Zmiri-strict-provenance" {
             miri_config.provenance_mode = ProvenanceMode::Strict;
         } else if arg == "-Zmiri-permissive-provenance" {
@@ -679,6 +695,34 @@ fn main() -> ExitCode {
                 fatal_error!("-Zmiri-force-page-size requires a power of 2: {page_size}");
             };
             miri_config.page_size = Some(page_size);
+        } else if arg == "-Zmiri-virtual-fs" {
+            miri_config.virtual_fs = true;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-virtual-fs=") {
+            miri_config.virtual_fs = true;
+            miri_config.virtual_fs_seed = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-virtual-fs-dump=") {
+            miri_config.virtual_fs = true;
+            miri_config.virtual_fs_dump = Some(param.into());
+        } else if arg == "-Zmiri-coverage" {
+            miri_config.coverage = Some("lcov.info".into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-coverage=") {
+            miri_config.coverage = Some(param.into());
+        } else if arg == "-Zmiri-heap-profile" {
+            miri_config.heap_profile = Some("dhat-heap.json".into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-heap-profile=") {
+            miri_config.heap_profile = Some(param.into());
+        } else if arg == "-Zmiri-leak-report" {
+            miri_config.leak_report = Some("miri-leaks.json".into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-leak-report=") {
+            miri_config.leak_report = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-leak-allowlist=") {
+            miri_config.leak_allowlist = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-record-trace=") {
+            miri_config.record_trace = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-replay-trace=") {
+            miri_config.replay_trace = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-gdb-server=") {
+            miri_config.gdb_server = Some(param.to_owned());
         } else if let Some(param) = arg.strip_prefix("-Zmiri-user-relevant-crates=") {
             miri_config.user_relevant_crates.extend(param.split(',').map(|s| s.to_owned()));
         } else {
@@ -692,6 +736,32 @@ fn main() -> ExitCode {
         miri_config.borrow_tracker = None;
     }
 
+    // The virtual file system replaces the host file system under isolation.
+    if miri_config.virtual_fs && miri_config.isolated_op == miri::IsolatedOp::Allow {
+        fatal_error!("-Zmiri-virtual-fs is not compatible with -Zmiri-disable-isolation");
+    }
+
+    // A trace can either be recorded or replayed, and replaying only makes sense for a single seed.
+    // GenMC does its own scheduling, which cannot be traced.
+    if miri_config.record_trace.is_some() && miri_config.replay_trace.is_some() {
+        fatal_error!("-Zmiri-record-trace and -Zmiri-replay-trace cannot be used together");
+    }
+    if miri_config.replay_trace.is_some() && many_seeds.is_some() {
+        fatal_error!("-Zmiri-replay-trace is not compatible with -Zmiri-many-seeds");
+    }
+    if (miri_config.record_trace.is_some() || miri_config.replay_trace.is_some())
+        && miri_config.genmc_config.is_some()
+    {
+        fatal_error!("recording or replaying traces is not supported in GenMC mode");
+    }
+
+    // The debugger drives a single execution, and GenMC explores many of them.
+    if miri_config.gdb_server.is_some()
+        && (many_seeds.is_some() || miri_config.genmc_config.is_some())
+    {
+        fatal_error!("-Zmiri-gdb-server is not compatible with -Zmiri-many-seeds or GenMC mode");
+    }
+
     // Native calls and strict provenance are not compatible.
     if !miri_config.native_lib.is_empty() && miri_config.provenance_mode == ProvenanceMode::Strict {
         fatal_error!("strict provenance is not compatible with calling native functions");
@@ -721,6 +791,15 @@ fn main() -> ExitCode {
         fatal_error!("Invalid settings: {err}");
     }
 
+    // Coverage needs the MIR to be instrumented, but not the profiler runtime that would record
+    // the counters in a real execution.
+    if miri_config.coverage.is_some() {
+        if !rustc_args.iter().any(|arg| arg.starts_with("-Cinstrument-coverage")) {
+            rustc_args.push("-Cinstrument-coverage".to_owned());
+        }
+        rustc_args.push("-Zno-profiler-runtime".to_owned());
+    }
+
     // Ensure we have parallelism for many-seeds mode.
     if many_seeds.is_some() && !rustc_args.iter().any(|arg| arg.starts_with("-Zthreads=")) {
         // Clamp to 20 threads; things get a less efficient beyond that due to lock contention.
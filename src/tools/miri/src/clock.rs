@@ -60,6 +60,10 @@ pub fn duration_since(&self, earlier: Instant) -> Duration {
 #[derive(Debug)]
 pub struct MonotonicClock {
     kind: MonotonicClockKind,
+    /// When an execution trace is recorded or replayed, the time of the system clock when the
+    /// trace was recorded. The system clock then advances with this clock instead of being read
+    /// from the host.
+    system_epoch: Option<SystemTime>,
 }
 
 #[derive(Debug)]
@@ -76,15 +80,17 @@ enum MonotonicClockKind {
 }
 
 impl MonotonicClock {
-    /// Create a new clock based on the availability of communication with the host.
-    pub fn new(communicate: bool) -> Self {
-        let kind = if communicate {
+    /// Create a new clock based on the availability of communication with the host. The clock is
+    /// virtual if there is no communication, or if an execution trace is recorded or replayed,
+    /// which is when `system_epoch` is set.
+    pub fn new(communicate: bool, system_epoch: Option<SystemTime>) -> Self {
+        let kind = if communicate && system_epoch.is_none() {
             MonotonicClockKind::Host { epoch: StdInstant::now() }
         } else {
             MonotonicClockKind::Virtual { nanoseconds: 0.into() }
         };
 
-        Self { kind }
+        Self { kind, system_epoch }
     }
 
     /// Let the time pass for a small interval.
@@ -131,6 +137,15 @@ pub fn now(&self) -> Instant {
                 Instant { kind: InstantKind::Virtual { nanoseconds: nanoseconds.get() } },
         }
     }
+
+    /// The current time of the system clock. Must only be called if communication with the host
+    /// is allowed.
+    pub fn system_time_now(&self) -> SystemTime {
+        match self.system_epoch {
+            Some(epoch) => epoch + self.now().duration_since(self.epoch()),
+            None => SystemTime::now(),
+        }
+    }
 }
 
 /// A deadline for some event to occur.
@@ -195,7 +210,7 @@ pub fn timeout(
                 assert!(self.communicate(), "cannot have `RealTime` timeout with isolation");
                 Deadline::RealTime(match style {
                     TimeoutStyle::Absolute => SystemTime::UNIX_EPOCH,
-                    TimeoutStyle::Relative => SystemTime::now(),
+                    TimeoutStyle::Relative => self.monotonic_clock.system_time_now(),
                 })
             }
             TimeoutClock::Monotonic =>
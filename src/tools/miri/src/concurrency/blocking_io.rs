@@ -6,6 +6,7 @@
 use mio::event::Source;
 use mio::{Events, Interest, Poll, Token};
 
+use crate::shims::process::CHILD_POLL_INTERVAL;
 use crate::shims::{FdId, FileDescription, FileDescriptionRef, WeakFileDescriptionRef};
 use crate::*;
 
@@ -318,23 +319,34 @@ fn block_thread_for_io(
     /// - If the timeout is [`None`] the poll blocks indefinitely until an event occurs.
     ///
     /// Unblocks all threads which are blocked on I/O and whose I/O interests
-    /// are currently fulfilled.
+    /// are currently fulfilled. This also exchanges data with child processes.
     fn poll_and_unblock(&mut self, timeout: Option<Duration>) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
 
+        // While child processes are running, we need to wake up regularly to check on them.
+        let timeout = if this.machine.child_processes.is_active() {
+            Some(timeout.map_or(CHILD_POLL_INTERVAL, |timeout| timeout.min(CHILD_POLL_INTERVAL)))
+        } else {
+            timeout
+        };
         match BlockingIoManager::poll(this, timeout)? {
-            Ok(_) => interp_ok(()),
+            Ok(_) => {}
             // We can ignore errors originating from interrupts; that's just a spurious wakeup.
-            Err(e) if e.kind() == io::ErrorKind::Interrupted => interp_ok(()),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
             // For other errors we panic. On Linux and BSD hosts this should only be
             // reachable when a system resource error (e.g. ENOMEM or ENOSPC) occurred.
             Err(e) => panic!("unexpected error while polling: {e}"),
         }
+        this.poll_child_processes()
     }
 
     /// Returns whether there exists any thread that is blocked on host I/O.
+    /// Running child processes count as well, since threads may wait for their output or exit.
     fn any_thread_blocked_on_host(&self) -> bool {
         let this = self.eval_context_ref();
+        if this.machine.child_processes.is_active() {
+            return true;
+        }
         this.machine.blocking_io.sources.values().any(|source| {
             // There's two ways something could be blocked on this: directly,
             // or indirectly via a readiness watcher.
@@ -1041,7 +1041,8 @@ pub fn new_allocation(
                 | MiriMemoryKind::WinHeap
                 | MiriMemoryKind::WinLocal
                 | MiriMemoryKind::Mmap
-                | MiriMemoryKind::SocketAddress,
+                | MiriMemoryKind::SocketAddress
+                | MiriMemoryKind::IoUring,
             )
             | MemoryKind::Stack => {
                 let (alloc_index, clocks) = global.active_thread_state(thread_mgr);
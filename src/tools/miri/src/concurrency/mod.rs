@@ -6,6 +6,7 @@
 pub mod scheduler;
 pub mod sync;
 pub mod thread;
+mod trace;
 mod vector_clock;
 pub mod weak_memory;
 
@@ -24,4 +25,5 @@
 
 pub use self::data_race_handler::{AllocDataRaceHandler, GlobalDataRaceHandler};
 pub use self::genmc::{ExitType, GenmcConfig, GenmcCtx, GenmcEvalContextExt, run_genmc_mode};
+pub use self::trace::{ExecutionTrace, HostValue, encode_hex, parse_hex};
 pub use self::vector_clock::VClock;
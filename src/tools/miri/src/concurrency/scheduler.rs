@@ -113,6 +113,15 @@ fn schedule(&mut self) -> InterpResult<'tcx, SchedulingAction> {
         // abstime has already been passed at the time of the call".
         // <https://pubs.opengroup.org/onlinepubs/9699919799/functions/pthread_cond_timedwait.html>
         let potential_sleep_time = this.unblock_expired_deadlines()?;
+        // File descriptions with a deadline (e.g. timers) may also wake up a thread blocked on them.
+        let potential_sleep_time = potential_sleep_time
+            .into_iter()
+            .chain(
+                this.machine
+                    .delayed_readiness_updates
+                    .time_until_next_deadline(&this.machine.monotonic_clock),
+            )
+            .min();
 
         let thread_manager = &mut this.machine.threads;
         let rng = this.machine.rng.get_mut();
@@ -136,8 +145,16 @@ fn schedule(&mut self) -> InterpResult<'tcx, SchedulingAction> {
         } else {
             threads_iter.choose(rng)
         };
+        let mut new_thread = new_thread.map(|(id, _thread)| id);
+        // When recording or replaying a trace, the trace has the final say. The random choice
+        // above still happens to keep the random number generator in sync.
+        if let Some(trace) = &this.machine.trace {
+            new_thread = trace
+                .borrow_mut()
+                .schedule(new_thread, |id| thread_manager.thread_ref(id).is_enabled())?;
+        }
 
-        if let Some((id, _thread)) = new_thread {
+        if let Some(id) = new_thread {
             if thread_manager.active_thread() != id {
                 thread_manager.set_active_thread(id);
             }
@@ -198,6 +215,9 @@ fn run_threads(&mut self) -> InterpResult<'tcx, !> {
             }
             match this.schedule()? {
                 SchedulingAction::ExecuteStep => {
+                    if this.machine.gdb.is_some() {
+                        this.gdb_before_step()?;
+                    }
                     this.step_current_thread()?;
                 }
                 SchedulingAction::SleepAndWaitForIo(duration) => {
@@ -2,7 +2,7 @@
 
 use std::mem;
 use std::task::Poll;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 use rand::RngExt;
 use rustc_abi::ExternAbi;
@@ -97,10 +97,20 @@ pub enum BlockReason {
     Readiness,
     /// Blocked on eventfd.
     Eventfd,
+    /// Blocked on timerfd.
+    Timerfd,
+    /// Blocked on signalfd.
+    Signalfd,
+    /// Blocked on inotify.
+    Inotify,
     /// Blocked on virtual socket.
     VirtualSocket,
     /// Blocked on an IO operation.
     IO,
+    /// Waiting for a signal (`pause`, `sigsuspend` and `sigwait`).
+    Signal,
+    /// Waiting for a child process to terminate (`waitpid`).
+    ChildProcess,
     /// Blocked for any reason related to GenMC, such as `assume` statements (GenMC mode only).
     /// Will be implicitly unblocked when GenMC schedules this thread again.
     Genmc,
@@ -489,7 +499,12 @@ pub fn active_thread_stack_mut(
         &mut self.threads[self.active_thread].stack
     }
 
-    pub(super) fn all_threads(&self) -> impl Iterator<Item = (ThreadId, &Thread<'tcx>)> {
+    /// Borrow the stack of the given thread.
+    pub fn thread_stack(&self, thread: ThreadId) -> &[Frame<'tcx, Provenance, FrameExtra<'tcx>>] {
+        &self.threads[thread].stack
+    }
+
+    pub(crate) fn all_threads(&self) -> impl Iterator<Item = (ThreadId, &Thread<'tcx>)> {
         self.threads.iter_enumerated()
     }
 
@@ -876,7 +891,8 @@ fn unblock_expired_deadlines(&mut self) -> InterpResult<'tcx, Option<Duration>>
                             instant.duration_since(this.machine.monotonic_clock.now()),
                         Deadline::RealTime(time) => {
                             assert!(communicate, "cannot have `RealTime` timeout with isolation");
-                            time.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO)
+                            time.duration_since(this.machine.monotonic_clock.system_time_now())
+                                .unwrap_or(Duration::ZERO)
                         }
                     };
 
@@ -1100,9 +1116,14 @@ fn yield_active_thread(&mut self) {
     #[inline]
     fn maybe_preempt_active_thread(&mut self) {
         let this = self.eval_context_mut();
-        if !this.machine.threads.fixed_scheduling
-            && this.machine.rng.get_mut().random_bool(this.machine.preemption_rate)
-        {
+        if this.machine.threads.fixed_scheduling {
+            return;
+        }
+        let mut preempt = this.machine.rng.get_mut().random_bool(this.machine.preemption_rate);
+        if let Some(trace) = &mut this.machine.trace {
+            preempt = trace.get_mut().preempt(preempt);
+        }
+        if preempt {
             this.yield_active_thread();
         }
     }
@@ -0,0 +1,636 @@
+//! Recording and replaying of the nondeterministic decisions made during an execution.
+//!
+//! In record mode, every scheduler decision, every weak memory load choice and the result of every
+//! read from and write to host files, stdin and sockets is appended to a trace file, as well as the
+//! other values the program obtains from the host: random bytes, file metadata, directory listings
+//! and the current directory. In replay mode, the same decisions are taken from the trace instead,
+//! and the host is not accessed: reads return the recorded data, writes the recorded result, and
+//! queries the recorded value. The first point where the execution no longer matches the trace is
+//! reported as an error. This makes it possible to reproduce a bug found with one particular seed,
+//! or with one particular input, even after the program has been changed in a way that shifts the
+//! random decisions. Output to stdout and stderr is not recorded, so that replayed executions print
+//! it as usual.
+//!
+//! Reading the host's clocks is not recorded, as that happens far too often. Instead, the clocks
+//! are virtual while a trace is recorded or replayed, like under isolation: the monotonic clock
+//! advances with the executed basic blocks, and the system clock advances with it from the time at
+//! which the trace was recorded.
+//!
+//! The trace is a text file with a header line followed by one event per line:
+//! - `clock <secs>.<nanos>`: the time of the system clock when the trace was recorded, as a
+//!   duration since the Unix epoch. This is always the first event.
+//! - `schedule <thread>`: the scheduler switched to the given thread (or found no thread for
+//!   `none`).
+//! - `preempt <checks>`: the active thread was preempted after the given number of preemption
+//!   checks that did not preempt.
+//! - `load <index>/<candidates>`: a weak memory load read from the `index`-th of the stores it
+//!   could observe, starting from the latest one.
+//! - `read <result>`: a read from the host completed, where the result is either `ok <data>` with
+//!   the data that was read in hexadecimal, or `err <kind>`.
+//! - `write <hash> <result>`: a write to the host of data with the given hash completed, where the
+//!   result is either `ok <len>` with the number of bytes written, or `err <kind>`.
+//! - `random <data>`: random bytes were obtained from the host, in hexadecimal.
+//! - `host <query> <value>`: another value was obtained from the host, e.g. `stat` for the metadata
+//!   of a file. The value is encoded by its [`HostValue`] implementation.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use std::{fmt, mem};
+
+use crate::*;
+
+const HEADER: &str = "miri-trace v3";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TraceEvent {
+    Clock(Duration),
+    Schedule(Option<ThreadId>),
+    Preempt { checks: u64 },
+    Load { index: usize, candidates: usize },
+    Read(Result<Vec<u8>, io::ErrorKind>),
+    Write { hash: u64, result: Result<usize, io::ErrorKind> },
+    Random(Vec<u8>),
+    Host { query: String, value: String },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::Clock(time) =>
+                write!(f, "clock {}.{:09}", time.as_secs(), time.subsec_nanos()),
+            TraceEvent::Schedule(Some(thread)) => write!(f, "schedule {}", thread.to_u32()),
+            TraceEvent::Schedule(None) => write!(f, "schedule none"),
+            TraceEvent::Preempt { checks } => write!(f, "preempt {checks}"),
+            TraceEvent::Load { index, candidates } => write!(f, "load {index}/{candidates}"),
+            TraceEvent::Read(Ok(data)) if data.is_empty() => write!(f, "read ok"),
+            TraceEvent::Read(Ok(data)) => write!(f, "read ok {}", encode_hex(data)),
+            TraceEvent::Read(Err(kind)) => write!(f, "read err {kind:?}"),
+            TraceEvent::Write { hash, result: Ok(len) } => write!(f, "write {hash:016x} ok {len}"),
+            TraceEvent::Write { hash, result: Err(kind) } => {
+                write!(f, "write {hash:016x} err {kind:?}")
+            }
+            TraceEvent::Random(data) => write!(f, "random {}", encode_hex(data)),
+            TraceEvent::Host { query, value } if value.is_empty() => write!(f, "host {query}"),
+            TraceEvent::Host { query, value } => write!(f, "host {query} {value}"),
+        }
+    }
+}
+
+impl FromStr for TraceEvent {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, String> {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        Ok(match kind {
+            "clock" => {
+                let (secs, nanos) =
+                    rest.split_once('.').ok_or_else(|| format!("invalid clock event `{line}`"))?;
+                TraceEvent::Clock(Duration::new(parse(secs)?, parse(nanos)?))
+            }
+            "schedule" if rest == "none" => TraceEvent::Schedule(None),
+            "schedule" => TraceEvent::Schedule(Some(ThreadId::new_unchecked(parse(rest)?))),
+            "preempt" => TraceEvent::Preempt { checks: parse(rest)? },
+            "load" => {
+                let (index, candidates) =
+                    rest.split_once('/').ok_or_else(|| format!("invalid load event `{line}`"))?;
+                TraceEvent::Load { index: parse(index)?, candidates: parse(candidates)? }
+            }
+            "read" =>
+                TraceEvent::Read(match rest.split_once(' ').unwrap_or((rest, "")) {
+                    ("ok", data) => Ok(parse_hex(data)?),
+                    ("err", kind) => Err(parse_error_kind(kind)?),
+                    _ => return Err(format!("invalid read event `{line}`")),
+                }),
+            "write" => {
+                let (hash, result) = rest
+                    .split_once(' ')
+                    .and_then(|(hash, result)| Some((hash, result.split_once(' ')?)))
+                    .ok_or_else(|| format!("invalid write event `{line}`"))?;
+                let hash =
+                    u64::from_str_radix(hash, 16).map_err(|err| format!("`{hash}`: {err}"))?;
+                TraceEvent::Write {
+                    hash,
+                    result: match result {
+                        ("ok", len) => Ok(parse(len)?),
+                        ("err", kind) => Err(parse_error_kind(kind)?),
+                        _ => return Err(format!("invalid write event `{line}`")),
+                    },
+                }
+            }
+            "random" => TraceEvent::Random(parse_hex(rest)?),
+            "host" => {
+                let (query, value) = rest.split_once(' ').unwrap_or((rest, ""));
+                if query.is_empty() {
+                    return Err(format!("invalid host event `{line}`"));
+                }
+                TraceEvent::Host { query: query.to_owned(), value: value.to_owned() }
+            }
+            _ => return Err(format!("unknown event `{line}`")),
+        })
+    }
+}
+
+#[derive(Debug)]
+enum TraceMode {
+    Record {
+        out: BufWriter<File>,
+        /// The first error that occurred while writing the trace, reported when it is finished.
+        error: Option<io::Error>,
+    },
+    Replay(Peekable<std::vec::IntoIter<TraceEvent>>),
+}
+
+/// A trace of the nondeterministic decisions of an execution that is being recorded or replayed.
+#[derive(Debug)]
+pub struct ExecutionTrace {
+    mode: TraceMode,
+    /// The time of the system clock when the trace was recorded.
+    system_epoch: SystemTime,
+    /// The number of events recorded or replayed so far.
+    position: u64,
+    /// The number of preemption checks since the last preemption.
+    preemption_checks: u64,
+}
+
+impl ExecutionTrace {
+    /// Creates a trace that records the decisions of this execution to `path`.
+    pub fn record(path: &Path) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let system_epoch = SystemTime::now();
+        let since_unix_epoch =
+            system_epoch.duration_since(SystemTime::UNIX_EPOCH).map_err(io::Error::other)?;
+        writeln!(out, "{HEADER}")?;
+        writeln!(out, "{}", TraceEvent::Clock(since_unix_epoch))?;
+        Ok(ExecutionTrace {
+            mode: TraceMode::Record { out, error: None },
+            system_epoch,
+            position: 0,
+            preemption_checks: 0,
+        })
+    }
+
+    /// Creates a trace that replays the decisions recorded in `path`.
+    pub fn replay(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut lines = BufReader::new(file).lines();
+        match lines.next() {
+            Some(Ok(header)) if header == HEADER => {}
+            Some(Err(err)) => return Err(err.to_string()),
+            _ => return Err(format!("not a Miri trace (expected `{HEADER}` header)")),
+        }
+        let events = lines
+            .enumerate()
+            .map(|(idx, line)| {
+                // The header is on the first line.
+                let line_number = idx.strict_add(2);
+                line.map_err(|err| err.to_string())?
+                    .parse()
+                    .map_err(|err| format!("line {line_number}: {err}"))
+            })
+            .collect::<Result<Vec<TraceEvent>, String>>()?;
+        let mut events = events.into_iter().peekable();
+        let Some(TraceEvent::Clock(since_unix_epoch)) = events.next() else {
+            return Err("line 2: expected a `clock` event".to_owned());
+        };
+        Ok(ExecutionTrace {
+            mode: TraceMode::Replay(events),
+            system_epoch: SystemTime::UNIX_EPOCH + since_unix_epoch,
+            position: 0,
+            preemption_checks: 0,
+        })
+    }
+
+    /// The time of the system clock when the trace was recorded, from which the virtual system
+    /// clock starts.
+    pub fn system_epoch(&self) -> SystemTime {
+        self.system_epoch
+    }
+
+    /// Flushes a recorded trace to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.mode {
+            TraceMode::Record { out, error } =>
+                match error.take() {
+                    Some(err) => Err(err),
+                    None => out.flush(),
+                },
+            TraceMode::Replay(_) => Ok(()),
+        }
+    }
+
+    /// Returns the number of events of a replayed trace that have not been reached (yet).
+    pub fn remaining_events(&self) -> usize {
+        match &self.mode {
+            TraceMode::Record { .. } => 0,
+            TraceMode::Replay(events) => events.len(),
+        }
+    }
+
+    /// Takes one decision: in record mode, `actual` is written to the trace and returned. In replay
+    /// mode, the next event of the trace is returned instead, which has to be of the same kind.
+    fn decide<'tcx>(&mut self, actual: TraceEvent) -> InterpResult<'tcx, TraceEvent> {
+        self.position = self.position.strict_add(1);
+        match &mut self.mode {
+            TraceMode::Record { out, error } => {
+                write_event(out, error, &actual);
+                interp_ok(actual)
+            }
+            TraceMode::Replay(events) =>
+                match events.next() {
+                    Some(event) if mem::discriminant(&event) == mem::discriminant(&actual) =>
+                        interp_ok(event),
+                    event => self.diverged(event.as_ref(), &actual),
+                },
+        }
+    }
+
+    fn diverged<'tcx, T>(
+        &self,
+        expected: Option<&TraceEvent>,
+        found: &dyn fmt::Display,
+    ) -> InterpResult<'tcx, T> {
+        throw_machine_stop!(TerminationInfo::ReplayDivergence {
+            event: self.position,
+            expected: expected.map_or_else(|| "the end of the trace".to_owned(), |e| e.to_string()),
+            found: found.to_string(),
+        })
+    }
+
+    /// Records or replays which thread the scheduler switches to. `actual` is the thread that was
+    /// picked by the scheduler, and `is_enabled` checks whether a thread may be scheduled.
+    pub fn schedule<'tcx>(
+        &mut self,
+        actual: Option<ThreadId>,
+        is_enabled: impl Fn(ThreadId) -> bool,
+    ) -> InterpResult<'tcx, Option<ThreadId>> {
+        let actual = TraceEvent::Schedule(actual);
+        let event = self.decide(actual.clone())?;
+        match (&event, &actual) {
+            // Only enabled threads can be scheduled, and if there is one, the scheduler must
+            // pick one.
+            (&TraceEvent::Schedule(Some(thread)), _) if is_enabled(thread) =>
+                interp_ok(Some(thread)),
+            (TraceEvent::Schedule(None), TraceEvent::Schedule(None)) => interp_ok(None),
+            _ => self.diverged(Some(&event), &actual),
+        }
+    }
+
+    /// Records or replays whether the active thread gets preempted. `actual` is the random
+    /// decision.
+    pub fn preempt(&mut self, actual: bool) -> bool {
+        let checks = self.preemption_checks;
+        let preempt = match &mut self.mode {
+            TraceMode::Record { out, error } => {
+                if actual {
+                    write_event(out, error, &TraceEvent::Preempt { checks });
+                }
+                actual
+            }
+            // Preemptions are replayed after the same number of checks. If the execution diverged
+            // and reaches a different decision first, that decision reports the divergence.
+            TraceMode::Replay(events) =>
+                events.next_if_eq(&TraceEvent::Preempt { checks }).is_some(),
+        };
+        if preempt {
+            self.position = self.position.strict_add(1);
+            self.preemption_checks = 0;
+        } else {
+            self.preemption_checks = checks.strict_add(1);
+        }
+        preempt
+    }
+
+    /// Records or replays which of the `candidates` stores a weak memory load reads from. `actual`
+    /// is the index of the randomly chosen store.
+    pub fn load<'tcx>(&mut self, actual: usize, candidates: usize) -> InterpResult<'tcx, usize> {
+        let actual = TraceEvent::Load { index: actual, candidates };
+        let event = self.decide(actual.clone())?;
+        match event {
+            TraceEvent::Load { index, candidates: expected }
+                if expected == candidates && index < candidates =>
+                interp_ok(index),
+            _ => self.diverged(Some(&event), &actual),
+        }
+    }
+
+    /// Records or replays a read from the host into `buf`. In record mode, `read` is called and the
+    /// data it read is recorded. In replay mode, `buf` is filled with the recorded data instead and
+    /// `read` is not called.
+    pub fn host_read<'tcx>(
+        &mut self,
+        buf: &mut [u8],
+        read: impl FnOnce(&mut [u8]) -> io::Result<usize>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        self.position = self.position.strict_add(1);
+        match &mut self.mode {
+            TraceMode::Record { out, error } => {
+                let result = read(buf);
+                let event = TraceEvent::Read(match &result {
+                    Ok(len) => Ok(buf[..*len].to_vec()),
+                    Err(err) => Err(err.kind()),
+                });
+                write_event(out, error, &event);
+                interp_ok(result)
+            }
+            TraceMode::Replay(events) =>
+                match events.next() {
+                    // The program may read into a smaller buffer than when the trace was recorded.
+                    Some(TraceEvent::Read(Ok(data))) if data.len() <= buf.len() => {
+                        buf[..data.len()].copy_from_slice(&data);
+                        interp_ok(Ok(data.len()))
+                    }
+                    Some(TraceEvent::Read(Err(kind))) => interp_ok(Err(kind.into())),
+                    event => self.diverged(event.as_ref(), &format!("read of {} bytes", buf.len())),
+                },
+        }
+    }
+
+    /// Records or replays a write of `data` to the host. In record mode, `write` is called and its
+    /// result is recorded. In replay mode, the recorded result is returned instead and `write` is
+    /// not called. The data has to be the same as when the trace was recorded.
+    pub fn host_write<'tcx>(
+        &mut self,
+        data: &[u8],
+        write: impl FnOnce(&[u8]) -> io::Result<usize>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        self.position = self.position.strict_add(1);
+        let hash = fnv1a(data);
+        match &mut self.mode {
+            TraceMode::Record { out, error } => {
+                let result = write(data);
+                let event = TraceEvent::Write {
+                    hash,
+                    result: result.as_ref().copied().map_err(|err| err.kind()),
+                };
+                write_event(out, error, &event);
+                interp_ok(result)
+            }
+            TraceMode::Replay(events) =>
+                match events.next() {
+                    Some(TraceEvent::Write { hash: expected, result })
+                        if expected == hash && result.is_ok_and(|len| len <= data.len()) =>
+                        interp_ok(result.map_err(io::Error::from)),
+                    Some(TraceEvent::Write { hash: expected, result: Err(kind) })
+                        if expected == hash =>
+                        interp_ok(Err(kind.into())),
+                    event => self.diverged(event.as_ref(), &format!("write {hash:016x}")),
+                },
+        }
+    }
+
+    /// Records or replays random bytes obtained from the host. In record mode, `fill` is called
+    /// to fill `buf` and the bytes are recorded. In replay mode, `buf` is filled with the recorded
+    /// bytes instead and `fill` is not called.
+    pub fn host_random<'tcx>(
+        &mut self,
+        buf: &mut [u8],
+        fill: impl FnOnce(&mut [u8]) -> InterpResult<'tcx>,
+    ) -> InterpResult<'tcx> {
+        self.position = self.position.strict_add(1);
+        match &mut self.mode {
+            TraceMode::Record { out, error } => {
+                fill(buf)?;
+                write_event(out, error, &TraceEvent::Random(buf.to_vec()));
+                interp_ok(())
+            }
+            TraceMode::Replay(events) =>
+                match events.next() {
+                    Some(TraceEvent::Random(data)) if data.len() == buf.len() => {
+                        buf.copy_from_slice(&data);
+                        interp_ok(())
+                    }
+                    event =>
+                        self.diverged(event.as_ref(), &format!("random of {} bytes", buf.len())),
+                },
+        }
+    }
+
+    /// Replays the value of a query to the host, such as `stat`. Returns `None` in record mode,
+    /// where the host has to be queried and the result passed to `record_host_value`.
+    pub fn replay_host_value<'tcx, T: HostValue>(
+        &mut self,
+        query: &'static str,
+    ) -> InterpResult<'tcx, Option<T>> {
+        let TraceMode::Replay(events) = &mut self.mode else {
+            return interp_ok(None);
+        };
+        self.position = self.position.strict_add(1);
+        let event = events.next();
+        if let Some(TraceEvent::Host { query: expected, value }) = &event
+            && expected == query
+            && let Ok(value) = T::decode(value)
+        {
+            return interp_ok(Some(value));
+        }
+        self.diverged(event.as_ref(), &format!("host {query}"))
+    }
+
+    /// Records the value of a query to the host, such as `stat`.
+    pub fn record_host_value<T: HostValue>(&mut self, query: &'static str, value: &T) {
+        let TraceMode::Record { out, error } = &mut self.mode else {
+            panic!("host values are only recorded in record mode");
+        };
+        self.position = self.position.strict_add(1);
+        let event = TraceEvent::Host { query: query.to_owned(), value: value.encode() };
+        write_event(out, error, &event);
+    }
+}
+
+/// A value obtained from the host that can be recorded in a trace.
+pub trait HostValue: Sized {
+    /// Encodes the value on a single line.
+    fn encode(&self) -> String;
+    /// Decodes a value encoded by `encode`.
+    fn decode(s: &str) -> Result<Self, String>;
+}
+
+impl<T: HostValue> HostValue for io::Result<T> {
+    fn encode(&self) -> String {
+        match self {
+            Ok(value) => {
+                let value = value.encode();
+                if value.is_empty() { "ok".to_owned() } else { format!("ok {value}") }
+            }
+            Err(err) => format!("err {:?}", err.kind()),
+        }
+    }
+
+    fn decode(s: &str) -> Result<Self, String> {
+        match s.split_once(' ').unwrap_or((s, "")) {
+            ("ok", value) => Ok(Ok(T::decode(value)?)),
+            ("err", kind) => Ok(Err(parse_error_kind(kind)?.into())),
+            _ => Err(format!("invalid result `{s}`")),
+        }
+    }
+}
+
+impl HostValue for PathBuf {
+    fn encode(&self) -> String {
+        encode_hex(self.as_os_str().as_encoded_bytes())
+    }
+
+    fn decode(s: &str) -> Result<Self, String> {
+        let bytes = parse_hex(s)?;
+        cfg_select! {
+            unix => {
+                use std::os::unix::ffi::OsStringExt;
+                Ok(PathBuf::from(OsString::from_vec(bytes)))
+            }
+            _ => String::from_utf8(bytes).map(PathBuf::from).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Writes an event to a recorded trace. Only the first error is kept.
+fn write_event(out: &mut BufWriter<File>, error: &mut Option<io::Error>, event: &TraceEvent) {
+    if error.is_none()
+        && let Err(err) = writeln!(out, "{event}")
+    {
+        *error = Some(err);
+    }
+}
+
+fn parse<T: FromStr<Err: fmt::Display>>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|err| format!("`{s}`: {err}"))
+}
+
+/// Encodes data in hexadecimal, for [`HostValue`] implementations.
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes data encoded by [`encode_hex`].
+pub fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    if s.len() % 2 != 0 {
+        return Err(format!("`{s}`: odd number of hex digits"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            let byte = s.get(i..i + 2).ok_or_else(|| format!("`{s}`: invalid hex data"))?;
+            u8::from_str_radix(byte, 16).map_err(|err| format!("`{byte}`: {err}"))
+        })
+        .collect()
+}
+
+/// Parses the `Debug` representation of an `io::ErrorKind`. Kinds that cannot be reported to the
+/// program anyway are replayed as `Other`.
+fn parse_error_kind(s: &str) -> Result<io::ErrorKind, String> {
+    if s.is_empty() || s.contains(' ') {
+        return Err(format!("invalid error kind `{s}`"));
+    }
+    Ok(crate::shims::io_error::UNIX_IO_ERROR_TABLE
+        .iter()
+        .map(|&(_, kind)| kind)
+        .find(|kind| format!("{kind:?}") == s)
+        .unwrap_or(io::ErrorKind::Other))
+}
+
+/// A small stable hash, so that traces do not have to contain the data that was written.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn events_roundtrip() {
+        let events = [
+            TraceEvent::Clock(Duration::new(1_000_000_000, 42)),
+            TraceEvent::Schedule(Some(ThreadId::new_unchecked(3))),
+            TraceEvent::Schedule(None),
+            TraceEvent::Preempt { checks: 42 },
+            TraceEvent::Load { index: 1, candidates: 4 },
+            TraceEvent::Read(Ok(b"hello\n".to_vec())),
+            TraceEvent::Read(Ok(Vec::new())),
+            TraceEvent::Read(Err(io::ErrorKind::NotFound)),
+            TraceEvent::Write { hash: 0x0123456789abcdef, result: Ok(5) },
+            TraceEvent::Write { hash: 1, result: Err(io::ErrorKind::BrokenPipe) },
+            TraceEvent::Random(vec![0, 1, 255]),
+            TraceEvent::Host { query: "cwd".to_owned(), value: "ok 2f746d70".to_owned() },
+            TraceEvent::Host { query: "readdir".to_owned(), value: String::new() },
+        ];
+        for event in events {
+            assert_eq!(event.to_string().parse::<TraceEvent>().unwrap(), event);
+        }
+        assert!("load 1".parse::<TraceEvent>().is_err());
+        assert!("schedule main".parse::<TraceEvent>().is_err());
+        assert!("yield 1".parse::<TraceEvent>().is_err());
+        assert!("read ok 123".parse::<TraceEvent>().is_err());
+        assert!("read ok zz".parse::<TraceEvent>().is_err());
+        assert!("write 1 ok".parse::<TraceEvent>().is_err());
+        assert!("clock 1".parse::<TraceEvent>().is_err());
+        assert!("host".parse::<TraceEvent>().is_err());
+    }
+
+    #[test]
+    fn host_values_roundtrip() {
+        let values: [io::Result<PathBuf>; 2] =
+            [Ok(PathBuf::from("/tmp/dir with spaces")), Err(io::ErrorKind::NotFound.into())];
+        for value in values {
+            let decoded = io::Result::<PathBuf>::decode(&value.encode()).unwrap();
+            match (value, decoded) {
+                (Ok(value), Ok(decoded)) => assert_eq!(value, decoded),
+                (Err(value), Err(decoded)) => assert_eq!(value.kind(), decoded.kind()),
+                (value, decoded) => panic!("{value:?} was decoded as {decoded:?}"),
+            }
+        }
+        assert!(io::Result::<PathBuf>::decode("ok 2f7").is_err());
+        assert!(io::Result::<PathBuf>::decode("maybe").is_err());
+    }
+
+    #[test]
+    fn host_io_record_then_replay() {
+        let path = std::env::temp_dir().join(format!("miri-trace-test-{}", std::process::id()));
+
+        let mut trace = ExecutionTrace::record(&path).unwrap();
+        let mut buf = [0; 8];
+        let read = trace.host_read(&mut buf, |buf| (&b"hello"[..]).read(buf)).report_err();
+        assert_eq!(read.unwrap().unwrap(), 5);
+        let write = trace.host_write(b"world", |data| Ok(data.len() - 1)).report_err();
+        assert_eq!(write.unwrap().unwrap(), 4);
+        let failed = trace.host_write(b"!", |_| Err(io::ErrorKind::BrokenPipe.into())).report_err();
+        assert_eq!(failed.unwrap().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        let mut random = [0; 2];
+        let fill = |buf: &mut [u8]| {
+            buf.fill(7);
+            interp_ok(())
+        };
+        trace.host_random(&mut random, fill).report_err().unwrap();
+        assert_eq!(trace.replay_host_value::<PathBuf>("cwd").report_err().unwrap(), None);
+        trace.record_host_value("cwd", &PathBuf::from("/tmp"));
+        let system_epoch = trace.system_epoch();
+        trace.flush().unwrap();
+        drop(trace);
+
+        // Replaying does not touch the host, but returns the recorded results.
+        let mut trace = ExecutionTrace::replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut buf = [0; 8];
+        let read = trace.host_read(&mut buf, |_| unreachable!()).report_err();
+        assert_eq!(read.unwrap().unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        let write = trace.host_write(b"world", |_| unreachable!()).report_err();
+        assert_eq!(write.unwrap().unwrap(), 4);
+        let failed = trace.host_write(b"!", |_| unreachable!()).report_err();
+        assert_eq!(failed.unwrap().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        let mut random = [0; 2];
+        trace.host_random(&mut random, |_| unreachable!()).report_err().unwrap();
+        assert_eq!(random, [7, 7]);
+        let cwd = trace.replay_host_value::<PathBuf>("cwd").report_err().unwrap();
+        assert_eq!(cwd, Some(PathBuf::from("/tmp")));
+        assert_eq!(trace.system_epoch(), system_epoch);
+        assert_eq!(trace.remaining_events(), 0);
+    }
+}
@@ -95,9 +95,9 @@
 
 use rustc_data_structures::fx::FxHashMap;
 
-use super::AllocDataRaceHandler;
 use super::data_race::{GlobalState as DataRaceState, ThreadClockSet};
 use super::vector_clock::{VClock, VTimestamp, VectorIdx};
+use super::{AllocDataRaceHandler, ExecutionTrace};
 use crate::concurrency::GlobalDataRaceHandler;
 use crate::data_structures::range_object_map::{AccessType, RangeObjectMap};
 use crate::*;
@@ -297,6 +297,7 @@ fn buffered_read(
         thread_mgr: &ThreadManager<'_>,
         is_seqcst: bool,
         rng: &mut (impl rand::Rng + ?Sized),
+        trace: Option<&mut ExecutionTrace>,
         validate: impl FnOnce(Option<&VClock>) -> InterpResult<'tcx>,
     ) -> InterpResult<'tcx, (Option<Scalar>, LoadRecency)> {
         // Having a live borrow to store_buffer while calling validate_atomic_load is fine
@@ -307,7 +308,7 @@ fn buffered_read(
             // as the race detector will update it
             let (.., clocks) = global.active_thread_state(thread_mgr);
             // Load from a valid entry in the store buffer
-            self.fetch_store(is_seqcst, &clocks, &mut *rng)
+            self.fetch_store(is_seqcst, &clocks, &mut *rng, trace)?
         };
 
         // Unlike in buffered_atomic_write, thread clock updates have to be done
@@ -335,13 +336,15 @@ fn buffered_write(
         interp_ok(())
     }
 
-    /// Selects a valid store element in the buffer.
+    /// Selects a valid store element in the buffer. If a trace is being recorded or replayed, the
+    /// choice is made by the trace.
     fn fetch_store<R: rand::Rng + ?Sized>(
         &self,
         is_seqcst: bool,
         clocks: &ThreadClockSet,
         rng: &mut R,
-    ) -> (&StoreElement, LoadRecency) {
+        trace: Option<&mut ExecutionTrace>,
+    ) -> InterpResult<'tcx, (&StoreElement, LoadRecency)> {
         use rand::seq::IteratorRandom;
         let mut found_sc = false;
         // FIXME: we want an inclusive take_while (stops after a false predicate, but
@@ -398,7 +401,7 @@ fn fetch_store<R: rand::Rng + ?Sized>(
 
                 true
             })
-            .filter(|&store_elem| {
+            .filter(move |&store_elem| {
                 if is_seqcst && store_elem.is_seqcst {
                     // An SC load needs to ignore all but last store marked SC (stores not marked SC are not
                     // affected)
@@ -410,11 +413,16 @@ fn fetch_store<R: rand::Rng + ?Sized>(
                 }
             });
 
-        let chosen = candidates.choose(rng).expect("store buffer cannot be empty");
+        let (index, mut chosen) =
+            candidates.clone().enumerate().choose(rng).expect("store buffer cannot be empty");
+        if let Some(trace) = trace {
+            let index = trace.load(index, candidates.clone().count())?;
+            chosen = candidates.clone().nth(index).unwrap();
+        }
         if std::ptr::eq(chosen, self.buffer.back().expect("store buffer cannot be empty")) {
-            (chosen, LoadRecency::Latest)
+            interp_ok((chosen, LoadRecency::Latest))
         } else {
-            (chosen, LoadRecency::Outdated)
+            interp_ok((chosen, LoadRecency::Outdated))
         }
     }
 
@@ -560,6 +568,7 @@ fn buffered_atomic_read(
                         global.sc_read(&this.machine.threads);
                     }
                     let mut rng = this.machine.rng.borrow_mut();
+                    let mut trace = this.machine.trace.as_ref().map(|trace| trace.borrow_mut());
                     let Some(buffer) = alloc_buffers
                         .get_store_buffer(alloc_range(base_offset, place.layout.size))?
                     else {
@@ -571,6 +580,7 @@ fn buffered_atomic_read(
                         &this.machine.threads,
                         atomic == AtomicReadOrd::SeqCst,
                         &mut *rng,
+                        trace.as_deref_mut(),
                         validate,
                     )?;
                     if global.track_outdated_loads && recency == LoadRecency::Outdated {
@@ -0,0 +1,210 @@
+//! Source code coverage for `-Zmiri-coverage`, written as an lcov tracefile (the format of
+//! `llvm-cov export -format=lcov`), which `genhtml` and most coverage tools understand.
+//!
+//! When a crate is built with `-Cinstrument-coverage`, the `InstrumentCoverage` MIR pass puts a
+//! counter statement into the first basic block of every node of the function's coverage graph, and
+//! records which source regions are covered by which of those nodes. Miri does not need the
+//! physical counters LLVM would create from that: it counts how often each block with a counter
+//! statement is entered, which gives the exact execution count of every node.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir;
+use rustc_middle::mir::coverage::{BasicCoverageBlock, CoverageKind, MappingKind};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::Span;
+
+use crate::*;
+
+/// The execution counts of all coverage graph nodes that have been reached.
+#[derive(Default, Debug)]
+pub struct CoverageCounters {
+    counts: FxHashMap<DefId, FxHashMap<BasicCoverageBlock, u64>>,
+}
+
+/// A source region of a function, with the number of times it was executed.
+#[derive(Debug)]
+struct Region {
+    start_line: usize,
+    end_line: usize,
+    /// The length of the region in bytes, to find the innermost of nested regions.
+    len: u32,
+    count: u64,
+}
+
+#[derive(Default, Debug)]
+struct FileCoverage {
+    /// The functions in this file, as (name, line, number of calls).
+    functions: Vec<(String, usize, u64)>,
+    regions: Vec<Region>,
+    /// The branches in this file, as (line, times taken, times not taken).
+    branches: Vec<(usize, u64, u64)>,
+}
+
+impl CoverageCounters {
+    /// Counts the coverage graph nodes of the basic block that `frame` is executing.
+    pub fn enter_block(&mut self, frame: &Frame<'_, Provenance, FrameExtra<'_>>) {
+        let body = frame.body();
+        if body.function_coverage_info.is_none() {
+            return;
+        }
+        // Coverage information only exists for the bodies of actual items, not for shims.
+        let ty::InstanceKind::Item(def_id) = frame.instance().def else { return };
+        let Either::Left(loc) = frame.current_loc() else { return };
+        for stmt in &body.basic_blocks[loc.block].statements {
+            if let mir::StatementKind::Coverage(CoverageKind::VirtualCounter { bcb }) = stmt.kind {
+                let count = self.counts.entry(def_id).or_default().entry(bcb).or_default();
+                *count = count.strict_add(1);
+            }
+        }
+    }
+
+    /// Writes the coverage of all functions that were executed, and of all instrumented functions
+    /// of the local crate (so that functions that were never called show up as uncovered).
+    pub fn write(&self, path: &Path, tcx: TyCtxt<'_>) -> io::Result<()> {
+        let local_fns = tcx
+            .mir_keys(())
+            .iter()
+            .map(|def_id| def_id.to_def_id())
+            .filter(|&def_id| tcx.def_kind(def_id).is_fn_like());
+        let mut fns: Vec<DefId> = self.counts.keys().copied().chain(local_fns).collect();
+        let mut seen = FxHashSet::default();
+        fns.retain(|def_id| seen.insert(*def_id));
+
+        let sm = tcx.sess.source_map();
+        let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+        let file_of = |span: Span| {
+            let pos = sm.lookup_char_pos(span.lo());
+            pos.file
+                .name
+                .is_real()
+                .then(|| (pos.file.name.prefer_local_unconditionally().to_string(), pos.line))
+        };
+        for def_id in fns {
+            let body = tcx.optimized_mir(def_id);
+            let Some(info) = body.function_coverage_info.as_deref() else { continue };
+            let counts = self.counts.get(&def_id);
+            let count = |bcb| counts.and_then(|counts| counts.get(&bcb)).copied().unwrap_or(0);
+
+            let Some((file, line)) = file_of(body.span) else { continue };
+            let name = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+            let calls = count(BasicCoverageBlock::START_BCB);
+            files.entry(file).or_default().functions.push((name, line, calls));
+
+            for mapping in &info.mappings {
+                let Some((file, start_line)) = file_of(mapping.span) else { continue };
+                let file = files.entry(file).or_default();
+                match mapping.kind {
+                    MappingKind::Code { bcb } => {
+                        let end_line = sm.lookup_char_pos(mapping.span.hi()).line;
+                        let len = mapping.span.hi().0.saturating_sub(mapping.span.lo().0);
+                        file.regions.push(Region { start_line, end_line, len, count: count(bcb) });
+                    }
+                    MappingKind::Branch { true_bcb, false_bcb } =>
+                        file.branches.push((start_line, count(true_bcb), count(false_bcb))),
+                }
+            }
+        }
+
+        let mut out = BufWriter::new(File::create(path)?);
+        for (file, coverage) in &files {
+            write_file(&mut out, file, coverage)?;
+        }
+        out.flush()
+    }
+}
+
+/// Writes the lcov record of one source file.
+fn write_file(out: &mut impl Write, file: &str, coverage: &FileCoverage) -> io::Result<()> {
+    writeln!(out, "SF:{file}")?;
+    for (name, line, _) in &coverage.functions {
+        writeln!(out, "FN:{line},{name}")?;
+    }
+    for (name, _, calls) in &coverage.functions {
+        writeln!(out, "FNDA:{calls},{name}")?;
+    }
+    let hit = coverage.functions.iter().filter(|(_, _, calls)| *calls > 0).count();
+    writeln!(out, "FNF:{}", coverage.functions.len())?;
+    writeln!(out, "FNH:{hit}")?;
+
+    for (block, &(line, taken, not_taken)) in coverage.branches.iter().enumerate() {
+        writeln!(out, "BRDA:{line},{block},0,{taken}")?;
+        writeln!(out, "BRDA:{line},{block},1,{not_taken}")?;
+    }
+    let hit = coverage
+        .branches
+        .iter()
+        .flat_map(|&(_, taken, not_taken)| [taken, not_taken])
+        .filter(|&count| count > 0)
+        .count();
+    writeln!(out, "BRF:{}", coverage.branches.len().strict_mul(2))?;
+    writeln!(out, "BRH:{hit}")?;
+
+    let lines = line_counts(&coverage.regions);
+    for (line, count) in &lines {
+        writeln!(out, "DA:{line},{count}")?;
+    }
+    writeln!(out, "LF:{}", lines.len())?;
+    writeln!(out, "LH:{}", lines.values().filter(|&&count| count > 0).count())?;
+    writeln!(out, "end_of_record")
+}
+
+/// Computes the execution count of every line that is covered by a region. Like `llvm-cov`, this
+/// uses the highest count of the regions that start on a line, and otherwise the count of the
+/// innermost region the line is part of.
+fn line_counts(regions: &[Region]) -> BTreeMap<usize, u64> {
+    let mut starting: BTreeMap<usize, u64> = BTreeMap::new();
+    for region in regions {
+        let count = starting.entry(region.start_line).or_default();
+        *count = (*count).max(region.count);
+    }
+    let mut lines = BTreeMap::new();
+    for region in regions {
+        for line in region.start_line..=region.end_line {
+            if lines.contains_key(&line) {
+                continue;
+            }
+            let count = match starting.get(&line) {
+                Some(&count) => count,
+                None =>
+                    regions
+                        .iter()
+                        .filter(|r| r.start_line <= line && line <= r.end_line)
+                        .min_by_key(|r| r.len)
+                        .map_or(0, |r| r.count),
+            };
+            lines.insert(line, count);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_regions() {
+        let region = |start_line, end_line, len, count| Region { start_line, end_line, len, count };
+        // A function body that ran once, with an `if` that was never taken and a loop whose body
+        // ran three times.
+        let regions =
+            [region(1, 10, 200, 1), region(3, 5, 40, 0), region(6, 8, 50, 3), region(7, 7, 10, 1)];
+        let lines = line_counts(&regions);
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[&2], 1);
+        assert_eq!(lines[&3], 0);
+        assert_eq!(lines[&4], 0);
+        assert_eq!(lines[&6], 3);
+        // A region that starts on a line takes precedence over the region around it.
+        assert_eq!(lines[&7], 1);
+        assert_eq!(lines[&8], 3);
+        assert_eq!(lines[&9], 1);
+    }
+}
@@ -39,6 +39,13 @@ pub enum TerminationInfo {
     GlobalDeadlock,
     /// Some thread discovered a deadlock condition (e.g. in a mutex with reentrancy checking).
     LocalDeadlock,
+    /// The execution no longer matches the trace that is being replayed.
+    ReplayDivergence {
+        /// The 1-based index of the first event that did not match.
+        event: u64,
+        expected: String,
+        found: String,
+    },
     MultipleSymbolDefinitions {
         link_name: Symbol,
         first: SpanData,
@@ -84,6 +91,12 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             TreeBorrowsUb { title, .. } => write!(f, "{title}"),
             GlobalDeadlock => write!(f, "the evaluated program deadlocked"),
             LocalDeadlock => write!(f, "a thread deadlocked"),
+            ReplayDivergence { event, expected, found } =>
+                write!(
+                    f,
+                    "the execution diverged from the replayed trace at event {event}: \
+                    expected `{expected}`, found `{found}`"
+                ),
             GenmcMoot => write!(f, "GenMC wants to skip this execution"),
             MultipleSymbolDefinitions { link_name, .. } =>
                 write!(f, "multiple definitions of symbol `{link_name}`"),
@@ -266,6 +279,10 @@ pub fn report_result<'tcx>(
                 labels.push(format!("thread got stuck here"));
                 None
             }
+            ReplayDivergence { .. } => {
+                labels.push(format!("the execution diverged here"));
+                None
+            }
             GlobalDeadlock => {
                 // Global deadlocks are reported differently: just show all blocked threads.
                 // The "active" thread might actually be terminated, so we ignore it.
@@ -343,6 +360,11 @@ pub fn report_result<'tcx>(
                 ],
             SymbolShimClashing { link_name, span } =>
                 vec![note_span!(*span, "the `{link_name}` symbol is defined here")],
+            ReplayDivergence { .. } =>
+                vec![
+                    note!("the program followed the replayed trace up to this point, but now makes a decision or observes a host I/O result that differs from the recording"),
+                    note!("use `-Zmiri-record-trace` to record a new trace for the current version of the program"),
+                ],
             Int2PtrWithStrictProvenance =>
                 vec![note!("use Strict Provenance APIs (https://doc.rust-lang.org/nightly/std/ptr/index.html#strict-provenance, https://crates.io/crates/sptr) instead")],
             DataRace { op1, extra, retag_explain, .. } => {
@@ -25,6 +25,7 @@
 use crate::concurrency::thread::TlsAllocAction;
 use crate::diagnostics::report_leaks;
 use crate::helpers::is_no_core;
+use crate::leak_report::{LeakAllowlist, LeakReport};
 use crate::shims::{global_ctor, tls};
 use crate::*;
 
@@ -168,8 +169,36 @@ pub struct MiriConfig {
     pub float_rounding_error: FloatRoundingErrorMode,
     /// Whether Miri artificially introduces short reads/writes on file descriptors.
     pub short_fd_operations: bool,
+    /// Whether the completions of io_uring operations that complete together are posted in random
+    /// order.
+    pub io_uring_shuffle_completions: bool,
     /// A list of crates that are considered user-relevant.
     pub user_relevant_crates: Vec<String>,
+    /// Whether file system accesses are served by an in-memory file system.
+    pub virtual_fs: bool,
+    /// The host directory or tar file to seed the in-memory file system with.
+    pub virtual_fs_seed: Option<PathBuf>,
+    /// If `Some`, the final state of the in-memory file system is written to this file.
+    pub virtual_fs_dump: Option<PathBuf>,
+    /// If `Some`, the source code coverage is written to this file in lcov format.
+    pub coverage: Option<PathBuf>,
+    /// If `Some`, a heap profile in DHAT's JSON format is written to this file.
+    pub heap_profile: Option<PathBuf>,
+    /// If `Some`, the memory leaks are written to this file as a JSON report grouped by allocation
+    /// site.
+    pub leak_report: Option<PathBuf>,
+    /// If `Some`, leaks allocated at a backtrace matching a pattern in this file are not reported.
+    pub leak_allowlist: Option<PathBuf>,
+    /// If `Some`, the nondeterministic decisions of the execution are recorded to this file.
+    pub record_trace: Option<PathBuf>,
+    /// If `Some`, the nondeterministic decisions of the execution are replayed from this file.
+    pub replay_trace: Option<PathBuf>,
+    /// If `Some`, wait for a debugger to connect to this address (or port) before running the
+    /// program.
+    pub gdb_server: Option<String>,
+    /// The directory Miri was started in and its command-line arguments up to `--`, to start Miri
+    /// again when the program spawns itself.
+    pub invocation: Option<(PathBuf, Vec<String>)>,
 }
 
 impl Default for MiriConfig {
@@ -211,7 +240,19 @@ fn default() -> MiriConfig {
             float_nondet: true,
             float_rounding_error: FloatRoundingErrorMode::Random,
             short_fd_operations: true,
+            io_uring_shuffle_completions: false,
             user_relevant_crates: vec![],
+            virtual_fs: false,
+            virtual_fs_seed: None,
+            virtual_fs_dump: None,
+            coverage: None,
+            heap_profile: None,
+            leak_report: None,
+            leak_allowlist: None,
+            record_trace: None,
+            replay_trace: None,
+            gdb_server: None,
+            invocation: None,
         }
     }
 }
@@ -519,8 +560,14 @@ pub fn eval_entry<'tcx>(
     config: &MiriConfig,
     genmc_ctx: Option<Rc<GenmcCtx>>,
 ) -> Result<(), NonZeroI32> {
-    // Copy setting before we move `config`.
+    // Copy settings before we move `config`.
     let ignore_leaks = config.ignore_leaks;
+    let virtual_fs_dump = config.virtual_fs_dump.clone();
+    let coverage = config.coverage.clone();
+    let heap_profile = config.heap_profile.clone();
+    let leak_report = config.leak_report.clone();
+    let leak_allowlist = config.leak_allowlist.clone();
+    let cmd = config.args.join(" ");
 
     let mut ecx = match create_ecx(tcx, entry_id, entry_type, config, genmc_ctx).report_err() {
         Ok(v) => v,
@@ -546,12 +593,80 @@ pub fn eval_entry<'tcx>(
     // indicate an error.
     let Err(res) = res.report_err();
 
+    // Tell the debugger how the program ended. If Miri stopped it with an error, the debugger can
+    // still inspect the stack at the point of the error.
+    if let Some(mut gdb) = ecx.machine.gdb.take() {
+        let exit_code = match res.kind() {
+            InterpErrorKind::MachineStop(info) =>
+                match info.downcast_ref::<TerminationInfo>() {
+                    Some(&TerminationInfo::Exit { code, .. }) => Some(code),
+                    _ => None,
+                },
+            _ => None,
+        };
+        let reported = match exit_code {
+            Some(code) => gdb.report_exit(code),
+            None => gdb.report_error(&ecx, &res.to_string()),
+        };
+        if let Err(err) = reported {
+            tcx.dcx().warn(format!("failed to report the termination to the debugger: {err}"));
+        }
+    }
+
+    // Write out the recorded trace, the coverage and the heap profile. This happens before the
+    // error is reported since they are also useful when the execution stopped with an error.
+    let mut output_failed = false;
+    if let Some(trace) = &ecx.machine.trace
+        && let Err(err) = trace.borrow_mut().flush()
+    {
+        tcx.dcx().err(format!("failed to write the execution trace: {err}"));
+        output_failed = true;
+    }
+    if let Some(path) = &coverage
+        && let Some(counters) = &ecx.machine.coverage
+        && let Err(err) = counters.write(path, tcx)
+    {
+        tcx.dcx().err(format!("failed to write the coverage to {}: {err}", path.display()));
+        output_failed = true;
+    }
+    if let Some(path) = &heap_profile
+        && let Some(heap_profiler) = &ecx.machine.heap_profiler
+        && let Err(err) =
+            heap_profiler.borrow_mut().write(path, tcx, &cmd, ecx.machine.basic_block_count)
+    {
+        tcx.dcx().err(format!("failed to write the heap profile to {}: {err}", path.display()));
+        output_failed = true;
+    }
+
     // Error reporting: if we survive all checks, we return the exit code the program gave us.
     'miri_error: {
         // Show diagnostic, if any.
         let Some((return_code, leak_check)) = report_result(&ecx, res) else {
             break 'miri_error;
         };
+        if output_failed {
+            break 'miri_error;
+        }
+        if let Some(trace) = &ecx.machine.trace {
+            let remaining = trace.borrow().remaining_events();
+            if remaining > 0 {
+                tcx.dcx().warn(format!(
+                    "the program terminated before the end of the replayed trace was reached \
+                    ({remaining} events left)"
+                ));
+            }
+        }
+
+        if let Some(path) = &virtual_fs_dump
+            && let Some(vfs) = &ecx.machine.vfs
+            && let Err(err) = vfs.dump(path)
+        {
+            tcx.dcx().err(format!(
+                "failed to dump the virtual file system to {}: {err}",
+                path.display()
+            ));
+            break 'miri_error;
+        }
 
         // If we get here there was no fatal error -- yet.
         // Possibly check for memory leaks.
@@ -565,7 +680,43 @@ pub fn eval_entry<'tcx>(
             }
             // Check for memory leaks.
             info!("Additional static roots: {:?}", ecx.machine.static_roots);
-            let leaks = ecx.take_leaked_allocations(|ecx| &ecx.machine.static_roots);
+            let mut leaks = ecx.take_leaked_allocations(|ecx| &ecx.machine.static_roots);
+            let suppressed = match &leak_allowlist {
+                Some(path) =>
+                    match LeakAllowlist::read(path) {
+                        Ok(allowlist) => allowlist.filter(&ecx, &mut leaks),
+                        Err(err) => {
+                            tcx.dcx().err(format!(
+                                "failed to read the leak allowlist {}: {err}",
+                                path.display()
+                            ));
+                            break 'miri_error;
+                        }
+                    },
+                None => 0,
+            };
+            if let Some(path) = &leak_report {
+                let report = LeakReport::new(&ecx, &leaks, suppressed);
+                if let Err(err) = report.write(path) {
+                    tcx.dcx().err(format!(
+                        "failed to write the leak report to {}: {err}",
+                        path.display()
+                    ));
+                    break 'miri_error;
+                }
+                if !leaks.is_empty() {
+                    let bytes: u64 = leaks.iter().map(|(_, _, alloc)| alloc.size().bytes()).sum();
+                    tcx.dcx().err(format!(
+                        "memory leaked: {} allocations ({bytes} bytes) at {} allocation sites, \
+                        see the leak report in {}",
+                        leaks.len(),
+                        report.site_count(),
+                        path.display()
+                    ));
+                    tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
+                    break 'miri_error;
+                }
+            }
             if !leaks.is_empty() {
                 report_leaks(&ecx, leaks);
                 tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
@@ -0,0 +1,700 @@
+//! The synthetic code and stack of the debugged program, and the symbol files that describe them
+//! to the debugger in terms of the source program.
+//!
+//! Every function the debugger gets to see is assigned a range of code addresses, with
+//! `INSTRUCTION_SIZE` bytes for each MIR statement and terminator; the program counter of a frame
+//! is the address of the statement it is about to execute. Every stack frame is assigned a block
+//! in a synthetic stack region, which `rbp` and `rsp` point to. Such a block starts with the
+//! address of the block of the caller and the return address, like a frame of machine code that
+//! uses `rbp` as frame pointer, followed by the addresses of the user-visible locals of the frame.
+//! Locals that live in memory are at their real address; for locals that are immediates, the block
+//! holds a copy of their value. So the debug info can describe the location of the `i`-th local
+//! as "the address stored at `rbp + 16 + 8 * i`" for every function.
+
+use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use rustc_abi::{BackendRepr, FieldIdx, FieldsShape, Size, TagEncoding, VariantIdx, Variants};
+use rustc_codegen_ssa::debuginfo::type_names::compute_debuginfo_type_name;
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::mir;
+use rustc_middle::ty::layout::{HasTypingEnv, TyAndLayout};
+use rustc_middle::ty::print::{with_no_trimmed_paths, with_resolve_crate_name};
+use rustc_middle::ty::{self, Ty};
+use rustc_span::Span;
+
+use super::elf::*;
+use crate::*;
+
+/// The number of code bytes of each MIR statement and terminator.
+const INSTRUCTION_SIZE: u64 = 4;
+/// The byte all code reads as: `nop`.
+const NOP: u8 = 0x90;
+/// The code and stack regions are above the addresses Miri hands out for allocations, and above
+/// the user-space addresses of the host for native calls.
+const CODE_BASE: u64 = 0x6000_0000_0000_0000;
+const STACK_BASE: u64 = 0x7000_0000_0000_0000;
+const THREAD_STACK_SIZE: u64 = 1 << 40;
+const FRAME_SIZE: u64 = 1 << 16;
+/// The address of the caller's frame and the return address.
+const FRAME_HEADER_SIZE: u64 = 16;
+/// The space for a copy of each local that is an immediate, which is at most a pair of `u128`.
+const IMMEDIATE_SIZE: u64 = 32;
+/// Locals beyond this many get no location in the debug info.
+const MAX_LOCALS: u64 = (FRAME_SIZE - FRAME_HEADER_SIZE) / (8 + IMMEDIATE_SIZE);
+
+/// The code range of a function.
+#[derive(Debug)]
+struct Function<'tcx> {
+    body: &'tcx mir::Body<'tcx>,
+    start: u64,
+    /// The index of the first statement of each basic block.
+    block_starts: Vec<u64>,
+    /// The number of statements and terminators.
+    len: u64,
+}
+
+impl Function<'_> {
+    fn code(&self) -> Range<u64> {
+        self.start..self.start.strict_add(self.len.strict_mul(INSTRUCTION_SIZE))
+    }
+
+    fn pc(&self, loc: mir::Location) -> u64 {
+        let idx = self.block_starts[loc.block.as_usize()]
+            .strict_add(u64::try_from(loc.statement_index).unwrap());
+        self.start.strict_add(idx.strict_mul(INSTRUCTION_SIZE))
+    }
+
+    fn location(&self, pc: u64) -> mir::Location {
+        let idx = pc.strict_sub(self.start) / INSTRUCTION_SIZE;
+        let block = self.block_starts.partition_point(|&start| start <= idx).strict_sub(1);
+        let statement_index = usize::try_from(idx.strict_sub(self.block_starts[block])).unwrap();
+        mir::Location { block: mir::BasicBlock::from_usize(block), statement_index }
+    }
+}
+
+/// The functions that have been assigned code, and the symbol files describing them.
+#[derive(Debug)]
+pub struct CodeMap<'tcx> {
+    /// The functions in the order of their code addresses.
+    functions: FxIndexMap<ty::Instance<'tcx>, Function<'tcx>>,
+    /// How many of the `functions` are described by the symbol files.
+    described: usize,
+    /// The paths of the symbol files and the start of the code they describe.
+    libraries: Vec<(PathBuf, u64)>,
+    /// How many of the `libraries` the debugger has been told about.
+    reported: usize,
+    dir: PathBuf,
+}
+
+impl<'tcx> CodeMap<'tcx> {
+    pub fn new() -> Self {
+        CodeMap {
+            functions: Default::default(),
+            described: 0,
+            libraries: Vec::new(),
+            reported: 0,
+            dir: std::env::temp_dir().join(format!("miri-gdb-{}", std::process::id())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    pub fn contains(&self, instance: ty::Instance<'tcx>) -> bool {
+        self.functions.contains_key(&instance)
+    }
+
+    fn add(&mut self, instance: ty::Instance<'tcx>, body: &'tcx mir::Body<'tcx>) {
+        if self.contains(instance) {
+            return;
+        }
+        let start = self.functions.last().map_or(CODE_BASE, |(_, f)| f.code().end);
+        let mut block_starts = Vec::with_capacity(body.basic_blocks.len());
+        let mut len = 0u64;
+        for block in body.basic_blocks.iter() {
+            block_starts.push(len);
+            // The statements and the terminator.
+            len = len.strict_add(u64::try_from(block.statements.len()).unwrap()).strict_add(1);
+        }
+        self.functions.insert(instance, Function { body, start, block_starts, len: len.max(1) });
+    }
+
+    /// Assigns code to the functions of the local crate that do not need to be monomorphized, so
+    /// that the debugger can resolve breakpoints on them before they are called.
+    pub fn add_local_functions(&mut self, ecx: &MiriInterpCx<'tcx>) {
+        let tcx = *ecx.tcx;
+        for &def_id in tcx.mir_keys(()) {
+            if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+                || tcx.generics_of(def_id).requires_monomorphization(tcx)
+            {
+                continue;
+            }
+            let instance = ty::Instance::mono(tcx, def_id.to_def_id());
+            self.add(instance, tcx.instance_mir(instance.def));
+        }
+    }
+
+    /// Assigns code to the functions of all frames of all threads.
+    pub fn add_stacks(&mut self, ecx: &MiriInterpCx<'tcx>) {
+        for (thread, _) in ecx.machine.threads.all_threads() {
+            for frame in ecx.machine.threads.thread_stack(thread) {
+                self.add(frame.instance(), frame.body());
+            }
+        }
+    }
+
+    /// Writes a symbol file for the functions that are not described yet. Returns whether there
+    /// are libraries the debugger has not been told about.
+    pub fn write_symbols(&mut self, ecx: &MiriInterpCx<'tcx>) -> io::Result<bool> {
+        if self.described < self.functions.len() {
+            let functions = self.functions.get_range(self.described..).unwrap();
+            let file = SymbolFileBuilder::new(ecx).build(functions.iter());
+            std::fs::create_dir_all(&self.dir)?;
+            let path = self.dir.join(format!("symbols-{}.so", self.libraries.len()));
+            std::fs::write(&path, file.encode())?;
+            self.libraries.push((path, file.code.start));
+            self.described = self.functions.len();
+        }
+        Ok(self.reported < self.libraries.len())
+    }
+
+    /// The reply to `qXfer:libraries:read`.
+    pub fn libraries_xml(&mut self) -> String {
+        self.reported = self.libraries.len();
+        let mut xml = String::from("<library-list>");
+        for (path, start) in &self.libraries {
+            let path = path.to_string_lossy();
+            let path = path
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;");
+            xml.push_str(&format!(
+                "<library name=\"{path}\"><segment address=\"{start:#x}\"/></library>"
+            ));
+        }
+        xml.push_str("</library-list>");
+        xml
+    }
+
+    /// The program counter of `frame`, if its function has been assigned code.
+    pub fn pc(&self, frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>) -> Option<u64> {
+        let function = self.functions.get(&frame.instance())?;
+        // While no statement is executing, the frame is at the start of the function.
+        Some(function.pc(frame.current_loc().left().unwrap_or(mir::Location::START)))
+    }
+
+    /// The function and statement at `pc`.
+    pub fn location(&self, pc: u64) -> Option<(ty::Instance<'tcx>, mir::Location)> {
+        // The functions are sorted by their addresses.
+        let (mut lo, mut hi) = (0, self.functions.len());
+        while lo < hi {
+            let mid = lo.midpoint(hi);
+            if self.functions[mid].start <= pc { lo = mid.strict_add(1) } else { hi = mid }
+        }
+        let (&instance, function) = self.functions.get_index(lo.checked_sub(1)?)?;
+        function.code().contains(&pc).then(|| (instance, function.location(pc)))
+    }
+
+    /// Whether `frame` just returned from the call that `return_address` belongs to, i.e. whether
+    /// it is at the start of a block the call continues with. Return addresses are one byte after
+    /// the call, see `frame_block`.
+    pub fn returned_to(
+        &self,
+        frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+        return_address: u64,
+    ) -> bool {
+        if return_address % INSTRUCTION_SIZE != 1 {
+            return false;
+        }
+        let Some(mir::Location { block, statement_index: 0 }) = frame.current_loc().left() else {
+            return false;
+        };
+        let Some((instance, call)) = self.location(return_address.strict_sub(1)) else {
+            return false;
+        };
+        let data = &frame.body().basic_blocks[call.block];
+        instance == frame.instance()
+            && call.statement_index == data.statements.len()
+            && data.terminator().successors().any(|succ| succ == block)
+    }
+
+    /// The address of the block of the frame at `depth` (counting from the outermost frame) of
+    /// `thread`, which is the value of `rbp` and `rsp` in that frame.
+    pub fn frame_address(thread: ThreadId, depth: usize) -> u64 {
+        let thread_stack = u64::from(thread.to_u32()).strict_mul(THREAD_STACK_SIZE);
+        let frame = u64::try_from(depth).unwrap().strict_mul(FRAME_SIZE);
+        STACK_BASE.strict_add(thread_stack).strict_add(frame)
+    }
+
+    /// Reads synthetic code or stack memory. Returns `None` for all other addresses.
+    pub fn read(&self, ecx: &MiriInterpCx<'tcx>, addr: u64, len: u64) -> Option<Vec<u8>> {
+        let code_end = self.functions.last().map_or(CODE_BASE, |(_, f)| f.code().end);
+        if (CODE_BASE..code_end).contains(&addr) {
+            let len = usize::try_from(len.min(code_end.strict_sub(addr))).unwrap();
+            return Some(vec![NOP; len]);
+        }
+        let offset = addr.checked_sub(STACK_BASE)?;
+        let thread = u32::try_from(offset / THREAD_STACK_SIZE).ok()?;
+        if usize::try_from(thread).ok()? >= ecx.machine.threads.get_total_thread_count() {
+            return None;
+        }
+        let thread = ThreadId::new_unchecked(thread);
+        let depth = usize::try_from(offset % THREAD_STACK_SIZE / FRAME_SIZE).ok()?;
+        let block = self.frame_block(ecx, thread, depth)?;
+        let start = usize::try_from(offset % FRAME_SIZE).ok()?;
+        let end = start.saturating_add(usize::try_from(len).unwrap_or(usize::MAX)).min(block.len());
+        block.get(start..end).map(<[u8]>::to_vec)
+    }
+
+    /// The contents of the block of a frame, see the module docs.
+    fn frame_block(
+        &self,
+        ecx: &MiriInterpCx<'tcx>,
+        thread: ThreadId,
+        depth: usize,
+    ) -> Option<Vec<u8>> {
+        let stack = ecx.machine.threads.thread_stack(thread);
+        let frame = stack.get(depth)?;
+        let address = Self::frame_address(thread, depth);
+        let mut block = Vec::new();
+        let (caller, return_address) = match depth.checked_sub(1) {
+            Some(caller) => {
+                let return_address = self.pc(&stack[caller]).map_or(0, |pc| pc.strict_add(1));
+                (Self::frame_address(thread, caller), return_address)
+            }
+            None => (0, 0),
+        };
+        block.extend_from_slice(&caller.to_le_bytes());
+        block.extend_from_slice(&return_address.to_le_bytes());
+
+        let locals = debug_locals(frame.body())
+            .take(usize::try_from(MAX_LOCALS).unwrap())
+            .map(|(_, local)| local)
+            .collect::<Vec<_>>();
+        let count = u64::try_from(locals.len()).unwrap();
+        let immediates_start = FRAME_HEADER_SIZE.strict_add(count.strict_mul(8));
+        let mut immediates = vec![0; usize::try_from(count.strict_mul(IMMEDIATE_SIZE)).unwrap()];
+        for (idx, local) in locals.into_iter().enumerate() {
+            let local_address = match frame.locals[local].as_mplace_or_imm() {
+                None => 0,
+                Some(Either::Left((ptr, _meta))) => ptr.addr().bytes(),
+                Some(Either::Right(imm)) => {
+                    let start = idx.strict_mul(usize::try_from(IMMEDIATE_SIZE).unwrap());
+                    let slot = &mut immediates[start..];
+                    if let Some(layout) = ecx.layout_of_local(frame, local, None).discard_err() {
+                        write_immediate(ecx, slot, imm, layout);
+                    }
+                    let offset = u64::try_from(start).unwrap();
+                    address.strict_add(immediates_start).strict_add(offset)
+                }
+            };
+            block.extend_from_slice(&local_address.to_le_bytes());
+        }
+        block.extend_from_slice(&immediates);
+        Some(block)
+    }
+}
+
+impl Drop for CodeMap<'_> {
+    fn drop(&mut self) {
+        if !self.libraries.is_empty() {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// The user-visible locals of a body, i.e. those with a name in the debug info. The debug info of
+/// a frame refers to them by their index in this sequence.
+pub fn debug_locals<'a, 'tcx>(
+    body: &'a mir::Body<'tcx>,
+) -> impl Iterator<Item = (&'a mir::VarDebugInfo<'tcx>, mir::Local)> {
+    body.var_debug_info.iter().filter_map(|var| {
+        let mir::VarDebugInfoContents::Place(place) = var.value else { return None };
+        (place.projection.is_empty() && var.composite.is_none()).then_some((var, place.local))
+    })
+}
+
+/// Writes the bytes of `imm` the way they would be stored in memory.
+fn write_immediate<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    out: &mut [u8],
+    imm: Immediate<Provenance>,
+    layout: TyAndLayout<'tcx>,
+) {
+    let mut write_scalar = |scalar: Scalar<Provenance>, offset: Size, size: Size| {
+        let bits = match scalar {
+            Scalar::Int(int) => int.to_bits_unchecked(),
+            Scalar::Ptr(ptr, _) => u128::from(ptr.addr().bytes()),
+        };
+        let (offset, size) = (offset.bytes_usize(), size.bytes_usize());
+        out[offset..offset.strict_add(size)].copy_from_slice(&bits.to_le_bytes()[..size]);
+    };
+    match (imm, layout.backend_repr) {
+        (Immediate::Scalar(scalar), _) => write_scalar(scalar, Size::ZERO, layout.size),
+        (Immediate::ScalarPair(a, b), BackendRepr::ScalarPair(a_layout, b_layout)) => {
+            let b_offset = a_layout.size(ecx).align_to(b_layout.align(ecx).abi);
+            write_scalar(a, Size::ZERO, a_layout.size(ecx));
+            write_scalar(b, b_offset, b_layout.size(ecx));
+        }
+        // Uninitialized values read as zeros.
+        _ => {}
+    }
+}
+
+/// Builds the debug info describing some functions.
+struct SymbolFileBuilder<'a, 'tcx> {
+    ecx: &'a MiriInterpCx<'tcx>,
+    unit: Unit,
+    lines: LineProgram,
+    types: FxHashMap<Ty<'tcx>, DieId>,
+}
+
+impl<'a, 'tcx> SymbolFileBuilder<'a, 'tcx> {
+    fn new(ecx: &'a MiriInterpCx<'tcx>) -> Self {
+        SymbolFileBuilder {
+            ecx,
+            unit: Unit::new(),
+            lines: LineProgram::default(),
+            types: Default::default(),
+        }
+    }
+
+    fn build<'f>(
+        mut self,
+        functions: impl ExactSizeIterator<Item = (&'f ty::Instance<'tcx>, &'f Function<'tcx>)>,
+    ) -> SymbolFile
+    where
+        'tcx: 'f,
+    {
+        let tcx = *self.ecx.tcx;
+        let functions = functions.collect::<Vec<_>>();
+        let start = functions[0].1.start;
+        let end = functions[functions.len().strict_sub(1)].1.code().end;
+        let root = Unit::ROOT;
+        self.unit.set(root, DW_AT_PRODUCER, AttrValue::String("miri".to_owned()));
+        self.unit.set(root, DW_AT_LANGUAGE, AttrValue::Udata(DW_LANG_RUST));
+        self.unit.set(root, DW_AT_NAME, AttrValue::String(tcx.crate_name(LOCAL_CRATE).to_string()));
+        if let Ok(dir) = std::env::current_dir() {
+            let dir = dir.to_string_lossy().into_owned();
+            self.unit.set(root, DW_AT_COMP_DIR, AttrValue::String(dir));
+        }
+        self.unit.set(root, DW_AT_LOW_PC, AttrValue::Addr(start));
+        self.unit.set(root, DW_AT_HIGH_PC, AttrValue::Udata(end.strict_sub(start)));
+        self.unit.set(root, DW_AT_STMT_LIST, AttrValue::LineProgram);
+
+        let mut symbols = Vec::new();
+        for (&instance, function) in functions {
+            let name = with_resolve_crate_name!(with_no_trimmed_paths!(
+                tcx.def_path_str_with_args(instance.def_id(), instance.args)
+            ));
+            self.function(instance, function, &name);
+            symbols.push((name, function.code()));
+        }
+        SymbolFile { code: start..end, unit: self.unit, lines: self.lines, functions: symbols }
+    }
+
+    fn function(&mut self, instance: ty::Instance<'tcx>, function: &Function<'tcx>, name: &str) {
+        let tcx = *self.ecx.tcx;
+        let body = function.body;
+        let code = function.code();
+        let subprogram = self.unit.add(Unit::ROOT, DW_TAG_SUBPROGRAM);
+        self.unit.set(subprogram, DW_AT_NAME, AttrValue::String(name.to_owned()));
+        self.unit.set(subprogram, DW_AT_LOW_PC, AttrValue::Addr(code.start));
+        self.unit.set(subprogram, DW_AT_HIGH_PC, AttrValue::Udata(code.end.strict_sub(code.start)));
+        self.unit.set(subprogram, DW_AT_FRAME_BASE, AttrValue::Expr(rbp_frame_base()));
+        if let Some((file, line, _)) = self.position(body.span) {
+            self.unit.set(subprogram, DW_AT_DECL_FILE, AttrValue::Udata(file));
+            self.unit.set(subprogram, DW_AT_DECL_LINE, AttrValue::Udata(line));
+        }
+        if tcx.entry_fn(()).is_some_and(|(def_id, _)| def_id == instance.def_id()) {
+            self.unit.set(subprogram, DW_AT_MAIN_SUBPROGRAM, AttrValue::Flag);
+        }
+
+        for (idx, (var, local)) in debug_locals(body).enumerate() {
+            let idx = u64::try_from(idx).unwrap();
+            if idx >= MAX_LOCALS {
+                break;
+            }
+            let ty = instance.try_instantiate_mir_and_normalize_erasing_regions(
+                tcx,
+                self.ecx.typing_env(),
+                ty::EarlyBinder::bind(tcx, body.local_decls[local].ty),
+            );
+            let Some(layout) = ty.ok().and_then(|ty| self.ecx.layout_of(ty).discard_err()) else {
+                continue;
+            };
+            if !layout.is_sized() {
+                continue;
+            }
+            let tag = if var.argument_index.is_some() {
+                DW_TAG_FORMAL_PARAMETER
+            } else {
+                DW_TAG_VARIABLE
+            };
+            let ty = self.type_die(layout);
+            let die = self.unit.add(subprogram, tag);
+            self.unit.set(die, DW_AT_NAME, AttrValue::String(var.name.to_string()));
+            self.unit.set(die, DW_AT_TYPE, AttrValue::Ref(ty));
+            let offset = i64::try_from(FRAME_HEADER_SIZE.strict_add(idx.strict_mul(8))).unwrap();
+            self.unit.set(die, DW_AT_LOCATION, AttrValue::Expr(indirect_frame_location(offset)));
+        }
+
+        let mut rows = Vec::new();
+        for (block, data) in body.basic_blocks.iter_enumerated() {
+            for statement_index in 0..=data.statements.len() {
+                let loc = mir::Location { block, statement_index };
+                let Some((file, line, column)) = self.position(source_span(body, loc)) else {
+                    continue;
+                };
+                rows.push(LineRow { address: function.pc(loc), file, line, column });
+            }
+        }
+        self.lines.add_sequence(code, rows);
+    }
+
+    /// The file index, line and column of the start of `span`.
+    fn position(&mut self, span: Span) -> Option<(u64, u64, u64)> {
+        if span.is_dummy() {
+            return None;
+        }
+        let pos = self.ecx.tcx.sess.source_map().lookup_char_pos(span.lo());
+        let file = self.lines.file(&pos.file.name.prefer_local_unconditionally().to_string());
+        let line = u64::try_from(pos.line).unwrap();
+        let column = u64::try_from(pos.col_display).unwrap().strict_add(1);
+        Some((file, line, column))
+    }
+
+    fn type_die(&mut self, layout: TyAndLayout<'tcx>) -> DieId {
+        if let Some(&die) = self.types.get(&layout.ty) {
+            return die;
+        }
+        let ecx = self.ecx;
+        let name = compute_debuginfo_type_name(*ecx.tcx, layout.ty, true);
+        let size = layout.size.bytes();
+        let base_type = |this: &mut Self, encoding| {
+            let die = this.named(DW_TAG_BASE_TYPE, &name, size);
+            this.unit.set(die, DW_AT_ENCODING, AttrValue::Udata(encoding));
+            die
+        };
+        let die = match *layout.ty.kind() {
+            ty::Bool => base_type(self, DW_ATE_BOOLEAN),
+            ty::Char => base_type(self, DW_ATE_UTF),
+            ty::Int(_) => base_type(self, DW_ATE_SIGNED),
+            ty::Uint(_) => base_type(self, DW_ATE_UNSIGNED),
+            ty::Float(_) => base_type(self, DW_ATE_FLOAT),
+            ty::RawPtr(..) | ty::Ref(..) => self.pointer_type(layout, &name),
+            ty::Array(..) => {
+                let FieldsShape::Array { count, .. } = layout.fields else { unreachable!() };
+                let die = self.named(DW_TAG_ARRAY_TYPE, &name, size);
+                self.types.insert(layout.ty, die);
+                let elem = self.type_die(layout.field(ecx, 0));
+                self.unit.set(die, DW_AT_TYPE, AttrValue::Ref(elem));
+                let range = self.unit.add(die, DW_TAG_SUBRANGE_TYPE);
+                self.unit.set(range, DW_AT_COUNT, AttrValue::Udata(count));
+                die
+            }
+            ty::Adt(def, _) if def.is_enum() => self.enum_type(layout, &name, def),
+            ty::Adt(def, _) if def.is_union() => {
+                let die = self.named(DW_TAG_UNION_TYPE, &name, size);
+                self.types.insert(layout.ty, die);
+                self.members(die, layout, |idx| super::field_name(layout.ty, idx));
+                die
+            }
+            ty::Adt(..) | ty::Tuple(_) | ty::Closure(..) => {
+                let die = self.named(DW_TAG_STRUCTURE_TYPE, &name, size);
+                self.types.insert(layout.ty, die);
+                self.members(die, layout, |idx| super::field_name(layout.ty, idx));
+                die
+            }
+            // Everything else is shown as an opaque value of the right size.
+            _ => self.named(DW_TAG_STRUCTURE_TYPE, &name, size),
+        };
+        self.types.insert(layout.ty, die);
+        die
+    }
+
+    fn named(&mut self, tag: u16, name: &str, size: u64) -> DieId {
+        let die = self.unit.add(Unit::ROOT, tag);
+        self.unit.set(die, DW_AT_NAME, AttrValue::String(name.to_owned()));
+        self.unit.set(die, DW_AT_BYTE_SIZE, AttrValue::Udata(size));
+        die
+    }
+
+    /// Adds the fields of `layout` as members of `die`.
+    fn members(
+        &mut self,
+        die: DieId,
+        layout: TyAndLayout<'tcx>,
+        field_name: impl Fn(usize) -> String,
+    ) {
+        if !matches!(layout.fields, FieldsShape::Arbitrary { .. } | FieldsShape::Union(_)) {
+            return;
+        }
+        for idx in 0..layout.fields.count() {
+            let field = layout.field(self.ecx, idx);
+            let ty = self.type_die(field);
+            let member = self.unit.add(die, DW_TAG_MEMBER);
+            self.unit.set(member, DW_AT_NAME, AttrValue::String(field_name(idx)));
+            self.unit.set(member, DW_AT_TYPE, AttrValue::Ref(ty));
+            let offset = layout.fields.offset(idx).bytes();
+            self.unit.set(member, DW_AT_DATA_MEMBER_LOCATION, AttrValue::Udata(offset));
+        }
+    }
+
+    /// A pointer to a sized type is a `DW_TAG_pointer_type`; a wide pointer is a struct of the
+    /// data pointer and the metadata, named like the fields of Rust's debugger pretty-printers.
+    fn pointer_type(&mut self, layout: TyAndLayout<'tcx>, name: &str) -> DieId {
+        let ecx = self.ecx;
+        let tcx = *ecx.tcx;
+        let pointee = layout.ty.builtin_deref(true).unwrap();
+        if let BackendRepr::Scalar(_) = layout.backend_repr {
+            let die = self.named(DW_TAG_POINTER_TYPE, name, layout.size.bytes());
+            self.types.insert(layout.ty, die);
+            if let Some(pointee) = ecx.layout_of(pointee).discard_err().filter(|l| l.is_sized()) {
+                let pointee = self.type_die(pointee);
+                self.unit.set(die, DW_AT_TYPE, AttrValue::Ref(pointee));
+            }
+            return die;
+        }
+        let die = self.named(DW_TAG_STRUCTURE_TYPE, name, layout.size.bytes());
+        self.types.insert(layout.ty, die);
+        let tail = tcx.struct_tail_for_codegen(pointee, ecx.typing_env());
+        let (data_name, elem, meta_name) = match *tail.kind() {
+            ty::Slice(elem) => ("data_ptr", Some(elem), "length"),
+            ty::Str => ("data_ptr", Some(tcx.types.u8), "length"),
+            _ => ("pointer", None, "vtable"),
+        };
+        let pointer_size = tcx.data_layout.pointer_size().bytes();
+        let elem = elem.and_then(|elem| ecx.layout_of(elem).discard_err());
+        let data = self.unit.add(Unit::ROOT, DW_TAG_POINTER_TYPE);
+        let elem_name =
+            elem.map_or("()".to_owned(), |elem| compute_debuginfo_type_name(tcx, elem.ty, true));
+        self.unit.set(data, DW_AT_NAME, AttrValue::String(format!("*const {elem_name}")));
+        self.unit.set(data, DW_AT_BYTE_SIZE, AttrValue::Udata(pointer_size));
+        if let Some(elem) = elem {
+            let elem = self.type_die(elem);
+            self.unit.set(data, DW_AT_TYPE, AttrValue::Ref(elem));
+        }
+        let meta = if meta_name == "length" {
+            self.type_die(ecx.layout_of(tcx.types.usize).unwrap())
+        } else {
+            let vtable = self.unit.add(Unit::ROOT, DW_TAG_POINTER_TYPE);
+            self.unit.set(vtable, DW_AT_NAME, AttrValue::String("*const [usize; 3]".to_owned()));
+            self.unit.set(vtable, DW_AT_BYTE_SIZE, AttrValue::Udata(pointer_size));
+            vtable
+        };
+        for (member_name, ty, offset) in [(data_name, data, 0), (meta_name, meta, pointer_size)] {
+            let member = self.unit.add(die, DW_TAG_MEMBER);
+            self.unit.set(member, DW_AT_NAME, AttrValue::String(member_name.to_owned()));
+            self.unit.set(member, DW_AT_TYPE, AttrValue::Ref(ty));
+            self.unit.set(member, DW_AT_DATA_MEMBER_LOCATION, AttrValue::Udata(offset));
+        }
+        die
+    }
+
+    /// Fieldless enums are `DW_TAG_enumeration_type`s. All other enums are structs with a variant
+    /// part, which selects the variant by the value of the tag; the variant without a value is the
+    /// one encoded by the absence of a niche value.
+    fn enum_type(&mut self, layout: TyAndLayout<'tcx>, name: &str, def: ty::AdtDef<'tcx>) -> DieId {
+        let ecx = self.ecx;
+        let size = layout.size.bytes();
+        let variant_field_name = |variant: VariantIdx, idx: usize| {
+            def.variant(variant).fields[FieldIdx::from_usize(idx)].name.to_string()
+        };
+        let Variants::Multiple { tag, ref tag_encoding, tag_field, ref variants } = layout.variants
+        else {
+            let die = self.named(DW_TAG_STRUCTURE_TYPE, name, size);
+            self.types.insert(layout.ty, die);
+            if let Variants::Single { index } = layout.variants {
+                self.members(die, layout, |idx| variant_field_name(index, idx));
+            }
+            return die;
+        };
+        let tag_for_variant = |variant: VariantIdx| {
+            let input = ecx.typing_env().as_query_input((layout.ty, variant));
+            ecx.tcx
+                .tag_for_variant(input)
+                .and_then(|tag| u64::try_from(tag.to_bits_unchecked()).ok())
+        };
+        let inhabited = variants.indices().filter(|&v| !layout.is_variant_uninhabited(v));
+        let tag_offset = layout.fields.offset(tag_field.as_usize()).bytes();
+        let fieldless = def.variants().iter().all(|variant| variant.fields.is_empty());
+        if fieldless && matches!(tag_encoding, TagEncoding::Direct) && tag_offset == 0 {
+            let die = self.named(DW_TAG_ENUMERATION_TYPE, name, size);
+            self.types.insert(layout.ty, die);
+            for variant in inhabited {
+                let Some(value) = tag_for_variant(variant) else { continue };
+                let enumerator = self.unit.add(die, DW_TAG_ENUMERATOR);
+                let variant_name = def.variant(variant).name.to_string();
+                self.unit.set(enumerator, DW_AT_NAME, AttrValue::String(variant_name));
+                self.unit.set(enumerator, DW_AT_CONST_VALUE, AttrValue::Udata(value));
+            }
+            return die;
+        }
+
+        let die = self.named(DW_TAG_STRUCTURE_TYPE, name, size);
+        self.types.insert(layout.ty, die);
+        let tag_size = tag.size(ecx);
+        let tag_type = self.type_die(ecx.layout_of(uint_ty(ecx, tag_size)).unwrap());
+        let variant_part = self.unit.add(die, DW_TAG_VARIANT_PART);
+        let discr = self.unit.add(variant_part, DW_TAG_MEMBER);
+        self.unit.set(discr, DW_AT_NAME, AttrValue::String("tag".to_owned()));
+        self.unit.set(discr, DW_AT_TYPE, AttrValue::Ref(tag_type));
+        self.unit.set(discr, DW_AT_DATA_MEMBER_LOCATION, AttrValue::Udata(tag_offset));
+        self.unit.set(discr, DW_AT_ARTIFICIAL, AttrValue::Flag);
+        self.unit.set(variant_part, DW_AT_DISCR, AttrValue::Ref(discr));
+        for variant in inhabited {
+            let variant_name = def.variant(variant).name.to_string();
+            let variant_layout = layout.for_variant(ecx, variant);
+            let fields = self.unit.add(die, DW_TAG_STRUCTURE_TYPE);
+            self.unit.set(fields, DW_AT_NAME, AttrValue::String(variant_name.clone()));
+            self.unit.set(fields, DW_AT_BYTE_SIZE, AttrValue::Udata(size));
+            self.members(fields, variant_layout, |idx| variant_field_name(variant, idx));
+            let variant_die = self.unit.add(variant_part, DW_TAG_VARIANT);
+            if let Some(value) = tag_for_variant(variant) {
+                self.unit.set(variant_die, DW_AT_DISCR_VALUE, AttrValue::Udata(value));
+            }
+            let member = self.unit.add(variant_die, DW_TAG_MEMBER);
+            self.unit.set(member, DW_AT_NAME, AttrValue::String(variant_name));
+            self.unit.set(member, DW_AT_TYPE, AttrValue::Ref(fields));
+            self.unit.set(member, DW_AT_DATA_MEMBER_LOCATION, AttrValue::Udata(0));
+        }
+        die
+    }
+}
+
+/// The unsigned integer type of the given size.
+fn uint_ty<'tcx>(ecx: &MiriInterpCx<'tcx>, size: Size) -> Ty<'tcx> {
+    let types = &ecx.tcx.types;
+    match size.bytes() {
+        1 => types.u8,
+        2 => types.u16,
+        4 => types.u32,
+        8 => types.u64,
+        _ => types.u128,
+    }
+}
+
+/// The span of the source code a statement belongs to: for code inlined from other functions,
+/// that is the outermost call site, and for code expanded from macros, the macro invocation.
+fn source_span(body: &mir::Body<'_>, loc: mir::Location) -> Span {
+    let source_info = body.source_info(loc);
+    let mut scope = source_info.scope;
+    let mut span = source_info.span;
+    loop {
+        let data = &body.source_scopes[scope];
+        if let Some((_, callsite)) = data.inlined {
+            span = callsite;
+        }
+        match data.inlined_parent_scope {
+            Some(parent) => scope = parent,
+            None => return span.source_callsite(),
+        }
+    }
+}
@@ -0,0 +1,653 @@
+//! Encoding of the symbol files that describe the synthetic code of the debugged program to the
+//! debugger. They are ELF objects without any code, but with DWARF 4 debug info, a line table,
+//! unwind info and a symbol table, so that the debugger can map program counters to source lines,
+//! functions and local variables on its own.
+//!
+//! Only the small subset of ELF and DWARF that `debuginfo` needs is supported.
+
+use std::ops::Range;
+
+use rustc_data_structures::fx::FxHashMap;
+
+pub const DW_TAG_ARRAY_TYPE: u16 = 0x01;
+pub const DW_TAG_ENUMERATION_TYPE: u16 = 0x04;
+pub const DW_TAG_FORMAL_PARAMETER: u16 = 0x05;
+pub const DW_TAG_MEMBER: u16 = 0x0d;
+pub const DW_TAG_POINTER_TYPE: u16 = 0x0f;
+pub const DW_TAG_COMPILE_UNIT: u16 = 0x11;
+pub const DW_TAG_STRUCTURE_TYPE: u16 = 0x13;
+pub const DW_TAG_UNION_TYPE: u16 = 0x17;
+pub const DW_TAG_VARIANT: u16 = 0x19;
+pub const DW_TAG_SUBRANGE_TYPE: u16 = 0x21;
+pub const DW_TAG_BASE_TYPE: u16 = 0x24;
+pub const DW_TAG_ENUMERATOR: u16 = 0x28;
+pub const DW_TAG_SUBPROGRAM: u16 = 0x2e;
+pub const DW_TAG_VARIANT_PART: u16 = 0x33;
+pub const DW_TAG_VARIABLE: u16 = 0x34;
+
+pub const DW_AT_LOCATION: u16 = 0x02;
+pub const DW_AT_NAME: u16 = 0x03;
+pub const DW_AT_BYTE_SIZE: u16 = 0x0b;
+pub const DW_AT_STMT_LIST: u16 = 0x10;
+pub const DW_AT_LOW_PC: u16 = 0x11;
+pub const DW_AT_HIGH_PC: u16 = 0x12;
+pub const DW_AT_LANGUAGE: u16 = 0x13;
+pub const DW_AT_DISCR: u16 = 0x15;
+pub const DW_AT_DISCR_VALUE: u16 = 0x16;
+pub const DW_AT_COMP_DIR: u16 = 0x1b;
+pub const DW_AT_CONST_VALUE: u16 = 0x1c;
+pub const DW_AT_PRODUCER: u16 = 0x25;
+pub const DW_AT_ARTIFICIAL: u16 = 0x34;
+pub const DW_AT_COUNT: u16 = 0x37;
+pub const DW_AT_DATA_MEMBER_LOCATION: u16 = 0x38;
+pub const DW_AT_DECL_FILE: u16 = 0x3a;
+pub const DW_AT_DECL_LINE: u16 = 0x3b;
+pub const DW_AT_ENCODING: u16 = 0x3e;
+pub const DW_AT_FRAME_BASE: u16 = 0x40;
+pub const DW_AT_TYPE: u16 = 0x49;
+pub const DW_AT_MAIN_SUBPROGRAM: u16 = 0x6a;
+
+pub const DW_ATE_BOOLEAN: u64 = 0x02;
+pub const DW_ATE_FLOAT: u64 = 0x04;
+pub const DW_ATE_SIGNED: u64 = 0x05;
+pub const DW_ATE_UNSIGNED: u64 = 0x07;
+pub const DW_ATE_UTF: u64 = 0x10;
+
+pub const DW_LANG_RUST: u64 = 0x1c;
+
+const DW_FORM_ADDR: u8 = 0x01;
+const DW_FORM_STRING: u8 = 0x08;
+const DW_FORM_UDATA: u8 = 0x0f;
+const DW_FORM_REF4: u8 = 0x13;
+const DW_FORM_SEC_OFFSET: u8 = 0x17;
+const DW_FORM_EXPRLOC: u8 = 0x18;
+const DW_FORM_FLAG_PRESENT: u8 = 0x19;
+
+const DW_OP_DEREF: u8 = 0x06;
+const DW_OP_REG6: u8 = 0x56;
+const DW_OP_FBREG: u8 = 0x91;
+
+const DW_LNS_COPY: u8 = 0x01;
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+const DW_LNS_SET_FILE: u8 = 0x04;
+const DW_LNS_SET_COLUMN: u8 = 0x05;
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+
+const DW_CFA_DEF_CFA: u8 = 0x0c;
+const DW_CFA_OFFSET: u8 = 0x80;
+
+/// The DWARF numbers of the x86-64 registers the unwind info refers to.
+const DWARF_RBP: u8 = 6;
+const DWARF_RETURN_ADDRESS: u8 = 16;
+
+/// The size of the header of a DWARF 4 compilation unit.
+const UNIT_HEADER_SIZE: usize = 11;
+
+/// A reference to a debugging information entry of a `Unit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DieId(usize);
+
+#[derive(Clone, Debug)]
+pub enum AttrValue {
+    String(String),
+    Udata(u64),
+    Addr(u64),
+    Flag,
+    Ref(DieId),
+    Expr(Vec<u8>),
+    /// The offset of the line program, which is always at the start of `.debug_line`.
+    LineProgram,
+}
+
+impl AttrValue {
+    fn form(&self) -> u8 {
+        match self {
+            AttrValue::String(_) => DW_FORM_STRING,
+            AttrValue::Udata(_) => DW_FORM_UDATA,
+            AttrValue::Addr(_) => DW_FORM_ADDR,
+            AttrValue::Flag => DW_FORM_FLAG_PRESENT,
+            AttrValue::Ref(_) => DW_FORM_REF4,
+            AttrValue::Expr(_) => DW_FORM_EXPRLOC,
+            AttrValue::LineProgram => DW_FORM_SEC_OFFSET,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>, offsets: &[u32]) {
+        match self {
+            AttrValue::String(s) => {
+                out.extend_from_slice(s.as_bytes());
+                out.push(0);
+            }
+            &AttrValue::Udata(value) => uleb128(out, value),
+            AttrValue::Addr(addr) => out.extend_from_slice(&addr.to_le_bytes()),
+            AttrValue::Flag => {}
+            AttrValue::Ref(die) => out.extend_from_slice(&offsets[die.0].to_le_bytes()),
+            AttrValue::Expr(expr) => {
+                uleb128(out, u64::try_from(expr.len()).unwrap());
+                out.extend_from_slice(expr);
+            }
+            AttrValue::LineProgram => out.extend_from_slice(&0u32.to_le_bytes()),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            // References are the only values that are not known yet, but they have a fixed size.
+            AttrValue::Ref(_) => 4,
+            _ => {
+                let mut out = Vec::new();
+                self.write(&mut out, &[]);
+                out.len()
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Die {
+    tag: u16,
+    attrs: Vec<(u16, AttrValue)>,
+    children: Vec<DieId>,
+}
+
+/// A DWARF compilation unit: a tree of debugging information entries whose root is the
+/// `DW_TAG_compile_unit` entry.
+#[derive(Debug)]
+pub struct Unit {
+    dies: Vec<Die>,
+}
+
+impl Unit {
+    pub const ROOT: DieId = DieId(0);
+
+    pub fn new() -> Self {
+        Unit {
+            dies: vec![Die { tag: DW_TAG_COMPILE_UNIT, attrs: Vec::new(), children: Vec::new() }],
+        }
+    }
+
+    /// Adds an entry with the given tag as the last child of `parent`.
+    pub fn add(&mut self, parent: DieId, tag: u16) -> DieId {
+        let id = DieId(self.dies.len());
+        self.dies.push(Die { tag, attrs: Vec::new(), children: Vec::new() });
+        self.dies[parent.0].children.push(id);
+        id
+    }
+
+    pub fn set(&mut self, die: DieId, attr: u16, value: AttrValue) {
+        self.dies[die.0].attrs.push((attr, value));
+    }
+
+    /// Returns the `.debug_abbrev` and `.debug_info` sections describing this unit.
+    fn encode(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut layout = UnitLayout {
+            abbrevs: FxHashMap::default(),
+            abbrev_section: Vec::new(),
+            codes: vec![0; self.dies.len()],
+            offsets: vec![0; self.dies.len()],
+            size: UNIT_HEADER_SIZE,
+        };
+        layout.visit(self, Unit::ROOT);
+        layout.abbrev_section.push(0);
+
+        let mut info = Vec::with_capacity(layout.size);
+        // The length does not include the length field itself.
+        info.extend_from_slice(&u32::try_from(layout.size.strict_sub(4)).unwrap().to_le_bytes());
+        info.extend_from_slice(&4u16.to_le_bytes());
+        // The offset of the abbreviations in `.debug_abbrev`.
+        info.extend_from_slice(&0u32.to_le_bytes());
+        // The size of an address.
+        info.push(8);
+        self.write_die(Unit::ROOT, &layout, &mut info);
+        assert_eq!(info.len(), layout.size);
+        (layout.abbrev_section, info)
+    }
+
+    fn write_die(&self, id: DieId, layout: &UnitLayout, out: &mut Vec<u8>) {
+        let die = &self.dies[id.0];
+        uleb128(out, layout.codes[id.0]);
+        for (_, value) in &die.attrs {
+            value.write(out, &layout.offsets);
+        }
+        for &child in &die.children {
+            self.write_die(child, layout, out);
+        }
+        if !die.children.is_empty() {
+            out.push(0);
+        }
+    }
+}
+
+/// An abbreviation: the tag, whether there are children, and the attributes with their forms.
+type Abbrev = (u16, bool, Vec<(u16, u8)>);
+
+/// The abbreviations and offsets of the entries of a `Unit`, computed before it is written.
+struct UnitLayout {
+    abbrevs: FxHashMap<Abbrev, u64>,
+    abbrev_section: Vec<u8>,
+    codes: Vec<u64>,
+    /// The offsets of the entries from the start of the unit.
+    offsets: Vec<u32>,
+    size: usize,
+}
+
+impl UnitLayout {
+    fn visit(&mut self, unit: &Unit, id: DieId) {
+        let die = &unit.dies[id.0];
+        let has_children = !die.children.is_empty();
+        let attrs = die.attrs.iter().map(|(attr, value)| (*attr, value.form())).collect();
+        let next_code = u64::try_from(self.abbrevs.len()).unwrap().strict_add(1);
+        let code = *self.abbrevs.entry((die.tag, has_children, attrs)).or_insert_with_key(
+            |(tag, has_children, attrs)| {
+                let out = &mut self.abbrev_section;
+                uleb128(out, next_code);
+                uleb128(out, u64::from(*tag));
+                out.push(u8::from(*has_children));
+                for &(attr, form) in attrs {
+                    uleb128(out, u64::from(attr));
+                    uleb128(out, u64::from(form));
+                }
+                out.extend_from_slice(&[0, 0]);
+                next_code
+            },
+        );
+        self.codes[id.0] = code;
+        self.offsets[id.0] = u32::try_from(self.size).unwrap();
+        let mut code_bytes = Vec::new();
+        uleb128(&mut code_bytes, code);
+        self.size = self.size.strict_add(code_bytes.len());
+        for (_, value) in &die.attrs {
+            self.size = self.size.strict_add(value.size());
+        }
+        for &child in &die.children {
+            self.visit(unit, child);
+        }
+        if has_children {
+            self.size = self.size.strict_add(1);
+        }
+    }
+}
+
+/// A row of the line table: the code starting at `address` belongs to the given source position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    /// The index returned by `LineProgram::file`.
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// The line table: which source lines the code belongs to.
+#[derive(Debug, Default)]
+pub struct LineProgram {
+    files: Vec<String>,
+    file_indices: FxHashMap<String, u64>,
+    program: Vec<u8>,
+}
+
+impl LineProgram {
+    /// Returns the index of the source file with the given path, adding it if necessary.
+    pub fn file(&mut self, path: &str) -> u64 {
+        if let Some(&idx) = self.file_indices.get(path) {
+            return idx;
+        }
+        self.files.push(path.to_owned());
+        // File indices start at 1 in DWARF 4.
+        let idx = u64::try_from(self.files.len()).unwrap();
+        self.file_indices.insert(path.to_owned(), idx);
+        idx
+    }
+
+    /// Adds the rows of the code in `code`, which have to be sorted by their address.
+    pub fn add_sequence(&mut self, code: Range<u64>, rows: impl IntoIterator<Item = LineRow>) {
+        let out = &mut self.program;
+        out.extend_from_slice(&[0, 9, DW_LNE_SET_ADDRESS]);
+        out.extend_from_slice(&code.start.to_le_bytes());
+        // The initial state of the line program.
+        let mut current = LineRow { address: code.start, file: 1, line: 1, column: 0 };
+        let mut first = true;
+        for row in rows {
+            assert!(row.address >= current.address && row.address < code.end);
+            let same_position =
+                (row.file, row.line, row.column) == (current.file, current.line, current.column);
+            if same_position && !first {
+                continue;
+            }
+            first = false;
+            if row.address != current.address {
+                out.push(DW_LNS_ADVANCE_PC);
+                uleb128(out, row.address.strict_sub(current.address));
+            }
+            if row.file != current.file {
+                out.push(DW_LNS_SET_FILE);
+                uleb128(out, row.file);
+            }
+            if row.line != current.line {
+                out.push(DW_LNS_ADVANCE_LINE);
+                let delta = i64::try_from(row.line).unwrap() - i64::try_from(current.line).unwrap();
+                sleb128(out, delta);
+            }
+            if row.column != current.column {
+                out.push(DW_LNS_SET_COLUMN);
+                uleb128(out, row.column);
+            }
+            out.push(DW_LNS_COPY);
+            current = row;
+        }
+        out.push(DW_LNS_ADVANCE_PC);
+        uleb128(out, code.end.strict_sub(current.address));
+        out.extend_from_slice(&[0, 1, DW_LNE_END_SEQUENCE]);
+    }
+
+    /// Returns the `.debug_line` section.
+    fn encode(&self) -> Vec<u8> {
+        let mut header = Vec::new();
+        // The minimum instruction length and the maximum number of operations per instruction.
+        header.extend_from_slice(&[1, 1]);
+        // Rows are statements by default.
+        header.push(1);
+        // The line base (-5), line range and opcode base. Special opcodes are never used.
+        header.extend_from_slice(&[0xfb, 14, 13]);
+        // The number of operands of the standard opcodes.
+        header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        // No include directories: the file names are either absolute or relative to the
+        // compilation directory.
+        header.push(0);
+        for file in &self.files {
+            header.extend_from_slice(file.as_bytes());
+            // The null terminator, the directory index, modification time and length.
+            header.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        header.push(0);
+
+        let mut out = Vec::new();
+        let length = 2usize.strict_add(4).strict_add(header.len()).strict_add(self.program.len());
+        out.extend_from_slice(&u32::try_from(length).unwrap().to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&u32::try_from(header.len()).unwrap().to_le_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&self.program);
+        out
+    }
+}
+
+/// Returns the location expression of a variable whose address is stored at `offset` from the
+/// frame base.
+pub fn indirect_frame_location(offset: i64) -> Vec<u8> {
+    let mut expr = vec![DW_OP_FBREG];
+    sleb128(&mut expr, offset);
+    expr.push(DW_OP_DEREF);
+    expr
+}
+
+/// Returns the location expression of a frame base that is the value of `rbp`.
+pub fn rbp_frame_base() -> Vec<u8> {
+    vec![DW_OP_REG6]
+}
+
+/// Returns the `.debug_frame` section. All functions are unwound the same way: `rbp` points to the
+/// saved `rbp` of the caller, which is followed by the return address.
+fn debug_frame(functions: impl Iterator<Item = Range<u64>>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut cie = Vec::new();
+    // The CIE id, version and (empty) augmentation.
+    cie.extend_from_slice(&u32::MAX.to_le_bytes());
+    cie.extend_from_slice(&[1, 0]);
+    // The code alignment factor (1), data alignment factor (-8) and return address register.
+    cie.extend_from_slice(&[1, 0x78, DWARF_RETURN_ADDRESS]);
+    // The CFA is `rbp + 16`, the return address is saved at `CFA - 8` and `rbp` at `CFA - 16`.
+    cie.extend_from_slice(&[DW_CFA_DEF_CFA, DWARF_RBP, 16]);
+    cie.extend_from_slice(&[DW_CFA_OFFSET | DWARF_RETURN_ADDRESS, 1]);
+    cie.extend_from_slice(&[DW_CFA_OFFSET | DWARF_RBP, 2]);
+    // Pad the entry, including its length, to a multiple of the address size with `DW_CFA_nop`.
+    while (cie.len().strict_add(4)) % 8 != 0 {
+        cie.push(0);
+    }
+    out.extend_from_slice(&u32::try_from(cie.len()).unwrap().to_le_bytes());
+    out.extend_from_slice(&cie);
+    for code in functions {
+        // The length, the offset of the CIE, and the code range.
+        out.extend_from_slice(&20u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&code.start.to_le_bytes());
+        out.extend_from_slice(&code.end.strict_sub(code.start).to_le_bytes());
+    }
+    out
+}
+
+/// Returns the `.symtab` and `.strtab` sections with a symbol for each function. `text` is the
+/// index of the `.text` section.
+fn symbol_table(functions: &[(String, Range<u64>)], text: u16) -> (Vec<u8>, Vec<u8>) {
+    const STB_GLOBAL_STT_FUNC: u8 = 0x12;
+    let mut strtab = vec![0];
+    // The first symbol is always the null symbol.
+    let mut symtab = vec![0; 24];
+    for (name, code) in functions {
+        symtab.extend_from_slice(&u32::try_from(strtab.len()).unwrap().to_le_bytes());
+        strtab.extend_from_slice(name.as_bytes());
+        strtab.push(0);
+        symtab.extend_from_slice(&[STB_GLOBAL_STT_FUNC, 0]);
+        symtab.extend_from_slice(&text.to_le_bytes());
+        symtab.extend_from_slice(&code.start.to_le_bytes());
+        symtab.extend_from_slice(&code.end.strict_sub(code.start).to_le_bytes());
+    }
+    (symtab, strtab)
+}
+
+/// A symbol file describing the functions whose synthetic code is in `code`.
+#[derive(Debug)]
+pub struct SymbolFile {
+    pub code: Range<u64>,
+    pub unit: Unit,
+    pub lines: LineProgram,
+    /// The names and code ranges of the functions.
+    pub functions: Vec<(String, Range<u64>)>,
+}
+
+impl SymbolFile {
+    /// Returns the contents of the ELF file.
+    pub fn encode(&self) -> Vec<u8> {
+        const SHT_PROGBITS: u32 = 1;
+        const SHT_SYMTAB: u32 = 2;
+        const SHT_STRTAB: u32 = 3;
+        const SHT_NOBITS: u32 = 8;
+        const SHF_ALLOC_EXECINSTR: u64 = 0x6;
+        const TEXT: u16 = 1;
+        const STRTAB: u32 = 7;
+
+        let (abbrev, info) = self.unit.encode();
+        let (symtab, strtab) = symbol_table(&self.functions, TEXT);
+        let code_size = self.code.end.strict_sub(self.code.start);
+        // (name, type, flags, address, contents, link, info, entry size). The code is not in the
+        // file, only the size of the `.text` section is.
+        let sections: [(&str, u32, u64, u64, Vec<u8>, u32, u32, u64); 9] = [
+            ("", 0, 0, 0, Vec::new(), 0, 0, 0),
+            (".text", SHT_NOBITS, SHF_ALLOC_EXECINSTR, self.code.start, Vec::new(), 0, 0, 0),
+            (".debug_abbrev", SHT_PROGBITS, 0, 0, abbrev, 0, 0, 0),
+            (".debug_info", SHT_PROGBITS, 0, 0, info, 0, 0, 0),
+            (".debug_line", SHT_PROGBITS, 0, 0, self.lines.encode(), 0, 0, 0),
+            (
+                ".debug_frame",
+                SHT_PROGBITS,
+                0,
+                0,
+                debug_frame(self.functions.iter().map(|f| f.1.clone())),
+                0,
+                0,
+                0,
+            ),
+            // All symbols are global, so the first non-local symbol is the one after the null
+            // symbol.
+            (".symtab", SHT_SYMTAB, 0, 0, symtab, STRTAB, 1, 24),
+            (".strtab", SHT_STRTAB, 0, 0, strtab, 0, 0, 0),
+            (".shstrtab", SHT_STRTAB, 0, 0, Vec::new(), 0, 0, 0),
+        ];
+
+        let mut shstrtab = vec![0];
+        let mut name_offsets = Vec::new();
+        for (name, ..) in &sections {
+            if name.is_empty() {
+                name_offsets.push(0);
+            } else {
+                name_offsets.push(u32::try_from(shstrtab.len()).unwrap());
+                shstrtab.extend_from_slice(name.as_bytes());
+                shstrtab.push(0);
+            }
+        }
+
+        // The ELF header and the program header are followed by the contents of the sections and
+        // the section headers.
+        let mut data = Vec::new();
+        let data_start = 64 + 56;
+        let mut headers = Vec::new();
+        for (idx, (_, kind, flags, addr, contents, link, info, entsize)) in
+            sections.iter().enumerate()
+        {
+            let contents = if idx == sections.len() - 1 { &shstrtab } else { contents };
+            while data.len() % 8 != 0 {
+                data.push(0);
+            }
+            let offset = if *kind == 0 { 0 } else { data_start + data.len() };
+            let size = if *kind == SHT_NOBITS {
+                code_size
+            } else {
+                u64::try_from(contents.len()).unwrap()
+            };
+            data.extend_from_slice(contents);
+            headers.extend_from_slice(&name_offsets[idx].to_le_bytes());
+            headers.extend_from_slice(&kind.to_le_bytes());
+            headers.extend_from_slice(&flags.to_le_bytes());
+            headers.extend_from_slice(&addr.to_le_bytes());
+            headers.extend_from_slice(&u64::try_from(offset).unwrap().to_le_bytes());
+            headers.extend_from_slice(&size.to_le_bytes());
+            headers.extend_from_slice(&link.to_le_bytes());
+            headers.extend_from_slice(&info.to_le_bytes());
+            let align: u64 = if *kind == 0 {
+                0
+            } else if *kind == SHT_NOBITS {
+                16
+            } else {
+                1
+            };
+            headers.extend_from_slice(&align.to_le_bytes());
+            headers.extend_from_slice(&entsize.to_le_bytes());
+        }
+        while data.len() % 8 != 0 {
+            data.push(0);
+        }
+        let section_headers = data_start.strict_add(data.len());
+
+        let mut out = Vec::new();
+        // The magic number, 64-bit, little endian, version 1, System V ABI.
+        out.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+        out.extend_from_slice(&[0; 8]);
+        // A shared object for x86-64, version 1.
+        out.extend_from_slice(&3u16.to_le_bytes());
+        out.extend_from_slice(&62u16.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        // No entry point; the program headers follow the ELF header.
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(&64u64.to_le_bytes());
+        out.extend_from_slice(&u64::try_from(section_headers).unwrap().to_le_bytes());
+        // No flags, and the sizes of the headers.
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&64u16.to_le_bytes());
+        out.extend_from_slice(&56u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&64u16.to_le_bytes());
+        out.extend_from_slice(&u16::try_from(sections.len()).unwrap().to_le_bytes());
+        out.extend_from_slice(&u16::try_from(sections.len() - 1).unwrap().to_le_bytes());
+
+        // A single loadable, executable segment for the code, which is not in the file.
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&5u32.to_le_bytes());
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(&self.code.start.to_le_bytes());
+        out.extend_from_slice(&self.code.start.to_le_bytes());
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(&code_size.to_le_bytes());
+        out.extend_from_slice(&0x1000u64.to_le_bytes());
+        assert_eq!(out.len(), data_start);
+
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&headers);
+        out
+    }
+}
+
+fn uleb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn sleb128(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        // This is an arithmetic shift, so negative values end up as -1.
+        value >>= 7;
+        let sign_bit = byte & 0x40 != 0;
+        if (value == 0 && !sign_bit) || (value == -1 && sign_bit) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128() {
+        let encode = |value: i64| {
+            let (mut unsigned, mut signed) = (Vec::new(), Vec::new());
+            if let Ok(value) = u64::try_from(value) {
+                uleb128(&mut unsigned, value);
+            }
+            sleb128(&mut signed, value);
+            (unsigned, signed)
+        };
+        assert_eq!(encode(2), (vec![2], vec![2]));
+        assert_eq!(encode(127), (vec![0x7f], vec![0xff, 0x00]));
+        assert_eq!(encode(624485), (vec![0xe5, 0x8e, 0x26], vec![0xe5, 0x8e, 0x26]));
+        assert_eq!(encode(-8), (vec![], vec![0x78]));
+        assert_eq!(encode(-123456), (vec![], vec![0xc0, 0xbb, 0x78]));
+    }
+
+    #[test]
+    fn unit_references() {
+        let mut unit = Unit::new();
+        unit.set(Unit::ROOT, DW_AT_NAME, AttrValue::String("unit".to_owned()));
+        let int = unit.add(Unit::ROOT, DW_TAG_BASE_TYPE);
+        let var = unit.add(Unit::ROOT, DW_TAG_VARIABLE);
+        unit.set(var, DW_AT_TYPE, AttrValue::Ref(int));
+        unit.set(int, DW_AT_BYTE_SIZE, AttrValue::Udata(4));
+        let (abbrev, info) = unit.encode();
+        // The root entry with its name is followed by the base type, so the reference to it from
+        // the variable points right after the root entry.
+        let int_offset = UNIT_HEADER_SIZE + 1 + "unit\0".len();
+        assert_eq!(
+            &info[info.len() - 5..info.len() - 1],
+            &u32::try_from(int_offset).unwrap().to_le_bytes()
+        );
+        assert_eq!(info[int_offset], 2);
+        // Three abbreviations with a single attribute each (code, tag, children flag, attribute and
+        // form, terminator), followed by the end of the table.
+        assert_eq!(abbrev.len(), 3 * (3 + 2 + 2) + 1);
+        assert_eq!(
+            u32::from_le_bytes(info[..4].try_into().unwrap()),
+            u32::try_from(info.len() - 4).unwrap()
+        );
+    }
+}
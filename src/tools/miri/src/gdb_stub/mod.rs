@@ -0,0 +1,951 @@
+//! A stub for GDB's remote serial protocol, so that an interpreted program can be debugged
+//! interactively with `-Zmiri-gdb-server`.
+//!
+//! Miri does not execute machine code, so the stub presents a synthetic x86-64 target to the
+//! debugger: every MIR statement and terminator has a code address, and every stack frame has a
+//! block of synthetic stack memory that `rbp` points to (see `debuginfo`). The stub describes this
+//! code and stack to the debugger with symbol files that it reports as shared libraries, so the
+//! debugger's own commands work: `break <function>` and `break <file>:<line>`, `step`, `next`,
+//! `finish`, `backtrace`, `info locals` and printing values with Rust's pretty-printers. Memory
+//! can be read at the addresses Miri assigned to allocations, but not written. Single-stepping
+//! executes one MIR statement or terminator. `monitor` commands (`monitor help` lists them) show
+//! what the debug info cannot express, such as the MIR statement a frame is about to execute and
+//! the memory layout of a local.
+
+mod debuginfo;
+mod elf;
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+
+use rustc_abi::{FieldIdx, FieldsShape, Variants};
+use rustc_data_structures::fx::FxHashSet;
+use rustc_middle::mir;
+use rustc_middle::ty::layout::TyAndLayout;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, Ty};
+
+use self::debuginfo::{CodeMap, debug_locals};
+use crate::*;
+
+/// How many steps to execute between two checks whether the debugger asked to interrupt.
+const INTERRUPT_POLL_INTERVAL: u32 = 10_000;
+/// The byte the debugger sends outside of a packet to interrupt the program.
+const INTERRUPT: u8 = 0x03;
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+
+/// The registers of the synthetic target, as (name, size in bits, type, group). The order
+/// determines the register numbers; `rip` is the program counter.
+#[rustfmt::skip]
+const REGISTERS: &[(&str, u32, &str, &str)] = &[
+    ("rax", 64, "int64", "general"), ("rbx", 64, "int64", "general"),
+    ("rcx", 64, "int64", "general"), ("rdx", 64, "int64", "general"),
+    ("rsi", 64, "int64", "general"), ("rdi", 64, "int64", "general"),
+    ("rbp", 64, "data_ptr", "general"), ("rsp", 64, "data_ptr", "general"),
+    ("r8", 64, "int64", "general"), ("r9", 64, "int64", "general"),
+    ("r10", 64, "int64", "general"), ("r11", 64, "int64", "general"),
+    ("r12", 64, "int64", "general"), ("r13", 64, "int64", "general"),
+    ("r14", 64, "int64", "general"), ("r15", 64, "int64", "general"),
+    ("rip", 64, "code_ptr", "general"), ("eflags", 32, "int32", "general"),
+    ("cs", 32, "int32", "general"), ("ss", 32, "int32", "general"),
+    ("ds", 32, "int32", "general"), ("es", 32, "int32", "general"),
+    ("fs", 32, "int32", "general"), ("gs", 32, "int32", "general"),
+    ("st0", 80, "i387_ext", "float"), ("st1", 80, "i387_ext", "float"),
+    ("st2", 80, "i387_ext", "float"), ("st3", 80, "i387_ext", "float"),
+    ("st4", 80, "i387_ext", "float"), ("st5", 80, "i387_ext", "float"),
+    ("st6", 80, "i387_ext", "float"), ("st7", 80, "i387_ext", "float"),
+    ("fctrl", 32, "int", "float"), ("fstat", 32, "int", "float"),
+    ("ftag", 32, "int", "float"), ("fiseg", 32, "int", "float"),
+    ("fioff", 32, "int", "float"), ("foseg", 32, "int", "float"),
+    ("fooff", 32, "int", "float"), ("fop", 32, "int", "float"),
+    ("xmm0", 128, "uint128", "vector"), ("xmm1", 128, "uint128", "vector"),
+    ("xmm2", 128, "uint128", "vector"), ("xmm3", 128, "uint128", "vector"),
+    ("xmm4", 128, "uint128", "vector"), ("xmm5", 128, "uint128", "vector"),
+    ("xmm6", 128, "uint128", "vector"), ("xmm7", 128, "uint128", "vector"),
+    ("xmm8", 128, "uint128", "vector"), ("xmm9", 128, "uint128", "vector"),
+    ("xmm10", 128, "uint128", "vector"), ("xmm11", 128, "uint128", "vector"),
+    ("xmm12", 128, "uint128", "vector"), ("xmm13", 128, "uint128", "vector"),
+    ("xmm14", 128, "uint128", "vector"), ("xmm15", 128, "uint128", "vector"),
+    ("mxcsr", 32, "int", "vector"),
+];
+const FRAME_POINTER_REGISTER: usize = 6;
+const STACK_POINTER_REGISTER: usize = 7;
+const PC_REGISTER: usize = 16;
+/// The number of registers that belong to the `org.gnu.gdb.i386.core` feature.
+const CORE_REGISTERS: usize = 40;
+
+const MONITOR_HELP: &str = "\
+Miri debugger commands:
+  break <function>         stop when <function> is called (matches a path suffix)
+  break <file>:<line>      stop when execution reaches <line> of <file>
+  delete [<n>]             delete breakpoint <n>, or all breakpoints
+  breakpoints              list the breakpoints
+  backtrace                print the stack of the selected thread
+  frame <n>                select frame <n> of the selected thread
+  where                    print the statement the selected frame is about to execute
+  locals                   print the locals of the selected frame
+  layout <local>           print the memory layout of a local of the selected frame
+";
+
+#[derive(Debug)]
+enum Breakpoint {
+    Function(String),
+    Line { file: String, line: usize },
+}
+
+/// What the program does until the debugger gets control back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunState {
+    /// The debugger just attached and has not resumed the program yet.
+    Attached,
+    Continuing,
+    Stepping,
+}
+
+/// What the interpreter has to do after the debugger resumed the program.
+#[derive(Debug)]
+pub enum DebuggerAction {
+    Resume,
+    /// The debugger detached, the program runs on without it.
+    Detach,
+    /// The debugger asked to kill the program.
+    Kill,
+}
+
+/// A connection to the debugger.
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    /// Whether the debugger asked to stop acknowledging packets.
+    no_ack: bool,
+}
+
+impl Connection {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// Reads the next packet, ignoring acknowledgments and stray interrupts.
+    fn read_packet(&mut self) -> io::Result<String> {
+        loop {
+            while self.read_byte()? != b'$' {}
+            let mut data = Vec::new();
+            let mut sum = 0u8;
+            loop {
+                let byte = self.read_byte()?;
+                if byte == b'#' {
+                    break;
+                }
+                sum = sum.wrapping_add(byte);
+                data.push(byte);
+            }
+            let checksum = [self.read_byte()?, self.read_byte()?];
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(sum);
+            if self.no_ack {
+                // Without acknowledgments the transport is trusted to be reliable.
+            } else if valid {
+                self.stream.write_all(b"+")?;
+            } else {
+                self.stream.write_all(b"-")?;
+                continue;
+            }
+            return Ok(String::from_utf8_lossy(&data).into_owned());
+        }
+    }
+
+    fn send_packet(&mut self, data: &str) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(data.len().strict_add(4));
+        let mut sum = 0u8;
+        packet.push(b'$');
+        for &byte in data.as_bytes() {
+            // These bytes have to be escaped.
+            if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+                packet.push(b'}');
+                packet.push(byte ^ 0x20);
+                sum = sum.wrapping_add(b'}').wrapping_add(byte ^ 0x20);
+            } else {
+                packet.push(byte);
+                sum = sum.wrapping_add(byte);
+            }
+        }
+        write!(packet, "#{sum:02x}")?;
+        loop {
+            self.stream.write_all(&packet)?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match self.read_byte()? {
+                b'+' => return Ok(()),
+                b'-' => continue,
+                byte => {
+                    let byte = char::from(byte);
+                    let msg = format!("expected an acknowledgment, got `{byte}`");
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                }
+            }
+        }
+    }
+
+    /// Sends text to be printed by the debugger.
+    fn send_output(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.send_packet(&format!("O{}", hex(text.as_bytes())))
+    }
+
+    /// Checks, without blocking, whether the debugger sent an interrupt.
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0];
+        let res = self.stream.read(&mut byte);
+        self.stream.set_nonblocking(false)?;
+        match res {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => Ok(byte[0] == INTERRUPT),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The state of `-Zmiri-gdb-server`: the connection to the debugger and its breakpoints.
+#[derive(Debug)]
+pub struct GdbStub<'tcx> {
+    conn: Connection,
+    state: RunState,
+    /// The synthetic code of the functions the debugger knows about.
+    code: CodeMap<'tcx>,
+    /// The breakpoints set with `monitor break`, numbered from 1.
+    breakpoints: Vec<Option<Breakpoint>>,
+    /// The breakpoints set by the debugger on synthetic program counters.
+    pc_breakpoints: FxHashSet<u64>,
+    /// The thread that stopped at a breakpoint on a return address, and that address. Until the
+    /// program resumes, it is the program counter of that thread, rather than the start of the
+    /// block the call returned to.
+    return_stop: Option<(ThreadId, u64)>,
+    /// The (thread, stack depth, file, line) of the previous step, so that line breakpoints only
+    /// stop once when execution reaches the line.
+    last_line: Option<(ThreadId, usize, String, usize)>,
+    steps_since_poll: u32,
+    /// The thread and frame (counting from the innermost one) the debugger looks at.
+    selected_thread: ThreadId,
+    selected_frame: usize,
+}
+
+impl<'tcx> GdbStub<'tcx> {
+    /// Listens on `addr`, which is either a port or a `host:port` pair, and waits for a debugger
+    /// to connect.
+    pub fn listen(addr: &str) -> io::Result<Self> {
+        let addr = if addr.contains(':') { addr.to_owned() } else { format!("127.0.0.1:{addr}") };
+        let listener = TcpListener::bind(&addr)?;
+        eprintln!("Waiting for a debugger to connect on {}...", listener.local_addr()?);
+        let (stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        Ok(GdbStub {
+            conn: Connection { stream, no_ack: false },
+            state: RunState::Attached,
+            code: CodeMap::new(),
+            breakpoints: Vec::new(),
+            pc_breakpoints: Default::default(),
+            return_stop: None,
+            last_line: None,
+            steps_since_poll: 0,
+            selected_thread: ThreadId::MAIN_THREAD,
+            selected_frame: 0,
+        })
+    }
+
+    /// Called before the active thread executes a step. Gives control to the debugger if the
+    /// program should stop here.
+    pub fn before_step(&mut self, ecx: &MiriInterpCx<'tcx>) -> io::Result<DebuggerAction> {
+        self.return_stop = None;
+        if self.state == RunState::Attached && self.code.is_empty() {
+            self.code.add_local_functions(ecx);
+        }
+        let line_hit = self.reached_line_breakpoint(ecx);
+        let mut new_function = false;
+        let signal = match self.state {
+            RunState::Attached => None,
+            RunState::Stepping => Some(SIGTRAP),
+            RunState::Continuing =>
+                if line_hit || self.at_function_breakpoint(ecx) || self.at_pc_breakpoint(ecx) {
+                    Some(SIGTRAP)
+                } else if self.entering_new_function(ecx) {
+                    // Stop to tell the debugger about the new function, so that it can resolve
+                    // pending breakpoints on it. It resumes the program right away.
+                    new_function = true;
+                    Some(SIGTRAP)
+                } else {
+                    self.steps_since_poll = self.steps_since_poll.strict_add(1);
+                    if self.steps_since_poll < INTERRUPT_POLL_INTERVAL {
+                        return Ok(DebuggerAction::Resume);
+                    }
+                    self.steps_since_poll = 0;
+                    if !self.conn.poll_interrupt()? {
+                        return Ok(DebuggerAction::Resume);
+                    }
+                    Some(SIGINT)
+                },
+        };
+        self.selected_thread = ecx.machine.threads.active_thread();
+        self.selected_frame = 0;
+        self.code.add_stacks(ecx);
+        if let Some(signal) = signal {
+            let library = self.code.write_symbols(ecx)?;
+            let reply = self.stop_reply(signal, /* breakpoint */ !new_function, library);
+            self.conn.send_packet(&reply)?;
+        }
+        let action = self.serve(ecx, /* terminated */ false)?;
+        // The debugger may just have set a breakpoint right where the program is.
+        if new_function
+            && matches!(action, DebuggerAction::Resume)
+            && self.state == RunState::Continuing
+            && self.at_pc_breakpoint(ecx)
+        {
+            let reply =
+                self.stop_reply(SIGTRAP, /* breakpoint */ true, /* library */ false);
+            self.conn.send_packet(&reply)?;
+            return self.serve(ecx, /* terminated */ false);
+        }
+        Ok(action)
+    }
+
+    /// Reports to the debugger that the program exited with `code`.
+    pub fn report_exit(&mut self, code: i32) -> io::Result<()> {
+        // The debugger only understands exit codes that fit into a byte.
+        self.conn.send_packet(&format!("W{:02x}", code.to_le_bytes()[0]))
+    }
+
+    /// Reports to the debugger that Miri stopped the program because of `msg`, and lets it
+    /// inspect the final state of the program until it resumes, detaches or kills it.
+    pub fn report_error(&mut self, ecx: &MiriInterpCx<'tcx>, msg: &str) -> io::Result<()> {
+        self.selected_thread = ecx.machine.threads.active_thread();
+        self.selected_frame = 0;
+        self.conn.send_output(&format!("Miri stopped the program: {msg}\n"))?;
+        self.code.add_stacks(ecx);
+        let library = self.code.write_symbols(ecx)?;
+        let reply = self.stop_reply(SIGABRT, /* breakpoint */ false, library);
+        self.conn.send_packet(&reply)?;
+        if let DebuggerAction::Resume = self.serve(ecx, /* terminated */ true)? {
+            self.conn.send_packet(&format!("X{SIGABRT:02x}"))?;
+        }
+        Ok(())
+    }
+
+    /// The reply that tells the debugger why the program stopped. `library` tells it to reload the
+    /// symbol files.
+    fn stop_reply(&self, signal: u8, breakpoint: bool, library: bool) -> String {
+        let thread = gdb_thread_id(self.selected_thread);
+        let swbreak = if breakpoint && signal == SIGTRAP && self.state == RunState::Continuing {
+            "swbreak:;"
+        } else {
+            ""
+        };
+        let library = if library { "library:;" } else { "" };
+        format!("T{signal:02x}thread:{thread:x};{swbreak}{library}")
+    }
+
+    /// Answers requests of the debugger until it resumes the program. If the program
+    /// `terminated`, the debugger can still inspect it, but not resume it.
+    fn serve(&mut self, ecx: &MiriInterpCx<'tcx>, terminated: bool) -> io::Result<DebuggerAction> {
+        loop {
+            let packet = self.conn.read_packet()?;
+            let reply = match packet.as_str() {
+                "?" => {
+                    let signal = if terminated { SIGABRT } else { SIGTRAP };
+                    self.stop_reply(signal, /* breakpoint */ false, /* library */ false)
+                }
+                "c" => {
+                    self.state = RunState::Continuing;
+                    return Ok(DebuggerAction::Resume);
+                }
+                "s" => {
+                    self.state = RunState::Stepping;
+                    return Ok(DebuggerAction::Resume);
+                }
+                "D" => {
+                    self.conn.send_packet("OK")?;
+                    return Ok(DebuggerAction::Detach);
+                }
+                "k" => return Ok(DebuggerAction::Kill),
+                "g" => hex(&self.registers(ecx)),
+                "qC" => format!("QC{:x}", gdb_thread_id(ecx.machine.threads.active_thread())),
+                "qAttached" => "1".to_owned(),
+                "qfThreadInfo" => {
+                    let threads = ecx
+                        .machine
+                        .threads
+                        .all_threads()
+                        .filter(|(_, thread)| !thread.is_terminated())
+                        .map(|(id, _)| format!("{:x}", gdb_thread_id(id)))
+                        .collect::<Vec<_>>();
+                    format!("m{}", threads.join(","))
+                }
+                "qsThreadInfo" => "l".to_owned(),
+                "QStartNoAckMode" => {
+                    self.conn.send_packet("OK")?;
+                    self.conn.no_ack = true;
+                    continue;
+                }
+                "qHostInfo" | "qProcessInfo" =>
+                    format!(
+                        "pid:1;triple:{};ostype:linux;endian:little;ptrsize:8;",
+                        hex(b"x86_64-unknown-linux-gnu")
+                    ),
+                "vCont?" => "vCont;c;C;s;S".to_owned(),
+                "qSymbol::" => "OK".to_owned(),
+                _ =>
+                    if let Some(action) = packet.strip_prefix("vCont;") {
+                        // All threads are resumed together, so the only thing that matters is
+                        // whether any of them is asked to step.
+                        let step = action.split(';').any(|action| action.starts_with(['s', 'S']));
+                        self.state = if step { RunState::Stepping } else { RunState::Continuing };
+                        return Ok(DebuggerAction::Resume);
+                    } else if packet.starts_with(['C', 'S']) {
+                        // Signals cannot be delivered to the program, so these resume it as usual.
+                        let step = packet.starts_with('S');
+                        self.state = if step { RunState::Stepping } else { RunState::Continuing };
+                        return Ok(DebuggerAction::Resume);
+                    } else {
+                        self.handle_request(ecx, &packet)?
+                    },
+            };
+            self.conn.send_packet(&reply)?;
+        }
+    }
+
+    /// Handles the requests that do not affect whether the program runs.
+    fn handle_request(&mut self, ecx: &MiriInterpCx<'tcx>, packet: &str) -> io::Result<String> {
+        let reply = if packet.starts_with("qSupported") {
+            "PacketSize=4000;QStartNoAckMode+;qXfer:features:read+;qXfer:libraries:read+;\
+            swbreak+;vContSupported+"
+                .to_owned()
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            xfer_reply(&target_xml(), args)
+        } else if let Some(args) = packet.strip_prefix("qXfer:libraries:read::") {
+            self.code.write_symbols(ecx)?;
+            xfer_reply(&self.code.libraries_xml(), args)
+        } else if let Some(args) = packet.strip_prefix('m') {
+            let bytes = parse_pair(args).and_then(|(addr, len)| {
+                self.code.read(ecx, addr, len).or_else(|| read_memory(ecx, addr, len))
+            });
+            match bytes {
+                Some(bytes) => hex(&bytes),
+                None => "E01".to_owned(),
+            }
+        } else if let Some(reg) = packet.strip_prefix('p') {
+            match u64::from_str_radix(reg, 16).ok().and_then(|reg| usize::try_from(reg).ok()) {
+                Some(reg) if reg < REGISTERS.len() => {
+                    let start = REGISTERS[..reg].iter().map(|r| r.1 / 8).sum::<u32>();
+                    let start = usize::try_from(start).unwrap();
+                    let end = start.strict_add(usize::try_from(REGISTERS[reg].1 / 8).unwrap());
+                    hex(&self.registers(ecx)[start..end])
+                }
+                _ => "E01".to_owned(),
+            }
+        } else if packet.starts_with(['M', 'X', 'G', 'P']) {
+            // Writing memory or registers is not supported.
+            "E01".to_owned()
+        } else if let Some(thread) = packet.strip_prefix("Hg") {
+            match parse_thread_id(ecx, thread) {
+                Some(thread) => {
+                    if let Some(thread) = thread {
+                        self.selected_thread = thread;
+                        self.selected_frame = 0;
+                    }
+                    "OK".to_owned()
+                }
+                None => "E01".to_owned(),
+            }
+        } else if packet.starts_with('H') {
+            // The thread for `c` and `s` does not matter since all threads are resumed together.
+            "OK".to_owned()
+        } else if let Some(thread) = packet.strip_prefix('T') {
+            match parse_thread_id(ecx, thread) {
+                Some(Some(thread)) if !ecx.machine.threads.thread_ref(thread).is_terminated() =>
+                    "OK".to_owned(),
+                _ => "E01".to_owned(),
+            }
+        } else if let Some(thread) = packet.strip_prefix("qThreadExtraInfo,") {
+            match parse_thread_id(ecx, thread) {
+                Some(Some(thread)) => {
+                    let name = ecx.machine.threads.get_thread_display_name(thread);
+                    hex(name.as_bytes())
+                }
+                _ => "E01".to_owned(),
+            }
+        } else if let Some(args) = packet.strip_prefix("Z0,").or(packet.strip_prefix("Z1,")) {
+            match parse_pair(args) {
+                Some((addr, _kind)) => {
+                    self.pc_breakpoints.insert(addr);
+                    "OK".to_owned()
+                }
+                None => "E01".to_owned(),
+            }
+        } else if let Some(args) = packet.strip_prefix("z0,").or(packet.strip_prefix("z1,")) {
+            match parse_pair(args) {
+                Some((addr, _kind)) => {
+                    self.pc_breakpoints.remove(&addr);
+                    "OK".to_owned()
+                }
+                None => "E01".to_owned(),
+            }
+        } else if let Some(cmd) = packet.strip_prefix("qRcmd,") {
+            match unhex(cmd) {
+                Some(cmd) => {
+                    let output = self.monitor(ecx, &cmd);
+                    self.conn.send_output(&output)?;
+                    "OK".to_owned()
+                }
+                None => "E01".to_owned(),
+            }
+        } else {
+            // The empty reply tells the debugger that this request is not supported.
+            String::new()
+        };
+        Ok(reply)
+    }
+
+    /// Runs a `monitor` command and returns its output.
+    fn monitor(&mut self, ecx: &MiriInterpCx<'tcx>, cmd: &str) -> String {
+        let (cmd, arg) = cmd.trim().split_once(' ').unwrap_or((cmd.trim(), ""));
+        let arg = arg.trim();
+        let mut out = String::new();
+        match cmd {
+            "help" | "" => out.push_str(MONITOR_HELP),
+            "break" | "b" => {
+                let breakpoint = match arg.rsplit_once(':') {
+                    Some((file, line)) if !file.ends_with(':') && line.parse::<usize>().is_ok() =>
+                        Breakpoint::Line { file: file.to_owned(), line: line.parse().unwrap() },
+                    _ if !arg.is_empty() => Breakpoint::Function(arg.to_owned()),
+                    _ => return "usage: break <function> | <file>:<line>\n".to_owned(),
+                };
+                self.breakpoints.push(Some(breakpoint));
+                writeln!(out, "Breakpoint {} at {arg}", self.breakpoints.len()).unwrap();
+            }
+            "delete" | "d" =>
+                if arg.is_empty() {
+                    self.breakpoints.clear();
+                } else {
+                    match arg.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+                        Some(idx) if self.breakpoints.get(idx).is_some_and(Option::is_some) =>
+                            self.breakpoints[idx] = None,
+                        _ => writeln!(out, "No breakpoint number {arg}.").unwrap(),
+                    }
+                },
+            "breakpoints" | "info" => {
+                for (idx, breakpoint) in self.breakpoints.iter().enumerate() {
+                    let n = idx.strict_add(1);
+                    match breakpoint {
+                        Some(Breakpoint::Function(name)) => writeln!(out, "{n}: {name}").unwrap(),
+                        Some(Breakpoint::Line { file, line }) =>
+                            writeln!(out, "{n}: {file}:{line}").unwrap(),
+                        None => {}
+                    }
+                }
+                if out.is_empty() {
+                    out.push_str("No breakpoints.\n");
+                }
+            }
+            "backtrace" | "bt" => {
+                let stack = ecx.machine.threads.thread_stack(self.selected_thread);
+                for (n, frame) in stack.iter().rev().enumerate() {
+                    let marker = if n == self.selected_frame { '>' } else { ' ' };
+                    writeln!(out, "{marker}#{n} {}", describe_frame(ecx, frame)).unwrap();
+                }
+                if stack.is_empty() {
+                    out.push_str("No stack.\n");
+                }
+            }
+            "frame" | "f" => {
+                let depth = ecx.machine.threads.thread_stack(self.selected_thread).len();
+                match arg.parse::<usize>() {
+                    Ok(n) if n < depth => {
+                        self.selected_frame = n;
+                        out = self.monitor(ecx, "where");
+                    }
+                    _ => writeln!(out, "No frame {arg}; the stack has {depth} frames.").unwrap(),
+                }
+            }
+            "where" =>
+                match self.frame(ecx) {
+                    Some(frame) => {
+                        let n = self.selected_frame;
+                        writeln!(out, "#{n} {}", describe_frame(ecx, frame)).unwrap();
+                        if let Either::Left(loc) = frame.current_loc() {
+                            let body = frame.body();
+                            let block = &body.basic_blocks[loc.block];
+                            let stmt = match block.statements.get(loc.statement_index) {
+                                Some(stmt) => format!("{stmt:?}"),
+                                None => format!("{:?}", block.terminator().kind),
+                            };
+                            writeln!(out, "    {loc:?}: {stmt}").unwrap();
+                        }
+                    }
+                    None => out.push_str("No stack.\n"),
+                },
+            "locals" =>
+                match self.frame(ecx) {
+                    Some(frame) => {
+                        for (var, local) in debug_locals(frame.body()) {
+                            let name = var.name.to_string();
+                            writeln!(out, "{}", describe_local(ecx, frame, name, local)).unwrap();
+                        }
+                        if out.is_empty() {
+                            out.push_str("No locals.\n");
+                        }
+                    }
+                    None => out.push_str("No stack.\n"),
+                },
+            "layout" => {
+                let local = self.frame(ecx).and_then(|frame| {
+                    let local =
+                        debug_locals(frame.body()).find(|(var, _)| var.name.as_str() == arg)?.1;
+                    ecx.layout_of_local(frame, local, None).discard_err()
+                });
+                match local {
+                    Some(layout) => describe_layout(ecx, &mut out, arg, layout, 0, 0),
+                    None => writeln!(out, "No local `{arg}` in the selected frame.").unwrap(),
+                }
+            }
+            _ => writeln!(out, "Unknown command `{cmd}`; try `monitor help`.").unwrap(),
+        }
+        out
+    }
+
+    fn frame<'a>(
+        &self,
+        ecx: &'a MiriInterpCx<'tcx>,
+    ) -> Option<&'a Frame<'tcx, Provenance, FrameExtra<'tcx>>> {
+        ecx.machine.threads.thread_stack(self.selected_thread).iter().rev().nth(self.selected_frame)
+    }
+
+    /// The registers of the innermost frame of the selected thread.
+    fn registers(&self, ecx: &MiriInterpCx<'tcx>) -> Vec<u8> {
+        let stack = ecx.machine.threads.thread_stack(self.selected_thread);
+        let Some(frame) = stack.last() else { return registers(0, 0) };
+        let pc = match self.return_stop {
+            Some((thread, return_address)) if thread == self.selected_thread => return_address,
+            _ => self.code.pc(frame).unwrap_or(0),
+        };
+        registers(pc, CodeMap::frame_address(self.selected_thread, stack.len().strict_sub(1)))
+    }
+
+    fn at_pc_breakpoint(&mut self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        if self.pc_breakpoints.is_empty() {
+            return false;
+        }
+        let Some(frame) = ecx.active_thread_stack().last() else { return false };
+        // Functions without code cannot have a breakpoint.
+        if self.code.pc(frame).is_some_and(|pc| self.pc_breakpoints.contains(&pc)) {
+            return true;
+        }
+        // The debugger sets breakpoints on return addresses to stop when a call returns, e.g. for
+        // `finish` and `next`.
+        let return_stop =
+            self.pc_breakpoints.iter().copied().find(|&addr| self.code.returned_to(frame, addr));
+        self.return_stop = return_stop.map(|addr| (ecx.machine.threads.active_thread(), addr));
+        self.return_stop.is_some()
+    }
+
+    /// Whether the active thread is about to execute the first statement of a function of the
+    /// local crate that the debugger does not know about yet.
+    fn entering_new_function(&self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        let Some(frame) = ecx.active_thread_stack().last() else { return false };
+        frame.current_loc().left() == Some(mir::Location::START)
+            && frame.instance().def_id().is_local()
+            && !self.code.contains(frame.instance())
+    }
+
+    /// Whether the active thread is about to execute the first statement of a function with a
+    /// breakpoint.
+    fn at_function_breakpoint(&self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        let Some(frame) = ecx.active_thread_stack().last() else { return false };
+        if frame.current_loc().left() != Some(mir::Location::START) {
+            return false;
+        }
+        let mut path = None;
+        self.breakpoints.iter().any(|breakpoint| {
+            let Some(Breakpoint::Function(name)) = breakpoint else { return false };
+            let path = path.get_or_insert_with(|| {
+                with_no_trimmed_paths!(ecx.tcx.def_path_str(frame.instance().def_id()))
+            });
+            *path == *name || path.strip_suffix(name.as_str()).is_some_and(|p| p.ends_with("::"))
+        })
+    }
+
+    /// Whether the active thread just reached a line with a breakpoint, coming from a different
+    /// line (or a different frame).
+    fn reached_line_breakpoint(&mut self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        if !self.breakpoints.iter().any(|b| matches!(b, Some(Breakpoint::Line { .. }))) {
+            return false;
+        }
+        let stack = ecx.active_thread_stack();
+        let line = stack.last().and_then(|frame| {
+            frame.current_loc().left()?;
+            let pos = ecx.tcx.sess.source_map().lookup_char_pos(frame.current_span().lo());
+            let file = pos.file.name.prefer_local_unconditionally().to_string();
+            Some((ecx.machine.threads.active_thread(), stack.len(), file, pos.line))
+        });
+        let reached = line.as_ref().is_some_and(|line| {
+            self.last_line.as_ref() != Some(line)
+                && self.breakpoints.iter().any(|breakpoint| {
+                    matches!(
+                        breakpoint,
+                        Some(Breakpoint::Line { file, line: l })
+                            if *l == line.3 && Path::new(&line.2).ends_with(file)
+                    )
+                })
+        });
+        self.last_line = line;
+        reached
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Gives the debugger a chance to stop the program before the active thread takes a step.
+    fn gdb_before_step(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let Some(mut gdb) = this.machine.gdb.take() else { return interp_ok(()) };
+        match gdb.before_step(this) {
+            Ok(DebuggerAction::Resume) => this.machine.gdb = Some(gdb),
+            Ok(DebuggerAction::Detach) => {}
+            Ok(DebuggerAction::Kill) =>
+                throw_machine_stop!(TerminationInfo::Abort(
+                    "the program was killed by the debugger".to_owned()
+                )),
+            Err(err) => {
+                let msg = format!("lost the connection to the debugger, continuing without: {err}");
+                this.tcx.dcx().warn(msg);
+            }
+        }
+        interp_ok(())
+    }
+}
+
+/// The thread ids the debugger sees start at 1, since 0 means "any thread".
+fn gdb_thread_id(thread: ThreadId) -> u32 {
+    thread.to_u32().strict_add(1)
+}
+
+/// Parses a thread id sent by the debugger. Returns `Some(None)` for "any thread".
+fn parse_thread_id(ecx: &MiriInterpCx<'_>, thread: &str) -> Option<Option<ThreadId>> {
+    if thread == "-1" || thread == "0" {
+        return Some(None);
+    }
+    let id = u32::from_str_radix(thread, 16).ok()?.checked_sub(1)?;
+    let count = ecx.machine.threads.get_total_thread_count();
+    (usize::try_from(id).ok()? < count).then(|| Some(ThreadId::new_unchecked(id)))
+}
+
+/// The contents of all registers, in the order of `REGISTERS`. `frame` is the address of the
+/// frame's block of synthetic stack memory.
+fn registers(pc: u64, frame: u64) -> Vec<u8> {
+    let mut regs = Vec::new();
+    for (idx, &(_, bits, _, _)) in REGISTERS.iter().enumerate() {
+        let value = match idx {
+            PC_REGISTER => pc,
+            FRAME_POINTER_REGISTER | STACK_POINTER_REGISTER => frame,
+            _ => 0,
+        };
+        let bytes = usize::try_from(bits / 8).unwrap();
+        regs.extend(value.to_le_bytes().into_iter().chain(std::iter::repeat(0)).take(bytes));
+    }
+    regs
+}
+
+fn target_xml() -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\"?>\n<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n");
+    xml.push_str("<target version=\"1.0\">\n<architecture>i386:x86-64</architecture>\n");
+    // Without an operating system ABI, the debugger loads the symbol files as described by
+    // `qXfer:libraries:read`, rather than looking for the dynamic linker's data structures.
+    xml.push_str("<osabi>none</osabi>\n");
+    for (idx, (name, bits, ty, group)) in REGISTERS.iter().enumerate() {
+        match idx {
+            0 => xml.push_str("<feature name=\"org.gnu.gdb.i386.core\">\n"),
+            CORE_REGISTERS => xml.push_str("</feature>\n<feature name=\"org.gnu.gdb.i386.sse\">\n"),
+            _ => {}
+        }
+        writeln!(
+            xml,
+            "<reg name=\"{name}\" bitsize=\"{bits}\" type=\"{ty}\" group=\"{group}\" \
+            regnum=\"{idx}\"/>"
+        )
+        .unwrap();
+    }
+    xml.push_str("</feature>\n</target>\n");
+    xml
+}
+
+/// The reply to a `qXfer:<object>:read` request for the part of `data` at `args` (offset, length).
+fn xfer_reply(data: &str, args: &str) -> String {
+    match parse_pair(args) {
+        Some((offset, len)) => {
+            let start = usize::try_from(offset).unwrap_or(usize::MAX).min(data.len());
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            let end = start.saturating_add(len).min(data.len());
+            let more = if end < data.len() { 'm' } else { 'l' };
+            format!("{more}{}", &data[start..end])
+        }
+        None => "E01".to_owned(),
+    }
+}
+
+/// Reads up to `len` bytes at `addr`, stopping at the end of the allocation. Provenance is
+/// ignored, and uninitialized bytes read as whatever Miri stores for them.
+fn read_memory(ecx: &MiriInterpCx<'_>, addr: u64, len: u64) -> Option<Vec<u8>> {
+    let alloc_id = ecx.alloc_id_containing_addr(addr, 0)?;
+    let base = ecx.addr_from_alloc_id(alloc_id, None).discard_err()?;
+    let alloc = ecx.get_alloc_raw(alloc_id).discard_err()?;
+    let start = usize::try_from(addr.strict_sub(base)).ok()?;
+    let len = usize::try_from(len).unwrap_or(usize::MAX);
+    let end = start.saturating_add(len).min(alloc.len());
+    Some(alloc.inspect_with_uninit_and_ptr_outside_interpreter(start..end).to_vec())
+}
+
+fn describe_frame<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+) -> String {
+    let span = ecx.tcx.sess.source_map().span_to_diagnostic_string(frame.current_span());
+    with_no_trimmed_paths!(format!("{} at {span}", frame.instance()))
+}
+
+/// Describes a local: immediates are printed with their value, locals in memory with their
+/// address, so that the debugger can read them with `x` or a cast like `*(T *)addr`.
+fn describe_local<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+    name: String,
+    local: mir::Local,
+) -> String {
+    let Some(value) = frame.locals[local].as_mplace_or_imm() else {
+        return format!("{name} = <dead>");
+    };
+    let Some(layout) = ecx.layout_of_local(frame, local, None).discard_err() else {
+        return format!("{name} = <unknown layout>");
+    };
+    let ty = with_no_trimmed_paths!(layout.ty.to_string());
+    match value {
+        Either::Left((ptr, _meta)) => {
+            let size = layout.size.bytes();
+            format!("{name}: {ty} @ {:#x} ({size} bytes)", ptr.addr().bytes())
+        }
+        Either::Right(Immediate::Uninit) => format!("{name}: {ty} = <uninit>"),
+        Either::Right(imm) => {
+            let value = with_no_trimmed_paths!(ImmTy::from_immediate(imm, layout).to_string());
+            format!("{name}: {ty} = {value}")
+        }
+    }
+}
+
+/// Prints the fields of `layout` with their offsets, the way Rust's debugger pretty-printers
+/// expect them: struct and tuple fields by name (`__0` for tuple fields), enums by variant.
+fn describe_layout<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    out: &mut String,
+    name: &str,
+    layout: TyAndLayout<'tcx>,
+    offset: u64,
+    depth: usize,
+) {
+    /// Nested fields are only shown up to this depth, to keep the output readable.
+    const MAX_DEPTH: usize = 3;
+    let indent = "  ".repeat(depth);
+    let ty = with_no_trimmed_paths!(layout.ty.to_string());
+    let size = layout.size.bytes();
+    let align = layout.align.abi.bytes();
+    writeln!(out, "{indent}+{offset:<4} {name}: {ty} (size {size}, align {align})").unwrap();
+    if depth >= MAX_DEPTH || !matches!(layout.fields, FieldsShape::Arbitrary { .. }) {
+        return;
+    }
+    match layout.variants {
+        Variants::Single { .. } | Variants::Empty =>
+            for idx in 0..layout.fields.count() {
+                let field = layout.field(ecx, idx);
+                let field_offset = offset.strict_add(layout.fields.offset(idx).bytes());
+                let name = field_name(layout.ty, idx);
+                describe_layout(ecx, out, &name, field, field_offset, depth.strict_add(1));
+            },
+        Variants::Multiple { ref variants, .. } => {
+            let tag_offset = offset.strict_add(layout.fields.offset(0).bytes());
+            writeln!(out, "{indent}  +{tag_offset:<4} <discriminant>").unwrap();
+            for variant in variants.indices() {
+                let variant_layout = layout.for_variant(ecx, variant);
+                let name = match layout.ty.kind() {
+                    ty::Adt(def, _) => def.variant(variant).name.to_string(),
+                    _ => format!("{variant:?}"),
+                };
+                describe_layout(ecx, out, &name, variant_layout, offset, depth.strict_add(1));
+            }
+        }
+    }
+}
+
+fn field_name(ty: Ty<'_>, idx: usize) -> String {
+    match ty.kind() {
+        ty::Adt(def, _) if !def.is_enum() =>
+            def.non_enum_variant().fields[FieldIdx::from_usize(idx)].name.to_string(),
+        _ => format!("__{idx}"),
+    }
+}
+
+fn parse_pair(args: &str) -> Option<(u64, u64)> {
+    let (a, b) = args.split_once(',')?;
+    Some((u64::from_str_radix(a, 16).ok()?, u64::from_str_radix(b, 16).ok()?))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().strict_mul(2));
+    for byte in bytes {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out
+}
+
+fn unhex(s: &str) -> Option<String> {
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i.strict_add(2))?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_layout() {
+        // GDB expects exactly this many bytes in a `g` reply for its x86-64 description.
+        assert_eq!(registers(0, 0).len(), 536);
+        assert_eq!(REGISTERS[FRAME_POINTER_REGISTER].0, "rbp");
+        assert_eq!(REGISTERS[STACK_POINTER_REGISTER].0, "rsp");
+        assert_eq!(REGISTERS[PC_REGISTER].0, "rip");
+        assert_eq!(REGISTERS[CORE_REGISTERS].0, "xmm0");
+        let regs = registers(0x1234, 0x7000);
+        assert_eq!(&regs[48..56], &0x7000u64.to_le_bytes());
+        assert_eq!(&regs[56..64], &0x7000u64.to_le_bytes());
+        assert_eq!(&regs[128..136], &0x1234u64.to_le_bytes());
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        assert_eq!(hex(b"help"), "68656c70");
+        assert_eq!(unhex("68656c70").as_deref(), Some("help"));
+        assert_eq!(unhex("6"), None);
+        assert_eq!(parse_pair("1000,4"), Some((0x1000, 4)));
+    }
+}
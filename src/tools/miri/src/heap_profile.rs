@@ -0,0 +1,269 @@
+//! A heap profiler that writes its results in the JSON format of DHAT, Valgrind's dynamic heap
+//! analysis tool, so that they can be explored with the DHAT viewer (`dh_view.html`).
+//!
+//! Unlike DHAT, Miri observes every heap allocation and every memory access exactly, so the
+//! numbers are precise rather than sampled. Time is measured in executed basic blocks, which keeps
+//! the profile deterministic.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::{iter, slice};
+
+use rustc_abi::Size;
+use rustc_data_structures::either::Either;
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap, FxIndexSet};
+use rustc_middle::mir;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::Span;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::borrow_tracker::AccessKind;
+use crate::*;
+
+/// Blocks that live for fewer basic blocks than this are considered short-lived by the viewer.
+const SHORT_LIVED_THRESHOLD: u64 = 500;
+
+/// The statistics for all blocks allocated at one allocation site (a "program point" in DHAT's
+/// terminology).
+#[derive(Default, Debug)]
+struct ProgramPoint {
+    total_bytes: u64,
+    total_blocks: u64,
+    /// The sum of the lifetimes of all blocks that have been freed.
+    total_lifetimes: u64,
+    curr_bytes: u64,
+    curr_blocks: u64,
+    max_bytes: u64,
+    max_blocks: u64,
+    /// The live bytes and blocks at the time of the global maximum.
+    gmax_bytes: u64,
+    gmax_blocks: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+/// A live heap block.
+#[derive(Debug)]
+struct Block {
+    /// The index of the program point this block was allocated at.
+    pp: usize,
+    size: u64,
+    allocated_at: u64,
+}
+
+/// The state of `-Zmiri-heap-profile`: statistics about all heap blocks and where they were
+/// allocated.
+#[derive(Default, Debug)]
+pub struct HeapProfiler<'tcx> {
+    /// All frames that occur in allocation backtraces.
+    frames: FxIndexSet<(ty::Instance<'tcx>, Span)>,
+    /// The frames (as indices into `frames`, innermost first) of each location in a function that
+    /// allocated. There is more than one frame if the location is in MIR inlined into the function.
+    /// This avoids generating a full stack trace for every allocation.
+    call_sites: FxHashMap<(ty::Instance<'tcx>, Either<mir::Location, Span>), SmallVec<[usize; 1]>>,
+    /// The program points, keyed by their backtrace (as indices into `frames`, innermost first).
+    pps: FxIndexMap<Vec<usize>, ProgramPoint>,
+    live: FxHashMap<AllocId, Block>,
+    curr_bytes: u64,
+    max_bytes: u64,
+    /// The time at which `max_bytes` was reached.
+    max_time: u64,
+    /// Whether `curr_bytes` is at a new global maximum whose per-program-point breakdown has not
+    /// been recorded yet. This is done lazily, right before the live bytes decrease.
+    at_unrecorded_max: bool,
+}
+
+impl<'tcx> HeapProfiler<'tcx> {
+    pub fn allocate(
+        &mut self,
+        id: AllocId,
+        size: Size,
+        stack: &[Frame<'tcx, Provenance, FrameExtra<'tcx>>],
+        tcx: TyCtxt<'tcx>,
+        now: u64,
+    ) {
+        let mut frames = Vec::new();
+        for frame in stack.iter().rev() {
+            let call_site = self
+                .call_sites
+                .entry((frame.instance(), frame.current_loc()))
+                .or_insert_with(|| {
+                    Frame::generate_stacktrace_from_stack(slice::from_ref(frame), tcx)
+                        .into_iter()
+                        .map(|info| self.frames.insert_full((info.instance, info.span)).0)
+                        .collect()
+                });
+            frames.extend_from_slice(call_site);
+        }
+        self.allocate_at(id, size, frames, now);
+    }
+
+    /// Records an allocation at the program point with the given backtrace.
+    fn allocate_at(&mut self, id: AllocId, size: Size, frames: Vec<usize>, now: u64) {
+        let entry = self.pps.entry(frames);
+        let pp_idx = entry.index();
+        let pp = entry.or_default();
+        let size = size.bytes();
+        pp.total_bytes = pp.total_bytes.strict_add(size);
+        pp.total_blocks = pp.total_blocks.strict_add(1);
+        pp.curr_bytes = pp.curr_bytes.strict_add(size);
+        pp.curr_blocks = pp.curr_blocks.strict_add(1);
+        if pp.curr_bytes > pp.max_bytes {
+            pp.max_bytes = pp.curr_bytes;
+            pp.max_blocks = pp.curr_blocks;
+        }
+        self.live.insert(id, Block { pp: pp_idx, size, allocated_at: now });
+
+        self.curr_bytes = self.curr_bytes.strict_add(size);
+        if self.curr_bytes > self.max_bytes {
+            self.max_bytes = self.curr_bytes;
+            self.max_time = now;
+            self.at_unrecorded_max = true;
+        }
+    }
+
+    pub fn deallocate(&mut self, id: AllocId, now: u64) {
+        let Some(block) = self.live.remove(&id) else { return };
+        self.record_max();
+        let pp = &mut self.pps[block.pp];
+        pp.curr_bytes = pp.curr_bytes.strict_sub(block.size);
+        pp.curr_blocks = pp.curr_blocks.strict_sub(1);
+        pp.total_lifetimes = pp.total_lifetimes.strict_add(now.strict_sub(block.allocated_at));
+        self.curr_bytes = self.curr_bytes.strict_sub(block.size);
+    }
+
+    pub fn access(&mut self, id: AllocId, size: Size, kind: AccessKind) {
+        let Some(block) = self.live.get(&id) else { return };
+        let pp = &mut self.pps[block.pp];
+        let bytes = match kind {
+            AccessKind::Read => &mut pp.read_bytes,
+            AccessKind::Write => &mut pp.write_bytes,
+        };
+        *bytes = bytes.saturating_add(size.bytes());
+    }
+
+    /// Records how the live bytes are distributed over the program points if we are at a new
+    /// global maximum.
+    fn record_max(&mut self) {
+        if !self.at_unrecorded_max {
+            return;
+        }
+        for pp in self.pps.values_mut() {
+            pp.gmax_bytes = pp.curr_bytes;
+            pp.gmax_blocks = pp.curr_blocks;
+        }
+        self.at_unrecorded_max = false;
+    }
+
+    /// Writes the profile in DHAT's JSON format. `cmd` is the command line of the program and `now`
+    /// the time at which it ended.
+    pub fn write(&mut self, path: &Path, tcx: TyCtxt<'tcx>, cmd: &str, now: u64) -> io::Result<()> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|&(instance, span)| {
+                let span = tcx.sess.source_map().span_to_diagnostic_string(span);
+                with_no_trimmed_paths!(format!("{instance} ({span})"))
+            })
+            .collect();
+        let profile = self.dhat_json(frames, cmd, now);
+        let mut out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut out, &profile)?;
+        writeln!(out)?;
+        out.flush()
+    }
+
+    /// Returns the profile in DHAT's JSON format, where `frames` are the descriptions of
+    /// `self.frames`.
+    fn dhat_json(&mut self, frames: Vec<String>, cmd: &str, now: u64) -> serde_json::Value {
+        self.record_max();
+        // Blocks that are still live at the end count as living until then.
+        let mut lifetimes: Vec<u64> = self.pps.values().map(|pp| pp.total_lifetimes).collect();
+        for block in self.live.values() {
+            lifetimes[block.pp] =
+                lifetimes[block.pp].strict_add(now.strict_sub(block.allocated_at));
+        }
+        let pps = iter::zip(&self.pps, lifetimes)
+            .map(|((frames, pp), lifetime)| {
+                json!({
+                    "tb": pp.total_bytes,
+                    "tbk": pp.total_blocks,
+                    "tl": lifetime,
+                    "mb": pp.max_bytes,
+                    "mbk": pp.max_blocks,
+                    "gb": pp.gmax_bytes,
+                    "gbk": pp.gmax_blocks,
+                    "eb": pp.curr_bytes,
+                    "ebk": pp.curr_blocks,
+                    "rb": pp.read_bytes,
+                    "wb": pp.write_bytes,
+                    // Frame 0 is the root, so the indices of the actual frames are shifted by one.
+                    "fs": frames.iter().map(|frame| frame.strict_add(1)).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "dhatFileVersion": 2,
+            "mode": "rust-heap",
+            "verb": "Allocated",
+            "bklt": true,
+            "bkacc": true,
+            "tu": "blocks",
+            "Mtu": "block",
+            "tuth": SHORT_LIVED_THRESHOLD,
+            "cmd": cmd,
+            "pid": std::process::id(),
+            "te": now,
+            "tg": self.max_time,
+            "pps": pps,
+            "ftbl": iter::once("[root]".to_owned()).chain(frames).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+
+    #[test]
+    fn dhat_json() {
+        let alloc = |id| AllocId(NonZero::new(id).unwrap());
+        let mut profiler = HeapProfiler::default();
+        // `main` allocates 16 bytes itself, and 100 bytes twice by calling `helper`. The first of
+        // those is freed before the second is allocated.
+        profiler.allocate_at(alloc(1), Size::from_bytes(16), vec![0], 10);
+        profiler.allocate_at(alloc(2), Size::from_bytes(100), vec![1, 0], 20);
+        profiler.access(alloc(2), Size::from_bytes(8), AccessKind::Write);
+        profiler.access(alloc(2), Size::from_bytes(4), AccessKind::Read);
+        profiler.deallocate(alloc(2), 50);
+        profiler.allocate_at(alloc(3), Size::from_bytes(100), vec![1, 0], 60);
+
+        let profile =
+            profiler.dhat_json(vec!["main".to_owned(), "helper".to_owned()], "prog arg", 100);
+        assert_eq!(profile["dhatFileVersion"], 2);
+        assert_eq!(profile["mode"], "rust-heap");
+        assert_eq!(profile["cmd"], "prog arg");
+        assert_eq!(profile["te"], 100);
+        // The maximum was reached when the first block of `helper` was allocated.
+        assert_eq!(profile["tg"], 20);
+        assert_eq!(profile["ftbl"], json!(["[root]", "main", "helper"]));
+        assert_eq!(
+            profile["pps"],
+            json!([
+                {
+                    "tb": 16, "tbk": 1, "tl": 90, "mb": 16, "mbk": 1, "gb": 16, "gbk": 1,
+                    "eb": 16, "ebk": 1, "rb": 0, "wb": 0, "fs": [1],
+                },
+                {
+                    "tb": 200, "tbk": 2, "tl": 70, "mb": 100, "mbk": 1, "gb": 100, "gbk": 1,
+                    "eb": 100, "ebk": 1, "rb": 4, "wb": 8, "fs": [2, 1],
+                },
+            ])
+        );
+    }
+}
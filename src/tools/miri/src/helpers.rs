@@ -387,8 +387,15 @@ fn gen_random(&mut self, ptr: Pointer, len: u64) -> InterpResult<'tcx> {
 
         if this.machine.communicate() {
             // Fill the buffer using the host's rng.
-            getrandom::fill(&mut data)
-                .map_err(|err| err_unsup_format!("host getrandom failed: {}", err))?;
+            let fill = |data: &mut [u8]| -> InterpResult<'tcx> {
+                getrandom::fill(data)
+                    .map_err(|err| err_unsup_format!("host getrandom failed: {}", err))
+                    .into()
+            };
+            match &this.machine.trace {
+                Some(trace) => trace.borrow_mut().host_random(&mut data, fill)?,
+                None => fill(&mut data)?,
+            }
         } else {
             let rng = this.machine.rng.get_mut();
             rng.fill_bytes(&mut data);
@@ -0,0 +1,291 @@
+//! Grouping of leaked allocations by the backtrace they were allocated at, for the JSON report of
+//! `-Zmiri-leak-report`, and the suppression of known leaks with `-Zmiri-leak-allowlist`.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::iter;
+use std::path::Path;
+
+use rustc_abi::Size;
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap, FxIndexSet};
+use rustc_middle::ty;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_span::Span;
+use serde_json::json;
+
+use crate::*;
+
+/// A leaked allocation, as returned by `take_leaked_allocations`.
+pub type Leak<'tcx> =
+    (AllocId, MemoryKind, Allocation<Provenance, AllocExtra<'tcx>, MiriAllocBytes>);
+
+fn render_span(ecx: &MiriInterpCx<'_>, span: Span) -> String {
+    ecx.tcx.sess.source_map().span_to_diagnostic_string(span)
+}
+
+/// Renders a backtrace frame the way it appears in the leak report and the way the allowlist
+/// patterns are matched against.
+fn render_frame<'tcx>(ecx: &MiriInterpCx<'tcx>, frame: &FrameInfo<'tcx>) -> String {
+    let span = render_span(ecx, frame.span);
+    with_no_trimmed_paths!(format!("{} ({span})", frame.instance))
+}
+
+/// The patterns of `-Zmiri-leak-allowlist`. A leak is suppressed if any frame of the backtrace it
+/// was allocated at contains one of the patterns.
+#[derive(Debug)]
+pub struct LeakAllowlist {
+    patterns: Vec<String>,
+}
+
+impl LeakAllowlist {
+    /// Reads the allowlist from `path`: one pattern per line, ignoring empty lines and lines
+    /// starting with `#`.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let patterns = fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect();
+        Ok(LeakAllowlist { patterns })
+    }
+
+    fn matches<'tcx>(&self, ecx: &MiriInterpCx<'tcx>, backtrace: &[FrameInfo<'tcx>]) -> bool {
+        backtrace.iter().any(|frame| {
+            let frame = render_frame(ecx, frame);
+            self.patterns.iter().any(|pattern| frame.contains(pattern.as_str()))
+        })
+    }
+
+    /// Removes the allowed leaks from `leaks`, together with all leaks that are only reachable
+    /// through them. Returns the number of removed leaks.
+    pub fn filter<'tcx>(&self, ecx: &MiriInterpCx<'tcx>, leaks: &mut Vec<Leak<'tcx>>) -> usize {
+        let index: FxHashMap<AllocId, usize> =
+            leaks.iter().enumerate().map(|(idx, (id, _, _))| (*id, idx)).collect();
+        let mut suppressed = vec![false; leaks.len()];
+        let mut todo: Vec<usize> = leaks
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, _, alloc))| {
+                alloc.extra.backtrace.as_deref().is_some_and(|bt| self.matches(ecx, bt))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        // Whatever an allowed leak points to is leaked as a consequence, e.g. the heap buffers
+        // owned by a value passed to `Box::leak`.
+        while let Some(idx) = todo.pop() {
+            if std::mem::replace(&mut suppressed[idx], true) {
+                continue;
+            }
+            let (_, _, alloc) = &leaks[idx];
+            todo.extend(
+                alloc
+                    .provenance()
+                    .provenances()
+                    .filter_map(|prov| prov.get_alloc_id())
+                    .filter_map(|id| index.get(&id).copied()),
+            );
+        }
+        let mut suppressed = suppressed.into_iter();
+        leaks.retain(|_| !suppressed.next().unwrap());
+        index.len().strict_sub(leaks.len())
+    }
+}
+
+/// All leaked allocations that were allocated at the same backtrace.
+#[derive(Default, Debug)]
+struct LeakSite {
+    allocations: u64,
+    bytes: u64,
+    /// The number of allocations that no other leaked allocation points to. The last pointer to
+    /// them was overwritten or went out of scope somewhere Miri does not keep track of.
+    direct: u64,
+    kinds: FxIndexSet<String>,
+    /// For the allocations that are still pointed to by other leaked allocations: the site at
+    /// which the allocation holding the pointer was allocated and the rendered span of the last
+    /// store of the pointer into it, with the number of pointed-to allocations. The span is
+    /// missing if the pointer was not written by the program, e.g. in a static's initializer.
+    held_by: FxIndexMap<(usize, Option<String>), u64>,
+}
+
+/// The JSON report of `-Zmiri-leak-report`.
+#[derive(Debug)]
+pub struct LeakReport {
+    /// The rendered backtrace of each site, innermost frame first.
+    backtraces: Vec<Vec<String>>,
+    /// The sites, sorted by the number of leaked bytes, largest first.
+    sites: Vec<LeakSite>,
+    suppressed: usize,
+}
+
+impl LeakReport {
+    pub fn new<'tcx>(ecx: &MiriInterpCx<'tcx>, leaks: &[Leak<'tcx>], suppressed: usize) -> Self {
+        let mut sites: FxIndexMap<Vec<(ty::Instance<'tcx>, Span)>, LeakSite> =
+            FxIndexMap::default();
+        let mut site_of: FxHashMap<AllocId, usize> = FxHashMap::default();
+        for (id, kind, alloc) in leaks {
+            let frames = alloc
+                .extra
+                .backtrace
+                .iter()
+                .flatten()
+                .map(|frame| (frame.instance, frame.span))
+                .collect();
+            let entry = sites.entry(frames);
+            site_of.insert(*id, entry.index());
+            let site = entry.or_default();
+            site.allocations = site.allocations.strict_add(1);
+            site.bytes = site.bytes.strict_add(alloc.size().bytes());
+            site.kinds.insert(kind.to_string());
+        }
+
+        // Find out which leaked allocations are still pointed to by other leaked allocations, and
+        // where those pointers were stored.
+        let mut holder_of: FxHashMap<AllocId, (usize, Option<String>)> = FxHashMap::default();
+        for (id, _, alloc) in leaks {
+            let provenances =
+                alloc.provenance().get_range(alloc_range(Size::ZERO, alloc.size()), ecx);
+            for (range, prov) in provenances {
+                let Some(target) = prov.get_alloc_id() else { continue };
+                if target == *id || !site_of.contains_key(&target) {
+                    continue;
+                }
+                holder_of.entry(target).or_insert_with(|| {
+                    let stored_at = alloc
+                        .extra
+                        .stores
+                        .as_ref()
+                        .and_then(|stores| stores.iter(range.start, range.size).next())
+                        .and_then(|(_, span)| *span)
+                        .map(|span| render_span(ecx, span));
+                    (site_of[id], stored_at)
+                });
+            }
+        }
+        for (id, _, _) in leaks {
+            let site = &mut sites[site_of[id]];
+            match holder_of.remove(id) {
+                Some(holder) => {
+                    let count = site.held_by.entry(holder).or_default();
+                    *count = count.strict_add(1);
+                }
+                None => site.direct = site.direct.strict_add(1),
+            }
+        }
+
+        // Sort the sites and renumber the references accordingly.
+        let mut order: Vec<usize> = (0..sites.len()).collect();
+        order.sort_by_key(|&idx| std::cmp::Reverse(sites[idx].bytes));
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+        let mut sites: Vec<_> = sites.into_iter().map(Some).collect();
+        let mut backtraces = Vec::with_capacity(order.len());
+        let mut sorted = Vec::with_capacity(order.len());
+        for &old in &order {
+            let (frames, mut site) = sites[old].take().unwrap();
+            site.held_by = site
+                .held_by
+                .into_iter()
+                .map(|((idx, stored_at), n)| ((new_index[idx], stored_at), n))
+                .collect();
+            let backtrace = frames
+                .into_iter()
+                .map(|(instance, span)| render_frame(ecx, &FrameInfo { instance, span }))
+                .collect();
+            backtraces.push(backtrace);
+            sorted.push(site);
+        }
+        LeakReport { backtraces, sites: sorted, suppressed }
+    }
+
+    /// The number of distinct allocation sites.
+    pub fn site_count(&self) -> usize {
+        self.sites.len()
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut out, &self.json())?;
+        writeln!(out)?;
+        out.flush()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let allocations: u64 = self.sites.iter().map(|site| site.allocations).sum();
+        let bytes: u64 = self.sites.iter().map(|site| site.bytes).sum();
+        let sites = iter::zip(&self.sites, &self.backtraces)
+            .enumerate()
+            .map(|(idx, (site, backtrace))| {
+                let held_by = site
+                    .held_by
+                    .iter()
+                    .map(|((site, stored_at), allocations)| {
+                        json!({ "site": site, "stored_at": stored_at, "allocations": allocations })
+                    })
+                    .collect::<Vec<_>>();
+                json!({
+                    "site": idx,
+                    "allocations": site.allocations,
+                    "bytes": site.bytes,
+                    "direct_allocations": site.direct,
+                    "kinds": site.kinds.iter().collect::<Vec<_>>(),
+                    "backtrace": backtrace,
+                    "held_by": held_by,
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "leaked_allocations": allocations,
+            "leaked_bytes": bytes,
+            "suppressed_allocations": self.suppressed,
+            "sites": sites,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_by() {
+        let site = |allocations, bytes, direct, held_by: &[(usize, Option<&str>, u64)]| {
+            LeakSite {
+                allocations,
+                bytes,
+                direct,
+                kinds: ["Rust heap".to_owned()].into_iter().collect(),
+                held_by: held_by
+                    .iter()
+                    .map(|&(site, stored_at, n)| ((site, stored_at.map(str::to_owned)), n))
+                    .collect(),
+            }
+        };
+        // A forgotten linked list: the head was allocated in line 2, the two other nodes in line 3,
+        // and each node was linked to the next one in line 4.
+        let link = Some("main.rs:4:9: 4:30");
+        let report = LeakReport {
+            backtraces: vec![
+                vec!["main (main.rs:3:5: 3:20)".to_owned()],
+                vec!["main (main.rs:2:5: 2:20)".to_owned()],
+            ],
+            sites: vec![site(2, 32, 0, &[(0, link, 1), (1, link, 1)]), site(1, 16, 1, &[])],
+            suppressed: 4,
+        };
+        let json = report.json();
+        assert_eq!(json["leaked_allocations"], 3);
+        assert_eq!(json["leaked_bytes"], 48);
+        assert_eq!(json["suppressed_allocations"], 4);
+        assert_eq!(
+            json["sites"][0]["held_by"],
+            json!([
+                { "site": 0, "stored_at": "main.rs:4:9: 4:30", "allocations": 1 },
+                { "site": 1, "stored_at": "main.rs:4:9: 4:30", "allocations": 1 },
+            ])
+        );
+        assert_eq!(json["sites"][1]["direct_allocations"], 1);
+        assert_eq!(json["sites"][1]["backtrace"], json!(["main (main.rs:2:5: 2:20)"]));
+    }
+}
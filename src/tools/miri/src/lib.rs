@@ -85,9 +85,13 @@
 mod borrow_tracker;
 mod clock;
 mod concurrency;
+mod coverage;
 mod data_structures;
 mod diagnostics;
 mod eval;
+mod gdb_stub;
+mod heap_profile;
+mod leak_report;
 mod helpers;
 mod intrinsics;
 mod machine;
@@ -154,6 +158,7 @@ pub mod native_lib {
     EvalContextExt as _, NonHaltingDiagnostic, TerminationInfo, report_result,
 };
 pub use crate::eval::{MiriConfig, MiriEntryFnType, create_ecx, entry_fn, eval_entry};
+pub use crate::gdb_stub::EvalContextExt as _;
 pub use crate::helpers::{EvalContextExt as _, ToU64 as _, ToUsize as _};
 pub use crate::intrinsics::EvalContextExt as _;
 pub use crate::machine::{
@@ -170,11 +175,13 @@ pub mod native_lib {
 pub use crate::shims::io_error::{EvalContextExt as _, IoError, LibcError};
 pub use crate::shims::os_str::EvalContextExt as _;
 pub use crate::shims::panic::EvalContextExt as _;
+pub use crate::shims::process::EvalContextExt as _;
 pub use crate::shims::readiness::{
     EvalContextExt as _, Readiness, ReadinessInterest, ReadinessUpdateFlags, ReadinessWatched,
     ReadinessWatcher,
 };
 pub use crate::shims::sig::EvalContextExt as _;
+pub use crate::shims::signal::EvalContextExt as _;
 pub use crate::shims::time::EvalContextExt as _;
 pub use crate::shims::tls::TlsData;
 pub use crate::shims::unwind::{CatchUnwindData, EvalContextExt as _};
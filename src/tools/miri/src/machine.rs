@@ -40,8 +40,12 @@
 use crate::concurrency::data_race::{self, NaReadType, NaWriteType};
 use crate::concurrency::sync::SyncObj;
 use crate::concurrency::{
-    AllocDataRaceHandler, GenmcCtx, GenmcEvalContextExt as _, GlobalDataRaceHandler, weak_memory,
+    AllocDataRaceHandler, ExecutionTrace, GenmcCtx, GenmcEvalContextExt as _,
+    GlobalDataRaceHandler, weak_memory,
 };
+use crate::coverage::CoverageCounters;
+use crate::gdb_stub::GdbStub;
+use crate::heap_profile::HeapProfiler;
 use crate::helpers::is_no_core;
 use crate::shims::readiness::DelayedReadinessUpdates;
 use crate::*;
@@ -140,6 +144,10 @@ pub struct FrameExtra<'tcx> {
     /// we stop unwinding, use the `CatchUnwindData` to handle catching.
     pub catch_unwind: Option<CatchUnwindData<'tcx>>,
 
+    /// If this is Some(), then this is the frame of a signal handler. When this frame is popped,
+    /// we restore the signal mask the thread had before the signal was delivered.
+    pub signal_handler: Option<shims::signal::SignalHandlerFrame<'tcx>>,
+
     /// If `measureme` profiling is enabled, holds timing information
     /// for the start of this frame. When we finish executing this frame,
     /// we use this to register a completed event with `measureme`.
@@ -157,11 +165,18 @@ pub struct FrameExtra<'tcx> {
 impl<'tcx> std::fmt::Debug for FrameExtra<'tcx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Omitting `timing`, it does not support `Debug`.
-        let FrameExtra { borrow_tracker, catch_unwind, timing: _, user_relevance, data_race } =
-            self;
+        let FrameExtra {
+            borrow_tracker,
+            catch_unwind,
+            signal_handler,
+            timing: _,
+            user_relevance,
+            data_race,
+        } = self;
         f.debug_struct("FrameData")
             .field("borrow_tracker", borrow_tracker)
             .field("catch_unwind", catch_unwind)
+            .field("signal_handler", signal_handler)
             .field("user_relevance", user_relevance)
             .field("data_race", data_race)
             .finish()
@@ -170,10 +185,17 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 
 impl VisitProvenance for FrameExtra<'_> {
     fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
-        let FrameExtra { catch_unwind, borrow_tracker, timing: _, user_relevance: _, data_race: _ } =
-            self;
+        let FrameExtra {
+            catch_unwind,
+            signal_handler,
+            borrow_tracker,
+            timing: _,
+            user_relevance: _,
+            data_race: _,
+        } = self;
 
         catch_unwind.visit_provenance(visit);
+        signal_handler.visit_provenance(visit);
         borrow_tracker.visit_provenance(visit);
     }
 }
@@ -210,6 +232,8 @@ pub enum MiriMemoryKind {
     Mmap,
     /// Memory allocated for `getaddrinfo` result.
     SocketAddress,
+    /// The rings of an io_uring instance, shared between the program and Miri.
+    IoUring,
 }
 
 impl From<MiriMemoryKind> for MemoryKind {
@@ -225,18 +249,28 @@ fn may_leak(self) -> bool {
         use self::MiriMemoryKind::*;
         match self {
             Rust | Miri | C | WinHeap | WinLocal | Runtime => false,
-            Machine | Global | ExternStatic | Tls | Mmap | SocketAddress => true,
+            Machine | Global | ExternStatic | Tls | Mmap | SocketAddress | IoUring => true,
         }
     }
 }
 
 impl MiriMemoryKind {
+    /// Whether this memory was allocated by the program via a heap allocator.
+    fn is_heap(self) -> bool {
+        use self::MiriMemoryKind::*;
+        match self {
+            Rust | Miri | C | WinHeap | WinLocal => true,
+            Machine | Runtime | Global | ExternStatic | Tls | Mmap | SocketAddress | IoUring =>
+                false,
+        }
+    }
+
     /// Whether we have a useful allocation span for an allocation of this kind.
     fn should_save_allocation_span(self) -> bool {
         use self::MiriMemoryKind::*;
         match self {
             // Heap allocations are fine since the `Allocation` is created immediately.
-            Rust | Miri | C | WinHeap | WinLocal | Mmap => true,
+            Rust | Miri | C | WinHeap | WinLocal | Mmap | IoUring => true,
             // Everything else is unclear, let's not show potentially confusing spans.
             Machine | Global | ExternStatic | Tls | Runtime | SocketAddress => false,
         }
@@ -259,6 +293,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             Tls => write!(f, "thread-local static"),
             Mmap => write!(f, "mmap"),
             SocketAddress => write!(f, "socket address"),
+            IoUring => write!(f, "io_uring ring"),
         }
     }
 }
@@ -398,6 +433,10 @@ pub struct AllocExtra<'tcx> {
     /// freed.
     /// This maps offsets to synchronization-primitive-specific data.
     pub sync_objs: BTreeMap<Size, Box<dyn SyncObj>>,
+    /// Where each byte of this allocation was last written, for `-Zmiri-leak-report` to tell where
+    /// the pointers keeping leaked allocations alive were stored. Like `backtrace`, this only
+    /// exists if this allocation is leakable.
+    pub stores: Option<DedupRangeMap<Option<Span>>>,
 }
 
 // We need a `Clone` impl because the machine passes `Allocation` through `Cow`...
@@ -410,7 +449,7 @@ fn clone(&self) -> Self {
 
 impl VisitProvenance for AllocExtra<'_> {
     fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
-        let AllocExtra { borrow_tracker, data_race, backtrace: _, sync_objs: _ } = self;
+        let AllocExtra { borrow_tracker, data_race, backtrace: _, sync_objs: _, stores: _ } = self;
 
         borrow_tracker.visit_provenance(visit);
         data_race.visit_provenance(visit);
@@ -534,6 +573,12 @@ pub struct MiriMachine<'tcx> {
     pub(crate) fds: shims::FdTable,
     /// The table of directory descriptors.
     pub(crate) dirs: shims::DirTable,
+    /// The in-memory file system used instead of the host file system, if enabled.
+    pub(crate) vfs: Option<shims::VirtualFs>,
+    /// The installed signal actions, and the signal masks and pending signals.
+    pub(crate) signals: shims::signal::SignalState,
+    /// The child processes spawned by the program.
+    pub(crate) child_processes: shims::process::ChildProcesses,
 
     /// Managing file descriptors whose readiness needs to be updated.
     pub(crate) delayed_readiness_updates: Rc<DelayedReadinessUpdates>,
@@ -587,6 +632,8 @@ pub struct MiriMachine<'tcx> {
     /// The random number generator used for resolving non-determinism.
     /// Needs to be queried by ptr_to_int, hence needs interior mutability.
     pub(crate) rng: RefCell<StdRng>,
+    /// The trace the nondeterministic decisions are recorded to or replayed from, if any.
+    pub(crate) trace: Option<RefCell<ExecutionTrace>>,
 
     /// The allocator used for the machine's `AllocBytes` in native-libs mode.
     pub(crate) allocator: Option<Rc<RefCell<crate::alloc::isolated_alloc::IsolatedAlloc>>>,
@@ -635,6 +682,14 @@ pub struct MiriMachine<'tcx> {
 
     /// Whether to collect a backtrace when each allocation is created, just in case it leaks.
     pub(crate) collect_leak_backtraces: bool,
+    /// Whether to record where each leakable allocation was last written, for the leak report.
+    pub(crate) record_stores: bool,
+    /// The execution counts for `-Zmiri-coverage`, if enabled.
+    pub(crate) coverage: Option<CoverageCounters>,
+    /// The heap profile for `-Zmiri-heap-profile`, if enabled.
+    pub(crate) heap_profiler: Option<RefCell<HeapProfiler<'tcx>>>,
+    /// The connection to the debugger for `-Zmiri-gdb-server`, if enabled.
+    pub(crate) gdb: Option<Box<GdbStub<'tcx>>>,
 
     /// The spans we will use to report where an allocation was created and deallocated in
     /// diagnostics.
@@ -670,6 +725,11 @@ pub struct MiriMachine<'tcx> {
 
     /// Whether Miri artificially introduces short reads/writes on file descriptors.
     pub short_fd_operations: bool,
+    /// Whether the completions of io_uring operations that complete together are posted in random
+    /// order.
+    pub io_uring_shuffle_completions: bool,
+    /// The rings of all io_uring instances.
+    pub io_uring_regions: shims::IoUringRegions,
 }
 
 impl<'tcx> MiriMachine<'tcx> {
@@ -753,6 +813,24 @@ pub(crate) fn new(
             .expect("Couldn't create poll instance");
         let alloc_addresses =
             RefCell::new(alloc_addresses::GlobalStateInner::new(config, stack_addr, tcx));
+        let trace = if let Some(path) = &config.record_trace {
+            Some(ExecutionTrace::record(path).unwrap_or_else(|err| {
+                let path = path.display();
+                tcx.dcx().fatal(format!("failed to create trace file {path}: {err}"))
+            }))
+        } else if let Some(path) = &config.replay_trace {
+            Some(ExecutionTrace::replay(path).unwrap_or_else(|err| {
+                let path = path.display();
+                tcx.dcx().fatal(format!("failed to read trace file {path}: {err}"))
+            }))
+        } else {
+            None
+        };
+        // Reading the host's clocks is not traced, so they are virtual while tracing.
+        let monotonic_clock = MonotonicClock::new(
+            config.isolated_op == IsolatedOp::Allow,
+            trace.as_ref().map(ExecutionTrace::system_epoch),
+        );
 
         MiriMachine {
             tcx,
@@ -771,6 +849,13 @@ pub(crate) fn new(
             fds: shims::FdTable::init(config.mute_stdout_stderr),
             delayed_readiness_updates: Rc::new(DelayedReadinessUpdates::default()),
             dirs: Default::default(),
+            vfs: config.virtual_fs.then(|| {
+                shims::VirtualFs::new(config.virtual_fs_seed.as_deref()).unwrap_or_else(|err| {
+                    tcx.dcx().fatal(format!("failed to seed the virtual file system: {err}"))
+                })
+            }),
+            signals: Default::default(),
+            child_processes: shims::process::ChildProcesses::new(config.invocation.clone()),
             layouts,
             threads,
             thread_cpu_affinity,
@@ -785,6 +870,7 @@ pub(crate) fn new(
             extern_statics_imports: FxHashMap::default(),
             extern_static_weak_import_default: None,
             rng: RefCell::new(rng),
+            trace: trace.map(RefCell::new),
             allocator: (!config.native_lib.is_empty())
                 .then(|| Rc::new(RefCell::new(crate::alloc::isolated_alloc::IsolatedAlloc::new()))),
             tracked_alloc_ids: config.tracked_alloc_ids.clone(),
@@ -794,7 +880,7 @@ pub(crate) fn new(
             preemption_rate: config.preemption_rate,
             report_progress: config.report_progress,
             basic_block_count: 0,
-            monotonic_clock: MonotonicClock::new(config.isolated_op == IsolatedOp::Allow),
+            monotonic_clock,
             #[cfg(all(feature = "native-lib", unix))]
             native_lib: config.native_lib.iter().map(|lib_file_path| {
                 let host_triple = rustc_session::config::host_tuple();
@@ -830,6 +916,15 @@ pub(crate) fn new(
             stack_addr,
             stack_size,
             collect_leak_backtraces: config.collect_leak_backtraces,
+            record_stores: config.leak_report.is_some(),
+            coverage: config.coverage.is_some().then(Default::default),
+            heap_profiler: config.heap_profile.is_some().then(Default::default),
+            gdb: config.gdb_server.as_ref().map(|addr| {
+                let gdb = GdbStub::listen(addr).unwrap_or_else(|err| {
+                    tcx.dcx().fatal(format!("failed to accept a debugger on {addr}: {err}"))
+                });
+                Box::new(gdb)
+            }),
             allocation_spans: RefCell::new(FxHashMap::default()),
             symbolic_alignment: RefCell::new(FxHashMap::default()),
             union_data_ranges: FxHashMap::default(),
@@ -841,6 +936,8 @@ pub(crate) fn new(
             float_nondet: config.float_nondet,
             float_rounding_error: config.float_rounding_error,
             short_fd_operations: config.short_fd_operations,
+            io_uring_shuffle_completions: config.io_uring_shuffle_completions,
+            io_uring_regions: Default::default(),
         }
     }
 
@@ -991,6 +1088,15 @@ fn init_allocation(
         } else {
             Some(ecx.generate_stacktrace())
         };
+        let stores =
+            (!kind.may_leak() && ecx.machine.record_stores).then(|| DedupRangeMap::new(size, None));
+
+        if let Some(heap_profiler) = &ecx.machine.heap_profiler
+            && matches!(kind, MemoryKind::Machine(kind) if kind.is_heap())
+        {
+            let now = ecx.machine.basic_block_count;
+            heap_profiler.borrow_mut().allocate(id, size, ecx.active_thread_stack(), *ecx.tcx, now);
+        }
 
         if matches!(kind, MemoryKind::Machine(kind) if kind.should_save_allocation_span()) {
             ecx.machine
@@ -1004,6 +1110,7 @@ fn init_allocation(
             data_race,
             backtrace,
             sync_objs: BTreeMap::default(),
+            stores,
         })
     }
 }
@@ -1024,12 +1131,15 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             extern_statics_imports,
             extern_static_weak_import_default,
             dirs,
+            signals,
             borrow_tracker,
             data_race,
             alloc_addresses,
             fds,
             blocking_io:_,
+            child_processes: _,
             delayed_readiness_updates: _,
+            vfs: _,
             tcx: _,
             isolated_op: _,
             validation: _,
@@ -1042,6 +1152,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             backtrace_style: _,
             user_relevant_crates: _,
             rng: _,
+            trace: _,
             allocator: _,
             tracked_alloc_ids: _,
             track_alloc_accesses: _,
@@ -1060,6 +1171,10 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             stack_addr: _,
             stack_size: _,
             collect_leak_backtraces: _,
+            record_stores: _,
+            coverage: _,
+            heap_profiler: _,
+            gdb: _,
             allocation_spans: _,
             symbolic_alignment: _,
             union_data_ranges: _,
@@ -1071,13 +1186,17 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             float_nondet: _,
             float_rounding_error: _,
             short_fd_operations: _,
+            io_uring_shuffle_completions: _,
+            io_uring_regions,
         } = self;
 
         threads.visit_provenance(visit);
         tls.visit_provenance(visit);
         env_vars.visit_provenance(visit);
         dirs.visit_provenance(visit);
+        signals.visit_provenance(visit);
         fds.visit_provenance(visit);
+        io_uring_regions.visit_provenance(visit);
         data_race.visit_provenance(visit);
         borrow_tracker.visit_provenance(visit);
         alloc_addresses.visit_provenance(visit);
@@ -1400,7 +1519,8 @@ fn atomic_fence(
         singlethread: bool,
     ) -> InterpResult<'tcx> {
         if singlethread {
-            // We don't support signal handlers or interrupts so this is a NOP.
+            // Signal handlers run in the thread they interrupt, and the data race detector treats
+            // them like any other code of that thread, so this is a NOP.
             return interp_ok(());
         }
         ecx.atomic_fence(AtomicFenceOrd::from(ordering))
@@ -1688,6 +1808,10 @@ fn before_memory_read(
                 borrow_tracker::AccessKind::Read,
             ));
         }
+        if let Some(heap_profiler) = &machine.heap_profiler {
+            let kind = borrow_tracker::AccessKind::Read;
+            heap_profiler.borrow_mut().access(alloc_id, range.size, kind);
+        }
         // The order of checks is deliberate, to prefer reporting a data race over a borrow tracker error.
         match &machine.data_race {
             GlobalDataRaceHandler::None => {}
@@ -1729,6 +1853,15 @@ fn before_memory_write(
                 borrow_tracker::AccessKind::Write,
             ));
         }
+        if let Some(heap_profiler) = &mut machine.heap_profiler {
+            heap_profiler.get_mut().access(alloc_id, range.size, borrow_tracker::AccessKind::Write);
+        }
+        if let Some(stores) = &mut alloc_extra.stores {
+            let span = machine.current_user_relevant_span();
+            for (_offset, store) in stores.iter_mut(range.start, range.size) {
+                *store = Some(span);
+            }
+        }
         match &machine.data_race {
             GlobalDataRaceHandler::None => {}
             GlobalDataRaceHandler::Genmc(genmc_ctx) =>
@@ -1781,6 +1914,9 @@ fn before_memory_deallocation(
         if machine.tracked_alloc_ids.contains(&alloc_id) {
             machine.emit_diagnostic(NonHaltingDiagnostic::FreedAlloc(alloc_id));
         }
+        if let Some(heap_profiler) = &mut machine.heap_profiler {
+            heap_profiler.get_mut().deallocate(alloc_id, machine.basic_block_count);
+        }
         match &machine.data_race {
             GlobalDataRaceHandler::None => {}
             GlobalDataRaceHandler::Genmc(genmc_ctx) =>
@@ -1881,6 +2017,7 @@ fn init_frame(
         let extra = FrameExtra {
             borrow_tracker: borrow_tracker.map(|bt| bt.borrow_mut().new_frame()),
             catch_unwind: None,
+            signal_handler: None,
             timing,
             user_relevance: ecx.machine.user_relevance(&frame),
             data_race: ecx
@@ -1908,6 +2045,10 @@ fn stack_mut<'a>(
     fn before_terminator(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
         ecx.machine.basic_block_count += 1u64; // a u64 that is only incremented by 1 will "never" overflow
         ecx.machine.since_gc += 1;
+        if let Some(coverage) = &mut ecx.machine.coverage {
+            let frame = ecx.machine.threads.active_thread_stack().last().unwrap();
+            coverage.enter_block(frame);
+        }
         // Possibly report our progress. This will point at the terminator we are about to execute.
         if let Some(report_progress) = ecx.machine.report_progress {
             if ecx.machine.basic_block_count.is_multiple_of(u64::from(report_progress)) {
@@ -1986,7 +2127,9 @@ fn after_stack_pop(
             // Move `frame` into a sub-scope so we control when it will be dropped.
             let mut frame = frame;
             let timing = frame.extra.timing.take();
-            let res = ecx.handle_stack_pop_unwind(frame.extra, unwinding);
+            let res = ecx
+                .return_from_signal_handler(frame.extra.signal_handler.take())
+                .and_then(|()| ecx.handle_stack_pop_unwind(frame.extra, unwinding));
             if let Some(profiler) = ecx.machine.profiler.as_ref() {
                 profiler.finish_recording_interval_event(timing.unwrap());
             }
@@ -9,6 +9,7 @@
 
 use rustc_abi::Size;
 
+use crate::concurrency::HostValue;
 use crate::shims::unix::UnixFileDescription;
 use crate::*;
 
@@ -100,7 +101,8 @@ pub fn is_closed(&self) -> bool {
 
 impl<T> VisitProvenance for FileDescriptionRef<T> {
     fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {
-        // All our FileDescription instances do not have any provenance.
+        // File descriptions only hold provenance for in-flight operations, which the `FdTable`
+        // visits.
     }
 }
 
@@ -225,10 +227,21 @@ fn readiness_watched(&self) -> Option<&ReadinessWatched> {
     fn readiness(&self) -> Readiness {
         panic!("FD type {} implements `readiness_watched` but not `readiness`", self.name());
     }
+
+    /// Called once a deadline registered with `DelayedReadinessUpdates::add_deadline` has been
+    /// reached, right before the readiness of the file description gets updated. Returns the
+    /// next deadline to register, if any.
+    fn deadline_reached(&self, _now: &Instant) -> Option<Instant> {
+        panic!("FD type {} registered a deadline but does not handle it", self.name());
+    }
+
+    /// Visits the provenance of the pointers held by operations that were started on this file
+    /// description but did not complete yet. Most file descriptions do not hold any pointers.
+    fn visit_in_flight_provenance(&self, _visit: &mut VisitWith<'_>) {}
 }
 
 #[derive(Debug)]
-struct Stdin {
+pub struct Stdin {
     stdin: io::Stdin,
     watched: ReadinessWatched,
 }
@@ -258,7 +271,7 @@ fn read<'tcx>(
         }
 
         // FIXME: this can block on the host, halting the entire interpreter.
-        let result = ecx.read_from_host(|buf| (&mut &self.stdin).read(buf), len, ptr)?;
+        let result = ecx.read_from_host_traced(|buf| (&mut &self.stdin).read(buf), len, ptr)?;
         finish.call(ecx, result)
     }
 
@@ -281,7 +294,7 @@ fn readiness(&self) -> Readiness {
 }
 
 #[derive(Debug)]
-struct Stdout {
+pub struct Stdout {
     stdout: io::Stdout,
     watched: ReadinessWatched,
 }
@@ -334,7 +347,7 @@ fn readiness(&self) -> Readiness {
 }
 
 #[derive(Debug)]
-struct Stderr {
+pub struct Stderr {
     stderr: io::Stderr,
     watched: ReadinessWatched,
 }
@@ -448,7 +461,7 @@ fn read<'tcx>(
         }
 
         let mut file = &self.file;
-        let result = ecx.read_from_host(|buf| file.read(buf), len, ptr)?;
+        let result = ecx.read_from_host_traced(|buf| file.read(buf), len, ptr)?;
         finish.call(ecx, result)
     }
 
@@ -471,7 +484,7 @@ fn write<'tcx>(
             // thing.
             return finish.call(ecx, Err(ErrorKind::PermissionDenied.into()));
         }
-        let result = ecx.write_to_host(&self.file, len, ptr)?;
+        let result = ecx.write_to_host_traced(&self.file, len, ptr)?;
         finish.call(ecx, result)
     }
 
@@ -540,8 +553,10 @@ pub struct FdTable {
 }
 
 impl VisitProvenance for FdTable {
-    fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {
-        // All our FileDescription instances do not have any provenance.
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        for fd in self.fds.values() {
+            fd.visit_in_flight_provenance(visit);
+        }
     }
 }
 
@@ -656,4 +671,71 @@ fn write_to_host(
         let result = file.write(bytes);
         interp_ok(result.map_err(IoError::HostError))
     }
+
+    /// Like `read_from_host`, for reads whose result depends on the host. When an execution trace
+    /// is recorded, the data that was read is recorded; when a trace is replayed, the recorded
+    /// data is used instead and `read_cb` is not called.
+    fn read_from_host_traced(
+        &mut self,
+        read_cb: impl FnMut(&mut [u8]) -> io::Result<usize>,
+        len: usize,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+        let Some(trace) = &this.machine.trace else {
+            return this.read_from_host(read_cb, len, ptr);
+        };
+
+        let mut bytes = vec![0; len];
+        let result = trace.borrow_mut().host_read(&mut bytes, read_cb)?;
+        match result {
+            Ok(read_size) => {
+                this.write_bytes_ptr(ptr, bytes[..read_size].iter().copied())?;
+                interp_ok(Ok(read_size))
+            }
+            Err(e) => interp_ok(Err(IoError::HostError(e))),
+        }
+    }
+
+    /// Like `write_to_host`, for writes whose result depends on the host. When an execution trace
+    /// is recorded, the result of the write is recorded; when a trace is replayed, the recorded
+    /// result is used instead and nothing is written to `file`.
+    fn write_to_host_traced(
+        &mut self,
+        mut file: impl io::Write,
+        len: usize,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+        let Some(trace) = &this.machine.trace else {
+            return this.write_to_host(file, len, ptr);
+        };
+
+        let bytes = this.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = trace.borrow_mut().host_write(bytes, |bytes| file.write(bytes))?;
+        interp_ok(result.map_err(IoError::HostError))
+    }
+
+    /// Queries the host with `query_cb`, e.g. for the metadata of a file. When an execution trace
+    /// is recorded, the result is recorded under the name `query`; when a trace is replayed, the
+    /// recorded result is used instead and `query_cb` is not called.
+    fn query_host_traced<T: HostValue>(
+        &mut self,
+        query: &'static str,
+        query_cb: impl FnOnce(&mut MiriInterpCx<'tcx>) -> InterpResult<'tcx, T>,
+    ) -> InterpResult<'tcx, T> {
+        let this = self.eval_context_mut();
+        let Some(trace) = &this.machine.trace else {
+            return query_cb(this);
+        };
+
+        if let Some(value) = trace.borrow_mut().replay_host_value(query)? {
+            return interp_ok(value);
+        }
+        let value = query_cb(this)?;
+        if let Some(trace) = &this.machine.trace {
+            trace.borrow_mut().record_host_value(query, &value);
+        }
+        interp_ok(value)
+    }
 }
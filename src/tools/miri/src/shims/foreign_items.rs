@@ -52,6 +52,8 @@ fn emulate_foreign_item(
     ) -> InterpResult<'tcx, Option<(&'tcx mir::Body<'tcx>, ty::Instance<'tcx>)>> {
         let this = self.eval_context_mut();
 
+        this.check_async_signal_safety(link_name)?;
+
         // Handle allocator shim.
         if let Some(shim) = this.machine.allocator_shim_symbols.get(&link_name) {
             match *shim {
@@ -74,7 +76,8 @@ fn emulate_foreign_item(
 
         // The rest either implements the logic, or falls back to `lookup_exported_symbol`.
         let res = this.emulate_foreign_item_inner(link_name, abi, args, &dest)?;
-        res.jump_to_next_block(this, &dest.clone().into(), ret, Some(unwind), |this| {
+        let returns = matches!(res, EmulateItemResult::NeedsReturn);
+        let body = res.jump_to_next_block(this, &dest.clone().into(), ret, Some(unwind), |this| {
             if let Some(body) = this.lookup_exported_fn(link_name)? {
                 return interp_ok(Some(body));
             }
@@ -83,7 +86,12 @@ fn emulate_foreign_item(
                 "can't call foreign function `{link_name}` on OS `{os}`",
                 os = this.tcx.sess.target.os,
             )));
-        })
+        })?;
+        // Returning from a shim is an interruption point, like returning from a syscall.
+        if returns {
+            this.deliver_pending_signal()?;
+        }
+        interp_ok(body)
     }
 
     fn is_dyn_sym(&self, name: &str) -> bool {
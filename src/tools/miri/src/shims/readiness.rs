@@ -2,6 +2,7 @@
 use std::cell::RefCell;
 use std::collections::{BTreeMap, VecDeque};
 use std::rc::{Rc, Weak};
+use std::time::Duration;
 
 use crate::concurrency::VClock;
 use crate::shims::files::{DynFileDescriptionRef, FdNum, WeakDynFileDescriptionRef};
@@ -452,9 +453,15 @@ pub fn remove_file_num_interests(&self, fd_id: FdId, fd_num: FdNum) {
 /// If a file description's readiness is known to change but we don't have an `ecx` around to update
 /// it immediately, we arange for a referene to the delayed readiness updates queue to be available
 /// and perform the update on the next scheduler call.
+///
+/// File descriptions whose readiness changes once some point in time is reached (e.g. timers) can
+/// also register a deadline here; they get notified with `FileDescription::deadline_reached` by the
+/// first scheduler call after the deadline.
 #[derive(Default, Debug)]
 pub struct DelayedReadinessUpdates {
     to_update: RefCell<Vec<DynFileDescriptionRef>>,
+    /// At most one deadline per file description.
+    deadlines: RefCell<Vec<(Instant, FdId, WeakDynFileDescriptionRef)>>,
 }
 
 impl DelayedReadinessUpdates {
@@ -462,7 +469,56 @@ pub fn add(&self, fd: DynFileDescriptionRef) {
         self.to_update.borrow_mut().push(fd);
     }
 
+    /// Arrange for `FileDescription::deadline_reached` to be called on `fd` once `deadline` has
+    /// been reached, replacing the previous deadline of `fd`. Deadlines of closed file descriptions
+    /// are dropped.
+    pub fn add_deadline(&self, fd: DynFileDescriptionRef, deadline: Instant) {
+        let mut deadlines = self.deadlines.borrow_mut();
+        deadlines.retain(|(_, id, _)| *id != fd.id());
+        deadlines.push((deadline, fd.id(), FileDescriptionRef::downgrade(&fd)));
+    }
+
+    /// Returns how long to wait until the next deadline a blocked thread cares about, i.e. the
+    /// next deadline of a file description watched by a blocked thread. Threads blocked on the
+    /// file description itself are expected to block with a deadline of their own.
+    pub fn time_until_next_deadline(&self, clock: &MonotonicClock) -> Option<Duration> {
+        let now = clock.now();
+        self.deadlines
+            .borrow()
+            .iter()
+            .filter(|(_, _, fd)| {
+                fd.upgrade().is_some_and(|fd| {
+                    fd.readiness_watched().is_some_and(|w| w.has_watcher_with_blocked_thread())
+                })
+            })
+            .map(|(deadline, _, _)| deadline.duration_since(now.clone()))
+            .min()
+    }
+
+    /// Notify the file descriptions whose deadline has been reached, and update their readiness.
+    fn process_deadlines<'tcx>(ecx: &mut MiriInterpCx<'tcx>) -> InterpResult<'tcx> {
+        let now = ecx.machine.monotonic_clock.now();
+        let reached = ecx
+            .machine
+            .delayed_readiness_updates
+            .deadlines
+            .borrow_mut()
+            .extract_if(.., |(deadline, _, fd)| {
+                fd.is_closed() || deadline.duration_since(now.clone()).is_zero()
+            })
+            .collect::<Vec<_>>();
+        for (_deadline, _id, fd) in reached {
+            let Some(fd) = fd.upgrade() else { continue };
+            if let Some(next) = fd.deadline_reached(&now) {
+                ecx.machine.delayed_readiness_updates.add_deadline(fd.clone(), next);
+            }
+            ecx.update_fd_readiness(fd, ReadinessUpdateFlags::DEFAULT)?;
+        }
+        interp_ok(())
+    }
+
     pub fn process<'tcx>(ecx: &mut MiriInterpCx<'tcx>) -> InterpResult<'tcx> {
+        Self::process_deadlines(ecx)?;
         loop {
             // Avoid keeping the RefCell open over the `update_fd_readiness` as that can invoke
             // arbitrary code via the unblock callback.
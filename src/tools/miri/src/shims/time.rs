@@ -74,7 +74,7 @@ fn clock_gettime(
         let duration = match this.parse_clockid(clk_id) {
             Some(TimeoutClock::RealTime) => {
                 this.check_no_isolation("`clock_gettime` with `REALTIME` clocks")?;
-                system_time_to_duration(&SystemTime::now())?
+                system_time_to_duration(&this.machine.monotonic_clock.system_time_now())?
             }
             Some(TimeoutClock::Monotonic) =>
                 this.machine
@@ -113,7 +113,7 @@ fn gettimeofday(
             return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
         }
 
-        let duration = system_time_to_duration(&SystemTime::now())?;
+        let duration = system_time_to_duration(&this.machine.monotonic_clock.system_time_now())?;
         let tv_sec = duration.as_secs();
         let tv_usec = duration.subsec_micros();
 
@@ -222,7 +222,8 @@ fn GetSystemTimeAsFileTime(
 
         let filetime = this.deref_pointer_as(LPFILETIME_op, this.windows_ty_layout("FILETIME"))?;
 
-        let duration = this.system_time_since_windows_epoch(&SystemTime::now())?;
+        let duration =
+            this.system_time_since_windows_epoch(&this.machine.monotonic_clock.system_time_now())?;
         let duration_ticks = this.windows_ticks_for(duration)?;
 
         let dwLowDateTime = u32::try_from(duration_ticks & 0x00000000FFFFFFFF).unwrap();
@@ -360,7 +361,7 @@ fn nanosleep(&mut self, duration: &OpTy<'tcx>, rem: &OpTy<'tcx>) -> InterpResult
         this.assert_target_os_is_unix("nanosleep");
 
         let duration = this.deref_pointer_as(duration, this.libc_ty_layout("timespec"))?;
-        let _rem = this.read_pointer(rem)?; // Signal handlers are not supported, so rem will never be written to.
+        let _rem = this.read_pointer(rem)?; // Signals don't interrupt sleeps, so rem will never be written to.
 
         let Some(duration) = this.read_timespec(&duration)? else {
             return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
@@ -394,7 +395,7 @@ fn clock_nanosleep(
         let clock_id = this.read_scalar(clock_id)?.to_int(clockid_t_size)?;
         let timespec = this.deref_pointer_as(timespec, this.libc_ty_layout("timespec"))?;
         let flags = this.read_scalar(flags)?.to_i32()?;
-        let _rem = this.read_pointer(rem)?; // Signal handlers are not supported, so rem will never be written to.
+        let _rem = this.read_pointer(rem)?; // Signals don't interrupt sleeps, so rem will never be written to.
 
         // The standard lib through sleep_until only needs CLOCK_MONOTONIC
         if clock_id != this.eval_libc("CLOCK_MONOTONIC").to_int(clockid_t_size)? {
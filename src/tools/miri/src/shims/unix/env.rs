@@ -9,6 +9,7 @@
 use rustc_target::spec::{Env, Os};
 
 use super::HOSTNAME;
+use crate::shims::files::EvalContextExt as _;
 use crate::*;
 
 pub struct UnixEnvVars<'tcx> {
@@ -200,6 +201,15 @@ fn getcwd(&mut self, buf_op: &OpTy<'tcx>, size_op: &OpTy<'tcx>) -> InterpResult<
         let buf = this.read_pointer(buf_op)?;
         let size = this.read_target_usize(size_op)?;
 
+        if let Some(vfs) = &this.machine.vfs {
+            let cwd = vfs.cwd().to_owned();
+            if this.write_c_str(&cwd, buf, size)?.0 {
+                return interp_ok(buf);
+            }
+            this.set_last_error(LibcError("ERANGE"))?;
+            return interp_ok(Pointer::null());
+        }
+
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`getcwd`", reject_with)?;
             this.set_last_error(ErrorKind::PermissionDenied)?;
@@ -207,7 +217,7 @@ fn getcwd(&mut self, buf_op: &OpTy<'tcx>, size_op: &OpTy<'tcx>) -> InterpResult<
         }
 
         // If we cannot get the current directory, we return null
-        match env::current_dir() {
+        match this.query_host_traced("cwd", |_| interp_ok(env::current_dir()))? {
             Ok(cwd) => {
                 if this.write_path_to_c_str(&cwd, buf, size)?.0 {
                     return interp_ok(buf);
@@ -299,7 +309,16 @@ fn chdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
         this.assert_target_os_is_unix("chdir");
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path_ptr = this.read_pointer(path_op)?;
+        let path = this.read_path_from_c_str(path_ptr)?;
+
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_ptr)?.to_owned();
+            return match this.machine.vfs.as_mut().unwrap().chdir(&path) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_errno_and_return_neg1_i32(err),
+            };
+        }
 
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`chdir`", reject_with)?;
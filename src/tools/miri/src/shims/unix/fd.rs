@@ -11,6 +11,7 @@
 use crate::shims::FileDescriptionRef;
 use crate::shims::files::{DynFileDescriptionRef, FdNum, FileDescription};
 use crate::shims::sig::check_min_vararg_count;
+use crate::shims::unix::linux_like::io_uring::EvalContextExt as _;
 use crate::shims::unix::socket::UnixSocketFileDescription;
 use crate::shims::unix::*;
 use crate::*;
@@ -111,6 +112,8 @@ fn close(&mut self, fd_num: FdNum) -> InterpResult<'tcx, Scalar> {
             }
         }
         drop(fd);
+        // If this closed an io_uring instance, its rings may not be needed anymore.
+        this.io_uring_free_regions()?;
         // Our close is always successful. Close does not reliably return errors anyway so it is
         // not worth the effort to try and return anything here.
         interp_ok(Scalar::from_i32(0))
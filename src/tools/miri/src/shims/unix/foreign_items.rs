@@ -34,6 +34,8 @@ pub fn is_dyn_sym(name: &str, target_os: &Os) -> bool {
         // where std uses them via `weak!` since bionic only gained them in API level 24), so
         // we allow them here too. They do not exist on Solaris.
         "preadv" | "pwritev" if !matches!(*target_os, Os::Solaris) => true,
+        // std looks this up with `weak!` to set the working directory of a spawned process.
+        "posix_spawn_file_actions_addchdir_np" => true,
         // Give specific OSes a chance to allow their symbols.
         _ =>
             match *target_os {
@@ -1318,6 +1320,158 @@ fn emulate_foreign_item_inner(
                 }
             }
 
+            // Signals
+            "sigaction" => {
+                let [sig, act, oldact] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigaction(sig, act, oldact)?;
+                this.write_scalar(result, dest)?;
+            }
+            "signal" => {
+                let [sig, handler] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.signal(sig, handler)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigemptyset" => {
+                let [set] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigemptyset(set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigfillset" => {
+                let [set] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigfillset(set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigaddset" | "sigdelset" => {
+                let [set, sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let add = link_name.as_str() == "sigaddset";
+                let result = this.sigaddset_or_sigdelset(set, sig, add)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigismember" => {
+                let [set, sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigismember(set, sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigprocmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigprocmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pthread_sigmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.pthread_sigmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigpending" => {
+                let [set] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigpending(set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "raise" => {
+                let [sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.raise(sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "kill" => {
+                let [pid, sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.kill(pid, sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pthread_kill" => {
+                let [thread, sig] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.pthread_kill(thread, sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pause" => {
+                let [] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.pause(dest)?;
+            }
+            "sigsuspend" => {
+                let [mask] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.sigsuspend(mask, dest)?;
+            }
+            "sigwait" => {
+                let [set, sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.sigwait(set, sig, dest)?;
+            }
+
+            // Child processes
+            "posix_spawn" | "posix_spawnp" => {
+                let [pid, path, file_actions, attrp, argv, envp] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let search_path = link_name.as_str() == "posix_spawnp";
+                let result =
+                    this.posix_spawn(pid, path, file_actions, attrp, argv, envp, search_path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_init" => {
+                let [file_actions] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_init(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_destroy" => {
+                let [file_actions] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_destroy(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_adddup2" => {
+                let [file_actions, fd, newfd] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_adddup2(file_actions, fd, newfd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addclose" => {
+                let [file_actions, fd] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addclose(file_actions, fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addchdir_np" | "posix_spawn_file_actions_addchdir" => {
+                let [file_actions, path] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addchdir(file_actions, path)?;
+                this.write_scalar(result, dest)?;
+            }
+            // The attributes do not affect a child that runs in a new instance of Miri (see
+            // `posix_spawn`), so there is no need to store them.
+            "posix_spawnattr_init" | "posix_spawnattr_destroy" => {
+                let [attr] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.read_pointer(attr)?;
+                this.write_null(dest)?;
+            }
+            "posix_spawnattr_setflags"
+            | "posix_spawnattr_setpgroup"
+            | "posix_spawnattr_setsigdefault"
+            | "posix_spawnattr_setsigmask" => {
+                let [attr, _value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.read_pointer(attr)?;
+                this.write_null(dest)?;
+            }
+            "waitpid" => {
+                let [pid, status, options] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.waitpid(pid, status, options, dest)?;
+            }
+            // A forked child would have to continue interpreting the program in a new process,
+            // which Miri cannot do. `Command` only forks when `posix_spawn` cannot do what it
+            // asks for, e.g. with `pre_exec`.
+            // Out of scope even when followed by `exec*`: until then, the child would have to keep
+            // running a copy of the whole interpreted program.
+            "fork" | "vfork" => {
+                throw_unsup_format!(
+                    "`{link_name}` is not supported: Miri can only run child processes that are \
+                    started with `posix_spawn`"
+                );
+            }
+
             // Miscellaneous
             "isatty" => {
                 let [fd] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
@@ -1472,14 +1626,10 @@ fn emulate_foreign_item_inner(
                 this.write_null(dest)?;
             }
 
-            "signal" | "sigaltstack" if this.frame_in_std() => {
+            "sigaltstack" if this.frame_in_std() => {
                 let [_, _] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
                 this.write_null(dest)?;
             }
-            "sigaction" if this.frame_in_std() => {
-                let [_, _, _] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
-                this.write_null(dest)?;
-            }
 
             "getpwuid_r" | "__posix_getpwuid_r" if this.frame_in_std() => {
                 // getpwuid_r is the standard name, __posix_getpwuid_r is used on solarish
@@ -4,8 +4,10 @@
 use std::ffi::OsString;
 use std::fs::{self, DirBuilder, File, FileTimes, FileType, OpenOptions, TryLockError};
 use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
-use std::path::{self, Path};
-use std::time::SystemTime;
+use std::path::{self, Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use std::vec;
 
 use rustc_abi::{FieldIdx, Size};
 use rustc_data_structures::either::Either;
@@ -13,36 +15,65 @@
 use rustc_target::spec::Os;
 
 use self::shims::time::system_time_to_duration;
-use crate::shims::files::FileHandle;
+use crate::concurrency::{HostValue, encode_hex};
+use crate::shims::files::{EvalContextExt as _, FileHandle};
 use crate::shims::os_str::bytes_to_os_str;
 use crate::shims::sig::check_min_vararg_count;
+use crate::shims::unix::UID;
 use crate::shims::unix::fd::{FlockOp, UnixFileDescription};
+use crate::shims::unix::vfs::{
+    self, VirtualDirEntry, VirtualFile, VirtualFileKind, VirtualFs, VirtualMetadata,
+    VirtualOpenOptions,
+};
 use crate::*;
 
+/// The entries of an open directory.
+#[derive(Debug)]
+enum ReadDir {
+    /// The directory reader on the host.
+    Host(fs::ReadDir),
+    /// A snapshot of a directory of the virtual file system.
+    Virtual(vec::IntoIter<VirtualDirEntry>),
+    /// A snapshot of a directory on the host, taken when an execution trace is recorded or
+    /// replayed.
+    Traced(vec::IntoIter<DirEntry>),
+}
+
+/// An entry of an open directory.
+enum OpenDirEntry {
+    Host(fs::DirEntry),
+    Virtual(VirtualDirEntry),
+    Traced(DirEntry),
+    /// `.` or `..`.
+    Special(&'static str),
+}
+
 /// An open directory, tracked by DirHandler.
 #[derive(Debug)]
 struct OpenDir {
     /// The "special" entries that must still be yielded by the iterator.
     /// Used for `.` and `..`.
     special_entries: Vec<&'static str>,
-    /// The directory reader on the host.
-    read_dir: fs::ReadDir,
+    read_dir: ReadDir,
     /// The most recent entry returned by readdir().
     /// Will be freed by the next call.
     entry: Option<Pointer>,
 }
 
 impl OpenDir {
-    fn new(read_dir: fs::ReadDir) -> Self {
+    fn new(read_dir: ReadDir) -> Self {
         Self { special_entries: vec!["..", "."], read_dir, entry: None }
     }
 
-    fn next_host_entry(&mut self) -> Option<io::Result<Either<fs::DirEntry, &'static str>>> {
+    fn next_entry(&mut self) -> Option<io::Result<OpenDirEntry>> {
         if let Some(special) = self.special_entries.pop() {
-            return Some(Ok(Either::Right(special)));
+            return Some(Ok(OpenDirEntry::Special(special)));
+        }
+        match &mut self.read_dir {
+            ReadDir::Host(read_dir) => Some(read_dir.next()?.map(OpenDirEntry::Host)),
+            ReadDir::Virtual(entries) => Some(Ok(OpenDirEntry::Virtual(entries.next()?))),
+            ReadDir::Traced(entries) => Some(Ok(OpenDirEntry::Traced(entries.next()?))),
         }
-        let entry = self.read_dir.next()?;
-        Some(entry.map(Either::Left))
     }
 }
 
@@ -185,7 +216,7 @@ pub struct DirTable {
 
 impl DirTable {
     #[expect(clippy::arithmetic_side_effects)]
-    fn insert_new(&mut self, read_dir: fs::ReadDir) -> u64 {
+    fn insert_new(&mut self, read_dir: ReadDir) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
         self.streams.try_insert(id, OpenDir::new(read_dir)).unwrap();
@@ -249,7 +280,9 @@ fn parse_utimens_timespec(
             return interp_ok(Some(TimeUpdate::Omit));
         }
         if nsec == this.eval_libc("UTIME_NOW").to_target_isize(this)? {
-            return interp_ok(Some(TimeUpdate::Set(SystemTime::now())));
+            return interp_ok(Some(TimeUpdate::Set(
+                this.machine.monotonic_clock.system_time_now(),
+            )));
         }
 
         let Some(duration) = this.read_timespec(tp)? else {
@@ -354,13 +387,10 @@ fn file_type_to_d_type(&self, file_type: std::io::Result<FileType>) -> InterpRes
         }
     }
 
-    fn dir_entry_fields(
-        &self,
-        entry: Either<fs::DirEntry, &'static str>,
-    ) -> InterpResult<'tcx, DirEntry> {
+    fn dir_entry_fields(&self, entry: OpenDirEntry) -> InterpResult<'tcx, DirEntry> {
         let this = self.eval_context_ref();
         interp_ok(match entry {
-            Either::Left(dir_entry) => {
+            OpenDirEntry::Host(dir_entry) => {
                 DirEntry {
                     name: dir_entry.file_name(),
                     d_type: this.file_type_to_d_type(dir_entry.file_type())?,
@@ -372,7 +402,20 @@ fn dir_entry_fields(
                     ino: 0u64,
                 }
             }
-            Either::Right(special) =>
+            OpenDirEntry::Virtual(dir_entry) => {
+                let d_type = match dir_entry.kind {
+                    VirtualFileKind::File => "DT_REG",
+                    VirtualFileKind::Dir => "DT_DIR",
+                    VirtualFileKind::Symlink => "DT_LNK",
+                };
+                DirEntry {
+                    name: bytes_to_os_str(&dir_entry.name)?.to_owned(),
+                    d_type: this.eval_libc(d_type).to_u8()?.into(),
+                    ino: dir_entry.ino,
+                }
+            }
+            OpenDirEntry::Traced(dir_entry) => dir_entry,
+            OpenDirEntry::Special(special) =>
                 DirEntry {
                     name: special.into(),
                     d_type: this.eval_libc("DT_DIR").to_u8()?.into(),
@@ -381,6 +424,39 @@ fn dir_entry_fields(
         })
     }
 
+    /// Reads all entries of a directory on the host at once.
+    fn read_host_dir(&self, path: &Path) -> InterpResult<'tcx, io::Result<Vec<DirEntry>>> {
+        let read_dir = match fs::read_dir(path) {
+            Ok(read_dir) => read_dir,
+            Err(err) => return interp_ok(Err(err)),
+        };
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            match entry {
+                Ok(entry) => entries.push(self.dir_entry_fields(OpenDirEntry::Host(entry))?),
+                Err(err) => return interp_ok(Err(err)),
+            }
+        }
+        interp_ok(Ok(entries))
+    }
+
+    /// The virtual file system and its current time. Must only be called if it is enabled.
+    fn virtual_fs(&mut self) -> (&mut VirtualFs, Duration) {
+        let this = self.eval_context_mut();
+        let now = vfs::now(&this.machine);
+        (this.machine.vfs.as_mut().expect("the virtual file system is not enabled"), now)
+    }
+
+    /// Returns `0`, or sets the last error and returns `-1`, depending on the result of an
+    /// operation on the virtual file system.
+    fn virtual_fs_result(&mut self, result: Result<(), IoError>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        match result {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(err) => this.set_errno_and_return_neg1_i32(err),
+        }
+    }
+
     #[cfg(unix)]
     fn host_permissions_from_mode(&self, mode: u32) -> InterpResult<'tcx, fs::Permissions> {
         use std::os::unix::fs::PermissionsExt;
@@ -418,6 +494,9 @@ fn open(
         let mut flag = flag;
 
         let mut options = OpenOptions::new();
+        // The same options, in case the virtual file system is used.
+        let mut virtual_options = VirtualOpenOptions::default();
+        let mut append = false;
 
         let o_rdonly = this.eval_libc_i32("O_RDONLY");
         let o_wronly = this.eval_libc_i32("O_WRONLY");
@@ -446,16 +525,20 @@ fn open(
         } else {
             throw_unsup_format!("unsupported access mode {:#x}", access_mode);
         }
+        virtual_options.read = readable;
+        virtual_options.write = writable;
 
         let o_append = this.eval_libc_i32("O_APPEND");
         if flag & o_append == o_append {
             flag &= !o_append;
             options.append(true);
+            append = true;
         }
         let o_trunc = this.eval_libc_i32("O_TRUNC");
         if flag & o_trunc == o_trunc {
             flag &= !o_trunc;
             options.truncate(true);
+            virtual_options.truncate = true;
         }
         let o_creat = this.eval_libc_i32("O_CREAT");
         if flag & o_creat == o_creat {
@@ -465,6 +548,7 @@ fn open(
             // (see https://github.com/rust-lang/rust/issues/71915).
             let [mode] = check_min_vararg_count("open(pathname, O_CREAT, ...)", varargs)?;
             let mode = this.read_scalar(mode)?.to_u32()?;
+            virtual_options.create = Some(mode);
 
             #[cfg(unix)]
             {
@@ -475,7 +559,7 @@ fn open(
             #[cfg(not(unix))]
             {
                 // Only support default mode for non-UNIX (i.e. Windows) host
-                if mode != 0o666 {
+                if mode != 0o666 && this.machine.vfs.is_none() {
                     throw_unsup_format!(
                         "non-default mode 0o{:o} is not supported on non-Unix hosts",
                         mode
@@ -487,6 +571,7 @@ fn open(
             if flag & o_excl == o_excl {
                 flag &= !o_excl;
                 options.create_new(true);
+                virtual_options.exclusive = true;
             } else {
                 options.create(true);
             }
@@ -508,6 +593,7 @@ fn open(
         let o_nofollow = this.eval_libc_i32("O_NOFOLLOW");
         if flag & o_nofollow == o_nofollow {
             flag &= !o_nofollow;
+            virtual_options.nofollow = true;
             #[cfg(unix)]
             {
                 use std::os::unix::fs::OpenOptionsExt;
@@ -520,7 +606,7 @@ fn open(
             {
                 // O_NOFOLLOW only fails when the trailing component is a symlink;
                 // the entire rest of the path can still contain symlinks.
-                if path.is_symlink() {
+                if this.machine.vfs.is_none() && path.is_symlink() {
                     return this.set_errno_and_return_neg1_i32(LibcError("ELOOP"));
                 }
             }
@@ -531,6 +617,19 @@ fn open(
             throw_unsup_format!("unsupported flags {:#x}", flag);
         }
 
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_raw)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let fd = match vfs.open(&path, &virtual_options, now) {
+                Ok(inode) => {
+                    let file = VirtualFile::new(inode, readable, writable, append);
+                    this.machine.fds.insert_new(file)
+                }
+                Err(err) => return this.set_errno_and_return_neg1_i32(err),
+            };
+            return interp_ok(Scalar::from_i32(fd));
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`open`", reject_with)?;
@@ -586,7 +685,15 @@ fn lseek(
     fn unlink(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path_ptr = this.read_pointer(path_op)?;
+        let path = this.read_path_from_c_str(path_ptr)?;
+
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.unlink(&path, now);
+            return this.virtual_fs_result(result);
+        }
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -615,8 +722,18 @@ fn create_link(src: &Path, dst: &Path) -> std::io::Result<()> {
         }
 
         let this = self.eval_context_mut();
-        let target = this.read_path_from_c_str(this.read_pointer(target_op)?)?;
-        let linkpath = this.read_path_from_c_str(this.read_pointer(linkpath_op)?)?;
+        let target_ptr = this.read_pointer(target_op)?;
+        let linkpath_ptr = this.read_pointer(linkpath_op)?;
+        let target = this.read_path_from_c_str(target_ptr)?;
+        let linkpath = this.read_path_from_c_str(linkpath_ptr)?;
+
+        if this.machine.vfs.is_some() {
+            let target = this.read_c_str(target_ptr)?.to_owned();
+            let linkpath = this.read_c_str(linkpath_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.symlink(&target, &linkpath, now);
+            return this.virtual_fs_result(result);
+        }
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -649,7 +766,9 @@ fn linkat(
         let at_fdcwd = this.eval_libc_i32("AT_FDCWD");
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`linkat`", reject_with)?;
             return this.set_errno_and_return_neg1_i32(ErrorKind::PermissionDenied);
         }
@@ -677,6 +796,14 @@ fn linkat(
         }
         let newpath = this.read_path_from_c_str(newpath_ptr)?.into_owned();
 
+        if this.machine.vfs.is_some() {
+            let oldpath = this.read_c_str(oldpath_ptr)?.to_owned();
+            let newpath = this.read_c_str(newpath_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.link(&oldpath, &newpath, now);
+            return this.virtual_fs_result(result);
+        }
+
         let result = fs::hard_link(&oldpath, &newpath).map(|()| 0);
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
     }
@@ -694,6 +821,16 @@ fn stat(&mut self, path_op: &OpTy<'tcx>, buf_op: &OpTy<'tcx>) -> InterpResult<'t
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_scalar)?.to_owned();
+            let metadata = this.virtual_fs().0.metadata(&path, true);
+            let metadata = match FileMetadata::from_virtual(this, metadata)? {
+                Ok(metadata) => metadata,
+                Err(err) => return this.set_errno_and_return_neg1_i32(err),
+            };
+            return interp_ok(Scalar::from_i32(this.write_stat_buf(metadata, buf_op)?));
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`stat`", reject_with)?;
@@ -723,6 +860,16 @@ fn lstat(&mut self, path_op: &OpTy<'tcx>, buf_op: &OpTy<'tcx>) -> InterpResult<'
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_scalar)?.to_owned();
+            let metadata = this.virtual_fs().0.metadata(&path, false);
+            let metadata = match FileMetadata::from_virtual(this, metadata)? {
+                Ok(metadata) => metadata,
+                Err(err) => return this.set_errno_and_return_neg1_i32(err),
+            };
+            return interp_ok(Scalar::from_i32(this.write_stat_buf(metadata, buf_op)?));
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`lstat`", reject_with)?;
@@ -750,7 +897,9 @@ fn fstat(&mut self, fd_op: &OpTy<'tcx>, buf_op: &OpTy<'tcx>) -> InterpResult<'tc
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`fstat`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
@@ -811,7 +960,9 @@ fn linux_statx(
         }
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`statx`", reject_with)?;
             let ecode = if path.is_absolute() || dirfd == this.eval_libc_i32("AT_FDCWD") {
                 // since `path` is provided, either absolute or
@@ -835,6 +986,10 @@ fn linux_statx(
         // represented by dirfd, whether it's a directory or otherwise.
         let metadata = if path.as_os_str().is_empty() && empty_path_flag {
             FileMetadata::from_fd_num(this, dirfd)?
+        } else if this.machine.vfs.is_some() {
+            let path = this.read_c_str(pathname_ptr)?.to_owned();
+            let metadata = this.virtual_fs().0.metadata(&path, follow_symlink);
+            FileMetadata::from_virtual(this, metadata)?
         } else {
             FileMetadata::from_path(this, &path, follow_symlink)?
         };
@@ -962,6 +1117,12 @@ fn chmod(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
         }
         let path = this.read_path_from_c_str(path_ptr)?;
 
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_ptr)?.to_owned();
+            let result = this.virtual_fs().0.chmod(&path, mode.try_into().unwrap());
+            return this.virtual_fs_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`chmod`", reject_with)?;
@@ -985,6 +1146,10 @@ fn fchmod(&mut self, fd_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<'
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         };
+        if let Some(file) = fd.clone().downcast::<VirtualFile>() {
+            file.inode.set_mode(mode.try_into().unwrap());
+            return interp_ok(Scalar::from_i32(0));
+        }
         let Some(file) = fd.downcast::<FileHandle>() else {
             // The docs don't talk about what happens for non-regular files...
             throw_unsup_format!("`fchmod` is only supported on regular files")
@@ -1021,6 +1186,14 @@ fn rename(
         let oldpath = this.read_path_from_c_str(oldpath_ptr)?;
         let newpath = this.read_path_from_c_str(newpath_ptr)?;
 
+        if this.machine.vfs.is_some() {
+            let oldpath = this.read_c_str(oldpath_ptr)?.to_owned();
+            let newpath = this.read_c_str(newpath_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.rename(&oldpath, &newpath, now);
+            return this.virtual_fs_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`rename`", reject_with)?;
@@ -1035,14 +1208,21 @@ fn rename(
     fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        #[cfg_attr(not(unix), allow(unused_variables))]
         let mode = if matches!(&this.tcx.sess.target.os, Os::MacOs | Os::FreeBsd) {
             u32::from(this.read_scalar(mode_op)?.to_u16()?)
         } else {
             this.read_scalar(mode_op)?.to_u32()?
         };
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path_ptr = this.read_pointer(path_op)?;
+        let path = this.read_path_from_c_str(path_ptr)?;
+
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.mkdir(&path, mode, now);
+            return this.virtual_fs_result(result);
+        }
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -1069,7 +1249,15 @@ fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
     fn rmdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path_ptr = this.read_pointer(path_op)?;
+        let path = this.read_path_from_c_str(path_ptr)?;
+
+        if this.machine.vfs.is_some() {
+            let path = this.read_c_str(path_ptr)?.to_owned();
+            let (vfs, now) = this.virtual_fs();
+            let result = vfs.rmdir(&path, now);
+            return this.virtual_fs_result(result);
+        }
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -1085,16 +1273,35 @@ fn rmdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
     fn opendir(&mut self, name_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        let name = this.read_path_from_c_str(this.read_pointer(name_op)?)?;
+        let name_ptr = this.read_pointer(name_op)?;
+        let name = this.read_path_from_c_str(name_ptr)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`opendir`", reject_with)?;
-            this.set_last_error(LibcError("EACCES"))?;
-            return interp_ok(Scalar::null_ptr(this));
-        }
+        let result = if this.machine.vfs.is_some() {
+            let name = this.read_c_str(name_ptr)?.to_owned();
+            match this.virtual_fs().0.read_dir(&name) {
+                Ok(entries) => Ok(ReadDir::Virtual(entries.into_iter())),
+                Err(err) => Err(err),
+            }
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`opendir`", reject_with)?;
+                this.set_last_error(LibcError("EACCES"))?;
+                return interp_ok(Scalar::null_ptr(this));
+            }
 
-        let result = fs::read_dir(name);
+            if this.machine.trace.is_some() {
+                // The directory is read at once, so that its entries can be traced together.
+                let name = name.into_owned();
+                let entries =
+                    this.query_host_traced("readdir", |this| this.read_host_dir(&name))?;
+                entries
+                    .map(|entries| ReadDir::Traced(entries.into_iter()))
+                    .map_err(IoError::HostError)
+            } else {
+                fs::read_dir(name).map(ReadDir::Host).map_err(IoError::HostError)
+            }
+        };
 
         match result {
             Ok(dir_iter) => {
@@ -1125,7 +1332,9 @@ fn readdir(&mut self, dirp_op: &OpTy<'tcx>, dest: &MPlaceTy<'tcx>) -> InterpResu
         let dirp = this.read_target_usize(dirp_op)?;
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`readdir`", reject_with)?;
             this.set_last_error(LibcError("EBADF"))?;
             this.write_null(dest)?;
@@ -1136,7 +1345,7 @@ fn readdir(&mut self, dirp_op: &OpTy<'tcx>, dest: &MPlaceTy<'tcx>) -> InterpResu
             err_ub_format!("the DIR pointer passed to `readdir` did not come from opendir")
         })?;
 
-        let entry = match open_dir.next_host_entry() {
+        let entry = match open_dir.next_entry() {
             Some(Ok(dir_entry)) => {
                 let dir_entry = this.dir_entry_fields(dir_entry)?;
 
@@ -1261,7 +1470,9 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let dirp = this.read_target_usize(dirp_op)?;
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`closedir`", reject_with)?;
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         }
@@ -1272,7 +1483,7 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         if let Some(entry) = open_dir.entry.take() {
             this.deallocate_ptr(entry, None, MiriMemoryKind::Runtime.into())?;
         }
-        // We drop the `open_dir`, which will close the host dir handle.
+        // We drop the `open_dir`, which will close the host dir handle, if any.
         drop(open_dir);
 
         interp_ok(Scalar::from_i32(0))
@@ -1284,6 +1495,16 @@ fn ftruncate64(&mut self, fd_num: i32, length: i128) -> InterpResult<'tcx, Scala
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         };
+        if let Some(file) = fd.clone().downcast::<VirtualFile>() {
+            if !file.writable {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            }
+            let Ok(length) = length.try_into() else {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            };
+            let result = file.inode.set_len(length, vfs::now(&this.machine));
+            return this.virtual_fs_result(result);
+        }
         let Some(file) = fd.downcast::<FileHandle>() else {
             // The docs say that EINVAL is returned when the FD "does not reference a regular file
             // or a POSIX shared memory object" (and we don't support shmem objects).
@@ -1315,7 +1536,9 @@ fn posix_fallocate(
         let this = self.eval_context_mut();
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`posix_fallocate`", reject_with)?;
             // Return error code "EBADF" (bad fd).
             return interp_ok(this.eval_libc("EBADF"));
@@ -1338,7 +1561,9 @@ fn linux_fallocate(
         let this = self.eval_context_mut();
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`fallocate`", reject_with)?;
             // Set error code "EBADF" (bad fd).
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
@@ -1373,6 +1598,23 @@ fn fallocate_impl(
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return interp_ok(Err(LibcError("EBADF")));
         };
+
+        // Checked i64 addition, to ensure the result does not exceed the max file size.
+        let new_size = match offset.checked_add(len) {
+            // `new_size` is definitely non-negative, so we can cast to `u64`.
+            Some(new_size) => u64::try_from(new_size).unwrap(),
+            None => return interp_ok(Err(LibcError("EFBIG"))), // new size too big
+        };
+
+        if let Some(file) = fd.clone().downcast::<VirtualFile>() {
+            if !file.writable {
+                return interp_ok(Err(LibcError("EBADF")));
+            }
+            if file.inode.size() < new_size {
+                return interp_ok(file.inode.set_len(new_size, vfs::now(&this.machine)));
+            }
+            return interp_ok(Ok(()));
+        }
         let Some(file) = fd.downcast::<FileHandle>() else {
             // Man page specifies to return ENODEV if `fd` is not a regular file.
             return interp_ok(Err(LibcError("ENODEV")));
@@ -1387,13 +1629,6 @@ fn fallocate_impl(
             Err(err) => return interp_ok(Err(err.into())),
         };
 
-        // Checked i64 addition, to ensure the result does not exceed the max file size.
-        let new_size = match offset.checked_add(len) {
-            // `new_size` is definitely non-negative, so we can cast to `u64`.
-            Some(new_size) => u64::try_from(new_size).unwrap(),
-            None => return interp_ok(Err(LibcError("EFBIG"))), // new size too big
-        };
-
         // If the size of the file is less than offset+size, then the file is increased to this
         // size; otherwise the file size is left unchanged.
         if current_size < new_size {
@@ -1424,6 +1659,10 @@ fn ffullsync_fd(&mut self, fd_num: i32) -> InterpResult<'tcx, Scalar> {
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         };
+        // There is nothing to synchronize for files of the virtual file system.
+        if fd.clone().downcast::<VirtualFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`fsync` is only supported on file-backed file descriptors")
@@ -1442,6 +1681,9 @@ fn fdatasync(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let Some(fd) = this.machine.fds.get(fd) else {
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         };
+        if fd.clone().downcast::<VirtualFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`fdatasync` is only supported on file-backed file descriptors")
@@ -1473,7 +1715,7 @@ fn futimens(
         assert!(this.machine.communicate(), "isolation should have prevented even opening a file");
 
         let (access, modified) = if this.ptr_is_null(times_ptr)? {
-            let now = TimeUpdate::Set(SystemTime::now());
+            let now = TimeUpdate::Set(this.machine.monotonic_clock.system_time_now());
             (now, now)
         } else {
             let timespec = this.libc_ty_layout("timespec");
@@ -1526,6 +1768,9 @@ fn sync_file_range(
         let Some(fd) = this.machine.fds.get(fd) else {
             return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
         };
+        if fd.clone().downcast::<VirtualFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`sync_data_range` is only supported on file-backed file descriptors")
@@ -1544,10 +1789,27 @@ fn readlink(
     ) -> InterpResult<'tcx, i64> {
         let this = self.eval_context_mut();
 
-        let pathname = this.read_path_from_c_str(this.read_pointer(pathname_op)?)?;
+        let pathname_ptr = this.read_pointer(pathname_op)?;
+        let pathname = this.read_path_from_c_str(pathname_ptr)?;
         let buf = this.read_pointer(buf_op)?;
         let bufsize = this.read_target_usize(bufsize_op)?;
 
+        if this.machine.vfs.is_some() {
+            let pathname = this.read_c_str(pathname_ptr)?.to_owned();
+            return match this.virtual_fs().0.readlink(&pathname) {
+                Ok(target) => {
+                    // Like below, the target is truncated without a null terminator.
+                    let len = target.len().min(bufsize.try_into().unwrap());
+                    this.write_bytes_ptr(buf, target[..len].iter().copied())?;
+                    interp_ok(len.try_into().unwrap())
+                }
+                Err(err) => {
+                    this.set_last_error(err)?;
+                    interp_ok(-1)
+                }
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`readlink`", reject_with)?;
@@ -1607,17 +1869,26 @@ fn realpath(
         let this = self.eval_context_mut();
         this.assert_target_os_is_unix("realpath");
 
-        let pathname = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path_ptr = this.read_pointer(path_op)?;
+        let pathname = this.read_path_from_c_str(path_ptr)?;
         let processed_ptr = this.read_pointer(processed_path_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`realpath`", reject_with)?;
-            this.set_last_error(LibcError("EACCES"))?;
-            return interp_ok(Scalar::from_target_usize(0, this));
-        }
+        let result = if this.machine.vfs.is_some() {
+            let pathname = this.read_c_str(path_ptr)?.to_owned();
+            match this.virtual_fs().0.realpath(&pathname) {
+                Ok(resolved) => Ok(Path::new(bytes_to_os_str(&resolved)?).to_owned()),
+                Err(err) => Err(err),
+            }
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`realpath`", reject_with)?;
+                this.set_last_error(LibcError("EACCES"))?;
+                return interp_ok(Scalar::from_target_usize(0, this));
+            }
 
-        let result = std::fs::canonicalize(pathname);
+            std::fs::canonicalize(pathname).map_err(IoError::HostError)
+        };
         match result {
             Ok(resolved) => {
                 let path_max = this
@@ -1684,7 +1955,9 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let template_bytes = template.as_mut_slice();
 
         // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && this.machine.vfs.is_none()
+        {
             this.reject_in_isolation("`mkstemp`", reject_with)?;
             return this.set_errno_and_return_neg1_i32(LibcError("EACCES"));
         }
@@ -1751,6 +2024,25 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
             // Write the modified template back to the passed in pointer to maintain POSIX semantics.
             this.write_bytes_ptr(template_ptr, template_bytes.iter().copied())?;
 
+            if this.machine.vfs.is_some() {
+                let options = VirtualOpenOptions {
+                    read: true,
+                    write: true,
+                    create: Some(0o600),
+                    exclusive: true,
+                    ..Default::default()
+                };
+                let (vfs, now) = this.virtual_fs();
+                match vfs.open(template_bytes, &options, now) {
+                    Ok(inode) => {
+                        let file = VirtualFile::new(inode, true, true, false);
+                        return interp_ok(Scalar::from_i32(this.machine.fds.insert_new(file)));
+                    }
+                    Err(LibcError("EEXIST")) => continue,
+                    Err(err) => return this.set_errno_and_return_neg1_i32(err),
+                }
+            }
+
             // See if we can create and open this file.
             let file = fopts.open(bytes_to_os_str(template_bytes)?);
             match file {
@@ -1853,10 +2145,15 @@ fn from_path<'tcx>(
         path: &Path,
         follow_symlink: bool,
     ) -> InterpResult<'tcx, Result<FileMetadata, IoError>> {
-        let metadata =
-            if follow_symlink { std::fs::metadata(path) } else { std::fs::symlink_metadata(path) };
-
-        FileMetadata::from_meta(ecx, metadata)
+        let metadata = ecx.query_host_traced("stat", |ecx| {
+            let metadata = if follow_symlink {
+                std::fs::metadata(path)
+            } else {
+                std::fs::symlink_metadata(path)
+            };
+            FileMetadata::from_meta(ecx, metadata)
+        })?;
+        interp_ok(metadata.map_err(IoError::HostError))
     }
 
     fn from_fd_num<'tcx>(
@@ -1866,12 +2163,50 @@ fn from_fd_num<'tcx>(
         let Some(fd) = ecx.machine.fds.get(fd_num) else {
             return interp_ok(Err(LibcError("EBADF")));
         };
+        if let Some(file) = fd.clone().downcast::<VirtualFile>() {
+            return Self::from_virtual(ecx, Ok(file.inode.metadata()));
+        }
         match fd.metadata()? {
-            Either::Left(host) => Self::from_meta(ecx, host),
+            Either::Left(host) => {
+                let metadata = ecx.query_host_traced("stat", |ecx| Self::from_meta(ecx, host))?;
+                interp_ok(metadata.map_err(IoError::HostError))
+            }
             Either::Right(name) => Self::synthetic(ecx, name),
         }
     }
 
+    fn from_virtual<'tcx>(
+        ecx: &mut MiriInterpCx<'tcx>,
+        metadata: Result<VirtualMetadata, IoError>,
+    ) -> InterpResult<'tcx, Result<FileMetadata, IoError>> {
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(err) => return interp_ok(Err(err)),
+        };
+        let mode_name = match metadata.kind {
+            VirtualFileKind::File => "S_IFREG",
+            VirtualFileKind::Dir => "S_IFDIR",
+            VirtualFileKind::Symlink => "S_IFLNK",
+        };
+        let mode = ecx.eval_libc(mode_name);
+        let mode: u32 = mode.to_uint(ecx.libc_ty_layout("mode_t").size)?.try_into().unwrap();
+        let time = |time: Duration| Some((time.as_secs(), time.subsec_nanos()));
+        interp_ok(Ok(FileMetadata {
+            mode: mode | metadata.mode,
+            size: metadata.size,
+            created: time(metadata.created),
+            accessed: time(metadata.accessed),
+            modified: time(metadata.modified),
+            dev: None,
+            ino: Some(metadata.ino),
+            nlink: Some(metadata.nlink),
+            uid: Some(UID),
+            gid: Some(UID),
+            blksize: Some(4096),
+            blocks: Some(metadata.size.div_ceil(512)),
+        }))
+    }
+
     fn synthetic<'tcx>(
         ecx: &mut MiriInterpCx<'tcx>,
         mode_name: &str,
@@ -1899,11 +2234,11 @@ fn synthetic<'tcx>(
     fn from_meta<'tcx>(
         ecx: &mut MiriInterpCx<'tcx>,
         metadata: Result<std::fs::Metadata, std::io::Error>,
-    ) -> InterpResult<'tcx, Result<FileMetadata, IoError>> {
+    ) -> InterpResult<'tcx, io::Result<FileMetadata>> {
         let metadata = match metadata {
             Ok(metadata) => metadata,
             Err(e) => {
-                return interp_ok(Err(e.into()));
+                return interp_ok(Err(e));
             }
         };
 
@@ -1970,3 +2305,99 @@ fn from_meta<'tcx>(
         }
     }
 }
+
+/// Encodes the fields separated by spaces, with `-` for missing fields.
+impl HostValue for FileMetadata {
+    fn encode(&self) -> String {
+        fn opt(field: Option<impl ToString>) -> String {
+            field.map_or_else(|| "-".to_owned(), |field| field.to_string())
+        }
+        let time = |time: Option<(u64, u32)>| opt(time.map(|(sec, nsec)| format!("{sec}.{nsec}")));
+        [
+            self.mode.to_string(),
+            self.size.to_string(),
+            time(self.created),
+            time(self.accessed),
+            time(self.modified),
+            opt(self.dev),
+            opt(self.ino),
+            opt(self.nlink),
+            opt(self.uid),
+            opt(self.gid),
+            opt(self.blksize),
+            opt(self.blocks),
+        ]
+        .join(" ")
+    }
+
+    fn decode(s: &str) -> Result<Self, String> {
+        fn parse<T: FromStr>(field: &str) -> Result<T, String> {
+            field.parse().map_err(|_| format!("invalid metadata field `{field}`"))
+        }
+        fn opt<T: FromStr>(field: &str) -> Result<Option<T>, String> {
+            if field == "-" { Ok(None) } else { parse(field).map(Some) }
+        }
+        let time = |field: &str| -> Result<Option<(u64, u32)>, String> {
+            if field == "-" {
+                return Ok(None);
+            }
+            let (sec, nsec) =
+                field.split_once('.').ok_or_else(|| format!("invalid metadata time `{field}`"))?;
+            Ok(Some((parse(sec)?, parse(nsec)?)))
+        };
+
+        let fields = s.split(' ').collect::<Vec<_>>();
+        let &[mode, size, created, accessed, modified, dev, ino, nlink, uid, gid, blksize, blocks] =
+            fields.as_slice()
+        else {
+            return Err(format!("invalid metadata `{s}`"));
+        };
+        Ok(FileMetadata {
+            mode: parse(mode)?,
+            size: parse(size)?,
+            created: time(created)?,
+            accessed: time(accessed)?,
+            modified: time(modified)?,
+            dev: opt(dev)?,
+            ino: opt(ino)?,
+            nlink: opt(nlink)?,
+            uid: opt(uid)?,
+            gid: opt(gid)?,
+            blksize: opt(blksize)?,
+            blocks: opt(blocks)?,
+        })
+    }
+}
+
+/// Encodes the entries separated by spaces, each as `<ino>/<d_type>/<name>` with the name in
+/// hexadecimal.
+impl HostValue for Vec<DirEntry> {
+    fn encode(&self) -> String {
+        self.iter()
+            .map(|entry| {
+                let name = encode_hex(entry.name.as_encoded_bytes());
+                format!("{}/{}/{name}", entry.ino, entry.d_type)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn decode(s: &str) -> Result<Self, String> {
+        s.split(' ')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut fields = entry.split('/');
+                let (Some(ino), Some(d_type), Some(name), None) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(format!("invalid directory entry `{entry}`"));
+                };
+                Ok(DirEntry {
+                    name: PathBuf::decode(name)?.into_os_string(),
+                    ino: ino.parse().map_err(|_| format!("invalid inode `{ino}`"))?,
+                    d_type: d_type.parse().map_err(|_| format!("invalid file type `{d_type}`"))?,
+                })
+            })
+            .collect()
+    }
+}
@@ -5,11 +5,14 @@
 
 use self::shims::unix::linux::mem::EvalContextExt as _;
 use self::shims::unix::linux_like::eventfd::EvalContextExt as _;
+use self::shims::unix::linux_like::inotify::EvalContextExt as _;
+use self::shims::unix::linux_like::signalfd::EvalContextExt as _;
 use self::shims::unix::linux_like::syscall::syscall;
 use crate::machine::{SIGRTMAX, SIGRTMIN};
 use crate::shims::unix::foreign_items::EvalContextExt as _;
 use crate::shims::unix::linux_like::epoll::EvalContextExt as _;
 use crate::shims::unix::linux_like::thread::prctl;
+use crate::shims::unix::linux_like::timerfd::EvalContextExt as _;
 use crate::shims::unix::*;
 use crate::*;
 
@@ -167,7 +170,7 @@ fn emulate_foreign_item_inner(
                 let result = this.linux_statx(dirfd, pathname, flags, mask, statxbuf)?;
                 this.write_scalar(result, dest)?;
             }
-            // epoll, eventfd
+            // epoll, eventfd, timerfd, signalfd, inotify
             "epoll_create1" => {
                 let [flag] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
                 let result = this.epoll_create1(flag)?;
@@ -189,6 +192,46 @@ fn emulate_foreign_item_inner(
                 let result = this.eventfd(val, flag)?;
                 this.write_scalar(result, dest)?;
             }
+            "timerfd_create" => {
+                let [clockid, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_create(clockid, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_settime" => {
+                let [fd, flags, new_value, old_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_settime(fd, flags, new_value, old_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_gettime" => {
+                let [fd, curr_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_gettime(fd, curr_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "signalfd" => {
+                let [fd, mask, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.signalfd(fd, mask, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_init1" => {
+                let [flags] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_init1(flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_add_watch" => {
+                let [fd, pathname, mask] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_add_watch(fd, pathname, mask)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_rm_watch" => {
+                let [fd, wd] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_rm_watch(fd, wd)?;
+                this.write_scalar(result, dest)?;
+            }
 
             // Threading
             "pthread_setname_np" => {
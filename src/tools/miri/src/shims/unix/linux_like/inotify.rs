@@ -0,0 +1,475 @@
+//! Linux `inotify` implementation over the host file system.
+//!
+//! Miri cannot portably subscribe to file system events of the host, so every watch keeps a
+//! snapshot of the metadata of the watched file or directory, and polls the host for changes. The
+//! differences between two snapshots are reported as events. This means that a file which is
+//! created and deleted between two polls goes unnoticed, and that events are not ordered precisely.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, iter};
+
+use rustc_abi::Size;
+
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::unix::UnixFileDescription;
+use crate::*;
+
+/// How often the watched paths are polled for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The size of `struct inotify_event`, not counting the name.
+const EVENT_HEADER_SIZE: u64 = 16;
+
+/// A kind of file descriptor created by `inotify_init1`.
+///
+/// <https://man7.org/linux/man-pages/man7/inotify.7.html>
+#[derive(Debug)]
+struct Inotify {
+    is_nonblock: bool,
+    masks: EventMasks,
+    state: RefCell<InotifyState>,
+    /// A list of thread ids blocked on inotify::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+    /// State for being watched by epoll.
+    watched: ReadinessWatched,
+}
+
+/// The values of the `IN_*` constants of the target.
+#[derive(Debug, Clone, Copy)]
+struct EventMasks {
+    create: u32,
+    delete: u32,
+    modify: u32,
+    attrib: u32,
+    delete_self: u32,
+    isdir: u32,
+    ignored: u32,
+}
+
+#[derive(Debug, Default)]
+struct InotifyState {
+    watches: BTreeMap<i32, Watch>,
+    last_wd: i32,
+    events: VecDeque<Event>,
+}
+
+#[derive(Debug)]
+struct Watch {
+    path: PathBuf,
+    /// The events the program is interested in.
+    mask: u32,
+    snapshot: Snapshot,
+}
+
+#[derive(Debug)]
+struct Event {
+    wd: i32,
+    mask: u32,
+    name: Option<OsString>,
+}
+
+/// The metadata of a watched path which changes are detected from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stamp {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+    readonly: bool,
+}
+
+impl Stamp {
+    fn of(metadata: &fs::Metadata) -> Stamp {
+        Stamp {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            readonly: metadata.permissions().readonly(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Snapshot {
+    Missing,
+    File(Stamp),
+    Dir(Stamp, BTreeMap<OsString, Stamp>),
+}
+
+impl Snapshot {
+    fn take(path: &Path) -> Snapshot {
+        let Ok(metadata) = fs::metadata(path) else { return Snapshot::Missing };
+        let stamp = Stamp::of(&metadata);
+        if !stamp.is_dir {
+            return Snapshot::File(stamp);
+        }
+        let entries = fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| Some((entry.file_name(), Stamp::of(&entry.metadata().ok()?))))
+            .collect();
+        Snapshot::Dir(stamp, entries)
+    }
+}
+
+impl InotifyState {
+    /// Polls the watched paths, and queues the events for the changes since the last poll.
+    fn poll(&mut self, masks: EventMasks) {
+        let InotifyState { watches, events, .. } = self;
+        let mut removed = Vec::new();
+        for (&wd, watch) in watches.iter_mut() {
+            let snapshot = Snapshot::take(&watch.path);
+            let mut push = |mask: u32, name: Option<&OsString>, is_dir: bool| {
+                let mask = if is_dir { mask | masks.isdir } else { mask };
+                if watch.mask & mask & !masks.isdir != 0 || mask & masks.ignored != 0 {
+                    events.push_back(Event { wd, mask, name: name.cloned() });
+                }
+            };
+            match (&watch.snapshot, &snapshot) {
+                (Snapshot::Dir(old_stamp, old), Snapshot::Dir(new_stamp, new)) => {
+                    for (name, stamp) in new {
+                        match old.get(name) {
+                            None => push(masks.create, Some(name), stamp.is_dir),
+                            Some(old) if old.len != stamp.len || old.modified != stamp.modified =>
+                                push(masks.modify, Some(name), stamp.is_dir),
+                            Some(old) if old.readonly != stamp.readonly =>
+                                push(masks.attrib, Some(name), stamp.is_dir),
+                            Some(_) => {}
+                        }
+                    }
+                    for (name, stamp) in old {
+                        if !new.contains_key(name) {
+                            push(masks.delete, Some(name), stamp.is_dir);
+                        }
+                    }
+                    if old_stamp.readonly != new_stamp.readonly {
+                        push(masks.attrib, None, true);
+                    }
+                }
+                (Snapshot::File(old), Snapshot::File(new)) =>
+                    if old.len != new.len || old.modified != new.modified {
+                        push(masks.modify, None, false);
+                    } else if old.readonly != new.readonly {
+                        push(masks.attrib, None, false);
+                    },
+                (Snapshot::Missing, _) => {}
+                (_, _) => {
+                    // The watched path was deleted or replaced, which removes the watch.
+                    push(masks.delete_self, None, false);
+                    push(masks.ignored, None, false);
+                    removed.push(wd);
+                }
+            }
+            watch.snapshot = snapshot;
+        }
+        for wd in removed {
+            watches.remove(&wd);
+        }
+    }
+}
+
+impl FileDescription for Inotify {
+    fn name(&self) -> &'static str {
+        "inotify"
+    }
+
+    /// Reads as many events as fit into the buffer, blocking until there is one if there is none.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        inotify_read(ptr, len, self, ecx, finish)
+    }
+
+    fn readiness_watched(&self) -> Option<&ReadinessWatched> {
+        Some(&self.watched)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness { readable: !self.state.borrow().events.is_empty(), ..Readiness::EMPTY }
+    }
+
+    fn deadline_reached(&self, now: &Instant) -> Option<Instant> {
+        let mut state = self.state.borrow_mut();
+        state.poll(self.masks);
+        // Keep polling as long as something is watched.
+        (!state.watches.is_empty()).then(|| now.add_lossy(POLL_INTERVAL))
+    }
+
+    fn as_unix<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _ecx: &MiriInterpCx<'tcx>,
+    ) -> FileDescriptionRef<dyn UnixFileDescription> {
+        self
+    }
+}
+
+impl UnixFileDescription for Inotify {}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Looks up the inotify instance `fd`, returning the errno to fail with if there is none.
+    fn inotify_get(
+        &self,
+        fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<FileDescriptionRef<Inotify>, IoError>> {
+        let this = self.eval_context_ref();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let Some(fd) = this.machine.fds.get(fd) else {
+            return interp_ok(Err(LibcError("EBADF")));
+        };
+        interp_ok(fd.downcast::<Inotify>().ok_or(LibcError("EINVAL")))
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Creates an inotify instance without any watch.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_init1.2.html>
+    fn inotify_init1(&mut self, flags: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        let in_cloexec = this.eval_libc_i32("IN_CLOEXEC");
+        let in_nonblock = this.eval_libc_i32("IN_NONBLOCK");
+
+        let mut is_nonblock = false;
+        if flags & in_cloexec == in_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !in_cloexec;
+        }
+        if flags & in_nonblock == in_nonblock {
+            flags &= !in_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+
+        let masks = EventMasks {
+            create: this.eval_libc_u32("IN_CREATE"),
+            delete: this.eval_libc_u32("IN_DELETE"),
+            modify: this.eval_libc_u32("IN_MODIFY"),
+            attrib: this.eval_libc_u32("IN_ATTRIB"),
+            delete_self: this.eval_libc_u32("IN_DELETE_SELF"),
+            isdir: this.eval_libc_u32("IN_ISDIR"),
+            ignored: this.eval_libc_u32("IN_IGNORED"),
+        };
+        let fd_value = this.machine.fds.insert_new(Inotify {
+            is_nonblock,
+            masks,
+            state: RefCell::new(InotifyState::default()),
+            blocked_read_tid: RefCell::new(Vec::new()),
+            watched: ReadinessWatched::default(),
+        });
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// Watches `pathname` for the events of `mask`, or changes the mask of its existing watch.
+    /// Only `IN_CREATE`, `IN_DELETE`, `IN_MODIFY`, `IN_ATTRIB` and `IN_DELETE_SELF` are ever
+    /// reported.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_add_watch.2.html>
+    fn inotify_add_watch(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        pathname: &OpTy<'tcx>,
+        mask: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let inotify = match this.inotify_get(fd)? {
+            Ok(inotify) => inotify,
+            Err(err) => return this.set_errno_and_return_neg1_i32(err),
+        };
+        let path = this.read_path_from_c_str(this.read_pointer(pathname)?)?.into_owned();
+        let mut mask = this.read_scalar(mask)?.to_u32()?;
+
+        // Reject if isolation is enabled.
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation("`inotify_add_watch`", reject_with)?;
+            return this.set_errno_and_return_neg1_i32(ErrorKind::PermissionDenied);
+        }
+
+        let in_mask_add = this.eval_libc_u32("IN_MASK_ADD");
+        let in_onlydir = this.eval_libc_u32("IN_ONLYDIR");
+        let in_dont_follow = this.eval_libc_u32("IN_DONT_FOLLOW");
+        let in_oneshot = this.eval_libc_u32("IN_ONESHOT");
+        let in_excl_unlink = this.eval_libc_u32("IN_EXCL_UNLINK");
+        let in_all_events = this.eval_libc_u32("IN_ALL_EVENTS");
+
+        if mask & (in_dont_follow | in_oneshot) != 0 {
+            throw_unsup_format!("inotify_add_watch: IN_DONT_FOLLOW and IN_ONESHOT are unsupported");
+        }
+        // Miri never reports events for unlinked files anyway.
+        mask &= !in_excl_unlink;
+        let options = mask & (in_mask_add | in_onlydir);
+        mask &= !options;
+        if mask & !in_all_events != 0 || mask == 0 {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) => return this.set_errno_and_return_neg1_i32(err),
+        };
+        if options & in_onlydir != 0 && !metadata.is_dir() {
+            return this.set_errno_and_return_neg1_i32(LibcError("ENOTDIR"));
+        }
+        // Watches are identified by the path, as Miri does not know about inodes.
+        let path = fs::canonicalize(&path).unwrap_or(path);
+
+        let mut state = inotify.state.borrow_mut();
+        if let Some((&wd, watch)) = state.watches.iter_mut().find(|(_, watch)| watch.path == path) {
+            if options & in_mask_add != 0 {
+                watch.mask |= mask;
+            } else {
+                watch.mask = mask;
+            }
+            return interp_ok(Scalar::from_i32(wd));
+        }
+        let wd = state.last_wd.strict_add(1);
+        state.last_wd = wd;
+        let snapshot = Snapshot::take(&path);
+        state.watches.insert(wd, Watch { path, mask, snapshot });
+        drop(state);
+
+        // Start polling the host for changes.
+        let next_poll = this.machine.monotonic_clock.now().add_lossy(POLL_INTERVAL);
+        this.machine.delayed_readiness_updates.add_deadline(inotify, next_poll);
+
+        interp_ok(Scalar::from_i32(wd))
+    }
+
+    /// Removes the watch `wd`, which queues an `IN_IGNORED` event.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_rm_watch.2.html>
+    fn inotify_rm_watch(&mut self, fd: &OpTy<'tcx>, wd: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let inotify = match this.inotify_get(fd)? {
+            Ok(inotify) => inotify,
+            Err(err) => return this.set_errno_and_return_neg1_i32(err),
+        };
+        let wd = this.read_scalar(wd)?.to_i32()?;
+
+        let mut state = inotify.state.borrow_mut();
+        if state.watches.remove(&wd).is_none() {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        state.events.push_back(Event { wd, mask: inotify.masks.ignored, name: None });
+        drop(state);
+
+        let waiting_threads = std::mem::take(&mut *inotify.blocked_read_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::Inotify)?;
+        }
+        this.update_fd_readiness(inotify, ReadinessUpdateFlags::DEFAULT)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+}
+
+/// The size of `event` in the buffer: the name is NUL-terminated and padded so that the next event
+/// is aligned.
+fn event_size(event: &Event) -> u64 {
+    let name_len = event.name.as_ref().map_or(0, |name| {
+        let len = u64::try_from(name.len()).unwrap().strict_add(1);
+        len.next_multiple_of(EVENT_HEADER_SIZE)
+    });
+    EVENT_HEADER_SIZE.strict_add(name_len)
+}
+
+/// Block the thread if there is no event, else write as many events as fit into the buffer.
+fn inotify_read<'tcx>(
+    ptr: Pointer,
+    len: usize,
+    inotify: FileDescriptionRef<Inotify>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let mut state = inotify.state.borrow_mut();
+    // Make sure to report the changes since the last poll.
+    state.poll(inotify.masks);
+
+    if state.events.is_empty() {
+        if inotify.is_nonblock {
+            drop(state);
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        // Poll again in a bit, or once a watch gets removed.
+        let deadline = ecx.machine.monotonic_clock.now().add_lossy(POLL_INTERVAL);
+        drop(state);
+        inotify.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+        ecx.block_thread(
+            BlockReason::Inotify,
+            Some(deadline.into()),
+            callback!(
+                @capture<'tcx> {
+                    ptr: Pointer,
+                    len: usize,
+                    inotify: FileDescriptionRef<Inotify>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                }
+                |this, unblock: UnblockKind| {
+                    if unblock == UnblockKind::TimedOut {
+                        let thread = this.active_thread();
+                        inotify.blocked_read_tid.borrow_mut().retain(|&id| id != thread);
+                    }
+                    inotify_read(ptr, len, inotify, this, finish)
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+
+    let len = u64::try_from(len).unwrap();
+    if event_size(state.events.front().unwrap()) > len {
+        drop(state);
+        return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+    }
+
+    let header_layout = ecx.libc_ty_layout("inotify_event");
+    let mut written = 0u64;
+    while let Some(event) = state.events.front() {
+        let size = event_size(event);
+        if written.strict_add(size) > len {
+            break;
+        }
+        let event = state.events.pop_front().unwrap();
+        let event_ptr = ptr.wrapping_offset(Size::from_bytes(written), ecx);
+        ecx.write_bytes_ptr(event_ptr, iter::repeat_n(0, usize::try_from(size).unwrap()))?;
+        let header = ecx.ptr_to_mplace_unaligned(event_ptr, header_layout);
+        let name_len = size.strict_sub(EVENT_HEADER_SIZE);
+        ecx.write_int_fields_named(
+            &[
+                ("wd", event.wd.into()),
+                ("mask", event.mask.into()),
+                ("cookie", 0),
+                ("len", name_len.into()),
+            ],
+            &header,
+        )?;
+        if let Some(name) = &event.name {
+            let name_ptr = event_ptr.wrapping_offset(Size::from_bytes(EVENT_HEADER_SIZE), ecx);
+            ecx.write_os_str_to_c_str(name, name_ptr, name_len)?;
+        }
+        written = written.strict_add(size);
+    }
+    drop(state);
+    ecx.update_fd_readiness(inotify, ReadinessUpdateFlags::DEFAULT)?;
+
+    finish.call(ecx, Ok(usize::try_from(written).unwrap()))
+}
@@ -0,0 +1,911 @@
+//! Linux `io_uring` implementation.
+//!
+//! The submission and completion rings live in memory owned by Miri, which the program maps with
+//! `mmap`. The "kernel side" of the rings is implemented with regular (atomic) accesses to that
+//! memory, so Miri checks the program's accesses to the rings like any other memory access. The
+//! submitted operations are carried out by the existing file and socket shims when the program
+//! calls `io_uring_enter`. Operations on file descriptions that support readiness (sockets, pipes,
+//! eventfds, ...) are deferred until the file description is ready, so that they never block.
+//! Deferred operations are carried out by the next `io_uring_enter` after their file description
+//! became ready, rather than in the background.
+//!
+//! The rings have their own memory kind. Like in the kernel, they stay allocated as long as the
+//! instance is open or the program has them mapped: `munmap` only marks a ring as unmapped while
+//! the instance is still open, and closing the instance frees the rings that are not mapped.
+//!
+//! Completions are posted in the order in which the operations complete, which is deterministic.
+//! With `-Zmiri-io-uring-shuffle-completions`, the completions of all operations that complete
+//! during the same `io_uring_enter` are posted in random order.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::rc::Rc;
+
+use rand::seq::SliceRandom;
+use rustc_abi::Size;
+use rustc_middle::ty::layout::TyAndLayout;
+
+use crate::shims::files::{
+    DynFileDescriptionRef, FdId, FdNum, FileDescription, FileDescriptionRef, WeakFileDescriptionRef,
+};
+use crate::shims::unix::*;
+use crate::*;
+
+// Constants from `include/uapi/linux/io_uring.h`. The `libc` crate does not provide them.
+const IORING_MAX_ENTRIES: u32 = 32768;
+const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+const IORING_SETUP_CQSIZE: u32 = 1 << 3;
+const IORING_SETUP_CLAMP: u32 = 1 << 4;
+const IORING_FEAT_NODROP: u32 = 1 << 1;
+const IORING_FEAT_SUBMIT_STABLE: u32 = 1 << 2;
+const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1;
+const IORING_OFF_SQ_RING: i128 = 0;
+const IORING_OFF_CQ_RING: i128 = 0x8000000;
+const IORING_OFF_SQES: i128 = 0x10000000;
+const IOSQE_ASYNC: u8 = 1 << 4;
+
+const IORING_OP_NOP: u8 = 0;
+const IORING_OP_FSYNC: u8 = 3;
+const IORING_OP_POLL_ADD: u8 = 6;
+const IORING_OP_CLOSE: u8 = 19;
+const IORING_OP_READ: u8 = 22;
+const IORING_OP_WRITE: u8 = 23;
+const IORING_OP_SEND: u8 = 26;
+const IORING_OP_RECV: u8 = 27;
+
+/// The size of `struct io_uring_params`.
+const PARAMS_SIZE: u64 = 120;
+/// The size of `struct io_uring_sqe`.
+const SQE_SIZE: u64 = 64;
+/// The size of `struct io_uring_cqe`.
+const CQE_SIZE: u64 = 16;
+
+// The layout of the rings. The program learns about it from the `sq_off` and `cq_off` fields of
+// `struct io_uring_params`, so we are free to choose it.
+const SQ_HEAD: u64 = 0;
+const SQ_TAIL: u64 = 4;
+const SQ_RING_MASK: u64 = 8;
+const SQ_RING_ENTRIES: u64 = 12;
+const SQ_FLAGS: u64 = 16;
+const SQ_DROPPED: u64 = 20;
+const SQ_ARRAY: u64 = 64;
+const CQ_HEAD: u64 = 0;
+const CQ_TAIL: u64 = 4;
+const CQ_RING_MASK: u64 = 8;
+const CQ_RING_ENTRIES: u64 = 12;
+const CQ_OVERFLOW: u64 = 16;
+const CQ_FLAGS: u64 = 20;
+const CQ_CQES: u64 = 64;
+
+/// A memory region shared between the program and the io_uring instance.
+#[derive(Debug)]
+struct Region {
+    ptr: Pointer,
+    alloc_id: AllocId,
+    /// The size of the region, rounded up to the page size.
+    size: u64,
+}
+
+/// What Miri needs to know to free a region, which may happen after its instance was closed.
+#[derive(Debug)]
+struct RegionState {
+    ptr: Pointer,
+    ring: WeakFileDescriptionRef<IoUring>,
+    /// The length, rounded up to the page size, of the program's mapping of the region.
+    mapped: Option<u64>,
+}
+
+/// The rings of all io_uring instances that have not been freed yet.
+#[derive(Debug, Default)]
+pub struct IoUringRegions {
+    regions: BTreeMap<AllocId, RegionState>,
+}
+
+impl VisitProvenance for IoUringRegions {
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        for region in self.regions.values() {
+            region.ptr.visit_provenance(visit);
+        }
+    }
+}
+
+/// An operation that waits for its file description to become ready.
+#[derive(Debug)]
+struct PendingOp {
+    fd_num: FdNum,
+    fd: DynFileDescriptionRef,
+    kind: OpKind,
+    /// The readiness that lets the operation proceed without blocking.
+    needs: Readiness,
+    user_data: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OpKind {
+    /// Read into `buf`. An `offset` of `u64::MAX` reads from the current file position.
+    Read {
+        buf: Pointer,
+        len: usize,
+        offset: u64,
+    },
+    /// Write from `buf`. An `offset` of `u64::MAX` writes at the current file position.
+    Write {
+        buf: Pointer,
+        len: usize,
+        offset: u64,
+    },
+    Poll,
+}
+
+/// A completion that has not been posted to the completion ring yet.
+#[derive(Debug, Clone, Copy)]
+struct Completion {
+    user_data: u64,
+    res: i32,
+}
+
+/// An io_uring instance created by `io_uring_setup`.
+///
+/// <https://man7.org/linux/man-pages/man7/io_uring.7.html>
+#[derive(Debug)]
+pub struct IoUring {
+    sq_entries: u32,
+    cq_entries: u32,
+    sq_ring: Region,
+    cq_ring: Region,
+    sqes: Region,
+    /// The head of the submission queue, i.e. the next entry we will consume.
+    sq_head: Cell<u32>,
+    /// The tail of the completion queue, i.e. the next entry we will fill.
+    cq_tail: Cell<u32>,
+    /// Operations waiting for their file description to become ready.
+    pending: RefCell<Vec<PendingOp>>,
+    /// Completions of the current `io_uring_enter`, which are posted together at its end.
+    batch: RefCell<Vec<Completion>>,
+    /// Whether completions are currently collected in `batch`.
+    batching: Cell<bool>,
+    /// Completions that did not fit into the completion ring yet.
+    overflow: RefCell<VecDeque<Completion>>,
+    /// Watches the file descriptions of the pending operations.
+    watcher: Rc<ReadinessWatcher>,
+}
+
+impl FileDescription for IoUring {
+    fn name(&self) -> &'static str {
+        "io_uring"
+    }
+
+    fn metadata<'tcx>(
+        &self,
+    ) -> InterpResult<'tcx, Either<io::Result<std::fs::Metadata>, &'static str>> {
+        // Like eventfd, io_uring is an "anonymous inode" reported as S_IFREG.
+        interp_ok(Either::Right("S_IFREG"))
+    }
+
+    fn as_unix<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _ecx: &MiriInterpCx<'tcx>,
+    ) -> FileDescriptionRef<dyn UnixFileDescription> {
+        self
+    }
+
+    fn visit_in_flight_provenance(&self, visit: &mut VisitWith<'_>) {
+        // The pointers to the rings are visited through `IoUringRegions`.
+        for op in self.pending.borrow().iter() {
+            match op.kind {
+                OpKind::Read { buf, .. } | OpKind::Write { buf, .. } => buf.visit_provenance(visit),
+                OpKind::Poll => {}
+            }
+        }
+    }
+}
+
+impl UnixFileDescription for IoUring {}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Returns the place of the `u32` at `offset` in `region`.
+    fn ring_u32(&self, region: &Region, offset: u64) -> MPlaceTy<'tcx> {
+        let this = self.eval_context_ref();
+        let ptr = region.ptr.wrapping_offset(Size::from_bytes(offset), this);
+        this.ptr_to_mplace(ptr, this.machine.layouts.u32)
+    }
+
+    /// Returns the place of the field at `offset` in the struct at `base`.
+    fn field_at(&self, base: Pointer, offset: u64, layout: TyAndLayout<'tcx>) -> MPlaceTy<'tcx> {
+        let this = self.eval_context_ref();
+        this.ptr_to_mplace(base.wrapping_offset(Size::from_bytes(offset), this), layout)
+    }
+
+    /// Reads a 32-bit integer argument of `syscall`. Callers often pass `c_long` instead, of which
+    /// the kernel only looks at the low 32 bits.
+    fn read_syscall_u32(&self, op: &OpTy<'tcx>) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_ref();
+        let bits = this.read_scalar(op)?.to_bits(op.layout.size)?;
+        interp_ok(u32::try_from(bits & 0xffff_ffff).unwrap())
+    }
+
+    /// Reads a pointer stored in a `u64` field of a submission queue entry. Programs usually store
+    /// it there with a pointer-to-integer cast.
+    fn read_sqe_pointer(&self, place: &MPlaceTy<'tcx>) -> InterpResult<'tcx, Pointer> {
+        let this = self.eval_context_ref();
+        match this.read_scalar(place)? {
+            Scalar::Ptr(ptr, _) => interp_ok(ptr.into()),
+            addr => this.ptr_from_addr_cast(addr.to_u64()?),
+        }
+    }
+
+    /// Runs a shim that reports errors via `errno` and returns its result the way io_uring does:
+    /// the negated error code on failure. The `errno` of the program is left untouched.
+    fn errno_to_io_uring_result(
+        &mut self,
+        f: impl FnOnce(&mut MiriInterpCx<'tcx>) -> InterpResult<'tcx, Scalar>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        let saved_errno = this.get_last_error()?;
+        let res = f(this)?.to_i32()?;
+        if res >= 0 {
+            return interp_ok(res);
+        }
+        let errno = this.get_last_error()?.to_i32()?;
+        this.set_last_error(saved_errno)?;
+        interp_ok(errno.strict_neg())
+    }
+
+    /// Returns the number of completions the program has not consumed yet.
+    fn io_uring_cq_ready(&self, ring: &IoUring) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_ref();
+        let head = this
+            .read_scalar_atomic(&this.ring_u32(&ring.cq_ring, CQ_HEAD), AtomicReadOrd::Acquire)?
+            .to_u32()?;
+        let posted = ring.cq_tail.get().wrapping_sub(head);
+        interp_ok(posted.saturating_add(u32::try_from(ring.overflow.borrow().len()).unwrap()))
+    }
+
+    /// Records the completion of an operation.
+    fn io_uring_complete(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        user_data: u64,
+        res: i32,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        ring.batch.borrow_mut().push(Completion { user_data, res });
+        if !ring.batching.get() {
+            // This operation completed asynchronously.
+            this.io_uring_flush(ring)?;
+        }
+        interp_ok(())
+    }
+
+    /// Posts the completions of the current batch, in random order if requested.
+    fn io_uring_flush(&mut self, ring: &FileDescriptionRef<IoUring>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let mut batch = ring.batch.take();
+        if this.machine.io_uring_shuffle_completions {
+            batch.shuffle(this.machine.rng.get_mut());
+        }
+        ring.overflow.borrow_mut().extend(batch);
+        this.io_uring_post_completions(ring)
+    }
+
+    /// Moves as many completions as fit into the completion ring.
+    fn io_uring_post_completions(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let head = this
+            .read_scalar_atomic(&this.ring_u32(&ring.cq_ring, CQ_HEAD), AtomicReadOrd::Acquire)?
+            .to_u32()?;
+        let mut tail = ring.cq_tail.get();
+        let mask = ring.cq_entries.strict_sub(1);
+        let mut overflow = ring.overflow.borrow_mut();
+        while tail.wrapping_sub(head) < ring.cq_entries
+            && let Some(completion) = overflow.pop_front()
+        {
+            let offset = CQ_CQES.strict_add(u64::from(tail & mask).strict_mul(CQE_SIZE));
+            let cqe = ring.cq_ring.ptr.wrapping_offset(Size::from_bytes(offset), this);
+            this.write_int(completion.user_data, &this.field_at(cqe, 0, this.machine.layouts.u64))?;
+            this.write_int(completion.res, &this.field_at(cqe, 8, this.machine.layouts.i32))?;
+            this.write_int(0, &this.field_at(cqe, 12, this.machine.layouts.u32))?;
+            tail = tail.wrapping_add(1);
+        }
+        let overflowing = !overflow.is_empty();
+        drop(overflow);
+        if tail != ring.cq_tail.get() {
+            ring.cq_tail.set(tail);
+            this.write_scalar_atomic(
+                Scalar::from_u32(tail),
+                &this.ring_u32(&ring.cq_ring, CQ_TAIL),
+                AtomicWriteOrd::Release,
+            )?;
+        }
+        // Tell the program whether completions are waiting for room in the completion ring.
+        let flags_place = this.ring_u32(&ring.sq_ring, SQ_FLAGS);
+        let flags = this.read_scalar_atomic(&flags_place, AtomicReadOrd::Relaxed)?.to_u32()?;
+        let new_flags = if overflowing {
+            flags | IORING_SQ_CQ_OVERFLOW
+        } else {
+            flags & !IORING_SQ_CQ_OVERFLOW
+        };
+        if new_flags != flags {
+            this.write_scalar_atomic(
+                Scalar::from_u32(new_flags),
+                &flags_place,
+                AtomicWriteOrd::Release,
+            )?;
+        }
+        interp_ok(())
+    }
+
+    /// Consumes up to `to_submit` entries from the submission queue and starts their operations.
+    /// Returns the number of consumed entries.
+    fn io_uring_submit(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        to_submit: u32,
+    ) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_mut();
+        let tail = this
+            .read_scalar_atomic(&this.ring_u32(&ring.sq_ring, SQ_TAIL), AtomicReadOrd::Acquire)?
+            .to_u32()?;
+        let mask = ring.sq_entries.strict_sub(1);
+        let mut submitted = 0;
+        while submitted < to_submit && ring.sq_head.get() != tail {
+            let head = ring.sq_head.get();
+            let array_offset = SQ_ARRAY.strict_add(u64::from(head & mask).strict_mul(4));
+            let idx = this.read_scalar(&this.ring_u32(&ring.sq_ring, array_offset))?.to_u32()?;
+            ring.sq_head.set(head.wrapping_add(1));
+            this.write_scalar_atomic(
+                Scalar::from_u32(ring.sq_head.get()),
+                &this.ring_u32(&ring.sq_ring, SQ_HEAD),
+                AtomicWriteOrd::Release,
+            )?;
+            if idx >= ring.sq_entries {
+                // Invalid entries are dropped.
+                let dropped_place = this.ring_u32(&ring.sq_ring, SQ_DROPPED);
+                let dropped = this.read_scalar(&dropped_place)?.to_u32()?;
+                this.write_scalar(Scalar::from_u32(dropped.wrapping_add(1)), &dropped_place)?;
+                continue;
+            }
+            let sqe = ring
+                .sqes
+                .ptr
+                .wrapping_offset(Size::from_bytes(u64::from(idx).strict_mul(SQE_SIZE)), this);
+            this.io_uring_submit_sqe(ring, sqe)?;
+            submitted = submitted.strict_add(1);
+        }
+        interp_ok(submitted)
+    }
+
+    /// Starts the operation described by the submission queue entry at `sqe`.
+    fn io_uring_submit_sqe(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        sqe: Pointer,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let layouts = &this.machine.layouts;
+        let opcode = this.read_scalar(&this.field_at(sqe, 0, layouts.u8))?.to_u8()?;
+        let flags = this.read_scalar(&this.field_at(sqe, 1, layouts.u8))?.to_u8()?;
+        let fd_num = this.read_scalar(&this.field_at(sqe, 4, layouts.i32))?.to_i32()?;
+        let offset = this.read_scalar(&this.field_at(sqe, 8, layouts.u64))?.to_u64()?;
+        let addr = this.field_at(sqe, 16, layouts.u64);
+        let len = this.read_scalar(&this.field_at(sqe, 24, layouts.u32))?.to_u32()?;
+        let op_flags = this.read_scalar(&this.field_at(sqe, 28, layouts.u32))?.to_u32()?;
+        let user_data = this.read_scalar(&this.field_at(sqe, 32, layouts.u64))?.to_u64()?;
+
+        // `IOSQE_ASYNC` only tells the kernel to not try the operation inline first.
+        if flags & !IOSQE_ASYNC != 0 {
+            throw_unsup_format!(
+                "io_uring: submission queue entry flags {flags:#x} are unsupported"
+            );
+        }
+
+        if opcode == IORING_OP_NOP {
+            return this.io_uring_complete(ring, user_data, 0);
+        }
+        let Some(fd) = this.machine.fds.get(fd_num) else {
+            let ebadf = this.eval_libc_i32("EBADF");
+            return this.io_uring_complete(ring, user_data, ebadf.strict_neg());
+        };
+
+        let kind = match opcode {
+            IORING_OP_READ | IORING_OP_WRITE | IORING_OP_SEND | IORING_OP_RECV => {
+                if matches!(opcode, IORING_OP_SEND | IORING_OP_RECV) && op_flags != 0 {
+                    throw_unsup_format!(
+                        "io_uring: send and recv flags {op_flags:#x} are unsupported"
+                    );
+                }
+                let buf = this.read_sqe_pointer(&addr)?;
+                // Check that the *entire* buffer is actually valid memory.
+                this.check_ptr_access(buf, Size::from_bytes(len), CheckInAllocMsg::MemoryAccess)?;
+                // Cap the length so that the result fits into the `i32` of the completion.
+                let len = usize::try_from(len.min(i32::MAX.cast_unsigned())).unwrap();
+                // Sockets have no file position.
+                let offset = if matches!(opcode, IORING_OP_SEND | IORING_OP_RECV) {
+                    u64::MAX
+                } else {
+                    offset
+                };
+                if matches!(opcode, IORING_OP_READ | IORING_OP_RECV) {
+                    OpKind::Read { buf, len, offset }
+                } else {
+                    OpKind::Write { buf, len, offset }
+                }
+            }
+            IORING_OP_POLL_ADD => {
+                if len != 0 {
+                    throw_unsup_format!("io_uring: multishot poll is unsupported");
+                }
+                OpKind::Poll
+            }
+            IORING_OP_FSYNC => {
+                // Miri always synchronizes data and metadata, so `IORING_FSYNC_DATASYNC` makes no
+                // difference.
+                let res = this.errno_to_io_uring_result(|this| this.ffullsync_fd(fd_num))?;
+                return this.io_uring_complete(ring, user_data, res);
+            }
+            IORING_OP_CLOSE => {
+                let res = this.errno_to_io_uring_result(|this| this.close(fd_num))?;
+                return this.io_uring_complete(ring, user_data, res);
+            }
+            _ => throw_unsup_format!("io_uring: opcode {opcode} is unsupported"),
+        };
+        let needs = match kind {
+            OpKind::Read { .. } =>
+                Readiness { readable: true, read_closed: true, error: true, ..Readiness::EMPTY },
+            OpKind::Write { .. } =>
+                Readiness { writable: true, write_closed: true, error: true, ..Readiness::EMPTY },
+            // The `poll` events are in the low half of `poll32_events`.
+            OpKind::Poll =>
+                this.poll_bitflag_to_readiness(u16::try_from(op_flags & 0xffff).unwrap())?,
+        };
+        let op = PendingOp { fd_num, fd, kind, needs, user_data };
+
+        if op.fd.readiness_watched().is_none() || op.fd.readiness() & op.needs != Readiness::EMPTY {
+            return this.io_uring_run(ring, op);
+        }
+        let fd_id = op.fd.id();
+        ring.pending.borrow_mut().push(op);
+        this.io_uring_update_interest(ring, fd_num, fd_id)
+    }
+
+    /// Carries out an operation whose file description is ready.
+    fn io_uring_run(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        op: PendingOp,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let (buf, len, offset) = match op.kind {
+            OpKind::Read { buf, len, offset } | OpKind::Write { buf, len, offset } =>
+                (buf, len, offset),
+            OpKind::Poll => {
+                let ready = if op.fd.readiness_watched().is_some() {
+                    op.fd.readiness() & op.needs
+                } else {
+                    // Files without readiness support are always readable and writable.
+                    Readiness { readable: true, writable: true, ..Readiness::EMPTY } & op.needs
+                };
+                let res = this.readiness_to_poll_bitflag(&ready);
+                return this.io_uring_complete(ring, op.user_data, i32::from(res));
+            }
+        };
+        let PendingOp { fd, kind, user_data, .. } = op;
+        let name = fd.name();
+        let ring = ring.clone();
+        let finish = callback!(
+            @capture<'tcx> {
+                ring: FileDescriptionRef<IoUring>,
+                user_data: u64,
+            }
+            |this, result: Result<usize, IoError>| {
+                let res = match result {
+                    // This fits since the length was capped at `i32::MAX`.
+                    Ok(size) => i32::try_from(size).unwrap(),
+                    Err(err) => this.io_error_to_errnum(err)?.to_i32()?.strict_neg(),
+                };
+                this.io_uring_complete(&ring, user_data, res)
+            }
+        );
+        let communicate = this.machine.communicate();
+        // File descriptions with readiness support are streams without a file position.
+        let positional = offset != u64::MAX && fd.readiness_watched().is_none();
+        match (kind, positional) {
+            (OpKind::Read { .. }, false) => fd.read(communicate, buf, len, this, finish)?,
+            (OpKind::Read { .. }, true) =>
+                fd.as_unix(this).pread(communicate, offset, buf, len, this, finish)?,
+            (OpKind::Write { .. }, false) => fd.write(communicate, buf, len, this, finish)?,
+            (OpKind::Write { .. }, true) =>
+                fd.as_unix(this).pwrite(communicate, buf, len, offset, this, finish)?,
+            (OpKind::Poll, _) => unreachable!(),
+        }
+        if !this.machine.threads.active_thread_ref().is_enabled() {
+            throw_unsup_format!(
+                "io_uring: an operation on {name} blocked even though the file description was ready"
+            );
+        }
+        interp_ok(())
+    }
+
+    /// Registers interest in the readiness that the pending operations on `fd_num` wait for.
+    fn io_uring_update_interest(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        fd_num: FdNum,
+        fd_id: FdId,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let needs = ring
+            .pending
+            .borrow()
+            .iter()
+            .filter(|op| op.fd_num == fd_num)
+            .fold(Readiness::EMPTY, |needs, op| needs | op.needs);
+        let key = (fd_id, fd_num);
+        if needs == Readiness::EMPTY {
+            ring.watcher.remove_interest(key);
+            return interp_ok(());
+        }
+        if this.machine.fds.get(fd_num).is_none_or(|fd| fd.id() != fd_id) {
+            throw_unsup_format!(
+                "io_uring: closing a file descriptor with pending operations is unsupported"
+            );
+        }
+        if ring.watcher.update_interest(key, this, |interest| interest.relevant = needs)?.is_none()
+        {
+            let data = u64::try_from(fd_num).unwrap();
+            ring.watcher
+                .add_interest(fd_num, needs, /* is_edge_triggered */ false, data, this)?
+                .unwrap();
+        }
+        interp_ok(())
+    }
+
+    /// Carries out pending operations whose file descriptions became ready.
+    fn io_uring_run_ready(&mut self, ring: &FileDescriptionRef<IoUring>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let ready = ring.watcher.get_ready_interests(ring.watcher.ready_count(), this)?;
+        for interest in ready {
+            let fd_num = FdNum::try_from(interest.data).expect("data is always a file descriptor");
+            let active = *interest.active();
+            // Only run one operation per file description: it might consume the readiness. If the
+            // file description is still ready afterwards, the (level-triggered) interest stays
+            // ready and the next operation runs later.
+            let mut pending = ring.pending.borrow_mut();
+            let Some(idx) = pending
+                .iter()
+                .position(|op| op.fd_num == fd_num && op.needs & active != Readiness::EMPTY)
+            else {
+                continue;
+            };
+            let op = pending.remove(idx);
+            drop(pending);
+            let fd_id = op.fd.id();
+            this.io_uring_run(ring, op)?;
+            this.io_uring_update_interest(ring, fd_num, fd_id)?;
+        }
+        interp_ok(())
+    }
+
+    /// Runs `f` while collecting completions in a batch, and posts them afterwards.
+    fn io_uring_batch<T>(
+        &mut self,
+        ring: &FileDescriptionRef<IoUring>,
+        f: impl FnOnce(&mut MiriInterpCx<'tcx>) -> InterpResult<'tcx, T>,
+    ) -> InterpResult<'tcx, T> {
+        let this = self.eval_context_mut();
+        ring.batching.set(true);
+        let res = f(this)?;
+        ring.batching.set(false);
+        this.io_uring_flush(ring)?;
+        interp_ok(res)
+    }
+
+    /// Blocks until at least `min_complete` completions are available, and then returns
+    /// `submitted` from `io_uring_enter`.
+    fn io_uring_wait(
+        &mut self,
+        ring: FileDescriptionRef<IoUring>,
+        min_complete: u32,
+        submitted: u32,
+        dest: MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        loop {
+            if this.io_uring_cq_ready(&ring)? >= min_complete {
+                return this.write_int(submitted, &dest);
+            }
+            // Level-triggered interests that are still ready do not wake us up again, so carry out
+            // their operations before blocking.
+            if ring.watcher.ready_count() == 0 {
+                break;
+            }
+            this.io_uring_batch(&ring, |this| this.io_uring_run_ready(&ring))?;
+        }
+        // Wait for a pending operation to become ready.
+        ring.watcher.add_blocked_thread(this.active_thread());
+        this.block_thread(
+            BlockReason::Readiness,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    ring: FileDescriptionRef<IoUring>,
+                    min_complete: u32,
+                    submitted: u32,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    this.io_uring_batch(&ring, |this| this.io_uring_run_ready(&ring))?;
+                    this.io_uring_wait(ring, min_complete, submitted, dest)
+                }
+            ),
+        );
+        interp_ok(())
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Creates an io_uring instance with its submission and completion rings. Only the
+    /// `IORING_SETUP_CQSIZE` and `IORING_SETUP_CLAMP` flags are supported.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/io_uring_setup.2.html>
+    fn io_uring_setup(
+        &mut self,
+        entries: &OpTy<'tcx>,
+        params: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let mut entries = this.read_syscall_u32(entries)?;
+        let params = this.read_pointer(params)?;
+        this.check_ptr_access(
+            params,
+            Size::from_bytes(PARAMS_SIZE),
+            CheckInAllocMsg::MemoryAccess,
+        )?;
+        let (u32_layout, u64_layout) = (this.machine.layouts.u32, this.machine.layouts.u64);
+        let flags = this.read_scalar(&this.field_at(params, 8, u32_layout))?.to_u32()?;
+
+        let supported_flags = IORING_SETUP_CQSIZE | IORING_SETUP_CLAMP;
+        if flags & !supported_flags != 0 {
+            throw_unsup_format!(
+                "io_uring_setup: flags {:#x} are unsupported",
+                flags & !supported_flags
+            );
+        }
+        let clamp = flags & IORING_SETUP_CLAMP != 0;
+        if entries == 0 || (entries > IORING_MAX_ENTRIES && !clamp) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        entries = entries.min(IORING_MAX_ENTRIES);
+        let sq_entries = entries.next_power_of_two();
+        let cq_entries = if flags & IORING_SETUP_CQSIZE != 0 {
+            let cq_entries = this.read_scalar(&this.field_at(params, 4, u32_layout))?.to_u32()?;
+            if cq_entries == 0 || (cq_entries > IORING_MAX_CQ_ENTRIES && !clamp) {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            }
+            let cq_entries = cq_entries.min(IORING_MAX_CQ_ENTRIES).next_power_of_two();
+            if cq_entries < sq_entries {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            }
+            cq_entries
+        } else {
+            sq_entries.strict_mul(2)
+        };
+
+        let mut allocate_region = |size: u64| -> InterpResult<'tcx, Region> {
+            let size = size.next_multiple_of(this.machine.page_size);
+            let ptr = this.allocate_ptr(
+                Size::from_bytes(size),
+                this.machine.page_align(),
+                MiriMemoryKind::IoUring.into(),
+                AllocInit::Zero,
+            )?;
+            let alloc_id = ptr.provenance.get_alloc_id().unwrap();
+            interp_ok(Region { ptr: ptr.into(), alloc_id, size })
+        };
+        let sq_ring = allocate_region(SQ_ARRAY.strict_add(u64::from(sq_entries).strict_mul(4)))?;
+        let cq_ring =
+            allocate_region(CQ_CQES.strict_add(u64::from(cq_entries).strict_mul(CQE_SIZE)))?;
+        let sqes = allocate_region(u64::from(sq_entries).strict_mul(SQE_SIZE))?;
+
+        for (region, mask, ring_entries, entries) in [
+            (&sq_ring, SQ_RING_MASK, SQ_RING_ENTRIES, sq_entries),
+            (&cq_ring, CQ_RING_MASK, CQ_RING_ENTRIES, cq_entries),
+        ] {
+            this.write_int(entries.strict_sub(1), &this.ring_u32(region, mask))?;
+            this.write_int(entries, &this.ring_u32(region, ring_entries))?;
+        }
+
+        // Fill in `struct io_uring_params`.
+        let features = IORING_FEAT_NODROP | IORING_FEAT_SUBMIT_STABLE | IORING_FEAT_RW_CUR_POS;
+        this.write_int(sq_entries, &this.field_at(params, 0, u32_layout))?;
+        this.write_int(cq_entries, &this.field_at(params, 4, u32_layout))?;
+        this.write_int(features, &this.field_at(params, 20, u32_layout))?;
+        let sq_off =
+            [SQ_HEAD, SQ_TAIL, SQ_RING_MASK, SQ_RING_ENTRIES, SQ_FLAGS, SQ_DROPPED, SQ_ARRAY];
+        let cq_off =
+            [CQ_HEAD, CQ_TAIL, CQ_RING_MASK, CQ_RING_ENTRIES, CQ_OVERFLOW, CQ_CQES, CQ_FLAGS];
+        for (base, offsets) in [(40u64, sq_off), (80, cq_off)] {
+            for (idx, offset) in (0u64..).zip(offsets) {
+                let place = this.field_at(params, base.strict_add(idx.strict_mul(4)), u32_layout);
+                this.write_int(offset, &place)?;
+            }
+            // `resv1` and `user_addr`.
+            this.write_int(0, &this.field_at(params, base.strict_add(28), u32_layout))?;
+            this.write_int(0, &this.field_at(params, base.strict_add(32), u64_layout))?;
+        }
+
+        let ring = this.machine.fds.new_ref(IoUring {
+            sq_entries,
+            cq_entries,
+            sq_ring,
+            cq_ring,
+            sqes,
+            sq_head: Cell::new(0),
+            cq_tail: Cell::new(0),
+            pending: RefCell::new(Vec::new()),
+            batch: RefCell::new(Vec::new()),
+            batching: Cell::new(false),
+            overflow: RefCell::new(VecDeque::new()),
+            watcher: Rc::new(ReadinessWatcher::default()),
+        });
+        for region in [&ring.sq_ring, &ring.cq_ring, &ring.sqes] {
+            let state = RegionState {
+                ptr: region.ptr,
+                ring: FileDescriptionRef::downgrade(&ring),
+                mapped: None,
+            };
+            this.machine.io_uring_regions.regions.insert(region.alloc_id, state);
+        }
+        let fd = this.machine.fds.insert(ring);
+        interp_ok(Scalar::from_i32(fd))
+    }
+
+    /// Submits new operations and waits for completions. Only the `IORING_ENTER_GETEVENTS` flag is
+    /// supported.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/io_uring_enter.2.html>
+    fn io_uring_enter(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        to_submit: &OpTy<'tcx>,
+        min_complete: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        sig: &OpTy<'tcx>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_syscall_u32(fd)?.cast_signed();
+        let to_submit = this.read_syscall_u32(to_submit)?;
+        let min_complete = this.read_syscall_u32(min_complete)?;
+        let flags = this.read_syscall_u32(flags)?;
+        let sig = this.read_pointer(sig)?;
+
+        let Some(fd) = this.machine.fds.get(fd) else {
+            return this.set_errno_and_return_neg1(LibcError("EBADF"), dest);
+        };
+        let Some(ring) = fd.downcast::<IoUring>() else {
+            return this.set_errno_and_return_neg1(LibcError("EOPNOTSUPP"), dest);
+        };
+        if flags & !IORING_ENTER_GETEVENTS != 0 {
+            throw_unsup_format!(
+                "io_uring_enter: flags {:#x} are unsupported",
+                flags & !IORING_ENTER_GETEVENTS
+            );
+        }
+        if !this.ptr_is_null(sig)? {
+            throw_unsup_format!("io_uring_enter: changing the signal mask is unsupported");
+        }
+
+        let submitted = this.io_uring_batch(&ring, |this| {
+            // Make room for completions that did not fit into the completion ring before.
+            this.io_uring_post_completions(&ring)?;
+            let submitted = this.io_uring_submit(&ring, to_submit)?;
+            this.io_uring_run_ready(&ring)?;
+            interp_ok(submitted)
+        })?;
+
+        if flags & IORING_ENTER_GETEVENTS != 0 {
+            this.io_uring_wait(ring, min_complete, submitted, dest.clone())
+        } else {
+            this.write_int(submitted, dest)
+        }
+    }
+
+    /// Maps one of the regions of an io_uring instance, selected by `offset`.
+    fn io_uring_mmap(
+        &mut self,
+        ring: FileDescriptionRef<IoUring>,
+        length: u64,
+        flags: i32,
+        offset: i128,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let map_shared = this.eval_libc_i32("MAP_SHARED");
+        let map_populate = this.eval_libc_i32("MAP_POPULATE");
+        if flags & !map_populate != map_shared {
+            throw_unsup_format!(
+                "Miri only supports mapping io_uring rings with MAP_SHARED, optionally combined \
+                with MAP_POPULATE"
+            );
+        }
+        let region = match offset {
+            IORING_OFF_SQ_RING => &ring.sq_ring,
+            IORING_OFF_CQ_RING => &ring.cq_ring,
+            IORING_OFF_SQES => &ring.sqes,
+            _ => {
+                this.set_last_error(LibcError("EINVAL"))?;
+                return interp_ok(this.eval_libc("MAP_FAILED"));
+            }
+        };
+        if length > region.size {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        }
+        let length = length.next_multiple_of(this.machine.page_size);
+        let state = this.machine.io_uring_regions.regions.get_mut(&region.alloc_id).unwrap();
+        if state.mapped.replace(length).is_some() {
+            throw_unsup_format!("Miri does not support mapping an io_uring region more than once");
+        }
+        interp_ok(Scalar::from_maybe_pointer(region.ptr, this))
+    }
+
+    /// Unmaps the region of an io_uring instance at `addr`, or returns `None` if `addr` is not the
+    /// start of such a region. Miri keeps using the region until the instance is closed, so it is
+    /// only freed once that happened.
+    fn io_uring_munmap(
+        &mut self,
+        addr: Pointer,
+        length: u64,
+    ) -> InterpResult<'tcx, Option<Scalar>> {
+        let this = self.eval_context_mut();
+
+        let Ok((alloc_id, offset, _)) = this.ptr_try_get_alloc_id(addr, 0) else {
+            return interp_ok(None);
+        };
+        let Some(state) = this.machine.io_uring_regions.regions.get_mut(&alloc_id) else {
+            return interp_ok(None);
+        };
+        let Some(mapped) = state.mapped else {
+            throw_ub_format!("unmapping an io_uring region that is not mapped");
+        };
+        if offset != Size::ZERO || length != mapped {
+            throw_unsup_format!(
+                "Miri only supports unmapping a mapping of an io_uring region as a whole"
+            );
+        }
+        state.mapped = None;
+        this.io_uring_free_regions()?;
+        interp_ok(Some(Scalar::from_i32(0)))
+    }
+
+    /// Frees the regions of closed io_uring instances that the program does not have mapped.
+    fn io_uring_free_regions(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let unused: Vec<AllocId> = this
+            .machine
+            .io_uring_regions
+            .regions
+            .iter()
+            .filter(|(_, state)| state.mapped.is_none() && state.ring.is_closed())
+            .map(|(&alloc_id, _)| alloc_id)
+            .collect();
+        for alloc_id in unused {
+            let state = this.machine.io_uring_regions.regions.remove(&alloc_id).unwrap();
+            this.deallocate_ptr(state.ptr, None, MiriMemoryKind::IoUring.into())?;
+        }
+        interp_ok(())
+    }
+}
@@ -1,5 +1,9 @@
 pub mod epoll;
 pub mod eventfd;
+pub mod inotify;
+pub mod io_uring;
+pub mod signalfd;
 pub mod sync;
 pub mod syscall;
 pub mod thread;
+pub mod timerfd;
@@ -0,0 +1,237 @@
+//! Linux `signalfd` implementation, on top of Miri's emulation of signals.
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::ErrorKind;
+use std::iter;
+
+use rustc_abi::Size;
+
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::signal::SigSet;
+use crate::shims::unix::{UID, UnixFileDescription};
+use crate::*;
+
+/// A kind of file descriptor created by `signalfd`, from which the pending signals of its mask
+/// can be read instead of being delivered to a handler (they need to be blocked for that).
+///
+/// <https://man7.org/linux/man-pages/man2/signalfd.2.html>
+#[derive(Debug)]
+pub struct SignalFd {
+    mask: Cell<SigSet>,
+    is_nonblock: bool,
+    /// The thread which created the file description. Its pending signals are the ones the
+    /// readiness reflects, along with the ones pending for the whole process.
+    thread: ThreadId,
+    /// Whether a signal of the mask is pending, as of the last change of the pending signals.
+    readable: Cell<bool>,
+    /// A list of thread ids blocked on signalfd::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+    /// State for being watched by epoll.
+    watched: ReadinessWatched,
+}
+
+impl FileDescription for SignalFd {
+    fn name(&self) -> &'static str {
+        "signalfd"
+    }
+
+    fn metadata<'tcx>(
+        &self,
+    ) -> InterpResult<'tcx, Either<io::Result<std::fs::Metadata>, &'static str>> {
+        // Like eventfd, signalfd is an "anonymous inode" reported as S_IFREG.
+        interp_ok(Either::Right("S_IFREG"))
+    }
+
+    /// Reads as many `signalfd_siginfo` as fit into the buffer, blocking until a signal of the
+    /// mask is pending if there is none.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let siginfo_size = ecx.libc_ty_layout("signalfd_siginfo").size.bytes_usize();
+        if len < siginfo_size {
+            return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+        }
+        signalfd_read(ptr, len.strict_div(siginfo_size), self, ecx, finish)
+    }
+
+    fn readiness_watched(&self) -> Option<&ReadinessWatched> {
+        Some(&self.watched)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness { readable: self.readable.get(), ..Readiness::EMPTY }
+    }
+
+    fn as_unix<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _ecx: &MiriInterpCx<'tcx>,
+    ) -> FileDescriptionRef<dyn UnixFileDescription> {
+        self
+    }
+}
+
+impl UnixFileDescription for SignalFd {}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Creates a signalfd for the signals of `mask` if `fd` is -1, or else replaces the mask of
+    /// the signalfd `fd`.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/signalfd.2.html>
+    fn signalfd(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        mask: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let mask = this.read_sigset(&this.deref_sigset(mask)?)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        let sfd_cloexec = this.eval_libc_i32("SFD_CLOEXEC");
+        let sfd_nonblock = this.eval_libc_i32("SFD_NONBLOCK");
+
+        let mut is_nonblock = false;
+        if flags & sfd_cloexec == sfd_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !sfd_cloexec;
+        }
+        if flags & sfd_nonblock == sfd_nonblock {
+            flags &= !sfd_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+
+        let fd_value = if fd == -1 {
+            let signalfd = this.machine.fds.new_ref(SignalFd {
+                mask: Cell::new(mask),
+                is_nonblock,
+                thread: this.active_thread(),
+                readable: Cell::new(false),
+                blocked_read_tid: RefCell::new(Vec::new()),
+                watched: ReadinessWatched::default(),
+            });
+            this.machine.signals.signalfds.push(FileDescriptionRef::downgrade(&signalfd));
+            this.machine.fds.insert(signalfd)
+        } else {
+            let Some(signalfd) = this.machine.fds.get(fd) else {
+                return this.set_errno_and_return_neg1_i32(LibcError("EBADF"));
+            };
+            let Some(signalfd) = signalfd.downcast::<SignalFd>() else {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            };
+            // The flags only apply to new file descriptions.
+            signalfd.mask.set(mask);
+            fd
+        };
+        this.update_signalfds()?;
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// Updates the readiness of all signalfds, and wakes up their blocked readers. This must be
+    /// called whenever the set of pending signals changed.
+    fn update_signalfds(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let signals = &mut this.machine.signals;
+        signals.signalfds.retain(|signalfd| !signalfd.is_closed());
+        if signals.signalfds.is_empty() {
+            return interp_ok(());
+        }
+
+        let signalfds =
+            signals.signalfds.iter().filter_map(|signalfd| signalfd.upgrade()).collect::<Vec<_>>();
+        for signalfd in signalfds {
+            let pending = this.machine.signals.pending_for(signalfd.thread);
+            let readable = pending.intersection(signalfd.mask.get()).first().is_some();
+            let changed = readable != signalfd.readable.replace(readable);
+            if readable {
+                // Unblock *all* threads previously blocked on `read`; the first one to run takes
+                // the signal, the others block again.
+                let waiting_threads =
+                    std::mem::take(&mut *signalfd.blocked_read_tid.borrow_mut());
+                for thread_id in waiting_threads {
+                    this.unblock_thread(thread_id, BlockReason::Signalfd)?;
+                }
+            }
+            if changed {
+                this.update_fd_readiness(signalfd, ReadinessUpdateFlags::DEFAULT)?;
+            }
+        }
+        interp_ok(())
+    }
+}
+
+/// Block the thread if no signal of the mask is pending, else dequeue up to `count` of them and
+/// write their `signalfd_siginfo` into the buffer.
+fn signalfd_read<'tcx>(
+    ptr: Pointer,
+    count: usize,
+    signalfd: FileDescriptionRef<SignalFd>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let thread = ecx.active_thread();
+    let mask = signalfd.mask.get();
+    let signals = iter::from_fn(|| ecx.machine.signals.take_pending(thread, mask))
+        .take(count)
+        .collect::<Vec<_>>();
+
+    if signals.is_empty() {
+        if signalfd.is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        signalfd.blocked_read_tid.borrow_mut().push(thread);
+        ecx.block_thread(
+            BlockReason::Signalfd,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    ptr: Pointer,
+                    count: usize,
+                    signalfd: FileDescriptionRef<SignalFd>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    signalfd_read(ptr, count, signalfd, this, finish)
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+
+    let siginfo_layout = ecx.libc_ty_layout("signalfd_siginfo");
+    let mut written = 0usize;
+    for sig in signals {
+        let siginfo = ecx.ptr_to_mplace_unaligned(
+            ptr.wrapping_offset(Size::from_bytes(written), ecx),
+            siginfo_layout,
+        );
+        ecx.write_bytes_ptr(siginfo.ptr(), iter::repeat_n(0, siginfo_layout.size.bytes_usize()))?;
+        // All signals are sent by the process itself, with `kill`, `raise` or `pthread_kill`.
+        ecx.write_int_fields_named(
+            &[
+                ("ssi_signo", sig.into()),
+                ("ssi_code", ecx.eval_libc_i32("SI_USER").into()),
+                ("ssi_pid", ecx.get_pid().into()),
+                ("ssi_uid", UID.into()),
+            ],
+            &siginfo,
+        )?;
+        written = written.strict_add(siginfo_layout.size.bytes_usize());
+    }
+    ecx.update_signalfds()?;
+
+    finish.call(ecx, Ok(written))
+}
@@ -6,6 +6,7 @@
 use crate::shims::sig::check_min_vararg_count;
 use crate::shims::unix::env::EvalContextExt;
 use crate::shims::unix::linux_like::eventfd::EvalContextExt as _;
+use crate::shims::unix::linux_like::io_uring::EvalContextExt as _;
 use crate::shims::unix::linux_like::sync::futex;
 use crate::shims::unix::socket::EvalContextExt as _;
 use crate::*;
@@ -28,6 +29,9 @@ pub fn syscall<'tcx>(
     let sys_eventfd2 = ecx.eval_libc("SYS_eventfd2").to_target_usize(ecx)?;
     let sys_gettid = ecx.eval_libc("SYS_gettid").to_target_usize(ecx)?;
     let sys_accept4 = ecx.eval_libc("SYS_accept4").to_target_usize(ecx)?;
+    let sys_io_uring_setup = ecx.eval_libc("SYS_io_uring_setup").to_target_usize(ecx)?;
+    let sys_io_uring_enter = ecx.eval_libc("SYS_io_uring_enter").to_target_usize(ecx)?;
+    let sys_io_uring_register = ecx.eval_libc("SYS_io_uring_register").to_target_usize(ecx)?;
 
     match ecx.read_target_usize(op)? {
         // `libc::syscall(NR_GETRANDOM, buf.as_mut_ptr(), buf.len(), GRND_NONBLOCK)`
@@ -67,6 +71,20 @@ pub fn syscall<'tcx>(
                 check_min_vararg_count("syscall(SYS_accept4, ...)", varargs)?;
             ecx.accept4(socket, address, address_len, Some(flags), dest)?;
         }
+        num if num == sys_io_uring_setup => {
+            let [entries, params] =
+                check_min_vararg_count("syscall(SYS_io_uring_setup, ...)", varargs)?;
+            let result = ecx.io_uring_setup(entries, params)?;
+            ecx.write_int(result.to_i32()?, dest)?;
+        }
+        num if num == sys_io_uring_enter => {
+            let [fd, to_submit, min_complete, flags, sig] =
+                check_min_vararg_count("syscall(SYS_io_uring_enter, ...)", varargs)?;
+            ecx.io_uring_enter(fd, to_submit, min_complete, flags, sig, dest)?;
+        }
+        num if num == sys_io_uring_register => {
+            throw_unsup_format!("io_uring_register is unsupported");
+        }
         num => {
             throw_unsup_format!("syscall: unsupported syscall number {num}");
         }
@@ -0,0 +1,370 @@
+//! Linux `timerfd` implementation, driven by Miri's monotonic clock.
+use std::cell::RefCell;
+use std::io;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::time::system_time_to_duration;
+use crate::shims::unix::UnixFileDescription;
+use crate::*;
+
+/// A kind of file descriptor created by `timerfd_create`. Reading from it returns how many times
+/// the timer expired since the last read.
+///
+/// <https://man7.org/linux/man-pages/man2/timerfd_create.2.html>
+#[derive(Debug)]
+struct TimerFd {
+    /// The clock absolute expiration times refer to.
+    clock_kind: TimeoutClock,
+    state: RefCell<TimerState>,
+    is_nonblock: bool,
+    /// The clock of the last `timerfd_settime`, which readers synchronize with.
+    clock: RefCell<VClock>,
+    /// A list of thread ids blocked on timerfd::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+    /// State for being watched by epoll.
+    watched: ReadinessWatched,
+}
+
+#[derive(Debug, Default)]
+struct TimerState {
+    /// When the timer expires next, or `None` if it is disarmed.
+    next: Option<Instant>,
+    /// The period of the timer, or zero for a one-shot timer.
+    interval: Duration,
+    /// How many times the timer expired since the last read.
+    expirations: u64,
+}
+
+impl TimerState {
+    /// Accounts for all the expirations up to `now`.
+    fn refresh(&mut self, now: &Instant) {
+        let Some(next) = &self.next else { return };
+        if !next.duration_since(now.clone()).is_zero() {
+            return;
+        }
+        if self.interval.is_zero() {
+            self.expirations = self.expirations.saturating_add(1);
+            self.next = None;
+        } else {
+            let interval = self.interval.as_nanos();
+            let periods = now.duration_since(next.clone()).as_nanos().strict_div(interval);
+            let periods = periods.saturating_add(1);
+            self.expirations =
+                self.expirations.saturating_add(u64::try_from(periods).unwrap_or(u64::MAX));
+            self.next =
+                Some(next.add_lossy(Duration::from_nanos_u128(periods.saturating_mul(interval))));
+        }
+    }
+
+    /// The time left until the next expiration, zero if the timer is disarmed.
+    fn remaining(&self, now: &Instant) -> Duration {
+        self.next.as_ref().map_or(Duration::ZERO, |next| next.duration_since(now.clone()))
+    }
+}
+
+impl FileDescription for TimerFd {
+    fn name(&self) -> &'static str {
+        "timerfd"
+    }
+
+    fn metadata<'tcx>(
+        &self,
+    ) -> InterpResult<'tcx, Either<io::Result<std::fs::Metadata>, &'static str>> {
+        // Like eventfd, timerfd is an "anonymous inode" reported as S_IFREG.
+        interp_ok(Either::Right("S_IFREG"))
+    }
+
+    /// Read the number of expirations in the buffer, blocking until the timer expires if it did
+    /// not expire yet.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        // We're treating the buffer as a `u64`.
+        let ty = ecx.machine.layouts.u64;
+        if len < ty.size.bytes_usize() {
+            return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+        }
+
+        // Turn the pointer into a place at the right type.
+        let buf_place = ecx.ptr_to_mplace_unaligned(ptr, ty);
+
+        timerfd_read(buf_place, self, ecx, finish)
+    }
+
+    fn readiness_watched(&self) -> Option<&ReadinessWatched> {
+        Some(&self.watched)
+    }
+
+    fn readiness(&self) -> Readiness {
+        Readiness { readable: self.state.borrow().expirations != 0, ..Readiness::EMPTY }
+    }
+
+    fn deadline_reached(&self, now: &Instant) -> Option<Instant> {
+        let mut state = self.state.borrow_mut();
+        state.refresh(now);
+        state.next.clone()
+    }
+
+    fn as_unix<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _ecx: &MiriInterpCx<'tcx>,
+    ) -> FileDescriptionRef<dyn UnixFileDescription> {
+        self
+    }
+}
+
+impl UnixFileDescription for TimerFd {}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Looks up the timerfd `fd`, returning the errno to fail with if there is none.
+    fn timerfd_get(
+        &self,
+        fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<FileDescriptionRef<TimerFd>, IoError>> {
+        let this = self.eval_context_ref();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let Some(fd) = this.machine.fds.get(fd) else {
+            return interp_ok(Err(LibcError("EBADF")));
+        };
+        interp_ok(fd.downcast::<TimerFd>().ok_or(LibcError("EINVAL")))
+    }
+
+    /// Writes the current setting of `timerfd` into the `itimerspec` `dest`.
+    fn write_itimerspec(&mut self, timerfd: &TimerFd, dest: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let now = this.machine.monotonic_clock.now();
+        let mut state = timerfd.state.borrow_mut();
+        state.refresh(&now);
+        let fields = [("it_value", state.remaining(&now)), ("it_interval", state.interval)];
+        for (field, duration) in fields {
+            let timespec = this.project_field_named(dest, field)?;
+            this.write_int_fields_named(
+                &[
+                    ("tv_sec", duration.as_secs().into()),
+                    ("tv_nsec", duration.subsec_nanos().into()),
+                ],
+                &timespec,
+            )?;
+        }
+        interp_ok(())
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Creates a timer which is initially disarmed. Only `CLOCK_MONOTONIC` and `CLOCK_REALTIME`
+    /// are supported, and the two only differ for absolute expiration times: Miri does not
+    /// support changes of the system clock, so `TFD_TIMER_CANCEL_ON_SET` is unsupported as well.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_create.2.html>
+    fn timerfd_create(
+        &mut self,
+        clockid: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let clockid = this.read_scalar(clockid)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        let Some(clock_kind) = this.parse_clockid(clockid) else {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        };
+
+        let tfd_cloexec = this.eval_libc_i32("TFD_CLOEXEC");
+        let tfd_nonblock = this.eval_libc_i32("TFD_NONBLOCK");
+
+        let mut is_nonblock = false;
+        if flags & tfd_cloexec == tfd_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !tfd_cloexec;
+        }
+        if flags & tfd_nonblock == tfd_nonblock {
+            flags &= !tfd_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+
+        let fd_value = this.machine.fds.insert_new(TimerFd {
+            clock_kind,
+            state: RefCell::new(TimerState::default()),
+            is_nonblock,
+            clock: RefCell::new(VClock::default()),
+            blocked_read_tid: RefCell::new(Vec::new()),
+            watched: ReadinessWatched::default(),
+        });
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// Arms or disarms the timer, and writes its previous setting into `old_value` if it is not
+    /// null. Arming the timer resets the number of expirations.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_settime.2.html>
+    fn timerfd_settime(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        new_value: &OpTy<'tcx>,
+        old_value: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let timerfd = match this.timerfd_get(fd)? {
+            Ok(timerfd) => timerfd,
+            Err(err) => return this.set_errno_and_return_neg1_i32(err),
+        };
+        let flags = this.read_scalar(flags)?.to_i32()?;
+        let itimerspec_layout = this.libc_ty_layout("itimerspec");
+        let new_value = this.deref_pointer_as(new_value, itimerspec_layout)?;
+        let old_value = this.read_pointer(old_value)?;
+
+        let tfd_timer_abstime = this.eval_libc_i32("TFD_TIMER_ABSTIME");
+        let tfd_timer_cancel_on_set = this.eval_libc_i32("TFD_TIMER_CANCEL_ON_SET");
+        if flags & tfd_timer_cancel_on_set != 0 {
+            throw_unsup_format!("timerfd_settime: TFD_TIMER_CANCEL_ON_SET is unsupported");
+        }
+        if flags & !tfd_timer_abstime != 0 {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+
+        let value = this.project_field_named(&new_value, "it_value")?;
+        let interval = this.project_field_named(&new_value, "it_interval")?;
+        let (Some(value), Some(interval)) =
+            (this.read_timespec(&value)?, this.read_timespec(&interval)?)
+        else {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        };
+
+        if !this.ptr_is_null(old_value)? {
+            let old_value = this.ptr_to_mplace(old_value, itimerspec_layout);
+            this.write_itimerspec(&timerfd, &old_value)?;
+        }
+
+        let now = this.machine.monotonic_clock.now();
+        let next = if value.is_zero() {
+            // A zero `it_value` disarms the timer.
+            None
+        } else if flags & tfd_timer_abstime == 0 {
+            Some(now.add_lossy(value))
+        } else {
+            // Absolute times are relative to the epoch of the timer's clock.
+            match timerfd.clock_kind {
+                TimeoutClock::Monotonic =>
+                    Some(this.machine.monotonic_clock.epoch().add_lossy(value)),
+                TimeoutClock::RealTime => {
+                    this.check_no_isolation("`timerfd_settime` with `REALTIME` clocks")?;
+                    let since_epoch =
+                        system_time_to_duration(&this.machine.monotonic_clock.system_time_now())?;
+                    Some(now.add_lossy(value.saturating_sub(since_epoch)))
+                }
+            }
+        };
+
+        *timerfd.state.borrow_mut() = TimerState { next: next.clone(), interval, expirations: 0 };
+        // Readers synchronize with the thread which armed the timer.
+        this.release_clock(|clock| {
+            timerfd.clock.borrow_mut().join(clock);
+        })?;
+        if let Some(next) = next {
+            this.machine.delayed_readiness_updates.add_deadline(timerfd.clone(), next);
+        }
+
+        // Blocked readers need to wait for the new expiration time.
+        let waiting_threads = std::mem::take(&mut *timerfd.blocked_read_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::Timerfd)?;
+        }
+        this.update_fd_readiness(timerfd, ReadinessUpdateFlags::DEFAULT)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Writes the time left until the next expiration and the period of the timer into
+    /// `curr_value`.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_gettime.2.html>
+    fn timerfd_gettime(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        curr_value: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let timerfd = match this.timerfd_get(fd)? {
+            Ok(timerfd) => timerfd,
+            Err(err) => return this.set_errno_and_return_neg1_i32(err),
+        };
+        let curr_value = this.deref_pointer_as(curr_value, this.libc_ty_layout("itimerspec"))?;
+        this.write_itimerspec(&timerfd, &curr_value)?;
+        // Expirations might have been accounted for just now.
+        this.update_fd_readiness(timerfd, ReadinessUpdateFlags::DEFAULT)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+}
+
+/// Block the thread until the timer expires if it did not expire since the last read, else return
+/// the number of expirations and reset it.
+fn timerfd_read<'tcx>(
+    buf_place: MPlaceTy<'tcx>,
+    timerfd: FileDescriptionRef<TimerFd>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let now = ecx.machine.monotonic_clock.now();
+    let mut state = timerfd.state.borrow_mut();
+    state.refresh(&now);
+    let expirations = std::mem::take(&mut state.expirations);
+
+    if expirations == 0 {
+        if timerfd.is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        // Block until the next expiration. If the timer is disarmed, only `timerfd_settime` can
+        // unblock us.
+        let deadline = state.next.clone().map(Deadline::from);
+        drop(state);
+        timerfd.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+        ecx.block_thread(
+            BlockReason::Timerfd,
+            deadline,
+            callback!(
+                @capture<'tcx> {
+                    buf_place: MPlaceTy<'tcx>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                    timerfd: FileDescriptionRef<TimerFd>,
+                }
+                |this, unblock: UnblockKind| {
+                    if unblock == UnblockKind::TimedOut {
+                        let thread = this.active_thread();
+                        timerfd.blocked_read_tid.borrow_mut().retain(|&id| id != thread);
+                    }
+                    timerfd_read(buf_place, timerfd, this, finish)
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+    drop(state);
+
+    // Synchronize with the `timerfd_settime` which armed the timer.
+    ecx.acquire_clock(&timerfd.clock.borrow())?;
+    ecx.write_int(expirations, &buf_place)?;
+    ecx.update_fd_readiness(timerfd, ReadinessUpdateFlags::DEFAULT)?;
+
+    // Tell userspace how many bytes we put into the buffer.
+    finish.call(ecx, Ok(buf_place.layout.size.bytes_usize()))
+}
@@ -5,7 +5,8 @@
 //!
 //! mmap/munmap behave a lot like alloc/dealloc, and for simple use they are exactly
 //! equivalent. That is the only part we support: no MAP_FIXED or MAP_SHARED or anything
-//! else that goes beyond a basic allocation API.
+//! else that goes beyond a basic allocation API. The only exception is mapping the rings of an
+//! io_uring instance, which already live in memory allocated by Miri.
 //!
 //! Note that in addition to only supporting malloc-like calls to mmap, we only support free-like
 //! calls to munmap, but for a very different reason. In principle, according to the man pages, it
@@ -17,6 +18,7 @@
 use rustc_abi::Size;
 use rustc_target::spec::Os;
 
+use crate::shims::unix::linux_like::io_uring::{EvalContextExt as _, IoUring};
 use crate::*;
 
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
@@ -67,6 +69,11 @@ fn mmap(
         // to work. It is possible that POSIX gives us enough leeway to return an error, but the
         // outcome for the user (I need to add cfg(miri)) is the same, just more frustrating.
         if fd != -1 {
+            // The rings of an io_uring instance are the exception: they live in Miri-owned memory.
+            if let Some(ring) = this.machine.fds.get(fd).and_then(|fd| fd.downcast::<IoUring>()) {
+                verify_prot(this, prot)?;
+                return this.io_uring_mmap(ring, length, flags, offset);
+            }
             throw_unsup_format!("Miri does not support file-backed memory mappings");
         }
 
@@ -126,6 +133,11 @@ fn munmap(&mut self, addr: &OpTy<'tcx>, length: &OpTy<'tcx>) -> InterpResult<'tc
             return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
         };
 
+        // The rings of an io_uring instance are still used by Miri.
+        if let Some(result) = this.io_uring_munmap(addr, length)? {
+            return interp_ok(result);
+        }
+
         let length = Size::from_bytes(length);
         this.deallocate_ptr(
             addr,
@@ -1,4 +1,6 @@
 pub mod foreign_items;
+pub mod process;
+pub mod signal;
 
 mod env;
 mod fd;
@@ -10,6 +12,7 @@
 mod sync;
 mod tcp_socket;
 mod thread;
+mod vfs;
 mod virtual_socket;
 
 mod android;
@@ -23,12 +26,14 @@
 pub use self::env::{EvalContextExt as _, UnixEnvVars};
 pub use self::fd::{EvalContextExt as _, UnixFileDescription};
 pub use self::fs::{DirTable, EvalContextExt as _};
+pub use self::linux_like::io_uring::IoUringRegions;
 pub use self::mem::EvalContextExt as _;
 pub use self::poll::EvalContextExt as _;
 pub use self::socket::EvalContextExt as _;
 pub use self::socket_address::EvalContextExt as _;
 pub use self::sync::EvalContextExt as _;
 pub use self::thread::{EvalContextExt as _, ThreadNameResult};
+pub use self::vfs::VirtualFs;
 pub use self::virtual_socket::EvalContextExt as _;
 
 // Make up some constants.
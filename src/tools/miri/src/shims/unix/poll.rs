@@ -143,36 +143,6 @@ fn poll(
 
         interp_ok(())
     }
-}
-
-impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
-trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
-    /// For all ready interests on the watcher, write the appropriate
-    /// readiness into the `revents` field of the associated poll interest.
-    fn write_ready_events(
-        &mut self,
-        watcher: Rc<ReadinessWatcher>,
-        interests: BTreeMap<FdNum, PollInterest<'tcx>>,
-    ) -> InterpResult<'tcx, u32> {
-        let this = self.eval_context_mut();
-
-        // Counts the number of poll interests that are fulfilled.
-        let mut fulfilled_interests = 0u32;
-
-        // Iterate over all ready interests of the watcher and
-        // write the output readiness of all related poll interests.
-        for ready in watcher.get_ready_interests(watcher.ready_count(), this)? {
-            let fd_num = FdNum::try_from(ready.data).expect("Data is always a file descriptor");
-            let interest = interests.get(&fd_num).expect("Interest should exist");
-
-            fulfilled_interests = fulfilled_interests.strict_add(1);
-
-            let poll_events = this.readiness_to_poll_bitflag(ready.active());
-            this.write_scalar(Scalar::from_u16(poll_events), &interest.revents_place)?;
-        }
-
-        interp_ok(fulfilled_interests)
-    }
 
     /// Convert a [`Readiness`] instance into the corresponding poll
     /// readiness bitflag.
@@ -263,3 +233,33 @@ fn poll_bitflag_to_readiness(&self, mut bitflag: u16) -> InterpResult<'tcx, Read
         interp_ok(readiness)
     }
 }
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// For all ready interests on the watcher, write the appropriate
+    /// readiness into the `revents` field of the associated poll interest.
+    fn write_ready_events(
+        &mut self,
+        watcher: Rc<ReadinessWatcher>,
+        interests: BTreeMap<FdNum, PollInterest<'tcx>>,
+    ) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_mut();
+
+        // Counts the number of poll interests that are fulfilled.
+        let mut fulfilled_interests = 0u32;
+
+        // Iterate over all ready interests of the watcher and
+        // write the output readiness of all related poll interests.
+        for ready in watcher.get_ready_interests(watcher.ready_count(), this)? {
+            let fd_num = FdNum::try_from(ready.data).expect("Data is always a file descriptor");
+            let interest = interests.get(&fd_num).expect("Interest should exist");
+
+            fulfilled_interests = fulfilled_interests.strict_add(1);
+
+            let poll_events = this.readiness_to_poll_bitflag(ready.active());
+            this.write_scalar(Scalar::from_u16(poll_events), &interest.revents_place)?;
+        }
+
+        interp_ok(fulfilled_interests)
+    }
+}
@@ -0,0 +1,637 @@
+//! Emulation of `posix_spawn` and `waitpid`, for programs that spawn child processes.
+//!
+//! Miri cannot run arbitrary host binaries as children of the interpreted program, but it can run
+//! another instance of itself. That is what happens when the program spawns the binary it is
+//! running itself (`std::env::current_exe` returns the Miri binary), or a binary built by
+//! `cargo miri` (which is a JSON file describing how to run the crate in Miri). The child is then
+//! interpreted in a new Miri process with the given arguments and environment.
+//!
+//! The standard streams of the child can be redirected to the pipes and socketpairs of the
+//! program. Those only exist inside Miri, so host threads copy the data between the child and the
+//! buffers of these virtual sockets; the interpreter moves it in and out of the buffers whenever
+//! it polls for host I/O events.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+use std::{env, thread};
+
+use rustc_data_structures::fx::FxHashMap;
+
+use super::virtual_socket::is_virtual_socket;
+use crate::shims::files::{DynFileDescriptionRef, FileHandle, NullOutput, Stderr, Stdin, Stdout};
+use crate::shims::os_str::bytes_to_os_str;
+use crate::*;
+
+/// How often to check whether child processes have terminated while all threads are blocked.
+/// Unlike their output, the termination of a child is not an event we can poll for.
+pub const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Flags of Miri that are not passed on to a child that runs the same program: a debugger and a
+/// replayed trace belong to the parent, and the outputs of the child would overwrite those of the
+/// parent.
+const PARENT_ONLY_FLAGS: &[&str] = &[
+    "-Zmiri-gdb-server",
+    "-Zmiri-record-trace",
+    "-Zmiri-replay-trace",
+    "-Zmiri-coverage",
+    "-Zmiri-heap-profile",
+    "-Zmiri-leak-report",
+];
+
+/// An action of a `posix_spawn_file_actions_t`, which is performed in the child before it starts.
+#[derive(Debug, Clone)]
+enum FileAction {
+    /// Make `newfd` refer to the file description of `fd`.
+    Dup2 {
+        fd: i32,
+        newfd: i32,
+    },
+    Close(i32),
+    Chdir(PathBuf),
+}
+
+/// A standard stream of a child process that is connected to a virtual socket of the program.
+#[derive(Debug)]
+enum Bridge {
+    /// The child writes to the socket. A host thread reads the output of the child and sends it
+    /// here; it is moved into the socket as space becomes available.
+    Output {
+        socket: DynFileDescriptionRef,
+        data: Receiver<Vec<u8>>,
+        pending: VecDeque<u8>,
+        /// Whether the child has closed the stream.
+        eof: bool,
+    },
+    /// The child reads from the socket. Everything written to the socket is sent to a host
+    /// thread, which writes it to the child.
+    Input { socket: DynFileDescriptionRef, data: Sender<Vec<u8>> },
+}
+
+/// The state of all child processes.
+#[derive(Debug, Default)]
+pub struct ChildProcesses {
+    /// The host directory this instance of Miri was started in and its arguments (up to `--`).
+    /// The same program is spawned by starting Miri again in this way.
+    invocation: Option<(PathBuf, Vec<String>)>,
+    /// The actions of each `posix_spawn_file_actions_t`, which stores the key into this map.
+    file_actions: FxHashMap<u32, Vec<FileAction>>,
+    next_file_actions_id: u32,
+    /// The children that have not been waited for yet, by pid, with their exit status once they
+    /// terminated.
+    children: BTreeMap<u32, (Child, Option<ExitStatus>)>,
+    /// The streams of children connected to virtual sockets. They outlive the child until all
+    /// its output has been read.
+    bridges: Vec<Bridge>,
+    /// The threads blocked in `waitpid`. They are woken up whenever a child terminates.
+    waiting_threads: Vec<ThreadId>,
+}
+
+impl ChildProcesses {
+    pub fn new(invocation: Option<(PathBuf, Vec<String>)>) -> Self {
+        ChildProcesses { invocation, ..Default::default() }
+    }
+
+    /// Returns whether a child process is still running or connected to a virtual socket.
+    pub fn is_active(&self) -> bool {
+        !self.bridges.is_empty() || self.children.values().any(|(_, status)| status.is_none())
+    }
+
+    /// Returns whether `pid` is a child process that has not been waited for yet.
+    pub fn is_child(&self, pid: u32) -> bool {
+        self.children.contains_key(&pid)
+    }
+}
+
+/// Starts a host thread that reads everything from `from` and sends it over the returned channel.
+fn forward_output(mut from: impl Read + Send + 'static) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = vec![0; 0x1000];
+        loop {
+            match from.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) =>
+                    if sender.send(buf[..len].to_vec()).is_err() {
+                        // Nobody reads the output anymore.
+                        break;
+                    },
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+    receiver
+}
+
+/// Starts a host thread that writes everything sent over the returned channel to `to`, and closes
+/// `to` once the channel is closed.
+fn forward_input(mut to: impl Write + Send + 'static) -> Sender<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        for data in receiver {
+            if to.write_all(&data).is_err() {
+                // The child closed its end.
+                break;
+            }
+        }
+    });
+    sender
+}
+
+/// Converts the exit status of a child into the status reported by `waitpid`.
+fn wait_status(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.into_raw()
+    }
+    #[cfg(not(unix))]
+    {
+        // The status of a normal exit, with the exit code in the second byte.
+        (status.code().unwrap_or(1) & 0xff).strict_shl(8)
+    }
+}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Reads a null-terminated array of C strings, like `argv`.
+    fn read_c_str_array(&self, ptr: &OpTy<'tcx>) -> InterpResult<'tcx, Vec<Vec<u8>>> {
+        let this = self.eval_context_ref();
+        let mut place = this.deref_pointer_as(ptr, this.machine.layouts.mut_raw_ptr)?;
+        let mut strs = Vec::new();
+        loop {
+            let str = this.read_pointer(&place)?;
+            if this.ptr_is_null(str)? {
+                return interp_ok(strs);
+            }
+            strs.push(this.read_c_str(str)?.to_owned());
+            place = place.offset(place.layout.size, place.layout, this)?;
+        }
+    }
+
+    /// Returns the id that a `posix_spawn_file_actions_t` stores, if it was initialized.
+    fn file_actions_id(&self, file_actions: &OpTy<'tcx>) -> InterpResult<'tcx, Option<u32>> {
+        let this = self.eval_context_ref();
+        let place = this.deref_pointer_as(file_actions, this.machine.layouts.u32)?;
+        let id = this.read_scalar(&place)?.to_u32()?;
+        interp_ok(this.machine.child_processes.file_actions.contains_key(&id).then_some(id))
+    }
+
+    /// Adds an action to a `posix_spawn_file_actions_t`.
+    fn add_file_action(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        action: FileAction,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let Some(id) = this.file_actions_id(file_actions)? else {
+            return interp_ok(this.eval_libc("EINVAL"));
+        };
+        this.machine.child_processes.file_actions.get_mut(&id).unwrap().push(action);
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Finds the binary `posix_spawn` would execute for `program`, like `execvp` if `search_path`
+    /// is set.
+    fn find_program(
+        &mut self,
+        program: &Path,
+        search_path: bool,
+    ) -> InterpResult<'tcx, Option<PathBuf>> {
+        let this = self.eval_context_mut();
+        if !search_path || program.components().count() > 1 {
+            return interp_ok(program.exists().then(|| program.to_owned()));
+        }
+        let Some(path) = this.get_env_var(OsStr::new("PATH"))? else {
+            return interp_ok(None);
+        };
+        interp_ok(env::split_paths(&path).map(|dir| dir.join(program)).find(|file| file.is_file()))
+    }
+
+    /// Returns the command that runs `program` in a new instance of Miri, with the working
+    /// directory `cwd` and the environment `env_vars`.
+    fn child_command(
+        &self,
+        program: &Path,
+        cwd: &Path,
+        env_vars: Vec<(OsString, OsString)>,
+    ) -> InterpResult<'tcx, Command> {
+        let this = self.eval_context_ref();
+
+        let miri = env::current_exe().unwrap();
+        if fs::canonicalize(program).ok() == fs::canonicalize(&miri).ok()
+            && let Some((dir, args)) = &this.machine.child_processes.invocation
+        {
+            // The program runs itself. We start Miri again just like it was started for us, and
+            // tell it to switch to the working directory of the child.
+            let args = args.iter().skip(1).filter(|arg| {
+                !PARENT_ONLY_FLAGS.iter().any(|flag| {
+                    arg.strip_prefix(flag)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+                })
+            });
+            let mut cmd = Command::new(miri);
+            cmd.args(args).arg("--").current_dir(dir);
+            cmd.env_clear().envs(env_vars).env("MIRI_CWD", cwd);
+            return interp_ok(cmd);
+        }
+
+        // Binaries built by `cargo miri` are run by `cargo miri`, which tells us where it is.
+        let mut header = [0; 1];
+        let is_json = File::open(program).and_then(|mut file| file.read_exact(&mut header)).is_ok()
+            && header == *b"{";
+        if is_json && let Some(cargo_miri) = env::var_os("MIRI_CARGO_MIRI") {
+            let mut cmd = Command::new(cargo_miri);
+            cmd.arg("runner").arg(program).current_dir(cwd).env_clear().envs(env_vars);
+            return interp_ok(cmd);
+        }
+
+        throw_unsup_format!(
+            "spawning {} is not supported: Miri can only spawn the program it is running, or \
+            other binaries built by `cargo miri`",
+            program.display()
+        )
+    }
+
+    /// Returns how the standard stream `stream` of a child is connected when the program passes
+    /// it `fd`, and the virtual socket that needs to be bridged to the child.
+    fn child_stdio(
+        &self,
+        fd: Option<&DynFileDescriptionRef>,
+        stream: usize,
+        name: &str,
+    ) -> InterpResult<'tcx, io::Result<(Stdio, Option<DynFileDescriptionRef>)>> {
+        // Closing a standard stream of the child has the same effect as redirecting it to
+        // `/dev/null` for almost all programs.
+        let Some(fd) = fd else { return interp_ok(Ok((Stdio::null(), None))) };
+        let stdio = if fd.clone().downcast::<Stdin>().is_some() && stream == 0 {
+            Stdio::inherit()
+        } else if fd.clone().downcast::<Stdout>().is_some() {
+            if stream == 1 { Stdio::inherit() } else { Stdio::from(io::stdout()) }
+        } else if fd.clone().downcast::<Stderr>().is_some() {
+            if stream == 2 { Stdio::inherit() } else { Stdio::from(io::stderr()) }
+        } else if fd.clone().downcast::<NullOutput>().is_some() {
+            Stdio::null()
+        } else if let Some(file) = fd.clone().downcast::<FileHandle>() {
+            match file.file.try_clone() {
+                Ok(file) => Stdio::from(file),
+                Err(err) => return interp_ok(Err(err)),
+            }
+        } else if is_virtual_socket(fd) {
+            return interp_ok(Ok((Stdio::piped(), Some(fd.clone()))));
+        } else {
+            throw_unsup_format!(
+                "{name}: passing a {} as standard stream to a child process is not supported",
+                fd.name()
+            );
+        };
+        interp_ok(Ok((stdio, None)))
+    }
+
+    /// Waits for the child `pid`, or for any child if that is `None`.
+    fn waitpid_inner(
+        &mut self,
+        pid: Option<u32>,
+        status: Pointer,
+        no_hang: bool,
+        dest: MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let children = &mut this.machine.child_processes.children;
+
+        if pid.is_some_and(|pid| !children.contains_key(&pid)) || children.is_empty() {
+            return this.set_errno_and_return_neg1(LibcError("ECHILD"), &dest);
+        }
+        let terminated = children
+            .iter()
+            .filter(|(child_pid, _)| pid.is_none_or(|pid| pid == **child_pid))
+            .find_map(|(child_pid, (_, status))| Some((*child_pid, (*status)?)));
+
+        if let Some((child_pid, exit_status)) = terminated {
+            // The child has been waited for, so its pid is free again.
+            children.remove(&child_pid);
+            if !this.ptr_is_null(status)? {
+                let status = this.ptr_to_mplace(status, this.machine.layouts.i32);
+                this.write_scalar(Scalar::from_i32(wait_status(exit_status)), &status)?;
+            }
+            this.write_int(child_pid, &dest)
+        } else if no_hang {
+            this.write_int(0, &dest)
+        } else {
+            this.machine.child_processes.waiting_threads.push(this.active_thread());
+            this.block_thread(
+                BlockReason::ChildProcess,
+                None,
+                callback!(
+                    @capture<'tcx> {
+                        pid: Option<u32>,
+                        status: Pointer,
+                        no_hang: bool,
+                        dest: MPlaceTy<'tcx>,
+                    }
+                    |this, unblock: UnblockKind| {
+                        assert_eq!(unblock, UnblockKind::Ready);
+                        this.waitpid_inner(pid, status, no_hang, dest)
+                    }
+                ),
+            );
+            interp_ok(())
+        }
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn posix_spawn(
+        &mut self,
+        pid: &OpTy<'tcx>,
+        path: &OpTy<'tcx>,
+        file_actions: &OpTy<'tcx>,
+        attrp: &OpTy<'tcx>,
+        argv: &OpTy<'tcx>,
+        envp: &OpTy<'tcx>,
+        search_path: bool,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let name = if search_path { "`posix_spawnp`" } else { "`posix_spawn`" };
+
+        let pid = this.deref_pointer_as(pid, this.libc_ty_layout("pid_t"))?;
+        let program = this.read_path_from_c_str(this.read_pointer(path)?)?.into_owned();
+        let actions = if this.ptr_is_null(this.read_pointer(file_actions)?)? {
+            Vec::new()
+        } else {
+            let Some(id) = this.file_actions_id(file_actions)? else {
+                return interp_ok(this.eval_libc("EINVAL"));
+            };
+            this.machine.child_processes.file_actions[&id].clone()
+        };
+        // The attributes concern process groups, signal dispositions and scheduling. The child
+        // is an entirely separate instance of Miri, so none of them carry over anyway.
+        this.read_pointer(attrp)?;
+        let args = this.read_c_str_array(argv)?;
+        let env = this.read_c_str_array(envp)?;
+
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation(name, reject_with)?;
+            return interp_ok(this.eval_libc("EPERM"));
+        }
+        if this.machine.vfs.is_some() {
+            throw_unsup_format!(
+                "{name}: spawning processes is not supported with a virtual file system"
+            );
+        }
+
+        // Figure out what the standard streams and the working directory of the child are.
+        let mut streams = [0, 1, 2].map(|fd| this.machine.fds.get(fd));
+        let mut cwd = env::current_dir().unwrap();
+        for action in actions {
+            match action {
+                FileAction::Dup2 { fd, newfd } => {
+                    let Some(newfd) = usize::try_from(newfd).ok().filter(|&newfd| newfd < 3) else {
+                        throw_unsup_format!(
+                            "{name}: only the standard streams of a child process can be redirected"
+                        );
+                    };
+                    let source = match usize::try_from(fd) {
+                        Ok(stream) if stream < 3 => streams[stream].clone(),
+                        _ => this.machine.fds.get(fd),
+                    };
+                    let Some(source) = source else {
+                        return interp_ok(this.eval_libc("EBADF"));
+                    };
+                    streams[newfd] = Some(source);
+                }
+                FileAction::Close(fd) =>
+                    if let Ok(stream) = usize::try_from(fd)
+                        && stream < 3
+                    {
+                        streams[stream] = None;
+                    },
+                FileAction::Chdir(dir) => cwd = cwd.join(dir),
+            }
+        }
+
+        let Some(program) = this.find_program(&program, search_path)? else {
+            return interp_ok(this.eval_libc("ENOENT"));
+        };
+        let mut env_vars = Vec::new();
+        for var in &env {
+            let (name, value) = match var.iter().position(|&b| b == b'=') {
+                Some(pos) => (&var[..pos], &var[pos.strict_add(1)..]),
+                None => (&var[..], &[][..]),
+            };
+            let (name, value) = (bytes_to_os_str(name)?, bytes_to_os_str(value)?);
+            env_vars.push((name.to_owned(), value.to_owned()));
+        }
+        let mut cmd = this.child_command(&program, &cwd, env_vars)?;
+        for arg in args.iter().skip(1) {
+            cmd.arg(bytes_to_os_str(arg)?);
+        }
+        let mut sockets = [None, None, None];
+        for (stream, fd) in streams.iter().enumerate() {
+            let (stdio, socket) = match this.child_stdio(fd.as_ref(), stream, name)? {
+                Ok(stdio) => stdio,
+                Err(err) => return this.io_error_to_errnum(err),
+            };
+            match stream {
+                0 => cmd.stdin(stdio),
+                1 => cmd.stdout(stdio),
+                _ => cmd.stderr(stdio),
+            };
+            sockets[stream] = socket;
+        }
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => return this.io_error_to_errnum(err),
+        };
+        let [stdin, stdout, stderr] = sockets;
+        let bridges = &mut this.machine.child_processes.bridges;
+        if let Some(socket) = stdin {
+            let data = forward_input(child.stdin.take().unwrap());
+            bridges.push(Bridge::Input { socket, data });
+        }
+        let outputs = [
+            stdout.map(|socket| (socket, forward_output(child.stdout.take().unwrap()))),
+            stderr.map(|socket| (socket, forward_output(child.stderr.take().unwrap()))),
+        ];
+        for (socket, data) in outputs.into_iter().flatten() {
+            bridges.push(Bridge::Output { socket, data, pending: VecDeque::new(), eof: false });
+        }
+        let child_pid = child.id();
+        this.machine.child_processes.children.insert(child_pid, (child, None));
+        this.write_int(child_pid, &pid)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_init(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let place = this.deref_pointer_as(file_actions, this.machine.layouts.u32)?;
+        let processes = &mut this.machine.child_processes;
+        let id = processes.next_file_actions_id;
+        processes.next_file_actions_id = id.strict_add(1);
+        processes.file_actions.insert(id, Vec::new());
+        this.write_scalar(Scalar::from_u32(id), &place)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_destroy(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let Some(id) = this.file_actions_id(file_actions)? else {
+            return interp_ok(this.eval_libc("EINVAL"));
+        };
+        this.machine.child_processes.file_actions.remove(&id);
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_adddup2(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        fd: &OpTy<'tcx>,
+        newfd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let newfd = this.read_scalar(newfd)?.to_i32()?;
+        if fd < 0 || newfd < 0 {
+            return interp_ok(this.eval_libc("EBADF"));
+        }
+        this.add_file_action(file_actions, FileAction::Dup2 { fd, newfd })
+    }
+
+    fn posix_spawn_file_actions_addclose(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        if fd < 0 {
+            return interp_ok(this.eval_libc("EBADF"));
+        }
+        this.add_file_action(file_actions, FileAction::Close(fd))
+    }
+
+    fn posix_spawn_file_actions_addchdir(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        path: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let path = this.read_path_from_c_str(this.read_pointer(path)?)?.into_owned();
+        this.add_file_action(file_actions, FileAction::Chdir(path))
+    }
+
+    fn waitpid(
+        &mut self,
+        pid: &OpTy<'tcx>,
+        status: &OpTy<'tcx>,
+        options: &OpTy<'tcx>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid)?.to_int(this.libc_ty_layout("pid_t").size)?;
+        let status = this.read_pointer(status)?;
+        let options = this.read_scalar(options)?.to_i32()?;
+
+        let wnohang = this.eval_libc_i32("WNOHANG");
+        if options & !wnohang != 0 {
+            throw_unsup_format!("`waitpid`: only the `WNOHANG` option is supported");
+        }
+        let pid = match pid {
+            -1 => None,
+            // A pid that does not fit is not one of our children either.
+            pid if pid > 0 => Some(u32::try_from(pid).unwrap_or(u32::MAX)),
+            _ => throw_unsup_format!("`waitpid`: waiting for process groups is not supported"),
+        };
+        this.waitpid_inner(pid, status, options == wnohang, dest.clone())
+    }
+
+    /// Sends the signal `sig` to the child process `pid`.
+    fn kill_child(&mut self, pid: u32, sig: i32) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        if sig == 0 {
+            // Only checks whether the process exists.
+            return interp_ok(Scalar::from_i32(0));
+        }
+        if sig != this.eval_libc_i32("SIGKILL") {
+            throw_unsup_format!("`kill`: only `SIGKILL` can be sent to child processes");
+        }
+        let (child, _) = this.machine.child_processes.children.get_mut(&pid).unwrap();
+        match child.kill() {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(err) => this.set_errno_and_return_neg1_i32(err),
+        }
+    }
+
+    /// Runs the host side of child processes: moves data between them and the virtual sockets
+    /// they are connected to, and wakes up the threads waiting for them to terminate.
+    fn poll_child_processes(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let bridges = std::mem::take(&mut this.machine.child_processes.bridges);
+        let mut open = Vec::new();
+        for mut bridge in bridges {
+            let done = match &mut bridge {
+                Bridge::Output { socket, data, pending, eof } => {
+                    loop {
+                        match data.try_recv() {
+                            Ok(chunk) => pending.extend(chunk),
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => {
+                                *eof = true;
+                                break;
+                            }
+                        }
+                    }
+                    let readable = this.virtual_socket_write_from_host(socket, pending)?;
+                    !readable || (*eof && pending.is_empty())
+                }
+                Bridge::Input { socket, data } =>
+                    match this.virtual_socket_read_to_host(socket)? {
+                        Some(chunk) => !chunk.is_empty() && data.send(chunk).is_err(),
+                        None => true,
+                    },
+            };
+            if done {
+                let (Bridge::Output { socket, .. } | Bridge::Input { socket, .. }) = bridge;
+                this.virtual_socket_close_from_host(socket)?;
+            } else {
+                open.push(bridge);
+            }
+        }
+        // Unblocking threads may have spawned new children.
+        open.append(&mut this.machine.child_processes.bridges);
+        this.machine.child_processes.bridges = open;
+
+        let mut terminated = false;
+        for (child, status) in this.machine.child_processes.children.values_mut() {
+            if status.is_none()
+                && let Ok(Some(exit_status)) = child.try_wait()
+            {
+                *status = Some(exit_status);
+                terminated = true;
+            }
+        }
+        if terminated {
+            let waiting_threads = std::mem::take(&mut this.machine.child_processes.waiting_threads);
+            for thread in waiting_threads {
+                this.unblock_thread(thread, BlockReason::ChildProcess)?;
+            }
+        }
+        interp_ok(())
+    }
+}
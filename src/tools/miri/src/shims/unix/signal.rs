@@ -0,0 +1,975 @@
+//! Emulation of POSIX signals: actions, per-thread signal masks, pending signals and the
+//! invocation of handlers.
+//!
+//! Signals are only ever generated by the program itself, with `raise`, `kill` and `pthread_kill`.
+//! They get delivered at interruption points, i.e. when a shim returns (which is also where a
+//! kernel delivers them, on the way back from a syscall), or when they wake up a thread blocked in
+//! `pause`, `sigsuspend` or `sigwait`. Other blocking calls are not interrupted: the signal is
+//! delivered once they return, as if the handler had been installed with `SA_RESTART`. Signals
+//! which become unblocked when a handler returns wait for the next interruption point.
+
+use std::iter;
+
+use rustc_abi::{ExternAbi, FieldIdx};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::{mir, ty};
+use rustc_span::Symbol;
+
+use super::linux_like::signalfd::{EvalContextExt as _, SignalFd};
+use crate::concurrency::thread::ThreadLookupError;
+use crate::shims::files::WeakFileDescriptionRef;
+use crate::*;
+
+/// The shims which are not async-signal-safe, and hence must not be called by a signal handler:
+/// those Miri supports which are missing from the list in
+/// <https://pubs.opengroup.org/onlinepubs/9799919799/functions/V2_chap02.html#tag_16_04_03_03>.
+const NOT_ASYNC_SIGNAL_SAFE: &[&str] = &[
+    // Allocation
+    "malloc",
+    "calloc",
+    "realloc",
+    "reallocarray",
+    "free",
+    "posix_memalign",
+    "aligned_alloc",
+    "memalign",
+    // Environment
+    "getenv",
+    "setenv",
+    "unsetenv",
+    "getcwd",
+    // Directories
+    "opendir",
+    "readdir",
+    "readdir64",
+    "readdir_r",
+    "closedir",
+    // Threads and synchronization
+    "pthread_create",
+    "pthread_join",
+    "pthread_detach",
+    "pthread_key_create",
+    "pthread_key_delete",
+    "pthread_getspecific",
+    "pthread_setspecific",
+    "pthread_mutex_lock",
+    "pthread_mutex_trylock",
+    "pthread_mutex_unlock",
+    "pthread_rwlock_rdlock",
+    "pthread_rwlock_tryrdlock",
+    "pthread_rwlock_wrlock",
+    "pthread_rwlock_trywrlock",
+    "pthread_rwlock_unlock",
+    "pthread_cond_wait",
+    "pthread_cond_timedwait",
+    "pthread_cond_signal",
+    "pthread_cond_broadcast",
+    // Miscellaneous
+    "dlsym",
+    "exit",
+    "getpwuid_r",
+    "localtime_r",
+    "realpath",
+    "strerror_r",
+];
+
+/// Names of the signals, for diagnostics.
+const SIGNAL_NAMES: &[&str] = &[
+    "SIGHUP",
+    "SIGINT",
+    "SIGQUIT",
+    "SIGILL",
+    "SIGTRAP",
+    "SIGABRT",
+    "SIGBUS",
+    "SIGFPE",
+    "SIGKILL",
+    "SIGUSR1",
+    "SIGSEGV",
+    "SIGUSR2",
+    "SIGPIPE",
+    "SIGALRM",
+    "SIGTERM",
+    "SIGCHLD",
+    "SIGCONT",
+    "SIGSTOP",
+    "SIGTSTP",
+    "SIGTTIN",
+    "SIGTTOU",
+    "SIGURG",
+    "SIGXCPU",
+    "SIGXFSZ",
+    "SIGVTALRM",
+    "SIGPROF",
+    "SIGWINCH",
+    "SIGSYS",
+];
+
+/// A set of signals, where signal `n` is bit `n - 1`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SigSet(u64);
+
+impl SigSet {
+    fn bit(sig: i32) -> u64 {
+        1u64.strict_shl(u32::try_from(sig.strict_sub(1)).unwrap())
+    }
+
+    fn single(sig: i32) -> Self {
+        SigSet(Self::bit(sig))
+    }
+
+    fn contains(self, sig: i32) -> bool {
+        self.0 & Self::bit(sig) != 0
+    }
+
+    fn insert(&mut self, sig: i32) {
+        self.0 |= Self::bit(sig);
+    }
+
+    fn remove(&mut self, sig: i32) {
+        self.0 &= !Self::bit(sig);
+    }
+
+    fn union(self, other: SigSet) -> SigSet {
+        SigSet(self.0 | other.0)
+    }
+
+    pub(crate) fn intersection(self, other: SigSet) -> SigSet {
+        SigSet(self.0 & other.0)
+    }
+
+    fn difference(self, other: SigSet) -> SigSet {
+        SigSet(self.0 & !other.0)
+    }
+
+    /// The lowest-numbered signal of the set, which is the one to deliver first.
+    pub(crate) fn first(self) -> Option<i32> {
+        (self.0 != 0).then(|| i32::try_from(self.0.trailing_zeros()).unwrap().strict_add(1))
+    }
+}
+
+impl VisitProvenance for SigSet {
+    fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {}
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SigHandler {
+    Default,
+    Ignore,
+    Handler(Pointer),
+}
+
+/// An action installed with `sigaction` or `signal`.
+#[derive(Clone, Copy, Debug)]
+struct SigAction {
+    handler: SigHandler,
+    /// The signals blocked while the handler runs, besides the signal itself.
+    mask: SigSet,
+    /// The `SA_*` flags, as passed to `sigaction`.
+    flags: i32,
+}
+
+/// What happens to a signal whose action is `SIG_DFL`.
+enum DefaultAction {
+    Terminate,
+    Ignore,
+    Stop,
+}
+
+/// What a thread blocked with `BlockReason::Signal` waits for.
+#[derive(Clone, Copy, Debug)]
+enum SignalWait {
+    /// The delivery of any signal which isn't blocked (`pause` and `sigsuspend`).
+    Delivery,
+    /// Any signal of the set becoming pending (`sigwait`).
+    Pending(SigSet),
+}
+
+#[derive(Debug, Default)]
+struct ThreadSignals {
+    mask: SigSet,
+    pending: SigSet,
+    /// How many signal handlers are currently running on this thread.
+    handler_depth: u32,
+    /// The mask to restore once the handler of the next delivered signal returns, set by
+    /// `sigsuspend`.
+    suspended_mask: Option<SigSet>,
+    waiting: Option<SignalWait>,
+}
+
+impl ThreadSignals {
+    fn wakes_on(&self, sig: i32) -> bool {
+        match self.waiting {
+            Some(SignalWait::Delivery) => !self.mask.contains(sig),
+            Some(SignalWait::Pending(set)) => set.contains(sig),
+            None => false,
+        }
+    }
+}
+
+/// The signal-related state of the process and of its threads.
+#[derive(Debug, Default)]
+pub struct SignalState {
+    /// The actions installed by the program. Signals missing from this map have their default
+    /// action.
+    actions: FxHashMap<i32, SigAction>,
+    /// The signals generated for the whole process which no thread could take yet.
+    pending: SigSet,
+    threads: FxHashMap<ThreadId, ThreadSignals>,
+    /// The signalfds, whose readiness depends on the pending signals.
+    pub(crate) signalfds: Vec<WeakFileDescriptionRef<SignalFd>>,
+}
+
+impl SignalState {
+    fn thread(&mut self, thread: ThreadId) -> &mut ThreadSignals {
+        self.threads.entry(thread).or_default()
+    }
+
+    /// New threads start with the signal mask of the thread which created them.
+    pub fn thread_created(&mut self, parent: ThreadId, child: ThreadId) {
+        let mask = self.thread(parent).mask;
+        self.thread(child).mask = mask;
+    }
+
+    /// Whether `thread` is currently running a signal handler.
+    pub fn in_handler(&self, thread: ThreadId) -> bool {
+        self.threads.get(&thread).is_some_and(|signals| signals.handler_depth > 0)
+    }
+
+    /// The signals pending for `thread` or for the whole process.
+    pub(crate) fn pending_for(&self, thread: ThreadId) -> SigSet {
+        match self.threads.get(&thread) {
+            Some(signals) => signals.pending.union(self.pending),
+            None => self.pending,
+        }
+    }
+
+    /// The pending signals which `thread` doesn't block.
+    fn deliverable(&self, thread: ThreadId) -> SigSet {
+        match self.threads.get(&thread) {
+            Some(signals) => signals.pending.union(self.pending).difference(signals.mask),
+            None => self.pending,
+        }
+    }
+
+    /// Removes the first pending signal of `set` from the pending signals of `thread`, or else of
+    /// the process.
+    pub(crate) fn take_pending(&mut self, thread: ThreadId, set: SigSet) -> Option<i32> {
+        let signals = self.thread(thread);
+        if let Some(sig) = signals.pending.intersection(set).first() {
+            signals.pending.remove(sig);
+            return Some(sig);
+        }
+        let sig = self.pending.intersection(set).first()?;
+        self.pending.remove(sig);
+        Some(sig)
+    }
+
+    /// Picks the thread which takes a signal generated for the whole process: the sending thread
+    /// if it doesn't block it, or else a thread waiting for it. Returns `None` if the signal must
+    /// stay pending for the process.
+    fn process_signal_target(&self, sig: i32, sender: ThreadId) -> Option<ThreadId> {
+        if !self.threads.get(&sender).is_some_and(|signals| signals.mask.contains(sig)) {
+            return Some(sender);
+        }
+        let mut waiting =
+            self.threads.iter().filter(|(_, signals)| signals.wakes_on(sig)).map(|(&id, _)| id);
+        // Pick deterministically, the map is not ordered.
+        waiting.min()
+    }
+}
+
+impl VisitProvenance for SignalState {
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        let SignalState { actions, pending: _, threads: _, signalfds: _ } = self;
+        for action in actions.values() {
+            if let SigHandler::Handler(handler) = action.handler {
+                handler.visit_provenance(visit);
+            }
+        }
+    }
+}
+
+/// Stored in the frame of a signal handler, to undo the delivery once the handler returns.
+#[derive(Debug)]
+pub struct SignalHandlerFrame<'tcx> {
+    /// The signal mask of the thread before the delivery.
+    restore_mask: SigSet,
+    /// The `siginfo_t` passed to handlers installed with `SA_SIGINFO`.
+    siginfo: Option<MPlaceTy<'tcx>>,
+}
+
+impl VisitProvenance for SignalHandlerFrame<'_> {
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        let SignalHandlerFrame { restore_mask: _, siginfo } = self;
+        siginfo.visit_provenance(visit);
+    }
+}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn is_valid_signal(&self, sig: i32) -> bool {
+        let this = self.eval_context_ref();
+        let max = this.libc_ty_layout("sigset_t").size.bits().min(64);
+        sig >= 1 && u64::try_from(sig).unwrap() <= max
+    }
+
+    /// `SIGKILL` and `SIGSTOP` can neither be caught, ignored nor blocked.
+    fn unblockable_signals(&self) -> SigSet {
+        let this = self.eval_context_ref();
+        SigSet::single(this.eval_libc_i32("SIGKILL"))
+            .union(SigSet::single(this.eval_libc_i32("SIGSTOP")))
+    }
+
+    fn signal_name(&self, sig: i32) -> String {
+        let this = self.eval_context_ref();
+        match SIGNAL_NAMES.iter().find(|name| this.eval_libc_i32(name) == sig) {
+            Some(name) => name.to_string(),
+            None => format!("signal {sig}"),
+        }
+    }
+
+    fn default_action(&self, sig: i32) -> DefaultAction {
+        let this = self.eval_context_ref();
+        let is = |names: &[&str]| names.iter().any(|name| this.eval_libc_i32(name) == sig);
+        if is(&["SIGCHLD", "SIGURG", "SIGWINCH", "SIGCONT"]) {
+            DefaultAction::Ignore
+        } else if is(&["SIGSTOP", "SIGTSTP", "SIGTTIN", "SIGTTOU"]) {
+            DefaultAction::Stop
+        } else {
+            DefaultAction::Terminate
+        }
+    }
+
+    fn is_ignored(&self, sig: i32) -> bool {
+        let this = self.eval_context_ref();
+        match this.machine.signals.actions.get(&sig).map(|action| action.handler) {
+            Some(SigHandler::Ignore) => true,
+            Some(SigHandler::Handler(_)) => false,
+            Some(SigHandler::Default) | None =>
+                matches!(this.default_action(sig), DefaultAction::Ignore),
+        }
+    }
+
+    /// The places of the integers making up a `sigset_t`, whose exact definition differs between
+    /// targets: each word holds the bits of the next signals, from the least significant bit.
+    fn sigset_words(&self, set: &MPlaceTy<'tcx>) -> InterpResult<'tcx, Vec<MPlaceTy<'tcx>>> {
+        let this = self.eval_context_ref();
+        let mut place = set.clone();
+        loop {
+            match place.layout.ty.kind() {
+                ty::Int(_) | ty::Uint(_) => return interp_ok(vec![place]),
+                ty::Array(..) => {
+                    let mut words = Vec::new();
+                    let mut elems = this.project_array_fields(&place)?;
+                    while let Some((_idx, elem)) = elems.next(this)? {
+                        words.push(elem);
+                    }
+                    return interp_ok(words);
+                }
+                ty::Adt(..) => place = this.project_field(&place, FieldIdx::ZERO)?,
+                _ => bug!("unexpected `sigset_t` type {}", place.layout.ty),
+            }
+        }
+    }
+
+    fn write_sigset(&mut self, set: SigSet, dest: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let mut offset = 0u32;
+        for word in this.sigset_words(dest)? {
+            let size = word.layout.size;
+            let value = set.0.checked_shr(offset).unwrap_or(0);
+            this.write_scalar(Scalar::from_uint(size.truncate(value.into()), size), &word)?;
+            offset = offset.strict_add(u32::try_from(size.bits()).unwrap());
+        }
+        interp_ok(())
+    }
+
+    fn sighandler_to_scalar(&self, handler: SigHandler) -> Scalar {
+        let this = self.eval_context_ref();
+        match handler {
+            SigHandler::Default => this.eval_libc("SIG_DFL"),
+            SigHandler::Ignore => this.eval_libc("SIG_IGN"),
+            SigHandler::Handler(ptr) => Scalar::from_maybe_pointer(ptr, this),
+        }
+    }
+
+    fn scalar_to_sighandler(&self, handler: Scalar) -> InterpResult<'tcx, SigHandler> {
+        let this = self.eval_context_ref();
+        let sig_dfl = this.eval_libc("SIG_DFL").to_target_usize(this)?;
+        let sig_ign = this.eval_libc("SIG_IGN").to_target_usize(this)?;
+        interp_ok(match handler.to_target_usize(this).discard_err() {
+            Some(addr) if addr == sig_dfl => SigHandler::Default,
+            Some(addr) if addr == sig_ign => SigHandler::Ignore,
+            _ => SigHandler::Handler(handler.to_pointer(this)?),
+        })
+    }
+
+    /// Installs `action` for `sig`, returning the previous action.
+    fn set_sigaction(&mut self, sig: i32, action: SigAction) -> SigAction {
+        let this = self.eval_context_mut();
+        if matches!(action.handler, SigHandler::Ignore) {
+            // Setting the action of a pending signal to `SIG_IGN` discards it.
+            let signals = &mut this.machine.signals;
+            signals.pending.remove(sig);
+            for thread in signals.threads.values_mut() {
+                thread.pending.remove(sig);
+            }
+        }
+        this.machine.signals.actions.insert(sig, action).unwrap_or(SigAction {
+            handler: SigHandler::Default,
+            mask: SigSet::default(),
+            flags: 0,
+        })
+    }
+
+    /// Generates `sig` for `target`, or for the whole process if `target` is `None`.
+    fn generate_signal(&mut self, sig: i32, target: Option<ThreadId>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let target = match target {
+            Some(thread) => Some(thread),
+            None => this.machine.signals.process_signal_target(sig, this.active_thread()),
+        };
+        // Ignored signals are discarded right away, unless they are blocked: they may then still
+        // be accepted with `sigwait` or `signalfd`, or the action may change before they get
+        // unblocked.
+        let blocked = |state: &SignalState, thread: ThreadId| {
+            state.threads.get(&thread).is_some_and(|signals| signals.mask.contains(sig))
+        };
+        if this.is_ignored(sig)
+            && target.is_some_and(|thread| !blocked(&this.machine.signals, thread))
+        {
+            return interp_ok(());
+        }
+
+        let Some(thread) = target else {
+            this.machine.signals.pending.insert(sig);
+            return this.update_signalfds();
+        };
+        let signals = this.machine.signals.thread(thread);
+        signals.pending.insert(sig);
+        if signals.wakes_on(sig) {
+            signals.waiting = None;
+            this.unblock_thread(thread, BlockReason::Signal)?;
+        }
+        this.update_signalfds()
+    }
+
+    /// Blocks the active thread until a signal is delivered to it, for `pause` and `sigsuspend`.
+    fn wait_for_signal_delivery(&mut self, dest: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        // These functions only ever return once a handler ran, and they then fail with `EINTR`.
+        this.set_errno_and_return_neg1(LibcError("EINTR"), dest)?;
+
+        let thread = this.active_thread();
+        if this.machine.signals.deliverable(thread).first().is_some() {
+            // The signal is delivered when the shim returns.
+            return interp_ok(());
+        }
+        this.machine.signals.thread(thread).waiting = Some(SignalWait::Delivery);
+        this.block_thread(
+            BlockReason::Signal,
+            None,
+            callback!(
+                @capture<'tcx> {}
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    this.deliver_pending_signal()
+                }
+            ),
+        );
+        interp_ok(())
+    }
+
+    fn update_signal_mask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<(), IoError>> {
+        let this = self.eval_context_mut();
+
+        let how = this.read_scalar(how)?.to_i32()?;
+        let set = this.read_pointer(set)?;
+        let oldset = this.read_pointer(oldset)?;
+
+        let sigset_layout = this.libc_ty_layout("sigset_t");
+        let thread = this.active_thread();
+        let mask = this.machine.signals.thread(thread).mask;
+        if !this.ptr_is_null(oldset)? {
+            this.write_sigset(mask, &this.ptr_to_mplace(oldset, sigset_layout))?;
+        }
+        if this.ptr_is_null(set)? {
+            return interp_ok(Ok(()));
+        }
+
+        let set = this.read_sigset(&this.ptr_to_mplace(set, sigset_layout))?;
+        let mask = if how == this.eval_libc_i32("SIG_BLOCK") {
+            mask.union(set)
+        } else if how == this.eval_libc_i32("SIG_UNBLOCK") {
+            mask.difference(set)
+        } else if how == this.eval_libc_i32("SIG_SETMASK") {
+            set
+        } else {
+            return interp_ok(Err(LibcError("EINVAL")));
+        };
+        // Trying to block the signals which can't be blocked is silently ignored. The signals
+        // this unblocks are delivered when the shim returns.
+        this.machine.signals.thread(thread).mask = mask.difference(this.unblockable_signals());
+        interp_ok(Ok(()))
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn deref_sigset(&self, set: &OpTy<'tcx>) -> InterpResult<'tcx, MPlaceTy<'tcx>> {
+        let this = self.eval_context_ref();
+        this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))
+    }
+
+    fn read_sigset(&self, set: &MPlaceTy<'tcx>) -> InterpResult<'tcx, SigSet> {
+        let this = self.eval_context_ref();
+        let mut bits = 0u64;
+        let mut offset = 0u32;
+        for word in this.sigset_words(set)? {
+            let size = word.layout.size;
+            let value = this.read_scalar(&word)?.to_bits(size)?;
+            // Only the bits of the first 64 signals matter, Miri doesn't support the others.
+            if let Some(value) = u64::try_from(value).unwrap().checked_shl(offset) {
+                bits |= value;
+            }
+            offset = offset.strict_add(u32::try_from(size.bits()).unwrap());
+        }
+        interp_ok(SigSet(bits))
+    }
+
+    /// Reports the calls of shims which are not async-signal-safe from a signal handler.
+    fn check_async_signal_safety(&self, link_name: Symbol) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        if !this.machine.signals.in_handler(this.active_thread()) {
+            return interp_ok(());
+        }
+        if this.machine.allocator_shim_symbols.contains_key(&link_name) {
+            throw_ub_format!(
+                "a signal handler allocated or deallocated memory, which is not async-signal-safe"
+            );
+        }
+        if NOT_ASYNC_SIGNAL_SAFE.contains(&link_name.as_str()) {
+            throw_ub_format!(
+                "a signal handler called `{link_name}`, which is not async-signal-safe"
+            );
+        }
+        interp_ok(())
+    }
+
+    /// Delivers the first pending signal the active thread doesn't block, if there is one. This
+    /// must only be called at an interruption point, where the active frame is at the start of a
+    /// basic block: a handler then runs before the execution of that block.
+    fn deliver_pending_signal(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let thread = this.active_thread();
+        if !this.active_thread_ref().is_enabled() {
+            return interp_ok(());
+        }
+        let deliverable = this.machine.signals.deliverable(thread);
+        if deliverable.first().is_none() {
+            return interp_ok(());
+        }
+        let sig = this.machine.signals.take_pending(thread, deliverable).unwrap();
+        this.update_signalfds()?;
+        let signals = this.machine.signals.thread(thread);
+        let restore_mask = signals.suspended_mask.take().unwrap_or(signals.mask);
+
+        let action = this.machine.signals.actions.get(&sig).copied();
+        let handler = match action.map(|action| action.handler) {
+            Some(SigHandler::Handler(handler)) => handler,
+            Some(SigHandler::Ignore) => {
+                this.machine.signals.thread(thread).mask = restore_mask;
+                return interp_ok(());
+            }
+            Some(SigHandler::Default) | None => match this.default_action(sig) {
+                DefaultAction::Ignore => {
+                    this.machine.signals.thread(thread).mask = restore_mask;
+                    return interp_ok(());
+                }
+                DefaultAction::Terminate => {
+                    let name = this.signal_name(sig);
+                    throw_machine_stop!(TerminationInfo::Abort(format!(
+                        "the evaluated program was terminated by {name}"
+                    )));
+                }
+                DefaultAction::Stop => {
+                    let name = this.signal_name(sig);
+                    throw_unsup_format!("stopping the process with {name} is not supported");
+                }
+            },
+        };
+        let action = action.unwrap();
+
+        // While the handler runs, the signals of `sa_mask` are blocked, and so is the signal
+        // itself unless `SA_NODEFER` is set.
+        let mut mask = restore_mask.union(action.mask);
+        if action.flags & this.eval_libc_i32("SA_NODEFER") == 0 {
+            mask.insert(sig);
+        }
+        if action.flags & this.eval_libc_i32("SA_RESETHAND") != 0 {
+            this.machine.signals.actions.remove(&sig);
+        }
+        let signals = this.machine.signals.thread(thread);
+        signals.mask = mask.difference(this.unblockable_signals());
+        signals.handler_depth = signals.handler_depth.strict_add(1);
+
+        let mut args = vec![ImmTy::from_int(sig, this.machine.layouts.i32)];
+        let siginfo = if action.flags & this.eval_libc_i32("SA_SIGINFO") != 0 {
+            let siginfo =
+                this.allocate(this.libc_ty_layout("siginfo_t"), MiriMemoryKind::Machine.into())?;
+            this.write_bytes_ptr(
+                siginfo.ptr(),
+                iter::repeat_n(0, siginfo.layout.size.bytes_usize()),
+            )?;
+            this.write_int_fields_named(
+                &[("si_signo", sig.into()), ("si_code", this.eval_libc_i32("SI_USER").into())],
+                &siginfo,
+            )?;
+            let ptr_layout = this.machine.layouts.mut_raw_ptr;
+            args.push(ImmTy::from_scalar(
+                Scalar::from_maybe_pointer(siginfo.ptr(), this),
+                ptr_layout,
+            ));
+            // We have no `ucontext_t` to offer.
+            args.push(ImmTy::from_scalar(Scalar::null_ptr(this), ptr_layout));
+            Some(siginfo)
+        } else {
+            None
+        };
+
+        let Either::Left(location) = this.frame().current_loc() else {
+            bug!("signals must be delivered while executing a MIR body")
+        };
+        assert_eq!(
+            location.statement_index, 0,
+            "signals must be delivered at the start of a block"
+        );
+        let instance = this.get_ptr_fn(handler)?.as_instance()?;
+        this.call_function(
+            instance,
+            ExternAbi::C { unwind: false },
+            &args,
+            None,
+            // Resume the interrupted code once the handler returns.
+            ReturnContinuation::Goto {
+                ret: Some(location.block),
+                unwind: mir::UnwindAction::Unreachable,
+            },
+        )?;
+        this.frame_mut().extra.signal_handler = Some(SignalHandlerFrame { restore_mask, siginfo });
+        interp_ok(())
+    }
+
+    /// Undoes the delivery of a signal once its handler returned.
+    fn return_from_signal_handler(
+        &mut self,
+        frame: Option<SignalHandlerFrame<'tcx>>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let Some(SignalHandlerFrame { restore_mask, siginfo }) = frame else {
+            return interp_ok(());
+        };
+        let signals = this.machine.signals.thread(this.active_thread());
+        signals.mask = restore_mask;
+        signals.handler_depth = signals.handler_depth.strict_sub(1);
+        if let Some(siginfo) = siginfo {
+            this.deallocate_ptr(siginfo.ptr(), None, MiriMemoryKind::Machine.into())?;
+        }
+        interp_ok(())
+    }
+
+    fn sigaction(
+        &mut self,
+        sig: &OpTy<'tcx>,
+        act: &OpTy<'tcx>,
+        oldact: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        let act = this.read_pointer(act)?;
+        let oldact = this.read_pointer(oldact)?;
+
+        if !this.is_valid_signal(sig) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        let sigaction_layout = this.libc_ty_layout("sigaction");
+
+        let old = if this.ptr_is_null(act)? {
+            this.machine.signals.actions.get(&sig).copied()
+        } else {
+            if this.unblockable_signals().contains(sig) {
+                return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+            }
+            let act = this.ptr_to_mplace(act, sigaction_layout);
+            let handler = this.project_field_named(&act, "sa_sigaction")?;
+            let handler = this.scalar_to_sighandler(this.read_scalar(&handler)?)?;
+            let mask = this.project_field_named(&act, "sa_mask")?;
+            let mask = this.read_sigset(&mask)?;
+            let flags = this.project_field_named(&act, "sa_flags")?;
+            let flags = this.read_scalar(&flags)?.to_int(flags.layout.size)?;
+            let action = SigAction { handler, mask, flags: i32::try_from(flags).unwrap() };
+            let old = this.set_sigaction(sig, action);
+            this.update_signalfds()?;
+            Some(old)
+        };
+
+        if !this.ptr_is_null(oldact)? {
+            let old = old.unwrap_or(SigAction {
+                handler: SigHandler::Default,
+                mask: SigSet::default(),
+                flags: 0,
+            });
+            let oldact = this.ptr_to_mplace(oldact, sigaction_layout);
+            this.write_bytes_ptr(
+                oldact.ptr(),
+                iter::repeat_n(0, sigaction_layout.size.bytes_usize()),
+            )?;
+            let handler = this.project_field_named(&oldact, "sa_sigaction")?;
+            this.write_scalar(this.sighandler_to_scalar(old.handler), &handler)?;
+            let mask = this.project_field_named(&oldact, "sa_mask")?;
+            this.write_sigset(old.mask, &mask)?;
+            let flags = this.project_field_named(&oldact, "sa_flags")?;
+            this.write_int(old.flags, &flags)?;
+        }
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn signal(&mut self, sig: &OpTy<'tcx>, handler: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        let handler = this.read_scalar(handler)?;
+
+        if !this.is_valid_signal(sig) || this.unblockable_signals().contains(sig) {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("SIG_ERR"));
+        }
+        // `signal` installs handlers with the BSD semantics, like glibc does.
+        let action = SigAction {
+            handler: this.scalar_to_sighandler(handler)?,
+            mask: SigSet::default(),
+            flags: this.eval_libc_i32("SA_RESTART"),
+        };
+        let old = this.set_sigaction(sig, action);
+        this.update_signalfds()?;
+        interp_ok(this.sighandler_to_scalar(old.handler))
+    }
+
+    fn sigemptyset(&mut self, set: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let set = this.deref_sigset(set)?;
+        this.write_sigset(SigSet::default(), &set)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigfillset(&mut self, set: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let set = this.deref_sigset(set)?;
+        this.write_sigset(SigSet(u64::MAX), &set)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Implements `sigaddset` and `sigdelset`.
+    fn sigaddset_or_sigdelset(
+        &mut self,
+        set: &OpTy<'tcx>,
+        sig: &OpTy<'tcx>,
+        add: bool,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        if !this.is_valid_signal(sig) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        let set = this.deref_sigset(set)?;
+        let mut signals = this.read_sigset(&set)?;
+        if add {
+            signals.insert(sig);
+        } else {
+            signals.remove(sig);
+        }
+        this.write_sigset(signals, &set)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigismember(&mut self, set: &OpTy<'tcx>, sig: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        if !this.is_valid_signal(sig) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        let signals = this.read_sigset(&this.deref_sigset(set)?)?;
+        interp_ok(Scalar::from_i32(signals.contains(sig).into()))
+    }
+
+    fn sigprocmask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        match this.update_signal_mask(how, set, oldset)? {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(err) => this.set_errno_and_return_neg1_i32(err),
+        }
+    }
+
+    fn pthread_sigmask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        // Unlike `sigprocmask`, this returns the error number.
+        match this.update_signal_mask(how, set, oldset)? {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(err) => this.io_error_to_errnum(err),
+        }
+    }
+
+    fn sigpending(&mut self, set: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let thread = this.active_thread();
+        let signals = &mut this.machine.signals;
+        let process_pending = signals.pending;
+        let thread_signals = signals.thread(thread);
+        let pending =
+            thread_signals.pending.union(process_pending).intersection(thread_signals.mask);
+        this.write_sigset(pending, &this.deref_sigset(set)?)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn raise(&mut self, sig: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        if !this.is_valid_signal(sig) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        this.generate_signal(sig, Some(this.active_thread()))?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn kill(&mut self, pid: &OpTy<'tcx>, sig: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid)?.to_int(this.libc_ty_layout("pid_t").size)?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        if let Ok(pid) = u32::try_from(pid)
+            && this.machine.child_processes.is_child(pid)
+        {
+            return this.kill_child(pid, sig);
+        }
+        // `0` and `-1` designate our process group and all the processes we may signal, of
+        // which we are the only member as far as the program can tell. (Child processes are
+        // not part of it, since they run in a new instance of Miri.)
+        if pid != i128::from(this.get_pid()) && pid != 0 && pid != -1 {
+            throw_unsup_format!("`kill`: sending signals to other processes is not supported");
+        }
+        if sig == 0 {
+            // Only checks whether the process exists.
+            return interp_ok(Scalar::from_i32(0));
+        }
+        if !this.is_valid_signal(sig) {
+            return this.set_errno_and_return_neg1_i32(LibcError("EINVAL"));
+        }
+        this.generate_signal(sig, None)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn pthread_kill(
+        &mut self,
+        thread: &OpTy<'tcx>,
+        sig: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let thread = this.read_scalar(thread)?.to_int(this.libc_ty_layout("pthread_t").size)?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        let thread = match this.thread_id_try_from(thread) {
+            Ok(id) => id,
+            Err(ThreadLookupError::InvalidId | ThreadLookupError::Terminated(_)) => {
+                return interp_ok(this.eval_libc("ESRCH"));
+            }
+        };
+        if sig == 0 {
+            return interp_ok(Scalar::from_i32(0));
+        }
+        if !this.is_valid_signal(sig) {
+            return interp_ok(this.eval_libc("EINVAL"));
+        }
+        this.generate_signal(sig, Some(thread))?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn pause(&mut self, dest: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.wait_for_signal_delivery(dest)
+    }
+
+    fn sigsuspend(&mut self, mask: &OpTy<'tcx>, dest: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let mask =
+            this.read_sigset(&this.deref_sigset(mask)?)?.difference(this.unblockable_signals());
+        // The mask of the thread is restored once the handler of the signal which woke it up
+        // returns.
+        let signals = this.machine.signals.thread(this.active_thread());
+        signals.suspended_mask = Some(signals.mask);
+        signals.mask = mask;
+        this.wait_for_signal_delivery(dest)
+    }
+
+    fn sigwait(
+        &mut self,
+        set: &OpTy<'tcx>,
+        sig: &OpTy<'tcx>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let set = this.read_sigset(&this.deref_sigset(set)?)?;
+        let sig = this.deref_pointer_as(sig, this.machine.layouts.i32)?;
+
+        let thread = this.active_thread();
+        if let Some(pending) = this.machine.signals.take_pending(thread, set) {
+            this.update_signalfds()?;
+            this.write_int(pending, &sig)?;
+            return this.write_null(dest);
+        }
+        this.machine.signals.thread(thread).waiting = Some(SignalWait::Pending(set));
+        this.block_thread(
+            BlockReason::Signal,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    thread: ThreadId,
+                    set: SigSet,
+                    sig: MPlaceTy<'tcx>,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    let pending = this.machine.signals.take_pending(thread, set).unwrap();
+                    this.update_signalfds()?;
+                    this.write_int(pending, &sig)?;
+                    this.write_null(&dest)
+                }
+            ),
+        );
+        interp_ok(())
+    }
+}
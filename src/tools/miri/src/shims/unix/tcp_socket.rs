@@ -1134,7 +1134,7 @@ fn try_non_block_send(
         };
 
         // This is a *non-blocking* write.
-        let result = this.write_to_host(stream, length, buffer_ptr)?;
+        let result = this.write_to_host_traced(stream, length, buffer_ptr)?;
 
         drop(state);
 
@@ -1269,7 +1269,7 @@ fn try_non_block_recv(
         };
 
         // This is a *non-blocking* read/peek.
-        let result = this.read_from_host(
+        let result = this.read_from_host_traced(
             |buf| {
                 if should_peek { stream.peek(buf) } else { stream.read(buf) }
             },
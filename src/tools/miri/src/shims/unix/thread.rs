@@ -27,13 +27,14 @@ fn pthread_create(
 
         let func_arg = this.read_immediate(arg)?;
 
-        this.start_regular_thread(
+        let new_thread = this.start_regular_thread(
             Some(thread_info_place),
             start_routine,
             ExternAbi::C { unwind: false },
             func_arg,
             this.machine.layouts.mut_raw_ptr,
         )?;
+        this.machine.signals.thread_created(this.active_thread(), new_thread);
 
         interp_ok(())
     }
@@ -0,0 +1,998 @@
+//! An in-memory file system, which serves the file system accesses of the program under isolation
+//! when `-Zmiri-virtual-fs` is set.
+//!
+//! It is fully deterministic: its initial state is a snapshot of a host directory or the contents
+//! of a tar file (or just an empty `/tmp`), and its timestamps come from Miri's monotonic clock.
+//! All files are owned by the user running the program, and permissions are checked against the
+//! owner bits.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, SeekFrom};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+use std::{fs, str};
+
+use rustc_abi::Size;
+
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::unix::fd::{FlockOp, UnixFileDescription};
+use crate::*;
+
+/// The maximal number of symbolic links followed while resolving a path, like on Linux.
+const MAX_SYMLINKS: u32 = 40;
+/// The umask applied to the mode of newly created files and directories.
+const UMASK: u32 = 0o022;
+
+// The permission bits checked for the different kinds of accesses.
+const READ: u32 = 0o4;
+const WRITE: u32 = 0o2;
+const EXEC: u32 = 0o1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VirtualFileKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Debug)]
+enum Content {
+    File(Vec<u8>),
+    /// The entries of the directory, which do not include `.` and `..`.
+    Dir(BTreeMap<Vec<u8>, Rc<Inode>>),
+    /// The target of the symbolic link.
+    Symlink(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct InodeState {
+    /// The permission bits of the mode.
+    mode: u32,
+    /// The number of directory entries referring to this inode.
+    nlink: u64,
+    accessed: Duration,
+    modified: Duration,
+    created: Duration,
+    content: Content,
+}
+
+/// A file, directory or symbolic link. Open files keep their inode alive, so that they can still
+/// be accessed after being unlinked.
+#[derive(Debug)]
+pub struct Inode {
+    ino: u64,
+    state: RefCell<InodeState>,
+}
+
+/// The metadata of an inode, as reported by `stat`.
+pub struct VirtualMetadata {
+    pub kind: VirtualFileKind,
+    pub mode: u32,
+    pub size: u64,
+    pub ino: u64,
+    pub nlink: u64,
+    pub accessed: Duration,
+    pub modified: Duration,
+    pub created: Duration,
+}
+
+/// An entry of a directory, as returned by `readdir`.
+#[derive(Debug)]
+pub struct VirtualDirEntry {
+    pub name: Vec<u8>,
+    pub ino: u64,
+    pub kind: VirtualFileKind,
+}
+
+/// The flags of `open` which are relevant to the virtual file system.
+#[derive(Default)]
+pub struct VirtualOpenOptions {
+    pub read: bool,
+    pub write: bool,
+    pub truncate: bool,
+    /// The mode of the file to create if it does not exist yet (`O_CREAT`).
+    pub create: Option<u32>,
+    /// Fail if the file already exists (`O_EXCL`).
+    pub exclusive: bool,
+    /// Fail if the path is a symbolic link (`O_NOFOLLOW`).
+    pub nofollow: bool,
+}
+
+impl Inode {
+    fn kind(&self) -> VirtualFileKind {
+        match self.state.borrow().content {
+            Content::File(_) => VirtualFileKind::File,
+            Content::Dir(_) => VirtualFileKind::Dir,
+            Content::Symlink(_) => VirtualFileKind::Symlink,
+        }
+    }
+
+    pub fn metadata(&self) -> VirtualMetadata {
+        let state = self.state.borrow();
+        let (kind, size, nlink) = match &state.content {
+            Content::File(data) => (VirtualFileKind::File, data.len(), state.nlink),
+            Content::Dir(entries) => {
+                // Like on Linux, directories are linked from their parent, their `.` entry and
+                // the `..` entries of their subdirectories.
+                let subdirs = entries.values().filter(|e| e.kind() == VirtualFileKind::Dir).count();
+                (VirtualFileKind::Dir, 4096, u64::try_from(subdirs).unwrap().strict_add(2))
+            }
+            Content::Symlink(target) => (VirtualFileKind::Symlink, target.len(), state.nlink),
+        };
+        VirtualMetadata {
+            kind,
+            mode: state.mode,
+            size: u64::try_from(size).unwrap(),
+            ino: self.ino,
+            nlink,
+            accessed: state.accessed,
+            modified: state.modified,
+            created: state.created,
+        }
+    }
+
+    /// Checks that the owner has all the permissions of `access`.
+    fn check_access(&self, access: u32) -> Result<(), IoError> {
+        if (self.state.borrow().mode >> 6) & access != access {
+            return Err(LibcError("EACCES"));
+        }
+        Ok(())
+    }
+
+    /// Looks up the entry `name` of this directory.
+    fn entry(&self, name: &[u8]) -> Result<Option<Rc<Inode>>, IoError> {
+        match &self.state.borrow().content {
+            Content::Dir(entries) => Ok(entries.get(name).cloned()),
+            _ => Err(LibcError("ENOTDIR")),
+        }
+    }
+
+    /// Adds the entry `name` to this directory, replacing any previous entry of that name.
+    fn insert(&self, name: Vec<u8>, inode: Rc<Inode>, now: Duration) {
+        {
+            let mut state = inode.state.borrow_mut();
+            state.nlink = state.nlink.strict_add(1);
+        }
+        let mut state = self.state.borrow_mut();
+        state.modified = now;
+        let Content::Dir(entries) = &mut state.content else { panic!("not a directory") };
+        if let Some(old) = entries.insert(name, inode) {
+            let mut old = old.state.borrow_mut();
+            old.nlink = old.nlink.strict_sub(1);
+        }
+    }
+
+    /// Removes the entry `name` of this directory, which must exist.
+    fn remove(&self, name: &[u8], now: Duration) -> Rc<Inode> {
+        let mut state = self.state.borrow_mut();
+        state.modified = now;
+        let Content::Dir(entries) = &mut state.content else { panic!("not a directory") };
+        let inode = entries.remove(name).expect("removed directory entry does not exist");
+        {
+            let mut inode_state = inode.state.borrow_mut();
+            inode_state.nlink = inode_state.nlink.strict_sub(1);
+        }
+        inode
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>, IoError> {
+        let state = self.state.borrow();
+        let Content::File(data) = &state.content else { return Err(LibcError("EISDIR")) };
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(data.len());
+        let end = start.saturating_add(len).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+
+    fn write_at(&self, offset: u64, bytes: &[u8], now: Duration) -> Result<usize, IoError> {
+        let mut state = self.state.borrow_mut();
+        state.modified = now;
+        let Content::File(data) = &mut state.content else { return Err(LibcError("EISDIR")) };
+        let Some(end) = offset.checked_add(u64::try_from(bytes.len()).unwrap()) else {
+            return Err(LibcError("EFBIG"));
+        };
+        let (Ok(start), Ok(end)) = (usize::try_from(offset), usize::try_from(end)) else {
+            return Err(LibcError("EFBIG"));
+        };
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[start..end].copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    pub fn size(&self) -> u64 {
+        match &self.state.borrow().content {
+            Content::File(data) => u64::try_from(data.len()).unwrap(),
+            _ => 0,
+        }
+    }
+
+    pub fn set_len(&self, len: u64, now: Duration) -> Result<(), IoError> {
+        let mut state = self.state.borrow_mut();
+        state.modified = now;
+        let Content::File(data) = &mut state.content else { return Err(LibcError("EINVAL")) };
+        let Ok(len) = usize::try_from(len) else { return Err(LibcError("EFBIG")) };
+        data.resize(len, 0);
+        Ok(())
+    }
+
+    pub fn set_mode(&self, mode: u32) {
+        self.state.borrow_mut().mode = mode & 0o7777;
+    }
+}
+
+/// The directories from the root to some inode, with the names of their entries.
+type PathStack = Vec<(Vec<u8>, Rc<Inode>)>;
+
+#[derive(Debug)]
+pub struct VirtualFs {
+    root: Rc<Inode>,
+    /// The absolute path of the current working directory.
+    cwd: Vec<u8>,
+    next_ino: u64,
+}
+
+impl VirtualFs {
+    /// Creates the file system, seeded with the contents of the host directory or tar file at
+    /// `seed`, if any. `/tmp` is created if the seed does not contain it.
+    pub fn new(seed: Option<&Path>) -> Result<VirtualFs, String> {
+        let root = Rc::new(Inode {
+            ino: 1,
+            state: RefCell::new(InodeState {
+                mode: 0o755,
+                nlink: 1,
+                accessed: Duration::ZERO,
+                modified: Duration::ZERO,
+                created: Duration::ZERO,
+                content: Content::Dir(BTreeMap::new()),
+            }),
+        });
+        let mut vfs = VirtualFs { root, cwd: b"/".to_vec(), next_ino: 2 };
+
+        if let Some(seed) = seed {
+            let metadata = fs::metadata(seed).map_err(|err| format!("{}: {err}", seed.display()))?;
+            if metadata.is_dir() {
+                let root = vfs.root.clone();
+                vfs.seed_from_dir(&root, seed)
+                    .map_err(|err| format!("{}: {err}", seed.display()))?;
+            } else {
+                let tar = fs::read(seed).map_err(|err| format!("{}: {err}", seed.display()))?;
+                vfs.seed_from_tar(&tar).map_err(|err| format!("{}: {err}", seed.display()))?;
+            }
+        }
+
+        if vfs.root.entry(b"tmp").unwrap().is_none() {
+            let tmp = vfs.new_inode(0o1777, Content::Dir(BTreeMap::new()), Duration::ZERO);
+            vfs.root.insert(b"tmp".to_vec(), tmp, Duration::ZERO);
+        }
+        Ok(vfs)
+    }
+
+    fn new_inode(&mut self, mode: u32, content: Content, now: Duration) -> Rc<Inode> {
+        let ino = self.next_ino;
+        self.next_ino = self.next_ino.strict_add(1);
+        Rc::new(Inode {
+            ino,
+            state: RefCell::new(InodeState {
+                mode: mode & 0o7777,
+                nlink: 0,
+                accessed: now,
+                modified: now,
+                created: now,
+                content,
+            }),
+        })
+    }
+
+    fn seed_from_dir(&mut self, dir: &Inode, host_dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(host_dir)? {
+            let entry = entry?;
+            let metadata = fs::symlink_metadata(entry.path())?;
+            let inode = if metadata.is_dir() {
+                let inode = self.new_inode(
+                    host_mode(&metadata, 0o755),
+                    Content::Dir(BTreeMap::new()),
+                    Duration::ZERO,
+                );
+                self.seed_from_dir(&inode, &entry.path())?;
+                inode
+            } else if metadata.is_symlink() {
+                let target = fs::read_link(entry.path())?.into_os_string().into_encoded_bytes();
+                self.new_inode(0o777, Content::Symlink(target), Duration::ZERO)
+            } else {
+                let data = fs::read(entry.path())?;
+                self.new_inode(host_mode(&metadata, 0o644), Content::File(data), Duration::ZERO)
+            };
+            dir.insert(entry.file_name().into_encoded_bytes(), inode, Duration::ZERO);
+        }
+        Ok(())
+    }
+
+    /// Adds the entries of a (ustar, GNU or pax) tar file.
+    fn seed_from_tar(&mut self, mut tar: &[u8]) -> Result<(), String> {
+        fn field(header: &[u8], range: std::ops::Range<usize>) -> &[u8] {
+            let field = &header[range];
+            field.split(|&b| b == 0).next().unwrap()
+        }
+        fn octal(header: &[u8], range: std::ops::Range<usize>) -> Result<u64, String> {
+            let field = str::from_utf8(field(header, range)).map_err(|err| err.to_string())?;
+            u64::from_str_radix(field.trim_matches(' '), 8)
+                .map_err(|err| format!("invalid number in tar header: {err}"))
+        }
+
+        let mut long_name = None;
+        let mut long_link = None;
+        while tar.len() >= 512 {
+            let (header, rest) = tar.split_at(512);
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+            let size = usize::try_from(octal(header, 124..136)?).unwrap();
+            let Some(data) = rest.get(..size) else { return Err("truncated tar file".into()) };
+            tar = rest.get(size.next_multiple_of(512)..).unwrap_or_default();
+
+            let mut name = field(header, 0..100).to_vec();
+            if &header[257..262] == b"ustar" && header[345] != 0 {
+                let mut prefix = field(header, 345..500).to_vec();
+                prefix.push(b'/');
+                name.splice(0..0, prefix);
+            }
+            let name = long_name.take().unwrap_or(name);
+            let link = long_link.take().unwrap_or_else(|| field(header, 157..257).to_vec());
+            let mode = u32::try_from(octal(header, 100..108)?).unwrap_or(0o644);
+            match header[156] {
+                b'0' | b'\0' | b'7' =>
+                    self.seed_entry(&name, mode, Content::File(data.to_vec()))?,
+                b'5' => self.seed_entry(&name, mode, Content::Dir(BTreeMap::new()))?,
+                b'2' => self.seed_entry(&name, 0o777, Content::Symlink(link))?,
+                b'1' => {
+                    let target = self
+                        .resolve(&normalize_tar_path(&link)?, false)
+                        .map_err(|_| "tar hard link to a missing file".to_owned())?;
+                    let (parent, name) = self.seed_parent(&name)?;
+                    parent.insert(name, target, Duration::ZERO);
+                }
+                // GNU long names.
+                b'L' => long_name = Some(field(data, 0..data.len()).to_vec()),
+                b'K' => long_link = Some(field(data, 0..data.len()).to_vec()),
+                // Pax extended headers, consisting of "<len> <key>=<value>\n" records.
+                b'x' =>
+                    for record in data.split_inclusive(|&b| b == b'\n') {
+                        let record = record.strip_suffix(b"\n").unwrap_or(record);
+                        let Some(space) = record.iter().position(|&b| b == b' ') else { continue };
+                        let record = &record[space.strict_add(1)..];
+                        if let Some(path) = record.strip_prefix(b"path=") {
+                            long_name = Some(path.to_vec());
+                        } else if let Some(path) = record.strip_prefix(b"linkpath=") {
+                            long_link = Some(path.to_vec());
+                        }
+                    },
+                b'g' => {}
+                kind =>
+                    return Err(format!(
+                        "unsupported tar entry type `{}` for {}",
+                        kind.escape_ascii(),
+                        name.escape_ascii()
+                    )),
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the parent directories of `path` as needed, and returns the last one along with the
+    /// name of the entry.
+    fn seed_parent(&mut self, path: &[u8]) -> Result<(Rc<Inode>, Vec<u8>), String> {
+        let path = normalize_tar_path(path)?;
+        if path.is_empty() {
+            return Err("tar entry with an empty name".into());
+        }
+        let mut components = path.split(|&b| b == b'/').peekable();
+        let mut dir = self.root.clone();
+        while let Some(name) = components.next() {
+            if components.peek().is_none() {
+                return Ok((dir, name.to_vec()));
+            }
+            dir = match dir.entry(name).map_err(|_| "tar entry below a file".to_owned())? {
+                Some(subdir) => subdir,
+                None => {
+                    let subdir =
+                        self.new_inode(0o755, Content::Dir(BTreeMap::new()), Duration::ZERO);
+                    dir.insert(name.to_vec(), subdir.clone(), Duration::ZERO);
+                    subdir
+                }
+            };
+        }
+        unreachable!("`split` returns at least one component")
+    }
+
+    fn seed_entry(&mut self, path: &[u8], mode: u32, content: Content) -> Result<(), String> {
+        if normalize_tar_path(path)?.is_empty() {
+            // The entry for the root directory itself.
+            self.root.set_mode(mode);
+            return Ok(());
+        }
+        let (parent, name) = self.seed_parent(path)?;
+        if let Content::Dir(_) = content
+            && let Ok(Some(existing)) = parent.entry(&name)
+            && existing.kind() == VirtualFileKind::Dir
+        {
+            // The directory was already created for an earlier entry.
+            existing.set_mode(mode);
+            return Ok(());
+        }
+        let inode = self.new_inode(mode, content, Duration::ZERO);
+        parent.insert(name, inode, Duration::ZERO);
+        Ok(())
+    }
+
+    /// Resolves `path` component by component, starting from the directories in `stack`.
+    fn walk(
+        &self,
+        stack: &mut PathStack,
+        path: &[u8],
+        follow_last: bool,
+        links: &mut u32,
+    ) -> Result<(), IoError> {
+        if path.is_empty() {
+            return Err(LibcError("ENOENT"));
+        }
+        if path.starts_with(b"/") {
+            stack.truncate(1);
+        }
+        // A trailing slash makes the last component get resolved like a directory.
+        let trailing_slash = path.ends_with(b"/");
+        let follow_last = follow_last || trailing_slash;
+        let mut components = path.split(|&b| b == b'/').filter(|c| !c.is_empty()).peekable();
+        while let Some(name) = components.next() {
+            let is_last = components.peek().is_none();
+            let dir = stack.last().unwrap().1.clone();
+            if dir.kind() != VirtualFileKind::Dir {
+                return Err(LibcError("ENOTDIR"));
+            }
+            dir.check_access(EXEC)?;
+            match name {
+                b"." => continue,
+                b".." => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            let Some(inode) = dir.entry(name)? else { return Err(LibcError("ENOENT")) };
+            let target = match &inode.state.borrow().content {
+                Content::Symlink(target) if !is_last || follow_last => Some(target.clone()),
+                _ => None,
+            };
+            if let Some(target) = target {
+                *links = links.strict_add(1);
+                if *links > MAX_SYMLINKS {
+                    return Err(LibcError("ELOOP"));
+                }
+                self.walk(stack, &target, true, links)?;
+            } else {
+                stack.push((name.to_vec(), inode));
+            }
+        }
+        if trailing_slash && stack.last().unwrap().1.kind() != VirtualFileKind::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, path: &[u8], follow: bool) -> Result<PathStack, IoError> {
+        let mut stack = vec![(Vec::new(), self.root.clone())];
+        if !path.starts_with(b"/") {
+            self.walk(&mut stack, &self.cwd, true, &mut 0)?;
+        }
+        self.walk(&mut stack, path, follow, &mut 0)?;
+        Ok(stack)
+    }
+
+    fn resolve(&self, path: &[u8], follow: bool) -> Result<Rc<Inode>, IoError> {
+        Ok(self.lookup(path, follow)?.pop().unwrap().1)
+    }
+
+    /// Resolves the parent directory of `path`, and returns it along with the last component of
+    /// `path`.
+    fn lookup_parent(&self, path: &[u8]) -> Result<(PathStack, Vec<u8>), IoError> {
+        if path.is_empty() {
+            return Err(LibcError("ENOENT"));
+        }
+        let trimmed = &path[..path.iter().rposition(|&b| b != b'/').map_or(0, |i| i.strict_add(1))];
+        let (parent, name): (&[u8], &[u8]) = match trimmed.iter().rposition(|&b| b == b'/') {
+            Some(pos) => (&trimmed[..=pos], &trimmed[pos.strict_add(1)..]),
+            None => (b".", trimmed),
+        };
+        if matches!(name, b"" | b"." | b"..") {
+            // The root directory, or a path ending with `.` or `..`.
+            return Err(LibcError("EBUSY"));
+        }
+        let stack = self.lookup(parent, true)?;
+        if stack.last().unwrap().1.kind() != VirtualFileKind::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        Ok((stack, name.to_vec()))
+    }
+
+    pub fn open(
+        &mut self,
+        path: &[u8],
+        options: &VirtualOpenOptions,
+        now: Duration,
+    ) -> Result<Rc<Inode>, IoError> {
+        let inode = if let Some(mode) = options.create {
+            let (stack, name) = self.lookup_parent(path)?;
+            let dir = &stack.last().unwrap().1;
+            match dir.entry(&name)? {
+                Some(_) if options.exclusive => return Err(LibcError("EEXIST")),
+                Some(_) => self.resolve(path, !options.nofollow)?,
+                None => {
+                    if path.ends_with(b"/") {
+                        return Err(LibcError("EISDIR"));
+                    }
+                    dir.check_access(WRITE | EXEC)?;
+                    let inode = self.new_inode(mode & !UMASK, Content::File(Vec::new()), now);
+                    dir.insert(name, inode.clone(), now);
+                    // The new file can be accessed regardless of its mode.
+                    return Ok(inode);
+                }
+            }
+        } else {
+            self.resolve(path, !options.nofollow)?
+        };
+
+        match inode.kind() {
+            // This can only happen with `O_NOFOLLOW`.
+            VirtualFileKind::Symlink => return Err(LibcError("ELOOP")),
+            VirtualFileKind::Dir if options.write => return Err(LibcError("EISDIR")),
+            _ => {}
+        }
+        if options.read {
+            inode.check_access(READ)?;
+        }
+        if options.write {
+            inode.check_access(WRITE)?;
+            if options.truncate && inode.kind() == VirtualFileKind::File {
+                inode.set_len(0, now)?;
+            }
+        }
+        Ok(inode)
+    }
+
+    pub fn metadata(&self, path: &[u8], follow: bool) -> Result<VirtualMetadata, IoError> {
+        Ok(self.resolve(path, follow)?.metadata())
+    }
+
+    pub fn mkdir(&mut self, path: &[u8], mode: u32, now: Duration) -> Result<(), IoError> {
+        let (stack, name) = self.lookup_parent(path)?;
+        let dir = &stack.last().unwrap().1;
+        if dir.entry(&name)?.is_some() {
+            return Err(LibcError("EEXIST"));
+        }
+        dir.check_access(WRITE | EXEC)?;
+        let inode = self.new_inode(mode & !UMASK, Content::Dir(BTreeMap::new()), now);
+        dir.insert(name, inode, now);
+        Ok(())
+    }
+
+    pub fn symlink(&mut self, target: &[u8], path: &[u8], now: Duration) -> Result<(), IoError> {
+        let (stack, name) = self.lookup_parent(path)?;
+        let dir = &stack.last().unwrap().1;
+        if dir.entry(&name)?.is_some() {
+            return Err(LibcError("EEXIST"));
+        }
+        dir.check_access(WRITE | EXEC)?;
+        let inode = self.new_inode(0o777, Content::Symlink(target.to_vec()), now);
+        dir.insert(name, inode, now);
+        Ok(())
+    }
+
+    pub fn link(&mut self, old: &[u8], new: &[u8], now: Duration) -> Result<(), IoError> {
+        // Like `linkat` without `AT_SYMLINK_FOLLOW`, this links the symbolic link itself.
+        let inode = self.resolve(old, false)?;
+        if inode.kind() == VirtualFileKind::Dir {
+            return Err(LibcError("EPERM"));
+        }
+        let (stack, name) = self.lookup_parent(new)?;
+        let dir = &stack.last().unwrap().1;
+        if dir.entry(&name)?.is_some() {
+            return Err(LibcError("EEXIST"));
+        }
+        dir.check_access(WRITE | EXEC)?;
+        dir.insert(name, inode, now);
+        Ok(())
+    }
+
+    pub fn unlink(&mut self, path: &[u8], now: Duration) -> Result<(), IoError> {
+        let (stack, name) = self.lookup_parent(path)?;
+        let dir = &stack.last().unwrap().1;
+        let Some(inode) = dir.entry(&name)? else { return Err(LibcError("ENOENT")) };
+        if inode.kind() == VirtualFileKind::Dir {
+            return Err(LibcError("EISDIR"));
+        }
+        dir.check_access(WRITE | EXEC)?;
+        dir.remove(&name, now);
+        Ok(())
+    }
+
+    pub fn rmdir(&mut self, path: &[u8], now: Duration) -> Result<(), IoError> {
+        let (stack, name) = self.lookup_parent(path)?;
+        let dir = &stack.last().unwrap().1;
+        let Some(inode) = dir.entry(&name)? else { return Err(LibcError("ENOENT")) };
+        match &inode.state.borrow().content {
+            Content::Dir(entries) if !entries.is_empty() => return Err(LibcError("ENOTEMPTY")),
+            Content::Dir(_) => {}
+            _ => return Err(LibcError("ENOTDIR")),
+        }
+        dir.check_access(WRITE | EXEC)?;
+        dir.remove(&name, now);
+        Ok(())
+    }
+
+    pub fn rename(&mut self, old: &[u8], new: &[u8], now: Duration) -> Result<(), IoError> {
+        let (old_stack, old_name) = self.lookup_parent(old)?;
+        let old_dir = &old_stack.last().unwrap().1;
+        let Some(inode) = old_dir.entry(&old_name)? else { return Err(LibcError("ENOENT")) };
+        let (new_stack, new_name) = self.lookup_parent(new)?;
+        let new_dir = &new_stack.last().unwrap().1;
+
+        if inode.kind() == VirtualFileKind::Dir
+            && new_stack.iter().any(|(_, dir)| Rc::ptr_eq(dir, &inode))
+        {
+            // A directory cannot be moved into itself.
+            return Err(LibcError("EINVAL"));
+        }
+        if let Some(existing) = new_dir.entry(&new_name)? {
+            if Rc::ptr_eq(&existing, &inode) {
+                return Ok(());
+            }
+            match (inode.kind(), &existing.state.borrow().content) {
+                (VirtualFileKind::Dir, Content::Dir(entries)) if !entries.is_empty() =>
+                    return Err(LibcError("ENOTEMPTY")),
+                (VirtualFileKind::Dir, Content::Dir(_)) => {}
+                (VirtualFileKind::Dir, _) => return Err(LibcError("ENOTDIR")),
+                (_, Content::Dir(_)) => return Err(LibcError("EISDIR")),
+                _ => {}
+            }
+        }
+        old_dir.check_access(WRITE | EXEC)?;
+        new_dir.check_access(WRITE | EXEC)?;
+        let inode = old_dir.remove(&old_name, now);
+        new_dir.insert(new_name, inode, now);
+        Ok(())
+    }
+
+    pub fn chmod(&mut self, path: &[u8], mode: u32) -> Result<(), IoError> {
+        self.resolve(path, true)?.set_mode(mode);
+        Ok(())
+    }
+
+    pub fn readlink(&self, path: &[u8]) -> Result<Vec<u8>, IoError> {
+        match &self.resolve(path, false)?.state.borrow().content {
+            Content::Symlink(target) => Ok(target.clone()),
+            _ => Err(LibcError("EINVAL")),
+        }
+    }
+
+    /// Returns the absolute path of `path` without symbolic links, `.` and `..` components.
+    pub fn realpath(&self, path: &[u8]) -> Result<Vec<u8>, IoError> {
+        let stack = self.lookup(path, true)?;
+        if stack.len() == 1 {
+            return Ok(b"/".to_vec());
+        }
+        let mut resolved = Vec::new();
+        for (name, _) in &stack[1..] {
+            resolved.push(b'/');
+            resolved.extend_from_slice(name);
+        }
+        Ok(resolved)
+    }
+
+    pub fn read_dir(&self, path: &[u8]) -> Result<Vec<VirtualDirEntry>, IoError> {
+        let inode = self.resolve(path, true)?;
+        inode.check_access(READ)?;
+        match &inode.state.borrow().content {
+            Content::Dir(entries) =>
+                Ok(entries
+                    .iter()
+                    .map(|(name, inode)| VirtualDirEntry {
+                        name: name.clone(),
+                        ino: inode.ino,
+                        kind: inode.kind(),
+                    })
+                    .collect()),
+            _ => Err(LibcError("ENOTDIR")),
+        }
+    }
+
+    pub fn cwd(&self) -> &[u8] {
+        &self.cwd
+    }
+
+    pub fn chdir(&mut self, path: &[u8]) -> Result<(), IoError> {
+        let cwd = self.realpath(path)?;
+        let inode = self.resolve(&cwd, true)?;
+        if inode.kind() != VirtualFileKind::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        inode.check_access(EXEC)?;
+        self.cwd = cwd;
+        Ok(())
+    }
+
+    /// Writes a listing of all files, directories and symbolic links to `path`, one per line and
+    /// sorted by path, along with their mode and contents.
+    pub fn dump(&self, path: &Path) -> io::Result<()> {
+        fn dump_inode(out: &mut String, path: &[u8], inode: &Inode) {
+            let state = inode.state.borrow();
+            let kind = match state.content {
+                Content::File(_) => '-',
+                Content::Dir(_) => 'd',
+                Content::Symlink(_) => 'l',
+            };
+            write!(out, "{kind} {:04o} {}", state.mode, path.escape_ascii()).unwrap();
+            match &state.content {
+                Content::File(data) =>
+                    writeln!(out, " {} \"{}\"", data.len(), data.escape_ascii()).unwrap(),
+                Content::Symlink(target) => writeln!(out, " -> {}", target.escape_ascii()).unwrap(),
+                Content::Dir(entries) => {
+                    out.push('\n');
+                    for (name, entry) in entries {
+                        let mut entry_path = path.to_vec();
+                        if entry_path != b"/" {
+                            entry_path.push(b'/');
+                        }
+                        entry_path.extend_from_slice(name);
+                        dump_inode(out, &entry_path, entry);
+                    }
+                }
+            }
+        }
+
+        let mut out = String::new();
+        dump_inode(&mut out, b"/", &self.root);
+        fs::write(path, out)
+    }
+}
+
+/// Removes the empty and `.` components of tar entry names (which are usually relative to `./`),
+/// and rejects names escaping the root.
+fn normalize_tar_path(path: &[u8]) -> Result<Vec<u8>, String> {
+    let mut components = path.split(|&b| b == b'/').filter(|c| !matches!(*c, b"" | b"."));
+    if components.clone().any(|c| c == b"..") {
+        return Err(format!("tar entry {} is outside of the root", path.escape_ascii()));
+    }
+    Ok(components.collect::<Vec<_>>().join(&b'/'))
+}
+
+/// The permission bits of a host file, or `default` if the host has no such notion.
+fn host_mode(metadata: &fs::Metadata, default: u32) -> u32 {
+    cfg_select! {
+        unix => {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = default;
+            metadata.permissions().mode() & 0o7777
+        }
+        _ => {
+            if metadata.permissions().readonly() { default & !0o222 } else { default }
+        }
+    }
+}
+
+/// The current time of the virtual file system: the time elapsed on Miri's monotonic clock since
+/// the program started, which is deterministic under isolation.
+pub fn now(machine: &MiriMachine<'_>) -> Duration {
+    machine.monotonic_clock.now().duration_since(machine.monotonic_clock.epoch())
+}
+
+/// An open file description of the virtual file system.
+#[derive(Debug)]
+pub struct VirtualFile {
+    pub(crate) inode: Rc<Inode>,
+    /// The file offset, shared by all duplicates of the file descriptor.
+    offset: Cell<u64>,
+    pub(crate) readable: bool,
+    pub(crate) writable: bool,
+    append: bool,
+}
+
+impl VirtualFile {
+    pub fn new(inode: Rc<Inode>, readable: bool, writable: bool, append: bool) -> Self {
+        Self { inode, offset: Cell::new(0), readable, writable, append }
+    }
+
+    fn read_to_ptr<'tcx>(
+        &self,
+        offset: u64,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        if !self.readable {
+            return interp_ok(Err(LibcError("EBADF")));
+        }
+        let bytes = match self.inode.read_at(offset, len) {
+            Ok(bytes) => bytes,
+            Err(err) => return interp_ok(Err(err)),
+        };
+        ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+        self.inode.state.borrow_mut().accessed = now(&ecx.machine);
+        interp_ok(Ok(bytes.len()))
+    }
+
+    fn write_from_ptr<'tcx>(
+        &self,
+        offset: u64,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        if !self.writable {
+            return interp_ok(Err(LibcError("EBADF")));
+        }
+        let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        interp_ok(self.inode.write_at(offset, bytes, now(&ecx.machine)))
+    }
+}
+
+impl FileDescription for VirtualFile {
+    fn name(&self) -> &'static str {
+        "virtual file"
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = self.read_to_ptr(self.offset.get(), ptr, len, ecx)?;
+        if let Ok(read) = result {
+            self.offset.set(self.offset.get().strict_add(u64::try_from(read).unwrap()));
+        }
+        finish.call(ecx, result)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        if self.append {
+            self.offset.set(self.inode.size());
+        }
+        let result = self.write_from_ptr(self.offset.get(), ptr, len, ecx)?;
+        if let Ok(written) = result {
+            self.offset.set(self.offset.get().strict_add(u64::try_from(written).unwrap()));
+        }
+        finish.call(ecx, result)
+    }
+
+    fn seek<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: SeekFrom,
+    ) -> InterpResult<'tcx, io::Result<u64>> {
+        let (base, offset) = match offset {
+            SeekFrom::Start(offset) => (0, i128::from(offset)),
+            SeekFrom::Current(offset) => (self.offset.get(), i128::from(offset)),
+            SeekFrom::End(offset) => (self.inode.size(), i128::from(offset)),
+        };
+        let Ok(new) = u64::try_from(i128::from(base).strict_add(offset)) else {
+            return interp_ok(Err(ErrorKind::InvalidInput.into()));
+        };
+        self.offset.set(new);
+        interp_ok(Ok(new))
+    }
+
+    fn short_fd_operations(&self) -> bool {
+        // Like for host files, this gives test coverage for programs not handling short accesses.
+        true
+    }
+
+    fn as_unix<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _ecx: &MiriInterpCx<'tcx>,
+    ) -> FileDescriptionRef<dyn UnixFileDescription> {
+        self
+    }
+}
+
+impl UnixFileDescription for VirtualFile {
+    fn pread<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: u64,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = self.read_to_ptr(offset, ptr, len, ecx)?;
+        finish.call(ecx, result)
+    }
+
+    fn pwrite<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        offset: u64,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = self.write_from_ptr(offset, ptr, len, ecx)?;
+        finish.call(ecx, result)
+    }
+
+    fn flock<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        _op: FlockOp,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        // Nobody else can access the virtual file system, so the locks are never contended.
+        interp_ok(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_entry(name: &str, kind: u8, mode: u32, data: &[u8], link: &str) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(format!("{mode:07o}").as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        header[257..263].copy_from_slice(b"ustar\0");
+        header.extend_from_slice(data);
+        header.resize(header.len().next_multiple_of(512), 0);
+        header
+    }
+
+    #[test]
+    fn seed_from_tar() {
+        let mut tar = Vec::new();
+        tar.extend(tar_entry("./data/", b'5', 0o700, b"", ""));
+        tar.extend(tar_entry("./data/file", b'0', 0o644, b"hello", ""));
+        tar.extend(tar_entry("./data/link", b'2', 0o777, b"", "file"));
+        tar.extend(tar_entry("./other/hard", b'1', 0o644, b"", "data/file"));
+        tar.extend([0; 1024]);
+
+        let mut vfs = VirtualFs::new(None).unwrap();
+        vfs.seed_from_tar(&tar).unwrap();
+
+        assert_eq!(vfs.metadata(b"/data", true).unwrap().mode, 0o700);
+        let file = vfs.resolve(b"/data/link", true).unwrap();
+        assert_eq!(file.read_at(0, 16).unwrap(), b"hello");
+        assert_eq!(file.metadata().nlink, 2);
+        assert!(Rc::ptr_eq(&file, &vfs.resolve(b"/other/hard", true).unwrap()));
+        assert_eq!(vfs.readlink(b"/data/link").unwrap(), b"file");
+        assert_eq!(vfs.realpath(b"/other/../data/./link").unwrap(), b"/data/file");
+    }
+
+    #[test]
+    fn resolution_errors() {
+        let mut vfs = VirtualFs::new(None).unwrap();
+        vfs.symlink(b"loop", b"/tmp/loop", Duration::ZERO).unwrap();
+        assert!(matches!(vfs.resolve(b"/tmp/loop", true), Err(LibcError("ELOOP"))));
+        assert!(matches!(vfs.resolve(b"/tmp/missing", true), Err(LibcError("ENOENT"))));
+        let options = VirtualOpenOptions { create: Some(0o644), ..Default::default() };
+        vfs.open(b"/tmp/file", &options, Duration::ZERO).unwrap();
+        assert!(matches!(vfs.resolve(b"/tmp/file/", true), Err(LibcError("ENOTDIR"))));
+        let res = vfs.mkdir(b"/tmp/file/dir", 0o755, Duration::ZERO);
+        assert!(matches!(res, Err(LibcError("ENOTDIR"))));
+    }
+}
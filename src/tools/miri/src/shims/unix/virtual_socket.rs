@@ -11,7 +11,8 @@
 
 use crate::concurrency::VClock;
 use crate::shims::files::{
-    EvalContextExt as _, FileDescription, FileDescriptionRef, WeakFileDescriptionRef,
+    DynFileDescriptionRef, EvalContextExt as _, FileDescription, FileDescriptionRef,
+    WeakFileDescriptionRef,
 };
 use crate::shims::readiness::DelayedReadinessUpdates;
 use crate::shims::unix::UnixFileDescription;
@@ -82,6 +83,11 @@ fn peer_fd(&self) -> &WeakFileDescriptionRef<VirtualSocket> {
     }
 }
 
+/// Returns whether `fd` is a pipe or socketpair implemented by this module.
+pub fn is_virtual_socket(fd: &DynFileDescriptionRef) -> bool {
+    fd.clone().downcast::<VirtualSocket>().is_some()
+}
+
 impl Drop for VirtualSocket {
     fn drop(&mut self) {
         if let Some(peer_fd) = self.peer_fd().upgrade() {
@@ -263,17 +269,20 @@ fn ioctl<'tcx>(
         arg: Option<&OpTy<'tcx>>,
         ecx: &mut MiriInterpCx<'tcx>,
     ) -> InterpResult<'tcx, i32> {
+        let fionbio = ecx.eval_libc("FIONBIO");
+
         match self.fd_type {
             VirtualSocketType::Socketpair => { /* fall-through to below */ }
             VirtualSocketType::PipeRead | VirtualSocketType::PipeWrite => {
-                // The standard library only uses ioctl for changing the blocking mode
-                // of Unix sockets. Thus, since using ioctl isn't the preferred way of
-                // changing the blocking mode, we don't support it on pipes.
-                throw_unsup_format!("cannot use ioctl on pipe");
+                // Besides changing the blocking mode of Unix sockets, the standard library only
+                // uses ioctl on Linux to make the pipes of a child process non-blocking when
+                // reading its output. We don't support any other operation on pipes.
+                if op != fionbio {
+                    throw_unsup_format!("ioctl: unsupported operation {op:#x} on pipe");
+                }
             }
         }
 
-        let fionbio = ecx.eval_libc("FIONBIO");
 
         if op == fionbio {
             // On these OSes, Rust uses the ioctl, so we trust that it is reasonable and controls
@@ -726,4 +735,96 @@ fn pipe2(
 
         interp_ok(Scalar::from_i32(0))
     }
+
+    /// Moves bytes from the front of `data` into the buffer that the peer of `socket` reads from,
+    /// as if another process had written them to `socket`. This moves as much as fits into the
+    /// buffer. Returns `false` if the peer has been closed, so nobody can read the data anymore.
+    fn virtual_socket_write_from_host(
+        &mut self,
+        socket: &DynFileDescriptionRef,
+        data: &mut VecDeque<u8>,
+    ) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        let socket = socket.clone().downcast::<VirtualSocket>().unwrap();
+
+        let Some(peer_fd) = socket.peer_fd().upgrade() else {
+            return interp_ok(false);
+        };
+        let Some(writebuf) = &peer_fd.readbuf else {
+            throw_unsup_format!("writing to the read end of a pipe from another process")
+        };
+        let mut writebuf = writebuf.borrow_mut();
+        let available_space = MAX_SOCKETPAIR_BUFFER_CAPACITY.strict_sub(writebuf.buf.len());
+        let write_size = data.len().min(available_space);
+        if write_size == 0 {
+            return interp_ok(true);
+        }
+        // The data comes from outside the program, so there is no clock to synchronize with.
+        writebuf.buf.extend(data.drain(..write_size));
+        drop(writebuf);
+
+        // This is the same as the end of a write in `virtual_socket_write`.
+        let waiting_threads = std::mem::take(&mut *peer_fd.blocked_read_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::VirtualSocket)?;
+        }
+        this.update_fd_readiness(socket, ReadinessUpdateFlags::DEFAULT)?;
+        this.update_fd_readiness(peer_fd, ReadinessUpdateFlags::FORCE_EDGE)?;
+        interp_ok(true)
+    }
+
+    /// Takes all bytes from the buffer of `socket`, as if another process had read them from
+    /// `socket`. Returns `None` at end-of-file, i.e. once the peer has been closed and the buffer
+    /// is empty.
+    fn virtual_socket_read_to_host(
+        &mut self,
+        socket: &DynFileDescriptionRef,
+    ) -> InterpResult<'tcx, Option<Vec<u8>>> {
+        let this = self.eval_context_mut();
+        let socket = socket.clone().downcast::<VirtualSocket>().unwrap();
+
+        let Some(readbuf) = &socket.readbuf else {
+            throw_unsup_format!("reading from the write end of a pipe from another process")
+        };
+        let data = Vec::from(std::mem::take(&mut readbuf.borrow_mut().buf));
+        if data.is_empty() {
+            let eof = socket.peer_fd().upgrade().is_none();
+            return interp_ok((!eof).then_some(data));
+        }
+
+        // This is the same as the end of a read in `virtual_socket_read`.
+        if let Some(peer_fd) = socket.peer_fd().upgrade() {
+            let waiting_threads = std::mem::take(&mut *peer_fd.blocked_write_tid.borrow_mut());
+            for thread_id in waiting_threads {
+                this.unblock_thread(thread_id, BlockReason::VirtualSocket)?;
+            }
+            this.update_fd_readiness(peer_fd, ReadinessUpdateFlags::FORCE_EDGE)?;
+        }
+        this.update_fd_readiness(socket, ReadinessUpdateFlags::DEFAULT)?;
+        interp_ok(Some(data))
+    }
+
+    /// Drops a reference to `socket` that was held by another process. If that was the last
+    /// reference, the threads blocked on the peer are woken up so they see end-of-file or a
+    /// broken pipe.
+    fn virtual_socket_close_from_host(
+        &mut self,
+        socket: DynFileDescriptionRef,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let socket = socket.downcast::<VirtualSocket>().unwrap();
+
+        let Some(peer_fd) = socket.peer_fd().upgrade() else {
+            return interp_ok(());
+        };
+        drop(socket);
+        // Threads that are blocked on the peer must check again whether their operation can now
+        // complete. Threads of this program block again if they still hold the socket open.
+        let mut waiting_threads = std::mem::take(&mut *peer_fd.blocked_read_tid.borrow_mut());
+        waiting_threads.append(&mut *peer_fd.blocked_write_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::VirtualSocket)?;
+        }
+        interp_ok(())
+    }
 }
@@ -6,6 +6,7 @@
 use rustc_target::spec::Os;
 
 use self::helpers::windows_check_buffer_size;
+use crate::shims::files::EvalContextExt as _;
 use crate::*;
 
 #[derive(Default)]
@@ -156,7 +157,7 @@ fn GetCurrentDirectoryW(
         }
 
         // If we cannot get the current directory, we return 0
-        match env::current_dir() {
+        match this.query_host_traced("cwd", |_| interp_ok(env::current_dir()))? {
             Ok(cwd) => {
                 // This can in fact return 0. It is up to the caller to set last_error to 0
                 // beforehand and check it afterwards to exclude that case.
@@ -0,0 +1,27 @@
+// The program `tests/coverage/mod.rs` runs with `-Zmiri-coverage`. The `hits` comments say how often
+// each line must be executed according to the lcov output.
+
+fn classify(n: u32) -> &'static str {
+    if n % 2 == 0 {
+        "even" // hits: 3
+    } else {
+        "odd" // hits: 2
+    }
+}
+
+fn never_called() {
+    println!("unreachable"); // hits: 0
+}
+
+fn main() {
+    let mut evens = 0; // hits: 1
+    for n in 0..5 {
+        if classify(n) == "even" {
+            evens += 1; // hits: 3
+        }
+    }
+    assert_eq!(evens, 3); // hits: 1
+    if evens > 10 {
+        never_called(); // hits: 0
+    }
+}
@@ -0,0 +1,64 @@
+//! Runs `line-counts.rs` with `-Zmiri-coverage` and checks the function and line execution counts
+//! of the lcov tracefile against the `// hits: N` comments of the program.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+use ui_test::color_eyre::eyre::{Result, ensure, eyre};
+
+use crate::miri_path;
+
+const PROGRAM: &str = "tests/coverage/line-counts.rs";
+
+pub fn run(target: &str, tmpdir: &Path) -> Result<()> {
+    let lcov = tmpdir.join("line-counts.lcov");
+    let output = Command::new(miri_path())
+        .arg(format!("--sysroot={}", env::var("MIRI_SYSROOT")?))
+        .arg(format!("--target={target}"))
+        .arg(format!("-Zmiri-coverage={}", lcov.display()))
+        .args(["--edition=2021", PROGRAM])
+        .output()?;
+    ensure!(output.status.success(), "Miri failed: {}", String::from_utf8_lossy(&output.stderr));
+    let lcov = fs::read_to_string(&lcov)?;
+
+    // Only the record of the program itself matters, not those of the standard library.
+    let record = lcov
+        .split("end_of_record")
+        .find(|record| {
+            let file = record.trim_start().lines().next().unwrap_or_default();
+            file.starts_with("SF:") && file.ends_with("line-counts.rs")
+        })
+        .ok_or_else(|| eyre!("no record for {PROGRAM}:\n{lcov}"))?;
+    let mut functions = BTreeMap::new();
+    let mut lines = BTreeMap::new();
+    for entry in record.lines() {
+        if let Some((calls, name)) = entry.strip_prefix("FNDA:").and_then(|e| e.split_once(',')) {
+            let name = name.rsplit("::").next().unwrap().to_owned();
+            functions.insert(name, calls.parse::<u64>()?);
+        } else if let Some((line, hits)) = entry.strip_prefix("DA:").and_then(|e| e.split_once(','))
+        {
+            lines.insert(line.parse::<usize>()?, hits.parse::<u64>()?);
+        }
+    }
+
+    let expected_calls = [("main", 1), ("classify", 5), ("never_called", 0)];
+    for (name, calls) in expected_calls {
+        ensure!(
+            functions.get(name) == Some(&calls),
+            "`{name}` should have {calls} calls, the lcov record has {:?}:\n{record}",
+            functions.get(name),
+        );
+    }
+    for (idx, source_line) in fs::read_to_string(PROGRAM)?.lines().enumerate() {
+        let Some((_, hits)) = source_line.split_once("// hits: ") else { continue };
+        let (line, hits) = (idx + 1, hits.parse::<u64>()?);
+        ensure!(
+            lines.get(&line) == Some(&hits),
+            "line {line} should have {hits} hits, the lcov record has {:?}:\n{record}",
+            lines.get(&line),
+        );
+    }
+    Ok(())
+}
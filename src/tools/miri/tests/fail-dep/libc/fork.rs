@@ -0,0 +1,9 @@
+//! Miri cannot fork the interpreted program; children can only be started with `posix_spawn`.
+//@ignore-target: windows # No `libc::fork` on Windows
+
+fn main() {
+    unsafe {
+        libc::fork();
+        //~^ ERROR: unsupported operation: `fork` is not supported
+    }
+}
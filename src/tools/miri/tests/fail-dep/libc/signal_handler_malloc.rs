@@ -0,0 +1,14 @@
+//@ignore-target: windows # No POSIX signals on Windows
+
+extern "C" fn handler(_sig: libc::c_int) {
+    unsafe {
+        libc::malloc(8); //~ERROR: not async-signal-safe
+    }
+}
+
+fn main() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+        libc::raise(libc::SIGUSR1);
+    }
+}
@@ -0,0 +1,12 @@
+//! The trace expects a write to the host first, but the program reads from stdin instead.
+//@ignore-target: windows # No libc read on Windows
+//@compile-flags: -Zmiri-disable-isolation -Zmiri-preemption-rate=0 -Zmiri-disable-weak-memory-emulation
+//@compile-flags: -Zmiri-replay-trace=tests/fail-dep/libc/trace-replay-divergence.trace
+
+fn main() {
+    let mut buf = [0u8; 4];
+    unsafe {
+        libc::read(0, buf.as_mut_ptr().cast(), buf.len());
+        //~^ ERROR: the execution diverged from the replayed trace at event 1
+    }
+}
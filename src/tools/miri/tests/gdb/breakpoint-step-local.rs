@@ -0,0 +1,11 @@
+// The program `tests/gdb/mod.rs` debugs through `-Zmiri-gdb-server`.
+
+fn inspect(x: u32) -> u32 {
+    let y = x + 1;
+    y * 2
+}
+
+fn main() {
+    let result = inspect(42);
+    assert_eq!(result, 86);
+}
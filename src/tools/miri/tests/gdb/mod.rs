@@ -0,0 +1,188 @@
+//! Debugs `breakpoint-step-local.rs` through `-Zmiri-gdb-server`, speaking the remote serial
+//! protocol the way GDB does: it finds a function in the symbol files the stub reports as
+//! libraries, sets a breakpoint on it, reads an argument through the frame pointer like the debug
+//! info describes it, and single-steps.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use std::{env, fs, thread};
+
+use ui_test::color_eyre::eyre::{Result, bail, ensure, eyre};
+
+use crate::miri_path;
+
+const PROGRAM: &str = "tests/gdb/breakpoint-step-local.rs";
+
+pub fn run() -> Result<()> {
+    let mut miri = Command::new(miri_path())
+        .arg(format!("--sysroot={}", env::var("MIRI_SYSROOT")?))
+        .args(["--edition=2021", "-Zmiri-gdb-server=127.0.0.1:0", PROGRAM])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stderr = BufReader::new(miri.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line)?;
+    let addr = line
+        .strip_prefix("Waiting for a debugger to connect on ")
+        .and_then(|addr| addr.trim().strip_suffix("..."))
+        .ok_or_else(|| eyre!("unexpected output of Miri: {line}"))?
+        .to_owned();
+    // Keep draining stderr so that Miri never blocks on it.
+    let stderr = thread::spawn(move || {
+        let mut rest = String::new();
+        stderr.read_to_string(&mut rest).map(|_| rest)
+    });
+
+    let mut gdb = Client::connect(&addr)?;
+    let supported = gdb.request("qSupported:swbreak+;xmlRegisters=i386")?;
+    ensure!(supported.contains("qXfer:libraries:read+"), "no library list: {supported}");
+
+    // Find `inspect` in the symbol files, like `break inspect` does.
+    let libraries = gdb.xfer("libraries")?;
+    let inspect = library_paths(&libraries)
+        .iter()
+        .find_map(|path| find_symbol(&fs::read(path).ok()?, "::inspect"))
+        .ok_or_else(|| eyre!("`inspect` is in none of the libraries: {libraries}"))?;
+    ensure!(gdb.request(&format!("Z0,{inspect:x},1"))? == "OK");
+
+    let stop = gdb.resume("c")?;
+    ensure!(stop.starts_with("T05") && stop.contains("swbreak"), "unexpected stop: {stop}");
+    let (pc, frame) = gdb.registers()?;
+    ensure!(pc == inspect, "stopped at {pc:#x} instead of {inspect:#x}");
+
+    // The debug info locates `x`, the first local, at the address stored at `rbp + 16`.
+    let x_addr = u64::from_le_bytes(gdb.read_memory(frame + 16, 8)?.try_into().unwrap());
+    let x = gdb.read_memory(x_addr, 4)?;
+    ensure!(x == 42u32.to_le_bytes(), "`x` is {x:?}");
+
+    let stop = gdb.resume("s")?;
+    ensure!(stop.starts_with("T05") && !stop.contains("swbreak"), "unexpected stop: {stop}");
+    let (pc, _) = gdb.registers()?;
+    ensure!(pc > inspect, "the step did not advance: {pc:#x}");
+
+    ensure!(gdb.request(&format!("z0,{inspect:x},1"))? == "OK");
+    let exit = gdb.resume("c")?;
+    ensure!(exit == "W00", "unexpected exit: {exit}");
+    let status = miri.wait()?;
+    let stderr = stderr.join().unwrap()?;
+    ensure!(status.success(), "Miri failed: {stderr}");
+    Ok(())
+}
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect(addr: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+        Ok(Client { stream })
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn request(&mut self, packet: &str) -> Result<String> {
+        let sum = packet.bytes().fold(0u8, u8::wrapping_add);
+        write!(self.stream, "${packet}#{sum:02x}")?;
+        let ack = self.read_byte()?;
+        ensure!(ack == b'+', "`{packet}` was not acknowledged");
+        self.reply()
+    }
+
+    fn reply(&mut self) -> Result<String> {
+        while self.read_byte()? != b'$' {}
+        let mut data = Vec::new();
+        loop {
+            match self.read_byte()? {
+                b'#' => break,
+                b'}' => data.push(self.read_byte()? ^ 0x20),
+                byte => data.push(byte),
+            }
+        }
+        // Skip the checksum; the transport is reliable.
+        self.read_byte()?;
+        self.read_byte()?;
+        self.stream.write_all(b"+")?;
+        Ok(String::from_utf8(data)?)
+    }
+
+    /// Resumes the program and returns the reply to the next stop that is not just a
+    /// notification about new libraries.
+    fn resume(&mut self, packet: &str) -> Result<String> {
+        let mut reply = self.request(packet)?;
+        while reply.contains("library:") && !reply.contains("swbreak") && packet == "c" {
+            reply = self.request("c")?;
+        }
+        Ok(reply)
+    }
+
+    fn xfer(&mut self, object: &str) -> Result<String> {
+        let mut data = String::new();
+        loop {
+            let reply = self.request(&format!("qXfer:{object}:read::{:x},800", data.len()))?;
+            match reply.split_at_checked(1) {
+                Some(("m", chunk)) => data.push_str(chunk),
+                Some(("l", chunk)) => return Ok(data + chunk),
+                _ => bail!("unexpected reply: {reply}"),
+            }
+        }
+    }
+
+    /// Returns `rip` and `rbp`.
+    fn registers(&mut self) -> Result<(u64, u64)> {
+        let regs = unhex(&self.request("g")?)?;
+        let reg = |idx: usize| u64::from_le_bytes(regs[idx * 8..][..8].try_into().unwrap());
+        Ok((reg(16), reg(6)))
+    }
+
+    fn read_memory(&mut self, addr: u64, len: u64) -> Result<Vec<u8>> {
+        unhex(&self.request(&format!("m{addr:x},{len:x}"))?)
+    }
+}
+
+fn unhex(s: &str) -> Result<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| Ok(u8::from_str_radix(s.get(i..i + 2).ok_or_else(|| eyre!("odd length"))?, 16)?))
+        .collect()
+}
+
+fn library_paths(xml: &str) -> Vec<String> {
+    xml.split("<library name=\"")
+        .skip(1)
+        .filter_map(|rest| {
+            Some(rest.split_once('"')?.0.replace("&quot;", "\"").replace("&amp;", "&"))
+        })
+        .collect()
+}
+
+/// Returns the address of the first function symbol of an ELF file whose name ends with
+/// `suffix`.
+fn find_symbol(elf: &[u8], suffix: &str) -> Option<u64> {
+    let u16_at =
+        |offset: usize| Some(u16::from_le_bytes(elf.get(offset..offset + 2)?.try_into().ok()?));
+    let u32_at =
+        |offset: usize| Some(u32::from_le_bytes(elf.get(offset..offset + 4)?.try_into().ok()?));
+    let u64_at =
+        |offset: usize| Some(u64::from_le_bytes(elf.get(offset..offset + 8)?.try_into().ok()?));
+    let section_headers = usize::try_from(u64_at(0x28)?).ok()?;
+    let section = |idx: usize| section_headers + idx * 64;
+    let symtab =
+        (0..usize::from(u16_at(0x3c)?)).map(section).find(|&s| u32_at(s + 4) == Some(2))?;
+    let strtab = section(usize::try_from(u32_at(symtab + 40)?).ok()?);
+    let strtab = usize::try_from(u64_at(strtab + 24)?).ok()?;
+    let (symbols, size) = (usize::try_from(u64_at(symtab + 24)?).ok()?, u64_at(symtab + 32)?);
+    (symbols..symbols + usize::try_from(size).ok()?).step_by(24).find_map(|symbol| {
+        let name = strtab + usize::try_from(u32_at(symbol)?).ok()?;
+        let name = elf[name..].split(|&b| b == 0).next()?;
+        std::str::from_utf8(name).ok()?.ends_with(suffix).then(|| u64_at(symbol + 8)).flatten()
+    })
+}
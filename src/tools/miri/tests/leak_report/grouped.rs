@@ -0,0 +1,30 @@
+// The program `tests/leak_report/mod.rs` runs with `-Zmiri-leak-report` and `allowlist.txt`. The
+// `site` comments name the allocation sites it looks for in the report, and the `store` comments
+// where the pointers holding leaked allocations are stored.
+
+struct Node {
+    _next: Option<Box<Node>>,
+}
+
+fn leak_many() {
+    for _ in 0..3 {
+        std::mem::forget(Box::new(0u64)); // site: many
+    }
+}
+
+fn leak_list() {
+    let tail = Box::new(Node { _next: None }); // site: tail
+    let mut head = Box::new(Node { _next: None }); // site: head
+    head._next = Some(tail); // store: tail
+    std::mem::forget(head);
+}
+
+fn intentional_leak() {
+    Box::leak(Box::new(vec![0u8; 100]));
+}
+
+fn main() {
+    leak_many();
+    leak_list();
+    intentional_leak();
+}
@@ -0,0 +1,82 @@
+//! Runs `grouped.rs` with `-Zmiri-leak-report` and `-Zmiri-leak-allowlist`, and checks how the
+//! report groups the leaks by allocation site.
+
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+use serde_json::{Value, json};
+use ui_test::color_eyre::eyre::{Result, ensure, eyre};
+
+use crate::miri_path;
+
+const PROGRAM: &str = "tests/leak_report/grouped.rs";
+
+pub fn run(target: &str, tmpdir: &Path) -> Result<()> {
+    let report = tmpdir.join("grouped.json");
+    let output = Command::new(miri_path())
+        .arg(format!("--sysroot={}", env::var("MIRI_SYSROOT")?))
+        .arg(format!("--target={target}"))
+        .arg(format!("-Zmiri-leak-report={}", report.display()))
+        .arg("-Zmiri-leak-allowlist=tests/leak_report/allowlist.txt")
+        .args(["--edition=2021", PROGRAM])
+        .output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    ensure!(!output.status.success(), "Miri did not report the leaks: {stderr}");
+    ensure!(
+        stderr.contains("memory leaked: 5 allocations (40 bytes) at 3 allocation sites"),
+        "unexpected output of Miri: {stderr}"
+    );
+    let report: Value = serde_json::from_str(&fs::read_to_string(&report)?)?;
+    ensure!(report["leaked_allocations"] == 5, "{report:#}");
+    ensure!(report["leaked_bytes"] == 40, "{report:#}");
+    // The boxed vector and its buffer.
+    ensure!(report["suppressed_allocations"] == 2, "{report:#}");
+
+    let source = fs::read_to_string(PROGRAM)?;
+    // The location of the line of `PROGRAM` ending with the given comment.
+    let location = |comment: &str| -> Result<String> {
+        let line = source
+            .lines()
+            .position(|line| line.ends_with(&format!("// {comment}")))
+            .ok_or_else(|| eyre!("no `{comment}` in {PROGRAM}"))?;
+        Ok(format!("grouped.rs:{}:", line + 1))
+    };
+    let site = |name: &str| -> Result<&Value> {
+        let location = location(&format!("site: {name}"))?;
+        report["sites"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|site| {
+                site["backtrace"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .any(|frame| frame.as_str().is_some_and(|frame| frame.contains(&location)))
+            })
+            .ok_or_else(|| eyre!("no site `{name}` in the report: {report:#}"))
+    };
+
+    // The sites are sorted by the number of leaked bytes, and the three boxes leaked in the loop
+    // form a single site.
+    let many = site("many")?;
+    ensure!(report["sites"][0] == *many, "{report:#}");
+    ensure!(many["allocations"] == 3 && many["bytes"] == 24, "{many:#}");
+    ensure!(many["direct_allocations"] == 3 && many["held_by"] == json!([]), "{many:#}");
+
+    let (head, tail) = (site("head")?, site("tail")?);
+    ensure!(head["allocations"] == 1 && head["direct_allocations"] == 1, "{head:#}");
+    ensure!(tail["allocations"] == 1 && tail["direct_allocations"] == 0, "{tail:#}");
+    let held_by = &tail["held_by"];
+    ensure!(held_by.as_array().is_some_and(|held_by| held_by.len() == 1), "{tail:#}");
+    ensure!(held_by[0]["site"] == head["site"] && held_by[0]["allocations"] == 1, "{report:#}");
+    // The pointer to the tail was stored where the list was linked, not where the head was
+    // allocated.
+    let stored_at = location("store: tail")?;
+    ensure!(
+        held_by[0]["stored_at"].as_str().is_some_and(|span| span.contains(&stored_at)),
+        "{tail:#}"
+    );
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//@only-target: linux android
+//@compile-flags: -Zmiri-disable-isolation -Zmiri-deterministic-concurrency
+
+use std::ffi::{CString, OsStr};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+#[path = "../../utils/mod.rs"]
+mod utils;
+use libc_utils::epoll::*;
+use libc_utils::*;
+
+fn main() {
+    test_dir_events();
+    test_file_events();
+    test_epoll();
+    test_invalid();
+}
+
+#[derive(Debug, PartialEq)]
+struct Event {
+    wd: i32,
+    mask: u32,
+    name: Option<String>,
+}
+
+fn add_watch(fd: i32, path: &Path, mask: u32) -> i32 {
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    errno_result(unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) }).unwrap()
+}
+
+/// Reads the queued events, blocking unless `fd` is non-blocking.
+fn read_events(fd: i32) -> std::io::Result<Vec<Event>> {
+    let mut buf = [0u8; 1024];
+    let len = errno_result(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) })?;
+    let mut buf = &buf[..len as usize];
+    let mut events = Vec::new();
+    while !buf.is_empty() {
+        let field = |i: usize| u32::from_ne_bytes(buf[i * 4..][..4].try_into().unwrap());
+        let name_len = field(3) as usize;
+        let name = &buf[16..][..name_len];
+        // The name is padded with null bytes.
+        let name = name.split(|&b| b == 0).next().unwrap();
+        events.push(Event {
+            wd: field(0) as i32,
+            mask: field(1),
+            name: (!name.is_empty()).then(|| OsStr::from_bytes(name).to_str().unwrap().to_owned()),
+        });
+        buf = &buf[16 + name_len..];
+    }
+    Ok(events)
+}
+
+fn fresh_dir(name: &str) -> std::path::PathBuf {
+    let dir = utils::tmp().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir(&dir).unwrap();
+    dir
+}
+
+fn test_dir_events() {
+    let dir = fresh_dir("miri_test_inotify_dir");
+    let fd = errno_result(unsafe { libc::inotify_init1(libc::IN_CLOEXEC) }).unwrap();
+    let wd = add_watch(fd, &dir, libc::IN_CREATE | libc::IN_DELETE);
+    // Watching the same path again returns the same watch descriptor.
+    assert_eq!(add_watch(fd, &dir, libc::IN_CREATE | libc::IN_DELETE), wd);
+
+    fs::write(dir.join("file"), b"hello").unwrap();
+    fs::create_dir(dir.join("subdir")).unwrap();
+    // The read blocks until the changes got noticed.
+    assert_eq!(read_events(fd).unwrap(), [
+        Event { wd, mask: libc::IN_CREATE, name: Some("file".to_owned()) },
+        Event { wd, mask: libc::IN_CREATE | libc::IN_ISDIR, name: Some("subdir".to_owned()) },
+    ]);
+
+    fs::remove_file(dir.join("file")).unwrap();
+    assert_eq!(read_events(fd).unwrap(), [Event {
+        wd,
+        mask: libc::IN_DELETE,
+        name: Some("file".to_owned())
+    }]);
+
+    // Removing the watch queues an `IN_IGNORED` event.
+    errno_check(unsafe { libc::inotify_rm_watch(fd, wd) });
+    assert_eq!(read_events(fd).unwrap(), [Event { wd, mask: libc::IN_IGNORED, name: None }]);
+
+    errno_check(unsafe { libc::close(fd) });
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn test_file_events() {
+    let dir = fresh_dir("miri_test_inotify_file");
+    let path = dir.join("file");
+    fs::write(&path, b"hello").unwrap();
+    let fd = errno_result(unsafe { libc::inotify_init1(libc::IN_NONBLOCK) }).unwrap();
+    let wd = add_watch(fd, &path, libc::IN_MODIFY | libc::IN_DELETE_SELF);
+    assert_eq!(read_events(fd).unwrap_err().raw_os_error(), Some(libc::EAGAIN));
+
+    fs::write(&path, b"hello world").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(read_events(fd).unwrap(), [Event { wd, mask: libc::IN_MODIFY, name: None }]);
+
+    // Deleting the file removes the watch.
+    fs::remove_file(&path).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(read_events(fd).unwrap(), [
+        Event { wd, mask: libc::IN_DELETE_SELF, name: None },
+        Event { wd, mask: libc::IN_IGNORED, name: None },
+    ]);
+    let res = errno_result(unsafe { libc::inotify_rm_watch(fd, wd) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+
+    errno_check(unsafe { libc::close(fd) });
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn test_epoll() {
+    let dir = fresh_dir("miri_test_inotify_epoll");
+    let fd = errno_result(unsafe { libc::inotify_init1(libc::IN_NONBLOCK) }).unwrap();
+    let wd = add_watch(fd, &dir, libc::IN_CREATE);
+    let epfd = errno_result(unsafe { libc::epoll_create1(0) }).unwrap();
+    epoll_ctl_add(epfd, fd, EPOLLIN | EPOLLET).unwrap();
+    check_epoll_wait_noblock(epfd, &[]);
+
+    fs::write(dir.join("file"), b"").unwrap();
+    // The change is noticed while `epoll_wait` is blocked.
+    check_epoll_wait(epfd, &[Ev { events: EPOLLIN, data: fd }], -1);
+    assert_eq!(read_events(fd).unwrap(), [Event {
+        wd,
+        mask: libc::IN_CREATE,
+        name: Some("file".to_owned())
+    }]);
+    check_epoll_wait_noblock(epfd, &[]);
+
+    errno_check(unsafe { libc::close(epfd) });
+    errno_check(unsafe { libc::close(fd) });
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn test_invalid() {
+    let res = errno_result(unsafe { libc::inotify_init1(0x1) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+
+    let dir = fresh_dir("miri_test_inotify_invalid");
+    let path = dir.join("file");
+    fs::write(&path, b"").unwrap();
+    let fd = errno_result(unsafe { libc::inotify_init1(0) }).unwrap();
+    let cpath = CString::new(path.as_os_str().as_bytes()).unwrap();
+    // `IN_ONLYDIR` rejects files.
+    let res = errno_result(unsafe {
+        libc::inotify_add_watch(fd, cpath.as_ptr(), libc::IN_MODIFY | libc::IN_ONLYDIR)
+    });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::ENOTDIR));
+    // An empty mask is invalid.
+    let res = errno_result(unsafe { libc::inotify_add_watch(fd, cpath.as_ptr(), 0) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+    // Missing paths are reported.
+    let missing = CString::new(dir.join("missing").as_os_str().as_bytes()).unwrap();
+    let res =
+        errno_result(unsafe { libc::inotify_add_watch(fd, missing.as_ptr(), libc::IN_MODIFY) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::ENOENT));
+
+    errno_check(unsafe { libc::close(fd) });
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,366 @@
+//@only-target: linux android
+// The buffers are passed to the kernel as integers.
+//@compile-flags: -Zmiri-permissive-provenance -Zmiri-deterministic-concurrency
+//@compile-flags: -Zmiri-no-short-fd-operations
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{ptr, thread};
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+use libc_utils::*;
+
+// The `libc` crate does not provide the io_uring definitions.
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+const IORING_ENTER_GETEVENTS: u32 = 1;
+const IORING_OP_NOP: u8 = 0;
+const IORING_OP_POLL_ADD: u8 = 6;
+const IORING_OP_CLOSE: u8 = 19;
+const IORING_OP_READ: u8 = 22;
+const IORING_OP_WRITE: u8 = 23;
+
+#[repr(C)]
+#[derive(Default)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    op_flags: u32,
+    user_data: u64,
+    pad: [u64; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+/// A minimal io_uring client.
+struct Ring {
+    fd: i32,
+    params: Params,
+    sq_ring: *mut u8,
+    cq_ring: *mut u8,
+    sqes: *mut Sqe,
+}
+
+impl Ring {
+    fn new(entries: u32) -> Ring {
+        let mut params = Params::default();
+        let fd = errno_result(unsafe {
+            libc::syscall(libc::SYS_io_uring_setup, entries, &raw mut params)
+        })
+        .unwrap();
+        let fd = i32::try_from(fd).unwrap();
+        let [sq_ring, cq_ring, sqes] =
+            Ring::regions(&params).map(|(size, offset)| map(fd, size, offset));
+        Ring { fd, params, sq_ring, cq_ring, sqes: sqes.cast() }
+    }
+
+    /// The sizes and offsets of the regions of an instance.
+    fn regions(params: &Params) -> [(usize, libc::off_t); 3] {
+        [
+            (params.sq_off.array as usize + params.sq_entries as usize * 4, IORING_OFF_SQ_RING),
+            (params.cq_off.cqes as usize + params.cq_entries as usize * 16, IORING_OFF_CQ_RING),
+            (params.sq_entries as usize * 64, IORING_OFF_SQES),
+        ]
+    }
+
+    fn unmap(&self) {
+        let ptrs = [self.sq_ring, self.cq_ring, self.sqes.cast()];
+        for (ptr, (size, _)) in ptrs.into_iter().zip(Ring::regions(&self.params)) {
+            assert_eq!(unsafe { libc::munmap(ptr.cast(), size) }, 0);
+        }
+    }
+
+    fn sq_u32(&self, offset: u32) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.sq_ring.add(offset as usize).cast()) }
+    }
+
+    fn cq_u32(&self, offset: u32) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.cq_ring.add(offset as usize).cast()) }
+    }
+
+    /// Adds an entry to the submission queue.
+    fn push(&self, sqe: Sqe) {
+        let tail = self.sq_u32(self.params.sq_off.tail).load(Ordering::Relaxed);
+        let head = self.sq_u32(self.params.sq_off.head).load(Ordering::Acquire);
+        assert!(tail.wrapping_sub(head) < self.params.sq_entries, "submission queue is full");
+        let idx = tail & self.params.sq_entries.wrapping_sub(1);
+        unsafe {
+            self.sqes.add(idx as usize).write(sqe);
+            let array = self.sq_ring.add(self.params.sq_off.array as usize).cast::<u32>();
+            array.add(idx as usize).write(idx);
+        }
+        self.sq_u32(self.params.sq_off.tail).store(tail.wrapping_add(1), Ordering::Release);
+    }
+
+    fn enter(&self, to_submit: u32, min_complete: u32, flags: u32) -> i64 {
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_enter,
+                self.fd,
+                to_submit,
+                min_complete,
+                flags,
+                ptr::null::<libc::sigset_t>(),
+                0usize,
+            )
+        };
+        errno_result(ret).unwrap()
+    }
+
+    /// Removes all entries from the completion queue.
+    fn drain(&self) -> Vec<Cqe> {
+        let head = self.cq_u32(self.params.cq_off.head).load(Ordering::Relaxed);
+        let tail = self.cq_u32(self.params.cq_off.tail).load(Ordering::Acquire);
+        let mask = self.params.cq_entries.wrapping_sub(1);
+        let mut cqes = Vec::new();
+        let mut idx = head;
+        while idx != tail {
+            let cqe = unsafe {
+                self.cq_ring
+                    .add(self.params.cq_off.cqes as usize)
+                    .cast::<Cqe>()
+                    .add((idx & mask) as usize)
+                    .read()
+            };
+            cqes.push(cqe);
+            idx = idx.wrapping_add(1);
+        }
+        self.cq_u32(self.params.cq_off.head).store(tail, Ordering::Release);
+        cqes
+    }
+}
+
+fn map(fd: i32, size: usize, offset: libc::off_t) -> *mut u8 {
+    let ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_POPULATE,
+            fd,
+            offset,
+        )
+    };
+    assert_ne!(ptr, libc::MAP_FAILED);
+    ptr.cast()
+}
+
+fn cqe(user_data: u64, res: i32) -> Cqe {
+    Cqe { user_data, res, flags: 0 }
+}
+
+fn main() {
+    test_setup();
+    test_nop();
+    test_pipe();
+    test_blocking_read();
+    test_poll();
+    test_bad_fd();
+    test_close();
+    test_unmap();
+}
+
+fn test_setup() {
+    let ring = Ring::new(3);
+    // The sizes are rounded up to a power of two, and the completion queue is twice as large.
+    assert_eq!(ring.params.sq_entries, 4);
+    assert_eq!(ring.params.cq_entries, 8);
+    assert_eq!(ring.sq_u32(ring.params.sq_off.ring_entries).load(Ordering::Relaxed), 4);
+    assert_eq!(ring.cq_u32(ring.params.cq_off.ring_mask).load(Ordering::Relaxed), 7);
+
+    let mut params = Params::default();
+    let ret = unsafe { libc::syscall(libc::SYS_io_uring_setup, 0u32, &raw mut params) };
+    assert_eq!(ret, -1);
+    assert_eq!(errno(), libc::EINVAL);
+}
+
+fn test_nop() {
+    let ring = Ring::new(4);
+    ring.push(Sqe { opcode: IORING_OP_NOP, user_data: 1, ..Default::default() });
+    ring.push(Sqe { opcode: IORING_OP_NOP, user_data: 2, ..Default::default() });
+    // Only submit the first entry.
+    assert_eq!(ring.enter(1, 0, 0), 1);
+    assert_eq!(ring.drain(), [cqe(1, 0)]);
+    assert_eq!(ring.enter(1, 1, IORING_ENTER_GETEVENTS), 1);
+    assert_eq!(ring.drain(), [cqe(2, 0)]);
+}
+
+fn test_pipe() {
+    let ring = Ring::new(4);
+    let mut fds = [-1, -1];
+    errno_check(unsafe { libc::pipe(fds.as_mut_ptr()) });
+
+    let mut buf = [0u8; 5];
+    let data = *b"hello";
+    // The read has to wait for the write.
+    ring.push(Sqe {
+        opcode: IORING_OP_READ,
+        fd: fds[0],
+        addr: buf.as_mut_ptr() as u64,
+        len: 5,
+        off: u64::MAX,
+        user_data: 1,
+        ..Default::default()
+    });
+    ring.push(Sqe {
+        opcode: IORING_OP_WRITE,
+        fd: fds[1],
+        addr: data.as_ptr() as u64,
+        len: 5,
+        off: u64::MAX,
+        user_data: 2,
+        ..Default::default()
+    });
+    assert_eq!(ring.enter(2, 2, IORING_ENTER_GETEVENTS), 2);
+    assert_eq!(ring.drain(), [cqe(2, 5), cqe(1, 5)]);
+    assert_eq!(buf, data);
+}
+
+fn test_blocking_read() {
+    let ring = Ring::new(4);
+    let mut fds = [-1, -1];
+    errno_check(unsafe { libc::pipe(fds.as_mut_ptr()) });
+
+    let mut buf = [0u8; 3];
+    ring.push(Sqe {
+        opcode: IORING_OP_READ,
+        fd: fds[0],
+        addr: buf.as_mut_ptr() as u64,
+        len: 3,
+        off: u64::MAX,
+        user_data: 7,
+        ..Default::default()
+    });
+    let writer = thread::spawn(move || {
+        write_all(fds[1], b"abc").unwrap();
+    });
+    // This blocks until the other thread wrote to the pipe.
+    assert_eq!(ring.enter(1, 1, IORING_ENTER_GETEVENTS), 1);
+    assert_eq!(ring.drain(), [cqe(7, 3)]);
+    assert_eq!(&buf, b"abc");
+    writer.join().unwrap();
+}
+
+fn test_poll() {
+    let ring = Ring::new(4);
+    let fd = errno_result(unsafe { libc::eventfd(0, libc::EFD_NONBLOCK) }).unwrap();
+
+    ring.push(Sqe {
+        opcode: IORING_OP_POLL_ADD,
+        fd,
+        op_flags: libc::POLLIN as u32,
+        user_data: 3,
+        ..Default::default()
+    });
+    assert_eq!(ring.enter(1, 0, 0), 1);
+    // The eventfd is not readable yet.
+    assert_eq!(ring.drain(), []);
+    eventfd::write_val(fd, 1).unwrap();
+    assert_eq!(ring.enter(0, 1, IORING_ENTER_GETEVENTS), 0);
+    assert_eq!(ring.drain(), [cqe(3, libc::POLLIN.into())]);
+}
+
+fn test_bad_fd() {
+    let ring = Ring::new(4);
+    let mut buf = [0u8; 1];
+    ring.push(Sqe {
+        opcode: IORING_OP_READ,
+        fd: 1000,
+        addr: buf.as_mut_ptr() as u64,
+        len: 1,
+        user_data: 4,
+        ..Default::default()
+    });
+    assert_eq!(ring.enter(1, 1, IORING_ENTER_GETEVENTS), 1);
+    assert_eq!(ring.drain(), [cqe(4, -libc::EBADF)]);
+
+    // `io_uring_enter` on a file descriptor that is not an io_uring instance.
+    let ret = unsafe {
+        libc::syscall(libc::SYS_io_uring_enter, 1i32, 0u32, 0u32, 0u32, ptr::null::<u8>(), 0usize)
+    };
+    assert_eq!(ret, -1);
+    assert_eq!(errno(), libc::EOPNOTSUPP);
+}
+
+fn test_close() {
+    let ring = Ring::new(4);
+    let fd = errno_result(unsafe { libc::eventfd(0, 0) }).unwrap();
+    ring.push(Sqe { opcode: IORING_OP_CLOSE, fd, user_data: 5, ..Default::default() });
+    ring.push(Sqe { opcode: IORING_OP_CLOSE, fd, user_data: 6, ..Default::default() });
+    assert_eq!(ring.enter(2, 2, IORING_ENTER_GETEVENTS), 2);
+    assert_eq!(ring.drain(), [cqe(5, 0), cqe(6, -libc::EBADF)]);
+    assert_eq!(unsafe { libc::close(fd) }, -1);
+}
+
+fn test_unmap() {
+    // Unmapping the regions leaves the instance open, and they can be mapped again.
+    let ring = Ring::new(4);
+    ring.unmap();
+    let (size, offset) = Ring::regions(&ring.params)[0];
+    let sq_ring = map(ring.fd, size, offset);
+    assert_eq!(unsafe { libc::munmap(sq_ring.cast(), size) }, 0);
+    assert_eq!(unsafe { libc::close(ring.fd) }, 0);
+
+    // Closing the instance leaves the regions mapped until they are unmapped.
+    let ring = Ring::new(4);
+    ring.push(Sqe { opcode: IORING_OP_NOP, user_data: 7, ..Default::default() });
+    assert_eq!(ring.enter(1, 1, IORING_ENTER_GETEVENTS), 1);
+    assert_eq!(unsafe { libc::close(ring.fd) }, 0);
+    assert_eq!(ring.drain(), [cqe(7, 0)]);
+    ring.unmap();
+}
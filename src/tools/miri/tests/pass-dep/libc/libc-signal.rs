@@ -0,0 +1,296 @@
+//@ignore-target: windows # No POSIX signals on Windows
+//@compile-flags: -Zmiri-deterministic-concurrency
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::{ptr, thread};
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+use libc_utils::*;
+
+static DELIVERED: AtomicUsize = AtomicUsize::new(0);
+static LAST_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn handler(sig: libc::c_int) {
+    DELIVERED.fetch_add(1, Ordering::Relaxed);
+    LAST_SIGNAL.store(sig, Ordering::Relaxed);
+}
+
+fn main() {
+    test_sigset();
+    test_raise();
+    test_siginfo();
+    test_signal_fn();
+    test_ignore();
+    test_mask_and_pending();
+    test_reentrancy_masked();
+    test_kill_self();
+    test_pthread_kill();
+    test_sigwait();
+    test_pause();
+    test_self_pipe();
+}
+
+fn install(sig: libc::c_int, handler: libc::sighandler_t, flags: libc::c_int) {
+    unsafe {
+        let mut act: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        act.sa_sigaction = handler;
+        act.sa_flags = flags;
+        errno_check(libc::sigemptyset(&mut act.sa_mask));
+        errno_check(libc::sigaction(sig, &act, ptr::null_mut()));
+    }
+}
+
+fn reset(sig: libc::c_int) {
+    install(sig, libc::SIG_DFL, 0);
+    DELIVERED.store(0, Ordering::Relaxed);
+    LAST_SIGNAL.store(0, Ordering::Relaxed);
+}
+
+fn sigset(signals: &[libc::c_int]) -> libc::sigset_t {
+    unsafe {
+        let mut set = MaybeUninit::uninit();
+        errno_check(libc::sigemptyset(set.as_mut_ptr()));
+        let mut set = set.assume_init();
+        for &sig in signals {
+            errno_check(libc::sigaddset(&mut set, sig));
+        }
+        set
+    }
+}
+
+fn test_sigset() {
+    unsafe {
+        let mut set = sigset(&[libc::SIGUSR1]);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR1), 1);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR2), 0);
+        errno_check(libc::sigdelset(&mut set, libc::SIGUSR1));
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR1), 0);
+        errno_check(libc::sigfillset(&mut set));
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR2), 1);
+
+        assert_eq!(libc::sigaddset(&mut set, 0), -1);
+        assert_eq!(errno(), libc::EINVAL);
+    }
+}
+
+fn test_raise() {
+    install(libc::SIGUSR1, handler as libc::sighandler_t, 0);
+    unsafe {
+        errno_check(libc::raise(libc::SIGUSR1));
+    }
+    // The handler ran before `raise` returned.
+    assert_eq!(DELIVERED.load(Ordering::Relaxed), 1);
+    assert_eq!(LAST_SIGNAL.load(Ordering::Relaxed), libc::SIGUSR1);
+
+    // The previous action is reported.
+    unsafe {
+        let mut old: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        errno_check(libc::sigaction(libc::SIGUSR1, ptr::null(), &mut old));
+        assert_eq!(old.sa_sigaction, handler as libc::sighandler_t);
+    }
+    reset(libc::SIGUSR1);
+
+    // Signals which can't be caught are rejected.
+    unsafe {
+        let act: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        assert_eq!(libc::sigaction(libc::SIGKILL, &act, ptr::null_mut()), -1);
+        assert_eq!(errno(), libc::EINVAL);
+    }
+}
+
+static SIGINFO_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn siginfo_handler(
+    sig: libc::c_int,
+    info: *mut libc::siginfo_t,
+    _context: *mut libc::c_void,
+) {
+    assert_eq!(sig, libc::SIGUSR2);
+    SIGINFO_SIGNAL.store(unsafe { (*info).si_signo }, Ordering::Relaxed);
+}
+
+fn test_siginfo() {
+    install(libc::SIGUSR2, siginfo_handler as libc::sighandler_t, libc::SA_SIGINFO);
+    unsafe {
+        errno_check(libc::raise(libc::SIGUSR2));
+    }
+    assert_eq!(SIGINFO_SIGNAL.load(Ordering::Relaxed), libc::SIGUSR2);
+    reset(libc::SIGUSR2);
+}
+
+fn test_signal_fn() {
+    unsafe {
+        let old = libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+        assert_eq!(old, libc::SIG_DFL);
+        errno_check(libc::raise(libc::SIGUSR1));
+        assert_eq!(DELIVERED.load(Ordering::Relaxed), 1);
+        let old = libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+        assert_eq!(old, handler as libc::sighandler_t);
+    }
+    reset(libc::SIGUSR1);
+}
+
+fn test_ignore() {
+    install(libc::SIGUSR1, libc::SIG_IGN, 0);
+    unsafe {
+        errno_check(libc::raise(libc::SIGUSR1));
+        // Signals ignored by default don't terminate the process either.
+        errno_check(libc::raise(libc::SIGCHLD));
+    }
+    reset(libc::SIGUSR1);
+}
+
+fn test_mask_and_pending() {
+    install(libc::SIGUSR1, handler as libc::sighandler_t, 0);
+    unsafe {
+        let set = sigset(&[libc::SIGUSR1]);
+        let mut old = MaybeUninit::uninit();
+        errno_check(libc::sigprocmask(libc::SIG_BLOCK, &set, old.as_mut_ptr()));
+        assert_eq!(libc::sigismember(old.as_ptr(), libc::SIGUSR1), 0);
+
+        // A blocked signal stays pending.
+        errno_check(libc::raise(libc::SIGUSR1));
+        assert_eq!(DELIVERED.load(Ordering::Relaxed), 0);
+        let mut pending = MaybeUninit::uninit();
+        errno_check(libc::sigpending(pending.as_mut_ptr()));
+        assert_eq!(libc::sigismember(pending.as_ptr(), libc::SIGUSR1), 1);
+
+        // Standard signals don't queue up.
+        errno_check(libc::raise(libc::SIGUSR1));
+
+        // It is delivered once unblocked.
+        errno_check(libc::sigprocmask(libc::SIG_UNBLOCK, &set, ptr::null_mut()));
+        assert_eq!(DELIVERED.load(Ordering::Relaxed), 1);
+        errno_check(libc::sigpending(pending.as_mut_ptr()));
+        assert_eq!(libc::sigismember(pending.as_ptr(), libc::SIGUSR1), 0);
+
+        assert_eq!(libc::pthread_sigmask(42, &set, ptr::null_mut()), libc::EINVAL);
+    }
+    reset(libc::SIGUSR1);
+}
+
+extern "C" fn reraising_handler(sig: libc::c_int) {
+    let depth = DELIVERED.fetch_add(1, Ordering::Relaxed);
+    if depth == 0 {
+        // The signal is blocked while its handler runs, so this only runs once the handler
+        // returned.
+        unsafe { libc::raise(sig) };
+        assert_eq!(DELIVERED.load(Ordering::Relaxed), 1);
+    }
+}
+
+fn test_reentrancy_masked() {
+    install(libc::SIGUSR1, reraising_handler as libc::sighandler_t, 0);
+    unsafe {
+        errno_check(libc::raise(libc::SIGUSR1));
+        // Miri delivers the signal the handler raised once the next libc call returns.
+        libc::getpid();
+    }
+    assert_eq!(DELIVERED.load(Ordering::Relaxed), 2);
+    reset(libc::SIGUSR1);
+}
+
+fn test_kill_self() {
+    install(libc::SIGUSR2, handler as libc::sighandler_t, 0);
+    unsafe {
+        errno_check(libc::kill(libc::getpid(), 0));
+        errno_check(libc::kill(libc::getpid(), libc::SIGUSR2));
+    }
+    assert_eq!(LAST_SIGNAL.load(Ordering::Relaxed), libc::SIGUSR2);
+    reset(libc::SIGUSR2);
+}
+
+static HANDLER_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn thread_recording_handler(_sig: libc::c_int) {
+    HANDLER_THREAD.store(unsafe { libc::pthread_self() } as usize, Ordering::Relaxed);
+}
+
+fn test_pthread_kill() {
+    install(libc::SIGUSR1, thread_recording_handler as libc::sighandler_t, 0);
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let child = thread::spawn(move || {
+        ready_tx.send(unsafe { libc::pthread_self() } as usize).unwrap();
+        rx.recv().unwrap();
+        // The handler runs on this thread, at the latest when this returns.
+        unsafe { libc::getpid() };
+    });
+    let target = ready_rx.recv().unwrap();
+    unsafe {
+        assert_eq!(libc::pthread_kill(target as libc::pthread_t, libc::SIGUSR1), 0);
+    }
+    tx.send(()).unwrap();
+    child.join().unwrap();
+    assert_eq!(HANDLER_THREAD.load(Ordering::Relaxed), target);
+    reset(libc::SIGUSR1);
+}
+
+fn test_sigwait() {
+    let set = sigset(&[libc::SIGUSR2]);
+    unsafe {
+        // `sigwait` requires the signals to be blocked.
+        errno_check(libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()));
+    }
+    // The spawned thread inherits the mask.
+    let child = thread::spawn(move || {
+        let mut sig = 0;
+        assert_eq!(unsafe { libc::sigwait(&set, &mut sig) }, 0);
+        sig
+    });
+    thread::yield_now();
+    unsafe {
+        errno_check(libc::kill(libc::getpid(), libc::SIGUSR2));
+    }
+    assert_eq!(child.join().unwrap(), libc::SIGUSR2);
+    unsafe {
+        errno_check(libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, ptr::null_mut()));
+    }
+}
+
+fn test_pause() {
+    install(libc::SIGUSR1, handler as libc::sighandler_t, 0);
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let child = thread::spawn(move || {
+        ready_tx.send(unsafe { libc::pthread_self() } as usize).unwrap();
+        let ret = unsafe { libc::pause() };
+        assert_eq!(ret, -1);
+        assert_eq!(errno(), libc::EINTR);
+    });
+    let target = ready_rx.recv().unwrap();
+    thread::yield_now();
+    unsafe {
+        assert_eq!(libc::pthread_kill(target as libc::pthread_t, libc::SIGUSR1), 0);
+    }
+    child.join().unwrap();
+    assert_eq!(DELIVERED.load(Ordering::Relaxed), 1);
+    reset(libc::SIGUSR1);
+}
+
+static PIPE_WRITE_END: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn self_pipe_handler(sig: libc::c_int) {
+    // `write` is async-signal-safe.
+    let byte = sig as u8;
+    unsafe { libc::write(PIPE_WRITE_END.load(Ordering::Relaxed), (&raw const byte).cast(), 1) };
+}
+
+fn test_self_pipe() {
+    let mut fds = [-1, -1];
+    unsafe {
+        errno_check(libc::pipe(fds.as_mut_ptr()));
+    }
+    PIPE_WRITE_END.store(fds[1], Ordering::Relaxed);
+    install(libc::SIGUSR2, self_pipe_handler as libc::sighandler_t, 0);
+    unsafe {
+        errno_check(libc::raise(libc::SIGUSR2));
+    }
+    let [byte] = read_exact_array::<1>(fds[0]).unwrap();
+    assert_eq!(i32::from(byte), libc::SIGUSR2);
+    reset(libc::SIGUSR2);
+    unsafe {
+        errno_check(libc::close(fds[0]));
+        errno_check(libc::close(fds[1]));
+    }
+}
@@ -0,0 +1,121 @@
+//@only-target: linux android
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+use std::mem::MaybeUninit;
+use std::{mem, ptr, thread};
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+use libc_utils::epoll::*;
+use libc_utils::*;
+
+fn main() {
+    let mask = sigset(&[libc::SIGUSR1, libc::SIGUSR2]);
+    // Signals read with a signalfd need to be blocked so that they don't get delivered.
+    // This is done before spawning threads so that they inherit the mask.
+    errno_check(unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &mask, ptr::null_mut()) });
+
+    test_read(&mask);
+    test_epoll(&mask);
+    test_blocking_read(&mask);
+    test_update_mask();
+}
+
+fn sigset(signals: &[libc::c_int]) -> libc::sigset_t {
+    unsafe {
+        let mut set = MaybeUninit::uninit();
+        errno_check(libc::sigemptyset(set.as_mut_ptr()));
+        let mut set = set.assume_init();
+        for &sig in signals {
+            errno_check(libc::sigaddset(&mut set, sig));
+        }
+        set
+    }
+}
+
+fn read_siginfos(fd: i32) -> std::io::Result<Vec<libc::signalfd_siginfo>> {
+    let mut buf: [libc::signalfd_siginfo; 4] = unsafe { mem::zeroed() };
+    let len = errno_result(unsafe {
+        libc::read(fd, buf.as_mut_ptr().cast(), mem::size_of_val(&buf))
+    })?;
+    let count = len as usize / mem::size_of::<libc::signalfd_siginfo>();
+    Ok(buf[..count].to_vec())
+}
+
+fn test_read(mask: &libc::sigset_t) {
+    let fd = errno_result(unsafe { libc::signalfd(-1, mask, libc::SFD_NONBLOCK) }).unwrap();
+    assert_eq!(read_siginfos(fd).unwrap_err().raw_os_error(), Some(libc::EAGAIN));
+
+    errno_check(unsafe { libc::raise(libc::SIGUSR1) });
+    errno_check(unsafe { libc::raise(libc::SIGUSR2) });
+    let infos = read_siginfos(fd).unwrap();
+    let signos = infos.iter().map(|info| info.ssi_signo as i32).collect::<Vec<_>>();
+    assert_eq!(signos, [libc::SIGUSR1, libc::SIGUSR2]);
+    assert_eq!(infos[0].ssi_pid, unsafe { libc::getpid() } as u32);
+    assert_eq!(infos[0].ssi_code, libc::SI_USER);
+
+    // The signals have been consumed.
+    let mut pending = MaybeUninit::uninit();
+    errno_check(unsafe { libc::sigpending(pending.as_mut_ptr()) });
+    assert_eq!(unsafe { libc::sigismember(pending.as_ptr(), libc::SIGUSR1) }, 0);
+    assert_eq!(read_siginfos(fd).unwrap_err().raw_os_error(), Some(libc::EAGAIN));
+
+    // Buffers too small for a single siginfo are rejected.
+    let mut buf = [0u8; 8];
+    let res = errno_result(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_epoll(mask: &libc::sigset_t) {
+    let fd = errno_result(unsafe { libc::signalfd(-1, mask, libc::SFD_NONBLOCK) }).unwrap();
+    let epfd = errno_result(unsafe { libc::epoll_create1(0) }).unwrap();
+    epoll_ctl_add(epfd, fd, EPOLLIN | EPOLLET).unwrap();
+    check_epoll_wait_noblock(epfd, &[]);
+
+    errno_check(unsafe { libc::kill(libc::getpid(), libc::SIGUSR2) });
+    check_epoll_wait_noblock(epfd, &[Ev { events: EPOLLIN, data: fd }]);
+    let infos = read_siginfos(fd).unwrap();
+    assert_eq!(infos[0].ssi_signo as i32, libc::SIGUSR2);
+    check_epoll_wait_noblock(epfd, &[]);
+
+    errno_check(unsafe { libc::close(fd) });
+    errno_check(unsafe { libc::close(epfd) });
+}
+
+fn test_blocking_read(mask: &libc::sigset_t) {
+    let fd = errno_result(unsafe { libc::signalfd(-1, mask, 0) }).unwrap();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let child = thread::spawn(move || {
+        ready_tx.send(unsafe { libc::pthread_self() } as usize).unwrap();
+        // Signals sent to the process are visible to the signalfds of all threads.
+        read_siginfos(fd).unwrap()[0].ssi_signo as i32
+    });
+    ready_rx.recv().unwrap();
+    // Let the child block on the read.
+    thread::yield_now();
+    errno_check(unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) });
+    assert_eq!(child.join().unwrap(), libc::SIGUSR1);
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_update_mask() {
+    let fd = errno_result(unsafe {
+        libc::signalfd(-1, &sigset(&[libc::SIGUSR1]), libc::SFD_NONBLOCK | libc::SFD_CLOEXEC)
+    })
+    .unwrap();
+    errno_check(unsafe { libc::raise(libc::SIGUSR2) });
+    assert_eq!(read_siginfos(fd).unwrap_err().raw_os_error(), Some(libc::EAGAIN));
+
+    // Changing the mask makes the pending signal readable.
+    let ret = errno_result(unsafe { libc::signalfd(fd, &sigset(&[libc::SIGUSR2]), 0) }).unwrap();
+    assert_eq!(ret, fd);
+    assert_eq!(read_siginfos(fd).unwrap()[0].ssi_signo as i32, libc::SIGUSR2);
+
+    // Only signalfds can be updated.
+    let efd = errno_result(unsafe { libc::eventfd(0, 0) }).unwrap();
+    let res = errno_result(unsafe { libc::signalfd(efd, &sigset(&[]), 0) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+    errno_check(unsafe { libc::close(efd) });
+    errno_check(unsafe { libc::close(fd) });
+}
@@ -0,0 +1,136 @@
+//@only-target: linux android
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+use std::{ptr, thread};
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+use libc_utils::epoll::*;
+use libc_utils::*;
+
+fn main() {
+    test_nonblock_disarmed();
+    test_blocking_read();
+    test_interval();
+    test_gettime_and_disarm();
+    test_epoll();
+    test_blocked_thread_woken_by_settime();
+    test_invalid();
+}
+
+fn itimerspec(value_ms: i64, interval_ms: i64) -> libc::itimerspec {
+    let timespec = |ms: i64| libc::timespec { tv_sec: ms / 1000, tv_nsec: (ms % 1000) * 1_000_000 };
+    libc::itimerspec { it_value: timespec(value_ms), it_interval: timespec(interval_ms) }
+}
+
+fn settime(fd: i32, value_ms: i64, interval_ms: i64) {
+    let new = itimerspec(value_ms, interval_ms);
+    errno_check(unsafe { libc::timerfd_settime(fd, 0, &new, ptr::null_mut()) });
+}
+
+fn test_nonblock_disarmed() {
+    let fd = errno_result(unsafe {
+        libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+    })
+    .unwrap();
+    // A disarmed timer never expires.
+    let err = eventfd::read_val(fd).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+    // Buffers smaller than 8 bytes are rejected.
+    let mut buf = [0u8; 4];
+    let res = errno_result(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_blocking_read() {
+    let fd = errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) }).unwrap();
+    settime(fd, 10, 0);
+    // The read blocks until the timer expired, once.
+    assert_eq!(eventfd::read_val(fd).unwrap(), 1);
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_interval() {
+    let fd = errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) }).unwrap();
+    settime(fd, 5, 5);
+    thread::sleep(std::time::Duration::from_millis(22));
+    // All expirations since the timer was armed are reported at once.
+    let expirations = eventfd::read_val(fd).unwrap();
+    assert!(expirations >= 4, "{expirations}");
+    // Later reads block for the next period.
+    assert_eq!(eventfd::read_val(fd).unwrap(), 1);
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_gettime_and_disarm() {
+    let fd =
+        errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) })
+            .unwrap();
+    settime(fd, 10_000, 1_000);
+
+    let mut cur = itimerspec(0, 0);
+    errno_check(unsafe { libc::timerfd_gettime(fd, &mut cur) });
+    assert_eq!(cur.it_interval.tv_sec, 1);
+    assert!(cur.it_value.tv_sec <= 10 && cur.it_value.tv_sec >= 9);
+
+    // Disarming reports the previous setting.
+    let disarm = itimerspec(0, 0);
+    let mut old = itimerspec(0, 0);
+    errno_check(unsafe { libc::timerfd_settime(fd, 0, &disarm, &mut old) });
+    assert_eq!(old.it_interval.tv_sec, 1);
+    errno_check(unsafe { libc::timerfd_gettime(fd, &mut cur) });
+    assert_eq!((cur.it_value.tv_sec, cur.it_value.tv_nsec), (0, 0));
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_epoll() {
+    let fd =
+        errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) })
+            .unwrap();
+    let epfd = errno_result(unsafe { libc::epoll_create1(0) }).unwrap();
+    epoll_ctl_add(epfd, fd, EPOLLIN | EPOLLET).unwrap();
+    check_epoll_wait_noblock(epfd, &[]);
+
+    // The timer expiring wakes up the blocked `epoll_wait`, without any other thread running.
+    settime(fd, 10, 0);
+    check_epoll_wait(epfd, &[Ev { events: EPOLLIN, data: fd }], -1);
+    assert_eq!(eventfd::read_val(fd).unwrap(), 1);
+    check_epoll_wait_noblock(epfd, &[]);
+
+    // Absolute deadlines in the past expire immediately.
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    errno_check(unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) });
+    let new = libc::itimerspec { it_value: now, it_interval: itimerspec(0, 0).it_interval };
+    errno_check(unsafe {
+        libc::timerfd_settime(fd, libc::TFD_TIMER_ABSTIME, &new, ptr::null_mut())
+    });
+    check_epoll_wait_noblock(epfd, &[Ev { events: EPOLLIN, data: fd }]);
+
+    errno_check(unsafe { libc::close(fd) });
+    errno_check(unsafe { libc::close(epfd) });
+}
+
+fn test_blocked_thread_woken_by_settime() {
+    let fd = errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) }).unwrap();
+    let reader = thread::spawn(move || eventfd::read_val(fd).unwrap());
+    // Let the reader block on the disarmed timer, then arm it.
+    thread::yield_now();
+    settime(fd, 1, 0);
+    assert_eq!(reader.join().unwrap(), 1);
+    errno_check(unsafe { libc::close(fd) });
+}
+
+fn test_invalid() {
+    let res = errno_result(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0x1) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+
+    let fd = errno_result(unsafe { libc::eventfd(0, 0) }).unwrap();
+    let new = itimerspec(10, 0);
+    let res = errno_result(unsafe { libc::timerfd_settime(fd, 0, &new, ptr::null_mut()) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EINVAL));
+    errno_check(unsafe { libc::close(fd) });
+
+    let res = errno_result(unsafe { libc::timerfd_settime(-1, 0, &new, ptr::null_mut()) });
+    assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EBADF));
+}
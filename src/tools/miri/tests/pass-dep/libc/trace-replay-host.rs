@@ -0,0 +1,27 @@
+//! Replaying a trace substitutes the recorded values for queries to the host: the current
+//! directory, the metadata of a file and random bytes. The system clock starts at the time the
+//! trace was recorded.
+//@only-target: linux # `getrandom` is Linux-specific
+//@compile-flags: -Zmiri-disable-isolation -Zmiri-preemption-rate=0 -Zmiri-disable-weak-memory-emulation
+//@compile-flags: -Zmiri-replay-trace=tests/pass-dep/libc/trace-replay-host.trace
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use std::{env, fs};
+
+fn main() {
+    let since_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    assert!(since_epoch >= Duration::from_secs(1_000_000_000));
+    assert!(since_epoch < Duration::from_secs(1_000_003_600));
+
+    assert_eq!(env::current_dir().unwrap(), Path::new("/replayed"));
+
+    let metadata = fs::metadata("/replayed/file").unwrap();
+    assert!(metadata.is_file());
+    assert_eq!(metadata.len(), 5);
+
+    let mut buf = [0u8; 4];
+    let ret = unsafe { libc::getrandom(buf.as_mut_ptr().cast(), buf.len(), 0) };
+    assert_eq!(ret, 4);
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
@@ -0,0 +1,11 @@
+//@compile-flags: -Zmiri-leak-allowlist=tests/pass/leak-allowlist.txt
+
+// The allowlist suppresses the leak of the box, and with it the leak of the vector's buffer, which
+// is only reachable through the box.
+fn intentional_leak() -> &'static mut Vec<u8> {
+    Box::leak(Box::new(vec![1, 2, 3]))
+}
+
+fn main() {
+    intentional_leak().push(4);
+}
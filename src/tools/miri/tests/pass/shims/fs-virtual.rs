@@ -0,0 +1,157 @@
+//@ignore-target: windows # File handling is not implemented yet
+//@compile-flags: -Zmiri-virtual-fs
+
+#![feature(io_error_more)]
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt, symlink};
+use std::path::Path;
+
+fn main() {
+    test_file();
+    test_create_new();
+    test_permissions();
+    test_dirs();
+    test_symlinks();
+    test_rename_and_links();
+    test_cwd();
+}
+
+fn test_file() {
+    let path = Path::new("/tmp/file.txt");
+    fs::write(path, b"Hello, World!").unwrap();
+    assert_eq!(fs::read(path).unwrap(), b"Hello, World!");
+
+    // Appending, seeking and truncating.
+    let mut file = OpenOptions::new().append(true).read(true).open(path).unwrap();
+    file.write_all(b" Bye.").unwrap();
+    file.seek(SeekFrom::Start(7)).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "World! Bye.");
+    file.set_len(5).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 5);
+    drop(file);
+    assert_eq!(fs::read(path).unwrap(), b"Hello");
+
+    // Read-only files cannot be written.
+    let mut file = File::open(path).unwrap();
+    assert!(file.write_all(b"x").is_err());
+
+    // Files stay accessible after being removed.
+    fs::remove_file(path).unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"Hello");
+    assert_eq!(fs::metadata(path).unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+fn test_create_new() {
+    let path = Path::new("/tmp/new.txt");
+    File::create_new(path).unwrap();
+    assert_eq!(File::create_new(path).unwrap_err().kind(), ErrorKind::AlreadyExists);
+    // Files cannot be created in missing directories.
+    assert_eq!(File::create("/tmp/missing/file").unwrap_err().kind(), ErrorKind::NotFound);
+    fs::remove_file(path).unwrap();
+}
+
+fn test_permissions() {
+    let path = Path::new("/tmp/perms.txt");
+    // The mode is subject to the umask.
+    let file = OpenOptions::new().write(true).create(true).mode(0o666).open(path).unwrap();
+    assert_eq!(file.metadata().unwrap().permissions().mode() & 0o777, 0o644);
+    drop(file);
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o444)).unwrap();
+    assert!(fs::metadata(path).unwrap().permissions().readonly());
+    let err = OpenOptions::new().write(true).open(path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+    // Directories without write permissions reject new entries.
+    fs::create_dir("/tmp/readonly").unwrap();
+    fs::set_permissions("/tmp/readonly", fs::Permissions::from_mode(0o555)).unwrap();
+    let err = File::create("/tmp/readonly/file").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    fs::remove_dir("/tmp/readonly").unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+fn test_dirs() {
+    let dir = Path::new("/tmp/dir");
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    assert_eq!(fs::create_dir(dir).unwrap_err().kind(), ErrorKind::AlreadyExists);
+    fs::write(dir.join("c"), b"").unwrap();
+    fs::write(dir.join("a/d"), b"").unwrap();
+
+    // Entries are listed in a deterministic order.
+    let names = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "c"]);
+    let entry = fs::read_dir(dir).unwrap().next().unwrap().unwrap();
+    assert!(entry.file_type().unwrap().is_dir());
+
+    assert!(fs::metadata(dir).unwrap().is_dir());
+    assert_eq!(fs::metadata(dir).unwrap().nlink(), 3);
+    assert_eq!(fs::remove_dir(dir).unwrap_err().kind(), ErrorKind::DirectoryNotEmpty);
+    assert_eq!(fs::remove_file(dir).unwrap_err().kind(), ErrorKind::IsADirectory);
+    assert_eq!(File::create(dir.join("c/e")).unwrap_err().kind(), ErrorKind::NotADirectory);
+    fs::remove_dir_all(dir).unwrap();
+    assert!(!dir.exists());
+}
+
+fn test_symlinks() {
+    fs::write("/tmp/target", b"data").unwrap();
+    symlink("target", "/tmp/link").unwrap();
+    assert_eq!(fs::read("/tmp/link").unwrap(), b"data");
+    assert_eq!(fs::read_link("/tmp/link").unwrap(), Path::new("target"));
+    assert!(fs::symlink_metadata("/tmp/link").unwrap().is_symlink());
+    assert_eq!(fs::canonicalize("/tmp/../tmp/./link").unwrap(), Path::new("/tmp/target"));
+
+    // Dangling links and loops.
+    symlink("/tmp/missing", "/tmp/dangling").unwrap();
+    assert_eq!(fs::metadata("/tmp/dangling").unwrap_err().kind(), ErrorKind::NotFound);
+    symlink("/tmp/loop", "/tmp/loop").unwrap();
+    assert_eq!(fs::metadata("/tmp/loop").unwrap_err().kind(), ErrorKind::FilesystemLoop);
+
+    for path in ["/tmp/target", "/tmp/link", "/tmp/dangling", "/tmp/loop"] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+fn test_rename_and_links() {
+    fs::write("/tmp/old", b"data").unwrap();
+    fs::hard_link("/tmp/old", "/tmp/hard").unwrap();
+    assert_eq!(fs::metadata("/tmp/old").unwrap().nlink(), 2);
+    assert_eq!(fs::metadata("/tmp/old").unwrap().ino(), fs::metadata("/tmp/hard").unwrap().ino());
+
+    fs::rename("/tmp/old", "/tmp/new").unwrap();
+    assert!(!Path::new("/tmp/old").exists());
+    assert_eq!(fs::read("/tmp/new").unwrap(), b"data");
+
+    // Directories cannot be moved into themselves.
+    fs::create_dir("/tmp/parent").unwrap();
+    let err = fs::rename("/tmp/parent", "/tmp/parent/child").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    fs::remove_dir("/tmp/parent").unwrap();
+    fs::remove_file("/tmp/new").unwrap();
+    fs::remove_file("/tmp/hard").unwrap();
+}
+
+fn test_cwd() {
+    assert_eq!(std::env::current_dir().unwrap(), Path::new("/"));
+    fs::create_dir("/tmp/cwd").unwrap();
+    std::env::set_current_dir("/tmp/cwd").unwrap();
+    assert_eq!(std::env::current_dir().unwrap(), Path::new("/tmp/cwd"));
+
+    // Relative paths are resolved against the current directory.
+    fs::write("relative.txt", b"data").unwrap();
+    assert_eq!(fs::read("/tmp/cwd/relative.txt").unwrap(), b"data");
+    fs::remove_file("../cwd/relative.txt").unwrap();
+
+    std::env::set_current_dir("/").unwrap();
+    fs::remove_dir("/tmp/cwd").unwrap();
+}
@@ -0,0 +1,36 @@
+//@ignore-target: windows # no `posix_spawn` on Windows
+//@compile-flags: -Zmiri-disable-isolation
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::{env, process};
+
+fn child() {
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).unwrap();
+    print!("child got {input}");
+    eprint!("child done");
+    process::exit(3);
+}
+
+fn main() {
+    if env::var_os("MIRI_TEST_CHILD").is_some() {
+        return child();
+    }
+
+    let mut child = Command::new(env::current_exe().unwrap())
+        .env("MIRI_TEST_CHILD", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"hello\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"child got hello\n");
+    assert_eq!(output.stderr, b"child done");
+
+    // Spawning something that is not interpreted by Miri fails cleanly.
+    assert!(Command::new("/this/does/not/exist").status().is_err());
+}
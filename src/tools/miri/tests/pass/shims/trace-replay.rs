@@ -0,0 +1,12 @@
+//! Replaying a trace substitutes the recorded data for reads from the host: stdin is empty when
+//! the test runs, but the trace says that a line was read from it.
+//@compile-flags: -Zmiri-disable-isolation -Zmiri-preemption-rate=0 -Zmiri-disable-weak-memory-emulation
+//@compile-flags: -Zmiri-replay-trace=tests/pass/shims/trace-replay.trace
+
+use std::io;
+
+fn main() {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).unwrap();
+    assert_eq!(line, "hello\n");
+}
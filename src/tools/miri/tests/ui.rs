@@ -17,6 +17,10 @@
 use ui_test::status_emitter::StatusEmitter;
 use ui_test::{CommandBuilder, Config, Match, ignore_output_conflict};
 
+mod coverage;
+mod gdb;
+mod leak_report;
+
 #[derive(Copy, Clone, Debug)]
 enum Mode {
     Pass {
@@ -460,6 +464,12 @@ fn main() -> Result<()> {
         )?;
         ui(Mode::Fail, "tests/native-lib/fail", &target, WithoutDeps, tmpdir.path())?;
     }
+    coverage::run(&target, tmpdir.path()).context("the coverage test failed")?;
+    leak_report::run(&target, tmpdir.path()).context("the leak report test failed")?;
+    // The gdb stub only presents an x86-64 target to the debugger.
+    if target == host && target.starts_with("x86_64-") {
+        gdb::run().context("the gdb stub test failed")?;
+    }
 
     // We only enable GenMC tests when the `genmc` feature is enabled, but also only on platforms we support:
     // FIXME(genmc,cross-platform): Technically we do support cross-target execution as long as the
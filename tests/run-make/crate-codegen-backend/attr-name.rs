@@ -0,0 +1,2 @@
+#![crate_type = "rlib"]
+#![crate_name = "renamed"]
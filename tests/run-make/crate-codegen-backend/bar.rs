@@ -0,0 +1,12 @@
+#![crate_type = "rlib"]
+
+extern crate foo;
+
+pub fn call_narrow() -> u32 {
+    foo::narrow(1)
+}
+
+#[cfg(wide)]
+pub fn call_wide() -> u128 {
+    foo::wide(1)
+}
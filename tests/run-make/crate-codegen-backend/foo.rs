@@ -0,0 +1,11 @@
+#![crate_type = "rlib"]
+
+#[inline(never)]
+pub fn narrow(x: u32) -> u32 {
+    x
+}
+
+#[inline(never)]
+pub fn wide(x: u128) -> u128 {
+    x
+}
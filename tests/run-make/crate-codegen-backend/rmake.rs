@@ -0,0 +1,60 @@
+// Check that `-Zcrate-codegen-backend` selects the codegen backend of a single crate, that the
+// backend is recorded in the crate metadata and that calls into a crate built with another
+// backend are rejected when the backends may disagree on the ABI of the callee.
+
+//@ ignore-cross-compile
+
+use run_make_support::{rust_lib_name, rustc};
+
+fn main() {
+    // `foo` is built with the dummy backend, the last matching option wins.
+    rustc()
+        .input("foo.rs")
+        .crate_name("foo")
+        .arg("-Zcrate-codegen-backend=foo=llvm")
+        .arg("-Zcrate-codegen-backend=foo=dummy")
+        .run();
+    let root = rustc().arg("-Zls=root").input(rust_lib_name("foo")).run().stdout_utf8();
+    assert!(root.contains("codegen_backend dummy"), "{root}");
+
+    // `bar` is not named and uses the default backend.
+    rustc()
+        .input("bar.rs")
+        .arg("-Zcrate-codegen-backend=foo=dummy")
+        .extern_("foo", rust_lib_name("foo"))
+        .run();
+    let root = rustc().arg("-Zls=root").input(rust_lib_name("bar")).run().stdout_utf8();
+    assert!(!root.contains("codegen_backend dummy"), "{root}");
+
+    // Passing a `u128` is lowered differently by the dummy backend.
+    rustc()
+        .input("bar.rs")
+        .cfg("wide")
+        .extern_("foo", rust_lib_name("foo"))
+        .run_fail()
+        .assert_stderr_contains("this function call passes scalars wider than a pointer")
+        .assert_stderr_contains("`foo::wide` is codegened in crate `foo`");
+
+    // Without `--crate-name`, the crate name is inferred from the input file.
+    rustc()
+        .input("bar.rs")
+        .arg("-Zcrate-codegen-backend=bar=dummy")
+        .extern_("foo", rust_lib_name("foo"))
+        .run();
+    let root = rustc().arg("-Zls=root").input(rust_lib_name("bar")).run().stdout_utf8();
+    assert!(root.contains("codegen_backend dummy"), "{root}");
+
+    // `#![crate_name]` is only known after the backend has been chosen.
+    rustc()
+        .input("attr-name.rs")
+        .arg("-Zcrate-codegen-backend=renamed=dummy")
+        .run_fail()
+        .assert_stderr_contains("`#![crate_name]` names the crate `renamed`");
+
+    // A missing `=` is rejected.
+    rustc()
+        .input("foo.rs")
+        .arg("-Zcrate-codegen-backend=foo")
+        .run_fail()
+        .assert_stderr_contains("<crate>=<backend> was expected");
+}
@@ -0,0 +1,46 @@
+//@ only-x86_64-unknown-linux-gnu
+//@ ignore-cross-compile
+//! Tests that `-Zincremental-link` has GNU gold update the previous output, and that the output is
+//! linked in full when it outgrows the space gold reserved for updates.
+
+use run_make_support::{bare_rustc, rfs, run};
+
+/// Builds a program that prints `message`, and returns the printed linker command line.
+fn build(message: &str) -> String {
+    rfs::write("main.rs", format!("fn main() {{ println!(\"{message}\"); }}\n"));
+    bare_rustc()
+        .input("main.rs")
+        .incremental("incr")
+        // Overrides the self-contained `rust-lld` of the target.
+        .arg("-Clinker-features=-lld")
+        .arg("-Clink-arg=-fuse-ld=gold")
+        .arg("-Cdebuginfo=2")
+        .arg("-Zincremental-link")
+        .print("link-args")
+        .run()
+        .stdout_utf8()
+}
+
+fn main() {
+    let link_args = build("first");
+    assert!(link_args.contains("--incremental-full"), "{link_args}");
+    run("main").assert_stdout_contains("first");
+    assert!(rfs::metadata("main.incr-link/state").is_file());
+
+    let link_args = build("second");
+    assert!(link_args.contains("--incremental-update"), "{link_args}");
+    run("main").assert_stdout_contains("second");
+
+    // A message this long doesn't fit into the space reserved in `.rodata`, so gold fails to
+    // update the output, and it is linked in full instead.
+    let long_message = "third".repeat(200_000);
+    build(&long_message);
+    run("main").assert_stdout_contains(&long_message);
+    assert!(rfs::metadata("main.incr-link/state").is_file());
+
+    // An output that was modified since the last link is linked in full.
+    rfs::remove_file("main");
+    let link_args = build("fourth");
+    assert!(link_args.contains("--incremental-full"), "{link_args}");
+    run("main").assert_stdout_contains("fourth");
+}
@@ -0,0 +1,10 @@
+mod inner {
+    #[inline(never)]
+    pub fn helper(x: u32) -> u32 {
+        x.wrapping_mul(31).rotate_left(3)
+    }
+}
+
+fn main() {
+    println!("{}", inner::helper(std::env::args().count() as u32));
+}
@@ -0,0 +1,36 @@
+//@ only-linux
+//@ ignore-cross-compile
+// Checks that `-Zlinker-map` has the linker write a map and normalizes it into JSON, with the
+// input sections attributed to the crate they come from.
+
+use run_make_support::serde_json::Value;
+use run_make_support::{rfs, rustc, serde_json};
+
+fn main() {
+    rustc().input("foo.rs").arg("-Zlinker-map").run();
+    assert!(rfs::metadata("foo.map").is_file());
+
+    let map: Value = serde_json::from_str(&rfs::read_to_string("foo.map.json")).unwrap();
+    assert_eq!(map["output"], "foo");
+    let format = map["format"].as_str().unwrap();
+    assert!(format == "gnu-ld" || format == "lld", "{format}");
+
+    let sections = map["sections"].as_array().unwrap();
+    let text = sections.iter().find(|section| section["name"] == ".text").unwrap();
+    let inputs = text["inputs"].as_array().unwrap();
+
+    // Local symbols are not listed by every linker, but the input sections are.
+    let helper = inputs
+        .iter()
+        .find(|input| input["section"].as_str().is_some_and(|s| s.contains("5inner6helper")))
+        .unwrap_or_else(|| panic!("no section for `foo::inner::helper` in {text:#}"));
+    assert_eq!(helper["crate"], "foo");
+    assert!(helper["size"].as_u64().unwrap() > 0);
+
+    let main = inputs
+        .iter()
+        .flat_map(|input| input["symbols"].as_array().unwrap())
+        .find(|symbol| symbol["name"] == "main")
+        .unwrap_or_else(|| panic!("no `main` in {text:#}"));
+    assert!(main["size"].as_u64().unwrap() > 0);
+}
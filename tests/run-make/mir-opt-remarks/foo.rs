@@ -0,0 +1,12 @@
+fn add_one(x: u32) -> u32 {
+    x + 1
+}
+
+#[inline(never)]
+fn opaque(x: u32) -> u32 {
+    x * 3
+}
+
+pub fn caller(x: u32) -> u32 {
+    opaque(add_one(x))
+}
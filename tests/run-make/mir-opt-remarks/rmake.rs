@@ -0,0 +1,61 @@
+//@ needs-target-std
+//
+// The MIR inliner makes remarks with `-Cremark=mir-inline`. With `-Zremark-dir`, they are written
+// in LLVM's YAML format, and merged with LLVM's remarks into a report grouped by function. They
+// are also emitted when incremental compilation reuses the optimized MIR.
+
+use run_make_support::{rfs, rustc, serde_json};
+
+fn main() {
+    rustc()
+        .opt()
+        .input("foo.rs")
+        .crate_type("lib")
+        .arg("-Cdebuginfo=1")
+        .arg("-Cremark=all")
+        .arg("-Zremark-dir=remarks")
+        .run();
+
+    let yaml = rfs::read_to_string("remarks/foo.mir.opt.yaml");
+    assert!(
+        yaml.contains("--- !Passed\nPass:            'mir-inline'\nName:            'Inlined'"),
+        "{yaml}"
+    );
+    assert!(yaml.contains("Name:            'NotInlined'"), "{yaml}");
+
+    let report: serde_json::Value =
+        serde_json::from_str(&rfs::read_to_string("remarks/foo.remarks.json")).unwrap();
+    let caller = report["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|function| function["demangled"] == "foo::caller")
+        .unwrap_or_else(|| panic!("no remarks for `foo::caller`: {report:#}"));
+    let remarks = caller["remarks"].as_array().unwrap();
+    let inlined = remarks
+        .iter()
+        .find(|remark| remark["pass"] == "mir-inline" && remark["name"] == "Inlined")
+        .unwrap_or_else(|| panic!("`add_one` was not inlined: {caller:#}"));
+    assert_eq!(inlined["stage"], "mir");
+    assert_eq!(inlined["message"], "add_one inlined into caller");
+    assert!(
+        remarks.iter().any(|remark| remark["stage"] != "mir"),
+        "no LLVM remarks for `foo::caller`: {caller:#}"
+    );
+
+    for session in ["first", "reused"] {
+        let dir = format!("remarks-{session}");
+        rustc()
+            .opt()
+            .input("foo.rs")
+            .crate_type("lib")
+            .incremental("incr")
+            .arg("-Cremark=mir-inline")
+            .arg(format!("-Zremark-dir={dir}"))
+            .run();
+        let yaml = rfs::read_to_string(format!("{dir}/foo.mir.opt.yaml"));
+        assert!(yaml.contains("Name:            'Inlined'"), "{session} session: {yaml}");
+        let report = rfs::read_to_string(format!("{dir}/foo.remarks.json"));
+        assert!(report.contains(r#""demangled":"foo::caller""#), "{session} session: {report}");
+    }
+}
@@ -0,0 +1,34 @@
+//@ only-msvc
+//! Tests that `-Zincremental-link` has `link.exe` link incrementally, and that the output is
+//! updated when the code changes.
+
+use run_make_support::{bare_rustc, rfs, run};
+
+fn build(message: &str) {
+    rfs::write("main.rs", format!("fn main() {{ println!(\"{message}\"); }}\n"));
+    bare_rustc()
+        .input("main.rs")
+        .incremental("incr")
+        // Overrides `rust.lld=true` on CI.
+        .arg("-Clinker=link.exe")
+        .arg("-Clinker-flavor=msvc")
+        .arg("-Cdebuginfo=2")
+        .arg("-Zincremental-link")
+        .run();
+}
+
+fn main() {
+    build("first");
+    run("main").assert_stdout_contains("first");
+    assert!(rfs::metadata("main.exe.incr-link/state").is_file());
+    assert!(rfs::metadata("main.exe.incr-link/incremental.ilk").is_file());
+
+    build("second");
+    run("main").assert_stdout_contains("second");
+    assert!(rfs::metadata("main.exe.incr-link/state").is_file());
+
+    // An output that was modified since the last link is linked in full.
+    rfs::remove_file("main.exe");
+    build("third");
+    run("main").assert_stdout_contains("third");
+}
@@ -0,0 +1,19 @@
+fn often(x: u32) -> u32 {
+    x.wrapping_mul(31).rotate_left(5) ^ 0x55
+}
+
+fn rarely(x: u32) -> u32 {
+    x.wrapping_mul(17).rotate_right(3) ^ 0xaa
+}
+
+fn main() {
+    let n = std::env::args().count() as u32;
+    let mut acc = 0;
+    for i in 0..n * 10_000 {
+        acc = often(acc ^ i);
+    }
+    if n > 100 {
+        acc = rarely(acc);
+    }
+    println!("{acc}");
+}
@@ -0,0 +1,47 @@
+// With `-Cprofile-use`, the MIR inliner leaves calls to functions that were never executed while
+// profiling out of line, while still inlining the hot ones.
+
+//@ needs-profiler-runtime
+//@ ignore-cross-compile
+
+use std::path::Path;
+
+use run_make_support::{llvm_profdata, rfs, run, rustc};
+
+fn main() {
+    let path_prof_data_dir = Path::new("prof_data_dir");
+    let path_merged_profdata = path_prof_data_dir.join("merged.profdata");
+    rfs::create_dir_all(&path_prof_data_dir);
+    // Keep both functions out of line while profiling, so that each gets its own counters.
+    rustc()
+        .input("main.rs")
+        .profile_generate(&path_prof_data_dir)
+        .opt()
+        .arg("-Zinline-mir=no")
+        .arg("-Cllvm-args=-disable-preinline")
+        .run();
+    run("main");
+    llvm_profdata().merge().output(&path_merged_profdata).input(path_prof_data_dir).run();
+
+    rustc()
+        .input("main.rs")
+        .opt()
+        .arg("-Zinline-mir=yes")
+        .emit("mir")
+        .output("no-profile.mir")
+        .run();
+    let mir = rfs::read_to_string("no-profile.mir");
+    assert!(!mir.contains("= often(") && !mir.contains("= rarely("), "{mir}");
+
+    rustc()
+        .input("main.rs")
+        .profile_use(&path_merged_profdata)
+        .opt()
+        .arg("-Zinline-mir=yes")
+        .emit("mir")
+        .output("profile.mir")
+        .run();
+    let mir = rfs::read_to_string("profile.mir");
+    assert!(!mir.contains("= often("), "{mir}");
+    assert!(mir.contains("= rarely("), "{mir}");
+}
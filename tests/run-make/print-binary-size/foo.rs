@@ -0,0 +1,17 @@
+#[inline(never)]
+fn sum<T: Copy + Into<u64>>(values: &[T]) -> u64 {
+    values.iter().map(|&v| v.into()).sum()
+}
+
+mod inner {
+    #[inline(never)]
+    pub fn helper(x: u64) -> u64 {
+        std::hint::black_box(x) * 3
+    }
+}
+
+fn main() {
+    let a = sum(std::hint::black_box(&[1u8, 2, 3][..]));
+    let b = sum(std::hint::black_box(&[1u32, 2, 3][..]));
+    println!("{}", inner::helper(a + b));
+}
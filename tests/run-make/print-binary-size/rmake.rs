@@ -0,0 +1,44 @@
+//@ ignore-cross-compile
+// Checks that `-Zprint-binary-size-json` attributes the symbols of the linked binary to the crate,
+// module, function and generic instantiation they were generated for.
+
+use run_make_support::serde_json::Value;
+use run_make_support::{rfs, rustc, serde_json};
+
+fn child<'a>(node: &'a Value, name: &str) -> &'a Value {
+    node["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|child| child["name"] == name)
+        .unwrap_or_else(|| panic!("no `{name}` in {node:#}"))
+}
+
+fn size(node: &Value) -> u64 {
+    node["size"].as_u64().unwrap()
+}
+
+fn main() {
+    let output = rustc()
+        .input("foo.rs")
+        .arg("-Copt-level=1")
+        .arg("-Zprint-binary-size")
+        .arg("-Zprint-binary-size-json=size.json")
+        .run();
+    output.assert_stdout_contains("binary size of");
+
+    let report: Value = serde_json::from_str(&rfs::read_to_string("size.json")).unwrap();
+    assert!(size(&report) > 0);
+    assert!(report["file_size"].as_u64().unwrap() >= size(&report));
+
+    let krate = child(&report, "foo");
+    let root = child(krate, "foo");
+    let sum = child(root, "sum");
+    assert!(size(child(sum, "<u8>")) > 0);
+    assert!(size(child(sum, "<u32>")) > 0);
+    assert!(size(child(child(krate, "foo::inner"), "helper")) > 0);
+
+    // The tree adds up.
+    let children: u64 = report["children"].as_array().unwrap().iter().map(size).sum();
+    assert_eq!(children, size(&report));
+}
@@ -0,0 +1,45 @@
+trait Describe {
+    fn describe(&self) -> usize;
+}
+
+impl Describe for u8 {
+    fn describe(&self) -> usize {
+        1
+    }
+}
+
+impl Describe for &str {
+    fn describe(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Describe for f32 {
+    fn describe(&self) -> usize {
+        4
+    }
+}
+
+// `T` is only used as `&T` and through `Describe::describe`.
+#[inline(never)]
+fn show<T: Describe>(value: &T) -> usize {
+    value.describe()
+}
+
+// `T` is passed by value and its size matters.
+#[inline(never)]
+fn pair<T: Copy>(value: T) -> (T, T) {
+    (value, value)
+}
+
+// `U` is never used.
+#[inline(never)]
+fn ignore<T: Copy, U>(value: T) -> T {
+    value
+}
+
+fn main() {
+    println!("{} {} {}", show(&1u8), show(&"a"), show(&2.0f32));
+    println!("{:?} {:?}", pair(1u8), pair(2u64));
+    println!("{} {}", ignore::<u8, String>(1), ignore::<u8, u16>(2));
+}
@@ -0,0 +1,44 @@
+//@ needs-target-std
+// Checks that `-Zprint-generic-bloat` reports the instantiations of generic functions and how
+// their type parameters are used.
+
+use run_make_support::rustc;
+
+fn main() {
+    let output = rustc().input("foo.rs").arg("-Zprint-generic-bloat=100").run();
+    let stdout = output.stdout_utf8();
+
+    let report = |function: &str| -> Vec<&str> {
+        let header = format!("print-generic-bloat: `{function}`: ");
+        let mut lines = stdout.lines().skip_while(|line| !line.starts_with(&header));
+        let header = lines.next().unwrap_or_else(|| panic!("`{function}` is missing:\n{stdout}"));
+        let mut report = vec![header];
+        report.extend(lines.take_while(|line| line.starts_with("print-generic-bloat:     ")));
+        report
+    };
+
+    let show = report("show");
+    assert!(show[0].contains("3 instantiations"), "{show:?}");
+    assert!(show[1].contains("`T`: 3 distinct types, only used behind references"), "{show:?}");
+    // The smallest and largest size of an instantiation bound the total, both for the MIR size
+    // estimate and for the number of instructions generated by the backend.
+    for label in ["MIR size estimate ", "codegen size "] {
+        let (each, total) = show[0].split_once(label).unwrap().1.split_once(" each, ").unwrap();
+        let total = total.split_once(" total").unwrap().0;
+        let (min, max) = each.split_once(" to ").unwrap_or((each, each));
+        let [min, max, total] = [min, max, total].map(|n| n.parse::<usize>().unwrap());
+        assert!(0 < min && 3 * min <= total && total <= 3 * max, "{show:?}");
+    }
+
+    let pair = report("pair");
+    assert!(pair[0].contains("2 instantiations"), "{pair:?}");
+    assert!(pair[1].contains("`T`: 2 distinct types, affects codegen"), "{pair:?}");
+
+    let ignore = report("ignore");
+    assert!(ignore[0].contains("2 instantiations"), "{ignore:?}");
+    assert!(
+        ignore[1].contains("`T`: 1 distinct types, affects codegen, but is always"),
+        "{ignore:?}"
+    );
+    assert!(ignore[2].contains("`U`: 2 distinct types, unused"), "{ignore:?}");
+}
@@ -0,0 +1,5 @@
+pub struct Foo;
+
+pub fn make_foo(_: u8) -> Foo {
+    Foo
+}
@@ -0,0 +1,63 @@
+// Check that `--serve` serves a finalized doc root over HTTP, and nothing if finalizing fails.
+
+//@ needs-target-std
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::Stdio;
+
+use run_make_support::{rustdoc, target};
+
+fn get(addr: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: {addr}\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+fn main() {
+    rustdoc().input("foo.rs").target(target()).out_dir("doc").run();
+
+    let mut child = rustdoc()
+        .args(&["-Zunstable-options", "--serve=127.0.0.1:0", "--out-dir=doc"])
+        .into_raw_command()
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The server reports the port it got from the OS.
+    let mut line = String::new();
+    BufReader::new(child.stderr.take().unwrap()).read_line(&mut line).unwrap();
+    let addr = line
+        .trim_end()
+        .split_once("http://")
+        .and_then(|(_, url)| url.strip_suffix('/'))
+        .unwrap_or_else(|| panic!("unexpected output: {line}"))
+        .to_owned();
+
+    let index = get(&addr, "/foo/index.html");
+    assert!(index.starts_with("HTTP/1.1 200 OK\r\n"), "{index}");
+    assert!(index.contains("make_foo"), "{index}");
+
+    let search = get(&addr, "/search?q=u8%20-%3E%20Foo");
+    assert!(search.starts_with("HTTP/1.1 302 Found\r\n"), "{search}");
+    assert!(search.contains("Location: /index.html?search=u8%20-%3E%20Foo\r\n"), "{search}");
+
+    assert!(get(&addr, "/../foo.rs").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // Nothing is served when finalizing the merged documentation fails.
+    let output = rustdoc()
+        .args(&[
+            "-Zunstable-options",
+            "--merge=finalize",
+            "--include-parts-dir=missing-parts",
+            "--serve=127.0.0.1:0",
+            "--out-dir=final",
+        ])
+        .run_fail();
+    assert!(!output.stderr_utf8().contains("http://"), "{}", output.stderr_utf8());
+}
@@ -0,0 +1,27 @@
+/// ```check_output
+/// for i in 0..3 {
+///     println!("{i}");
+/// }
+/// println!();
+/// println!("done");
+/// ```
+///
+/// ```text
+/// 0
+/// 1
+/// 2
+///
+/// done
+/// ```
+pub fn count() {}
+
+pub mod inner {
+    /// ```check_output
+    /// println!("Hello, world!");
+    /// ```
+    ///
+    /// ```text
+    /// Hello, world!
+    /// ```
+    pub fn hello() {}
+}
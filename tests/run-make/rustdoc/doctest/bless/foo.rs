@@ -0,0 +1,25 @@
+/// ```check_output
+/// for i in 0..3 {
+///     println!("{i}");
+/// }
+/// println!();
+/// println!("done");
+/// ```
+///
+/// ```text
+/// 0
+/// ```
+pub fn count() {}
+
+pub mod inner {
+    /// ```check_output
+    /// println!("Hello, world!");
+    /// ```
+    ///
+    /// ```text
+    /// Hello,
+    /// outdated
+    /// world!
+    /// ```
+    pub fn hello() {}
+}
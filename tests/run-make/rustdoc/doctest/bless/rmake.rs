@@ -0,0 +1,26 @@
+// Check that `--bless` rewrites the expected output blocks of `check_output` doctests.
+
+//@ ignore-cross-compile (running doctests)
+
+use run_make_support::{diff, rfs, rustdoc};
+
+fn main() {
+    rfs::copy("foo.rs", "blessed.rs");
+
+    rustdoc().input("blessed.rs").arg("--test").run_fail();
+    rustdoc().input("blessed.rs").args(&["--test", "-Zunstable-options", "--bless"]).run();
+    diff().expected_file("foo.blessed.rs").actual_file("blessed.rs").run();
+
+    // The blessed output must now be up to date.
+    rustdoc().input("blessed.rs").arg("--test").run();
+
+    // The lines of included documentation don't map to the file it is included from, so it is
+    // left untouched and the doctest still fails.
+    let included = rfs::read_to_string("included.md");
+    rustdoc()
+        .input("included.rs")
+        .args(&["--test", "-Zunstable-options", "--bless"])
+        .run_fail()
+        .assert_stdout_contains("The expected output can't be blessed");
+    assert_eq!(rfs::read_to_string("included.md"), included);
+}
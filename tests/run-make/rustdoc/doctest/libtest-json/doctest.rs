@@ -0,0 +1,18 @@
+/// ```
+/// assert_eq!(doctest::add(2, 2), 4);
+/// ```
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+pub mod inner {
+    /// Some text.
+    ///
+    /// ```
+    /// let x = doctest::inner::sub(2, 2);
+    /// assert_eq!(x, 0);
+    /// ```
+    pub fn sub(a: i32, b: i32) -> i32 {
+        a - b
+    }
+}
@@ -0,0 +1,49 @@
+// Check that libtest's JSON output describes the location and the item of each doctest.
+
+//@ ignore-cross-compile (running doctests)
+//@ ignore-stage1 (rustdoc depends on a change in libtest)
+
+use std::path::Path;
+
+use run_make_support::{cwd, rustc, rustdoc, serde_json};
+
+fn main() {
+    let rlib = cwd().join("libdoctest.rlib");
+    rustc().input("doctest.rs").crate_type("rlib").output(&rlib).run();
+
+    check_doctests(&rlib, "2021");
+    check_doctests(&rlib, "2024");
+}
+
+#[track_caller]
+fn check_doctests(rlib: &Path, edition: &str) {
+    let rustdoc_out = rustdoc()
+        .input("doctest.rs")
+        .args(&[
+            "--test",
+            "--test-args=-Zunstable-options",
+            "--test-args=--test-threads=1",
+            "--test-args=--format=json",
+        ])
+        .edition(edition)
+        .extern_("doctest", rlib.display().to_string())
+        .run();
+    let stdout = rustdoc_out.stdout_utf8();
+
+    let mut seen = Vec::new();
+    for line in stdout.lines() {
+        // Only libtest events are of interest here, skip anything else rustdoc prints.
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+        if event["type"] != "test" || event["event"] != "ok" {
+            continue;
+        }
+        assert_eq!(event["source_path"], "doctest.rs", "{line}");
+        assert_eq!(event["edition"], edition, "{line}");
+        let item_path = event["item_path"].as_str().unwrap().to_owned();
+        let start_line = event["start_line"].as_u64().unwrap();
+        let end_line = event["end_line"].as_u64().unwrap();
+        seen.push((item_path, start_line, end_line));
+    }
+    seen.sort();
+    assert_eq!(seen, [("add".to_owned(), 1, 3), ("inner::sub".to_owned(), 11, 14)]);
+}
@@ -0,0 +1,9 @@
+#![crate_type = "rlib"]
+
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub fn greet() -> &'static str {
+    "hello"
+}
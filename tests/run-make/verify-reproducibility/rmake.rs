@@ -0,0 +1,26 @@
+//@ needs-target-std
+// Checks that `-Zverify-reproducibility` compiles the crate twice, accepts identical artifacts and
+// reports the artifacts that depend on the working directory.
+
+use run_make_support::{path, rustc};
+
+fn main() {
+    rustc()
+        .input("foo.rs")
+        .arg("-Zverify-reproducibility")
+        .run()
+        .assert_stdout_contains("first run: hash seed 0, 1 frontend and ")
+        .assert_stdout_contains("second run: hash seed ")
+        .assert_stdout_contains("1 of 1 artifacts are reproducible");
+    // The check does not write anything to the output directory.
+    assert!(!path("libfoo.rlib").exists());
+
+    // Debuginfo records the working directory, which differs between the two runs.
+    rustc()
+        .input("foo.rs")
+        .arg("-Zverify-reproducibility")
+        .arg("-Cdebuginfo=2")
+        .run_fail()
+        .assert_stderr_contains("`libfoo.rlib` is not reproducible, the first difference is in")
+        .assert_stdout_contains("0 of 1 artifacts are reproducible");
+}
@@ -2,4 +2,5 @@
 //@ has "$.index[?(@.name=='PlainEmpty')].inner.struct"
 //@ is "$.index[?(@.name=='PlainEmpty')].inner.struct.kind.plain.has_stripped_fields" false
 //@ is "$.index[?(@.name=='PlainEmpty')].inner.struct.kind.plain.fields" []
+//@ is "$.index[?(@.name=='PlainEmpty')].inner.struct.facts" null
 pub struct PlainEmpty {}
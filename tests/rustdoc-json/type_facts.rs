@@ -0,0 +1,44 @@
+//@ compile-flags: --show-type-facts
+
+use std::marker::PhantomPinned;
+use std::rc::Rc;
+
+//@ is '$.index[?(@.name=="Plain")].inner.struct.facts.auto_traits' '{"send": "implemented", "sync": "implemented", "unpin": "implemented", "unwind_safe": "implemented"}'
+//@ is '$.index[?(@.name=="Plain")].inner.struct.facts.layout' '{"size": 3, "align": 1, "is_uninhabited": false}'
+//@ jq_is '. as $krate | [.index[] | select(.name == "Plain").inner.struct.facts.blanket_impls[] | $krate.index["\(.)"].inner.impl.blanket_impl != null] | length > 0 and all' true
+pub struct Plain([u8; 3]);
+
+//@ is '$.index[?(@.name=="Shared")].inner.struct.facts.auto_traits.send' '"not_implemented"'
+//@ is '$.index[?(@.name=="Shared")].inner.struct.facts.auto_traits.sync' '"not_implemented"'
+//@ is '$.index[?(@.name=="Shared")].inner.struct.facts.auto_traits.unpin' '"implemented"'
+pub struct Shared(Rc<u8>);
+
+//@ is '$.index[?(@.name=="Pinned")].inner.struct.facts.auto_traits.unpin' '"not_implemented"'
+pub struct Pinned(PhantomPinned);
+
+//@ is '$.index[?(@.name=="Wrapper")].inner.struct.facts.auto_traits.send' '"conditional"'
+//@ is '$.index[?(@.name=="Wrapper")].inner.struct.facts.layout' null
+pub struct Wrapper<T>(T);
+
+//@ is '$.index[?(@.name=="Bounded")].inner.struct.facts.auto_traits.send' '"implemented"'
+pub struct Bounded<T: Send>(T);
+
+//@ is '$.index[?(@.name=="Manual")].inner.struct.facts.auto_traits.send' '"conditional"'
+//@ is '$.index[?(@.name=="Manual")].inner.struct.facts.auto_traits.sync' '"not_implemented"'
+pub struct Manual<T>(*const T);
+
+unsafe impl<T: Sync> Send for Manual<T> {}
+
+//@ is '$.index[?(@.name=="Always")].inner.struct.facts.auto_traits.send' '"implemented"'
+pub struct Always(*const u8);
+
+unsafe impl Send for Always {}
+
+//@ is '$.index[?(@.name=="Never")].inner.enum.facts.layout' '{"size": 0, "align": 1, "is_uninhabited": true}'
+pub enum Never {}
+
+//@ is '$.index[?(@.name=="Bits")].inner.union.facts.layout.size' 1
+pub union Bits {
+    pub byte: u8,
+    pub flag: bool,
+}
@@ -0,0 +1,14 @@
+// Checks that `--check-ignored-doctests` fails the doctests marked `ignore` which don't compile
+// where `rustdoc::broken_ignored_doctests` is denied.
+
+//@ compile-flags:--test --test-args=--test-threads=1 -Zunstable-options --check-ignored-doctests
+//@ normalize-stdout: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout: "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ failure-status: 101
+
+#![deny(rustdoc::broken_ignored_doctests)]
+
+/// ```ignore
+/// let x: u32 = "not a number";
+/// ```
+pub struct Broken;
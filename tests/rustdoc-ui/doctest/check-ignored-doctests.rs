@@ -0,0 +1,27 @@
+// Checks that `--check-ignored-doctests` type-checks the doctests marked `ignore`, and reports the
+// ones which don't compile through the `rustdoc::broken_ignored_doctests` lint, which only warns by
+// default instead of failing them.
+
+//@ compile-flags:--test --test-args=--test-threads=1 -Zunstable-options --check-ignored-doctests
+//@ normalize-stdout: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stderr: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout: "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ check-pass
+
+/// ```ignore
+/// let x: u32 = 1;
+/// ```
+pub struct Compiles;
+
+/// ```ignore
+/// let x: u32 = "not a number";
+/// ```
+pub struct Broken;
+
+/// The lint is allowed here, so this doctest isn't type-checked at all.
+///
+/// ```ignore
+/// let x: u32 = "not a number";
+/// ```
+#[allow(rustdoc::broken_ignored_doctests)]
+pub struct Allowed;
@@ -0,0 +1,22 @@
+// Test the failure of a `check_output` doctest whose output doesn't match, and of one that isn't
+// followed by a code block.
+
+//@ edition: 2024
+//@ compile-flags:--test --test-args=--test-threads=1
+//@ normalize-stdout: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout: "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ failure-status: 101
+
+/// ```check_output
+/// println!("Hello, rustdoc!");
+/// ```
+///
+/// ```text
+/// Hello, world!
+/// ```
+pub fn foo() {}
+
+/// ```check_output
+/// println!("Hello, world!");
+/// ```
+pub fn bar() {}
@@ -0,0 +1,31 @@
+// Test that the stdout of `check_output` doctests is compared against the following code block.
+
+//@ check-pass
+//@ edition: 2024
+//@ compile-flags:--test --test-args=--test-threads=1
+//@ normalize-stdout: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout: "finished in \d+\.\d+s" -> "finished in $$TIME"
+
+/// ```check_output
+/// println!("Hello, world!");
+/// ```
+///
+/// ```text
+/// Hello, world!
+/// ```
+pub fn foo() {}
+
+/// Trailing whitespace is ignored and lines starting with `#` are kept:
+///
+/// ```check_output
+/// # fn print(s: &str) { println!("{s}   ") }
+/// print("# one");
+/// print("two");
+/// ```
+///
+/// ```text
+/// # one
+/// two
+///
+/// ```
+pub fn bar() {}
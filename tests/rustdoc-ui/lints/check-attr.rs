@@ -56,3 +56,12 @@ pub fn c() {}
 /// boo
 /// ```
 pub fn d() {}
+
+/// e
+//~^ ERROR
+//~^^ ERROR
+///
+/// ```check-output,checkOutput
+/// boo
+/// ```
+pub fn e() {}